@@ -0,0 +1,735 @@
+//! Assertions for testing KaTeX extensions
+//!
+//! These are the helpers katex-rs uses for its own test suite, published so
+//! that plugin authors and downstream integrators can test their functions,
+//! environments and macros in the same style:
+//!
+//! ```rust
+//! use katex::{KatexContext, Settings};
+//! use katex_test_utils::{TestResult, expect, strict_settings};
+//!
+//! fn check() -> TestResult<()> {
+//!     let settings = Settings::default();
+//!     expect!(r"\frac{a}{b}").to_parse(&settings)?;
+//!     expect!(r"a \over b").to_parse_like(r"\frac{a}{b}", &settings)?;
+//!     expect!(r"\frac{a}").not_to_parse(&settings)?;
+//!
+//!     let error = expect!(r"\frac{a}").parse_error(&settings)?;
+//!     assert!(error.to_string().contains("Unexpected end of input"));
+//!
+//!     // Against a context with custom functions or macros
+//!     let ctx = KatexContext::default();
+//!     expect!("é").in_context(&ctx).not_to_parse(&strict_settings())
+//! }
+//! # check().unwrap();
+//! ```
+//!
+//! Every assertion returns a [`TestResult`] whose error names the expression
+//! and the line of the [`expect!`] call, so test functions can use `?` and
+//! still report where they failed.
+//!
+//! The [`snapshot`] module normalizes rendered markup for snapshot tests, and
+//! [`check_function_examples`] renders the examples functions declare.
+
+pub mod snapshot;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::slice;
+use std::sync::OnceLock;
+
+use katex::parser::parse_node::{
+    AnyParseNode, ParseNode, ParseNodeArrayTag, ParseNodeOp, TensorIndex,
+};
+use katex::{
+    KatexContext, OutputFormat, ParseError, Settings, StrictSetting, TrustSetting, parse,
+    render_to_dom_tree, render_to_string,
+};
+use thiserror::Error;
+
+static DEFAULT_CONTEXT: OnceLock<KatexContext> = OnceLock::new();
+
+/// The context [`expect!`] tests against unless told otherwise, shared by all
+/// tests of a binary
+pub fn default_ctx() -> &'static KatexContext {
+    DEFAULT_CONTEXT.get_or_init(KatexContext::default)
+}
+
+/// Settings that reject LaTeX-incompatible input and throw on errors
+#[must_use]
+pub fn strict_settings() -> Settings {
+    Settings::builder()
+        .throw_on_error(true)
+        .strict(StrictSetting::Bool(true))
+        .build()
+}
+
+/// Settings that accept LaTeX-incompatible input and render errors inline
+#[must_use]
+pub fn nonstrict_settings() -> Settings {
+    Settings::builder()
+        .throw_on_error(false)
+        .strict(StrictSetting::Bool(false))
+        .build()
+}
+
+/// Settings that trust every command, for `\href`, `\includegraphics` and
+/// the `\html...` commands
+#[must_use]
+pub fn trust_settings() -> Settings {
+    Settings::builder().trust(TrustSetting::Bool(true)).build()
+}
+
+/// Where an assertion was written, as captured by [`expect!`]
+#[derive(Clone, Copy, Debug)]
+pub struct TestLocation {
+    /// Source file of the assertion
+    pub file: &'static str,
+    /// Line of the assertion
+    pub line: u32,
+    /// The expression passed to [`expect!`], as written
+    pub code: &'static str,
+}
+
+impl fmt::Display for TestLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.code.is_empty() {
+            write!(f, "{}:{}", self.file, self.line)
+        } else {
+            write!(f, "{}:{} ({})", self.file, self.line, self.code)
+        }
+    }
+}
+
+impl TestLocation {
+    /// Location of errors converted from a bare [`ParseError`]
+    pub const UNKNOWN: Self = Self {
+        file: "<unknown>",
+        line: 0,
+        code: "",
+    };
+}
+
+/// A failed assertion
+#[derive(Debug, Error)]
+pub enum TestError {
+    /// The expression was expected to parse, but did not
+    #[error("Failed to parse '{expression}' at {location}: {source}")]
+    Parse {
+        /// The expression that failed
+        expression: String,
+        /// Where the assertion was written
+        location: TestLocation,
+        /// Why parsing failed
+        #[source]
+        source: ParseError,
+    },
+    /// The expression parsed, but was expected not to
+    #[error("Expected parsing to fail for '{expression}' at {location}")]
+    ExpectedParseFailure {
+        /// The expression that parsed
+        expression: String,
+        /// Where the assertion was written
+        location: TestLocation,
+    },
+    /// The expression built, but was expected not to
+    #[error("Expected building to fail for '{expression}' at {location}")]
+    ExpectedBuildFailure {
+        /// The expression that built
+        expression: String,
+        /// Where the assertion was written
+        location: TestLocation,
+    },
+    /// Two expressions expected to build alike did not
+    #[error("{0}")]
+    DomMismatch(Box<DomMismatchDetails>),
+    /// The expression rendered, but was expected not to
+    #[error(
+        "Expected HTML rendering to fail for '{expression}' at {location}, but it succeeded: {html}"
+    )]
+    ExpectedHtmlFailure {
+        /// The expression that rendered
+        expression: String,
+        /// Where the assertion was written
+        location: TestLocation,
+        /// The markup it rendered to
+        html: String,
+    },
+    /// Examples of functions failed to render
+    #[error("{} function examples failed:\n{}", .failures.len(), .failures.join("\n"))]
+    FunctionExamples {
+        /// The examples with their output format and error
+        failures: Vec<String>,
+    },
+}
+
+/// The two trees of a [`TestError::DomMismatch`]
+#[derive(Debug)]
+pub struct DomMismatchDetails {
+    /// The expression passed to [`expect!`]
+    pub left_expr: String,
+    /// The expression it was compared with
+    pub right_expr: String,
+    /// Debug output of the left DOM tree
+    pub left_dom: String,
+    /// Debug output of the right DOM tree
+    pub right_dom: String,
+    /// Where the assertion was written
+    pub location: TestLocation,
+}
+
+impl fmt::Display for DomMismatchDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DOM mismatch at {} between '{}' and '{}':\n{}\n\n{}",
+            self.location, self.left_expr, self.right_expr, self.left_dom, self.right_dom
+        )
+    }
+}
+
+impl From<ParseError> for TestError {
+    fn from(source: ParseError) -> Self {
+        Self::Parse {
+            expression: "<unknown expression>".to_owned(),
+            location: TestLocation::UNKNOWN,
+            source,
+        }
+    }
+}
+
+/// Result of an assertion
+pub type TestResult<T> = Result<T, TestError>;
+
+/// Renders the examples of every function of `ctx` to HTML and to MathML,
+/// so that a function whose handler or builders fail on its own examples is
+/// caught. See [`FunctionPropSpec::examples`].
+///
+/// The examples are rendered in display mode, trusting every command and
+/// without strict checks. Returns how many examples were rendered.
+///
+/// [`FunctionPropSpec::examples`]: katex::define_function::FunctionPropSpec::examples
+pub fn check_function_examples(ctx: &KatexContext) -> TestResult<usize> {
+    let examples: BTreeSet<&str> = ctx
+        .functions
+        .values()
+        .flat_map(|spec| spec.examples.iter().copied())
+        .collect();
+    let settings = Settings::builder()
+        .display_mode(true)
+        .trust(TrustSetting::Bool(true))
+        .strict(StrictSetting::Bool(false))
+        .build();
+
+    let mut failures = Vec::new();
+    for &example in &examples {
+        for output in [OutputFormat::Html, OutputFormat::Mathml] {
+            let settings = Settings {
+                output,
+                ..settings.clone()
+            };
+            if let Err(error) = render_to_string(ctx, example, &settings) {
+                failures.push(format!("'{example}' to {output:?}: {error}"));
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(examples.len())
+    } else {
+        Err(TestError::FunctionExamples { failures })
+    }
+}
+
+/// Sets every `loc` in `nodes` to `None`, so that parse trees of different
+/// inputs can be compared.
+pub fn strip_positions(nodes: &mut [ParseNode]) {
+    for node in nodes.iter_mut() {
+        match node {
+            AnyParseNode::Array(parse_node_array) => {
+                parse_node_array.loc = None;
+                for row in &mut parse_node_array.body {
+                    strip_positions(row);
+                }
+                if let Some(tags) = &mut parse_node_array.tags {
+                    for tag in tags {
+                        if let ParseNodeArrayTag::Nodes(nodes) = tag {
+                            strip_positions(nodes);
+                        }
+                    }
+                }
+            }
+            AnyParseNode::OrdGroup(parse_node_ord_group) => {
+                parse_node_ord_group.loc = None;
+                strip_positions(&mut parse_node_ord_group.body);
+            }
+            AnyParseNode::SupSub(parse_node_sup_sub) => {
+                parse_node_sup_sub.loc = None;
+                if let Some(base) = &mut parse_node_sup_sub.base {
+                    strip_positions_single(base);
+                }
+                if let Some(sup) = &mut parse_node_sup_sub.sup {
+                    strip_positions_single(sup);
+                }
+                if let Some(sub) = &mut parse_node_sup_sub.sub {
+                    strip_positions_single(sub);
+                }
+            }
+            AnyParseNode::Genfrac(parse_node_genfrac) => {
+                parse_node_genfrac.loc = None;
+                strip_positions_single(&mut parse_node_genfrac.numer);
+                strip_positions_single(&mut parse_node_genfrac.denom);
+            }
+            AnyParseNode::LeftRight(parse_node_left_right) => {
+                parse_node_left_right.loc = None;
+                strip_positions(&mut parse_node_left_right.body);
+            }
+            AnyParseNode::LeftRightRight(parse_node_left_right_right) => {
+                parse_node_left_right_right.loc = None;
+            }
+            AnyParseNode::Sqrt(parse_node_sqrt) => {
+                parse_node_sqrt.loc = None;
+                strip_positions_single(&mut parse_node_sqrt.body);
+                if let Some(index) = &mut parse_node_sqrt.index {
+                    strip_positions_single(index);
+                }
+            }
+            AnyParseNode::Atom(parse_node_atom) => {
+                parse_node_atom.loc = None;
+            }
+            AnyParseNode::MathOrd(parse_node_math_ord) => {
+                parse_node_math_ord.loc = None;
+            }
+            AnyParseNode::Op(parse_node_op) => match parse_node_op {
+                ParseNodeOp::Symbol { loc, .. } => *loc = None,
+                ParseNodeOp::Body { loc, body, .. } => {
+                    *loc = None;
+                    strip_positions(body);
+                }
+            },
+            AnyParseNode::Spacing(parse_node_spacing) => {
+                parse_node_spacing.loc = None;
+            }
+            AnyParseNode::Text(parse_node_text) => {
+                parse_node_text.loc = None;
+                strip_positions(&mut parse_node_text.body);
+            }
+            AnyParseNode::Styling(parse_node_styling) => {
+                parse_node_styling.loc = None;
+                strip_positions(&mut parse_node_styling.body);
+            }
+            AnyParseNode::Font(parse_node_font) => {
+                parse_node_font.loc = None;
+                strip_positions_single(&mut parse_node_font.body);
+            }
+            AnyParseNode::Color(parse_node_color) => {
+                parse_node_color.loc = None;
+                strip_positions(&mut parse_node_color.body);
+            }
+            AnyParseNode::Accent(parse_node_accent) => {
+                parse_node_accent.loc = None;
+                strip_positions_single(&mut parse_node_accent.base);
+            }
+            AnyParseNode::Overline(parse_node_overline) => {
+                parse_node_overline.loc = None;
+                strip_positions_single(&mut parse_node_overline.body);
+            }
+            AnyParseNode::Underline(parse_node_underline) => {
+                parse_node_underline.loc = None;
+                strip_positions_single(&mut parse_node_underline.body);
+            }
+            AnyParseNode::Phantom(parse_node_phantom) => {
+                parse_node_phantom.loc = None;
+                strip_positions(&mut parse_node_phantom.body);
+            }
+            AnyParseNode::Hphantom(parse_node_hphantom) => {
+                parse_node_hphantom.loc = None;
+                strip_positions_single(&mut parse_node_hphantom.body);
+            }
+            AnyParseNode::Vphantom(parse_node_vphantom) => {
+                parse_node_vphantom.loc = None;
+                strip_positions_single(&mut parse_node_vphantom.body);
+            }
+            AnyParseNode::Rule(parse_node_rule) => {
+                parse_node_rule.loc = None;
+            }
+            AnyParseNode::CdLabel(parse_node_cd_label) => {
+                parse_node_cd_label.loc = None;
+                strip_positions_single(&mut parse_node_cd_label.label);
+            }
+            AnyParseNode::CdLabelParent(parse_node_cd_label_parent) => {
+                parse_node_cd_label_parent.loc = None;
+                strip_positions_single(&mut parse_node_cd_label_parent.fragment);
+            }
+            AnyParseNode::ColorToken(parse_node_color_token) => {
+                parse_node_color_token.loc = None;
+            }
+            AnyParseNode::Raw(parse_node_raw) => {
+                parse_node_raw.loc = None;
+            }
+            AnyParseNode::Size(parse_node_size) => {
+                parse_node_size.loc = None;
+            }
+            AnyParseNode::Tag(parse_node_tag) => {
+                parse_node_tag.loc = None;
+                strip_positions(&mut parse_node_tag.body);
+                strip_positions(&mut parse_node_tag.tag);
+            }
+            AnyParseNode::Url(parse_node_url) => {
+                parse_node_url.loc = None;
+            }
+            AnyParseNode::Verb(parse_node_verb) => {
+                parse_node_verb.loc = None;
+            }
+            AnyParseNode::TextOrd(parse_node_text_ord) => {
+                parse_node_text_ord.loc = None;
+            }
+            AnyParseNode::AccentToken(parse_node_accent_token) => {
+                parse_node_accent_token.loc = None;
+            }
+            AnyParseNode::OpToken(parse_node_op_token) => {
+                parse_node_op_token.loc = None;
+            }
+            AnyParseNode::AccentUnder(parse_node_accent_under) => {
+                parse_node_accent_under.loc = None;
+                strip_positions_single(&mut parse_node_accent_under.base);
+            }
+            AnyParseNode::Cr(parse_node_cr) => {
+                parse_node_cr.loc = None;
+            }
+            AnyParseNode::Nuclide(parse_node_nuclide) => {
+                parse_node_nuclide.loc = None;
+                strip_positions_single(&mut parse_node_nuclide.base);
+                if let Some(mass) = &mut parse_node_nuclide.mass {
+                    strip_positions_single(mass);
+                }
+                if let Some(atomic) = &mut parse_node_nuclide.atomic {
+                    strip_positions_single(atomic);
+                }
+            }
+            AnyParseNode::Diagbox(parse_node_diagbox) => {
+                parse_node_diagbox.loc = None;
+                strip_positions_single(&mut parse_node_diagbox.lower);
+                strip_positions_single(&mut parse_node_diagbox.upper);
+            }
+            AnyParseNode::Delimsizing(parse_node_delimsizing) => {
+                parse_node_delimsizing.loc = None;
+            }
+            AnyParseNode::Enclose(parse_node_enclose) => {
+                parse_node_enclose.loc = None;
+                strip_positions_single(&mut parse_node_enclose.body);
+            }
+            AnyParseNode::Environment(parse_node_environment) => {
+                parse_node_environment.loc = None;
+                strip_positions_single(&mut parse_node_environment.name_group);
+            }
+            AnyParseNode::Hbox(parse_node_hbox) => {
+                parse_node_hbox.loc = None;
+                strip_positions(&mut parse_node_hbox.body);
+            }
+            AnyParseNode::HorizBrace(parse_node_horiz_brace) => {
+                parse_node_horiz_brace.loc = None;
+                strip_positions_single(&mut parse_node_horiz_brace.base);
+            }
+            AnyParseNode::Href(parse_node_href) => {
+                parse_node_href.loc = None;
+                strip_positions(&mut parse_node_href.body);
+            }
+            AnyParseNode::Html(parse_node_html) => {
+                parse_node_html.loc = None;
+                strip_positions(&mut parse_node_html.body);
+            }
+            AnyParseNode::HtmlMathMl(parse_node_html_math_ml) => {
+                parse_node_html_math_ml.loc = None;
+                strip_positions(&mut parse_node_html_math_ml.html);
+                strip_positions(&mut parse_node_html_math_ml.mathml);
+            }
+            AnyParseNode::Includegraphics(parse_node_includegraphics) => {
+                parse_node_includegraphics.loc = None;
+            }
+            AnyParseNode::Infix(parse_node_infix) => {
+                parse_node_infix.loc = None;
+            }
+            AnyParseNode::Internal(parse_node_internal) => {
+                parse_node_internal.loc = None;
+            }
+            AnyParseNode::Kern(parse_node_kern) => {
+                parse_node_kern.loc = None;
+            }
+            AnyParseNode::Lap(parse_node_lap) => {
+                parse_node_lap.loc = None;
+                strip_positions_single(&mut parse_node_lap.body);
+            }
+            AnyParseNode::MathChoice(parse_node_math_choice) => {
+                parse_node_math_choice.loc = None;
+                strip_positions(&mut parse_node_math_choice.display);
+                strip_positions(&mut parse_node_math_choice.text);
+                strip_positions(&mut parse_node_math_choice.script);
+                strip_positions(&mut parse_node_math_choice.scriptscript);
+            }
+            AnyParseNode::Middle(parse_node_middle) => {
+                parse_node_middle.loc = None;
+            }
+            AnyParseNode::Mclass(parse_node_mclass) => {
+                parse_node_mclass.loc = None;
+                strip_positions(&mut parse_node_mclass.body);
+            }
+            AnyParseNode::OperatorName(parse_node_operator_name) => {
+                parse_node_operator_name.loc = None;
+                strip_positions(&mut parse_node_operator_name.body);
+            }
+            AnyParseNode::Pmb(parse_node_pmb) => {
+                parse_node_pmb.loc = None;
+                strip_positions(&mut parse_node_pmb.body);
+            }
+            AnyParseNode::Raisebox(parse_node_raisebox) => {
+                parse_node_raisebox.loc = None;
+                strip_positions_single(&mut parse_node_raisebox.body);
+            }
+            AnyParseNode::Sideset(parse_node_sideset) => {
+                parse_node_sideset.loc = None;
+                strip_positions_single(&mut parse_node_sideset.base);
+                for script in [
+                    &mut parse_node_sideset.pre_sup,
+                    &mut parse_node_sideset.pre_sub,
+                    &mut parse_node_sideset.post_sup,
+                    &mut parse_node_sideset.post_sub,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    strip_positions_single(script);
+                }
+            }
+            AnyParseNode::Tensor(parse_node_tensor) => {
+                parse_node_tensor.loc = None;
+                strip_positions_single(&mut parse_node_tensor.base);
+                for index in parse_node_tensor
+                    .pre
+                    .iter_mut()
+                    .chain(parse_node_tensor.post.iter_mut())
+                {
+                    match index {
+                        TensorIndex::Upper(script) | TensorIndex::Lower(script) => {
+                            strip_positions_single(script);
+                        }
+                    }
+                }
+            }
+            AnyParseNode::Sizing(parse_node_sizing) => {
+                parse_node_sizing.loc = None;
+                strip_positions(&mut parse_node_sizing.body);
+            }
+            AnyParseNode::Smash(parse_node_smash) => {
+                parse_node_smash.loc = None;
+                strip_positions_single(&mut parse_node_smash.body);
+            }
+            AnyParseNode::Vcenter(parse_node_vcenter) => {
+                parse_node_vcenter.loc = None;
+                strip_positions_single(&mut parse_node_vcenter.body);
+            }
+            AnyParseNode::XArrow(parse_node_xarrow) => {
+                parse_node_xarrow.loc = None;
+                if let Some(body) = &mut parse_node_xarrow.body {
+                    strip_positions_single(body);
+                }
+                if let Some(below) = &mut parse_node_xarrow.below {
+                    strip_positions_single(below);
+                }
+            }
+        }
+    }
+}
+
+/// Strips the positions of a single node, see [`strip_positions`]
+fn strip_positions_single(node: &mut ParseNode) {
+    strip_positions(slice::from_mut(node));
+}
+
+/// An expression under test, created by [`expect!`]
+pub struct TestExpr<'a> {
+    /// The LaTeX input
+    pub expr: String,
+    /// The context to parse and build with
+    pub ctx: &'a KatexContext,
+    /// Source file of the assertion
+    pub file: &'static str,
+    /// Line of the assertion
+    pub line: u32,
+    /// The expression passed to [`expect!`], as written
+    pub code: &'static str,
+}
+
+impl TestExpr<'_> {
+    /// Creates an expression tested against [`default_ctx`]; use
+    /// [`expect!`] to fill in the location.
+    #[must_use]
+    pub fn new(expr: &str, location: TestLocation) -> TestExpr<'static> {
+        TestExpr {
+            expr: expr.to_owned(),
+            ctx: default_ctx(),
+            file: location.file,
+            line: location.line,
+            code: location.code,
+        }
+    }
+
+    /// Tests against `ctx` instead of the default context, e.g. one with
+    /// custom functions or macros defined.
+    #[must_use]
+    pub fn in_context(self, ctx: &KatexContext) -> TestExpr<'_> {
+        TestExpr {
+            expr: self.expr,
+            ctx,
+            file: self.file,
+            line: self.line,
+            code: self.code,
+        }
+    }
+
+    /// Asserts that the expression parses.
+    pub fn to_parse(&self, settings: &Settings) -> TestResult<()> {
+        self.wrap_parse(parse(self.ctx, &self.expr, settings))?;
+        Ok(())
+    }
+
+    /// Asserts that the expression does not parse.
+    pub fn not_to_parse(self, settings: &Settings) -> TestResult<()> {
+        let location = self.location();
+        match parse(self.ctx, &self.expr, settings) {
+            Ok(_) => Err(TestError::ExpectedParseFailure {
+                expression: self.expr,
+                location,
+            }),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Asserts that the expression parses to the same tree as `other`,
+    /// ignoring source positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics with both trees when they differ.
+    pub fn to_parse_like(&self, other: &str, settings: &Settings) -> TestResult<()> {
+        let mut tree1 = self.wrap_parse(parse(self.ctx, &self.expr, settings))?;
+        let mut tree2 = self.wrap_parse_with_expr(other, parse(self.ctx, other, settings))?;
+
+        strip_positions(&mut tree1);
+        strip_positions(&mut tree2);
+
+        assert_eq!(
+            tree1, tree2,
+            "Parse trees do not match between '{}' and '{}'",
+            self.expr, other
+        );
+        Ok(())
+    }
+
+    /// Asserts that the expression does not parse, and returns the error.
+    pub fn parse_error(&self, settings: &Settings) -> TestResult<ParseError> {
+        match parse(self.ctx, &self.expr, settings) {
+            Ok(_) => Err(TestError::ExpectedParseFailure {
+                expression: self.expr.clone(),
+                location: self.location(),
+            }),
+            Err(error) => Ok(error),
+        }
+    }
+
+    /// Asserts that the expression builds a DOM tree.
+    pub fn to_build(&self, settings: &Settings) -> TestResult<()> {
+        self.wrap_parse(render_to_dom_tree(self.ctx, &self.expr, settings))?;
+        Ok(())
+    }
+
+    /// Asserts that building the expression fails.
+    pub fn not_to_build(self, settings: &Settings) -> TestResult<()> {
+        let location = self.location();
+        match render_to_dom_tree(self.ctx, &self.expr, settings) {
+            Ok(_) => Err(TestError::ExpectedBuildFailure {
+                expression: self.expr,
+                location,
+            }),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Asserts that the expression builds the same DOM tree as `other`.
+    pub fn to_build_like(&self, other: &str, settings: &Settings) -> TestResult<()> {
+        let dom1 = self.wrap_parse(render_to_dom_tree(self.ctx, &self.expr, settings))?;
+        let dom2 =
+            self.wrap_parse_with_expr(other, render_to_dom_tree(self.ctx, other, settings))?;
+
+        let dom1_debug = format!("{dom1:?}");
+        let dom2_debug = format!("{dom2:?}");
+        if dom1_debug == dom2_debug {
+            Ok(())
+        } else {
+            Err(TestError::DomMismatch(Box::new(DomMismatchDetails {
+                left_expr: self.expr.clone(),
+                right_expr: other.to_owned(),
+                left_dom: dom1_debug,
+                right_dom: dom2_debug,
+                location: self.location(),
+            })))
+        }
+    }
+
+    /// Asserts that rendering the expression to a string fails.
+    pub fn not_to_html(self, settings: &Settings) -> TestResult<()> {
+        let location = self.location();
+        match render_to_string(self.ctx, &self.expr, settings) {
+            Ok(html) => Err(TestError::ExpectedHtmlFailure {
+                expression: self.expr,
+                location,
+                html,
+            }),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Where the assertion was written
+    #[must_use]
+    pub const fn location(&self) -> TestLocation {
+        TestLocation {
+            file: self.file,
+            line: self.line,
+            code: self.code,
+        }
+    }
+
+    fn wrap_parse<T>(&self, result: Result<T, ParseError>) -> TestResult<T> {
+        result.map_err(|source| TestError::Parse {
+            expression: self.expr.clone(),
+            location: self.location(),
+            source,
+        })
+    }
+
+    fn wrap_parse_with_expr<T>(&self, expr: &str, result: Result<T, ParseError>) -> TestResult<T> {
+        result.map_err(|source| TestError::Parse {
+            expression: expr.to_owned(),
+            location: self.location(),
+            source,
+        })
+    }
+}
+
+/// Starts an assertion about a LaTeX expression, recording the file and line
+/// for error messages.
+///
+/// The expression is tested against [`default_ctx`]; call
+/// [`TestExpr::in_context`] to use another context.
+#[macro_export]
+macro_rules! expect {
+    ($e:expr) => {
+        $crate::TestExpr::new(
+            $e,
+            $crate::TestLocation {
+                file: file!(),
+                line: line!(),
+                code: stringify!($e),
+            },
+        )
+    };
+}
@@ -0,0 +1,318 @@
+//! Normalizing rendered markup for snapshot tests
+//!
+//! Snapshots of rendered HTML and MathML churn on changes that do not affect
+//! the output: a length that moved in the fifth decimal, declarations or
+//! class names emitted in a different order. The helpers here rewrite the
+//! attributes of every tag into a canonical form, so that a snapshot only
+//! changes when the rendering does:
+//!
+//! ```rust
+//! use katex::{Settings, render_to_string};
+//! use katex_test_utils::default_ctx;
+//! use katex_test_utils::snapshot::normalize_markup;
+//!
+//! let html = render_to_string(default_ctx(), r"\frac{a}{b}", &Settings::default()).unwrap();
+//! let snapshot = normalize_markup(&html);
+//! assert!(snapshot.contains(r#"<span class="mfrac">"#));
+//! // insta::assert_snapshot!(snapshot);
+//! ```
+//!
+//! Only attributes are rewritten; text content, including numbers in `<mn>`
+//! elements, is left as is.
+
+use core::fmt::Write as _;
+
+/// Decimal places [`normalize_markup`] rounds attribute numbers to
+pub const SNAPSHOT_PRECISION: usize = 3;
+
+/// Applies every normalization of this module: sorts attributes, style
+/// declarations and class names, and rounds numbers in attribute values to
+/// [`SNAPSHOT_PRECISION`] decimals.
+#[must_use]
+pub fn normalize_markup(markup: &str) -> String {
+    map_tags(markup, |attributes| {
+        for (name, value) in attributes.iter_mut() {
+            if let Some(value) = value {
+                *value = round_numbers(value, SNAPSHOT_PRECISION);
+                match name.as_str() {
+                    "style" => *value = sort_declarations(value),
+                    "class" => *value = sort_classes(value),
+                    _ => {}
+                }
+            }
+        }
+        attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    })
+}
+
+/// Sorts the attributes of every tag by name.
+#[must_use]
+pub fn normalize_html_attributes(markup: &str) -> String {
+    map_tags(markup, |attributes| {
+        attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    })
+}
+
+/// Sorts the declarations of every `style` attribute.
+///
+/// A trailing semicolon is kept when the attribute had one.
+#[must_use]
+pub fn normalize_style_attributes(markup: &str) -> String {
+    map_attribute(markup, "style", sort_declarations)
+}
+
+/// Sorts the names of every `class` attribute.
+#[must_use]
+pub fn sort_class_names(markup: &str) -> String {
+    map_attribute(markup, "class", sort_classes)
+}
+
+/// Rounds every decimal number in attribute values to `decimals` places,
+/// dropping trailing zeros.
+///
+/// Integers are left alone, so `0.27778em` becomes `0.278em` at three
+/// decimals while `viewBox="0 0 400000 1080"` is unchanged.
+#[must_use]
+pub fn round_attribute_numbers(markup: &str, decimals: usize) -> String {
+    map_tags(markup, |attributes| {
+        for value in attributes
+            .iter_mut()
+            .filter_map(|(_, value)| value.as_mut())
+        {
+            *value = round_numbers(value, decimals);
+        }
+    })
+}
+
+/// Sorts the entries of every `attributes: {` block in pretty-printed debug
+/// output, which come from a hash map and have no stable order.
+#[must_use]
+pub fn normalize_debug_snapshot(debug: &str) -> String {
+    let mut result = Vec::new();
+    let mut lines = debug.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        result.push(line);
+        if line.trim_end().ends_with("attributes: {") {
+            let mut attributes = Vec::new();
+            while let Some(entry) = lines.next_if(|next| !matches!(next.trim(), "}," | "}")) {
+                attributes.push(entry);
+            }
+            attributes.sort_unstable();
+            result.extend(attributes);
+        }
+    }
+
+    result.join("\n")
+}
+
+/// Attribute names and values of a tag; `None` for attributes without value
+type Attributes = Vec<(String, Option<String>)>;
+
+fn map_attribute(markup: &str, attribute: &str, f: impl Fn(&str) -> String) -> String {
+    map_tags(markup, |attributes| {
+        for (name, value) in attributes.iter_mut() {
+            if let Some(value) = value.as_mut().filter(|_| name == attribute) {
+                *value = f(value);
+            }
+        }
+    })
+}
+
+/// Calls `f` with the attributes of every start tag and writes the tag back
+/// with single spaces between its attributes.
+fn map_tags(markup: &str, mut f: impl FnMut(&mut Attributes)) -> String {
+    let mut output = String::with_capacity(markup.len());
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let is_start_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+        // Attribute values are escaped, so the first `>` closes the tag
+        let Some(end) = rest.find('>').filter(|_| is_start_tag) else {
+            output.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let name_end = tag
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(tag.len());
+        let (name, attributes) = tag.split_at(name_end);
+        let attributes = attributes.trim();
+        let (attributes, self_closing) = attributes
+            .strip_suffix('/')
+            .map_or((attributes, false), |attributes| (attributes, true));
+        let mut attributes = parse_attributes(attributes);
+        f(&mut attributes);
+
+        output.push('<');
+        output.push_str(name);
+        for (name, value) in &attributes {
+            output.push(' ');
+            output.push_str(name);
+            if let Some(value) = value {
+                let _ = write!(output, "=\"{value}\"");
+            }
+        }
+        if self_closing {
+            output.push('/');
+        }
+        output.push('>');
+    }
+    output.push_str(rest);
+    output
+}
+
+fn parse_attributes(mut source: &str) -> Attributes {
+    let mut attributes = Vec::new();
+    loop {
+        source = source.trim_start();
+        if source.is_empty() {
+            return attributes;
+        }
+        let name_end = source
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(source.len());
+        if name_end == 0 {
+            // An unquoted value; keep the rest of the tag as it is
+            attributes.push((source.to_owned(), None));
+            return attributes;
+        }
+        let name = source[..name_end].to_owned();
+        source = &source[name_end..];
+        let value = source.strip_prefix("=\"").map(|quoted| {
+            let value_end = quoted.find('"').unwrap_or(quoted.len());
+            source = quoted.get(value_end + 1..).unwrap_or_default();
+            quoted[..value_end].to_owned()
+        });
+        attributes.push((name, value));
+    }
+}
+
+fn sort_declarations(style: &str) -> String {
+    let mut declarations: Vec<&str> = style
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+        .collect();
+    declarations.sort_unstable();
+    let mut sorted = declarations.join(";");
+    if style.trim_end().ends_with(';') && !sorted.is_empty() {
+        sorted.push(';');
+    }
+    sorted
+}
+
+fn sort_classes(classes: &str) -> String {
+    let mut names: Vec<&str> = classes.split_whitespace().collect();
+    names.sort_unstable();
+    names.join(" ")
+}
+
+fn round_numbers(value: &str, decimals: usize) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '.') {
+        // Digits that continue an identifier, like the 1 in `size1`
+        let in_word = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let integer_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let fraction_len = rest[integer_len..].strip_prefix('.').map_or(0, |fraction| {
+            1 + fraction
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(fraction.len())
+        });
+        let len = integer_len + fraction_len;
+        let number = &rest[..len];
+        rest = &rest[len..];
+
+        match number.parse::<f64>() {
+            Ok(parsed) if fraction_len > 1 && !in_word => {
+                let mut rounded = format!("{parsed:.decimals$}");
+                if rounded.contains('.') {
+                    rounded.truncate(rounded.trim_end_matches('0').trim_end_matches('.').len());
+                }
+                if rounded == "0" && output.ends_with('-') {
+                    output.pop();
+                }
+                output.push_str(&rounded);
+            }
+            _ => output.push_str(number),
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorts_attributes_and_keeps_self_closing_tags() {
+        assert_eq!(
+            normalize_html_attributes(r#"<a style="x" class="b"><br/><img src="s" alt="a" />"#),
+            r#"<a class="b" style="x"><br/><img alt="a" src="s"/>"#
+        );
+    }
+
+    #[test]
+    fn test_sorts_style_declarations() {
+        assert_eq!(
+            normalize_style_attributes(r#"<span style="top:1em;height:2em;">"#),
+            r#"<span style="height:2em;top:1em;">"#
+        );
+        assert_eq!(
+            normalize_style_attributes(r#"<span style="b:1 ; a:2">"#),
+            r#"<span style="a:2;b:1">"#
+        );
+    }
+
+    #[test]
+    fn test_sorts_class_names() {
+        assert_eq!(
+            sort_class_names(r#"<span class="mord mathnormal">x</span>"#),
+            r#"<span class="mathnormal mord">x</span>"#
+        );
+    }
+
+    #[test]
+    fn test_rounds_numbers_in_attributes_only() {
+        assert_eq!(
+            round_attribute_numbers(
+                r#"<span class="size1" style="height:0.27778em;top:-0.0001em;width:.5em">1.23456</span>"#,
+                3
+            ),
+            r#"<span class="size1" style="height:0.278em;top:0em;width:0.5em">1.23456</span>"#
+        );
+        assert_eq!(
+            round_attribute_numbers(r#"<svg viewBox="0 0 400000 1080">"#, 3),
+            r#"<svg viewBox="0 0 400000 1080">"#
+        );
+    }
+
+    #[test]
+    fn test_leaves_text_and_closing_tags_alone() {
+        let markup = "<mi>a</mi><mo>&lt;</mo><mn>0.1234</mn>";
+        assert_eq!(normalize_markup(markup), markup);
+    }
+
+    #[test]
+    fn test_sorts_debug_attributes() {
+        let debug = "attributes: {\n    \"b\": 1,\n    \"a\": 2,\n},\nnext";
+        assert_eq!(
+            normalize_debug_snapshot(debug),
+            "attributes: {\n    \"a\": 2,\n    \"b\": 1,\n},\nnext"
+        );
+    }
+}
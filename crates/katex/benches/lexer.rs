@@ -0,0 +1,46 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use katex::Settings;
+use katex::lexer::Lexer;
+
+/// Prose in `\text`, the case the lexer's plain-run scan is for
+const TEXT: &str = r"\text{The quick brown fox jumps over the lazy dog, and then it rests (for a while) before running again; 0123456789!}";
+
+/// A formula with short runs between control sequences and spaces
+const FORMULA: &str = r"x = \frac{-b \pm \sqrt{b^2 - 4ac}}{2a}, \quad \sum_{i=1}^{n} x_i^2 \le \int_0^\infty e^{-t}\,dt";
+
+/// Non-ASCII text, which always takes the character-by-character path
+const UNICODE: &str =
+    "\\text{\u{3b1}\u{3b2}\u{3b3} d\u{e9}j\u{e0} vu, na\u{ef}ve caf\u{e9} \u{2264} \u{221e}}";
+
+fn lex_all(input: &Arc<str>, settings: &Settings) -> usize {
+    let mut lexer = Lexer::new(Arc::clone(input), settings);
+    let mut tokens = 0;
+    while let Ok(token) = lexer.lex() {
+        if token.text == "EOF" {
+            break;
+        }
+        tokens += 1;
+    }
+    tokens
+}
+
+fn bench_lexer(c: &mut Criterion) {
+    let settings = Settings::default();
+    let mut group = c.benchmark_group("lexer");
+    for (name, input) in [("text", TEXT), ("formula", FORMULA), ("unicode", UNICODE)] {
+        let input: Arc<str> = Arc::from(input.repeat(32));
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(lex_all(black_box(&input), &settings)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lexer);
+criterion_main!(benches);
@@ -91,5 +91,12 @@ fn bench_rendering(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_rendering);
+fn bench_context(c: &mut Criterion) {
+    let mut group = c.benchmark_group("katex_context");
+    group.bench_function("default", |b| b.iter(|| black_box(KatexContext::default())));
+    group.bench_function("minimal", |b| b.iter(|| black_box(KatexContext::minimal())));
+    group.finish();
+}
+
+criterion_group!(benches, bench_rendering, bench_context);
 criterion_main!(benches);
@@ -4,8 +4,9 @@ use alloc::collections::{BTreeMap, BTreeSet};
 use core::{
     error::Error as CoreError,
     fmt::{self, Write as _},
-    iter::Peekable,
+    iter::{self, Peekable},
 };
+use serde::de::DeserializeOwned;
 use std::{
     env, fs,
     fs::File,
@@ -45,6 +46,8 @@ fn main() -> BuildResult<()> {
     println!("cargo:rerun-if-changed=data/font_metrics_data.json");
     println!("cargo:rerun-if-changed=data/symbols.json");
     println!("cargo:rerun-if-changed=data/sigmas_and_xis.json");
+    println!("cargo:rerun-if-changed=data/katex.min.css");
+    println!("cargo:rerun-if-changed=data/upstream.json");
 
     let out_dir = PathBuf::from(env::var("OUT_DIR")?);
 
@@ -60,9 +63,39 @@ fn main() -> BuildResult<()> {
     let symbols = generate_symbols()?;
     write_file(out_dir.join("generated_symbols_data.rs"), &symbols)?;
 
+    let upstream = generate_upstream()?;
+    write_file(out_dir.join("upstream_generated.rs"), &upstream)?;
+
     Ok(())
 }
 
+/// Embeds the vendored KaTeX stylesheet and the upstream version it came from.
+///
+/// Both files are written by `cargo xtask extract-data`. A checkout without
+/// them still builds, with empty constants and a warning.
+fn generate_upstream() -> BuildResult<String> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let css_path = manifest_dir.join("data").join("katex.min.css");
+    let upstream_path = manifest_dir.join("data").join("upstream.json");
+    if !css_path.exists() || !upstream_path.exists() {
+        println!(
+            "cargo:warning=KaTeX stylesheet not vendored, KATEX_CSS and UPSTREAM_VERSION are empty; run `cargo xtask extract-data`"
+        );
+        return Ok("pub const CSS: &str = \"\";\npub const VERSION: &str = \"\";\n".to_owned());
+    }
+
+    let upstream: serde_json::Value = serde_json::from_str(&fs::read_to_string(&upstream_path)?)?;
+    let version = upstream["version"]
+        .as_str()
+        .ok_or_else(|| BuildScriptError("data/upstream.json has no version".to_owned()))?;
+    let css_path = css_path
+        .to_str()
+        .ok_or_else(|| BuildScriptError("non-UTF-8 path to data/katex.min.css".to_owned()))?;
+    Ok(format!(
+        "pub const CSS: &str = include_str!({css_path:?});\npub const VERSION: &str = {version:?};\n"
+    ))
+}
+
 // Function to convert camelCase to snake_case
 fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
@@ -156,8 +189,14 @@ fn generate_font_metrics() -> BuildResult<String> {
     use phf_codegen::Map as PhfMap;
 
     let json_data = fs::read_to_string("data/font_metrics_data.json")?;
-    let font_metrics: BTreeMap<String, BTreeMap<String, Vec<f64>>> =
+    let mut font_metrics: BTreeMap<String, BTreeMap<String, Vec<f64>>> =
         serde_json::from_str(&json_data)?;
+    if let Some(path) = extra_data_path("KATEX_EXTRA_METRICS") {
+        let extra: BTreeMap<String, BTreeMap<String, Vec<f64>>> = read_extra_data(&path)?;
+        for (font_family, metrics) in extra {
+            font_metrics.entry(font_family).or_default().extend(metrics);
+        }
+    }
 
     let mut output = String::new();
     let mut font_index = PhfMap::new();
@@ -257,7 +296,12 @@ pub const UNICODE_SYMBOLS: phf::Map<char, &str> = \n{};\n",
 
 fn generate_symbols() -> BuildResult<String> {
     let json_data = fs::read_to_string("data/symbols.json")?;
-    let symbol_data: Vec<Symbol> = serde_json::from_str(&json_data)?;
+    let mut symbol_data: Vec<Symbol> = serde_json::from_str(&json_data)?;
+    if let Some(path) = extra_data_path("KATEX_EXTRA_SYMBOLS") {
+        let extra: Vec<Symbol> = read_extra_data(&path)?;
+        symbol_data.retain(|s| !extra.iter().any(|e| e.mode == s.mode && e.name == s.name));
+        symbol_data.extend(extra);
+    }
 
     let (math_symbols, text_symbols): (Vec<Symbol>, Vec<Symbol>) =
         symbol_data.into_iter().partition(|s| s.mode == "math");
@@ -267,8 +311,12 @@ fn generate_symbols() -> BuildResult<String> {
     let _ = writeln!(&mut output, "// Generated from data/symbols.json\n");
     let _ = writeln!(&mut output, "/// Populate math symbols from JSON data");
     write_symbols(&mut output, &math_symbols, "POPULATE_MATH_SYMBOLS")?;
+    let _ = writeln!(&mut output, "/// Names of the math symbols from JSON data");
+    write_symbol_names(&mut output, &math_symbols, "MATH_SYMBOL_NAMES")?;
     let _ = writeln!(&mut output, "/// Populate text symbols from JSON data");
     write_symbols(&mut output, &text_symbols, "POPULATE_TEXT_SYMBOLS")?;
+    let _ = writeln!(&mut output, "/// Names of the text symbols from JSON data");
+    write_symbol_names(&mut output, &text_symbols, "TEXT_SYMBOL_NAMES")?;
 
     Ok(output)
 }
@@ -279,6 +327,29 @@ fn write_file(path: PathBuf, contents: &str) -> BuildResult<()> {
     Ok(())
 }
 
+/// Returns the path of a supplemental data file named by the environment
+/// variable `var`, if it is set.
+///
+/// `KATEX_EXTRA_SYMBOLS` and `KATEX_EXTRA_METRICS` let downstream builds add
+/// symbols and font metrics without patching the crate. The files use the
+/// formats of `data/symbols.json` and `data/font_metrics_data.json`. Entries
+/// replace built-in symbols with the same mode and name, and built-in metrics
+/// for the same font and character. Relative paths are resolved from the
+/// crate directory.
+fn extra_data_path(var: &str) -> Option<String> {
+    println!("cargo:rerun-if-env-changed={var}");
+    let path = env::var(var).ok().filter(|path| !path.is_empty())?;
+    println!("cargo:rerun-if-changed={path}");
+    Some(path)
+}
+
+fn read_extra_data<T: DeserializeOwned>(path: &str) -> BuildResult<T> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| BuildScriptError(format!("failed to read {path}: {err}")))?;
+    serde_json::from_str(&contents)
+        .map_err(|err| BuildScriptError(format!("failed to parse {path}: {err}")).into())
+}
+
 fn read_json(path: &str) -> BuildResult<serde_json::Value> {
     let contents = fs::read_to_string(path)?;
     Ok(serde_json::from_str(&contents)?)
@@ -368,6 +439,11 @@ fn normalize_to_single_char(s: &str) -> Option<char> {
     }
 }
 
+/// Writes a lookup function for `symbols` named `variant` in lower case.
+///
+/// Names are bucketed by their length and last byte before matching them
+/// exactly. Each bucket holds a handful of names at most, so a lookup compares
+/// a few strings instead of hashing the whole token first.
 fn write_symbols(buffer: &mut String, symbols: &[Symbol], variant: &str) -> BuildResult<()> {
     let mut grouped: BTreeMap<(String, String, Option<String>), BTreeSet<String>> = BTreeMap::new();
 
@@ -385,18 +461,9 @@ fn write_symbols(buffer: &mut String, symbols: &[Symbol], variant: &str) -> Buil
         }
     }
 
-    let _ = writeln!(
-        buffer,
-        "const {variant}_MAP: phf::Map<&str, CharInfo> = phf_map!("
-    );
+    let mut buckets: BTreeMap<(usize, u8), BTreeMap<String, Vec<String>>> = BTreeMap::new();
 
     for ((font, group, replace), names) in grouped {
-        let arm = names
-            .into_iter()
-            .map(|s| convert_unicode_escapes(&s))
-            .collect::<Vec<_>>()
-            .join("\" | \"");
-
         let font_str = match font.as_str() {
             "main" => "Font::Main",
             "ams" => "Font::Ams",
@@ -423,14 +490,99 @@ fn write_symbols(buffer: &mut String, symbols: &[Symbol], variant: &str) -> Buil
             |s| format!("Some(\'{}\')", convert_unicode_escapes(s)),
         );
 
-        let _ = writeln!(buffer, "    \"{arm}\" => CharInfo {{");
-        let _ = writeln!(buffer, "        font: {font_str},");
-        let _ = writeln!(buffer, "        group: {group_str},");
-        let _ = writeln!(buffer, "        replace: {replace_value},");
-        let _ = writeln!(buffer, "    }},");
+        let info = format!(
+            "CharInfo {{ font: {font_str}, group: {group_str}, replace: {replace_value} }}"
+        );
+
+        for name in names {
+            let literal = convert_unicode_escapes(&name);
+            let bytes = unescape_literal(&literal)?.into_bytes();
+            let Some(&last) = bytes.last() else {
+                return Err(BuildScriptError("Empty symbol name".to_owned()).into());
+            };
+            buckets
+                .entry((bytes.len(), last))
+                .or_default()
+                .entry(info.clone())
+                .or_default()
+                .push(literal);
+        }
+    }
+
+    let _ = writeln!(
+        buffer,
+        "fn {}(name: &str) -> Option<&'static CharInfo> {{",
+        variant.to_lowercase()
+    );
+    let _ = writeln!(buffer, "    let bytes = name.as_bytes();");
+    let _ = writeln!(buffer, "    let &last = bytes.last()?;");
+    let _ = writeln!(buffer, "    match (bytes.len(), last) {{");
+
+    for ((len, last), arms) in buckets {
+        let _ = writeln!(buffer, "        ({len}, {last}) => match name {{");
+        for (info, literals) in arms {
+            let _ = writeln!(
+                buffer,
+                "            \"{}\" => Some(&{info}),",
+                literals.join("\" | \"")
+            );
+        }
+        let _ = writeln!(buffer, "            _ => None,");
+        let _ = writeln!(buffer, "        }},");
     }
 
-    let _ = writeln!(buffer, ");\n");
+    let _ = writeln!(buffer, "        _ => None,");
+    let _ = writeln!(buffer, "    }}");
+    let _ = writeln!(buffer, "}}\n");
+
+    Ok(())
+}
+
+/// Writes the names of `symbols`, including the Unicode characters they
+/// accept, as a constant named `variant`, sorted so that it can be binary
+/// searched.
+fn write_symbol_names(buffer: &mut String, symbols: &[Symbol], variant: &str) -> BuildResult<()> {
+    let mut names = BTreeMap::new();
+    for symbol in symbols {
+        let accepted = symbol
+            .replace
+            .as_ref()
+            .filter(|_| symbol.accept_unicode_char);
+        for name in iter::once(&symbol.name).chain(accepted) {
+            let literal = convert_unicode_escapes(name);
+            names.insert(unescape_literal(&literal)?, literal);
+        }
+    }
 
+    let _ = writeln!(buffer, "const {variant}: &[&str] = &[");
+    for literal in names.values() {
+        let _ = writeln!(buffer, "    \"{literal}\",");
+    }
+    let _ = writeln!(buffer, "];\n");
     Ok(())
 }
+
+/// Decodes the `\\` and `\u{..}` escapes of a string literal's contents
+fn unescape_literal(literal: &str) -> BuildResult<String> {
+    let mut result = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => result.push('\\'),
+            Some('u') => {
+                let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                let decoded = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| BuildScriptError(format!("Invalid escape in {literal}")))?;
+                result.push(decoded);
+            }
+            _ => return Err(BuildScriptError(format!("Invalid escape in {literal}")).into()),
+        }
+    }
+    Ok(result)
+}
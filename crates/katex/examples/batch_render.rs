@@ -0,0 +1,109 @@
+//! Command-line batch renderer
+//!
+//! Renders one formula per line of the given files, or of the standard input
+//! without files, and writes the markup of each on a line of its own:
+//!
+//! ```sh
+//! printf '%s\n' 'x^2' '\sqrt{2}' | cargo run -p katex-rs --example batch_render
+//! cargo run -p katex-rs --example batch_render -- --display formulas.txt
+//! ```
+//!
+//! Blank lines are skipped. A formula that fails to parse is reported on the
+//! standard error with its line number, and the exit status is then 1.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::ExitCode;
+use std::{env, fs};
+
+use katex::{KatexContext, Settings, render_to_string};
+
+/// Renders every line of `input`, writing the markup to `output` and the
+/// errors to `errors`. Returns the number of formulas that failed.
+fn render_lines<R: BufRead, W: Write, E: Write>(
+    ctx: &KatexContext,
+    settings: &Settings,
+    name: &str,
+    input: R,
+    output: &mut W,
+    errors: &mut E,
+) -> io::Result<usize> {
+    let mut failed = 0;
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match render_to_string(ctx, &line, settings) {
+            // The paths of stretchy SVG images span several lines, where a
+            // space does as well
+            Ok(markup) => writeln!(output, "{}", markup.replace('\n', " "))?,
+            Err(error) => {
+                writeln!(errors, "{name}:{}: {error}", number + 1)?;
+                failed += 1;
+            }
+        }
+    }
+    Ok(failed)
+}
+
+fn main() -> io::Result<ExitCode> {
+    let mut display = false;
+    let mut files = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--display" => display = true,
+            _ => files.push(arg),
+        }
+    }
+
+    // Reuse the context and settings for every formula
+    let ctx = KatexContext::default();
+    let settings = Settings::builder().display_mode(display).build();
+    let mut output = io::stdout().lock();
+    let mut errors = io::stderr().lock();
+    let mut failed = 0;
+    if files.is_empty() {
+        let input = io::stdin().lock();
+        failed += render_lines(&ctx, &settings, "<stdin>", input, &mut output, &mut errors)?;
+    }
+    for file in &files {
+        let input = BufReader::new(fs::File::open(file)?);
+        failed += render_lines(&ctx, &settings, file, input, &mut output, &mut errors)?;
+    }
+
+    Ok(if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_each_line() {
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        let failed = render_lines(
+            &KatexContext::default(),
+            &Settings::default(),
+            "test",
+            b"x^2\n\n\\frac{1}{\n\\sqrt2\n".as_slice(),
+            &mut output,
+            &mut errors,
+        )
+        .unwrap();
+
+        assert_eq!(failed, 1);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(
+            output
+                .lines()
+                .all(|line| line.starts_with(r#"<span class="katex">"#))
+        );
+        let errors = String::from_utf8(errors).unwrap();
+        assert!(errors.starts_with("test:3: "), "{errors}");
+    }
+}
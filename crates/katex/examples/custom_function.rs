@@ -0,0 +1,96 @@
+//! Adding a command to the context
+//!
+//! Registers `\abs{x}` and `\norm{x}`, which stretch their bars to the
+//! height of the argument like `\left| x \right|` does, and renders a formula
+//! using them:
+//!
+//! ```sh
+//! cargo run -p katex-rs --example custom_function -- '\norm{\frac{v}{\abs{v}}} = 1'
+//! ```
+//!
+//! A handler turns the parsed arguments into a parse node. Reusing a node
+//! type that is built in, here the one of `\left...\right`, means its HTML and
+//! MathML builders apply without writing any. Simple commands that expand to
+//! other TeX are better written as macros in [`Settings::macros`].
+
+use std::env;
+
+use katex::define_function::{FunctionDefSpec, FunctionPropSpec, ord_argument};
+use katex::parser::parse_node::{ParseNode, ParseNodeLeftRight};
+use katex::types::ParseErrorKind;
+use katex::{KatexContext, ParseError, Settings, render_to_string};
+
+/// The default context with `\abs` and `\norm` added
+fn context() -> KatexContext {
+    let mut ctx = KatexContext::default();
+    ctx.define_function(FunctionDefSpec {
+        node_type: None,
+        names: &[r"\abs", r"\norm"],
+        props: FunctionPropSpec {
+            num_args: 1,
+            examples: &[r"\abs{x}", r"\norm{\frac{a}{b}}"],
+            ..Default::default()
+        },
+        handler: Some(|context, args, _opt_args| {
+            let delimiter = if context.func_name == r"\norm" {
+                r"\Vert"
+            } else {
+                r"\vert"
+            };
+            let Some(body) = args.first() else {
+                return Err(ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                }));
+            };
+            Ok(ParseNode::LeftRight(Box::new(ParseNodeLeftRight {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                body: ord_argument(body),
+                left: delimiter.to_owned(),
+                right: delimiter.to_owned(),
+                right_color: None,
+            })))
+        }),
+        html_builder: None,
+        mathml_builder: None,
+    });
+    ctx
+}
+
+fn main() -> Result<(), ParseError> {
+    let input = env::args()
+        .nth(1)
+        .unwrap_or_else(|| r"\norm{\frac{v}{\abs{v}}} = 1".to_owned());
+    let html = render_to_string(&context(), &input, &Settings::default())?;
+    println!("{html}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use katex_test_utils::{TestResult, check_function_examples, expect};
+
+    #[test]
+    fn test_parses_like_left_right() -> TestResult<()> {
+        let ctx = context();
+        let settings = Settings::default();
+        expect!(r"\abs{\frac{a}{b}}")
+            .in_context(&ctx)
+            .to_parse_like(r"\left\vert\frac{a}{b}\right\vert", &settings)?;
+        expect!(r"\norm x")
+            .in_context(&ctx)
+            .to_parse_like(r"\left\Vert x\right\Vert", &settings)
+    }
+
+    #[test]
+    fn test_renders_its_examples() -> TestResult<()> {
+        check_function_examples(&context())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_needs_an_argument() {
+        assert!(render_to_string(&context(), r"\abs", &Settings::default()).is_err());
+    }
+}
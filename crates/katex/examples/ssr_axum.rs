@@ -0,0 +1,127 @@
+//! Server-side rendering with axum
+//!
+//! Serves a page that renders the formula in its `tex` query parameter, so
+//! browsers get finished markup and need no JavaScript:
+//!
+//! ```sh
+//! cargo run -p katex-rs --example ssr_axum
+//! # then open http://127.0.0.1:3000/?tex=e^{i\pi}+1=0&display=true
+//! ```
+//!
+//! One [`KatexContext`] is shared by all requests, since building it is the
+//! expensive part. [`Settings`] are cheap and made per request.
+//!
+//! axum and tokio are not available on `wasm32`, where only the page
+//! rendering is built and tested.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use axum::{
+    Router,
+    extract::{Query, State},
+    response::Html,
+    routing::get,
+};
+use katex::{KatexContext, Settings, escape, render_to_string};
+use serde::Deserialize;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::net::TcpListener;
+
+/// Query parameters of the page
+#[derive(Debug, Default, Deserialize)]
+struct RenderQuery {
+    /// The formula, `\KaTeX` by default
+    tex: Option<String>,
+    /// Whether to render in display mode
+    #[serde(default)]
+    display: bool,
+}
+
+/// Renders the page for `query`, showing the error message if the formula
+/// does not parse.
+fn render_page(ctx: &KatexContext, query: &RenderQuery) -> String {
+    let tex = query.tex.as_deref().unwrap_or(r"\KaTeX");
+    let settings = Settings::builder().display_mode(query.display).build();
+    let body = match render_to_string(ctx, tex, &settings) {
+        Ok(markup) => markup,
+        Err(error) => format!(
+            r#"<p class="error">{}</p>"#,
+            escape::html_text(&error.to_string())
+        ),
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex/dist/katex.min.css">
+</head>
+<body>
+<form><input name="tex" value="{}"> <button>Render</button></form>
+{body}
+</body>
+</html>
+"#,
+        escape::html_attribute(tex)
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn page(
+    State(ctx): State<Arc<KatexContext>>,
+    Query(query): Query<RenderQuery>,
+) -> Html<String> {
+    Html(render_page(&ctx, &query))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    let app = Router::new()
+        .route("/", get(page))
+        .with_state(Arc::new(KatexContext::default()));
+    let listener = TcpListener::bind("127.0.0.1:3000").await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    println!(
+        "{}",
+        render_page(&KatexContext::default(), &RenderQuery::default())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_the_formula() {
+        let query = RenderQuery {
+            tex: Some(r"\frac12".to_owned()),
+            display: true,
+        };
+        let page = render_page(&KatexContext::default(), &query);
+        assert!(page.contains(r#"<span class="katex-display">"#));
+        assert!(page.contains(r#"<span class="mfrac">"#));
+        assert!(page.contains(r#"value="\frac12""#));
+    }
+
+    #[test]
+    fn test_shows_errors_escaped() {
+        let query = RenderQuery {
+            tex: Some(r"\frac<".to_owned()),
+            display: false,
+        };
+        let page = render_page(&KatexContext::default(), &query);
+        assert!(page.contains(r#"<p class="error">"#));
+        assert!(page.contains("&lt;"));
+        assert!(!page.contains(r#"class="katex""#));
+    }
+}
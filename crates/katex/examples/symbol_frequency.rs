@@ -0,0 +1,113 @@
+//! Walking the parse tree
+//!
+//! Parses the formulas given as arguments, or a sample formula without any,
+//! and prints how often each symbol occurs in them:
+//!
+//! ```sh
+//! cargo run -p katex-rs --example symbol_frequency -- 'a^2+b^2=c^2' '\sum_i a_i'
+//! ```
+//!
+//! [`parse_tree`] returns the same nodes the renderer builds from, so tools
+//! that lint, index or convert formulas can work on them directly. The walk
+//! below descends into the most common containers; a complete one would
+//! match every [`ParseNode`] variant.
+
+use std::collections::BTreeMap;
+use std::{env, slice};
+
+use katex::parse_tree::parse_tree;
+use katex::parser::parse_node::{ParseNode, ParseNodeOp};
+use katex::{KatexContext, ParseError, Settings};
+
+/// Counts the symbols in `nodes` and everything nested in them.
+fn count_symbols(nodes: &[ParseNode], counts: &mut BTreeMap<String, usize>) {
+    for node in nodes {
+        if let Some(text) = node.text() {
+            *counts.entry(text.to_owned()).or_default() += 1;
+        }
+        match node {
+            ParseNode::Op(op) => match op {
+                ParseNodeOp::Symbol { name, .. } => {
+                    *counts.entry(name.clone()).or_default() += 1;
+                }
+                ParseNodeOp::Body { body, .. } => count_symbols(body, counts),
+            },
+            ParseNode::OrdGroup(group) => count_symbols(&group.body, counts),
+            ParseNode::SupSub(supsub) => {
+                let scripts = [&supsub.base, &supsub.sup, &supsub.sub];
+                for script in scripts.into_iter().flatten() {
+                    count_symbols(slice::from_ref(script), counts);
+                }
+            }
+            ParseNode::Genfrac(frac) => {
+                count_symbols(slice::from_ref(&frac.numer), counts);
+                count_symbols(slice::from_ref(&frac.denom), counts);
+            }
+            ParseNode::Sqrt(sqrt) => {
+                count_symbols(slice::from_ref(&sqrt.body), counts);
+                count_symbols(sqrt.index.as_slice(), counts);
+            }
+            ParseNode::LeftRight(left_right) => count_symbols(&left_right.body, counts),
+            ParseNode::Accent(accent) => count_symbols(slice::from_ref(&accent.base), counts),
+            ParseNode::Font(font) => count_symbols(slice::from_ref(&font.body), counts),
+            ParseNode::Color(color) => count_symbols(&color.body, counts),
+            ParseNode::Styling(styling) => count_symbols(&styling.body, counts),
+            ParseNode::Text(text) => count_symbols(&text.body, counts),
+            ParseNode::Array(array) => {
+                for row in &array.body {
+                    count_symbols(row, counts);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Counts the symbols of every formula in `inputs`.
+fn symbol_frequency<S: AsRef<str>>(inputs: &[S]) -> Result<BTreeMap<String, usize>, ParseError> {
+    let ctx = KatexContext::default();
+    let settings = Settings::default();
+    let mut counts = BTreeMap::new();
+    for input in inputs {
+        count_symbols(&parse_tree(&ctx, input.as_ref(), &settings)?, &mut counts);
+    }
+    Ok(counts)
+}
+
+fn main() -> Result<(), ParseError> {
+    let mut inputs: Vec<String> = env::args().skip(1).collect();
+    if inputs.is_empty() {
+        inputs.push(
+            r"\int_0^1 \frac{x^2}{\sqrt{1-x^2}}\,dx + \sum_{n=1}^\infty \frac{1}{n^2}".to_owned(),
+        );
+    }
+
+    let mut counts: Vec<_> = symbol_frequency(&inputs)?.into_iter().collect();
+    // Most frequent first, then alphabetically
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    for (symbol, count) in counts {
+        println!("{count:>4}  {symbol}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_nested_symbols() {
+        let counts =
+            symbol_frequency(&["a^2+b^2=c^2", r"\frac{a}{\sqrt{a}}", r"\sum_i a_i"]).unwrap();
+        assert_eq!(counts["a"], 4);
+        assert_eq!(counts["2"], 3);
+        assert_eq!(counts["+"], 1);
+        assert_eq!(counts[r"\sum"], 1);
+        assert_eq!(counts["i"], 2);
+    }
+
+    #[test]
+    fn test_reports_parse_errors() {
+        assert!(symbol_frequency(&[r"\frac"]).is_err());
+    }
+}
@@ -0,0 +1,783 @@
+//! Braille renderings of parse trees
+//!
+//! Print formulas are two-dimensional; braille is read one cell at a time.
+//! The braille mathematics codes linearise a formula with indicators that
+//! announce scripts, fractions and radicals. This module writes a parse tree
+//! in either of the codes used for mathematics in English-speaking countries:
+//!
+//! - [`BrailleCode::Nemeth`], the Nemeth Code, with numbers in the lower part
+//!   of the cell, level indicators for scripts, and spaced comparison signs
+//! - [`BrailleCode::Ueb`], the technical material of Unified English Braille,
+//!   where scripts and roots apply to the next item or to a grouped item
+//!
+//! The output uses the Unicode braille patterns block (U+2800–U+28FF), with
+//! the blank pattern as the braille space and a newline between array rows.
+//! It covers letters, Greek letters, numbers, common operators, relations and
+//! delimiters, scripts, primes, fractions, radicals, operator limits and a
+//! few accents. Symbols without a braille equivalent in these tables are
+//! passed through as print characters, so that gaps stay visible rather than
+//! silently disappearing.
+
+use core::iter;
+
+use crate::parser::parse_node::{AnyParseNode, ParseNodeOp, TensorIndex};
+
+/// Braille code to transcribe mathematics in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BrailleCode {
+    /// The Nemeth Braille Code for Mathematics
+    #[default]
+    Nemeth,
+    /// Unified English Braille, technical material
+    Ueb,
+}
+
+/// Dot numbers of one or more braille cells, such as `&[46, 13]`
+///
+/// `0` stands for the blank cell.
+type Cells = &'static [u32];
+
+/// Converts dot numbers such as `1346` into a Unicode braille pattern
+fn cell(dots: u32) -> char {
+    let mut bits = 0;
+    let mut rest = dots;
+    while rest > 0 {
+        bits |= 1 << (rest % 10 - 1);
+        rest /= 10;
+    }
+    char::from_u32(0x2800 + bits).unwrap_or(' ')
+}
+
+/// The blank braille cell
+const BLANK: char = '\u{2800}';
+
+/// Dots of the letters `a` to `z`, shared by both codes
+const LETTERS: [u32; 26] = [
+    1, 12, 14, 145, 15, 124, 1245, 125, 24, 245, 13, 123, 134, 1345, 135, 1234, 12345, 1235, 234,
+    2345, 136, 1236, 2456, 1346, 13456, 1356,
+];
+
+/// Nemeth digits `0` to `9`, written in the lower part of the cell
+const NEMETH_DIGITS: [u32; 10] = [356, 2, 23, 25, 256, 26, 235, 2356, 236, 35];
+
+/// UEB digits `0` to `9`, the letters `j` and `a` to `i`
+const UEB_DIGITS: [u32; 10] = [245, 1, 12, 14, 145, 15, 124, 1245, 125, 24];
+
+/// Dots of a Greek letter, written after the Greek indicator
+fn greek(name: &str) -> Option<(u32, bool)> {
+    let lower = name.to_ascii_lowercase();
+    let lower = lower.strip_prefix("var").unwrap_or(&lower);
+    let dots = match lower {
+        "alpha" => 1,
+        "beta" => 12,
+        "gamma" => 1245,
+        "delta" => 145,
+        "epsilon" => 15,
+        "zeta" => 1356,
+        "eta" => 156,
+        "theta" => 1456,
+        "iota" => 24,
+        "kappa" => 13,
+        "lambda" => 123,
+        "mu" => 134,
+        "nu" => 1345,
+        "xi" => 1346,
+        "omicron" => 135,
+        "pi" => 1234,
+        "rho" => 1235,
+        "sigma" => 234,
+        "tau" => 2345,
+        "upsilon" => 136,
+        "phi" => 124,
+        "chi" => 12346,
+        "psi" => 13456,
+        "omega" => 2456,
+        _ => return None,
+    };
+    let capital = name.starts_with(|c: char| c.is_ascii_uppercase());
+    Some((dots, capital))
+}
+
+/// Nemeth symbols, with whether they are comparison signs, which are set off
+/// by spaces
+fn nemeth_symbol(text: &str) -> Option<(Cells, bool)> {
+    let symbol = match text {
+        "+" => (&[346][..], false),
+        "-" | "\u{2212}" => (&[36][..], false),
+        "\\times" => (&[4, 16][..], false),
+        "\\cdot" => (&[16][..], false),
+        "\\div" => (&[46, 34][..], false),
+        "\\pm" => (&[346, 36][..], false),
+        "\\mp" => (&[36, 346][..], false),
+        "/" => (&[456, 34][..], false),
+        "=" => (&[46, 13][..], true),
+        "<" => (&[5, 13][..], true),
+        ">" => (&[46, 2][..], true),
+        "\\leq" | "\\le" | "\u{2264}" => (&[5, 13, 156][..], true),
+        "\\geq" | "\\ge" | "\u{2265}" => (&[46, 2, 156][..], true),
+        "\\neq" | "\\ne" | "\u{2260}" => (&[34, 46, 13][..], true),
+        "\\in" => (&[4, 15][..], true),
+        "\\to" | "\\rightarrow" | "\\xrightarrow" => (&[1246, 25, 25, 135][..], true),
+        "(" => (&[12356][..], false),
+        ")" => (&[23456][..], false),
+        "[" | "\\lbrack" => (&[4, 12356][..], false),
+        "]" | "\\rbrack" => (&[4, 23456][..], false),
+        "\\{" | "\\lbrace" => (&[46, 12356][..], false),
+        "\\}" | "\\rbrace" => (&[46, 23456][..], false),
+        "|" | "\\vert" | "\\lvert" | "\\rvert" => (&[1256][..], false),
+        "," => (&[6][..], false),
+        "!" => (&[12346][..], false),
+        "'" | "\\prime" | "\u{2032}" => (&[3][..], false),
+        "\\infty" => (&[6, 123456][..], false),
+        "\\partial" => (&[4, 145][..], false),
+        "\\int" => (&[2346][..], false),
+        "\\sum" => (&[46, 6, 234][..], false),
+        "\\prod" => (&[46, 6, 1234][..], false),
+        "\\ldots" | "\\cdots" | "\\dots" => (&[3, 3, 3][..], false),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+/// UEB symbols
+fn ueb_symbol(text: &str) -> Option<Cells> {
+    let cells: Cells = match text {
+        "+" => &[5, 235],
+        "-" | "\u{2212}" => &[5, 36],
+        "\\times" => &[5, 236],
+        "\\cdot" => &[5, 256],
+        "\\div" => &[5, 34],
+        "\\pm" => &[456, 235],
+        "/" => &[456, 34],
+        "=" => &[5, 2356],
+        "<" => &[4, 126],
+        ">" => &[4, 345],
+        "\\leq" | "\\le" | "\u{2264}" => &[456, 4, 126],
+        "\\geq" | "\\ge" | "\u{2265}" => &[456, 4, 345],
+        "\\neq" | "\\ne" | "\u{2260}" => &[5, 2356, 4, 156],
+        "\\in" => &[4, 15],
+        "\\to" | "\\rightarrow" | "\\xrightarrow" => &[1256, 135],
+        "(" => &[5, 126],
+        ")" => &[5, 345],
+        "[" | "\\lbrack" => &[46, 126],
+        "]" | "\\rbrack" => &[46, 345],
+        "\\{" | "\\lbrace" => &[456, 126],
+        "\\}" | "\\rbrace" => &[456, 345],
+        "|" | "\\vert" | "\\lvert" | "\\rvert" => &[456, 1256],
+        "," => &[2],
+        "!" => &[235],
+        "'" | "\\prime" | "\u{2032}" => &[2356],
+        "\\infty" => &[3456, 1256],
+        "\\partial" => &[4, 145],
+        "\\int" => &[2346],
+        "\\sum" => &[6, 46, 234],
+        "\\prod" => &[6, 46, 1234],
+        "\\ldots" | "\\cdots" | "\\dots" => &[256, 256, 256],
+        _ => return None,
+    };
+    Some(cells)
+}
+
+/// Transcribes a parse tree into braille.
+///
+/// `display` selects whether operators with limits write them directly above
+/// and below, as they are drawn in display style, or as ordinary scripts.
+#[must_use]
+pub fn build_braille(tree: &[AnyParseNode], code: BrailleCode, display: bool) -> String {
+    let mut writer = Writer {
+        code,
+        display,
+        out: String::new(),
+        scripts: Vec::new(),
+        pending_level: false,
+        in_number: false,
+    };
+    writer.expression(tree);
+    writer.out
+}
+
+/// Script levels, outermost first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Sup,
+    Sub,
+}
+
+struct Writer {
+    code: BrailleCode,
+    display: bool,
+    out: String,
+    scripts: Vec<Script>,
+    /// Whether scripts just ended and the level reached must be indicated
+    /// before anything else (Nemeth only)
+    pending_level: bool,
+    /// Whether the last cell is part of a number
+    in_number: bool,
+}
+
+impl Writer {
+    fn put(&mut self, cells: &[u32]) {
+        if self.pending_level {
+            self.pending_level = false;
+            self.put_level();
+        }
+        self.out.extend(cells.iter().copied().map(cell));
+        self.in_number = false;
+    }
+
+    /// Writes the Nemeth level indicator for the current script level
+    fn put_level(&mut self) {
+        if self.scripts.is_empty() {
+            self.out.push(cell(5));
+        }
+        for script in &self.scripts {
+            self.out.push(cell(match script {
+                Script::Sup => 45,
+                Script::Sub => 56,
+            }));
+        }
+    }
+
+    fn digit(&mut self, c: char) {
+        let starts_number = !self.in_number;
+        match self.code {
+            BrailleCode::Nemeth => {
+                // The numeric indicator is only needed at the start of the
+                // expression or after a space
+                let after_space = self.out.is_empty() || self.out.ends_with(BLANK);
+                if starts_number && after_space && !self.pending_level {
+                    self.put(&[3456]);
+                }
+                match c.to_digit(10) {
+                    Some(d) => self.put(&[NEMETH_DIGITS[d as usize]]),
+                    None => self.put(&[46]),
+                }
+            }
+            BrailleCode::Ueb => {
+                if starts_number {
+                    self.put(&[3456]);
+                }
+                match c.to_digit(10) {
+                    Some(d) => self.put(&[UEB_DIGITS[d as usize]]),
+                    None => self.put(&[256]),
+                }
+            }
+        }
+        self.in_number = true;
+    }
+
+    fn letter(&mut self, c: char) {
+        let index = (c.to_ascii_lowercase() as u8 - b'a') as usize;
+        // In UEB, a-j right after a number would read as digits
+        if self.code == BrailleCode::Ueb && self.in_number && index < 10 {
+            self.put(&[56]);
+        }
+        if c.is_ascii_uppercase() {
+            self.put(&[6]);
+        }
+        self.put(&[LETTERS[index]]);
+    }
+
+    fn symbol(&mut self, text: &str) {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_digit() || (c == '.' && self.in_number) => {
+                return self.digit(c);
+            }
+            (Some(c), None) if c.is_ascii_alphabetic() => return self.letter(c),
+            _ => {}
+        }
+
+        if let Some((dots, capital)) = text.strip_prefix('\\').and_then(greek) {
+            let cells: &[u32] = match (capital, self.code) {
+                (false, _) => &[46],
+                (true, BrailleCode::Nemeth) => &[46, 6],
+                (true, BrailleCode::Ueb) => &[6, 46],
+            };
+            self.put(cells);
+            self.put(&[dots]);
+            return;
+        }
+
+        match self.code {
+            BrailleCode::Nemeth => match nemeth_symbol(text) {
+                Some((cells, true)) => {
+                    // The space before a comparison sign returns to the
+                    // baseline by itself
+                    if self.scripts.is_empty() {
+                        self.pending_level = false;
+                    }
+                    self.put(&[0]);
+                    self.put(cells);
+                    self.put(&[0]);
+                }
+                Some((cells, false)) => self.put(cells),
+                None => self.print(text),
+            },
+            BrailleCode::Ueb => match ueb_symbol(text) {
+                Some(cells) => self.put(cells),
+                None => self.print(text),
+            },
+        }
+    }
+
+    /// Passes a symbol without a braille equivalent through as print
+    fn print(&mut self, text: &str) {
+        self.put(&[]);
+        self.out.push_str(text.strip_prefix('\\').unwrap_or(text));
+    }
+
+    fn expression(&mut self, nodes: &[AnyParseNode]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    /// Whether a node is read as a single item, so that UEB needs no
+    /// grouping indicators around it
+    fn is_item(node: &AnyParseNode) -> bool {
+        match node {
+            AnyParseNode::OrdGroup(group) => match group.body.as_slice() {
+                [single] => Self::is_item(single),
+                body => {
+                    !body.is_empty()
+                        && body.iter().all(|node| {
+                            node.text()
+                                .is_some_and(|t| t.chars().all(|c| c.is_ascii_digit()))
+                        })
+                }
+            },
+            _ => node.text().is_some(),
+        }
+    }
+
+    fn is_number(node: &AnyParseNode) -> bool {
+        match node {
+            AnyParseNode::OrdGroup(group) => {
+                !group.body.is_empty() && group.body.iter().all(Self::is_number)
+            }
+            _ => node
+                .text()
+                .is_some_and(|text| !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())),
+        }
+    }
+
+    fn is_prime(node: &AnyParseNode) -> bool {
+        match node {
+            AnyParseNode::OrdGroup(group) => {
+                !group.body.is_empty() && group.body.iter().all(Self::is_prime)
+            }
+            _ => matches!(node.text(), Some("\\prime" | "'" | "\u{2032}")),
+        }
+    }
+
+    /// Writes `body` as a UEB item, grouping it unless it is a single item
+    fn ueb_item(&mut self, body: &AnyParseNode) {
+        if Self::is_item(body) {
+            self.node(body);
+        } else {
+            self.put(&[126]);
+            self.node(body);
+            self.put(&[345]);
+        }
+    }
+
+    fn script(&mut self, script: Script, body: &AnyParseNode, base: Option<&AnyParseNode>) {
+        match self.code {
+            BrailleCode::Nemeth => {
+                // A numeric subscript on a letter at the first level needs no
+                // indicator: x_1 is written x1
+                let letter_base = base
+                    .and_then(|base| base.to_base_elem().ok())
+                    .and_then(AnyParseNode::text)
+                    .is_some_and(|text| text.chars().all(|c| c.is_ascii_alphabetic()));
+                if script == Script::Sub
+                    && self.scripts.is_empty()
+                    && letter_base
+                    && Self::is_number(body)
+                {
+                    self.in_number = true;
+                    self.node(body);
+                    return;
+                }
+                self.scripts.push(script);
+                self.pending_level = false;
+                self.put_level();
+                self.node(body);
+                self.pending_level = false;
+                self.scripts.pop();
+                self.pending_level = true;
+            }
+            BrailleCode::Ueb => {
+                self.put(&[match script {
+                    Script::Sup => 35,
+                    Script::Sub => 26,
+                }]);
+                self.ueb_item(body);
+            }
+        }
+    }
+
+    /// Writes limits directly below and above an operator
+    fn limits(
+        &mut self,
+        base: &AnyParseNode,
+        under: Option<&AnyParseNode>,
+        over: Option<&AnyParseNode>,
+    ) {
+        match self.code {
+            BrailleCode::Nemeth => {
+                self.put(&[5]);
+                self.node(base);
+                if let Some(under) = under {
+                    self.put(&[146]);
+                    self.node(under);
+                }
+                if let Some(over) = over {
+                    self.put(&[126]);
+                    self.node(over);
+                }
+                self.put(&[12456]);
+            }
+            BrailleCode::Ueb => {
+                self.node(base);
+                if let Some(under) = under {
+                    self.put(&[46, 26]);
+                    self.ueb_item(under);
+                }
+                if let Some(over) = over {
+                    self.put(&[46, 35]);
+                    self.ueb_item(over);
+                }
+            }
+        }
+    }
+
+    /// Nesting depth of fractions inside `node`, used for Nemeth's complex
+    /// fraction indicators
+    fn fraction_depth(node: &AnyParseNode) -> usize {
+        let max =
+            |nodes: &[AnyParseNode]| nodes.iter().map(Self::fraction_depth).max().unwrap_or(0);
+        match node {
+            AnyParseNode::Genfrac(frac) => {
+                1 + Self::fraction_depth(&frac.numer).max(Self::fraction_depth(&frac.denom))
+            }
+            AnyParseNode::OrdGroup(group) => max(&group.body),
+            AnyParseNode::Styling(styling) => max(&styling.body),
+            AnyParseNode::Color(color) => max(&color.body),
+            AnyParseNode::LeftRight(leftright) => max(&leftright.body),
+            AnyParseNode::Font(font) => Self::fraction_depth(&font.body),
+            _ => 0,
+        }
+    }
+
+    fn fraction(&mut self, numer: &AnyParseNode, denom: &AnyParseNode) {
+        match self.code {
+            BrailleCode::Nemeth => {
+                let depth = Self::fraction_depth(numer).max(Self::fraction_depth(denom));
+                let mark =
+                    |dots: u32| -> Vec<u32> { iter::repeat_n(6, depth).chain([dots]).collect() };
+                self.put(&mark(1456));
+                self.node(numer);
+                self.put(&mark(34));
+                self.node(denom);
+                self.put(&mark(3456));
+            }
+            BrailleCode::Ueb if Self::is_number(numer) && Self::is_number(denom) => {
+                // Numeric fractions share the numeric indicator: 3/4 is
+                // written with the digits on either side of the line
+                self.node(numer);
+                self.put(&[34]);
+                self.in_number = true;
+                self.node(denom);
+            }
+            BrailleCode::Ueb => {
+                self.put(&[12356]);
+                self.node(numer);
+                self.put(&[46, 34]);
+                self.node(denom);
+                self.put(&[23456]);
+            }
+        }
+    }
+
+    fn radical(&mut self, body: &AnyParseNode, index: Option<&AnyParseNode>) {
+        match self.code {
+            BrailleCode::Nemeth => {
+                if let Some(index) = index {
+                    self.put(&[126]);
+                    self.node(index);
+                }
+                self.put(&[345]);
+                self.node(body);
+                self.put(&[12456]);
+            }
+            BrailleCode::Ueb => {
+                self.put(&[146]);
+                if let Some(index) = index {
+                    self.put(&[35]);
+                    self.ueb_item(index);
+                }
+                self.node(body);
+                self.put(&[346]);
+            }
+        }
+    }
+
+    /// Writes an expression with a mark above (`over`) or below it
+    fn modified(&mut self, base: &AnyParseNode, label: &str, over: bool) {
+        let modifier: Option<Cells> = match (self.code, label) {
+            (BrailleCode::Nemeth, "\\bar" | "\\overline" | "\\underline")
+            | (BrailleCode::Ueb, "\\bar" | "\\overline") => Some(&[156]),
+            (BrailleCode::Nemeth, "\\tilde" | "\\widetilde") => Some(&[4, 156]),
+            (BrailleCode::Nemeth, "\\vec" | "\\overrightarrow") => Some(&[1246, 25, 25, 135]),
+            (BrailleCode::Nemeth, "\\dot") => Some(&[16]),
+            (BrailleCode::Nemeth, "\\ddot") => Some(&[16, 16]),
+            _ => None,
+        };
+        let Some(modifier) = modifier else {
+            // Marks without a braille equivalent are left out
+            self.node(base);
+            return;
+        };
+        match self.code {
+            BrailleCode::Nemeth => {
+                self.put(&[5]);
+                self.node(base);
+                self.put(if over { &[126] } else { &[146] });
+                self.put(modifier);
+                self.put(&[12456]);
+            }
+            BrailleCode::Ueb => {
+                self.ueb_item(base);
+                self.put(modifier);
+            }
+        }
+    }
+
+    /// Writes a run of text letter by letter
+    fn text(&mut self, nodes: &[AnyParseNode]) {
+        for node in nodes {
+            match node {
+                AnyParseNode::Spacing(_) => self.put(&[0]),
+                AnyParseNode::OrdGroup(group) => self.text(&group.body),
+                AnyParseNode::Styling(styling) => self.text(&styling.body),
+                AnyParseNode::Text(text) => self.text(&text.body),
+                _ => self.node(node),
+            }
+        }
+    }
+
+    /// Nemeth sets function names off from their argument with a space
+    fn function_name_end(&mut self) {
+        if self.code == BrailleCode::Nemeth {
+            self.put(&[0]);
+        }
+    }
+
+    const fn has_limits(&self, base: Option<&AnyParseNode>) -> bool {
+        self.display
+            && match base {
+                Some(AnyParseNode::Op(op)) => op.limits(),
+                Some(AnyParseNode::OperatorName(name)) => name.limits,
+                _ => false,
+            }
+    }
+
+    fn node(&mut self, node: &AnyParseNode) {
+        match node {
+            AnyParseNode::MathOrd(ord) => self.symbol(&ord.text),
+            AnyParseNode::TextOrd(ord) => self.symbol(&ord.text),
+            AnyParseNode::Atom(atom) => self.symbol(&atom.text),
+            AnyParseNode::OpToken(token) => self.symbol(&token.text),
+            AnyParseNode::Op(ParseNodeOp::Symbol {
+                name,
+                symbol: false,
+                ..
+            }) => {
+                // Named operators such as \sin are spelled out
+                for c in name.trim_start_matches('\\').chars() {
+                    self.symbol(c.encode_utf8(&mut [0; 4]));
+                }
+                self.function_name_end();
+            }
+            AnyParseNode::Op(ParseNodeOp::Symbol { name, .. }) => self.symbol(name),
+            AnyParseNode::Op(ParseNodeOp::Body { body, .. }) => self.expression(body),
+            AnyParseNode::Delimsizing(delim) => self.symbol(&delim.delim),
+            AnyParseNode::Middle(middle) => self.symbol(&middle.delim),
+            AnyParseNode::Text(text) => self.text(&text.body),
+            AnyParseNode::OperatorName(name) => {
+                self.text(&name.body);
+                self.function_name_end();
+            }
+
+            AnyParseNode::OrdGroup(group) => self.expression(&group.body),
+            AnyParseNode::Styling(styling) => self.expression(&styling.body),
+            AnyParseNode::Sizing(sizing) => self.expression(&sizing.body),
+            AnyParseNode::Color(color) => self.expression(&color.body),
+            AnyParseNode::Mclass(mclass) => self.expression(&mclass.body),
+            AnyParseNode::Pmb(pmb) => self.expression(&pmb.body),
+            AnyParseNode::Hbox(hbox) => self.expression(&hbox.body),
+            AnyParseNode::Html(html) => self.expression(&html.body),
+            AnyParseNode::Href(href) => self.expression(&href.body),
+            AnyParseNode::HtmlMathMl(both) => self.expression(&both.mathml),
+            AnyParseNode::MathChoice(choice) => self.expression(&choice.display),
+            AnyParseNode::Tag(tag) => self.expression(&tag.body),
+            AnyParseNode::Font(font) => self.node(&font.body),
+            AnyParseNode::Enclose(enclose) => self.node(&enclose.body),
+            AnyParseNode::Lap(lap) => self.node(&lap.body),
+            AnyParseNode::Raisebox(raisebox) => self.node(&raisebox.body),
+            AnyParseNode::Smash(smash) => self.node(&smash.body),
+            AnyParseNode::Vcenter(vcenter) => self.node(&vcenter.body),
+            AnyParseNode::CdLabel(label) => self.node(&label.label),
+            AnyParseNode::CdLabelParent(parent) => self.node(&parent.fragment),
+
+            AnyParseNode::SupSub(supsub) => {
+                let base = supsub.base.as_deref();
+                let (sub, sup) = (supsub.sub.as_deref(), supsub.sup.as_deref());
+                if let Some(base) = base
+                    && self.has_limits(Some(base))
+                {
+                    self.limits(base, sub, sup);
+                    return;
+                }
+                if let Some(base) = base {
+                    self.node(base);
+                }
+                // Primes are written on the base level, before any script
+                let (primes, sup) = match sup {
+                    Some(sup) if Self::is_prime(sup) => (Some(sup), None),
+                    _ => (None, sup),
+                };
+                if let Some(primes) = primes {
+                    self.node(primes);
+                }
+                if let Some(sub) = sub {
+                    self.script(Script::Sub, sub, base);
+                }
+                if let Some(sup) = sup {
+                    self.script(Script::Sup, sup, base);
+                }
+            }
+            AnyParseNode::Genfrac(frac) if frac.has_bar_line => {
+                self.fraction(&frac.numer, &frac.denom);
+            }
+            AnyParseNode::Genfrac(frac) => {
+                // Binomial coefficients have no fraction line; write the two
+                // parts as a parenthesised pair
+                self.symbol("(");
+                self.node(&frac.numer);
+                self.symbol(",");
+                self.node(&frac.denom);
+                self.symbol(")");
+            }
+            AnyParseNode::Sqrt(sqrt) => self.radical(&sqrt.body, sqrt.index.as_ref()),
+            AnyParseNode::LeftRight(leftright) => {
+                if leftright.left != "." {
+                    self.symbol(&leftright.left);
+                }
+                self.expression(&leftright.body);
+                if leftright.right != "." {
+                    self.symbol(&leftright.right);
+                }
+            }
+            AnyParseNode::Accent(accent) => self.modified(&accent.base, &accent.label, true),
+            AnyParseNode::AccentUnder(accent) => self.modified(&accent.base, &accent.label, false),
+            AnyParseNode::Overline(overline) => self.modified(&overline.body, "\\overline", true),
+            AnyParseNode::Underline(underline) => {
+                self.modified(&underline.body, "\\underline", false);
+            }
+            AnyParseNode::HorizBrace(brace) => self.node(&brace.base),
+            AnyParseNode::XArrow(arrow) => self.symbol(&arrow.label),
+            AnyParseNode::Nuclide(nuclide) => {
+                self.node(&nuclide.base);
+                if let Some(mass) = nuclide.mass.as_deref() {
+                    self.script(Script::Sup, mass, Some(&nuclide.base));
+                }
+                if let Some(atomic) = nuclide.atomic.as_deref() {
+                    self.script(Script::Sub, atomic, Some(&nuclide.base));
+                }
+            }
+            AnyParseNode::Diagbox(diagbox) => {
+                self.node(&diagbox.lower);
+                self.node(&diagbox.upper);
+            }
+            AnyParseNode::Sideset(sideset) => {
+                self.node(&sideset.base);
+                if let Some(sub) = sideset.post_sub.as_deref() {
+                    self.script(Script::Sub, sub, Some(&sideset.base));
+                }
+                if let Some(sup) = sideset.post_sup.as_deref() {
+                    self.script(Script::Sup, sup, Some(&sideset.base));
+                }
+            }
+            AnyParseNode::Tensor(tensor) => {
+                self.node(&tensor.base);
+                for index in &tensor.post {
+                    match index {
+                        TensorIndex::Upper(index) => {
+                            self.script(Script::Sup, index, Some(&tensor.base));
+                        }
+                        TensorIndex::Lower(index) => {
+                            self.script(Script::Sub, index, Some(&tensor.base));
+                        }
+                    }
+                }
+            }
+            AnyParseNode::Verb(verb) => self.print(&verb.body),
+            AnyParseNode::Url(url) => self.print(&url.url),
+            AnyParseNode::Includegraphics(graphics) => self.print(&graphics.alt),
+            AnyParseNode::Array(array) => {
+                for (i, row) in array.body.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push('\n');
+                    }
+                    for (j, entry) in row.iter().enumerate() {
+                        if j > 0 {
+                            self.put(&[0, 0]);
+                        }
+                        self.node(entry);
+                    }
+                }
+            }
+
+            // Nodes without printed content
+            AnyParseNode::Spacing(_)
+            | AnyParseNode::AccentToken(_)
+            | AnyParseNode::Phantom(_)
+            | AnyParseNode::Hphantom(_)
+            | AnyParseNode::Vphantom(_)
+            | AnyParseNode::Kern(_)
+            | AnyParseNode::Rule(_)
+            | AnyParseNode::Cr(_)
+            | AnyParseNode::ColorToken(_)
+            | AnyParseNode::Raw(_)
+            | AnyParseNode::Size(_)
+            | AnyParseNode::Internal(_)
+            | AnyParseNode::Infix(_)
+            | AnyParseNode::Environment(_)
+            | AnyParseNode::LeftRightRight(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_from_dot_numbers() {
+        assert_eq!(cell(0), BLANK);
+        assert_eq!(cell(1), '\u{2801}');
+        assert_eq!(cell(1346), '\u{282D}');
+        assert_eq!(cell(123456), '\u{283F}');
+    }
+
+    #[test]
+    fn test_greek_letters() {
+        assert_eq!(greek("alpha"), Some((1, false)));
+        assert_eq!(greek("Sigma"), Some((234, true)));
+        assert_eq!(greek("varepsilon"), Some((15, false)));
+        assert_eq!(greek("aleph"), None);
+    }
+}
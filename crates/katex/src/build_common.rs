@@ -729,6 +729,7 @@ pub fn push_combine_chars(chars: &mut Vec<HtmlDomNode>, node: HtmlDomNode) {
                 prev_sym.height = prev_sym.height.max(next_sym.height);
                 prev_sym.depth = prev_sym.depth.max(next_sym.depth);
                 prev_sym.italic = next_sym.italic;
+                prev_sym.width += next_sym.width;
                 prev_sym.text.push_str(&next_sym.text);
             } else {
                 chars.push(HtmlDomNode::Symbol(next_sym));
@@ -7,10 +7,12 @@ use crate::build_common::{make_span, push_combine_chars};
 use crate::dom_tree::{DomSpan, HtmlDomNode};
 use crate::options::Options;
 use crate::parser::parse_node::AnyParseNode;
-use crate::spacing_data::{SPACINGS, TIGHT_SPACINGS};
+use crate::spacing_data::{MeasurementStatic, SPACINGS, TIGHT_SPACINGS};
+use crate::symbols::Atom;
 use crate::types::ClassList;
 use crate::types::{CssProperty, ParseError, ParseErrorKind};
 use crate::units::make_em;
+use crate::utils::with_stack;
 use crate::{KatexContext, build_common};
 use alloc::borrow::Cow;
 use core::str::FromStr as _;
@@ -25,6 +27,26 @@ const BIN_LEFT_CANCELLER: phf::Set<&str> =
 const BIN_RIGHT_CANCELLER: phf::Set<&str> = phf_set!("rightmost", "mrel", "mclose", "mpunct");
 
 /// DOM enum for atom classes
+///
+/// Every HTML node produced for a math atom carries one of these classes as
+/// its *first* CSS class. The class plays two roles:
+///
+/// - **Spacing.** [`build_expression`] reads the class of adjacent nodes with
+///   [`get_type_of_dom_tree`] and inserts the inter-atom glue from TeXbook
+///   p. 170 (e.g. a thick space around [`DomType::Mrel`], a medium space
+///   around [`DomType::Mbin`]). Nodes whose first class is not an atom class
+///   are invisible to this logic.
+/// - **Binary cancellation.** A [`DomType::Mbin`] that follows the start of a
+///   list or an `mbin`/`mopen`/`mrel`/`mop`/`mpunct`, or precedes an
+///   `mrel`/`mclose`/`mpunct` or the end of a list, is demoted to
+///   [`DomType::Mord`] (TeXbook rules 5 and 6), so `-x` gets no space.
+///
+/// Custom functions pick a class by wrapping their output in a span whose
+/// first class is [`DomType::as_str`]. To mirror how a symbol's
+/// [`Atom`] family maps onto these classes use
+/// [`DomType::from_atom`], and to reproduce amsmath's `\binrel@` (take the
+/// class of a reference argument) use
+/// [`binrel_class`](crate::functions::binrel_class).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum DomType {
@@ -94,6 +116,20 @@ impl GroupType {
 }
 
 impl DomType {
+    /// Class of a symbol from the given atom family, as assigned when the
+    /// symbol is rendered on its own.
+    #[must_use]
+    pub const fn from_atom(atom: Atom) -> Self {
+        match atom {
+            Atom::Bin => Self::Mbin,
+            Atom::Close => Self::Mclose,
+            Atom::Inner => Self::Minner,
+            Atom::Open => Self::Mopen,
+            Atom::Punct => Self::Mpunct,
+            Atom::Rel => Self::Mrel,
+        }
+    }
+
     /// Convert to string representation
     #[must_use]
     pub const fn as_str(&self) -> &'static str {
@@ -717,8 +753,8 @@ where
 /// # Behavior
 /// - Flattens DocumentFragment children into the result list
 /// - Combines consecutive symbol nodes for optimization
-/// - Applies spacing rules if `is_real_group` is true
-/// - Performs binary operator cancellation based on surrounding context
+/// - Applies spacing rules and binary operator cancellation with
+///   [`apply_spacing`] if `is_real_group` is true
 pub fn build_expression(
     ctx: &KatexContext,
     expression: &[AnyParseNode],
@@ -751,19 +787,76 @@ pub fn build_expression(
         return Ok(groups);
     }
 
-    let glue_options = if expression.len() == 1 {
-        if let AnyParseNode::Sizing(sizing) = &expression[0] {
-            options.having_size(sizing.size)
-        } else if let AnyParseNode::Styling(styling_node) = &expression[0] {
-            // Apply styling to options using the appropriate style
-            options.having_style(styling_node.style)
-        } else {
-            options.clone()
-        }
-    } else {
-        options.clone()
+    let glue_options = match expression {
+        [AnyParseNode::Sizing(sizing)] => options.having_size(sizing.size),
+        // Apply styling to options using the appropriate style
+        [AnyParseNode::Styling(styling_node)] => options.having_style(styling_node.style),
+        _ => options.clone(),
     };
 
+    apply_spacing(
+        ctx,
+        &mut groups,
+        options,
+        &glue_options,
+        is_real_group,
+        surrounding,
+    )?;
+
+    Ok(groups)
+}
+
+/// Returns the glue TeX puts between adjacent atoms of the classes `prev`
+/// and `next`, or `None` when they are set without space.
+///
+/// `tight` selects the table for script and scriptscript style, where only
+/// the thin spaces next to operators survive. This is the
+/// lookup [`apply_spacing`] performs for every pair of neighbouring atoms.
+#[must_use]
+pub fn spacing_between(prev: DomType, next: DomType, tight: bool) -> Option<MeasurementStatic> {
+    let table = if tight { &TIGHT_SPACINGS } else { &SPACINGS };
+    table
+        .get(prev.as_str())
+        .and_then(|inner| inner.get(next.as_str()))
+        .cloned()
+}
+
+/// Performs binary cancellation and inserts inter-atom glue into a list of
+/// built nodes.
+///
+/// This is the second half of [`build_expression`], exposed for builders
+/// that assemble a list from prebuilt fragments and need the spacing a
+/// single built expression would get. Partial groups among `groups` (see
+/// [`GroupType::False`]) are walked into, so atoms inside a `\color` get
+/// spaced against their neighbours outside it.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context used to build the glue spans
+/// * `groups` - Built nodes, updated in place; glue spans are inserted after
+///   the atom they follow
+/// * `options` - Rendering options for the dummy boundary spans
+/// * `glue_options` - Rendering options the glue is sized with; usually the
+///   same as `options`
+/// * `is_real_group` - Nothing is done for [`GroupType::False`], which
+///   leaves spacing to the enclosing list. [`GroupType::Root`] additionally
+///   treats every `newline` node as a fresh start of the list
+/// * `surrounding` - Classes of the atoms on either side of the list, or
+///   `None` for the start or end of the list
+///
+/// # Errors
+/// Returns `ParseError` if a glue span cannot be built.
+pub fn apply_spacing(
+    ctx: &KatexContext,
+    groups: &mut Vec<HtmlDomNode>,
+    options: &Options,
+    glue_options: &Options,
+    is_real_group: GroupType,
+    surrounding: (Option<DomType>, Option<DomType>),
+) -> Result<(), ParseError> {
+    if !is_real_group.is_real() {
+        return Ok(());
+    }
+
     // Dummy spans for determining spacings between surrounding atoms.
     // If `expression` has no atoms on the left or right, class "leftmost"
     // or "rightmost", respectively, is used to indicate it.
@@ -797,7 +890,7 @@ pub fn build_expression(
     let mut prev_tracker = PrevTracker::new();
     traverse_non_space_nodes(
         ctx,
-        &mut groups,
+        groups,
         &|_ctx: &KatexContext, node: &mut HtmlDomNode, prev: &mut HtmlDomNode| {
             let Some(prev_type) = prev.classes().first() else {
                 return Ok(None);
@@ -833,27 +926,17 @@ pub fn build_expression(
     let mut prev_tracker = PrevTracker::new();
     traverse_non_space_nodes(
         ctx,
-        &mut groups,
+        groups,
         &move |ctx: &KatexContext, node: &mut HtmlDomNode, prev: &mut HtmlDomNode| {
             let prev_type = get_type_of_dom_tree(prev, None);
             let type_opt = get_type_of_dom_tree(node, None);
-            if let (Some(prev_type), Some(type_val)) = (prev_type, type_opt) {
+            if let (Some(prev_type), Some(type_val)) = (prev_type, type_opt)
                 // 'mtight' indicates that the node is script or scriptscript style.
-                let space = if node.has_class("mtight") {
-                    TIGHT_SPACINGS
-                        .get(prev_type.as_str())
-                        .and_then(|inner| inner.get(type_val.as_str()))
-                } else {
-                    SPACINGS
-                        .get(prev_type.as_str())
-                        .and_then(|inner| inner.get(type_val.as_str()))
-                };
-
-                if let Some(space) = space {
-                    // Insert glue (spacing) after the `prev`.
-                    let glue = ctx.make_glue(space, &glue_options)?;
-                    return Ok(Some(glue.into()));
-                }
+                && let Some(space) = spacing_between(prev_type, type_val, node.has_class("mtight"))
+            {
+                // Insert glue (spacing) after the `prev`.
+                let glue = ctx.make_glue(&space, glue_options)?;
+                return Ok(Some(glue.into()));
             }
             Ok(None)
         },
@@ -863,7 +946,7 @@ pub fn build_expression(
         is_root,
     )?;
 
-    Ok(groups)
+    Ok(())
 }
 
 /// Build a single parse node using the appropriate group builder
@@ -916,7 +999,7 @@ pub fn build_group(
     // Try to find a group builder for this type
     let group_node = if let Some(builder) = ctx.html_group_builders.get(&group_type) {
         // Call the registered group builder
-        builder(group, options, ctx)?
+        with_stack(|| builder(group, options, ctx))?
     } else {
         return Err(ParseError::new(ParseErrorKind::UnknownGroupType {
             group_type,
@@ -943,6 +1026,8 @@ pub fn build_group(
 
 /// Combine an array of HTML DOM nodes into an unbreakable HTML node of class
 /// .base
+///
+/// `children` should have spare capacity for the strut inserted before them.
 fn build_html_unbreakable(children: Vec<HtmlDomNode>, options: &Options) -> HtmlDomNode {
     // Compute height and depth of this chunk.
     let mut body = make_span("base", children, Some(options), None);
@@ -1066,7 +1151,7 @@ pub fn build_html(
             }
             // Don't allow break if \nobreak among the post-operator glue.
             if !nobreak {
-                let mut chunk = Vec::with_capacity(parts.len());
+                let mut chunk = Vec::with_capacity(parts.len() + 1);
                 chunk.append(&mut parts);
                 children.push(build_html_unbreakable(chunk, options));
             }
@@ -1076,7 +1161,7 @@ pub fn build_html(
                 .pop()
                 .ok_or_else(|| ParseError::new(ParseErrorKind::NewlineNodeNotFound))?;
             if !parts.is_empty() {
-                let mut chunk = Vec::with_capacity(parts.len());
+                let mut chunk = Vec::with_capacity(parts.len() + 1);
                 chunk.append(&mut parts);
                 children.push(build_html_unbreakable(chunk, options));
             }
@@ -1086,7 +1171,7 @@ pub fn build_html(
     }
 
     if !parts.is_empty() {
-        let mut chunk = Vec::with_capacity(parts.len());
+        let mut chunk = Vec::with_capacity(parts.len() + 1);
         chunk.append(&mut parts);
         children.push(build_html_unbreakable(chunk, options));
     }
@@ -19,7 +19,10 @@ use crate::parser::parse_node::AnyParseNode;
 use crate::symbols::{Symbols, is_ligature};
 use crate::types::ClassList;
 use crate::types::Mode;
+use crate::types::NumberFormatter;
 use crate::types::ParseErrorKind;
+use crate::unicode::{combined_prime, prime_count};
+use crate::utils::with_stack;
 
 /// Creates a MathML text node with optional symbol replacement
 ///
@@ -273,6 +276,46 @@ fn is_number_punctuation(group: Option<&MathNode>) -> bool {
     false
 }
 
+/// Number of primes spelled by an `<mo>` holding only prime characters
+fn prime_run(node: &MathNode) -> Option<usize> {
+    if node.node_type != MathNodeType::Mo || node.children.len() != 1 {
+        return None;
+    }
+    let text = node.children.first()?.as_text_node()?;
+    text.text
+        .chars()
+        .map(|ch| prime_count(ch.encode_utf8(&mut [0; 4])))
+        .sum::<Option<usize>>()
+        .filter(|&count| count > 0)
+}
+
+/// Passes the text of every `<mn>` in the tree through `formatter`
+///
+/// This runs once on the finished tree rather than inside
+/// [`build_expression`], since nested groups are concatenated again by their
+/// parents and a run must only be formatted when it is complete.
+fn format_numbers(node: &mut MathDomNode, formatter: &NumberFormatter) {
+    match node {
+        MathDomNode::Math(math) if math.node_type == MathNodeType::Mn => {
+            let digits: String = math.children.iter().map(MathDomNode::to_text).collect();
+            math.children = vec![MathDomNode::Text(TextNode {
+                text: formatter.format(&digits),
+            })];
+        }
+        MathDomNode::Math(math) => {
+            for child in &mut math.children {
+                format_numbers(child, formatter);
+            }
+        }
+        MathDomNode::Fragment(fragment) => {
+            for child in &mut fragment.children {
+                format_numbers(child, formatter);
+            }
+        }
+        MathDomNode::Text(_) | MathDomNode::Space(_) => {}
+    }
+}
+
 /// Builds a list of MathML nodes from parse nodes with concatenation logic
 ///
 /// This function converts a sequence of parse nodes into MathML DOM nodes,
@@ -297,6 +340,7 @@ fn is_number_punctuation(group: Option<&MathNode>) -> bool {
 /// - Numbers followed by number punctuation (dots, commas)
 /// - Superscript/subscript operations on numbers or punctuation
 /// - `\not` combining with operators (combining long solidus U+0338)
+/// - Consecutive primes collapsing into one glyph (′′ becomes ″, up to ⁗)
 ///
 /// # Behavior
 /// - Single-element expressions return the element directly
@@ -313,8 +357,8 @@ pub fn build_expression(
         return Ok(Vec::new());
     }
 
-    if expression.len() == 1 {
-        let group = build_group(ctx, &expression[0], options)?;
+    if let [node] = expression {
+        let group = build_group(ctx, node, options)?;
         if let Some(math_node) = group.as_math_node()
             && is_ordgroup.unwrap_or(false)
             && math_node.node_type == MathNodeType::Mo
@@ -390,6 +434,17 @@ pub fn build_expression(
                         repush_last = false;
                     }
                 }
+                // Collapse consecutive primes into the combined glyph
+                else if !options.behavior.uncollapsed_primes
+                    && let (Some(last_count), Some(count)) =
+                        (prime_run(last_math), prime_run(current_math))
+                    && let Some(glyph) = combined_prime(last_count + count)
+                {
+                    last_math.children = vec![MathDomNode::Text(TextNode {
+                        text: glyph.to_string(),
+                    })];
+                    push_current = false;
+                }
                 // Handle \not combining with operators
                 else if last_math.node_type == MathNodeType::Mi
                     && last_math.children.len() == 1
@@ -508,7 +563,7 @@ pub fn build_group(
                 group_type,
             }))
         },
-        |builder| builder(group, options, ctx),
+        |builder| with_stack(|| builder(group, options, ctx)),
     )
 }
 
@@ -556,42 +611,26 @@ pub fn build_mathml(
     is_display_mode: bool,
     for_mathml_only: bool,
 ) -> Result<DomSpan, ParseError> {
-    let expression = build_expression(ctx, tree, options, None)?;
-
-    // Expression is already MathDomNodeEnum
-    let expression_enum = expression;
-
-    // Wrap in mrow if needed using MathDomNodeEnum
-    let wrapper_enum = if expression_enum.len() == 1 {
-        if let Some(math_node) = expression_enum[0].as_math_node() {
-            if matches!(
-                math_node.node_type,
-                MathNodeType::Mrow | MathNodeType::Mtable
-            ) {
-                expression_enum[0].clone()
-            } else {
-                MathDomNode::Math(MathNode {
-                    node_type: MathNodeType::Mrow,
-                    attributes: KeyMap::default(),
-                    children: expression_enum.clone(),
-                    classes: ClassList::Empty,
-                })
-            }
-        } else {
-            MathDomNode::Math(MathNode {
-                node_type: MathNodeType::Mrow,
-                attributes: KeyMap::default(),
-                children: expression_enum,
-                classes: ClassList::Empty,
-            })
+    let mut expression = build_expression(ctx, tree, options, None)?;
+    if let Some(formatter) = &options.number_formatter {
+        for node in &mut expression {
+            format_numbers(node, formatter);
         }
-    } else {
-        MathDomNode::Math(MathNode {
+    }
+
+    // Wrap in mrow if needed, moving rather than cloning the built nodes
+    let wrapper_enum = match expression.as_slice() {
+        [MathDomNode::Math(node)]
+            if matches!(node.node_type, MathNodeType::Mrow | MathNodeType::Mtable) =>
+        {
+            expression.swap_remove(0)
+        }
+        _ => MathDomNode::Math(MathNode {
             node_type: MathNodeType::Mrow,
             attributes: KeyMap::default(),
-            children: expression_enum,
+            children: expression,
             classes: ClassList::Empty,
-        })
+        }),
     };
 
     // Create annotation using MathDomNodeEnum
@@ -7,15 +7,18 @@ use crate::build_common::make_span;
 use crate::build_html::build_html;
 use crate::build_mathml::build_mathml;
 use crate::context::KatexContext;
-use crate::dom_tree::DomSpan;
+use crate::dom_tree::{DomSpan, HtmlDomNode};
+use crate::measure::{Alignment, alignment_of, estimate_width};
 use crate::options::{FontShape, FontWeight, Options};
 use crate::parser::parse_node::AnyParseNode;
 use crate::style;
-use crate::types::{OutputFormat, ParseError, Settings};
+use crate::types::{CssProperty, OutputFormat, OverflowMode, ParseError, Settings};
+use crate::units::make_em;
 use alloc::borrow::Cow;
 
 /// Creates Options from Settings for building
 fn options_from_settings(settings: &Settings) -> Options {
+    settings.behavior.warn_deprecated();
     let style = if settings.display_mode {
         style::DISPLAY
     } else {
@@ -35,12 +38,91 @@ fn options_from_settings(settings: &Settings) -> Options {
         size_multiplier: settings.size_multiplier,
         max_size: settings.max_size,
         min_rule_thickness: settings.min_rule_thickness,
+        min_script_scale: settings.min_script_scale,
+        number_formatter: settings.number_formatter.clone(),
+        text_wrap: settings.text_wrap,
+        behavior: settings.behavior,
+        cell_box: None,
     }
 }
 
-/// Wraps the node with display-related classes if in display mode
+/// Fits the `katex-html` node into `settings.max_width_em`
+///
+/// Nothing changes unless the estimated width exceeds the limit.
+fn fit_to_width(html_node: &mut HtmlDomNode, settings: &Settings) {
+    let Some(max_width) = settings.max_width_em else {
+        return;
+    };
+    let width = estimate_width(html_node);
+    if width <= max_width {
+        return;
+    }
+    let HtmlDomNode::DomSpan(span) = html_node else {
+        return;
+    };
+
+    match settings.overflow {
+        OverflowMode::Scale => {
+            let scale = max_width / width;
+            span.style
+                .insert(CssProperty::Transform, format!("scale({scale:.4})"));
+            // Scale towards the bottom left so the baseline stays close to
+            // the surrounding text, and give back the horizontal space that
+            // the transform leaves behind.
+            span.style
+                .insert(CssProperty::TransformOrigin, "left bottom");
+            span.style
+                .insert(CssProperty::MarginRight, make_em(max_width - width));
+        }
+        OverflowMode::Scroll => {
+            span.style.insert(CssProperty::MaxWidth, make_em(max_width));
+            span.style.insert(CssProperty::OverflowX, "auto");
+            span.style.insert(CssProperty::OverflowY, "hidden");
+        }
+    }
+}
+
+/// Pads the `katex-html` node so that its first relation sits at
+/// `settings.align_to.before` and its overall width matches the alignment
+fn align(html_node: &mut HtmlDomNode, settings: &Settings) {
+    let Some(shared) = settings.align_to else {
+        return;
+    };
+    let own = alignment_of(html_node);
+    let HtmlDomNode::DomSpan(span) = html_node else {
+        return;
+    };
+
+    // Padding rather than margin, so that a scale applied by `fit_to_width`
+    // shrinks it together with the formula.
+    let left = shared.before - own.before;
+    if left > 0.0 {
+        span.style.insert(CssProperty::PaddingLeft, make_em(left));
+    }
+    let right = shared.after - own.after;
+    if right > 0.0 {
+        span.style.insert(CssProperty::PaddingRight, make_em(right));
+    }
+}
+
+/// Measures where the first relation of a parse tree falls in its HTML output
+///
+/// Any `align_to` in `settings` is ignored, so the result describes the
+/// formula on its own.
+pub fn measure_tree_alignment(
+    ctx: &KatexContext,
+    tree: &[AnyParseNode],
+    settings: &Settings,
+) -> Result<Alignment, ParseError> {
+    let options = options_from_settings(settings);
+    let html_node = build_html(ctx, tree, &options)?;
+    Ok(alignment_of(&html_node))
+}
+
+/// Wraps the node with display-related classes if in display mode, and
+/// marks the root with the charset its markup is written in
 fn display_wrap(node: DomSpan, settings: &Settings) -> DomSpan {
-    if settings.display_mode {
+    let mut root = if settings.display_mode {
         let mut classes = vec![Cow::Borrowed("katex-display")];
         if settings.leqno {
             classes.push(Cow::Borrowed("leqno"));
@@ -51,7 +133,9 @@ fn display_wrap(node: DomSpan, settings: &Settings) -> DomSpan {
         make_span(classes, vec![node.into()], None, None)
     } else {
         node
-    }
+    };
+    root.charset = settings.charset;
+    root
 }
 
 /// Builds the final DOM tree from a parse tree
@@ -83,7 +167,9 @@ pub fn build_tree(
         }
         OutputFormat::Html => {
             // HTML only
-            let html_node = build_html(ctx, tree, &options)?;
+            let mut html_node = build_html(ctx, tree, &options)?;
+            align(&mut html_node, settings);
+            fit_to_width(&mut html_node, settings);
             make_span("katex", vec![html_node], None, None)
         }
         OutputFormat::HtmlAndMathml => {
@@ -96,7 +182,9 @@ pub fn build_tree(
                 settings.display_mode,
                 false,
             )?;
-            let html_node = build_html(ctx, tree, &options)?;
+            let mut html_node = build_html(ctx, tree, &options)?;
+            align(&mut html_node, settings);
+            fit_to_width(&mut html_node, settings);
             make_span("katex", vec![mathml_node.into(), html_node], None, None)
         }
     };
@@ -125,7 +213,9 @@ pub fn build_html_tree(
     settings: &Settings,
 ) -> Result<DomSpan, ParseError> {
     let options = options_from_settings(settings);
-    let html_node = build_html(ctx, tree, &options)?;
+    let mut html_node = build_html(ctx, tree, &options)?;
+    align(&mut html_node, settings);
+    fit_to_width(&mut html_node, settings);
     let katex_node = make_span("katex", vec![html_node], None, None);
     Ok(display_wrap(katex_node, settings))
 }
@@ -0,0 +1,906 @@
+//! Best-effort Content MathML from parse trees
+//!
+//! Presentation MathML describes how a formula looks; Content MathML
+//! describes what it means, as applications of operators to arguments. TeX
+//! input carries no such structure, so this module infers it the way a
+//! reader would: operator precedence for `+`, `\times` and relations,
+//! juxtaposition as multiplication, `\sin x` as a function application, and
+//! bound variables and limits for `\sum`, `\int` and `\lim`.
+//!
+//! The inference is deliberately conservative. Anything it does not
+//! recognise becomes a `<semantics>` element carrying the TeX source of that
+//! part as an annotation, so consumers can tell which parts were understood.
+//!
+//! [`build_content`] returns the inferred [`Content`] tree, whose
+//! [`Display`](fmt::Display) implementation writes the markup.
+
+use core::fmt::{self, Write as _};
+use core::{mem, slice};
+
+use crate::build_html::DomType;
+use crate::context::KatexContext;
+use crate::convert::primes;
+use crate::escape::{CharsetWriter, escape_into};
+use crate::parser::parse_node::{AnyParseNode, ParseNodeOp};
+use crate::symbols::Atom;
+use crate::types::{Charset, ErrorLocationProvider as _, Mode};
+
+/// Qualifier elements that refine an application, such as the bound
+/// variable of a sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Qualifier {
+    /// `<bvar>`, the bound variable
+    Bvar,
+    /// `<lowlimit>`, the lower limit
+    Lowlimit,
+    /// `<uplimit>`, the upper limit
+    Uplimit,
+    /// `<condition>`, a condition on the bound variable
+    Condition,
+    /// `<degree>`, the degree of a root
+    Degree,
+    /// `<logbase>`, the base of a logarithm
+    Logbase,
+}
+
+impl Qualifier {
+    /// Element name of the qualifier
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Bvar => "bvar",
+            Self::Lowlimit => "lowlimit",
+            Self::Uplimit => "uplimit",
+            Self::Condition => "condition",
+            Self::Degree => "degree",
+            Self::Logbase => "logbase",
+        }
+    }
+}
+
+/// A Content MathML expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    /// An identifier, `<ci>`
+    Ci(String),
+    /// A number, `<cn>`
+    Cn(String),
+    /// A predefined element such as `<plus/>`, `<sin/>` or `<pi/>`
+    Element(&'static str),
+    /// A symbol without a predefined element, `<csymbol>`
+    Csymbol(String),
+    /// An operator applied to arguments, `<apply>`
+    Apply {
+        /// The operator
+        op: Box<Self>,
+        /// Qualifiers, in order
+        qualifiers: Vec<(Qualifier, Self)>,
+        /// The arguments
+        args: Vec<Self>,
+    },
+    /// Structure that was not understood, kept as its TeX source
+    Unknown(String),
+}
+
+impl Content {
+    /// Applies a predefined operator element to `args`
+    fn apply(op: &'static str, args: Vec<Self>) -> Self {
+        Self::Apply {
+            op: Box::new(Self::Element(op)),
+            qualifiers: Vec::new(),
+            args,
+        }
+    }
+
+    /// Applies a `<csymbol>` operator to `args`
+    fn csymbol(name: &str, args: Vec<Self>) -> Self {
+        Self::Apply {
+            op: Box::new(Self::Csymbol(name.to_owned())),
+            qualifiers: Vec::new(),
+            args,
+        }
+    }
+}
+
+impl fmt::Display for Content {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ci(name) => {
+                f.write_str("<ci>")?;
+                escape_into(f, name)?;
+                f.write_str("</ci>")
+            }
+            Self::Cn(number) => {
+                f.write_str("<cn>")?;
+                escape_into(f, number)?;
+                f.write_str("</cn>")
+            }
+            Self::Element(name) => write!(f, "<{name}/>"),
+            Self::Csymbol(name) => {
+                f.write_str("<csymbol>")?;
+                escape_into(f, name)?;
+                f.write_str("</csymbol>")
+            }
+            Self::Apply {
+                op,
+                qualifiers,
+                args,
+            } => {
+                f.write_str("<apply>")?;
+                op.fmt(f)?;
+                for (qualifier, content) in qualifiers {
+                    let name = qualifier.as_str();
+                    write!(f, "<{name}>{content}</{name}>")?;
+                }
+                for arg in args {
+                    arg.fmt(f)?;
+                }
+                f.write_str("</apply>")
+            }
+            Self::Unknown(tex) if tex.is_empty() => f.write_str("<csymbol>unknown</csymbol>"),
+            Self::Unknown(tex) => {
+                f.write_str(
+                    "<semantics><csymbol>unknown</csymbol>\
+                     <annotation encoding=\"application/x-tex\">",
+                )?;
+                escape_into(f, tex)?;
+                f.write_str("</annotation></semantics>")
+            }
+        }
+    }
+}
+
+/// Infers the Content MathML for a parse tree.
+///
+/// Top-level comma-separated expressions become a `list` application.
+#[must_use]
+pub fn build_content(ctx: &KatexContext, tree: &[AnyParseNode]) -> Content {
+    let items = Items::new(ctx).collect(tree);
+    let mut parser = Parser {
+        ctx,
+        items: &items,
+        pos: 0,
+    };
+    let mut parts = Vec::new();
+    while parser.pos < items.len() {
+        if matches!(parser.peek(), Some(Item::Op(",", _))) {
+            parser.pos += 1;
+            continue;
+        }
+        parts.push(parser.relation());
+    }
+    match parts.len() {
+        0 => Content::Unknown(String::new()),
+        1 => parts.remove(0),
+        _ => Content::csymbol("list", parts),
+    }
+}
+
+/// Writes a Content MathML `<math>` element for a parse tree, encoded for
+/// `charset`.
+pub fn build_content_mathml(
+    ctx: &KatexContext,
+    tree: &[AnyParseNode],
+    charset: Charset,
+) -> Result<String, fmt::Error> {
+    let mut markup = String::new();
+    write!(
+        CharsetWriter::new(&mut markup, charset),
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+        build_content(ctx, tree)
+    )?;
+    Ok(markup)
+}
+
+/// The TeX source of a node, for [`Content::Unknown`]
+///
+/// Nodes built by functions such as `\overbrace` only record the location
+/// of the command itself, so the bracketed arguments that directly follow it
+/// are included as well.
+pub(crate) fn source(node: &AnyParseNode) -> String {
+    let Some(loc) = node.loc() else {
+        return String::new();
+    };
+    let input = &*loc.input;
+    let mut end = loc.end;
+    loop {
+        let rest = input.get(end..).unwrap_or_default();
+        let trimmed = rest.trim_start();
+        let close = match trimmed.chars().next() {
+            Some('{') => '}',
+            Some('[') => ']',
+            _ => break,
+        };
+        let open = if close == '}' { '{' } else { '[' };
+        let mut depth: usize = 0;
+        let Some(length) = trimmed.char_indices().find_map(|(i, c)| {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            None
+        }) else {
+            break;
+        };
+        end += rest.len() - trimmed.len() + length;
+    }
+    input.get(loc.start..end).unwrap_or_default().to_owned()
+}
+
+/// A parse node classified for the expression parser
+#[derive(Debug)]
+enum Item<'a> {
+    /// An operand
+    Term(Content),
+    /// A binary operator, relation or punctuation
+    Op(&'a str, Atom),
+    /// An opening delimiter
+    Open,
+    /// A closing delimiter
+    Close,
+    /// A postfix operator such as `!`
+    Postfix(&'a str),
+    /// Scripts on a closing delimiter, which apply to the whole group as in
+    /// `(x+1)^2`
+    Scripts {
+        sub: Option<&'a AnyParseNode>,
+        sup: Option<&'a AnyParseNode>,
+    },
+    /// A function applied to the following factor, such as `\sin`
+    Func {
+        name: String,
+        sub: Option<&'a AnyParseNode>,
+        sup: Option<&'a AnyParseNode>,
+    },
+    /// An operator that binds a variable, such as `\sum` or `\lim`
+    BigOp {
+        name: &'a str,
+        sub: Option<&'a AnyParseNode>,
+        sup: Option<&'a AnyParseNode>,
+    },
+}
+
+/// Flattens parse nodes into [`Item`]s
+struct Items<'a> {
+    ctx: &'a KatexContext,
+    /// Whether the last item is a number that following digits extend
+    number_run: bool,
+}
+
+impl<'a> Items<'a> {
+    const fn new(ctx: &'a KatexContext) -> Self {
+        Self {
+            ctx,
+            number_run: false,
+        }
+    }
+
+    fn collect(mut self, nodes: &[AnyParseNode]) -> Vec<Item<'_>> {
+        let mut items = Vec::new();
+        self.extend(&mut items, nodes);
+        items
+    }
+
+    fn extend<'n>(&mut self, items: &mut Vec<Item<'n>>, nodes: &'n [AnyParseNode]) {
+        for node in nodes {
+            self.push(items, node);
+        }
+    }
+
+    /// Identifier text for a symbol, using its Unicode form for commands
+    /// such as `\alpha`
+    fn identifier(&self, text: &str) -> String {
+        self.ctx
+            .symbols
+            .get(Mode::Math, text)
+            .and_then(|info| info.replace)
+            .map_or_else(|| text.trim_start_matches('\\').to_owned(), String::from)
+    }
+
+    fn ord<'n>(&mut self, items: &mut Vec<Item<'n>>, text: &'n str) {
+        let is_digit = is_number(text);
+        if is_digit
+            && self.number_run
+            && let Some(Item::Term(Content::Cn(number))) = items.last_mut()
+        {
+            number.push_str(text);
+            return;
+        }
+        let item = match text {
+            _ if is_digit => Item::Term(Content::Cn(text.to_owned())),
+            "/" => Item::Op(text, Atom::Bin),
+            "\\pi" => Item::Term(Content::Element("pi")),
+            "\\infty" => Item::Term(Content::Element("infinity")),
+            "\\emptyset" | "\\varnothing" => Item::Term(Content::Element("emptyset")),
+            _ => Item::Term(Content::Ci(self.identifier(text))),
+        };
+        items.push(item);
+        self.number_run = is_digit;
+    }
+
+    fn push<'n>(&mut self, items: &mut Vec<Item<'n>>, node: &'n AnyParseNode) {
+        match node {
+            AnyParseNode::MathOrd(ord) => return self.ord(items, &ord.text),
+            AnyParseNode::TextOrd(ord) => return self.ord(items, &ord.text),
+            AnyParseNode::Atom(atom) => items.push(match atom.family {
+                Atom::Bin | Atom::Rel | Atom::Punct => Item::Op(&atom.text, atom.family),
+                Atom::Open => Item::Open,
+                Atom::Close if &*atom.text == "!" => Item::Postfix(&atom.text),
+                Atom::Close => Item::Close,
+                Atom::Inner => Item::Term(Content::Ci(self.identifier(&atom.text))),
+            }),
+            AnyParseNode::Op(ParseNodeOp::Symbol {
+                name,
+                symbol: false,
+                ..
+            }) => items.push(Item::Func {
+                name: name.trim_start_matches('\\').to_owned(),
+                sub: None,
+                sup: None,
+            }),
+            AnyParseNode::Op(ParseNodeOp::Symbol { name, .. }) => items.push(Item::BigOp {
+                name,
+                sub: None,
+                sup: None,
+            }),
+            AnyParseNode::OperatorName(operator) => items.push(Item::Func {
+                name: operator
+                    .body
+                    .iter()
+                    .filter_map(AnyParseNode::text)
+                    .collect(),
+                sub: None,
+                sup: None,
+            }),
+            AnyParseNode::SupSub(supsub) => {
+                let (sub, sup) = (supsub.sub.as_deref(), supsub.sup.as_deref());
+                match supsub.base.as_deref() {
+                    Some(AnyParseNode::Op(ParseNodeOp::Symbol {
+                        name,
+                        symbol: false,
+                        ..
+                    })) if !matches!(name.as_str(), "\\lim" | "\\max" | "\\min") => {
+                        items.push(Item::Func {
+                            name: name.trim_start_matches('\\').to_owned(),
+                            sub,
+                            sup,
+                        });
+                    }
+                    Some(AnyParseNode::Op(ParseNodeOp::Symbol { name, .. })) => {
+                        items.push(Item::BigOp { name, sub, sup });
+                    }
+                    Some(base @ AnyParseNode::Atom(atom)) if atom.family == Atom::Close => {
+                        self.push(items, base);
+                        items.push(Item::Scripts { sub, sup });
+                    }
+                    base => {
+                        let base = self
+                            .number_base(items, base)
+                            .or_else(|| base.map(|base| self.content(base)));
+                        items.push(Item::Term(self.scripted(base, sub, sup, node)));
+                    }
+                }
+            }
+            AnyParseNode::Genfrac(frac) => {
+                let parts = vec![self.content(&frac.numer), self.content(&frac.denom)];
+                items.push(Item::Term(if frac.has_bar_line {
+                    Content::apply("divide", parts)
+                } else {
+                    Content::csymbol("binomial", parts)
+                }));
+            }
+            AnyParseNode::Sqrt(sqrt) => {
+                let qualifiers = sqrt
+                    .index
+                    .iter()
+                    .map(|index| (Qualifier::Degree, self.content(index)))
+                    .collect();
+                items.push(Item::Term(Content::Apply {
+                    op: Box::new(Content::Element("root")),
+                    qualifiers,
+                    args: vec![self.content(&sqrt.body)],
+                }));
+            }
+            AnyParseNode::LeftRight(leftright) => {
+                let inner = self.contents(&leftright.body);
+                let bars = ["|", "\\vert", "\\lvert", "\\rvert"];
+                items.push(Item::Term(
+                    if bars.contains(&leftright.left.as_str())
+                        && bars.contains(&leftright.right.as_str())
+                    {
+                        Content::apply("abs", vec![inner])
+                    } else {
+                        inner
+                    },
+                ));
+            }
+            AnyParseNode::Array(array) if array.is_cd != Some(true) => {
+                let rows = array
+                    .body
+                    .iter()
+                    .map(|row| {
+                        let cells = row.iter().map(|cell| self.content(cell)).collect();
+                        Content::csymbol("matrixrow", cells)
+                    })
+                    .collect();
+                items.push(Item::Term(Content::csymbol("matrix", rows)));
+            }
+            AnyParseNode::OrdGroup(group) => items.push(Item::Term(self.contents(&group.body))),
+            AnyParseNode::Font(font) => items.push(Item::Term(self.content(&font.body))),
+            AnyParseNode::Styling(styling) => self.extend(items, &styling.body),
+            AnyParseNode::Sizing(sizing) => self.extend(items, &sizing.body),
+            AnyParseNode::Color(color) => self.extend(items, &color.body),
+            AnyParseNode::Mclass(mclass) => {
+                // A single symbol made a relation or operator, as in the
+                // MathML form of `\neq`
+                let family = match mclass.mclass {
+                    DomType::Mrel => Some(Atom::Rel),
+                    DomType::Mbin => Some(Atom::Bin),
+                    _ => None,
+                };
+                let symbol = match mclass.body.as_slice() {
+                    [symbol] => symbol.text(),
+                    _ => None,
+                };
+                match family.zip(symbol) {
+                    Some((family, text)) => items.push(Item::Op(text, family)),
+                    None => self.extend(items, &mclass.body),
+                }
+            }
+            AnyParseNode::HtmlMathMl(both) => self.extend(items, &both.mathml),
+            AnyParseNode::Spacing(_)
+            | AnyParseNode::Kern(_)
+            | AnyParseNode::Phantom(_)
+            | AnyParseNode::Hphantom(_)
+            | AnyParseNode::Vphantom(_) => {}
+            _ => items.push(Item::Term(Content::Unknown(source(node)))),
+        }
+        self.number_run = false;
+    }
+
+    /// Content of a single node, parsed on its own
+    fn content(&self, node: &AnyParseNode) -> Content {
+        build_content(self.ctx, slice::from_ref(node))
+    }
+
+    /// Content of a node list, parsed on its own
+    fn contents(&self, nodes: &[AnyParseNode]) -> Content {
+        build_content(self.ctx, nodes)
+    }
+
+    /// The number a scripted digit ends, as the `10` of `10^{-3}`, taking
+    /// the digits before it off `items`
+    fn number_base(
+        &self,
+        items: &mut Vec<Item<'_>>,
+        base: Option<&AnyParseNode>,
+    ) -> Option<Content> {
+        let digits = match base? {
+            AnyParseNode::MathOrd(ord) => &ord.text,
+            AnyParseNode::TextOrd(ord) => &ord.text,
+            _ => return None,
+        };
+        if !self.number_run
+            || !is_number(digits)
+            || !matches!(items.last(), Some(Item::Term(Content::Cn(_))))
+        {
+            return None;
+        }
+        let Some(Item::Term(Content::Cn(mut number))) = items.pop() else {
+            return None;
+        };
+        number.push_str(digits);
+        Some(Content::Cn(number))
+    }
+
+    /// A base with scripts that are not operator limits
+    fn scripted(
+        &self,
+        base: Option<Content>,
+        sub: Option<&AnyParseNode>,
+        sup: Option<&AnyParseNode>,
+        node: &AnyParseNode,
+    ) -> Content {
+        let Some(mut content) = base else {
+            return Content::Unknown(source(node));
+        };
+        if let Some(sub) = sub {
+            content = match (content, self.content(sub)) {
+                // Indexed names such as x_1 or a_n are identifiers
+                (Content::Ci(name), Content::Ci(index) | Content::Cn(index)) => {
+                    Content::Ci(format!("{name}_{index}"))
+                }
+                (content, index) => Content::csymbol("subscript", vec![content, index]),
+            };
+        }
+        if let Some(sup) = sup {
+            let primes = primes(sup);
+            content = match content {
+                Content::Ci(name) if primes > 0 => {
+                    Content::Ci(format!("{name}{}", "\u{2032}".repeat(primes)))
+                }
+                content => Content::apply("power", vec![content, self.content(sup)]),
+            };
+        }
+        content
+    }
+}
+
+/// Precedence parser over [`Item`]s
+struct Parser<'a, 'n> {
+    ctx: &'a KatexContext,
+    items: &'a [Item<'n>],
+    pos: usize,
+}
+
+impl<'a, 'n> Parser<'a, 'n> {
+    fn peek(&self) -> Option<&'a Item<'n>> {
+        self.items.get(self.pos)
+    }
+
+    /// Relations, the loosest level; chains such as `a < b < c` become one
+    /// n-ary application
+    fn relation(&mut self) -> Content {
+        let mut lhs = self.additive();
+        let mut chain: Option<(&'static str, Vec<Content>)> = None;
+        while let Some(Item::Op(text, Atom::Rel)) = self.peek() {
+            let op = relation_element(text);
+            self.pos += 1;
+            let rhs = self.additive();
+            chain = match chain {
+                Some((current, mut args)) if current == op => {
+                    args.push(rhs);
+                    Some((current, args))
+                }
+                Some((current, args)) => {
+                    lhs = Content::apply(current, args);
+                    Some((op, vec![lhs.clone(), rhs]))
+                }
+                None => Some((op, vec![lhs.clone(), rhs])),
+            };
+        }
+        match chain {
+            Some((op, args)) => Content::apply(op, args),
+            None => lhs,
+        }
+    }
+
+    /// Sums and differences, with a leading sign
+    fn additive(&mut self) -> Content {
+        let mut lhs = match self.peek() {
+            Some(Item::Op("-" | "\u{2212}", Atom::Bin)) => {
+                self.pos += 1;
+                Content::apply("minus", vec![self.multiplicative()])
+            }
+            Some(Item::Op(op @ ("\\pm" | "\\mp"), Atom::Bin)) => {
+                let name = if *op == "\\pm" {
+                    "plusminus"
+                } else {
+                    "minusplus"
+                };
+                self.pos += 1;
+                Content::csymbol(name, vec![self.multiplicative()])
+            }
+            Some(Item::Op("+", Atom::Bin)) => {
+                self.pos += 1;
+                self.multiplicative()
+            }
+            _ => self.multiplicative(),
+        };
+        while let Some(Item::Op(text, Atom::Bin)) = self.peek() {
+            let text = *text;
+            self.pos += 1;
+            let rhs = self.multiplicative();
+            lhs = match (text, lhs) {
+                (
+                    "+",
+                    Content::Apply {
+                        op,
+                        qualifiers,
+                        mut args,
+                    },
+                ) if *op == Content::Element("plus") => {
+                    args.push(rhs);
+                    Content::Apply {
+                        op,
+                        qualifiers,
+                        args,
+                    }
+                }
+                ("+", lhs) => Content::apply("plus", vec![lhs, rhs]),
+                ("-" | "\u{2212}", lhs) => Content::apply("minus", vec![lhs, rhs]),
+                ("\\pm", lhs) => Content::csymbol("plusminus", vec![lhs, rhs]),
+                ("\\mp", lhs) => Content::csymbol("minusplus", vec![lhs, rhs]),
+                // Other binary operators, such as \oplus, keep their name
+                (text, lhs) => Content::csymbol(text.trim_start_matches('\\'), vec![lhs, rhs]),
+            };
+        }
+        lhs
+    }
+
+    /// Products, written or implied by juxtaposition, and quotients
+    fn multiplicative(&mut self) -> Content {
+        let mut factors = vec![self.factor()];
+        loop {
+            match self.peek() {
+                Some(Item::Op("\\times" | "\\cdot" | "*" | "\\ast", Atom::Bin)) => {
+                    self.pos += 1;
+                    factors.push(self.factor());
+                }
+                Some(Item::Op("/" | "\\div", Atom::Bin)) => {
+                    self.pos += 1;
+                    let numerator = Self::product(mem::take(&mut factors));
+                    factors.push(Content::apply("divide", vec![numerator, self.factor()]));
+                }
+                Some(Item::Term(_) | Item::Open | Item::Func { .. } | Item::BigOp { .. }) => {
+                    factors.push(self.factor());
+                }
+                _ => break,
+            }
+        }
+        Self::product(factors)
+    }
+
+    fn product(mut factors: Vec<Content>) -> Content {
+        if factors.len() == 1 {
+            factors.remove(0)
+        } else {
+            Content::apply("times", factors)
+        }
+    }
+
+    /// A single operand with any postfix operators
+    fn factor(&mut self) -> Content {
+        let Some(item) = self.items.get(self.pos) else {
+            return Content::Unknown(String::new());
+        };
+        self.pos += 1;
+        let mut content = match item {
+            // f(x), g(x) and h(x) are applications rather than products
+            Item::Term(Content::Ci(name))
+                if matches!(name.as_str(), "f" | "g" | "h")
+                    && matches!(self.peek(), Some(Item::Open)) =>
+            {
+                Content::Apply {
+                    op: Box::new(Content::Ci(name.clone())),
+                    qualifiers: Vec::new(),
+                    args: vec![self.factor()],
+                }
+            }
+            Item::Term(content) => content.clone(),
+            Item::Open => {
+                let inner = self.relation();
+                if matches!(self.peek(), Some(Item::Close)) {
+                    self.pos += 1;
+                }
+                inner
+            }
+            Item::Func { name, sub, sup } => {
+                let mut qualifiers = Vec::new();
+                if let Some(sub) = sub {
+                    qualifiers.push((
+                        Qualifier::Logbase,
+                        build_content(self.ctx, slice::from_ref(*sub)),
+                    ));
+                }
+                let applied = Content::Apply {
+                    op: Box::new(function_element(name)),
+                    qualifiers,
+                    args: vec![self.factor()],
+                };
+                match sup {
+                    Some(sup) => Content::apply(
+                        "power",
+                        vec![applied, build_content(self.ctx, slice::from_ref(*sup))],
+                    ),
+                    None => applied,
+                }
+            }
+            Item::BigOp { name, sub, sup } => self.big_op(name, *sub, *sup),
+            Item::Op(text, _) | Item::Postfix(text) => Content::Unknown((*text).to_owned()),
+            Item::Close | Item::Scripts { .. } => Content::Unknown(String::new()),
+        };
+        loop {
+            match self.peek() {
+                Some(Item::Postfix("!")) => {
+                    content = Content::apply("factorial", vec![content]);
+                }
+                Some(Item::Scripts { sub, sup }) => {
+                    if let Some(sub) = sub {
+                        let index = build_content(self.ctx, slice::from_ref(*sub));
+                        content = Content::csymbol("subscript", vec![content, index]);
+                    }
+                    if let Some(sup) = sup {
+                        let exponent = build_content(self.ctx, slice::from_ref(*sup));
+                        content = Content::apply("power", vec![content, exponent]);
+                    }
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        content
+    }
+
+    /// Sums, products, integrals and limits with their bound variables
+    fn big_op(
+        &mut self,
+        name: &str,
+        sub: Option<&AnyParseNode>,
+        sup: Option<&AnyParseNode>,
+    ) -> Content {
+        let sub = sub.map(|sub| build_content(self.ctx, slice::from_ref(sub)));
+        let sup = sup.map(|sup| build_content(self.ctx, slice::from_ref(sup)));
+        let op = match name {
+            "\\sum" => Content::Element("sum"),
+            "\\prod" => Content::Element("product"),
+            "\\int" => Content::Element("int"),
+            "\\lim" => Content::Element("limit"),
+            "\\max" => Content::Element("max"),
+            "\\min" => Content::Element("min"),
+            _ => Content::Csymbol(name.trim_start_matches('\\').to_owned()),
+        };
+
+        let mut qualifiers = Vec::new();
+        let body = if name == "\\int" {
+            // The variable of integration comes from the trailing dx
+            let (body, variable) = take_differential(self.additive());
+            if let Some(variable) = variable {
+                qualifiers.push((Qualifier::Bvar, variable));
+            }
+            if let Some(sub) = sub {
+                qualifiers.push((Qualifier::Lowlimit, sub));
+            }
+            body
+        } else {
+            match sub {
+                // i = 1 and x \to 0 bind the variable and give its limit
+                Some(Content::Apply {
+                    op: relation,
+                    qualifiers: _,
+                    mut args,
+                }) if matches!(*relation, Content::Element("eq" | "tendsto"))
+                    && args.len() == 2
+                    && matches!(args[0], Content::Ci(_)) =>
+                {
+                    let limit = args.pop();
+                    qualifiers.push((Qualifier::Bvar, args.remove(0)));
+                    if let Some(limit) = limit {
+                        qualifiers.push((Qualifier::Lowlimit, limit));
+                    }
+                }
+                Some(condition) => qualifiers.push((Qualifier::Condition, condition)),
+                None => {}
+            }
+            self.multiplicative()
+        };
+        if let Some(sup) = sup {
+            qualifiers.push((Qualifier::Uplimit, sup));
+        }
+
+        Content::Apply {
+            op: Box::new(op),
+            qualifiers,
+            args: vec![body],
+        }
+    }
+}
+
+/// Splits a trailing `d x` off an integrand
+fn take_differential(body: Content) -> (Content, Option<Content>) {
+    match body {
+        Content::Apply {
+            op,
+            qualifiers,
+            mut args,
+        } if matches!(*op, Content::Element("times")) => {
+            let n = args.len();
+            if n >= 2
+                && args[n - 2] == Content::Ci("d".to_owned())
+                && matches!(args[n - 1], Content::Ci(_))
+            {
+                let variable = args.pop();
+                args.pop();
+                let body = match args.len() {
+                    0 => Content::Cn("1".to_owned()),
+                    1 => args.remove(0),
+                    _ => Content::Apply {
+                        op,
+                        qualifiers,
+                        args,
+                    },
+                };
+                (body, variable)
+            } else {
+                (
+                    Content::Apply {
+                        op,
+                        qualifiers,
+                        args,
+                    },
+                    None,
+                )
+            }
+        }
+        // In a sum the differential belongs to the last term: x + 1 dx
+        Content::Apply {
+            op,
+            qualifiers,
+            mut args,
+        } if matches!(*op, Content::Element("plus" | "minus")) && !args.is_empty() => {
+            let last = args.pop().map(take_differential);
+            let variable = match last {
+                Some((last, variable)) => {
+                    args.push(last);
+                    variable
+                }
+                None => None,
+            };
+            (
+                Content::Apply {
+                    op,
+                    qualifiers,
+                    args,
+                },
+                variable,
+            )
+        }
+        body => (body, None),
+    }
+}
+
+/// Content MathML element for a relation
+fn relation_element(text: &str) -> &'static str {
+    match text {
+        "=" => "eq",
+        "<" => "lt",
+        ">" => "gt",
+        "\\leq" | "\\le" | "\u{2264}" => "leq",
+        "\\geq" | "\\ge" | "\u{2265}" => "geq",
+        "\\neq" | "\\ne" | "\u{2260}" => "neq",
+        "\\approx" => "approx",
+        "\\equiv" => "equivalent",
+        "\\in" => "in",
+        "\\notin" | "\u{2209}" => "notin",
+        "\\subset" => "prsubset",
+        "\\subseteq" => "subset",
+        "\\to" | "\\rightarrow" => "tendsto",
+        "\\implies" | "\\Rightarrow" => "implies",
+        _ => "unknown-relation",
+    }
+}
+
+/// Whether an ord is part of a number
+fn is_number(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Content MathML element for a named function such as `\sin`
+fn function_element(name: &str) -> Content {
+    let element = match name {
+        "sin" => "sin",
+        "cos" => "cos",
+        "tan" => "tan",
+        "sec" => "sec",
+        "csc" => "csc",
+        "cot" => "cot",
+        "sinh" => "sinh",
+        "cosh" => "cosh",
+        "tanh" => "tanh",
+        "coth" => "coth",
+        "arcsin" => "arcsin",
+        "arccos" => "arccos",
+        "arctan" => "arctan",
+        "exp" => "exp",
+        "ln" => "ln",
+        "log" => "log",
+        "det" => "determinant",
+        "gcd" => "gcd",
+        _ => return Content::Csymbol(name.to_owned()),
+    };
+    Content::Element(element)
+}
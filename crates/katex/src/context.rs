@@ -12,6 +12,7 @@ use crate::{
     font_metrics::{FONT_METRICS, FontMetrics, FontSizeIndex},
     functions,
     parser::parse_node::NodeType,
+    sublanguage::SublanguageParser,
     symbols::{Symbols, create_symbols},
 };
 
@@ -52,9 +53,106 @@ pub struct KatexContext {
     /// Font metrics data for character measurements
     #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
     pub font_metrics: FontMetricsData,
+    /// Parsers of the commands registered with
+    /// [`KatexContext::define_sublanguage`]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub sublanguages: KeyMap<String, SublanguageParser>,
 }
 
 impl KatexContext {
+    /// A context that only knows symbols, groups and scripts, so it renders
+    /// formulas such as `x_1^2 + {a'}` but no commands or environments.
+    ///
+    /// Building the symbol table is most of the cost of
+    /// [`KatexContext::default`]; registering every function on top of it
+    /// adds about half as much again. Callers that render a single formula and only
+    /// need some commands can start from here and register just those, e.g.
+    /// with [`functions::define_genfrac`].
+    #[must_use]
+    pub fn minimal() -> Self {
+        let mut ctx = Self {
+            functions: KeyMap::default(),
+            html_group_builders: KeyMap::default(),
+            mathml_group_builders: KeyMap::default(),
+            symbols: create_symbols(),
+            environments: KeyMap::default(),
+            font_metrics: FontMetricsData::default(),
+            sublanguages: KeyMap::default(),
+        };
+        functions::define_relax(&mut ctx);
+        functions::define_ordgroup(&mut ctx);
+        functions::define_supsub(&mut ctx);
+        functions::define_symbols_op(&mut ctx);
+        functions::define_symbols_ord(&mut ctx);
+        ctx
+    }
+
+    /// Registers every built-in function and environment, turning a
+    /// [`KatexContext::minimal`] context into the [`Default`] one.
+    ///
+    /// Functions and environments that were already defined with the same
+    /// names are replaced by the built-in ones.
+    #[must_use]
+    pub fn with_all_functions(mut self) -> Self {
+        functions::define_relax(&mut self);
+        functions::define_genfrac(&mut self);
+        functions::define_accent(&mut self);
+        functions::define_accentunder(&mut self);
+        functions::define_arrow(&mut self);
+        functions::define_char(&mut self);
+        functions::define_color(&mut self);
+        functions::define_cr(&mut self);
+        functions::define_def(&mut self);
+        functions::define_delimsizing(&mut self);
+        functions::define_enclose(&mut self);
+        functions::define_environment(&mut self);
+        functions::define_genfrac(&mut self);
+        functions::define_hbox(&mut self);
+        functions::define_horiz_brace(&mut self);
+        functions::define_href(&mut self);
+        functions::define_html(&mut self);
+        functions::define_htmlmathml(&mut self);
+        functions::define_includegraphics(&mut self);
+        functions::define_kern(&mut self);
+        functions::define_lap(&mut self);
+        functions::define_leftright(&mut self);
+        functions::define_math(&mut self);
+        functions::define_mathchoice(&mut self);
+        functions::define_mclass(&mut self);
+        functions::define_middle(&mut self);
+        functions::define_ordgroup(&mut self);
+        functions::define_overline(&mut self);
+        functions::define_phantom(&mut self);
+        functions::define_raisebox(&mut self);
+        functions::define_rule(&mut self);
+        functions::define_sizing(&mut self);
+        functions::define_smash(&mut self);
+        functions::define_spacing(&mut self);
+        functions::define_sqrt(&mut self);
+        functions::define_styling(&mut self);
+        functions::define_supsub(&mut self);
+        functions::define_symbols_op(&mut self);
+        functions::define_symbols_ord(&mut self);
+        functions::define_tag(&mut self);
+        functions::define_text(&mut self);
+        functions::define_underline(&mut self);
+        functions::define_vcenter(&mut self);
+        functions::define_verb(&mut self);
+        functions::define_pmb(&mut self);
+        functions::define_font(&mut self);
+        functions::define_op(&mut self);
+        functions::define_operatorname(&mut self);
+        functions::define_sideset(&mut self);
+        functions::define_tensor(&mut self);
+        functions::define_diagbox(&mut self);
+        functions::define_nuclide(&mut self);
+
+        // Register environments
+        define_environment::define_array(&mut self);
+        define_environment::define_cd(&mut self);
+        self
+    }
+
     /// Set default values of functions
     pub fn define_function(&mut self, spec: FunctionDefSpec) {
         let data = Arc::new(FunctionSpec {
@@ -67,6 +165,7 @@ impl KatexContext {
             num_optional_args: spec.props.num_optional_args,
             infix: spec.props.infix,
             primitive: spec.props.primitive,
+            examples: spec.props.examples,
             handler: spec.handler,
         });
 
@@ -105,6 +204,7 @@ impl KatexContext {
             arg_types: spec.props.arg_types.clone(),
             allowed_in_text: spec.props.allowed_in_text.unwrap_or(false),
             num_optional_args: spec.props.num_optional_args.unwrap_or(0),
+            raw_body: spec.props.raw_body.unwrap_or(false),
             handler: spec.handler,
         });
 
@@ -147,68 +247,9 @@ impl KatexContext {
 }
 
 impl Default for KatexContext {
+    /// A context with every function and environment registered, see
+    /// [`KatexContext::with_all_functions`].
     fn default() -> Self {
-        let mut ctx = Self {
-            functions: KeyMap::default(),
-            html_group_builders: KeyMap::default(),
-            mathml_group_builders: KeyMap::default(),
-            symbols: create_symbols(),
-            environments: KeyMap::default(),
-            font_metrics: FontMetricsData::default(),
-        };
-        // Register internal functions and symbols here if needed
-        functions::define_relax(&mut ctx);
-        functions::define_genfrac(&mut ctx);
-        functions::define_accent(&mut ctx);
-        functions::define_accentunder(&mut ctx);
-        functions::define_arrow(&mut ctx);
-        functions::define_char(&mut ctx);
-        functions::define_color(&mut ctx);
-        functions::define_cr(&mut ctx);
-        functions::define_def(&mut ctx);
-        functions::define_delimsizing(&mut ctx);
-        functions::define_enclose(&mut ctx);
-        functions::define_environment(&mut ctx);
-        functions::define_genfrac(&mut ctx);
-        functions::define_hbox(&mut ctx);
-        functions::define_horiz_brace(&mut ctx);
-        functions::define_href(&mut ctx);
-        functions::define_html(&mut ctx);
-        functions::define_htmlmathml(&mut ctx);
-        functions::define_includegraphics(&mut ctx);
-        functions::define_kern(&mut ctx);
-        functions::define_lap(&mut ctx);
-        functions::define_leftright(&mut ctx);
-        functions::define_math(&mut ctx);
-        functions::define_mathchoice(&mut ctx);
-        functions::define_mclass(&mut ctx);
-        functions::define_middle(&mut ctx);
-        functions::define_ordgroup(&mut ctx);
-        functions::define_overline(&mut ctx);
-        functions::define_phantom(&mut ctx);
-        functions::define_raisebox(&mut ctx);
-        functions::define_rule(&mut ctx);
-        functions::define_sizing(&mut ctx);
-        functions::define_smash(&mut ctx);
-        functions::define_spacing(&mut ctx);
-        functions::define_sqrt(&mut ctx);
-        functions::define_styling(&mut ctx);
-        functions::define_supsub(&mut ctx);
-        functions::define_symbols_op(&mut ctx);
-        functions::define_symbols_ord(&mut ctx);
-        functions::define_tag(&mut ctx);
-        functions::define_text(&mut ctx);
-        functions::define_underline(&mut ctx);
-        functions::define_vcenter(&mut ctx);
-        functions::define_verb(&mut ctx);
-        functions::define_pmb(&mut ctx);
-        functions::define_font(&mut ctx);
-        functions::define_op(&mut ctx);
-        functions::define_operatorname(&mut ctx);
-
-        // Register environments
-        define_environment::define_array(&mut ctx);
-        define_environment::define_cd(&mut ctx);
-        ctx
+        Self::minimal().with_all_functions()
     }
 }
@@ -0,0 +1,453 @@
+//! SymPy and Wolfram Language export, built on the Content MathML inference
+
+use core::fmt::Write as _;
+
+use thiserror::Error;
+
+use crate::content_mathml::{Content, Qualifier, build_content};
+use crate::context::KatexContext;
+use crate::parser::parse_node::AnyParseNode;
+
+/// Reasons a formula cannot be converted
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ConvertError {
+    /// Part of the formula has no counterpart in the target language
+    #[error("Cannot convert {what}")]
+    Unsupported {
+        /// Description of the unsupported part, such as its TeX source
+        what: String,
+    },
+    /// An operator lacks a bound variable or limit the target needs
+    #[error("{operator} needs {missing}")]
+    Incomplete {
+        /// The operator, such as `sum`
+        operator: &'static str,
+        /// What is missing, such as `a lower limit`
+        missing: &'static str,
+    },
+}
+
+/// Converts a parse tree into a SymPy expression string.
+///
+/// The result uses SymPy's function names (`sqrt`, `Sum`, `Integral`,
+/// `Eq`, ...) and can be evaluated with `sympy.parse_expr`.
+pub fn to_sympy(ctx: &KatexContext, ast: &[AnyParseNode]) -> Result<String, ConvertError> {
+    convert(ctx, ast, Dialect::SymPy)
+}
+
+/// Converts a parse tree into a Wolfram Language expression string.
+pub fn to_wolfram(ctx: &KatexContext, ast: &[AnyParseNode]) -> Result<String, ConvertError> {
+    convert(ctx, ast, Dialect::Wolfram)
+}
+
+fn convert(
+    ctx: &KatexContext,
+    ast: &[AnyParseNode],
+    dialect: Dialect,
+) -> Result<String, ConvertError> {
+    let mut out = String::new();
+    Writer {
+        dialect,
+        out: &mut out,
+    }
+    .write(&build_content(ctx, ast), Prec::Relation)?;
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    SymPy,
+    Wolfram,
+}
+
+/// Binding strength of the operator an expression is written under; operands
+/// that bind more loosely are parenthesized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Relation,
+    Additive,
+    Multiplicative,
+    Unary,
+    Power,
+    Atom,
+}
+
+/// Precedence of an infix operator and the least precedence of its first and
+/// later operands that is written without parentheses
+type Infix = (Prec, Prec, Prec);
+
+const RELATION: Infix = (Prec::Relation, Prec::Additive, Prec::Additive);
+const ADDITIVE: Infix = (Prec::Additive, Prec::Additive, Prec::Additive);
+const SUBTRACTION: Infix = (Prec::Additive, Prec::Additive, Prec::Multiplicative);
+const MULTIPLICATIVE: Infix = (
+    Prec::Multiplicative,
+    Prec::Multiplicative,
+    Prec::Multiplicative,
+);
+const DIVISION: Infix = (Prec::Multiplicative, Prec::Multiplicative, Prec::Unary);
+// Powers associate to the right
+const POWER: Infix = (Prec::Power, Prec::Atom, Prec::Power);
+
+/// SymPy and Wolfram names of the elementary functions
+const FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("sin", "sin", "Sin"),
+    ("cos", "cos", "Cos"),
+    ("tan", "tan", "Tan"),
+    ("sec", "sec", "Sec"),
+    ("csc", "csc", "Csc"),
+    ("cot", "cot", "Cot"),
+    ("sinh", "sinh", "Sinh"),
+    ("cosh", "cosh", "Cosh"),
+    ("tanh", "tanh", "Tanh"),
+    ("coth", "coth", "Coth"),
+    ("arcsin", "asin", "ArcSin"),
+    ("arccos", "acos", "ArcCos"),
+    ("arctan", "atan", "ArcTan"),
+    ("exp", "exp", "Exp"),
+    ("ln", "log", "Log"),
+    ("log", "log", "Log"),
+    ("abs", "Abs", "Abs"),
+    ("factorial", "factorial", "Factorial"),
+];
+
+/// Names for Greek letters, which the builder keeps as Unicode identifiers
+const GREEK: &[(char, &str)] = &[
+    ('\u{3b1}', "alpha"),
+    ('\u{3b2}', "beta"),
+    ('\u{3b3}', "gamma"),
+    ('\u{3b4}', "delta"),
+    ('\u{3b5}', "epsilon"),
+    ('\u{3f5}', "epsilon"),
+    ('\u{3b6}', "zeta"),
+    ('\u{3b7}', "eta"),
+    ('\u{3b8}', "theta"),
+    ('\u{3b9}', "iota"),
+    ('\u{3ba}', "kappa"),
+    ('\u{3bb}', "lambda"),
+    ('\u{3bc}', "mu"),
+    ('\u{3bd}', "nu"),
+    ('\u{3be}', "xi"),
+    ('\u{3c1}', "rho"),
+    ('\u{3c3}', "sigma"),
+    ('\u{3c4}', "tau"),
+    ('\u{3c5}', "upsilon"),
+    ('\u{3c6}', "phi"),
+    ('\u{3d5}', "phi"),
+    ('\u{3c7}', "chi"),
+    ('\u{3c8}', "psi"),
+    ('\u{3c9}', "omega"),
+    ('\u{393}', "Gamma"),
+    ('\u{394}', "Delta"),
+    ('\u{398}', "Theta"),
+    ('\u{39b}', "Lambda"),
+    ('\u{39e}', "Xi"),
+    ('\u{3a0}', "Pi"),
+    ('\u{3a3}', "Sigma"),
+    ('\u{3a6}', "Phi"),
+    ('\u{3a8}', "Psi"),
+    ('\u{3a9}', "Omega"),
+];
+
+struct Writer<'a> {
+    dialect: Dialect,
+    out: &'a mut String,
+}
+
+impl Writer<'_> {
+    /// Picks the SymPy or Wolfram spelling
+    const fn pick(&self, sympy: &'static str, wolfram: &'static str) -> &'static str {
+        match self.dialect {
+            Dialect::SymPy => sympy,
+            Dialect::Wolfram => wolfram,
+        }
+    }
+
+    fn write(&mut self, content: &Content, outer: Prec) -> Result<(), ConvertError> {
+        match content {
+            Content::Cn(number) => self.out.push_str(number),
+            Content::Ci(name) => self.identifier(name)?,
+            Content::Element("pi") => self.out.push_str(self.pick("pi", "Pi")),
+            Content::Element("infinity") => self.out.push_str(self.pick("oo", "Infinity")),
+            Content::Apply {
+                op,
+                qualifiers,
+                args,
+            } => self.apply(op, qualifiers, args, outer)?,
+            Content::Unknown(tex) if !tex.is_empty() => {
+                return Err(ConvertError::Unsupported { what: tex.clone() });
+            }
+            Content::Element(name) => {
+                return Err(ConvertError::Unsupported {
+                    what: (*name).to_owned(),
+                });
+            }
+            Content::Csymbol(name) => {
+                return Err(ConvertError::Unsupported { what: name.clone() });
+            }
+            Content::Unknown(_) => {
+                return Err(ConvertError::Unsupported {
+                    what: "an empty expression".to_owned(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an identifier; `x_1` becomes `x_1` or `Subscript[x, 1]`
+    fn identifier(&mut self, name: &str) -> Result<(), ConvertError> {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                GREEK
+                    .iter()
+                    .find(|(greek, _)| *greek == c)
+                    .map_or_else(|| c.to_string(), |(_, name)| (*name).to_owned())
+            })
+            .collect();
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(ConvertError::Unsupported { what: name });
+        }
+        match (self.dialect, name.split_once('_')) {
+            (Dialect::Wolfram, Some((base, index))) => {
+                let _ = write!(self.out, "Subscript[{base}, {index}]");
+            }
+            _ => self.out.push_str(&name),
+        }
+        Ok(())
+    }
+
+    /// Writes `items` separated by commas
+    fn list(&mut self, items: &[&Content]) -> Result<(), ConvertError> {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.write(item, Prec::Relation)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a call such as `sin(x)` or `Sin[x]`
+    fn call(&mut self, name: &str, args: &[&Content]) -> Result<(), ConvertError> {
+        self.out.push_str(name);
+        self.out.push_str(self.pick("(", "["));
+        self.list(args)?;
+        self.out.push_str(self.pick(")", "]"));
+        Ok(())
+    }
+
+    /// Writes operands joined by an infix operator, parenthesizing the
+    /// first operand below `left` and the others below `right`
+    fn infix(
+        &mut self,
+        args: &[Content],
+        operator: &str,
+        (prec, left, right): (Prec, Prec, Prec),
+        outer: Prec,
+    ) -> Result<(), ConvertError> {
+        let parenthesize = prec < outer;
+        if parenthesize {
+            self.out.push('(');
+        }
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(operator);
+            }
+            self.write(arg, if i == 0 { left } else { right })?;
+        }
+        if parenthesize {
+            self.out.push(')');
+        }
+        Ok(())
+    }
+
+    fn apply(
+        &mut self,
+        op: &Content,
+        qualifiers: &[(Qualifier, Content)],
+        args: &[Content],
+        outer: Prec,
+    ) -> Result<(), ConvertError> {
+        let qualifier = |wanted: Qualifier| {
+            qualifiers
+                .iter()
+                .find(|(qualifier, _)| *qualifier == wanted)
+                .map(|(_, content)| content)
+        };
+        let name = match op {
+            Content::Element(name) => *name,
+            Content::Csymbol(name) if name == "binomial" => "binomial",
+            // f(x) with an undefined function
+            Content::Ci(function) => {
+                let mut name = String::new();
+                Writer {
+                    dialect: self.dialect,
+                    out: &mut name,
+                }
+                .identifier(function)?;
+                return self.call(&name, &args.iter().collect::<Vec<_>>());
+            }
+            other => return self.write(other, outer),
+        };
+
+        match (name, args) {
+            ("plus", _) => self.infix(args, " + ", ADDITIVE, outer),
+            ("minus", [arg]) => {
+                let parenthesize = Prec::Unary < outer;
+                if parenthesize {
+                    self.out.push('(');
+                }
+                self.out.push('-');
+                // -a*b reads as (-a)*b, which has the same value
+                self.write(arg, Prec::Multiplicative)?;
+                if parenthesize {
+                    self.out.push(')');
+                }
+                Ok(())
+            }
+            ("minus", [_, _]) => self.infix(args, " - ", SUBTRACTION, outer),
+            ("times", _) => self.infix(args, "*", MULTIPLICATIVE, outer),
+            ("divide", [_, _]) => self.infix(args, "/", DIVISION, outer),
+            ("power", [_, _]) => self.infix(args, self.pick("**", "^"), POWER, outer),
+            ("root", [arg]) => match qualifier(Qualifier::Degree) {
+                None => self.call(self.pick("sqrt", "Sqrt"), &[arg]),
+                Some(degree) => match self.dialect {
+                    Dialect::SymPy => self.call("root", &[arg, degree]),
+                    Dialect::Wolfram => {
+                        self.out.push_str("Power[");
+                        self.write(arg, Prec::Relation)?;
+                        self.out.push_str(", 1/");
+                        self.write(degree, Prec::Atom)?;
+                        self.out.push(']');
+                        Ok(())
+                    }
+                },
+            },
+            ("log", [arg]) if let Some(base) = qualifier(Qualifier::Logbase) => {
+                match self.dialect {
+                    Dialect::SymPy => self.call("log", &[arg, base]),
+                    Dialect::Wolfram => self.call("Log", &[base, arg]),
+                }
+            }
+            ("binomial", [n, k]) => self.call(self.pick("binomial", "Binomial"), &[n, k]),
+            ("eq" | "neq" | "lt" | "gt" | "leq" | "geq", _) => self.relation(name, args, outer),
+            ("sum" | "product" | "int", [body]) => self.big_op(name, qualifiers, body),
+            ("limit", [body]) => {
+                let (Some(variable), Some(point)) =
+                    (qualifier(Qualifier::Bvar), qualifier(Qualifier::Lowlimit))
+                else {
+                    return Err(ConvertError::Incomplete {
+                        operator: "limit",
+                        missing: "a variable and the point it tends to",
+                    });
+                };
+                match self.dialect {
+                    Dialect::SymPy => self.call("Limit", &[body, variable, point]),
+                    Dialect::Wolfram => {
+                        self.out.push_str("Limit[");
+                        self.write(body, Prec::Relation)?;
+                        self.out.push_str(", ");
+                        self.write(variable, Prec::Relation)?;
+                        self.out.push_str(" -> ");
+                        self.write(point, Prec::Relation)?;
+                        self.out.push(']');
+                        Ok(())
+                    }
+                }
+            }
+            (name, [arg]) if qualifiers.is_empty() => {
+                let Some((_, sympy, wolfram)) = FUNCTIONS.iter().find(|(n, ..)| *n == name) else {
+                    return Err(ConvertError::Unsupported {
+                        what: name.to_owned(),
+                    });
+                };
+                self.call(self.pick(sympy, wolfram), &[arg])
+            }
+            (name, _) => Err(ConvertError::Unsupported {
+                what: name.to_owned(),
+            }),
+        }
+    }
+
+    /// Writes `a = b` as `Eq(a, b)` or `a == b`, and the other relations
+    /// infix; SymPy has no chained relations
+    fn relation(
+        &mut self,
+        name: &'static str,
+        args: &[Content],
+        outer: Prec,
+    ) -> Result<(), ConvertError> {
+        match (self.dialect, name, args) {
+            (Dialect::SymPy, "eq", [lhs, rhs]) => self.call("Eq", &[lhs, rhs]),
+            (Dialect::SymPy, "neq", [lhs, rhs]) => self.call("Ne", &[lhs, rhs]),
+            (Dialect::SymPy, _, [_, _]) | (Dialect::Wolfram, ..) => {
+                let operator = match name {
+                    "eq" => " == ",
+                    "neq" => " != ",
+                    "lt" => " < ",
+                    "gt" => " > ",
+                    "leq" => " <= ",
+                    _ => " >= ",
+                };
+                self.infix(args, operator, RELATION, outer)
+            }
+            (Dialect::SymPy, ..) => Err(ConvertError::Unsupported {
+                what: "a chained relation".to_owned(),
+            }),
+        }
+    }
+
+    /// Writes `Sum(body, (i, a, b))` or `Sum[body, {i, a, b}]`, and the
+    /// products and integrals alike
+    fn big_op(
+        &mut self,
+        name: &'static str,
+        qualifiers: &[(Qualifier, Content)],
+        body: &Content,
+    ) -> Result<(), ConvertError> {
+        let qualifier = |wanted: Qualifier| {
+            qualifiers
+                .iter()
+                .find(|(qualifier, _)| *qualifier == wanted)
+                .map(|(_, content)| content)
+        };
+        let operator = match name {
+            "sum" => self.pick("Sum", "Sum"),
+            "product" => self.pick("Product", "Product"),
+            _ => self.pick("Integral", "Integrate"),
+        };
+        let Some(variable) = qualifier(Qualifier::Bvar) else {
+            return Err(ConvertError::Incomplete {
+                operator: name,
+                missing: "a bound variable",
+            });
+        };
+        let limits = (
+            qualifier(Qualifier::Lowlimit),
+            qualifier(Qualifier::Uplimit),
+        );
+
+        self.out.push_str(operator);
+        self.out.push_str(self.pick("(", "["));
+        self.write(body, Prec::Relation)?;
+        self.out.push_str(", ");
+        match limits {
+            (Some(low), Some(high)) => {
+                self.out.push_str(self.pick("(", "{"));
+                self.list(&[variable, low, high])?;
+                self.out.push_str(self.pick(")", "}"));
+            }
+            (None, None) if name == "int" => self.write(variable, Prec::Relation)?,
+            _ => {
+                return Err(ConvertError::Incomplete {
+                    operator: name,
+                    missing: "both limits",
+                });
+            }
+        }
+        self.out.push_str(self.pick(")", "]"));
+        Ok(())
+    }
+}
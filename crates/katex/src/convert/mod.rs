@@ -0,0 +1,49 @@
+//! Conversion of parse trees into other math languages
+//!
+//! [`to_typst`] rewrites a formula in Typst math syntax, and
+//! [`to_unicode_math`] in the UnicodeMath linear format that Microsoft Word
+//! and OneNote accept. Both follow the layout of the parse tree, so anything
+//! KaTeX can typeset has a reasonable translation, and the result looks the
+//! same rather than meaning the same.
+//!
+//! **Experimental.** [`to_sympy`] and [`to_wolfram`] translate a formula into
+//! an expression string that SymPy's `parse_expr` or the Wolfram Language can
+//! evaluate. The structure is inferred by the Content MathML builder (see
+//! [`crate::content_mathml`]), so the same conventions apply: juxtaposition
+//! is multiplication, `\sin x` is a function application, and `\sum`,
+//! `\prod`, `\int` and `\lim` bind the variable given in their limits.
+//!
+//! Only a safe subset is translated: arithmetic, powers, roots, fractions,
+//! the elementary functions, factorials, absolute values, binomials, sums,
+//! products, integrals, limits and relations. Anything else is reported as a
+//! [`ConvertError`] instead of being guessed, so the output can be trusted
+//! when it is produced at all. Letters are always symbols: `e` and `i` are
+//! not taken to be Euler's number or the imaginary unit.
+
+mod algebra;
+mod typst;
+mod unicode_math;
+
+use core::slice;
+
+use crate::parser::parse_node::AnyParseNode;
+
+pub use algebra::{ConvertError, to_sympy, to_wolfram};
+pub use typst::to_typst;
+pub use unicode_math::to_unicode_math;
+
+/// Number of primes a superscript consists of, or zero.
+///
+/// The parser turns both `'` and `′` into `\prime`, so that is the only
+/// prime there is.
+pub(crate) fn primes(sup: &AnyParseNode) -> usize {
+    let body = match sup {
+        AnyParseNode::OrdGroup(group) => group.body.as_slice(),
+        _ => slice::from_ref(sup),
+    };
+    if body.iter().all(|node| node.text() == Some("\\prime")) {
+        body.len()
+    } else {
+        0
+    }
+}
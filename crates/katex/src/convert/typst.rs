@@ -0,0 +1,695 @@
+//! Typst math markup from parse trees
+
+use core::slice;
+
+use crate::build_html::DomType;
+use crate::content_mathml::source;
+use crate::context::KatexContext;
+use crate::convert::primes;
+use crate::parser::parse_node::{
+    AnyParseNode, ColSeparationType, ParseNodeArray, ParseNodeOp, ParseNodeOrdGroup,
+    ParseNodeStyling, ParseNodeSupSub, ParseNodeText,
+};
+use crate::symbols::Mode;
+
+/// Converts a parse tree into Typst math markup.
+///
+/// The result is the content of a Typst equation, without the surrounding
+/// `$` signs. Commands become Typst's symbol names (`alpha`, `arrow.r`,
+/// `integral`), other symbols are written as Unicode characters, and
+/// fractions, roots, accents, matrices and `cases` use the Typst functions
+/// of the same purpose. Constructs with no Typst counterpart are left as
+/// `/* ... */` comments holding their TeX source.
+#[must_use]
+pub fn to_typst(ctx: &KatexContext, ast: &[AnyParseNode]) -> String {
+    Writer { ctx, args: 0 }.expression(ast)
+}
+
+/// Commands whose Typst name differs from the TeX one
+const SYMBOLS: &[(&str, &str)] = &[
+    ("\\infty", "infinity"),
+    ("\\cdot", "dot.op"),
+    ("\\cdotp", "dot.op"),
+    ("\\times", "times"),
+    ("\\div", "div"),
+    ("\\pm", "plus.minus"),
+    ("\\mp", "minus.plus"),
+    ("\\ast", "ast"),
+    ("\\star", "star"),
+    ("\\circ", "compose"),
+    ("\\bullet", "bullet"),
+    ("\\cup", "union"),
+    ("\\cap", "sect"),
+    ("\\setminus", "without"),
+    ("\\wedge", "and"),
+    ("\\land", "and"),
+    ("\\vee", "or"),
+    ("\\lor", "or"),
+    ("\\oplus", "plus.circle"),
+    ("\\otimes", "times.circle"),
+    ("\\leq", "<="),
+    ("\\le", "<="),
+    ("\\geq", ">="),
+    ("\\ge", ">="),
+    ("\\neq", "!="),
+    ("\\ne", "!="),
+    ("\\ll", "<<"),
+    ("\\gg", ">>"),
+    ("\\approx", "approx"),
+    ("\\equiv", "equiv"),
+    ("\\sim", "tilde.op"),
+    ("\\simeq", "tilde.eq"),
+    ("\\cong", "tilde.equiv"),
+    ("\\propto", "prop"),
+    ("\\in", "in"),
+    ("\\notin", "in.not"),
+    ("\\ni", "in.rev"),
+    ("\\subset", "subset"),
+    ("\\subseteq", "subset.eq"),
+    ("\\supset", "supset"),
+    ("\\supseteq", "supset.eq"),
+    ("\\to", "->"),
+    ("\\rightarrow", "->"),
+    ("\\gets", "<-"),
+    ("\\leftarrow", "<-"),
+    ("\\leftrightarrow", "<->"),
+    ("\\Rightarrow", "=>"),
+    ("\\Leftarrow", "arrow.l.double"),
+    ("\\Leftrightarrow", "<=>"),
+    ("\\longrightarrow", "-->"),
+    ("\\longleftarrow", "<--"),
+    ("\\implies", "==>"),
+    ("\\impliedby", "<=="),
+    ("\\iff", "<==>"),
+    ("\\mapsto", "|->"),
+    ("\\mid", "divides"),
+    ("\\parallel", "parallel"),
+    ("\\perp", "perp"),
+    ("\\colon", "colon"),
+    ("\\ldots", "dots.h"),
+    ("\\dots", "dots.h"),
+    ("\\cdots", "dots.c"),
+    ("\\vdots", "dots.v"),
+    ("\\ddots", "dots.down"),
+    ("\\partial", "partial"),
+    ("\\nabla", "nabla"),
+    ("\\forall", "forall"),
+    ("\\exists", "exists"),
+    ("\\emptyset", "emptyset"),
+    ("\\varnothing", "nothing"),
+    ("\\hbar", "planck.reduce"),
+    ("\\ell", "ell"),
+    ("\\prime", "prime"),
+    ("\\angle", "angle"),
+    ("\\neg", "not"),
+    ("\\lnot", "not"),
+    ("\\top", "top"),
+    ("\\bot", "bot"),
+    ("\\langle", "angle.l"),
+    ("\\rangle", "angle.r"),
+    ("\\lfloor", "floor.l"),
+    ("\\rfloor", "floor.r"),
+    ("\\lceil", "ceil.l"),
+    ("\\rceil", "ceil.r"),
+    ("\\{", "{"),
+    ("\\lbrace", "{"),
+    ("\\}", "}"),
+    ("\\rbrace", "}"),
+    ("\\vert", "|"),
+    ("\\lvert", "|"),
+    ("\\rvert", "|"),
+    ("\\|", "||"),
+    ("\\Vert", "||"),
+    ("\\epsilon", "epsilon.alt"),
+    ("\\varepsilon", "epsilon"),
+    ("\\phi", "phi.alt"),
+    ("\\varphi", "phi"),
+    ("\\vartheta", "theta.alt"),
+    ("\\varrho", "rho.alt"),
+    ("\\varsigma", "sigma.alt"),
+    ("\\varpi", "pi.alt"),
+    ("\\varkappa", "kappa.alt"),
+    ("\\sum", "sum"),
+    ("\\prod", "product"),
+    ("\\coprod", "product.co"),
+    ("\\int", "integral"),
+    ("\\iint", "integral.double"),
+    ("\\iiint", "integral.triple"),
+    ("\\oint", "integral.cont"),
+    ("\\bigcup", "union.big"),
+    ("\\bigcap", "sect.big"),
+    ("\\bigoplus", "plus.circle.big"),
+    ("\\bigotimes", "times.circle.big"),
+    ("\\bigvee", "or.big"),
+    ("\\bigwedge", "and.big"),
+];
+
+/// Commands whose Typst name is the TeX name without the backslash
+const SAME_NAME: &[&str] = &[
+    "alpha", "beta", "gamma", "delta", "zeta", "eta", "theta", "iota", "kappa", "lambda", "mu",
+    "nu", "xi", "pi", "rho", "sigma", "tau", "upsilon", "chi", "psi", "omega", "Gamma", "Delta",
+    "Theta", "Lambda", "Xi", "Pi", "Sigma", "Upsilon", "Phi", "Psi", "Omega", "aleph", "beth",
+];
+
+/// Operator names that Typst predefines as text operators
+const OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min",
+    "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// Named colors that mean the same in TeX and Typst
+const COLORS: &[&str] = &[
+    "black", "gray", "silver", "white", "navy", "blue", "aqua", "teal", "purple", "fuchsia",
+    "maroon", "red", "orange", "yellow", "olive", "green", "lime",
+];
+
+struct Writer<'a> {
+    ctx: &'a KatexContext,
+    /// Depth of function call arguments, where `,` and `;` separate
+    /// arguments and must be escaped
+    args: usize,
+}
+
+impl Writer<'_> {
+    /// Writes a node list, keeping digit runs together and separating
+    /// everything else with spaces so letters do not merge into identifiers
+    fn expression(&mut self, nodes: &[AnyParseNode]) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut number = false;
+        for node in nodes {
+            let part = self.node(node);
+            if part.is_empty() {
+                continue;
+            }
+            let digits = matches!(node, AnyParseNode::MathOrd(_) | AnyParseNode::TextOrd(_))
+                && is_number(&part);
+            match parts.last_mut() {
+                Some(last) if digits && number => last.push_str(&part),
+                // The digits before a scripted digit are part of its base,
+                // as in `10^(-3)`
+                Some(last) if number && is_scripted_number(node) => last.push_str(&part),
+                _ => parts.push(part),
+            }
+            number = digits;
+        }
+        parts.join(" ")
+    }
+
+    #[expect(clippy::too_many_lines)]
+    fn node(&mut self, node: &AnyParseNode) -> String {
+        match node {
+            AnyParseNode::MathOrd(ord) => self.symbol(&ord.text),
+            AnyParseNode::TextOrd(ord) => self.symbol(&ord.text),
+            AnyParseNode::Atom(atom) => self.symbol(&atom.text),
+            AnyParseNode::OrdGroup(group) => self.expression(&group.body),
+            AnyParseNode::SupSub(supsub) => self.scripts(supsub),
+            AnyParseNode::Genfrac(frac) => {
+                let name = if frac.has_bar_line { "frac" } else { "binom" };
+                self.call(name, &[&frac.numer, &frac.denom])
+            }
+            AnyParseNode::Sqrt(sqrt) => match &sqrt.index {
+                Some(index) => self.call("root", &[index, &sqrt.body]),
+                None => self.call("sqrt", &[&sqrt.body]),
+            },
+            AnyParseNode::LeftRight(leftright) => {
+                if let [AnyParseNode::Array(array)] = leftright.body.as_slice() {
+                    return self.array(array, &leftright.left, &leftright.right);
+                }
+                let left = delimiter(&leftright.left);
+                let right = delimiter(&leftright.right);
+                self.args += 1;
+                let body = self.expression(&leftright.body);
+                self.args -= 1;
+                format!("lr({left} {body} {right})")
+            }
+            AnyParseNode::Middle(middle) => format!("mid({})", delimiter(&middle.delim)),
+            AnyParseNode::Array(array) => self.array(array, ".", "."),
+            AnyParseNode::Font(font) => {
+                let name = match font.font.as_str() {
+                    "mathbb" | "textbb" => "bb",
+                    "mathcal" => "cal",
+                    "mathscr" => "scr",
+                    "mathfrak" | "textfrak" => "frak",
+                    "mathbf" | "textbf" | "boldsymbol" | "bm" => "bold",
+                    "mathrm" | "textrm" | "mathnormal" => "upright",
+                    "mathit" | "textit" => "italic",
+                    "mathsf" | "textsf" => "sans",
+                    "mathtt" | "texttt" => "mono",
+                    _ => return self.node(&font.body),
+                };
+                self.call(name, &[&font.body])
+            }
+            AnyParseNode::Text(text) => {
+                let mut string = String::new();
+                self.text(&text.body, &mut string);
+                let quoted = format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""));
+                match text.font.as_deref() {
+                    Some("\\textbf") => format!("bold({quoted})"),
+                    Some("\\textit") => format!("italic({quoted})"),
+                    Some("\\textsf") => format!("sans({quoted})"),
+                    Some("\\texttt") => format!("mono({quoted})"),
+                    _ => quoted,
+                }
+            }
+            AnyParseNode::Styling(styling) => {
+                let name = match styling.style.size {
+                    0 => "display",
+                    1 => "inline",
+                    2 => "script",
+                    _ => "sscript",
+                };
+                self.args += 1;
+                let body = self.expression(&styling.body);
+                self.args -= 1;
+                format!("{name}({})", or_empty(body))
+            }
+            AnyParseNode::Sizing(sizing) => self.expression(&sizing.body),
+            AnyParseNode::Color(color) => {
+                let body = self.expression(&color.body);
+                match typst_color(&color.color) {
+                    Some(fill) => format!("#text(fill: {fill})[${body}$]"),
+                    None => body,
+                }
+            }
+            AnyParseNode::Mclass(mclass) => {
+                let class = match mclass.mclass {
+                    DomType::Mbin => "binary",
+                    DomType::Mrel => "relation",
+                    DomType::Mop => "large",
+                    DomType::Mopen => "opening",
+                    DomType::Mclose => "closing",
+                    DomType::Mpunct => "punctuation",
+                    DomType::Mord | DomType::Minner => return self.expression(&mclass.body),
+                };
+                self.args += 1;
+                let body = self.expression(&mclass.body);
+                self.args -= 1;
+                format!("class(\"{class}\", {})", or_empty(body))
+            }
+            AnyParseNode::Accent(accent) => {
+                let (name, symbol) = match accent.label.as_str() {
+                    "\\hat" | "\\widehat" => ("hat", None),
+                    "\\tilde" | "\\widetilde" => ("tilde", None),
+                    "\\bar" => ("macron", None),
+                    "\\vec" | "\\overrightarrow" => ("arrow", None),
+                    "\\overleftarrow" => ("accent", Some("arrow.l")),
+                    "\\overleftrightarrow" => ("accent", Some("arrow.l.r")),
+                    "\\dot" => ("dot", None),
+                    "\\ddot" => ("dot.double", None),
+                    "\\dddot" => ("dot.triple", None),
+                    "\\check" | "\\widecheck" => ("caron", None),
+                    "\\breve" => ("breve", None),
+                    "\\acute" => ("acute", None),
+                    "\\grave" => ("grave", None),
+                    "\\mathring" => ("circle", None),
+                    _ => return self.node(&accent.base),
+                };
+                let base = self.arguments(&[&accent.base]);
+                symbol.map_or_else(
+                    || format!("{name}({base})"),
+                    |symbol| format!("{name}({base}, {symbol})"),
+                )
+            }
+            AnyParseNode::AccentUnder(accent) => match accent.label.as_str() {
+                "\\underline" => self.call("underline", &[&accent.base]),
+                _ => self.node(&accent.base),
+            },
+            AnyParseNode::Overline(overline) => self.call("overline", &[&overline.body]),
+            AnyParseNode::Underline(underline) => self.call("underline", &[&underline.body]),
+            AnyParseNode::HorizBrace(brace) => {
+                let name = if brace.is_over {
+                    "overbrace"
+                } else {
+                    "underbrace"
+                };
+                self.call(name, &[&brace.base])
+            }
+            AnyParseNode::Op(ParseNodeOp::Symbol { name, symbol, .. }) => {
+                let bare = name.trim_start_matches('\\');
+                if *symbol {
+                    self.symbol(name)
+                } else if OPERATORS.contains(&bare) {
+                    bare.to_owned()
+                } else {
+                    format!("op(\"{bare}\")")
+                }
+            }
+            AnyParseNode::Op(ParseNodeOp::Body { body, .. }) => {
+                self.args += 1;
+                let body = self.expression(body);
+                self.args -= 1;
+                format!("op({})", or_empty(body))
+            }
+            AnyParseNode::OperatorName(operator) => {
+                let mut name = String::new();
+                self.text(&operator.body, &mut name);
+                let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+                if operator.limits {
+                    format!("op(\"{name}\", limits: #true)")
+                } else {
+                    format!("op(\"{name}\")")
+                }
+            }
+            AnyParseNode::XArrow(arrow) => {
+                let symbol = match arrow.label.as_str() {
+                    "\\xleftarrow" => "arrow.l",
+                    "\\xRightarrow" => "arrow.r.double",
+                    "\\xLeftarrow" => "arrow.l.double",
+                    "\\xleftrightarrow" => "arrow.l.r",
+                    "\\xLeftrightarrow" => "arrow.l.r.double",
+                    "\\xmapsto" => "arrow.r.bar",
+                    "\\xhookrightarrow" => "arrow.r.hook",
+                    "\\xhookleftarrow" => "arrow.l.hook",
+                    "\\xlongequal" => "eq",
+                    _ => "arrow.r",
+                };
+                let mut out = format!("stretch({symbol})");
+                if let Some(body) = &arrow.body {
+                    out.push('^');
+                    out.push_str(&self.script(body));
+                }
+                if let Some(below) = &arrow.below {
+                    out.push('_');
+                    out.push_str(&self.script(below));
+                }
+                out
+            }
+            AnyParseNode::Enclose(enclose) => {
+                let body = self.arguments(&[&enclose.body]);
+                match enclose.label.as_str() {
+                    "\\cancel" | "\\sout" => format!("cancel({body})"),
+                    "\\bcancel" => format!("cancel({body}, inverted: #true)"),
+                    "\\xcancel" => format!("cancel({body}, cross: #true)"),
+                    "\\boxed" | "\\fbox" => {
+                        // \boxed{x} is \fbox{$\displaystyle x$}; the box keeps
+                        // the style of its content
+                        let mut inner = &*enclose.body;
+                        while let AnyParseNode::Text(ParseNodeText { body, .. })
+                        | AnyParseNode::Styling(ParseNodeStyling { body, .. })
+                        | AnyParseNode::OrdGroup(ParseNodeOrdGroup { body, .. }) = inner
+                            && let [only] = body.as_slice()
+                        {
+                            inner = only;
+                        }
+                        let body = self.node(inner);
+                        format!("#box(stroke: 0.4pt, inset: 3pt, ${body}$)")
+                    }
+                    _ => self.node(&enclose.body),
+                }
+            }
+            AnyParseNode::Phantom(phantom) => {
+                format!("#hide[${}$]", self.expression(&phantom.body))
+            }
+            AnyParseNode::Hphantom(phantom) => format!("#hide[${}$]", self.node(&phantom.body)),
+            AnyParseNode::Vphantom(phantom) => format!("#hide[${}$]", self.node(&phantom.body)),
+            AnyParseNode::Tag(tag) => self.expression(&tag.body),
+            AnyParseNode::Spacing(spacing) => spacing_name(&spacing.text).to_owned(),
+            AnyParseNode::Kern(kern) => {
+                let number = kern.dimension.number;
+                match kern.dimension.unit.as_str() {
+                    unit @ ("em" | "pt" | "mm" | "cm" | "in") => format!("#h({number}{unit})"),
+                    // \, \: and \; are kerns of 3, 4 and 5mu
+                    "mu" => match number {
+                        3.0 => "thin".to_owned(),
+                        4.0 => "med".to_owned(),
+                        5.0 => "thick".to_owned(),
+                        _ => format!("#h({}em)", (number / 18.0 * 1e4).round() / 1e4),
+                    },
+                    _ => String::new(),
+                }
+            }
+            _ => node.text().map_or_else(
+                || format!("/* {} */", source(node).replace("*/", "* /")),
+                |text| self.symbol(text),
+            ),
+        }
+    }
+
+    /// Writes a symbol or character
+    fn symbol(&self, text: &str) -> String {
+        if let Some((_, name)) = SYMBOLS.iter().find(|(tex, _)| *tex == text) {
+            return (*name).to_owned();
+        }
+        if let Some(name) = text.strip_prefix('\\')
+            && SAME_NAME.contains(&name)
+        {
+            return name.to_owned();
+        }
+        let replaced;
+        let text = match self
+            .ctx
+            .symbols
+            .get(Mode::Math, text)
+            .and_then(|info| info.replace)
+        {
+            Some(replace) if text.starts_with('\\') => {
+                replaced = replace.to_string();
+                replaced.as_str()
+            }
+            _ => text,
+        };
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            let escape = match c {
+                '/' | '_' | '^' | '&' | '$' | '#' | '"' | '\\' | '@' => true,
+                ',' | ';' => self.args > 0,
+                _ => false,
+            };
+            if escape {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Writes a function call with each node as one argument
+    fn call(&mut self, name: &str, args: &[&AnyParseNode]) -> String {
+        format!("{name}({})", self.arguments(args))
+    }
+
+    /// Writes nodes as comma-separated function arguments
+    fn arguments(&mut self, args: &[&AnyParseNode]) -> String {
+        self.args += 1;
+        let args: Vec<String> = args.iter().map(|arg| or_empty(self.node(arg))).collect();
+        self.args -= 1;
+        args.join(", ")
+    }
+
+    /// Writes a script, in parentheses unless it is a single symbol
+    fn script(&mut self, node: &AnyParseNode) -> String {
+        let primary = self.args;
+        self.args = 0;
+        let script = self.node(node);
+        self.args = primary;
+        if script.is_empty() {
+            "\"\"".to_owned()
+        } else if script.chars().count() == 1
+            || script.chars().all(|c| c.is_alphanumeric() || c == '.')
+        {
+            script
+        } else {
+            format!("({script})")
+        }
+    }
+
+    fn scripts(&mut self, supsub: &ParseNodeSupSub) -> String {
+        let (sub, sup) = (supsub.sub.as_deref(), supsub.sup.as_deref());
+        // The labels of \overbrace and \underbrace are its second argument
+        if let Some(AnyParseNode::HorizBrace(brace)) = supsub.base.as_deref() {
+            let label = if brace.is_over { sup } else { sub };
+            if let Some(label) = label
+                && sub.xor(sup).is_some()
+            {
+                let name = if brace.is_over {
+                    "overbrace"
+                } else {
+                    "underbrace"
+                };
+                return self.call(name, &[&brace.base, label]);
+            }
+        }
+
+        let base = supsub.base.as_deref().map(|base| self.node(base));
+        let mut out = or_empty(base.unwrap_or_default());
+        if let Some(sub) = sub {
+            out.push('_');
+            out.push_str(&self.script(sub));
+        }
+        if let Some(sup) = sup {
+            let primes = primes(sup);
+            if primes > 0 {
+                out.push_str(&"'".repeat(primes));
+            } else {
+                out.push('^');
+                out.push_str(&self.script(sup));
+            }
+        }
+        out
+    }
+
+    /// Writes an array environment as `mat`, `cases` or aligned rows
+    fn array(&mut self, array: &ParseNodeArray, left: &str, right: &str) -> String {
+        let aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::Alignat | ColSeparationType::Gather)
+        );
+        if aligned && left == "." && right == "." {
+            let rows: Vec<String> = array
+                .body
+                .iter()
+                .map(|row| self.cells(row).join(" & ").trim().to_owned())
+                .collect();
+            return rows.join(" \\ ");
+        }
+
+        self.args += 1;
+        let out = match (left, right) {
+            ("\\{" | "\\lbrace", ".") | (".", "\\}" | "\\rbrace") => {
+                let rows: Vec<String> = array
+                    .body
+                    .iter()
+                    .map(|row| self.cells(row).join(" & "))
+                    .collect();
+                let reverse = if left == "." { "reverse: #true, " } else { "" };
+                format!("cases({reverse}{})", rows.join(", "))
+            }
+            _ => {
+                let delim = match left {
+                    "(" => "\"(\"",
+                    "[" | "\\lbrack" => "\"[\"",
+                    "\\{" | "\\lbrace" => "\"{\"",
+                    "|" | "\\vert" | "\\lvert" => "\"|\"",
+                    "\\|" | "\\Vert" | "\\lVert" => "\"||\"",
+                    _ => "#none",
+                };
+                let rows: Vec<String> = array
+                    .body
+                    .iter()
+                    .map(|row| {
+                        let cells: Vec<String> =
+                            self.cells(row).into_iter().map(or_empty).collect();
+                        cells.join(", ")
+                    })
+                    .collect();
+                format!("mat(delim: {delim}, {})", rows.join("; "))
+            }
+        };
+        self.args -= 1;
+        out
+    }
+
+    /// Writes the cells of an array row, without the style every cell is
+    /// wrapped in
+    fn cells(&mut self, row: &[AnyParseNode]) -> Vec<String> {
+        row.iter()
+            .map(|cell| match cell {
+                AnyParseNode::Styling(styling) => self.expression(&styling.body),
+                cell => self.node(cell),
+            })
+            .collect()
+    }
+
+    /// Collects the characters of text-mode nodes
+    fn text(&self, nodes: &[AnyParseNode], out: &mut String) {
+        for node in nodes {
+            match node {
+                AnyParseNode::Spacing(_) => out.push(' '),
+                AnyParseNode::Text(text) => self.text(&text.body, out),
+                AnyParseNode::Styling(styling) => self.text(&styling.body, out),
+                AnyParseNode::Sizing(sizing) => self.text(&sizing.body, out),
+                AnyParseNode::Color(color) => self.text(&color.body, out),
+                AnyParseNode::OrdGroup(group) => self.text(&group.body, out),
+                AnyParseNode::Font(font) => self.text(slice::from_ref(&*font.body), out),
+                _ => match node.text() {
+                    Some(text) if text.starts_with('\\') => {
+                        let mode = node.mode();
+                        if let Some(replace) = self
+                            .ctx
+                            .symbols
+                            .get(mode, text)
+                            .and_then(|info| info.replace)
+                        {
+                            out.push(replace);
+                        }
+                    }
+                    Some(text) => out.push_str(text),
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+/// Writes `""` for an empty argument, which Typst does not accept
+fn or_empty(part: String) -> String {
+    if part.is_empty() {
+        "\"\"".to_owned()
+    } else {
+        part
+    }
+}
+
+/// Typst spelling of a `\left`, `\middle` or `\right` delimiter
+fn delimiter(delim: &str) -> &str {
+    match delim {
+        "." => "",
+        "(" | ")" | "[" | "]" | "|" => delim,
+        "\\lbrack" => "[",
+        "\\rbrack" => "]",
+        "\\{" | "\\lbrace" => "{",
+        "\\}" | "\\rbrace" => "}",
+        "\\vert" | "\\lvert" | "\\rvert" => "|",
+        "\\|" | "\\Vert" | "\\lVert" | "\\rVert" => "||",
+        "\\langle" => "angle.l",
+        "\\rangle" => "angle.r",
+        "\\lfloor" => "floor.l",
+        "\\rfloor" => "floor.r",
+        "\\lceil" => "ceil.l",
+        "\\rceil" => "ceil.r",
+        "/" => "\\/",
+        other => other.trim_start_matches('\\'),
+    }
+}
+
+/// Typst name of a spacing command
+fn spacing_name(text: &str) -> &'static str {
+    match text {
+        "\\," | "\\thinspace" => "thin",
+        "\\:" | "\\>" | "\\medspace" => "med",
+        "\\;" | "\\thickspace" => "thick",
+        "\\quad" => "quad",
+        "\\qquad" => "wide",
+        "\\!" | "\\negthinspace" => "#h(-1em/6)",
+        "\\negmedspace" => "#h(-2em/9)",
+        "\\negthickspace" => "#h(-5em/18)",
+        _ => "space",
+    }
+}
+
+/// Whether a part is made of digits and decimal points
+fn is_number(part: &str) -> bool {
+    part.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Whether a node is a digit with scripts
+fn is_scripted_number(node: &AnyParseNode) -> bool {
+    let AnyParseNode::SupSub(supsub) = node else {
+        return false;
+    };
+    match supsub.base.as_deref() {
+        Some(AnyParseNode::MathOrd(ord)) => is_number(&ord.text),
+        Some(AnyParseNode::TextOrd(ord)) => is_number(&ord.text),
+        _ => false,
+    }
+}
+
+/// Typst color for a TeX color, if it has one
+fn typst_color(color: &str) -> Option<String> {
+    if color.starts_with('#') {
+        Some(format!("rgb(\"{color}\")"))
+    } else {
+        COLORS.contains(&color).then(|| color.to_owned())
+    }
+}
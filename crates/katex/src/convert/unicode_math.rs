@@ -0,0 +1,503 @@
+//! UnicodeMath, the linear format of Microsoft Office equations
+
+use core::slice;
+
+use crate::content_mathml::source;
+use crate::context::KatexContext;
+use crate::convert::primes;
+use crate::parser::parse_node::{
+    AnyParseNode, ColSeparationType, ParseNodeArray, ParseNodeOp, ParseNodeSupSub,
+};
+use crate::symbols::Mode;
+
+/// Converts a parse tree into UnicodeMath.
+///
+/// UnicodeMath is the linear format that Word and OneNote build equations
+/// from: `x^2`, `(a+b)/c`, `√(x+1)`, `■(a&b@c&d)`. Symbols are written as
+/// Unicode characters, and fonts such as `\mathbb` become Mathematical
+/// Alphanumeric Symbols. Colors, sizes and styles have no linear form and
+/// are dropped; constructs without any counterpart are kept as quoted TeX
+/// source, so nothing is lost silently.
+#[must_use]
+pub fn to_unicode_math(ctx: &KatexContext, ast: &[AnyParseNode]) -> String {
+    Writer { ctx }.expression(ast)
+}
+
+/// Characters that build structures and must be escaped to appear literally
+const OPERATORS: &[char] = &[
+    '/', '^', '_', '&', '@', '"', '\\', '\u{a6}', '\u{221a}', '\u{221b}', '\u{221c}', '\u{2592}',
+    '\u{252c}', '\u{2534}', '\u{25a0}', '\u{2588}',
+];
+
+/// Characters that make a part of the output a built structure, after which
+/// a space is needed to end the last operand
+const BUILDERS: &[char] = &[
+    '/', '^', '_', '\u{a6}', '\u{221a}', '\u{221b}', '\u{221c}', '\u{252c}', '\u{2534}',
+];
+
+struct Writer<'a> {
+    ctx: &'a KatexContext,
+}
+
+impl Writer<'_> {
+    fn expression(&self, nodes: &[AnyParseNode]) -> String {
+        let mut out = String::new();
+        let mut built = false;
+        for node in nodes {
+            let part = self.node(node);
+            if part.is_empty() {
+                continue;
+            }
+            // A space ends the operand of a script or fraction and is not
+            // shown
+            if built {
+                out.push(' ');
+            }
+            built = is_built(&part);
+            out.push_str(&part);
+        }
+        out
+    }
+
+    #[expect(clippy::too_many_lines)]
+    fn node(&self, node: &AnyParseNode) -> String {
+        match node {
+            AnyParseNode::MathOrd(ord) => self.symbol(&ord.text, Mode::Math),
+            AnyParseNode::TextOrd(ord) => self.symbol(&ord.text, Mode::Math),
+            AnyParseNode::Atom(atom) => self.symbol(&atom.text, Mode::Math),
+            AnyParseNode::OrdGroup(group) => self.expression(&group.body),
+            AnyParseNode::SupSub(supsub) => self.scripts(supsub),
+            AnyParseNode::Genfrac(frac) => {
+                let numer = self.operand(&frac.numer);
+                let denom = self.operand(&frac.denom);
+                match (frac.has_bar_line, frac.left_delim.as_deref()) {
+                    (true, _) => format!("{numer}/{denom}"),
+                    (false, Some("(")) => format!("({numer}\u{a6}{denom})"),
+                    (false, _) => format!("\u{3016}{numer}\u{a6}{denom}\u{3017}"),
+                }
+            }
+            AnyParseNode::Sqrt(sqrt) => {
+                let body = self.operand(&sqrt.body);
+                match sqrt.index.as_ref().map(|index| self.node(index)).as_deref() {
+                    None => format!("\u{221a}{body}"),
+                    Some("3") => format!("\u{221b}{body}"),
+                    Some("4") => format!("\u{221c}{body}"),
+                    Some(index) => {
+                        let body = self.node(&sqrt.body);
+                        format!("\u{221a}({index}&{body})")
+                    }
+                }
+            }
+            AnyParseNode::LeftRight(leftright) => {
+                let left = self.delimiter(&leftright.left, '\u{251c}');
+                let right = self.delimiter(&leftright.right, '\u{2524}');
+                let body = match leftright.body.as_slice() {
+                    [AnyParseNode::Array(array)] => self.array(array),
+                    body => self.expression(body),
+                };
+                format!("{left}{body}{right}")
+            }
+            AnyParseNode::Middle(middle) => match middle.delim.as_str() {
+                "|" | "\\vert" => "\u{2223}".to_owned(),
+                delim => self.delimiter(delim, ' ').trim().to_owned(),
+            },
+            AnyParseNode::Array(array) => self.array(array),
+            AnyParseNode::Font(font) => {
+                let body = self.node(&font.body);
+                let style = match font.font.as_str() {
+                    "mathbb" | "textbb" => Style::DoubleStruck,
+                    "mathbf" | "textbf" => Style::Bold,
+                    "boldsymbol" | "bm" => Style::BoldItalic,
+                    "mathit" | "textit" => Style::Italic,
+                    "mathcal" | "mathscr" => Style::Script,
+                    "mathfrak" | "textfrak" => Style::Fraktur,
+                    "mathsf" | "textsf" => Style::Sans,
+                    "mathtt" | "texttt" => Style::Mono,
+                    "mathrm" | "textrm" => return format!("\"{}\"", body.replace('"', "\\\"")),
+                    _ => return body,
+                };
+                body.chars().map(|c| style.apply(c)).collect()
+            }
+            AnyParseNode::Text(text) => {
+                let mut string = String::new();
+                self.text(&text.body, &mut string);
+                format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            AnyParseNode::Styling(styling) => self.expression(&styling.body),
+            AnyParseNode::Sizing(sizing) => self.expression(&sizing.body),
+            AnyParseNode::Color(color) => self.expression(&color.body),
+            AnyParseNode::Mclass(mclass) => self.expression(&mclass.body),
+            AnyParseNode::Tag(tag) => self.expression(&tag.body),
+            AnyParseNode::Accent(accent) => {
+                let mark = match accent.label.as_str() {
+                    "\\hat" | "\\widehat" => '\u{302}',
+                    "\\tilde" | "\\widetilde" => '\u{303}',
+                    "\\bar" => '\u{305}',
+                    "\\vec" | "\\overrightarrow" => '\u{20d7}',
+                    "\\overleftarrow" => '\u{20d6}',
+                    "\\dot" => '\u{307}',
+                    "\\ddot" => '\u{308}',
+                    "\\dddot" => '\u{20db}',
+                    "\\check" | "\\widecheck" => '\u{30c}',
+                    "\\breve" => '\u{306}',
+                    "\\acute" => '\u{301}',
+                    "\\grave" => '\u{300}',
+                    "\\mathring" => '\u{30a}',
+                    _ => return self.node(&accent.base),
+                };
+                format!("{}{mark}", self.operand(&accent.base))
+            }
+            AnyParseNode::AccentUnder(accent) => self.node(&accent.base),
+            AnyParseNode::Overline(overline) => format!("\u{af}{}", self.operand(&overline.body)),
+            AnyParseNode::Underline(underline) => {
+                format!("\u{2581}{}", self.operand(&underline.body))
+            }
+            AnyParseNode::HorizBrace(brace) => {
+                let symbol = if brace.is_over {
+                    '\u{23de}'
+                } else {
+                    '\u{23df}'
+                };
+                format!("{symbol}{}", self.operand(&brace.base))
+            }
+            AnyParseNode::Op(ParseNodeOp::Symbol { name, symbol, .. }) => {
+                if *symbol {
+                    self.symbol(name, Mode::Math)
+                } else {
+                    // Function names are followed by the invisible function
+                    // application operator
+                    format!("{}\u{2061}", name.trim_start_matches('\\'))
+                }
+            }
+            AnyParseNode::Op(ParseNodeOp::Body { body, .. }) => self.expression(body),
+            AnyParseNode::OperatorName(operator) => {
+                let mut name = String::new();
+                self.text(&operator.body, &mut name);
+                format!("{name}\u{2061}")
+            }
+            AnyParseNode::XArrow(arrow) => {
+                let symbol = match arrow.label.as_str() {
+                    "\\xleftarrow" => "\u{2190}",
+                    "\\xRightarrow" => "\u{21d2}",
+                    "\\xLeftarrow" => "\u{21d0}",
+                    "\\xleftrightarrow" => "\u{2194}",
+                    "\\xLeftrightarrow" => "\u{21d4}",
+                    "\\xmapsto" => "\u{21a6}",
+                    "\\xlongequal" => "=",
+                    _ => "\u{2192}",
+                };
+                let mut out = symbol.to_owned();
+                if let Some(below) = &arrow.below {
+                    out.push('\u{252c}');
+                    out.push_str(&self.operand(below));
+                }
+                if let Some(body) = &arrow.body {
+                    out.push('\u{2534}');
+                    out.push_str(&self.operand(body));
+                }
+                out
+            }
+            AnyParseNode::Enclose(enclose) => match enclose.label.as_str() {
+                "\\boxed" | "\\fbox" => format!("\u{25ad}{}", self.operand(&enclose.body)),
+                _ => self.node(&enclose.body),
+            },
+            AnyParseNode::Phantom(phantom) => {
+                format!("\u{27e1}({})", self.expression(&phantom.body))
+            }
+            AnyParseNode::Hphantom(phantom) => {
+                format!("\u{27e1}{}", self.operand(&phantom.body))
+            }
+            AnyParseNode::Vphantom(phantom) => {
+                format!("\u{27e1}{}", self.operand(&phantom.body))
+            }
+            AnyParseNode::Spacing(spacing) => match spacing.text.as_str() {
+                "\\," | "\\thinspace" => "\u{2009}",
+                "\\:" | "\\>" | "\\medspace" => "\u{205f}",
+                "\\;" | "\\thickspace" => "\u{2005}",
+                "\\quad" => "\u{2003}",
+                "\\qquad" => "\u{2003}\u{2003}",
+                "\\!" | "\\negthinspace" | "\\negmedspace" | "\\negthickspace" => "",
+                _ => "\u{a0}",
+            }
+            .to_owned(),
+            AnyParseNode::Kern(kern) => {
+                let em = match kern.dimension.unit.as_str() {
+                    "mu" => kern.dimension.number / 18.0,
+                    "em" => kern.dimension.number,
+                    _ => 0.0,
+                };
+                // The nearest of the Unicode spaces
+                match em {
+                    em if em <= 0.0 => "",
+                    em if em < 0.2 => "\u{2009}",
+                    em if em < 0.25 => "\u{205f}",
+                    em if em < 0.5 => "\u{2005}",
+                    em if em < 1.5 => "\u{2003}",
+                    _ => "\u{2003}\u{2003}",
+                }
+                .to_owned()
+            }
+            _ => node.text().map_or_else(
+                || format!("\"{}\"", source(node).replace('"', "\\\"")),
+                |text| self.symbol(text, node.mode()),
+            ),
+        }
+    }
+
+    /// Writes a symbol as its Unicode character, escaping build operators
+    fn symbol(&self, text: &str, mode: Mode) -> String {
+        let replaced;
+        let text = match self
+            .ctx
+            .symbols
+            .get(mode, text)
+            .and_then(|info| info.replace)
+        {
+            Some(replace) if text.starts_with('\\') => {
+                replaced = replace.to_string();
+                replaced.as_str()
+            }
+            _ => text.strip_prefix('\\').unwrap_or(text),
+        };
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            if OPERATORS.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Writes a delimiter, or `empty` for a missing one
+    fn delimiter(&self, delim: &str, empty: char) -> String {
+        match delim {
+            "." => empty.to_string(),
+            "(" | ")" | "[" | "]" | "|" => delim.to_owned(),
+            "\\{" | "\\lbrace" => "{".to_owned(),
+            "\\}" | "\\rbrace" => "}".to_owned(),
+            "\\|" | "\\Vert" | "\\lVert" | "\\rVert" => "\u{2016}".to_owned(),
+            "\\vert" | "\\lvert" | "\\rvert" => "|".to_owned(),
+            delim => self.symbol(delim, Mode::Math),
+        }
+    }
+
+    /// Writes the operand of a build operator, in parentheses unless it is a
+    /// single symbol or number; the parentheses are not displayed
+    fn operand(&self, node: &AnyParseNode) -> String {
+        let part = self.node(node);
+        let mut chars = part.chars();
+        let single = match (chars.next(), chars.next()) {
+            (Some(_), None) => true,
+            (Some(_), Some(_)) => part.chars().all(char::is_alphanumeric),
+            (None, _) => false,
+        };
+        if single { part } else { format!("({part})") }
+    }
+
+    fn scripts(&self, supsub: &ParseNodeSupSub) -> String {
+        let (sub, sup) = (supsub.sub.as_deref(), supsub.sup.as_deref());
+        let base = supsub.base.as_deref();
+        // Operators such as \lim and braces take their scripts as limits
+        let limits = match base {
+            Some(AnyParseNode::Op(ParseNodeOp::Symbol {
+                symbol: false,
+                limits,
+                ..
+            })) => *limits,
+            Some(AnyParseNode::HorizBrace(_)) => true,
+            _ => false,
+        };
+        // Delimited groups and braces already end where their scripts begin
+        let delimited = matches!(
+            base,
+            Some(AnyParseNode::Op(_) | AnyParseNode::LeftRight(_) | AnyParseNode::HorizBrace(_))
+        );
+        let mut out = match base.map(|base| self.node(base)) {
+            Some(base) if !base.is_empty() => {
+                let simple = delimited
+                    || base.chars().count() == 1
+                    || base.chars().all(char::is_alphanumeric);
+                if simple {
+                    base
+                } else {
+                    // Invisible grouping brackets
+                    format!("\u{3016}{base}\u{3017}")
+                }
+            }
+            _ => String::new(),
+        };
+        // Limits come before the function application operator
+        let applied = out.ends_with('\u{2061}');
+        if applied {
+            out.pop();
+        }
+        let (below, above) = if limits {
+            ('\u{252c}', '\u{2534}')
+        } else {
+            ('_', '^')
+        };
+        if let Some(sub) = sub {
+            out.push(below);
+            out.push_str(&self.operand(sub));
+        }
+        if let Some(sup) = sup {
+            let primes = primes(sup);
+            if primes > 0 {
+                out.push_str(&"\u{2032}".repeat(primes));
+            } else {
+                out.push(above);
+                out.push_str(&self.operand(sup));
+            }
+        }
+        if applied {
+            out.push('\u{2061}');
+        }
+        out
+    }
+
+    /// Writes an array as a matrix, `■(a&b@c&d)`, or as an equation array,
+    /// `█(a&=b@&=c)`, for the aligned environments
+    fn array(&self, array: &ParseNodeArray) -> String {
+        let aligned = matches!(
+            array.col_separation_type,
+            Some(ColSeparationType::Align | ColSeparationType::Alignat | ColSeparationType::Gather)
+        );
+        let rows: Vec<String> = array
+            .body
+            .iter()
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|cell| match cell {
+                        AnyParseNode::Styling(styling) => self.expression(&styling.body),
+                        cell => self.node(cell),
+                    })
+                    .collect();
+                cells.join("&")
+            })
+            .collect();
+        let symbol = if aligned { '\u{2588}' } else { '\u{25a0}' };
+        format!("{symbol}({})", rows.join("@"))
+    }
+
+    /// Collects the characters of text-mode nodes
+    fn text(&self, nodes: &[AnyParseNode], out: &mut String) {
+        for node in nodes {
+            match node {
+                AnyParseNode::Spacing(_) => out.push(' '),
+                AnyParseNode::Text(text) => self.text(&text.body, out),
+                AnyParseNode::Styling(styling) => self.text(&styling.body, out),
+                AnyParseNode::Sizing(sizing) => self.text(&sizing.body, out),
+                AnyParseNode::Color(color) => self.text(&color.body, out),
+                AnyParseNode::OrdGroup(group) => self.text(&group.body, out),
+                AnyParseNode::Font(font) => self.text(slice::from_ref(&*font.body), out),
+                _ => match node.text() {
+                    Some(text) if text.starts_with('\\') => {
+                        let replace = self
+                            .ctx
+                            .symbols
+                            .get(node.mode(), text)
+                            .and_then(|info| info.replace);
+                        if let Some(replace) = replace {
+                            out.push(replace);
+                        }
+                    }
+                    Some(text) => out.push_str(text),
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+/// Whether a part contains an unescaped build operator
+fn is_built(part: &str) -> bool {
+    let mut escaped = false;
+    for c in part.chars() {
+        if !escaped && BUILDERS.contains(&c) {
+            return true;
+        }
+        escaped = !escaped && c == '\\';
+    }
+    false
+}
+
+/// Mathematical Alphanumeric Symbols styles
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    Bold,
+    Italic,
+    BoldItalic,
+    Script,
+    Fraktur,
+    DoubleStruck,
+    Sans,
+    Mono,
+}
+
+impl Style {
+    /// Offsets of `A`, `a` and `0` in the style, if it has them
+    const fn offsets(self) -> (u32, u32, Option<u32>) {
+        match self {
+            Self::Bold => (0x1d400, 0x1d41a, Some(0x1d7ce)),
+            Self::Italic => (0x1d434, 0x1d44e, None),
+            Self::BoldItalic => (0x1d468, 0x1d482, None),
+            Self::Script => (0x1d49c, 0x1d4b6, None),
+            Self::Fraktur => (0x1d504, 0x1d51e, None),
+            Self::DoubleStruck => (0x1d538, 0x1d552, Some(0x1d7d8)),
+            Self::Sans => (0x1d5a0, 0x1d5ba, Some(0x1d7e2)),
+            Self::Mono => (0x1d670, 0x1d68a, Some(0x1d7f6)),
+        }
+    }
+
+    /// Letters that were encoded before the block and are missing from it
+    const fn hole(self, c: char) -> Option<char> {
+        Some(match (self, c) {
+            (Self::Italic, 'h') => '\u{210e}',
+            (Self::Script, 'B') => '\u{212c}',
+            (Self::Script, 'E') => '\u{2130}',
+            (Self::Script, 'F') => '\u{2131}',
+            (Self::Script, 'H') => '\u{210b}',
+            (Self::Script, 'I') => '\u{2110}',
+            (Self::Script, 'L') => '\u{2112}',
+            (Self::Script, 'M') => '\u{2133}',
+            (Self::Script, 'R') => '\u{211b}',
+            (Self::Script, 'e') => '\u{212f}',
+            (Self::Script, 'g') => '\u{210a}',
+            (Self::Script, 'o') => '\u{2134}',
+            (Self::Fraktur, 'C') => '\u{212d}',
+            (Self::Fraktur, 'H') => '\u{210c}',
+            (Self::Fraktur, 'I') => '\u{2111}',
+            (Self::Fraktur, 'R') => '\u{211c}',
+            (Self::Fraktur, 'Z') => '\u{2128}',
+            (Self::DoubleStruck, 'C') => '\u{2102}',
+            (Self::DoubleStruck, 'H') => '\u{210d}',
+            (Self::DoubleStruck, 'N') => '\u{2115}',
+            (Self::DoubleStruck, 'P') => '\u{2119}',
+            (Self::DoubleStruck, 'Q') => '\u{211a}',
+            (Self::DoubleStruck, 'R') => '\u{211d}',
+            (Self::DoubleStruck, 'Z') => '\u{2124}',
+            _ => return None,
+        })
+    }
+
+    /// The styled form of an ASCII letter or digit; other characters are
+    /// returned unchanged
+    fn apply(self, c: char) -> char {
+        if let Some(hole) = self.hole(c) {
+            return hole;
+        }
+        let (upper, lower, digits) = self.offsets();
+        let code = match c {
+            'A'..='Z' => upper + (c as u32 - 'A' as u32),
+            'a'..='z' => lower + (c as u32 - 'a' as u32),
+            '0'..='9' => match digits {
+                Some(digits) => digits + (c as u32 - '0' as u32),
+                None => return c,
+            },
+            _ => return c,
+        };
+        char::from_u32(code).unwrap_or(c)
+    }
+}
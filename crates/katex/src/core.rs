@@ -2,13 +2,19 @@
 
 #[cfg(feature = "wasm")]
 use crate::types::ParseErrorKind;
+use alloc::collections::BTreeSet;
+
 use crate::{
     KatexContext,
+    braille::{BrailleCode, build_braille},
     build_common::make_span,
-    build_tree::{build_html_tree, build_tree},
-    dom_tree::{DomSpan, SymbolNode},
-    parse_tree::parse_tree,
+    build_tree::{build_html_tree, build_tree, measure_tree_alignment},
+    content_mathml::build_content_mathml,
+    dom_tree::{DomSpan, HtmlDomNode, SymbolNode, drop_tree},
+    measure::Alignment,
+    parse_tree::{drop_tree as drop_parse_tree, parse_tree},
     parser::parse_node::AnyParseNode,
+    speech::{SpeechOptions, SpeechSpan, build_speech},
     tree::VirtualNode as _,
     types::{ParseError, Settings},
 };
@@ -35,10 +41,18 @@ fn render_error(
         "style".to_owned(),
         format!("color: {}", settings.error_color),
     );
+    node.charset = settings.charset;
 
     Ok(node)
 }
 
+/// Runs `f` on a parse tree, then frees the tree without recursion.
+fn with_tree<R>(tree: Vec<AnyParseNode>, f: impl FnOnce(&[AnyParseNode]) -> R) -> R {
+    let result = f(&tree);
+    drop_parse_tree(tree);
+    result
+}
+
 #[cfg(feature = "wasm")]
 use web_sys::Node;
 
@@ -49,7 +63,7 @@ pub fn render_to_string(
     settings: &Settings,
 ) -> Result<String, ParseError> {
     let dom_tree = match parse_tree(ctx, expression, settings) {
-        Ok(tree) => match build_tree(ctx, &tree, expression, settings) {
+        Ok(tree) => match with_tree(tree, |tree| build_tree(ctx, tree, expression, settings)) {
             Ok(dom) => Ok(dom),
             Err(e) => {
                 if settings.throw_on_error {
@@ -68,7 +82,43 @@ pub fn render_to_string(
         }
     }?;
 
-    dom_tree.to_markup()
+    let markup = dom_tree.to_markup();
+    drop_tree(dom_tree.into());
+    markup
+}
+
+/// Markup of a render together with the CSS classes it uses
+///
+/// Returned by [`render_to_output`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOutput {
+    /// The HTML markup, identical to what [`render_to_string`] returns
+    pub markup: String,
+    /// Every distinct CSS class in `markup`, in sorted order
+    pub classes_used: BTreeSet<String>,
+}
+
+/// Parse and build an expression, returning its markup and the set of CSS
+/// classes the markup uses
+///
+/// Pipelines that purge unused CSS can keep just the `katex.css` rules for
+/// [`RenderOutput::classes_used`], summed over every formula on a page,
+/// instead of whitelisting the whole stylesheet. When `throw_on_error` is
+/// off, the classes of the error box are reported for input that fails.
+pub fn render_to_output(
+    ctx: &KatexContext,
+    expression: &str,
+    settings: &Settings,
+) -> Result<RenderOutput, ParseError> {
+    let dom_tree = HtmlDomNode::from(render_to_dom_tree(ctx, expression, settings)?);
+    let mut classes_used = BTreeSet::new();
+    dom_tree.collect_classes(&mut classes_used);
+    let markup = dom_tree.to_markup();
+    drop_tree(dom_tree);
+    Ok(RenderOutput {
+        markup: markup?,
+        classes_used,
+    })
 }
 
 /// Parse and build an expression, and place that expression in the DOM node
@@ -136,7 +186,7 @@ pub fn render_to_dom_tree(
     settings: &Settings,
 ) -> Result<DomSpan, ParseError> {
     let dom_tree = match parse_tree(ctx, expression, settings) {
-        Ok(tree) => match build_tree(ctx, &tree, expression, settings) {
+        Ok(tree) => match with_tree(tree, |tree| build_tree(ctx, tree, expression, settings)) {
             Ok(dom) => Ok(dom),
             Err(e) => {
                 if settings.throw_on_error {
@@ -177,7 +227,9 @@ pub fn render_to_html_tree(
     settings: &Settings,
 ) -> Result<DomSpan, ParseError> {
     let dom_tree = match parse_tree(ctx, expression, settings) {
-        Ok(tree) => match build_html_tree(ctx, &tree, expression, settings) {
+        Ok(tree) => match with_tree(tree, |tree| {
+            build_html_tree(ctx, tree, expression, settings)
+        }) {
             Ok(dom) => Ok(dom),
             Err(e) => {
                 if settings.throw_on_error {
@@ -198,3 +250,106 @@ pub fn render_to_html_tree(
 
     Ok(dom_tree)
 }
+
+/// Measure where the first relation of an expression falls
+///
+/// This function parses and builds the HTML output of an expression and
+/// splits its estimated width at the first top-level relation. Errors are
+/// always returned, regardless of `throw_on_error`, since an error box has
+/// nothing to align.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to measure
+/// * `settings` - Settings the expression will be rendered with
+///
+/// # Returns
+/// A `Result` containing the [`Alignment`] of the expression or a `ParseError`
+pub fn measure_alignment(
+    ctx: &KatexContext,
+    expression: &str,
+    settings: &Settings,
+) -> Result<Alignment, ParseError> {
+    let tree = parse_tree(ctx, expression, settings)?;
+    with_tree(tree, |tree| measure_tree_alignment(ctx, tree, settings))
+}
+
+/// Describe an expression in words, MathSpeak style
+///
+/// This function parses a LaTeX expression and returns the speech for it as
+/// a list of spans, each tied to the source range of the node it describes.
+/// Parse errors are always returned, regardless of `throw_on_error`.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to describe
+/// * `settings` - Settings for parsing; `display_mode` decides how limits are
+///   read
+/// * `options` - Verbosity and locale of the speech
+///
+/// # Returns
+/// A `Result` containing the speech spans or a `ParseError`
+pub fn render_to_speech(
+    ctx: &KatexContext,
+    expression: &str,
+    settings: &Settings,
+    options: &SpeechOptions,
+) -> Result<Vec<SpeechSpan>, ParseError> {
+    let tree = parse_tree(ctx, expression, settings)?;
+    Ok(with_tree(tree, |tree| {
+        build_speech(tree, options, settings.display_mode)
+    }))
+}
+
+/// Transcribe an expression into braille
+///
+/// This function parses a LaTeX expression and writes it in the given braille
+/// mathematics code as a string of Unicode braille patterns. Parse errors are
+/// always returned, regardless of `throw_on_error`.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to transcribe
+/// * `settings` - Settings for parsing; `display_mode` decides how limits are
+///   written
+/// * `code` - The braille code to use
+///
+/// # Returns
+/// A `Result` containing the braille string or a `ParseError`
+pub fn render_to_braille(
+    ctx: &KatexContext,
+    expression: &str,
+    settings: &Settings,
+    code: BrailleCode,
+) -> Result<String, ParseError> {
+    let tree = parse_tree(ctx, expression, settings)?;
+    Ok(with_tree(tree, |tree| {
+        build_braille(tree, code, settings.display_mode)
+    }))
+}
+
+/// Render an expression to Content MathML
+///
+/// This function parses a LaTeX expression and infers its mathematical
+/// structure as Content MathML, for consumers such as computer algebra
+/// systems. Parts that are not understood are kept as `<semantics>` elements
+/// annotated with their TeX source. Parse errors are always returned,
+/// regardless of `throw_on_error`.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to convert
+/// * `settings` - Settings for parsing
+///
+/// # Returns
+/// A `Result` containing the `<math>` markup or a `ParseError`
+pub fn render_to_content_mathml(
+    ctx: &KatexContext,
+    expression: &str,
+    settings: &Settings,
+) -> Result<String, ParseError> {
+    let tree = parse_tree(ctx, expression, settings)?;
+    Ok(with_tree(tree, |tree| {
+        build_content_mathml(ctx, tree, settings.charset)
+    })?)
+}
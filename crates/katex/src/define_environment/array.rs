@@ -14,7 +14,7 @@ use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
 use crate::dom_tree::HtmlDomNode;
 use crate::macros::{MacroContextInterface as _, MacroDefinition};
 use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
-use crate::options::Options;
+use crate::options::{CellBox, Options};
 use crate::parser::Parser;
 use crate::parser::parse_node::{
     AlignSpec, AnyParseNode, ColSeparationType, NodeType, ParseNode, ParseNodeArray,
@@ -23,7 +23,7 @@ use crate::parser::parse_node::{
 };
 use crate::spacing_data::Measurement;
 use crate::style::{DISPLAY, SCRIPT, Style, TEXT};
-use crate::types::{BreakToken, CssProperty, ParseError, ParseErrorKind, Token};
+use crate::types::{ArgType, BreakToken, CssProperty, ParseError, ParseErrorKind, Token};
 use crate::utils::{push_and_get_mut, push_and_get_ref};
 use crate::{ClassList, KatexContext, build_html, build_mathml, units};
 use alloc::borrow::Cow;
@@ -235,7 +235,7 @@ pub fn parse_array(
             }
             "\\\\" => {
                 parser.consume();
-                let size = if parser.gullet.future_mut()?.text == " " {
+                let size = if parser.gullet.peek_n(0)?.text == " " {
                     None
                 } else {
                     parser.parse_size_group(true)?
@@ -379,7 +379,11 @@ fn html_builder(
         }
 
         let mut row_elements: Vec<Option<HtmlDomNode>> = Vec::with_capacity(inrow.len());
-        for group in inrow {
+        let mut filling_cells = Vec::new();
+        for (c, group) in inrow.iter().enumerate() {
+            if fills_cell(group) {
+                filling_cells.push(c);
+            }
             let elt = build_html::build_group(ctx, group, options, None)?;
             depth = depth.max(elt.depth());
             height = height.max(elt.height());
@@ -408,6 +412,23 @@ fn html_builder(
             depth += jot;
         }
 
+        // Now that the box of the row is known, build the cells that fill it
+        // again
+        if !filling_cells.is_empty() {
+            let cell_options = options.with_cell_box(Some(CellBox {
+                height,
+                depth,
+                size_multiplier: options.size_multiplier,
+            }));
+            for c in filling_cells {
+                let mut elt = build_html::build_group(ctx, &inrow[c], &cell_options, None)?;
+                if let Some(style) = elt.style_mut() {
+                    style.insert(CssProperty::Width, "100%".to_owned());
+                }
+                row_elements[c] = Some(elt);
+            }
+        }
+
         body.push(Outrow {
             elements: row_elements,
             height,
@@ -473,17 +494,14 @@ fn html_builder(
     let mut col_descr_num = 0;
     while c < nc || col_descr_num < col_descriptions.len() {
         let mut first_separator = true;
-        loop {
-            let Some(separator) = col_descriptions
+        while let Some(separator) =
+            col_descriptions
                 .get(col_descr_num)
                 .and_then(|spec| match spec {
                     AlignSpec::Separator { separator } => Some(separator.as_str()),
                     AlignSpec::Align { .. } => None,
                 })
-            else {
-                break;
-            };
-
+        {
             if !first_separator {
                 col_sep = make_span("arraycolsep", vec![], None, None);
                 col_sep.style.insert(
@@ -666,6 +684,19 @@ fn html_builder(
     }
 }
 
+/// Whether the cell consists of a construct that fills the box of its cell,
+/// like `\diagbox`. Such cells are built a second time with
+/// [`Options::cell_box`] set, once the box of their row is known.
+fn fills_cell(cell: &ParseNode) -> bool {
+    let ParseNode::Styling(styling) = cell else {
+        return false;
+    };
+    let [ParseNode::OrdGroup(group)] = styling.body.as_slice() else {
+        return false;
+    };
+    matches!(group.body.as_slice(), [ParseNode::Diagbox(_)])
+}
+
 /// Helper structure for row layout
 #[derive(Debug, Clone)]
 struct Outrow {
@@ -782,9 +813,29 @@ fn mathml_builder(
                 0.0
             }
     };
+    // A \\[size] gap is added below its row, like in the HTML output, and
+    // may be negative to pull rows together. The rowspacing attribute takes
+    // one value per row boundary.
+    let boundaries = array_node.body.len().saturating_sub(1);
+    let row_gaps = &array_node.row_gaps[..boundaries.min(array_node.row_gaps.len())];
+    let row_spacing = if row_gaps.iter().any(Option::is_some) {
+        row_gaps
+            .iter()
+            .map(|row_gap| {
+                let extra = match row_gap {
+                    Some(size) => ctx.calculate_size(size, options)?,
+                    None => 0.0,
+                };
+                Ok(units::make_em(gap + extra))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?
+            .join(" ")
+    } else {
+        units::make_em(gap)
+    };
     table
         .attributes
-        .insert("rowspacing".to_owned(), units::make_em(gap));
+        .insert("rowspacing".to_owned(), row_spacing);
 
     // MathML table lines go only between cells.
     // To place a line on an edge we'll use <menclose>, if necessary.
@@ -959,6 +1010,72 @@ fn mathml_builder(
     Ok(result)
 }
 
+/// Handler for the matrix environments (matrix, pmatrix, bmatrix, Bmatrix,
+/// vmatrix, Vmatrix) and their starred versions, whose optional argument sets
+/// the alignment of all columns
+const MATRIX_HANDLER: EnvHandler = |context, _args, opt_args| {
+    let delimiters = match context.env_name.as_str().trim_end_matches('*') {
+        "matrix" => None,
+        "pmatrix" => Some(("(".to_owned(), ")".to_owned())),
+        "bmatrix" => Some(("[".to_owned(), "]".to_owned())),
+        "Bmatrix" => Some(("\\{".to_owned(), "\\}".to_owned())),
+        "vmatrix" => Some(("|".to_owned(), "|".to_owned())),
+        "Vmatrix" => Some(("\\Vert".to_owned(), "\\Vert".to_owned())),
+        _ => {
+            return Err(ParseError::new(ParseErrorKind::NoSuchEnvironment {
+                name: context.env_name.clone(),
+            }));
+        }
+    };
+
+    let col_align = match opt_args.first() {
+        Some(Some(ParseNode::Raw(raw))) => match raw.string.trim() {
+            align @ ("l" | "c" | "r") => align.to_owned(),
+            _ => return Err(ParseError::new(ParseErrorKind::ExpectedAlignmentSpecifier)),
+        },
+        _ => "c".to_owned(),
+    };
+
+    // \hskip -\arraycolsep in amsmath
+    let payload = ArrayParseConfig {
+        hskip_before_and_after: Some(false),
+        cols: Some(vec![AlignSpec::Align {
+            align: col_align.clone(),
+            pregap: None,
+            postgap: None,
+        }]),
+        ..Default::default()
+    };
+    let mut res = parse_array(context.parser, payload, d_cell_style(&context.env_name))?;
+
+    // Populate cols with the correct number of column alignment specs.
+    let num_cols = res.body.iter().map(Vec::len).max().unwrap_or(0);
+    res.cols = Some(
+        repeat_n(
+            AlignSpec::Align {
+                align: col_align,
+                pregap: None,
+                postgap: None,
+            },
+            num_cols,
+        )
+        .collect(),
+    );
+
+    if let Some((left, right)) = delimiters {
+        Ok(ParseNode::LeftRight(Box::new(ParseNodeLeftRight {
+            mode: context.mode,
+            loc: None,
+            body: vec![ParseNode::Array(Box::new(res))],
+            left,
+            right,
+            right_color: None,
+        })))
+    } else {
+        Ok(ParseNode::Array(Box::new(res)))
+    }
+};
+
 /// Handler for aligned environments (align, align*, aligned, alignat, alignat*,
 /// alignedat, split)
 const ALIGNED_HANDLER: EnvHandler = |context, args, _opt_args| {
@@ -1078,7 +1195,7 @@ const ALIGNED_HANDLER: EnvHandler = |context, args, _opt_args| {
         ColSeparationType::Alignat
     });
 
-    Ok(ParseNode::Array(res))
+    Ok(ParseNode::Array(Box::new(res)))
 };
 
 /// Registers array environment in the KaTeX context
@@ -1154,7 +1271,7 @@ pub fn define_array(ctx: &mut KatexContext) {
                 d_cell_style(&context.env_name),
             )?;
 
-            Ok(ParseNode::Array(res))
+            Ok(ParseNode::Array(Box::new(res)))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1162,8 +1279,6 @@ pub fn define_array(ctx: &mut KatexContext) {
 
     // The matrix environments of amsmath builds on the array environment
     // of LaTeX, which is discussed above.
-    // The mathtools package adds starred versions of the same environments.
-    // These have an optional argument to choose left|center|right justification.
     ctx.define_environment(EnvDefSpec {
         node_type: NodeType::Array,
         names: vec![
@@ -1173,6 +1288,21 @@ pub fn define_array(ctx: &mut KatexContext) {
             "Bmatrix".to_owned(),
             "vmatrix".to_owned(),
             "Vmatrix".to_owned(),
+        ],
+        props: EnvProps {
+            num_args: Some(0),
+            ..Default::default()
+        },
+        handler: MATRIX_HANDLER,
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+
+    // The mathtools package adds starred versions of the same environments.
+    // These have an optional argument to choose left|center|right justification.
+    ctx.define_environment(EnvDefSpec {
+        node_type: NodeType::Array,
+        names: vec![
             "matrix*".to_owned(),
             "pmatrix*".to_owned(),
             "bmatrix*".to_owned(),
@@ -1182,90 +1312,11 @@ pub fn define_array(ctx: &mut KatexContext) {
         ],
         props: EnvProps {
             num_args: Some(0),
+            num_optional_args: Some(1),
+            arg_types: Some(vec![ArgType::Raw]),
             ..Default::default()
         },
-        handler: |context, _args, _opt_args| {
-            let delimiters = match context.env_name.as_str().trim_end_matches('*') {
-                "matrix" => None,
-                "pmatrix" => Some(("(".to_owned(), ")".to_owned())),
-                "bmatrix" => Some(("[".to_owned(), "]".to_owned())),
-                "Bmatrix" => Some(("\\{".to_owned(), "\\}".to_owned())),
-                "vmatrix" => Some(("|".to_owned(), "|".to_owned())),
-                "Vmatrix" => Some(("\\Vert".to_owned(), "\\Vert".to_owned())),
-                _ => unreachable!(),
-            };
-
-            // \hskip -\arraycolsep in amsmath
-            let mut col_align = "c".to_owned();
-            let payload = ArrayParseConfig {
-                hskip_before_and_after: Some(false),
-                cols: Some(vec![AlignSpec::Align {
-                    align: col_align.clone(),
-                    pregap: None,
-                    postgap: None,
-                }]),
-                ..Default::default()
-            };
-
-            let mut payload = payload;
-            if context.env_name.ends_with('*') {
-                // It's one of the mathtools starred functions.
-                // Parse the optional alignment argument.
-                context.parser.gullet.consume_spaces()?;
-                if context.parser.fetch()?.text == "[" {
-                    context.parser.consume();
-                    context.parser.gullet.consume_spaces()?;
-                    context.parser.fetch()?.text.clone_into(&mut col_align);
-                    if !["l", "c", "r"].contains(&col_align.as_str()) {
-                        return Err(ParseError::new(ParseErrorKind::ExpectedAlignmentSpecifier));
-                    }
-                    context.parser.consume();
-                    context.parser.gullet.consume_spaces()?;
-                    let next = context.parser.fetch()?;
-                    if next.text != "]" {
-                        return Err(ParseError::new(ParseErrorKind::ExpectedClosingBracket {
-                            found: next.text.to_owned_string(),
-                        }));
-                    }
-                    context.parser.consume();
-                    context.parser.consume();
-                    payload.cols = Some(vec![AlignSpec::Align {
-                        align: col_align.clone(),
-                        pregap: None,
-                        postgap: None,
-                    }]);
-                }
-            }
-
-            let mut res = parse_array(context.parser, payload, d_cell_style(&context.env_name))?;
-
-            // Populate cols with the correct number of column alignment specs.
-            let num_cols = res.body.iter().map(Vec::len).max().unwrap_or(0);
-            res.cols = Some(
-                repeat_n(
-                    AlignSpec::Align {
-                        align: col_align.clone(),
-                        pregap: None,
-                        postgap: None,
-                    },
-                    num_cols,
-                )
-                .collect(),
-            );
-
-            if let Some((left, right)) = delimiters {
-                Ok(ParseNode::LeftRight(ParseNodeLeftRight {
-                    mode: context.mode,
-                    loc: None,
-                    body: vec![ParseNode::Array(res)],
-                    left,
-                    right,
-                    right_color: None,
-                }))
-            } else {
-                Ok(ParseNode::Array(res))
-            }
-        },
+        handler: MATRIX_HANDLER,
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
     });
@@ -1285,7 +1336,7 @@ pub fn define_array(ctx: &mut KatexContext) {
             };
             let mut res = parse_array(context.parser, payload, SCRIPT)?;
             res.col_separation_type = Some(ColSeparationType::Small);
-            Ok(ParseNode::Array(res))
+            Ok(ParseNode::Array(Box::new(res)))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1358,7 +1409,7 @@ pub fn define_array(ctx: &mut KatexContext) {
                 }));
             }
 
-            Ok(ParseNode::Array(res))
+            Ok(ParseNode::Array(Box::new(res)))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1405,14 +1456,14 @@ pub fn define_array(ctx: &mut KatexContext) {
                 ("\\{".to_owned(), ".".to_owned())
             };
 
-            Ok(ParseNode::LeftRight(ParseNodeLeftRight {
+            Ok(ParseNode::LeftRight(Box::new(ParseNodeLeftRight {
                 mode: context.mode,
                 loc: None,
-                body: vec![ParseNode::Array(res)],
+                body: vec![ParseNode::Array(Box::new(res))],
                 left,
                 right,
                 right_color: None,
-            }))
+            })))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1453,7 +1504,7 @@ pub fn define_array(ctx: &mut KatexContext) {
                 DISPLAY,
             )?;
 
-            Ok(ParseNode::Array(res))
+            Ok(ParseNode::Array(Box::new(res)))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1518,7 +1569,7 @@ pub fn define_array(ctx: &mut KatexContext) {
                 DISPLAY,
             )?;
 
-            Ok(ParseNode::Array(res))
+            Ok(ParseNode::Array(Box::new(res)))
         },
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -1551,6 +1602,10 @@ pub fn define_array(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             allowed_in_math: true,
+            examples: &[
+                r"\begin{array}{c}\hline x\end{array}",
+                r"\begin{array}{c}\hdashline x\end{array}",
+            ],
             ..Default::default()
         },
         handler: Some(
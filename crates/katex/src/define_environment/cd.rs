@@ -68,26 +68,20 @@ fn is_label_end(node: &AnyParseNode, end_char: &str) -> bool {
 /// Create an arrow node with labels
 fn cd_arrow(
     arrow_char: &str,
-    labels: &[ParseNode],
+    labels: [ParseNode; 2],
     parser: &mut Parser,
 ) -> Result<AnyParseNode, ParseError> {
     let func_name = CD_ARROW_FUNCTION_NAMES
         .get(arrow_char)
         .unwrap_or(&"no arrow");
-
-    assert!(labels.len() >= 2);
+    let [above, below] = labels;
 
     match func_name {
-        &"\\\\cdrightarrow" | &"\\\\cdleftarrow" => parser.call_function(
-            func_name,
-            vec![labels[0].clone()],
-            vec![Some(labels[1].clone())],
-            None,
-            None,
-        ),
+        &"\\\\cdrightarrow" | &"\\\\cdleftarrow" => {
+            parser.call_function(func_name, vec![above], vec![Some(below)], None, None)
+        }
         &"\\uparrow" | &"\\downarrow" => {
-            let left_label =
-                parser.call_function("\\\\cdleft", vec![labels[0].clone()], vec![], None, None)?;
+            let left_label = parser.call_function("\\\\cdleft", vec![above], vec![], None, None)?;
             let bare_arrow = AnyParseNode::Atom(ParseNodeAtom {
                 family: Atom::Rel,
                 mode: Mode::Math,
@@ -97,7 +91,7 @@ fn cd_arrow(
             let sized_arrow =
                 parser.call_function("\\Big", vec![bare_arrow], vec![], None, None)?;
             let right_label =
-                parser.call_function("\\\\cdright", vec![labels[1].clone()], vec![], None, None)?;
+                parser.call_function("\\\\cdright", vec![below], vec![], None, None)?;
             let arrow_group = AnyParseNode::OrdGroup(ParseNodeOrdGroup {
                 mode: Mode::Math,
                 loc: None,
@@ -234,14 +228,7 @@ pub fn parse_cd(parser: &mut Parser) -> Result<AnyParseNode, ParseError> {
                 }
 
                 // Create arrow
-                let arrow = cd_arrow(
-                    arrow_char,
-                    &labels
-                        .iter()
-                        .map(|l| ParseNode::from(l.clone()))
-                        .collect::<Vec<_>>(),
-                    parser,
-                )?;
+                let arrow = cd_arrow(arrow_char, labels.map(ParseNode::from), parser)?;
 
                 // Wrap arrow in styling
                 let wrapped_arrow = ParseNode::Styling(ParseNodeStyling {
@@ -294,7 +281,7 @@ pub fn parse_cd(parser: &mut Parser) -> Result<AnyParseNode, ParseError> {
 
     let body_len = body.len();
 
-    Ok(AnyParseNode::Array(ParseNodeArray {
+    Ok(AnyParseNode::Array(Box::new(ParseNodeArray {
         mode: Mode::Math,
         loc: None,
         col_separation_type: Some(ColSeparationType::CD),
@@ -308,7 +295,7 @@ pub fn parse_cd(parser: &mut Parser) -> Result<AnyParseNode, ParseError> {
         tags: None,
         leqno: None,
         is_cd: Some(true),
-    }))
+    })))
 }
 
 /// Define CD-related functions in the KaTeX context
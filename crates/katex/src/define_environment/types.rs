@@ -6,6 +6,7 @@
 use crate::parser::Parser;
 use crate::parser::parse_node::AnyParseNode;
 use crate::parser::parse_node::NodeType;
+use crate::parser::parse_node::ParseNodeRaw;
 use crate::types::{ArgType, Mode, ParseError, ParseErrorKind};
 
 /// Context information passed to environment handlers during LaTeX environment
@@ -59,6 +60,14 @@ pub struct EnvContext<'a, 'b> {
     pub env_name: String,
     /// The parser instance used for processing the environment content.
     pub parser: &'a mut Parser<'b>,
+    /// The body of the environment as raw text, for environments that set
+    /// [`EnvProps::raw_body`].
+    ///
+    /// The text runs from the end of the arguments to the matching
+    /// `\end{env_name}`, which is left for the parser to consume after the
+    /// handler returns. It is `None` for all other environments, whose
+    /// handlers parse the body themselves.
+    pub raw_body: Option<ParseNodeRaw>,
 }
 
 /// Function type for handling LaTeX environment parsing and processing.
@@ -167,6 +176,20 @@ pub struct EnvProps {
     /// - `Some(1)`: One optional argument allowed
     /// - `None`: Use default (typically 0)
     pub num_optional_args: Option<usize>,
+
+    /// Whether the body is captured as raw text instead of being parsed.
+    ///
+    /// The body is taken character for character up to `\end{name}`,
+    /// without tokenizing or expanding macros, and handed to the handler as
+    /// [`EnvContext::raw_body`]. This suits verbatim-like environments and
+    /// bodies in a syntax of their own.
+    ///
+    /// # Examples
+    ///
+    /// - `Some(true)`: The body is raw text
+    /// - `Some(false)`: The handler parses the body
+    /// - `None`: Use default (false)
+    pub raw_body: Option<bool>,
 }
 
 /// Provides sensible defaults for environment properties.
@@ -181,6 +204,7 @@ impl Default for EnvProps {
             arg_types: None,
             allowed_in_text: Some(false),
             num_optional_args: Some(0),
+            raw_body: Some(false),
         }
     }
 }
@@ -255,6 +279,11 @@ pub struct EnvSpec {
     /// - `1`: One optional argument allowed
     pub num_optional_args: usize,
 
+    /// Whether the body is captured as raw text instead of being parsed.
+    ///
+    /// This is resolved from the optional `raw_body` in [`EnvProps`].
+    pub raw_body: bool,
+
     /// The handler function that processes this environment.
     ///
     /// This function is called during parsing to validate arguments
@@ -285,6 +314,7 @@ impl Default for EnvSpec {
             arg_types: None,
             allowed_in_text: false,
             num_optional_args: 0,
+            raw_body: false,
             handler: |_, _, _| {
                 Err(ParseError::new(
                     ParseErrorKind::EnvironmentHandlerNotImplemented,
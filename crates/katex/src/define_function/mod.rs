@@ -79,6 +79,10 @@ pub struct FunctionPropSpec {
 
     /// Whether the function is a TeX primitive
     pub primitive: bool,
+
+    /// Inputs using the function, which the test suite renders to HTML and
+    /// MathML to check its handler and builders
+    pub examples: &'static [&'static str],
 }
 
 /// Default implementation for [`FunctionPropSpec`].
@@ -93,6 +97,7 @@ impl Default for FunctionPropSpec {
             num_optional_args: 0,
             infix: false,
             primitive: false,
+            examples: &[],
         }
     }
 }
@@ -148,6 +153,9 @@ pub struct FunctionSpec {
     /// TeX primitive
     pub primitive: bool,
 
+    /// Example inputs
+    pub examples: &'static [&'static str],
+
     /// Handler function
     pub handler: Option<FunctionHandler>,
 }
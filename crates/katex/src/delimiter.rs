@@ -159,6 +159,17 @@ fn mathrm_size(
     mode: Mode,
     options: &Options,
 ) -> Result<SymbolNode, ParseError> {
+    if let Some(glyph) = ctx.symbols.get_sized_glyph(value, size) {
+        return make_symbol(
+            ctx,
+            &glyph.text,
+            &glyph.font,
+            mode,
+            Some(options),
+            ClassList::Owned(vec![Cow::Owned(glyph.class.clone())]),
+        );
+    }
+
     let font_name = format!("Size{size}-Regular");
     make_symbol(
         ctx,
@@ -816,8 +827,15 @@ fn traverse_sequence<'a>(
             break;
         }
 
-        let font = delim_type_to_font(delim_type);
-        let metrics = get_metrics(ctx, delim, &font, Mode::Math)?;
+        let sized_glyph = match delim_type {
+            DelimiterType::Large(size) => ctx.symbols.get_sized_glyph(delim, *size),
+            _ => None,
+        };
+        let metrics = if let Some(glyph) = sized_glyph {
+            get_metrics(ctx, &glyph.text, &glyph.font, Mode::Math)?
+        } else {
+            get_metrics(ctx, delim, &delim_type_to_font(delim_type), Mode::Math)?
+        };
         let mut height_depth = metrics.height + metrics.depth;
 
         // Small delimiters are scaled down versions of the same font, so we
@@ -834,7 +852,11 @@ fn traverse_sequence<'a>(
     }
 
     // If we reached the end of the sequence, return the last sequence element.
-    Ok(&sequence[sequence.len() - 1])
+    sequence.last().ok_or_else(|| {
+        ParseError::new(ParseErrorKind::IllegalDelimiter {
+            delim: delim.to_owned(),
+        })
+    })
 }
 
 /// Get the font used in a delimiter based on what kind of delimiter it is.
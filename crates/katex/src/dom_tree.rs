@@ -4,6 +4,7 @@
 //! extra data. They can then be transformed into real DOM nodes with the
 //! `to_node` function or HTML markup using `to_markup`.
 
+use alloc::collections::BTreeSet;
 use core::fmt::{self, Write as _};
 
 use crate::ParseError;
@@ -18,15 +19,16 @@ use wasm_bindgen::UnwrapThrowExt as _;
 #[cfg(feature = "wasm")]
 use web_sys;
 
-use crate::mathml_tree::MathNode;
+use crate::escape::escape_into;
+use crate::mathml_tree::{MathDomNode, MathNode};
 use crate::options::Options;
 use crate::svg_geometry::PATH_MAP;
-use crate::tree::{DocumentFragment, VirtualNode};
-use crate::types::ClassList;
+use crate::tree::{DocumentFragment, VirtualNode, write_markup_encoded};
+use crate::types::{Charset, ClassList};
 use crate::types::{CssProperty, CssStyle};
 use crate::unicode::script_from_codepoint;
 use crate::units::make_em;
-use crate::utils::escape_into;
+use crate::utils::with_stack;
 
 const EMPTY_CLASS_LIST: ClassList = ClassList::Empty;
 
@@ -51,9 +53,12 @@ pub struct Span<T> {
     pub style: CssStyle,
 
     /// For `src/functions/delimsizing.rs` only
-    pub is_middle: Option<(String, Options)>,
+    pub is_middle: Option<(String, Box<Options>)>,
     /// For `src/functions/op.rs` and `src/functions/supsub.rs` only
     pub italic: Option<f64>,
+    /// Encoding of the markup of this span and everything in it, set on the
+    /// root of a rendered tree from [`Settings::charset`](crate::Settings::charset)
+    pub charset: Charset,
 }
 
 #[bon]
@@ -83,7 +88,9 @@ impl<T> Span<T> {
         /// Inline CSS style object
         style: Option<CssStyle>,
         /// `is_middle` tuple
-        is_middle: Option<(String, Options)>,
+        is_middle: Option<(String, Box<Options>)>,
+        /// Encoding of the markup of this span and everything in it
+        charset: Option<Charset>,
     ) -> Self {
         let mut span = Self {
             children,
@@ -96,6 +103,7 @@ impl<T> Span<T> {
             style: style.unwrap_or_default(),
             is_middle,
             italic: None,
+            charset: charset.unwrap_or_default(),
         };
 
         if let Some(options) = options {
@@ -124,6 +132,7 @@ impl<T> Span<T> {
             style: style.unwrap_or_default(),
             is_middle: None,
             italic: None,
+            charset: Charset::Utf8,
         };
 
         if let Some(options) = options {
@@ -367,7 +376,7 @@ pub type DomSpan = Span<HtmlDomNode>;
 #[derive(Debug, Clone)]
 pub enum HtmlDomNode {
     /// Span wrapping other DOM nodes
-    DomSpan(Span<HtmlDomNode>),
+    DomSpan(Span<Self>),
     /// Anchor (`<a>`) element with hyperlink
     Anchor(Anchor),
     /// Image embed (`<img>`) element
@@ -498,6 +507,35 @@ pub fn to_markup(node: &HtmlDomNode) -> Result<String, ParseError> {
     node.to_markup()
 }
 
+/// Drops a built tree one node at a time.
+///
+/// A tree nests as deeply as the formula it was built from, and the drop the
+/// compiler generates recurses once per level. The render functions that
+/// only keep the markup of a tree free it through this instead.
+pub(crate) fn drop_tree(node: HtmlDomNode) {
+    let mut html = vec![node];
+    let mut math = Vec::new();
+    loop {
+        if let Some(node) = html.pop() {
+            match node {
+                HtmlDomNode::DomSpan(mut span) => html.append(&mut span.children),
+                HtmlDomNode::Anchor(mut anchor) => html.append(&mut anchor.children),
+                HtmlDomNode::Fragment(mut fragment) => html.append(&mut fragment.children),
+                HtmlDomNode::MathML(mut node) => math.append(&mut node.children),
+                HtmlDomNode::Img(_) | HtmlDomNode::Symbol(_) | HtmlDomNode::SvgNode(_) => {}
+            }
+        } else if let Some(node) = math.pop() {
+            match node {
+                MathDomNode::Math(mut node) => math.append(&mut node.children),
+                MathDomNode::Fragment(mut fragment) => math.append(&mut fragment.children),
+                MathDomNode::Text(_) | MathDomNode::Space(_) => {}
+            }
+        } else {
+            break;
+        }
+    }
+}
+
 fn map_fmt(result: fmt::Result) -> Result<(), ParseError> {
     result.map_err(ParseError::from)
 }
@@ -569,18 +607,10 @@ fn create_element_ns(ctx: &WebContext, ns: &str, name: &str) -> web_sys::Element
 /// Implement VirtualNode for `Span<T>`
 impl<T: VirtualNode> VirtualNode for Span<T> {
     fn write_markup(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), ParseError> {
-        map_fmt(fmt.write_str("<span"))?;
-        map_fmt(write_node_class(fmt, &self.classes))?;
-        map_fmt(write_node_style(fmt, &self.style))?;
-        node_attributes_to_markup(fmt, &self.attributes)?;
-        map_fmt(fmt.write_char('>'))?;
-
-        for child in &self.children {
-            child.write_markup(fmt)?;
+        if self.charset != Charset::Utf8 {
+            return write_markup_encoded(fmt, self.charset, |fmt| self.write_span_markup(fmt));
         }
-
-        map_fmt(fmt.write_str("</span>"))?;
-        Ok(())
+        self.write_span_markup(fmt)
     }
 
     #[cfg(feature = "wasm")]
@@ -608,6 +638,26 @@ impl<T: VirtualNode> VirtualNode for Span<T> {
     }
 }
 
+impl<T: VirtualNode> Span<T> {
+    /// Writes the markup of the span without encoding it
+    fn write_span_markup(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), ParseError> {
+        map_fmt(fmt.write_str("<span"))?;
+        map_fmt(write_node_class(fmt, &self.classes))?;
+        map_fmt(write_node_style(fmt, &self.style))?;
+        node_attributes_to_markup(fmt, &self.attributes)?;
+        map_fmt(fmt.write_char('>'))?;
+
+        with_stack(|| {
+            self.children
+                .iter()
+                .try_for_each(|child| child.write_markup(fmt))
+        })?;
+
+        map_fmt(fmt.write_str("</span>"))?;
+        Ok(())
+    }
+}
+
 /// Implement VirtualNode for Anchor
 impl VirtualNode for Anchor {
     fn write_markup(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), ParseError> {
@@ -831,6 +881,16 @@ impl VirtualNode for HtmlDomNode {
     }
 }
 
+/// Adds the classes in `classes` to `used`, splitting entries that hold
+/// several space-separated classes.
+pub(crate) fn insert_classes(classes: &ClassList, used: &mut BTreeSet<String>) {
+    for class in classes.iter().flat_map(str::split_whitespace) {
+        if !used.contains(class) {
+            used.insert(class.to_owned());
+        }
+    }
+}
+
 /// Helper methods for HtmlDomNode to maintain API compatibility
 ///
 /// These methods provide a unified interface for accessing properties of
@@ -871,6 +931,33 @@ impl HtmlDomNode {
         }
     }
 
+    /// Add every CSS class written out for this node and its descendants to
+    /// `used`.
+    ///
+    /// Fragments have no element of their own in the markup, so only their
+    /// children contribute. MathML children are included.
+    pub fn collect_classes(&self, used: &mut BTreeSet<String>) {
+        let children: &[Self] = match self {
+            Self::DomSpan(span) => &span.children,
+            Self::Anchor(anchor) => &anchor.children,
+            Self::Fragment(fragment) => {
+                for child in &fragment.children {
+                    child.collect_classes(used);
+                }
+                return;
+            }
+            Self::MathML(node) => {
+                node.collect_classes(used);
+                return;
+            }
+            Self::Img(_) | Self::Symbol(_) | Self::SvgNode(_) => &[],
+        };
+        insert_classes(self.classes(), used);
+        for child in children {
+            child.collect_classes(used);
+        }
+    }
+
     /// Get the height of this node
     ///
     /// Returns the vertical height above the baseline in em units.
@@ -0,0 +1,207 @@
+//! Escaping for HTML and MathML output
+//!
+//! Every text node and attribute value in the markup KaTeX produces goes
+//! through [`escape_into`]. Code that adds its own elements to the output,
+//! such as custom node builders or post-processors of the rendered string,
+//! should escape with the same functions so that its markup cannot diverge
+//! from the core's.
+//!
+//! The same five characters are replaced in every context: `&`, `<`, `>`,
+//! `"` and `'`. Escaping quotes in text content is redundant but harmless,
+//! and escaping `<` and `>` in attribute values keeps the output safe to
+//! embed in other markup. The context-specific functions, [`html_text`],
+//! [`html_attribute`] and [`mathml_text`], name the intent at the call site.
+//!
+//! ```rust
+//! use katex::escape;
+//!
+//! assert_eq!(escape::html_text("a < b & c"), "a &lt; b &amp; c");
+//! assert_eq!(escape::html_attribute(r#"say "hi""#), "say &quot;hi&quot;");
+//! assert_eq!(escape::mathml_text("x"), "x");
+//! ```
+//!
+//! The markup is encoded for [`Settings::charset`] as it is written: the
+//! root of a rendered tree carries the charset, and with [`Charset::Ascii`]
+//! everything inside it, text and attribute values alike, is written through
+//! a [`CharsetWriter`], which turns characters outside ASCII into numeric
+//! character references. [`encode_charset`] does the same for finished
+//! markup.
+//!
+//! [`Settings::charset`]: crate::Settings::charset
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::types::Charset;
+
+/// The entity that replaces `ch`, if `ch` must be escaped
+const fn replacement(ch: char) -> Option<&'static str> {
+    match ch {
+        '&' => Some("&amp;"),
+        '>' => Some("&gt;"),
+        '<' => Some("&lt;"),
+        '"' => Some("&quot;"),
+        '\'' => Some("&#x27;"),
+        _ => None,
+    }
+}
+
+/// Writes the escaped HTML representation of `text` into the provided writer.
+///
+/// This helper avoids intermediate string allocations by emitting directly
+/// into any `fmt::Write` implementor.
+///
+/// # Errors
+///
+/// Returns the error of the underlying writer.
+#[inline]
+pub fn escape_into<W: fmt::Write>(writer: &mut W, text: &str) -> fmt::Result {
+    let mut last = 0;
+    for (idx, ch) in text.char_indices() {
+        if let Some(rep) = replacement(ch) {
+            if last < idx {
+                writer.write_str(&text[last..idx])?;
+            }
+            writer.write_str(rep)?;
+            last = idx + ch.len_utf8();
+        }
+    }
+
+    if last < text.len() {
+        writer.write_str(&text[last..])
+    } else {
+        Ok(())
+    }
+}
+
+/// Escapes `text` for any HTML or MathML context, borrowing it when nothing
+/// needs to be replaced.
+#[must_use]
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|ch| replacement(ch).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + text.len() / 8);
+    // Writing to a `String` cannot fail
+    let _ = escape_into(&mut escaped, text);
+    Cow::Owned(escaped)
+}
+
+/// Escapes the text content of an HTML element.
+#[inline]
+#[must_use]
+pub fn html_text(text: &str) -> Cow<'_, str> {
+    escape(text)
+}
+
+/// Escapes an HTML attribute value for use between double quotes.
+#[inline]
+#[must_use]
+pub fn html_attribute(value: &str) -> Cow<'_, str> {
+    escape(value)
+}
+
+/// Escapes the text content of a MathML token element such as `<mi>`.
+#[inline]
+#[must_use]
+pub fn mathml_text(text: &str) -> Cow<'_, str> {
+    escape(text)
+}
+
+/// Writes `text` into `writer` encoded for `charset`.
+///
+/// With [`Charset::Ascii`], every character outside ASCII becomes a numeric
+/// character reference, e.g. `α` becomes `&#x3b1;`. Characters outside ASCII
+/// only occur in text and attribute values, where references mean the same
+/// character. With [`Charset::Utf8`], `text` is written unchanged.
+///
+/// # Errors
+///
+/// Returns the error of the underlying writer.
+pub fn encode_into<W: fmt::Write + ?Sized>(
+    writer: &mut W,
+    text: &str,
+    charset: Charset,
+) -> fmt::Result {
+    if charset == Charset::Utf8 || text.is_ascii() {
+        return writer.write_str(text);
+    }
+    let mut last = 0;
+    for (idx, ch) in text.char_indices() {
+        if !ch.is_ascii() {
+            writer.write_str(&text[last..idx])?;
+            write!(writer, "&#x{:x};", u32::from(ch))?;
+            last = idx + ch.len_utf8();
+        }
+    }
+    writer.write_str(&text[last..])
+}
+
+/// Encodes finished markup for `charset`, like [`encode_into`].
+#[must_use]
+pub fn encode_charset(markup: String, charset: Charset) -> String {
+    if charset == Charset::Utf8 || markup.is_ascii() {
+        return markup;
+    }
+    let mut encoded = String::with_capacity(markup.len() + markup.len() / 4);
+    // Writing to a `String` cannot fail
+    let _ = encode_into(&mut encoded, &markup, charset);
+    encoded
+}
+
+/// A writer that encodes everything written through it for a charset, see
+/// [`encode_into`]
+pub(crate) struct CharsetWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    charset: Charset,
+}
+
+impl<'a, W: fmt::Write + ?Sized> CharsetWriter<'a, W> {
+    pub(crate) const fn new(inner: &'a mut W, charset: Charset) -> Self {
+        Self { inner, charset }
+    }
+}
+
+impl<W: fmt::Write + ?Sized> fmt::Write for CharsetWriter<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        encode_into(self.inner, text, self.charset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_replaces_markup_characters() {
+        assert_eq!(
+            escape(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#x27;&lt;/a&gt;"
+        );
+        assert_eq!(escape("\u{3b1} + \u{2264}"), "\u{3b1} + \u{2264}");
+    }
+
+    #[test]
+    fn test_escape_borrows_clean_text() {
+        assert!(matches!(escape("x^2 + 1"), Cow::Borrowed(_)));
+        assert!(matches!(escape("a&b"), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_escape_matches_escape_into() {
+        let text = "f(x) < 'y' && \"z\" > 0";
+        let mut written = String::new();
+        escape_into(&mut written, text).unwrap();
+        assert_eq!(escape(text), written);
+    }
+
+    #[test]
+    fn test_encode_charset_writes_references_for_ascii() {
+        let markup = "<mi>\u{3b1}</mi><mo>\u{2264}</mo><mi>\u{1d400}</mi>".to_owned();
+        assert_eq!(
+            encode_charset(markup.clone(), Charset::Ascii),
+            "<mi>&#x3b1;</mi><mo>&#x2264;</mo><mi>&#x1d400;</mi>"
+        );
+        assert_eq!(encode_charset(markup.clone(), Charset::Utf8), markup);
+    }
+}
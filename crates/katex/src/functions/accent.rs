@@ -114,6 +114,15 @@ pub fn define_accent(ctx: &mut KatexContext) {
             allowed_in_text: true,
             allowed_in_math: true,
             arg_types: Some(vec![ArgType::Primitive]),
+            examples: &[
+                r"\text{\'a}",
+                r"\text{\`a}",
+                r"\text{\^a}",
+                r"\text{\~a}",
+                r"\text{\=a}",
+                r"\text{\.a}",
+                r#"\text{\"a}"#,
+            ],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -352,8 +361,8 @@ pub fn html_builder(
         // Here, we replace the "base" child of the supsub with our newly
         // generated accent.
         if let HtmlDomNode::DomSpan(span) = &mut supsub_group {
-            if !span.children.is_empty() {
-                span.children[0] = accent_wrap;
+            if let Some(first) = span.children.first_mut() {
+                *first = accent_wrap;
             }
             // Since we don't rerun the height calculation after replacing the
             // accent, we manually recalculate height.
@@ -57,6 +57,7 @@ pub fn define_arrow(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             num_optional_args: 1,
+            examples: &[r"\xrightarrow{f}", r"\xleftarrow[g]{f}"],
             ..Default::default()
         },
         handler: Some(|context, args, opt_args| {
@@ -17,6 +17,7 @@ pub fn define_char(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             allowed_in_text: true,
+            examples: &[r"\@char{65}"],
             ..Default::default()
         },
         handler: Some(
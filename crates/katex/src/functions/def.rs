@@ -50,6 +50,7 @@ fn define_global(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 0,
             allowed_in_text: true,
+            examples: &[r"\global\def\f{x}\f", r"\long\def\f{x}\f"],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
@@ -92,6 +93,13 @@ fn define_def_cmd(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             primitive: true,
+            examples: &[
+                r"\def\f{x}\f",
+                r"\gdef\f{x}\f",
+                r"\edef\f{x}\f",
+                r"\xdef\f{x}\f",
+                r"\def\f#1{#1^2}\f{y}",
+            ],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
@@ -112,13 +120,13 @@ fn define_def_cmd(ctx: &mut KatexContext) {
             let mut insert: Option<Token> = None;
 
             loop {
-                let next_text = context.parser.gullet.future_mut()?.text.to_owned_string();
+                let next_text = context.parser.gullet.peek_n(0)?.text.to_owned_string();
                 if next_text == "{" {
                     break;
                 }
                 let tok = context.parser.gullet.pop_token()?;
                 if tok.text == "#" {
-                    if context.parser.gullet.future_mut()?.text == "{" {
+                    if context.parser.gullet.peek_n(0)?.text == "{" {
                         insert = Some(context.parser.gullet.future_mut()?);
                         delimiters[num_args].push("{".to_owned());
                         break;
@@ -211,6 +219,7 @@ fn define_let_cmd(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             primitive: true,
+            examples: &[r"\let\f=x\f", r"\global\let\f=x\f"],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
@@ -284,6 +293,7 @@ fn define_futurelet_cmd(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             primitive: true,
+            examples: &[r"\futurelet\f a b"],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
@@ -299,8 +309,8 @@ fn define_futurelet_cmd(ctx: &mut KatexContext) {
                 ));
             }
 
-            let middle_tok = context.parser.gullet.pop_token()?;
-            let tok = context.parser.gullet.pop_token()?;
+            // The token after the next one, both of which stay in the stream
+            let tok = context.parser.gullet.peek_n(1)?.clone();
 
             let global = context.func_name == "\\\\globalfuture";
 
@@ -325,9 +335,6 @@ fn define_futurelet_cmd(ctx: &mut KatexContext) {
                 .macros_mut()
                 .set(&name, Some(macro_def), global);
 
-            context.parser.gullet.push_token(tok);
-            context.parser.gullet.push_token(middle_tok);
-
             Ok(ParseNode::Internal(ParseNodeInternal {
                 mode: context.parser.mode,
                 loc: context.loc(),
@@ -99,7 +99,11 @@ pub fn define_delimsizing(ctx: &mut KatexContext) {
         handler: Some(|context, args, _opt_args| {
             let delim_text = check_delimiter(args.first(), &context)?;
 
-            let (mclass, size) = DELIMITER_SIZES[context.func_name];
+            let &(mclass, size) = DELIMITER_SIZES.get(context.func_name).ok_or_else(|| {
+                ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                })
+            })?;
 
             Ok(ParseNode::Delimsizing(ParseNodeDelimsizing {
                 mode: context.parser.mode,
@@ -123,6 +127,7 @@ pub fn define_leftright(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             primitive: true,
+            examples: &[r"\left(x\right)", r"\left.\frac{a}{b}\right|"],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -141,14 +146,14 @@ pub fn define_leftright(ctx: &mut KatexContext) {
                 return Err(ParseError::new(ParseErrorKind::ExpectedRightAfterLeft));
             };
 
-            Ok(ParseNode::LeftRight(ParseNodeLeftRight {
+            Ok(ParseNode::LeftRight(Box::new(ParseNodeLeftRight {
                 mode: parser.mode,
                 loc,
                 body,
                 left: delim_text,
                 right: right_node.delim.clone(),
                 right_color: right_node.color,
-            }))
+            })))
         }),
         html_builder: Some(leftright_html_builder),
         mathml_builder: Some(leftright_mathml_builder),
@@ -161,6 +166,7 @@ pub fn define_leftright(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             primitive: true,
+            examples: &[r"\left(x\right)", r"\left[x\right."],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -197,6 +203,7 @@ pub fn define_middle(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             primitive: true,
+            examples: &[r"\left(x\middle|x\right)"],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -459,7 +466,7 @@ fn middle_html_builder(
     };
 
     // Mark as middle delimiter
-    middle_delim.is_middle = Some((group.delim.clone(), options.clone()));
+    middle_delim.is_middle = Some((group.delim.clone(), Box::new(options.clone())));
 
     Ok(middle_delim.into())
 }
@@ -0,0 +1,189 @@
+//! Diagbox function implementation for KaTeX Rust
+//!
+//! This module handles the `\diagbox` command from the LaTeX diagbox
+//! package, which splits a box with a diagonal rule into two labelled halves,
+//! e.g. `\diagbox{x}{y}` in the top left cell of a table of values.
+
+use crate::build_common::{VListElemAndShift, VListParam, make_span, make_v_list};
+use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
+use crate::dom_tree::HtmlDomNode;
+use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
+use crate::options::Options;
+use crate::parser::parse_node::{NodeType, ParseNode, ParseNodeDiagbox};
+use crate::spacing_data::Measurement;
+use crate::stretchy::{Diagonal, diagonal_rule_span};
+use crate::types::{ClassList, CssProperty, CssStyle, ParseError, ParseErrorKind};
+use crate::{KatexContext, build_html, build_mathml};
+
+/// Space above and below the labels
+const PAD: f64 = 0.1;
+
+/// Space between the two labels, vertically
+const GAP: f64 = 0.2;
+
+/// Builds a row holding `label` and an invisible copy of `other`, so both
+/// rows are as wide as the two labels side by side and the labels never
+/// cross the rule.
+fn label_row(
+    ctx: &KatexContext,
+    label: &ParseNode,
+    other: &ParseNode,
+    label_first: bool,
+    options: &Options,
+) -> Result<HtmlDomNode, ParseError> {
+    let label = build_html::build_group(ctx, label, options, None)?;
+    let other = build_html::build_group(ctx, other, &options.with_phantom(), None)?;
+    let glue = ctx.make_glue(
+        &Measurement {
+            number: 0.5,
+            unit: "em",
+        },
+        options,
+    )?;
+
+    let (height, depth) = (label.height(), label.depth());
+    let children = if label_first {
+        vec![label, glue.into(), other]
+    } else {
+        vec![other, glue.into(), label]
+    };
+    let mut row = make_span(ClassList::Empty, children, None, None);
+    row.height = height;
+    row.depth = depth;
+    Ok(row.into())
+}
+
+/// HTML builder for diagbox nodes
+///
+/// The upper label sits in the top right corner and the lower label in the
+/// bottom left one. On its own the box is centered on the math axis; when
+/// [`Options::cell_box`] is set, it fills the cell of the array instead.
+fn html_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<HtmlDomNode, ParseError> {
+    let ParseNode::Diagbox(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Diagbox,
+        }));
+    };
+
+    // The cell box is only meant for the diagbox filling the cell, not for
+    // anything nested in its labels
+    let cell_box = options.cell_box;
+    let options = options.with_cell_box(None);
+    let upper = label_row(ctx, &group.upper, &group.lower, false, &options)?;
+    let lower = label_row(ctx, &group.lower, &group.upper, true, &options)?;
+
+    let total = 2.0f64.mul_add(
+        PAD,
+        upper.height() + upper.depth() + GAP + lower.height() + lower.depth(),
+    );
+    let natural_depth = total / 2.0 - options.font_metrics().axis_height;
+    let (height, depth) = cell_box.map_or((total - natural_depth, natural_depth), |cell| {
+        let scale = cell.size_multiplier / options.size_multiplier;
+        (cell.height * scale, cell.depth * scale)
+    });
+
+    let upper_shift = -(height - PAD - upper.height());
+    let lower_shift = depth - PAD - lower.depth();
+    let rule = diagonal_rule_span(&[Diagonal::Falling], height + depth, &options);
+
+    let aligned = |align: &str| {
+        let mut style = CssStyle::default();
+        style.insert(CssProperty::TextAlign, align.to_owned());
+        style
+    };
+    let mut vlist = make_v_list(
+        VListParam::IndividualShift {
+            children: vec![
+                VListElemAndShift::builder()
+                    .elem(rule.into())
+                    .shift(depth)
+                    .wrapper_classes(ClassList::Static("svg-align"))
+                    .build(),
+                VListElemAndShift::builder()
+                    .elem(upper)
+                    .shift(upper_shift)
+                    .wrapper_style(aligned("right"))
+                    .build(),
+                VListElemAndShift::builder()
+                    .elem(lower)
+                    .shift(lower_shift)
+                    .wrapper_style(aligned("left"))
+                    .build(),
+            ],
+        },
+        &options,
+    )?;
+
+    let mut span = make_span("mord", vec![], Some(&options), None);
+    if cell_box.is_some() {
+        // Stretch to the width of the column
+        vlist.style.insert(CssProperty::Width, "100%".to_owned());
+        span.style.insert(CssProperty::Width, "100%".to_owned());
+    }
+    span.height = vlist.height;
+    span.depth = vlist.depth;
+    span.children.push(vlist.into());
+    Ok(span.into())
+}
+
+/// MathML builder for diagbox nodes
+fn mathml_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<MathDomNode, ParseError> {
+    let ParseNode::Diagbox(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Diagbox,
+        }));
+    };
+
+    let row = |label: &ParseNode, align: &str| -> Result<MathDomNode, ParseError> {
+        let mut cell = MathNode::with_children(
+            MathNodeType::Mtd,
+            vec![build_mathml::build_group(ctx, label, options)?],
+        );
+        cell.set_attribute("columnalign", align);
+        Ok(MathNode::with_children(MathNodeType::Mtr, vec![cell.into()]).into())
+    };
+
+    let table = MathNode::with_children(
+        MathNodeType::Mtable,
+        vec![row(&group.upper, "right")?, row(&group.lower, "left")?],
+    );
+    let mut menclose = MathNode::with_children(MathNodeType::Menclose, vec![table.into()]);
+    menclose.set_attribute("notation", "downdiagonalstrike");
+    Ok(menclose.into())
+}
+
+/// Registers the \diagbox function in the KaTeX context
+pub fn define_diagbox(ctx: &mut KatexContext) {
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Diagbox),
+        names: &["\\diagbox"],
+        props: FunctionPropSpec {
+            num_args: 2,
+            ..Default::default()
+        },
+        handler: Some(|context, args, _opt_args| {
+            let [lower, upper] = args.try_into().map_err(|_| {
+                ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                })
+            })?;
+
+            Ok(ParseNode::Diagbox(Box::new(ParseNodeDiagbox {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                lower: Box::new(lower),
+                upper: Box::new(upper),
+            })))
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+}
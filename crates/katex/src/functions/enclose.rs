@@ -8,10 +8,12 @@ use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
 use crate::dom_tree::{HtmlDomNode, PathNode, SvgChildNode, SvgNode};
 use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
 use crate::options::Options;
-use crate::parser::parse_node::{AnyParseNode, NodeType, ParseNode, ParseNodeEnclose};
+use crate::parser::parse_node::{
+    AnyParseNode, NodeType, ParseNode, ParseNodeEnclose, ParseNodeOrdGroup,
+};
 use crate::spacing_data::Measurement;
 use crate::stretchy::enclose_span;
-use crate::svg_geometry::phase_path;
+use crate::svg_geometry::{longdiv_path, phase_path};
 use crate::types::ClassList;
 use crate::types::{ArgType, CssProperty, Mode, ParseError, ParseErrorKind};
 use crate::units::make_em as units_make_em;
@@ -42,14 +44,14 @@ pub fn define_enclose(ctx: &mut KatexContext) {
 
             let body = args[1].clone();
 
-            Ok(ParseNode::Enclose(ParseNodeEnclose {
+            Ok(ParseNode::Enclose(Box::new(ParseNodeEnclose {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 label: context.func_name.to_owned(),
                 background_color: Some(color.to_string()),
                 border_color: None,
                 body: Box::new(body),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -90,14 +92,14 @@ pub fn define_enclose(ctx: &mut KatexContext) {
 
             let body = args[2].clone();
 
-            Ok(ParseNode::Enclose(ParseNodeEnclose {
+            Ok(ParseNode::Enclose(Box::new(ParseNodeEnclose {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 label: context.func_name.to_owned(),
                 background_color: Some(background_color.to_string()),
                 border_color: Some(border_color.to_string()),
                 body: Box::new(body),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -116,14 +118,14 @@ pub fn define_enclose(ctx: &mut KatexContext) {
         handler: Some(|context, args, _opt_args| {
             let body = args[0].clone();
 
-            Ok(ParseNode::Enclose(ParseNodeEnclose {
+            Ok(ParseNode::Enclose(Box::new(ParseNodeEnclose {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 label: context.func_name.to_owned(),
                 background_color: None,
                 border_color: None,
                 body: Box::new(body),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -146,14 +148,14 @@ pub fn define_enclose(ctx: &mut KatexContext) {
 
             let body = args[0].clone();
 
-            Ok(ParseNode::Enclose(ParseNodeEnclose {
+            Ok(ParseNode::Enclose(Box::new(ParseNodeEnclose {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 label: context.func_name.to_owned(),
                 background_color: None,
                 border_color: None,
                 body: Box::new(body),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -172,13 +174,49 @@ pub fn define_enclose(ctx: &mut KatexContext) {
         handler: Some(|context, args, _opt_args| {
             let body = args[0].clone();
 
-            Ok(ParseNode::Enclose(ParseNodeEnclose {
+            Ok(ParseNode::Enclose(Box::new(ParseNodeEnclose {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 label: context.func_name.to_owned(),
                 background_color: None,
                 border_color: None,
                 body: Box::new(body),
+            })))
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+
+    // \longdiv{dividend}{divisor}: the divisor, then the dividend under the
+    // long division bracket
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Enclose),
+        names: &["\\longdiv"],
+        props: FunctionPropSpec {
+            num_args: 2,
+            ..Default::default()
+        },
+        handler: Some(|context, args, _opt_args| {
+            let [dividend, divisor] = args.try_into().map_err(|_| {
+                ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                })
+            })?;
+
+            let bracket = ParseNode::Enclose(Box::new(ParseNodeEnclose {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                label: context.func_name.to_owned(),
+                background_color: None,
+                border_color: None,
+                body: Box::new(dividend),
+            }));
+
+            Ok(ParseNode::OrdGroup(ParseNodeOrdGroup {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                body: vec![divisor, bracket],
+                semisimple: None,
             }))
         }),
         html_builder: Some(html_builder),
@@ -324,6 +362,79 @@ fn html_builder(
         return Ok(make_span("mord", vec![vlist.into()], None, None).into());
     }
 
+    if label == "longdiv" {
+        let line_weight = options
+            .font_metrics()
+            .default_rule_thickness
+            .max(options.min_rule_thickness);
+        let clearance = 3.0 * line_weight;
+        let bulge = 0.2;
+
+        // Prevent size changes
+        let new_options = options.having_base_sizing();
+        let scale = scale / new_options.size_multiplier;
+
+        let bracket_height = inner.height() + inner.depth() + clearance + line_weight;
+        if let Some(style) = inner.style_mut() {
+            style.insert(
+                CssProperty::PaddingLeft,
+                units_make_em(bulge + line_weight + 0.1),
+            );
+        }
+
+        // Create SVG
+        let view_box_height = 1000.0 * bracket_height * scale;
+        let path = longdiv_path(
+            view_box_height,
+            1000.0 * bulge * scale,
+            1000.0 * line_weight * scale,
+        );
+        let mut svg_node = SvgNode::builder()
+            .children(vec![SvgChildNode::Path(PathNode {
+                path_name: "longdiv".to_owned(),
+                alternate: Some(path),
+            })])
+            .build();
+
+        svg_node.attributes.extend([
+            ("width".to_owned(), "400em".to_owned()),
+            ("height".to_owned(), units_make_em(view_box_height / 1000.0)),
+            (
+                "viewBox".to_owned(),
+                format!("0 0 400000 {view_box_height}"),
+            ),
+            (
+                "preserveAspectRatio".to_owned(),
+                "xMinYMin slice".to_owned(),
+            ),
+        ]);
+
+        let mut img = build_common::make_svg_span("hide-tail", vec![svg_node], options);
+        img.style
+            .insert(CssProperty::Height, units_make_em(bracket_height));
+        // The bracket rises above the dividend, so it counts towards the
+        // height of the vlist
+        img.height = bracket_height;
+        img_shift = inner.depth();
+
+        // Create the vlist
+        let vlist = make_v_list(
+            VListParam::IndividualShift {
+                children: vec![
+                    VListElemAndShift::builder().elem(inner).shift(0.0).build(),
+                    VListElemAndShift::builder()
+                        .elem(img.into())
+                        .shift(img_shift)
+                        .wrapper_classes(ClassList::Static("svg-align"))
+                        .build(),
+                ],
+            },
+            options,
+        )?;
+
+        return Ok(make_span("mord", vec![vlist.into()], None, None).into());
+    }
+
     // Handle other enclosures (cancel, box, angl)
     let top_pad;
     let bottom_pad;
@@ -500,6 +611,9 @@ fn mathml_builder(
         "\\phase" => {
             math_node.set_attribute("notation", "phasorangle");
         }
+        "\\longdiv" => {
+            math_node.set_attribute("notation", "longdiv");
+        }
         "\\sout" => {
             math_node.set_attribute("notation", "horizontalstrike");
         }
@@ -21,6 +21,7 @@ pub fn define_environment(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             arg_types: Some(vec![ArgType::Mode(Mode::Text)]),
+            examples: &[r"\begin{matrix}x\end{matrix}"],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -67,10 +68,16 @@ pub fn define_environment(ctx: &mut KatexContext) {
             // be made available to the begin and end methods using properties.
             let (args, opt_args) =
                 parser.parse_arguments(&format!("\\begin{{{env_name}}}"), env_spec.as_ref())?;
+            let raw_body = if env_spec.raw_body {
+                Some(parser.parse_raw_until(&format!("\\end{{{env_name}}}"))?)
+            } else {
+                None
+            };
             let env_context = EnvContext {
                 mode: parser.mode,
                 parser,
                 env_name: env_name.clone(),
+                raw_body,
             };
             let result = (env_spec.handler)(env_context, args, opt_args)?;
             parser.expect("\\end", false)?;
@@ -59,6 +59,19 @@ const fn adjust_style<'a>(size: Option<&'a Style>, original_style: &'a Style) ->
     style
 }
 
+/// Moves the numerator and denominator out of the arguments of `\frac` and
+/// friends.
+///
+/// Fractions nest as deeply as the input, so cloning them here would copy,
+/// and recurse through, every fraction inside them again.
+fn take_fraction_args(args: Vec<ParseNode>) -> Result<(ParseNode, ParseNode), ParseError> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(numer), Some(denom)) => Ok((numer, denom)),
+        _ => Err(ParseError::new(ParseErrorKind::NullArgument)),
+    }
+}
+
 /// Infix generalized fractions -- these are not rendered directly, but replaced
 /// immediately by one of the variants above.
 pub fn define_genfrac(ctx: &mut crate::KatexContext) {
@@ -82,8 +95,7 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
-            let numer = args[0].clone();
-            let denom = args[1].clone();
+            let (numer, denom) = take_fraction_args(args)?;
 
             let (left_delim, right_delim, has_bar_line) = match context.func_name {
                 "\\dfrac" | "\\frac" | "\\tfrac" => (None, None, true),
@@ -133,8 +145,7 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
-            let numer = args[0].clone();
-            let denom = args[1].clone();
+            let (numer, denom) = take_fraction_args(args)?;
 
             Ok(ParseNode::Genfrac(Box::new(ParseNodeGenfrac {
                 mode: context.parser.mode,
@@ -163,13 +174,13 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
         },
         handler: Some(|context, _args, _opt_args| {
             if let Some(replace_with) = INFIX_REPLACE_MAP.get(context.func_name) {
-                Ok(ParseNode::Infix(ParseNodeInfix {
+                Ok(ParseNode::Infix(Box::new(ParseNodeInfix {
                     mode: context.parser.mode,
                     loc: context.loc(),
                     replace_with: (*replace_with).to_owned(),
                     size: None,
                     token: None,
-                }))
+                })))
             } else {
                 let kind = ParseErrorKind::UnrecognizedInfixGenfracCommand {
                     command: context.func_name.to_owned(),
@@ -193,6 +204,7 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
             num_args: 1,
             arg_types: Some(vec![ArgType::Size]),
             infix: true,
+            examples: &[r"a \above 1pt b"],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -203,13 +215,13 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
             } else {
                 return Err(ParseError::new(ParseErrorKind::AboveArgumentMustBeSize));
             };
-            Ok(ParseNode::Infix(ParseNodeInfix {
+            Ok(ParseNode::Infix(Box::new(ParseNodeInfix {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 replace_with: "\\\\abovefrac".to_owned(),
                 size: size_measurement,
                 token: None,
-            }))
+            })))
         }),
         html_builder: None,
         mathml_builder: None,
@@ -272,6 +284,7 @@ pub fn define_genfrac(ctx: &mut crate::KatexContext) {
                 ArgType::Mode(Mode::Math),
                 ArgType::Mode(Mode::Math),
             ]),
+            examples: &[r"\genfrac(){1pt}{0}{a}{b}", r"\genfrac{}{}{0pt}{}{a}{b}"],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
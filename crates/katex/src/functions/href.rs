@@ -183,14 +183,8 @@ fn mathml_builder(
     }
 
     // If there's only one element and it's already a MathNode, use it directly
-    let math_node = if body_elements.len() == 1 {
-        match &body_elements[0] {
-            MathDomNode::Math(math) => math.clone(),
-            _ => MathNode::builder()
-                .node_type(MathNodeType::Mrow)
-                .children(body_elements)
-                .build(),
-        }
+    let math_node = if let [MathDomNode::Math(math)] = body_elements.as_slice() {
+        math.clone()
     } else {
         MathNode::builder()
             .node_type(MathNodeType::Mrow)
@@ -26,6 +26,12 @@ pub fn define_html(ctx: &mut crate::KatexContext) {
             num_args: 2,
             arg_types: Some(vec![ArgType::Raw, ArgType::Original]),
             allowed_in_text: true,
+            examples: &[
+                r"\htmlClass{foo}{x}",
+                r"\htmlId{bar}{x}",
+                r"\htmlStyle{color: red}{x}",
+                r"\htmlData{foo=x}{x}",
+            ],
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
@@ -80,13 +86,13 @@ pub fn define_html(ctx: &mut crate::KatexContext) {
                     let data_parts: Vec<&str> = value.split(',').collect();
                     for part in data_parts {
                         let key_val: Vec<&str> = part.split('=').collect();
-                        if key_val.len() != 2 {
+                        let [key, val] = key_val.as_slice() else {
                             return Err(ParseError::new(
                                 ParseErrorKind::HtmlDataKeyValueParseError,
                             ));
-                        }
-                        let key = format!("data-{}", key_val[0].trim());
-                        let val = key_val[1].trim().to_owned();
+                        };
+                        let key = format!("data-{}", key.trim());
+                        let val = val.trim().to_owned();
                         attributes.insert(key, val);
                     }
                     TrustContext {
@@ -107,12 +113,12 @@ pub fn define_html(ctx: &mut crate::KatexContext) {
                     .into());
             }
 
-            Ok(ParseNode::Html(ParseNodeHtml {
+            Ok(ParseNode::Html(Box::new(ParseNodeHtml {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 attributes,
                 body: ord_argument(&body),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
@@ -273,6 +273,7 @@ pub fn define_includegraphics(ctx: &mut KatexContext) {
             num_optional_args: 1,
             arg_types: Some(vec![ArgType::Raw, ArgType::Url]),
             allowed_in_text: false,
+            examples: &[r"\includegraphics[height=1em]{https://katex.org/img/khan-academy.png}"],
             ..Default::default()
         },
         handler: Some(
@@ -301,9 +302,9 @@ pub fn define_includegraphics(ctx: &mut KatexContext) {
 
                     for attribute in attributes {
                         let key_val: Vec<&str> = attribute.split('=').collect();
-                        if key_val.len() == 2 {
-                            let key = key_val[0].trim();
-                            let value = key_val[1].trim();
+                        if let [key, value] = key_val.as_slice() {
+                            let key = key.trim();
+                            let value = value.trim();
 
                             match key {
                                 "alt" => {
@@ -354,11 +355,7 @@ pub fn define_includegraphics(ctx: &mut KatexContext) {
                 let mut trust_ctx = TrustContext {
                     command: "\\includegraphics".to_owned(),
                     url: Some(src.clone()),
-                    protocol: None,
-                    class: None,
-                    id: None,
-                    style: None,
-                    attributes: None,
+                    ..Default::default()
                 };
 
                 // Check if the command is trusted
@@ -370,15 +367,17 @@ pub fn define_includegraphics(ctx: &mut KatexContext) {
                     ));
                 }
 
-                Ok(ParseNode::Includegraphics(ParseNodeIncludegraphics {
-                    mode: context.parser.mode,
-                    loc: context.loc(),
-                    alt,
-                    width,
-                    height,
-                    total_height,
-                    src,
-                }))
+                Ok(ParseNode::Includegraphics(Box::new(
+                    ParseNodeIncludegraphics {
+                        mode: context.parser.mode,
+                        loc: context.loc(),
+                        alt,
+                        width,
+                        height,
+                        total_height,
+                        src,
+                    },
+                )))
             },
         ),
         html_builder: Some(html_builder),
@@ -34,7 +34,11 @@ pub fn define_lap(ctx: &mut KatexContext) {
                 "\\mathllap" => LapAlignment::Left,
                 "\\mathrlap" => LapAlignment::Right,
                 "\\mathclap" => LapAlignment::Center,
-                _ => unreachable!(),
+                name => {
+                    return Err(ParseError::new(ParseErrorKind::UnhandledCommand {
+                        name: name.to_owned(),
+                    }));
+                }
             };
 
             Ok(ParseNode::Lap(ParseNodeLap {
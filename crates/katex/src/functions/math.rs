@@ -26,6 +26,7 @@ fn define_math_open(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             allowed_in_math: false,
+            examples: &[r"\text{$x$}", r"\text{\(x\)}"],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
@@ -68,6 +69,7 @@ fn define_math_close(ctx: &mut KatexContext) {
             num_args: 0,
             allowed_in_text: true,
             allowed_in_math: false,
+            examples: &[r"\text{\(x\)}"],
             ..Default::default()
         },
         handler: Some(|context, _args, _opt_args| {
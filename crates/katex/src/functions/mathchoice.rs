@@ -43,14 +43,14 @@ pub fn define_mathchoice(ctx: &mut crate::KatexContext) {
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
-            Ok(ParseNode::MathChoice(ParseNodeMathChoice {
+            Ok(ParseNode::MathChoice(Box::new(ParseNodeMathChoice {
                 mode: context.parser.mode,
                 loc: context.loc(),
                 display: ord_argument(&args[0]),
                 text: ord_argument(&args[1]),
                 script: ord_argument(&args[2]),
                 scriptscript: ord_argument(&args[3]),
-            }))
+            })))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
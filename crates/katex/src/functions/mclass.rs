@@ -7,7 +7,7 @@ use crate::build_html::DomType;
 use crate::namespace::KeyMap;
 
 use crate::build_common::make_span;
-use crate::define_function::{FunctionDefSpec, FunctionPropSpec, ord_argument};
+use crate::define_function::{FunctionContext, FunctionDefSpec, FunctionPropSpec, ord_argument};
 use crate::dom_tree::HtmlDomNode;
 use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
 use crate::options::Options;
@@ -20,8 +20,11 @@ use crate::{KatexContext, build_html, build_mathml};
 
 /// Determines the math class for binrel spacing based on the argument node.
 ///
-/// This function analyzes the type of the argument node and returns the
-/// appropriate math class ("mbin", "mrel", or "mord") for spacing purposes.
+/// This mirrors amsmath's `\binrel@`: the result is [`DomType::Mbin`] or
+/// [`DomType::Mrel`] when the argument (or the first element of an ordgroup
+/// argument) is a binary operator or relation atom, and [`DomType::Mord`]
+/// otherwise. `\overset`, `\underset`, `\pmb` and `\boldsymbol` use it to
+/// keep the spacing of the symbol they decorate.
 ///
 /// # Arguments
 ///
@@ -29,14 +32,15 @@ use crate::{KatexContext, build_html, build_mathml};
 ///
 /// # Returns
 ///
-/// The math class string ("mbin", "mrel", or "mord")
+/// The class to give the wrapping node, see [`DomType`]
+#[must_use]
 pub fn binrel_class(arg: &AnyParseNode) -> DomType {
     // \binrel@ spacing varies with (bin|rel|ord) of the atom in the argument.
     // (by rendering separately and with {}s before and after, and measuring
     // the change in spacing).  We'll do roughly the same by detecting the
     // atom type directly.
     let atom = match arg {
-        AnyParseNode::OrdGroup(ord) if !ord.body.is_empty() => &ord.body[0],
+        AnyParseNode::OrdGroup(ord) => ord.body.first().unwrap_or(arg),
         _ => arg,
     };
 
@@ -93,42 +97,24 @@ fn mathml_builder(
             .build()
             .into()
     } else if mclass_node.mclass == DomType::Mord {
-        if mclass_node.is_character_box {
+        match inner.first() {
             // Use the first inner element directly
-            if inner.is_empty() {
-                MathNode::builder()
-                    .node_type(MathNodeType::Mi)
-                    .children(inner)
-                    .build()
-                    .into()
-            } else {
-                inner[0].clone()
-            }
-        } else {
-            MathNode::builder()
+            Some(first) if mclass_node.is_character_box => first.clone(),
+            _ => MathNode::builder()
                 .node_type(MathNodeType::Mi)
                 .children(inner)
                 .build()
-                .into()
+                .into(),
         }
     } else {
-        let mut node = if mclass_node.is_character_box {
+        let mut node = match inner.first() {
             // Use the first inner element directly
-            if inner.is_empty() {
-                MathNode::builder()
-                    .node_type(MathNodeType::Mo)
-                    .children(inner)
-                    .build()
-                    .into()
-            } else {
-                inner[0].clone()
-            }
-        } else {
-            MathNode::builder()
+            Some(first) if mclass_node.is_character_box => first.clone(),
+            _ => MathNode::builder()
                 .node_type(MathNodeType::Mo)
                 .children(inner)
                 .build()
-                .into()
+                .into(),
         };
 
         // Set spacing based on what is the most likely adjacent atom type.
@@ -242,60 +228,107 @@ pub fn define_mclass(ctx: &mut crate::KatexContext) {
     });
 
     // Build a relation or stacked op by placing one symbol on top of another
-    let stacked_names = ["\\stackrel", "\\overset", "\\underset"];
-
     ctx.define_function(FunctionDefSpec {
         node_type: Some(NodeType::Mclass),
-        names: &stacked_names,
+        names: &["\\overset", "\\underset"],
         props: FunctionPropSpec {
             num_args: 2,
             ..Default::default()
         },
         handler: Some(|context, args, _opt_args| {
-            let base_arg = &args[1];
             let shifted_arg = &args[0];
-            let func_name = context.func_name;
-
-            let mclass = if func_name == "\\stackrel" {
-                DomType::Mrel
+            let (sup, sub) = if context.func_name == "\\underset" {
+                (None, Some(shifted_arg))
             } else {
-                // LaTeX applies \binrel spacing to \overset and \underset.
-                binrel_class(base_arg)
-            };
-
-            let base_op = ParseNodeOp::Body {
-                mode: base_arg.mode(),
-                loc: context.loc(),
-                limits: true,
-                always_handle_sup_sub: Some(true),
-                suppress_base_shift: Some(func_name != "\\stackrel"),
-                parent_is_sup_sub: false,
-                body: ord_argument(base_arg),
-            };
-
-            let supsub = ParseNodeSupSub {
-                mode: shifted_arg.mode(),
-                loc: context.loc(),
-                base: Some(Box::new(ParseNode::Op(base_op))),
-                sup: if func_name == "\\underset" {
-                    None
-                } else {
-                    Some(Box::new(shifted_arg.clone()))
-                },
-                sub: (func_name == "\\underset").then(|| Box::new(shifted_arg.clone())),
+                (Some(shifted_arg), None)
             };
+            stacked(&context, &args[1], sup, sub)
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
 
-            let supsub = ParseNode::SupSub(supsub);
+    // \stackrel[below]{above}{rel} always produces a relation. The optional
+    // argument stacks a second annotation below the relation.
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Mclass),
+        names: &["\\stackrel"],
+        props: FunctionPropSpec {
+            num_args: 2,
+            num_optional_args: 1,
+            examples: &[r"\stackrel{!}{=}", r"\stackrel[b]{a}{=}"],
+            ..Default::default()
+        },
+        handler: Some(|context, args, opt_args| {
+            stacked(
+                &context,
+                &args[1],
+                Some(&args[0]),
+                opt_args.first().and_then(Option::as_ref),
+            )
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
 
-            Ok(ParseNode::Mclass(ParseNodeMclass {
-                mode: context.parser.mode,
-                loc: context.loc(),
-                mclass,
-                is_character_box: supsub.is_character_box()?,
-                body: vec![supsub],
-            }))
+    // \overunderset{above}{below}{base} stacks annotations on both sides at
+    // once, instead of nesting \overset inside \underset.
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Mclass),
+        names: &["\\overunderset"],
+        props: FunctionPropSpec {
+            num_args: 3,
+            ..Default::default()
+        },
+        handler: Some(|context, args, _opt_args| {
+            stacked(&context, &args[2], Some(&args[0]), Some(&args[1]))
         }),
         html_builder: Some(html_builder),
         mathml_builder: Some(mathml_builder),
     });
 }
+
+/// Build the mclass node shared by the stacking commands: the base becomes a
+/// limits-style op carrying the annotations above and below it.
+fn stacked(
+    context: &FunctionContext,
+    base_arg: &ParseNode,
+    above: Option<&ParseNode>,
+    below: Option<&ParseNode>,
+) -> Result<ParseNode, ParseError> {
+    let is_stackrel = context.func_name == "\\stackrel";
+
+    let mclass = if is_stackrel {
+        DomType::Mrel
+    } else {
+        // LaTeX applies \binrel spacing to \overset and \underset.
+        binrel_class(base_arg)
+    };
+
+    let base_op = ParseNodeOp::Body {
+        mode: base_arg.mode(),
+        loc: context.loc(),
+        limits: true,
+        always_handle_sup_sub: Some(true),
+        suppress_base_shift: Some(!is_stackrel),
+        parent_is_sup_sub: false,
+        body: ord_argument(base_arg),
+    };
+
+    let mode = above.or(below).map_or(context.parser.mode, ParseNode::mode);
+    let supsub = ParseNode::SupSub(ParseNodeSupSub {
+        mode,
+        loc: context.loc(),
+        base: Some(Box::new(ParseNode::Op(base_op))),
+        sup: above.map(|arg| Box::new(arg.clone())),
+        sub: below.map(|arg| Box::new(arg.clone())),
+    });
+
+    Ok(ParseNode::Mclass(ParseNodeMclass {
+        mode: context.parser.mode,
+        loc: context.loc(),
+        mclass,
+        is_character_box: supsub.is_character_box()?,
+        body: vec![supsub],
+    }))
+}
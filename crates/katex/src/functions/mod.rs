@@ -44,6 +44,7 @@ mod color;
 mod cr;
 mod def;
 mod delimsizing;
+mod diagbox;
 mod enclose;
 mod environment;
 mod font;
@@ -59,6 +60,7 @@ mod lap;
 mod math;
 mod mathchoice;
 mod mclass;
+mod nuclide;
 mod op;
 mod operatorname;
 mod ordgroup;
@@ -68,6 +70,7 @@ mod pmb;
 mod raisebox;
 mod relax;
 mod rule;
+mod sideset;
 mod sizing;
 mod smash;
 mod sqrt;
@@ -77,6 +80,7 @@ mod symbols_op;
 mod symbols_ord;
 mod symbols_spacing;
 mod tag;
+mod tensor;
 mod text;
 mod underline;
 pub mod utils;
@@ -656,6 +660,8 @@ pub use accent::define_accent;
 /// \mathinner{\int} % Inner operator
 /// \@binrel{a}{b}   % Binrel with class from first argument
 /// \stackrel{a}{b}  % Stack symbols
+/// \stackrel[c]{a}{b}        % Annotations above and below a relation
+/// \overunderset{a}{c}{b}    % Same, with \binrel spacing
 /// ```
 ///
 /// # Math Classes
@@ -679,6 +685,19 @@ pub use accent::define_accent;
 /// - [`define_accent`] for accent-related commands.
 pub use mclass::define_mclass;
 
+/// Determines the atom class `\binrel@` would assign for a reference argument.
+///
+/// Custom functions can use this to render their content with the spacing of
+/// another symbol, the way `\overset` keeps the class of its base. See
+/// [`crate::build_html::DomType`] for how the class affects layout.
+///
+/// # LaTeX Syntax
+///
+/// ```latex
+/// \@binrel{=}{x}   % Renders x with the spacing of a relation
+/// ```
+pub use mclass::binrel_class;
+
 /// Registers extensible arrow functions (\xleftarrow, \xrightarrow, etc.) in
 /// the KaTeX context.
 ///
@@ -1001,6 +1020,7 @@ pub use htmlmathml::define_htmlmathml;
 /// \sout{x}                 % Strikethrough
 /// \phase{x}                % Phase angle symbol
 /// \angl{x}                 % Actuarial angle
+/// \longdiv{1234}{5}        % Long division of 1234 by 5
 /// ```
 ///
 /// # Supported Commands
@@ -1015,11 +1035,13 @@ pub use htmlmathml::define_htmlmathml;
 /// - `\sout`: Horizontal strike
 /// - `\phase`: Phase angle with SVG
 /// - `\angl`: Actuarial angle
+/// - `\longdiv`: Long division bracket, with the divisor before it
 ///
 /// # Arguments
 ///
 /// - `\colorbox`: Background color, content
 /// - `\fcolorbox`: Border color, background color, content
+/// - `\longdiv`: Dividend, divisor
 /// - Other commands: Content only
 ///
 /// # Error Handling
@@ -1552,6 +1574,157 @@ pub use op::define_op;
 /// - [`define_op`] for built-in operators.
 pub use operatorname::define_operatorname;
 
+/// Registers the `\sideset` function in the KaTeX context.
+///
+/// This function defines amsmath's `\sideset`, which decorates a big operator
+/// with scripts on its left and right while leaving the limit positions free
+/// for the subscripts and superscripts that follow.
+///
+/// # Parameters
+///
+/// - `ctx`: A mutable reference to the [`crate::KatexContext`] where the
+///   function is registered.
+///
+/// # Return Value
+///
+/// This function does not return a value; it modifies the provided context by
+/// adding the function definition.
+///
+/// # LaTeX Syntax
+///
+/// ```latex
+/// \sideset{_a^b}{_c^d}\sum      % Pre- and post-scripts
+/// \sideset{}{'}\sum_{n<k} a_n  % Prime beside, limit below
+/// ```
+///
+/// # Arguments
+///
+/// - Required: Scripts placed before the operator, e.g. `{_a^b}`
+/// - Required: Scripts placed after the operator, e.g. `{_c^d}`
+/// - Required: The operator itself
+///
+/// # Error Handling
+///
+/// Errors may occur during parsing if the first two arguments contain anything
+/// other than subscripts and superscripts.
+///
+/// # See Also
+///
+/// - [`define_op`] for the operators usually decorated this way.
+pub use sideset::define_sideset;
+
+/// Registers the `\tensor` function in the KaTeX context.
+///
+/// This function defines a subset of the tensor package's `\tensor`, which
+/// attaches staggered indices to a symbol so that every upper and lower index
+/// occupies its own slot.
+///
+/// # Parameters
+///
+/// - `ctx`: A mutable reference to the [`crate::KatexContext`] where the
+///   function is registered.
+///
+/// # Return Value
+///
+/// This function does not return a value; it modifies the provided context by
+/// adding the function definition.
+///
+/// # LaTeX Syntax
+///
+/// ```latex
+/// \tensor{R}{^a_{bcd}}        % Riemann tensor with staggered indices
+/// \tensor[^a_b]{T}{^c_d}      % Pre- and post-indices
+/// ```
+///
+/// # Arguments
+///
+/// - Optional: Indices placed before the symbol, e.g. `[^a_b]`
+/// - Required: The tensor symbol
+/// - Required: Indices placed after the symbol, e.g. `{^c_d}`
+///
+/// # Error Handling
+///
+/// Errors may occur during parsing if an index list contains anything other
+/// than `^` and `_` followed by their index.
+///
+/// # See Also
+///
+/// - [`define_sideset`] for scripts on both sides of big operators.
+pub use tensor::define_tensor;
+
+/// Registers the `\diagbox` function in the KaTeX context.
+///
+/// This function defines the diagbox package's `\diagbox`, which splits a
+/// box with a diagonal rule into a label in the bottom left corner and one in
+/// the top right corner. In an array it fills its whole cell, which makes it
+/// suited for the top left cell of a table.
+///
+/// # Parameters
+///
+/// - `ctx`: A mutable reference to the [`crate::KatexContext`] where the
+///   function is registered.
+///
+/// # Return Value
+///
+/// This function does not return a value; it modifies the provided context by
+/// adding the function definition.
+///
+/// # LaTeX Syntax
+///
+/// ```latex
+/// \begin{array}{c|cc}
+///   \diagbox{x}{y} & 0 & 1 \\ \hline
+///   0 & 0 & 1 \\
+///   1 & 1 & 0
+/// \end{array}
+/// ```
+///
+/// # Arguments
+///
+/// - Required: The label below the rule, usually naming the rows
+/// - Required: The label above the rule, usually naming the columns
+///
+/// # See Also
+///
+/// - [`define_enclose`] for `\cancel` and friends, which draw the same rules
+///   across their content.
+pub use diagbox::define_diagbox;
+
+/// Registers the `\nuclide` function in the KaTeX context.
+///
+/// This function defines `\nuclide`, which writes an isotope in the notation
+/// of chemistry and nuclear physics: the mass number and the atomic number
+/// stacked before the element symbol, both aligned to its right.
+///
+/// # Parameters
+///
+/// - `ctx`: A mutable reference to the [`crate::KatexContext`] where the
+///   function is registered.
+///
+/// # Return Value
+///
+/// This function does not return a value; it modifies the provided context by
+/// adding the function definition.
+///
+/// # LaTeX Syntax
+///
+/// ```latex
+/// \nuclide[14][6]{C}       % Carbon-14
+/// \nuclide[235]{U}         % Mass number only
+/// \nuclide[4][2]{He}^{2+}  % Alpha particle
+/// ```
+///
+/// # Arguments
+///
+/// - Optional: The mass number
+/// - Optional: The atomic number
+/// - Required: The element symbol, set upright
+///
+/// # See Also
+///
+/// - [`define_sideset`] and [`define_tensor`] for prescripts on other bases.
+pub use nuclide::define_nuclide;
+
 /// Registers ordgroup functions (\@ordgroup) in the KaTeX context.
 ///
 /// This function defines the `\@ordgroup` command for creating ordinary
@@ -0,0 +1,205 @@
+//! Nuclide function implementation for KaTeX Rust
+//!
+//! This module handles the `\nuclide` command, which writes an isotope with
+//! its mass and atomic numbers stacked before the element symbol, e.g.
+//! `\nuclide[14][6]{C}` or `\nuclide[235][92]{U}`.
+
+use crate::build_common::{VListElemAndShift, VListParam, make_span, make_v_list};
+use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
+use crate::dom_tree::HtmlDomNode;
+use crate::functions::utils::script_placement::{self, ScriptBox, ScriptShifts};
+use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
+use crate::options::Options;
+use crate::parser::parse_node::{NodeType, ParseNode, ParseNodeFont, ParseNodeNuclide};
+use crate::types::{ClassList, CssProperty, CssStyle, ParseError, ParseErrorKind};
+use crate::units::make_em;
+use crate::{KatexContext, build_html, build_mathml};
+
+/// HTML builder for nuclide nodes
+///
+/// The numbers are placed like the scripts of the element symbol, following
+/// TeXbook rule 18, but in one right-aligned column before it: a one-digit
+/// atomic number stays next to the symbol under a three-digit mass number.
+fn html_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<HtmlDomNode, ParseError> {
+    let ParseNode::Nuclide(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Nuclide,
+        }));
+    };
+
+    let base = build_html::build_group(ctx, &group.base, options, None)?;
+    let Some(scripts) = build_prescripts(group, &base, options, ctx)? else {
+        return Ok(make_span("mord", vec![base], Some(options), None).into());
+    };
+    Ok(make_span("mord", vec![scripts, base], Some(options), None).into())
+}
+
+/// Builds the right-aligned column of the mass and atomic numbers, or `None`
+/// if the nuclide has neither.
+fn build_prescripts(
+    group: &ParseNodeNuclide,
+    base: &HtmlDomNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<Option<HtmlDomNode>, ParseError> {
+    let metrics = options.font_metrics();
+    let mut drops = ScriptShifts::default();
+
+    let mass = group
+        .mass
+        .as_deref()
+        .map(|mass| {
+            let new_options = options.having_style(options.style.sup());
+            drops.sup = new_options.font_metrics().sup_drop * new_options.size_multiplier
+                / options.size_multiplier;
+            build_html::build_group(ctx, mass, &new_options, Some(options))
+        })
+        .transpose()?;
+    let atomic = group
+        .atomic
+        .as_deref()
+        .map(|atomic| {
+            let new_options = options.having_style(options.style.sub());
+            drops.sub = new_options.font_metrics().sub_drop * new_options.size_multiplier
+                / options.size_multiplier;
+            build_html::build_group(ctx, atomic, &new_options, Some(options))
+        })
+        .transpose()?;
+
+    let nucleus = ScriptBox {
+        height: base.height(),
+        depth: base.depth(),
+    };
+    let is_character_box = group.base.is_character_box().unwrap_or(false);
+    let script_box = |elem: &HtmlDomNode| ScriptBox {
+        height: elem.height(),
+        depth: elem.depth(),
+    };
+    let shifts = script_placement::place_scripts(
+        metrics,
+        options.style,
+        script_placement::nucleus_shifts(nucleus, is_character_box, drops),
+        mass.as_ref().map(script_box),
+        atomic.as_ref().map(script_box),
+    );
+
+    // The column is as wide as the wider number, and the narrower one is
+    // pushed against the symbol
+    let mut right_aligned = CssStyle::default();
+    right_aligned.insert(CssProperty::TextAlign, "right".to_owned());
+    let children: Vec<_> = [(atomic, shifts.sub), (mass, -shifts.sup)]
+        .into_iter()
+        .filter_map(|(elem, shift)| {
+            elem.map(|elem| {
+                VListElemAndShift::builder()
+                    .elem(elem)
+                    .shift(shift)
+                    .wrapper_style(right_aligned.clone())
+                    .build()
+            })
+        })
+        .collect();
+    if children.is_empty() {
+        return Ok(None);
+    }
+
+    let vlist = make_v_list(VListParam::IndividualShift { children }, options)?;
+    let mut scripts = make_span(ClassList::Empty, vec![vlist.into()], None, None);
+    // \scriptspace separates the numbers from the symbol
+    scripts.style.insert(
+        CssProperty::MarginRight,
+        make_em(script_placement::script_space(
+            metrics,
+            options.size_multiplier,
+        )),
+    );
+    Ok(Some(scripts.into()))
+}
+
+/// MathML builder for nuclide nodes
+fn mathml_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<MathDomNode, ParseError> {
+    let ParseNode::Nuclide(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Nuclide,
+        }));
+    };
+
+    let base = build_mathml::build_group(ctx, &group.base, options)?;
+    if group.mass.is_none() && group.atomic.is_none() {
+        return Ok(base);
+    }
+
+    let script = |script: Option<&ParseNode>| -> Result<MathDomNode, ParseError> {
+        script.map_or_else(
+            || {
+                Ok(MathNode::builder()
+                    .node_type(MathNodeType::None)
+                    .build()
+                    .into())
+            },
+            |script| build_mathml::build_group(ctx, script, options),
+        )
+    };
+
+    Ok(MathNode::builder()
+        .node_type(MathNodeType::Mmultiscripts)
+        .children(vec![
+            base,
+            MathNode::builder()
+                .node_type(MathNodeType::Mprescripts)
+                .build()
+                .into(),
+            script(group.atomic.as_deref())?,
+            script(group.mass.as_deref())?,
+        ])
+        .build()
+        .into())
+}
+
+/// Registers the \nuclide function in the KaTeX context
+pub fn define_nuclide(ctx: &mut KatexContext) {
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Nuclide),
+        names: &["\\nuclide"],
+        props: FunctionPropSpec {
+            num_args: 1,
+            num_optional_args: 2,
+            examples: &[r"\nuclide{C}", r"\nuclide[14][6]{C}"],
+            ..Default::default()
+        },
+        handler: Some(|context, args, opt_args| {
+            let [base] = args.try_into().map_err(|_| {
+                ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                })
+            })?;
+            let mut opt_args = opt_args.into_iter().map(|arg| arg.map(Box::new));
+
+            // Element symbols are upright
+            let base = ParseNode::Font(ParseNodeFont {
+                mode: context.parser.mode,
+                loc: None,
+                font: "mathrm".to_owned(),
+                body: Box::new(base),
+            });
+
+            Ok(ParseNode::Nuclide(Box::new(ParseNodeNuclide {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                base: Box::new(base),
+                mass: opt_args.next().flatten(),
+                atomic: opt_args.next().flatten(),
+            })))
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+}
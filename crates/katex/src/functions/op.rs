@@ -111,20 +111,33 @@ pub fn html_builder(
             };
 
             let mut base_classes = ClassList::Const(&["mop", "op-symbol"]);
-            if large {
-                base_classes.push("large-op");
+            let sized_glyph = ctx
+                .symbols
+                .get_sized_glyph(symbol_name, if large { 2 } else { 1 });
+
+            let symbol_base = if let Some(glyph) = sized_glyph {
+                // A registered glyph brings its own font class in place of
+                // the KaTeX size fonts.
+                base_classes.push(glyph.class.clone());
+                make_symbol(
+                    ctx,
+                    &glyph.text,
+                    &glyph.font,
+                    Mode::Math,
+                    Some(options),
+                    base_classes,
+                )?
             } else {
-                base_classes.push("small-op");
-            }
-
-            let symbol_base = make_symbol(
-                ctx,
-                symbol_name,
-                font_name,
-                Mode::Math,
-                Some(options),
-                base_classes,
-            )?;
+                base_classes.push(if large { "large-op" } else { "small-op" });
+                make_symbol(
+                    ctx,
+                    symbol_name,
+                    font_name,
+                    Mode::Math,
+                    Some(options),
+                    base_classes,
+                )?
+            };
 
             if let Some(stash) = stash {
                 // We're in \oiint or \oiiint. Overlay the oval.
@@ -179,15 +192,13 @@ pub fn html_builder(
             build_html::GroupType::True,
             (None, None),
         )?;
-        if inner.len() == 1 && matches!(inner[0], HtmlDomNode::Symbol(_)) {
-            let mut symbol = inner[0].clone();
-            if let HtmlDomNode::Symbol(ref mut sym) = symbol
-                && let Some(class) = sym.classes.get_mut(0)
-            {
+        if let [HtmlDomNode::Symbol(sym)] = inner.as_slice() {
+            let mut sym = sym.clone();
+            if let Some(class) = sym.classes.get_mut(0) {
                 // replace old mclass
                 *class = Cow::Borrowed("mop");
             }
-            symbol
+            HtmlDomNode::Symbol(sym)
         } else {
             make_span("mop", inner, Some(options), None).into()
         }
@@ -162,21 +162,16 @@ fn mathml_builder(
                 | MathNodeType::Mn
                 | MathNodeType::Mspace
                 | MathNodeType::Mtext => {}
-                MathNodeType::Mo => {
-                    if math_node.children.len() == 1 {
-                        if let MathDomNode::Text(text_node) = &mut math_node.children[0] {
-                            let replaced = text_node
-                                .text
-                                .replace('\u{2212}', "-")
-                                .replace('\u{2217}', "*");
-                            if text_node.text != replaced {
-                                text_node.text = replaced;
-                            }
-                        } else {
-                            is_all_string = false;
-                        }
-                    } else {
-                        is_all_string = false;
+                MathNodeType::Mo
+                    if math_node.children.len() == 1
+                        && let MathDomNode::Text(text_node) = &mut math_node.children[0] =>
+                {
+                    let replaced = text_node
+                        .text
+                        .replace('\u{2212}', "-")
+                        .replace('\u{2217}', "*");
+                    if text_node.text != replaced {
+                        text_node.text = replaced;
                     }
                 }
                 _ => {
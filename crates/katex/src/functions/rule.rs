@@ -24,6 +24,7 @@ pub fn define_rule(ctx: &mut KatexContext) {
             num_optional_args: 1,
             allowed_in_text: true,
             allowed_in_math: true,
+            examples: &[r"\rule{1em}{2em}", r"\rule[-1em]{1em}{2em}"],
             ..Default::default()
         },
         handler: Some(
@@ -56,13 +57,13 @@ pub fn define_rule(ctx: &mut KatexContext) {
                     None
                 };
 
-                Ok(ParseNode::Rule(ParseNodeRule {
+                Ok(ParseNode::Rule(Box::new(ParseNodeRule {
                     mode: context.parser.mode,
                     loc: context.loc(),
                     shift,
                     width: width_node.value.clone(),
                     height: height_node.value.clone(),
-                }))
+                })))
             },
         ),
         html_builder: Some(html_builder),
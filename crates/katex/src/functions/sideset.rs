@@ -0,0 +1,263 @@
+//! Sideset function implementation for KaTeX Rust
+//!
+//! This module handles amsmath's \sideset command, which attaches scripts to
+//! the left and right of a big operator while keeping the operator's own
+//! limits available for a following `_` and `^`.
+
+use crate::build_common::make_span;
+use crate::define_function::{FunctionDefSpec, FunctionPropSpec, normalize_argument};
+use crate::dom_tree::HtmlDomNode;
+use crate::functions::utils::assemble_sup_sub;
+use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
+use crate::options::Options;
+use crate::parser::parse_node::{
+    LapAlignment, NodeType, ParseNode, ParseNodeHphantom, ParseNodeLap, ParseNodeSideset,
+    ParseNodeSupSub, ParseNodeVphantom,
+};
+use crate::types::{ParseError, ParseErrorKind};
+use crate::{KatexContext, build_html, build_mathml};
+use core::slice;
+
+/// Scripts collected from one of the first two `\sideset` arguments.
+struct SideScripts {
+    sup: Option<Box<ParseNode>>,
+    sub: Option<Box<ParseNode>>,
+}
+
+/// Extract the scripts from a `\sideset` argument such as `{_a^b}` or `{'}`.
+///
+/// The argument must either be empty or consist of a single base-less
+/// sup/sub group.
+fn side_scripts(arg: &ParseNode) -> Result<SideScripts, ParseError> {
+    let nodes = match arg {
+        ParseNode::OrdGroup(ord) => ord.body.as_slice(),
+        other => slice::from_ref(other),
+    };
+
+    match nodes {
+        [] => Ok(SideScripts {
+            sup: None,
+            sub: None,
+        }),
+        [ParseNode::SupSub(supsub)] if supsub.base.is_none() => Ok(SideScripts {
+            sup: supsub.sup.clone(),
+            sub: supsub.sub.clone(),
+        }),
+        _ => Err(ParseError::with_token(
+            ParseErrorKind::SidesetExpectedScripts,
+            arg,
+        )),
+    }
+}
+
+/// Expand a sideset node into the sequence that is laid out horizontally:
+/// the pre-scripts hung on a vertical phantom of the operator, followed by
+/// the operator carrying its post-scripts.
+fn scripted_body(group: &ParseNodeSideset) -> Vec<ParseNode> {
+    let mut body = Vec::with_capacity(3);
+
+    if group.pre_sup.is_some() || group.pre_sub.is_some() {
+        // The phantom gives the pre-scripts the same height and depth the
+        // post-scripts see, so both sides line up.
+        let phantom = || {
+            Box::new(ParseNode::Vphantom(ParseNodeVphantom {
+                mode: group.mode,
+                loc: None,
+                body: group.base.clone(),
+            }))
+        };
+        let hphantom = |body: Box<ParseNode>| {
+            ParseNode::Hphantom(ParseNodeHphantom {
+                mode: group.mode,
+                loc: None,
+                body,
+            })
+        };
+        let llap = |body: Box<ParseNode>| {
+            ParseNode::Lap(ParseNodeLap {
+                mode: group.mode,
+                loc: None,
+                alignment: LapAlignment::Left,
+                body,
+            })
+        };
+        let scripts = |wrap: &dyn Fn(Box<ParseNode>) -> ParseNode| {
+            ParseNode::SupSub(ParseNodeSupSub {
+                mode: group.mode,
+                loc: None,
+                base: Some(phantom()),
+                sup: group.pre_sup.clone().map(|sup| Box::new(wrap(sup))),
+                sub: group.pre_sub.clone().map(|sub| Box::new(wrap(sub))),
+            })
+        };
+        // Pre-scripts sit flush against the operator like in amsmath: a pair
+        // of phantom scripts takes up the width of the wider one, and the
+        // visible pair is lapped to the left over it.
+        body.push(scripts(&hphantom));
+        body.push(scripts(&llap));
+    }
+
+    if group.post_sup.is_some() || group.post_sub.is_some() {
+        body.push(ParseNode::SupSub(ParseNodeSupSub {
+            mode: group.mode,
+            loc: None,
+            base: Some(group.base.clone()),
+            sup: group.post_sup.clone(),
+            sub: group.post_sub.clone(),
+        }));
+    } else {
+        body.push((*group.base).clone());
+    }
+
+    body
+}
+
+/// HTML builder for sideset nodes
+/// NOTE: Like the op builder, this one handles not only "sideset", but also
+/// "supsub" since the limits of the decorated operator are set above and
+/// below the whole construct.
+pub fn html_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<HtmlDomNode, ParseError> {
+    let (group, super_group, sub_group, has_limits) = match node {
+        ParseNode::SupSub(supsub) => {
+            if let Some(base) = supsub.base.as_deref()
+                && let ParseNode::Sideset(group) = base
+            {
+                (group, supsub.sup.as_deref(), supsub.sub.as_deref(), true)
+            } else {
+                return Err(ParseError::new(ParseErrorKind::ExpectedSupSubBaseNode {
+                    node: NodeType::Sideset,
+                }));
+            }
+        }
+        ParseNode::Sideset(group) => (group, None, None, false),
+        _ => {
+            return Err(ParseError::new(ParseErrorKind::ExpectedNodeOrSupSub {
+                node: NodeType::Sideset,
+            }));
+        }
+    };
+
+    // Build the pieces individually: the scripts hug the operator, so no
+    // inter-atom spacing is inserted between them.
+    let inner = scripted_body(group)
+        .iter()
+        .map(|part| build_html::build_group(ctx, part, options, None))
+        .collect::<Result<Vec<_>, _>>()?;
+    let base: HtmlDomNode = make_span("mop", inner, Some(options), None).into();
+
+    if has_limits {
+        assemble_sup_sub(
+            ctx,
+            base,
+            super_group,
+            sub_group,
+            options,
+            options.style,
+            0.0,
+            0.0,
+        )
+    } else {
+        Ok(base)
+    }
+}
+
+/// MathML builder for sideset nodes
+fn mathml_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<MathDomNode, ParseError> {
+    let ParseNode::Sideset(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Sideset,
+        }));
+    };
+
+    let script = |script: Option<&ParseNode>| -> Result<MathDomNode, ParseError> {
+        script.map_or_else(
+            || {
+                Ok(MathNode::builder()
+                    .node_type(MathNodeType::None)
+                    .build()
+                    .into())
+            },
+            |script| build_mathml::build_group(ctx, script, options),
+        )
+    };
+
+    let mut children = vec![
+        build_mathml::build_group(ctx, &group.base, options)?,
+        script(group.post_sub.as_deref())?,
+        script(group.post_sup.as_deref())?,
+    ];
+
+    if group.pre_sup.is_some() || group.pre_sub.is_some() {
+        children.push(
+            MathNode::builder()
+                .node_type(MathNodeType::Mprescripts)
+                .build()
+                .into(),
+        );
+        children.push(script(group.pre_sub.as_deref())?);
+        children.push(script(group.pre_sup.as_deref())?);
+    }
+
+    Ok(MathNode::builder()
+        .node_type(MathNodeType::Mmultiscripts)
+        .children(children)
+        .build()
+        .into())
+}
+
+/// Registers the \sideset function in the KaTeX context
+pub fn define_sideset(ctx: &mut KatexContext) {
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Sideset),
+        names: &["\\sideset"],
+        props: FunctionPropSpec {
+            num_args: 3,
+            examples: &[r"\sideset{_a}{^b}\sum", r"\sideset{_1^2}{_3^4}\prod_i"],
+            ..Default::default()
+        },
+        handler: Some(|context, args, _opt_args| {
+            let pre = side_scripts(&args[0])?;
+            let post = side_scripts(&args[1])?;
+
+            // The side scripts belong beside the operator, so its own limit
+            // placement is switched off and carried over to the sideset node.
+            let mut base = normalize_argument(&args[2]).clone();
+            let limits = match &mut base {
+                ParseNode::Op(op_node) => {
+                    let limits = op_node.limits();
+                    *op_node.limits_mut() = false;
+                    *op_node.always_handle_sup_sub_mut() = None;
+                    limits
+                }
+                ParseNode::OperatorName(op_name) => {
+                    let limits = op_name.always_handle_sup_sub;
+                    op_name.always_handle_sup_sub = false;
+                    limits
+                }
+                _ => false,
+            };
+
+            Ok(ParseNode::Sideset(ParseNodeSideset {
+                mode: context.parser.mode,
+                loc: context.loc(),
+                base: Box::new(base),
+                pre_sup: pre.sup,
+                pre_sub: pre.sub,
+                post_sup: post.sup,
+                post_sub: post.sub,
+                limits,
+                always_handle_sup_sub: false,
+            }))
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+}
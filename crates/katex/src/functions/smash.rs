@@ -55,6 +55,7 @@ pub fn define_smash(ctx: &mut KatexContext) {
             num_args: 1,
             num_optional_args: 1,
             allowed_in_text: true,
+            examples: &[r"\smash{y}", r"\smash[b]{y}"],
             ..Default::default()
         },
         handler: Some(|context, args, opt_args| {
@@ -26,11 +26,18 @@ pub fn define_sqrt(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 1,
             num_optional_args: 1,
+            examples: &[r"\sqrt{x}", r"\sqrt[3]{x}"],
             ..Default::default()
         },
         handler: Some(|context, args, opt_args| {
-            let body = args[0].clone();
-            let index = opt_args[0].clone();
+            // Take the arguments rather than cloning them, which would copy
+            // every root nested in them once per level
+            let [body] = args.try_into().map_err(|_| {
+                ParseError::new(ParseErrorKind::UnhandledCommand {
+                    name: context.func_name.to_owned(),
+                })
+            })?;
+            let index = opt_args.into_iter().next().flatten();
             Ok(ParseNode::Sqrt(Box::new(ParseNodeSqrt {
                 mode: context.parser.mode,
                 loc: context.loc(),
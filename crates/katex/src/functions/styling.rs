@@ -14,13 +14,13 @@ use crate::style::{DISPLAY, SCRIPT, SCRIPTSCRIPT, Style, TEXT};
 use crate::types::{ParseError, ParseErrorKind};
 
 /// Style mapping from string names to Style references
-fn style_map(style_name: &str) -> &'static Style {
+fn style_map(style_name: &str) -> Option<&'static Style> {
     match style_name {
-        "display" => DISPLAY,
-        "text" => TEXT,
-        "script" => SCRIPT,
-        "scriptscript" => SCRIPTSCRIPT,
-        _ => unreachable!(),
+        "display" => Some(DISPLAY),
+        "text" => Some(TEXT),
+        "script" => Some(SCRIPT),
+        "scriptscript" => Some(SCRIPTSCRIPT),
+        _ => None,
     }
 }
 
@@ -114,8 +114,16 @@ pub fn define_styling(ctx: &mut crate::KatexContext) {
 
             // Figure out what style to use by pulling out the style from
             // the function name
-            let style_name = &context.func_name[1..context.func_name.len() - 5]; // Remove \ and style
-            let style = style_map(style_name);
+            let style = context
+                .func_name
+                .strip_prefix('\\')
+                .and_then(|name| name.strip_suffix("style"))
+                .and_then(style_map)
+                .ok_or_else(|| {
+                    ParseError::new(ParseErrorKind::UnhandledCommand {
+                        name: context.func_name.to_owned(),
+                    })
+                })?;
 
             Ok(ParseNode::Styling(ParseNodeStyling {
                 mode: context.parser.mode,
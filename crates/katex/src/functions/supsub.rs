@@ -11,7 +11,8 @@ use crate::build_common::{
 use crate::build_html::{DomType, Side};
 use crate::define_function::HtmlBuilder;
 use crate::dom_tree::HtmlDomNode;
-use crate::functions::{accent, horiz_brace, op, operatorname};
+use crate::functions::utils::script_placement::{self, ScriptBox, ScriptShifts};
+use crate::functions::{accent, horiz_brace, op, operatorname, sideset};
 use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
 use crate::options::Options;
 use crate::parser::parse_node::{AnyParseNode, NodeType, ParseNode, ParseNodeOp, ParseNodeSupSub};
@@ -47,11 +48,16 @@ fn html_builder_delegate(group: &ParseNodeSupSub, options: &Options) -> Option<H
                 return Some(operatorname::html_builder);
             }
         }
-        AnyParseNode::Accent(accent) => {
-            // Accents handle supsubs when base is a character box
-            if accent.base.is_character_box().unwrap_or(false) {
-                return Some(accent::html_builder);
-            }
+        // \sideset sets its limits above and below the whole construct
+        AnyParseNode::Sideset(sideset)
+            if sideset.limits
+                && (options.style.size == DISPLAY.size || sideset.always_handle_sup_sub) =>
+        {
+            return Some(sideset::html_builder);
+        }
+        // Accents handle supsubs when base is a character box
+        AnyParseNode::Accent(accent) if accent.base.is_character_box().unwrap_or(false) => {
+            return Some(accent::html_builder);
         }
         AnyParseNode::HorizBrace(hbrace) => {
             // Horizontal braces handle supsubs based on position
@@ -104,10 +110,12 @@ fn html_builder(
     let metrics = options.font_metrics();
 
     // Rule 18a
-    let mut super_shift = 0.0;
-    let mut sub_shift = 0.0;
-
+    let nucleus = ScriptBox {
+        height: base_html.height(),
+        depth: base_html.depth(),
+    };
     let is_character_box = value_base.is_some_and(|b| b.is_character_box().unwrap_or(false));
+    let mut drops = ScriptShifts::default();
 
     if let Some(sup_group) = value_super {
         let new_options = options.having_style(options.style.sup());
@@ -117,11 +125,8 @@ fn html_builder(
             &new_options,
             Some(options),
         )?);
-        if !is_character_box {
-            super_shift = base_html.height()
-                - new_options.font_metrics().sup_drop * new_options.size_multiplier
-                    / options.size_multiplier;
-        }
+        drops.sup = new_options.font_metrics().sup_drop * new_options.size_multiplier
+            / options.size_multiplier;
     }
 
     if let Some(sub_group) = value_sub {
@@ -132,26 +137,30 @@ fn html_builder(
             &new_options,
             Some(options),
         )?);
-        if !is_character_box {
-            sub_shift = base_html.depth()
-                + new_options.font_metrics().sub_drop * new_options.size_multiplier
-                    / options.size_multiplier;
-        }
+        drops.sub = new_options.font_metrics().sub_drop * new_options.size_multiplier
+            / options.size_multiplier;
     }
 
-    // Rule 18c
-    let min_sup_shift = if options.style == DISPLAY {
-        metrics.sup1
-    } else if options.style.cramped {
-        metrics.sup3
-    } else {
-        metrics.sup2
-    };
+    let shifts = script_placement::place_scripts(
+        metrics,
+        options.style,
+        script_placement::nucleus_shifts(nucleus, is_character_box, drops),
+        super_m.as_ref().map(|elem| ScriptBox {
+            height: elem.height(),
+            depth: elem.depth(),
+        }),
+        sub_m.as_ref().map(|elem| ScriptBox {
+            height: elem.height(),
+            depth: elem.depth(),
+        }),
+    );
 
     // scriptspace is a font-size-independent size, so scale it
     // appropriately for use as the marginRight.
-    let multiplier = options.size_multiplier;
-    let margin_right = make_em((0.5 / metrics.pt_per_em) / multiplier);
+    let margin_right = make_em(script_placement::script_space(
+        metrics,
+        options.size_multiplier,
+    ));
 
     let mut margin_left = None;
     if sub_m.is_some() {
@@ -169,11 +178,11 @@ fn html_builder(
         if matches!(base_html, HtmlDomNode::Symbol(_)) || is_oiint {
             match &base_html {
                 HtmlDomNode::Symbol(sym) => {
-                    margin_left = Some(make_em(-sym.italic));
+                    margin_left = Some(make_em(script_placement::subscript_kern(sym.italic)));
                 }
                 HtmlDomNode::DomSpan(span) => {
                     if let Some(italic) = span.italic {
-                        margin_left = Some(make_em(-italic));
+                        margin_left = Some(make_em(script_placement::subscript_kern(italic)));
                     }
                 }
                 _ => {}
@@ -182,97 +191,57 @@ fn html_builder(
     }
 
     let supsub = match (super_m.take(), sub_m.take(), margin_left) {
-        (Some(super_elem), Some(sub_elem), margin_left) => {
-            // Both superscript and subscript
-            let sup_depth = super_elem.depth();
-            let sub_height = sub_elem.height();
-
-            super_shift = super_shift
-                .max(min_sup_shift)
-                .max(0.25f64.mul_add(metrics.x_height, sup_depth));
-            sub_shift = sub_shift.max(metrics.sub2);
-
-            let rule_width = metrics.default_rule_thickness;
-
-            // Rule 18e
-            let max_width = 4.0 * rule_width;
-            if (super_shift - sup_depth) - (sub_height - sub_shift) < max_width {
-                sub_shift = max_width - (super_shift - sup_depth) + sub_height;
-                let psi = 0.8f64.mul_add(metrics.x_height, -(super_shift - sup_depth));
-                if psi > 0.0 {
-                    super_shift += psi;
-                    sub_shift -= psi;
-                }
-            }
-
-            make_v_list(
-                VListParam::IndividualShift {
-                    children: vec![
-                        VListElemAndShift {
-                            elem: sub_elem,
-                            shift: sub_shift,
-                            margin_left,
-                            margin_right: Some(margin_right.clone()),
-                            wrapper_classes: None,
-                            wrapper_style: None,
-                        },
-                        VListElemAndShift {
-                            elem: super_elem,
-                            shift: -super_shift,
-                            margin_left: None,
-                            margin_right: Some(margin_right),
-                            wrapper_classes: None,
-                            wrapper_style: None,
-                        },
-                    ],
-                },
-                options,
-            )?
-        }
-        (None, Some(sub_elem), margin_left) => {
-            // Rule 18b
-            let sub_height = sub_elem.height();
-            sub_shift = sub_shift
-                .max(metrics.sub1)
-                .max(0.8f64.mul_add(-metrics.x_height, sub_height));
-
-            make_v_list(
-                VListParam::Shift {
-                    position_data: sub_shift,
-                    children: vec![VListChild::Elem(Box::new(VListElem {
+        (Some(super_elem), Some(sub_elem), margin_left) => make_v_list(
+            VListParam::IndividualShift {
+                children: vec![
+                    VListElemAndShift {
                         elem: sub_elem,
-                        shift: None,
+                        shift: shifts.sub,
                         margin_left,
-                        margin_right: Some(margin_right),
+                        margin_right: Some(margin_right.clone()),
                         wrapper_classes: None,
                         wrapper_style: None,
-                    }))],
-                },
-                options,
-            )?
-        }
-        (Some(sup_elem), None, _) => {
-            // Rule 18c, d
-            let sup_depth = sup_elem.depth();
-            super_shift = super_shift
-                .max(min_sup_shift)
-                .max(0.25f64.mul_add(metrics.x_height, sup_depth));
-
-            make_v_list(
-                VListParam::Shift {
-                    position_data: -super_shift,
-                    children: vec![VListChild::Elem(Box::new(VListElem {
-                        elem: sup_elem,
-                        shift: None,
+                    },
+                    VListElemAndShift {
+                        elem: super_elem,
+                        shift: -shifts.sup,
                         margin_left: None,
                         margin_right: Some(margin_right),
                         wrapper_classes: None,
                         wrapper_style: None,
-                    }))],
-                },
-                options,
-            )?
-        }
+                    },
+                ],
+            },
+            options,
+        )?,
+        (None, Some(sub_elem), margin_left) => make_v_list(
+            VListParam::Shift {
+                position_data: shifts.sub,
+                children: vec![VListChild::Elem(Box::new(VListElem {
+                    elem: sub_elem,
+                    shift: None,
+                    margin_left,
+                    margin_right: Some(margin_right),
+                    wrapper_classes: None,
+                    wrapper_style: None,
+                }))],
+            },
+            options,
+        )?,
+        (Some(sup_elem), None, _) => make_v_list(
+            VListParam::Shift {
+                position_data: -shifts.sup,
+                children: vec![VListChild::Elem(Box::new(VListElem {
+                    elem: sup_elem,
+                    shift: None,
+                    margin_left: None,
+                    margin_right: Some(margin_right),
+                    wrapper_classes: None,
+                    wrapper_style: None,
+                }))],
+            },
+            options,
+        )?,
         (None, None, _) => {
             return Err(ParseError::new(ParseErrorKind::SupSubMissingSupOrSub));
         }
@@ -320,7 +289,9 @@ fn mathml_builder(
         }
     }
 
-    let mut children = if let Some(base) = group.base.as_deref() {
+    // The base, then the subscript and superscript
+    let mut children = Vec::with_capacity(3);
+    children.push(if let Some(base) = group.base.as_deref() {
         let mut base_clone = base.clone();
         match &mut base_clone {
             AnyParseNode::Op(op_node) => match op_node {
@@ -338,15 +309,13 @@ fn mathml_builder(
             }
             _ => {}
         }
-        vec![build_mathml::build_group(ctx, &base_clone, options)?]
+        build_mathml::build_group(ctx, &base_clone, options)?
     } else {
-        vec![
-            MathNode::builder()
-                .node_type(MathNodeType::Mrow)
-                .build()
-                .into(),
-        ]
-    };
+        MathNode::builder()
+            .node_type(MathNodeType::Mrow)
+            .build()
+            .into()
+    });
 
     if let Some(sub) = &group.sub {
         children.push(build_mathml::build_group(ctx, sub, options)?);
@@ -356,12 +325,25 @@ fn mathml_builder(
         children.push(build_mathml::build_group(ctx, sup, options)?);
     }
 
+    // Does the inner group set its limits above and below?
+    let sideset_limits = matches!(
+        group.base.as_deref(),
+        Some(AnyParseNode::Sideset(sideset))
+            if sideset.limits && (options.style == DISPLAY || sideset.always_handle_sup_sub)
+    );
+
     let node_type = if is_brace {
         if is_over {
             MathNodeType::Mover
         } else {
             MathNodeType::Munder
         }
+    } else if sideset_limits {
+        match (&group.sup, &group.sub) {
+            (Some(_), Some(_)) => MathNodeType::Munderover,
+            (Some(_), None) => MathNodeType::Mover,
+            (None, _) => MathNodeType::Munder,
+        }
     } else if group.sub.is_none() {
         let base = group.base.as_deref();
         if let Some(base) = base
@@ -71,6 +71,13 @@ fn html_builder(
                 } else {
                     return Err(ParseError::new(ParseErrorKind::GeneratedOrdMissingClasses));
                 }
+                // Wrappable text uses a real space where the line may break
+                if options.text_wrap
+                    && class_name.is_none()
+                    && let HtmlDomNode::Symbol(symbol) = &mut ord
+                {
+                    " ".clone_into(&mut symbol.text);
+                }
                 Ok(ord)
             } else {
                 // In math mode, create a span with the symbol
@@ -16,7 +16,10 @@ pub fn define_tag(ctx: &mut KatexContext) {
     ctx.define_function(FunctionDefSpec {
         node_type: Some(NodeType::Tag),
         names: &["\\tag"],
-        props: FunctionPropSpec::default(),
+        props: FunctionPropSpec {
+            examples: &[r"\tag{1}x", r"\tag*{$\ast$}x"],
+            ..Default::default()
+        },
         handler: None,
         html_builder: None, // Tag only has MathML builder
         mathml_builder: Some(mathml_builder),
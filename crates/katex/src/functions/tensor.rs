@@ -0,0 +1,204 @@
+//! Tensor function implementation for KaTeX Rust
+//!
+//! This module handles the `\tensor` command from the LaTeX tensor package,
+//! which staggers upper and lower indices so that each one keeps its own
+//! horizontal slot, e.g. `\tensor{R}{^a_{bcd}}` or `\tensor[^a_b]{T}{^c}`.
+
+use crate::build_common::make_span;
+use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
+use crate::dom_tree::HtmlDomNode;
+use crate::macros::MacroContextInterface as _;
+use crate::mathml_tree::{MathDomNode, MathNode, MathNodeType};
+use crate::options::Options;
+use crate::parser::Parser;
+use crate::parser::parse_node::{
+    NodeType, ParseNode, ParseNodeSupSub, ParseNodeTensor, ParseNodeVphantom, TensorIndex,
+};
+use crate::types::{ParseError, ParseErrorKind};
+use crate::{KatexContext, build_html, build_mathml};
+
+/// Parse an index list such as `{^a_b^c}` into its slots.
+///
+/// The list cannot be parsed as an ordinary group: consecutive superscripts
+/// would be rejected as a double superscript, and the order of the indices
+/// would be lost. Returns `None` if an optional list is absent.
+fn parse_indices(
+    parser: &mut Parser,
+    optional: bool,
+) -> Result<Option<Vec<TensorIndex>>, ParseError> {
+    if parser.gullet.scan_argument(optional)?.is_none() {
+        return Ok(None);
+    }
+    parser.gullet.begin_group();
+
+    let mut indices = Vec::new();
+    loop {
+        parser.consume_spaces()?;
+        let token = parser.fetch()?.clone();
+        match token.text.as_str() {
+            "^" => indices.push(TensorIndex::Upper(
+                parser.handle_sup_subscript("superscript")?,
+            )),
+            "_" => indices.push(TensorIndex::Lower(
+                parser.handle_sup_subscript("subscript")?,
+            )),
+            "EOF" => break,
+            found => {
+                return Err(ParseError::with_token(
+                    ParseErrorKind::TensorExpectedIndex {
+                        found: found.to_owned(),
+                    },
+                    &token,
+                ));
+            }
+        }
+    }
+
+    parser.expect("EOF", true)?;
+    parser.gullet.end_group()?;
+    Ok(Some(indices))
+}
+
+/// Expand the index slots into the sequence that is laid out horizontally.
+///
+/// Each index hangs on its own vertical phantom of the base, so all slots
+/// see the same height and depth and line up with one another.
+fn index_slots(group: &ParseNodeTensor, indices: &[TensorIndex]) -> Vec<ParseNode> {
+    indices
+        .iter()
+        .map(|index| {
+            let phantom = ParseNode::Vphantom(ParseNodeVphantom {
+                mode: group.mode,
+                loc: None,
+                body: group.base.clone(),
+            });
+            let (sup, sub) = match index {
+                TensorIndex::Upper(script) => (Some(Box::new(script.clone())), None),
+                TensorIndex::Lower(script) => (None, Some(Box::new(script.clone()))),
+            };
+            ParseNode::SupSub(ParseNodeSupSub {
+                mode: group.mode,
+                loc: None,
+                base: Some(Box::new(phantom)),
+                sup,
+                sub,
+            })
+        })
+        .collect()
+}
+
+/// HTML builder for tensor nodes
+fn html_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<HtmlDomNode, ParseError> {
+    let ParseNode::Tensor(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Tensor,
+        }));
+    };
+
+    let mut body = index_slots(group, &group.pre);
+    body.push((*group.base).clone());
+    body.extend(index_slots(group, &group.post));
+
+    // Build the pieces individually: the indices hug the base, so no
+    // inter-atom spacing is inserted between them.
+    let inner = body
+        .iter()
+        .map(|part| build_html::build_group(ctx, part, options, None))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(make_span("mord", inner, Some(options), None).into())
+}
+
+/// MathML builder for tensor nodes
+fn mathml_builder(
+    node: &ParseNode,
+    options: &Options,
+    ctx: &KatexContext,
+) -> Result<MathDomNode, ParseError> {
+    let ParseNode::Tensor(group) = node else {
+        return Err(ParseError::new(ParseErrorKind::ExpectedNode {
+            node: NodeType::Tensor,
+        }));
+    };
+
+    let empty = || -> MathDomNode {
+        MathNode::builder()
+            .node_type(MathNodeType::None)
+            .build()
+            .into()
+    };
+
+    // <mmultiscripts> takes (subscript, superscript) pairs, so every slot
+    // fills one side and leaves the other empty.
+    let push_slots =
+        |children: &mut Vec<MathDomNode>, indices: &[TensorIndex]| -> Result<(), ParseError> {
+            for index in indices {
+                match index {
+                    TensorIndex::Upper(script) => {
+                        children.push(empty());
+                        children.push(build_mathml::build_group(ctx, script, options)?);
+                    }
+                    TensorIndex::Lower(script) => {
+                        children.push(build_mathml::build_group(ctx, script, options)?);
+                        children.push(empty());
+                    }
+                }
+            }
+            Ok(())
+        };
+
+    let mut children = vec![build_mathml::build_group(ctx, &group.base, options)?];
+    push_slots(&mut children, &group.post)?;
+    if !group.pre.is_empty() {
+        children.push(
+            MathNode::builder()
+                .node_type(MathNodeType::Mprescripts)
+                .build()
+                .into(),
+        );
+        push_slots(&mut children, &group.pre)?;
+    }
+
+    Ok(MathNode::builder()
+        .node_type(MathNodeType::Mmultiscripts)
+        .children(children)
+        .build()
+        .into())
+}
+
+/// Registers the \tensor function in the KaTeX context
+pub fn define_tensor(ctx: &mut KatexContext) {
+    ctx.define_function(FunctionDefSpec {
+        node_type: Some(NodeType::Tensor),
+        names: &["\\tensor"],
+        props: FunctionPropSpec {
+            num_args: 0,
+            examples: &[r"\tensor{R}{^a_{bc}}"],
+            ..Default::default()
+        },
+        handler: Some(|context, _args, _opt_args| {
+            // The arguments are scanned by hand since the index lists are
+            // sequences of scripts rather than ordinary math lists.
+            let loc = context.loc();
+            let parser = context.parser;
+            let pre = parse_indices(parser, true)?.unwrap_or_default();
+            let base = parser
+                .parse_argument_group(false, None)?
+                .ok_or_else(|| ParseError::new(ParseErrorKind::NullArgument))?;
+            let post = parse_indices(parser, false)?.unwrap_or_default();
+
+            Ok(ParseNode::Tensor(Box::new(ParseNodeTensor {
+                mode: parser.mode,
+                loc,
+                base: Box::new(base),
+                pre,
+                post,
+            })))
+        }),
+        html_builder: Some(html_builder),
+        mathml_builder: Some(mathml_builder),
+    });
+}
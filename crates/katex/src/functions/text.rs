@@ -9,7 +9,7 @@ use crate::dom_tree::HtmlDomNode;
 use crate::mathml_tree::MathDomNode;
 use crate::options::{FontShape, FontWeight, Options};
 use crate::parser::parse_node::{NodeType, ParseNode, ParseNodeText};
-use crate::types::{ArgType, Mode, ParseError, ParseErrorKind};
+use crate::types::{ArgType, CssProperty, Mode, ParseError, ParseErrorKind};
 use crate::{ClassList, build_html, build_mathml};
 use phf::phf_map;
 
@@ -130,13 +130,17 @@ fn html_builder(
         build_html::GroupType::True,
         (None, None),
     )?;
-    Ok(make_span(
+    let mut span = make_span(
         ClassList::Const(&["mord", "text"]),
         inner,
         Some(&new_options),
         None,
-    )
-    .into())
+    );
+    if options.text_wrap {
+        span.style
+            .insert(CssProperty::WhiteSpace, "normal".to_owned());
+    }
+    Ok(span.into())
 }
 
 /// MathML builder for text nodes
@@ -6,5 +6,6 @@
 
 // Export utility modules
 mod assemble_sup_sub;
+pub mod script_placement;
 
 pub use assemble_sup_sub::assemble_sup_sub;
@@ -0,0 +1,269 @@
+//! Placement of superscripts and subscripts beside a nucleus.
+//!
+//! This module holds the arithmetic of TeXbook rules 18a–f, separated from
+//! the HTML construction in `supsub.rs`. Every function works on plain
+//! measurements in ems of the nucleus' style, so the placement can be tested
+//! directly against font metrics and reused by other output backends.
+//!
+//! Primes need no special handling here: the parser turns `'` into an
+//! ordinary superscript (merged with any explicit `^`), which is placed by
+//! the same rules.
+
+use crate::font_metrics::FontMetrics;
+use crate::style::{DISPLAY, Style};
+
+/// Height and depth of a box, in ems of the nucleus' style.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScriptBox {
+    /// Height above the baseline
+    pub height: f64,
+    /// Depth below the baseline
+    pub depth: f64,
+}
+
+/// Vertical shifts of the scripts, in ems of the nucleus' style.
+///
+/// Both values are distances from the nucleus' baseline: `sup` is how far the
+/// superscript baseline is raised and `sub` how far the subscript baseline is
+/// lowered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScriptShifts {
+    /// Amount the superscript is raised
+    pub sup: f64,
+    /// Amount the subscript is lowered
+    pub sub: f64,
+}
+
+/// Rule 18a: the initial shifts derived from the nucleus box.
+///
+/// Single characters start at the baseline; any other nucleus hangs its
+/// scripts from its own height and depth. `drops` holds the script styles'
+/// `sup_drop` and `sub_drop`, already converted to ems of the nucleus' style.
+#[must_use]
+pub fn nucleus_shifts(
+    nucleus: ScriptBox,
+    is_character_box: bool,
+    drops: ScriptShifts,
+) -> ScriptShifts {
+    if is_character_box {
+        ScriptShifts::default()
+    } else {
+        ScriptShifts {
+            sup: nucleus.height - drops.sup,
+            sub: nucleus.depth + drops.sub,
+        }
+    }
+}
+
+/// Rule 18c: the minimum superscript shift for the nucleus' style.
+#[must_use]
+pub fn min_sup_shift(metrics: &FontMetrics, style: &Style) -> f64 {
+    if style == DISPLAY {
+        metrics.sup1
+    } else if style.cramped {
+        metrics.sup3
+    } else {
+        metrics.sup2
+    }
+}
+
+/// Rules 18b–e: the final shifts for the scripts that are present.
+///
+/// `initial` comes from [`nucleus_shifts`]. A lone subscript only has to
+/// clear `sub1` and keep its top below 4/5 of the x-height (18b). A
+/// superscript clears the style's minimum and keeps its bottom above 1/4 of
+/// the x-height (18c, d). With both scripts, the subscript is pushed down to
+/// `sub2` and the gap between them is widened to four rule thicknesses,
+/// moving the superscript up as long as its bottom stays below 4/5 of the
+/// x-height (18e).
+#[must_use]
+pub fn place_scripts(
+    metrics: &FontMetrics,
+    style: &Style,
+    initial: ScriptShifts,
+    sup: Option<ScriptBox>,
+    sub: Option<ScriptBox>,
+) -> ScriptShifts {
+    let mut shifts = initial;
+
+    if let Some(sup) = sup {
+        shifts.sup = shifts
+            .sup
+            .max(min_sup_shift(metrics, style))
+            .max(0.25f64.mul_add(metrics.x_height, sup.depth));
+    }
+
+    match (sup, sub) {
+        (Some(sup), Some(sub)) => {
+            shifts.sub = shifts.sub.max(metrics.sub2);
+
+            // Rule 18e
+            let min_gap = 4.0 * metrics.default_rule_thickness;
+            if (shifts.sup - sup.depth) - (sub.height - shifts.sub) < min_gap {
+                shifts.sub = min_gap - (shifts.sup - sup.depth) + sub.height;
+                let psi = 0.8f64.mul_add(metrics.x_height, -(shifts.sup - sup.depth));
+                if psi > 0.0 {
+                    shifts.sup += psi;
+                    shifts.sub -= psi;
+                }
+            }
+        }
+        (None, Some(sub)) => {
+            // Rule 18b
+            shifts.sub = shifts
+                .sub
+                .max(metrics.sub1)
+                .max(0.8f64.mul_add(-metrics.x_height, sub.height));
+        }
+        _ => {}
+    }
+
+    shifts
+}
+
+/// Horizontal offset of a subscript under a slanted nucleus.
+///
+/// The nucleus' italic correction only applies to the superscript, so the
+/// subscript is pulled back by the same amount.
+#[must_use]
+pub fn subscript_kern(italic_correction: f64) -> f64 {
+    -italic_correction
+}
+
+/// `\scriptspace` placed after the scripts, in ems of a style whose size
+/// multiplier is `size_multiplier`.
+///
+/// `\scriptspace` is 0.5pt regardless of the font size, so it is scaled back
+/// by the multiplier.
+#[must_use]
+pub fn script_space(metrics: &FontMetrics, size_multiplier: f64) -> f64 {
+    (0.5 / metrics.pt_per_em) / size_multiplier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_metrics::FONT_METRICS;
+    use crate::style::{SCRIPT, TEXT};
+
+    const EPS: f64 = 1e-9;
+
+    fn text_metrics() -> &'static FontMetrics {
+        &FONT_METRICS[0]
+    }
+
+    #[test]
+    fn test_nucleus_shifts() {
+        let nucleus = ScriptBox {
+            height: 1.0,
+            depth: 0.3,
+        };
+        let drops = ScriptShifts {
+            sup: 0.4,
+            sub: 0.05,
+        };
+        assert_eq!(
+            nucleus_shifts(nucleus, true, drops),
+            ScriptShifts::default()
+        );
+
+        let shifts = nucleus_shifts(nucleus, false, drops);
+        assert!((shifts.sup - 0.6).abs() < EPS);
+        assert!((shifts.sub - 0.35).abs() < EPS);
+    }
+
+    #[test]
+    fn test_min_sup_shift_by_style() {
+        let metrics = text_metrics();
+        assert_eq!(min_sup_shift(metrics, DISPLAY), metrics.sup1);
+        assert_eq!(min_sup_shift(metrics, DISPLAY.cramp()), metrics.sup3);
+        assert_eq!(min_sup_shift(metrics, TEXT), metrics.sup2);
+        assert_eq!(min_sup_shift(metrics, SCRIPT.cramp()), metrics.sup3);
+    }
+
+    #[test]
+    fn test_lone_superscript_uses_minimum() {
+        let metrics = text_metrics();
+        let sup = ScriptBox {
+            height: 0.4,
+            depth: 0.0,
+        };
+        let shifts = place_scripts(metrics, TEXT, ScriptShifts::default(), Some(sup), None);
+        assert_eq!(shifts.sup, metrics.sup2);
+        assert_eq!(shifts.sub, 0.0);
+
+        // A deep superscript is raised until its bottom clears x_height / 4
+        let deep = ScriptBox {
+            height: 0.4,
+            depth: 0.5,
+        };
+        let shifts = place_scripts(metrics, TEXT, ScriptShifts::default(), Some(deep), None);
+        assert!((shifts.sup - (0.5 + metrics.x_height / 4.0)).abs() < EPS);
+    }
+
+    #[test]
+    fn test_lone_subscript() {
+        let metrics = text_metrics();
+        let sub = ScriptBox {
+            height: 0.3,
+            depth: 0.0,
+        };
+        let shifts = place_scripts(metrics, TEXT, ScriptShifts::default(), None, Some(sub));
+        assert_eq!(shifts.sub, metrics.sub1);
+
+        // A tall subscript is lowered until its top is below 4/5 x_height
+        let tall = ScriptBox {
+            height: 1.2,
+            depth: 0.0,
+        };
+        let shifts = place_scripts(metrics, TEXT, ScriptShifts::default(), None, Some(tall));
+        assert!((shifts.sub - 0.8f64.mul_add(-metrics.x_height, 1.2)).abs() < EPS);
+    }
+
+    #[test]
+    fn test_clashing_scripts_are_separated() {
+        let metrics = text_metrics();
+        let sup = ScriptBox {
+            height: 0.5,
+            depth: 0.2,
+        };
+        let sub = ScriptBox {
+            height: 0.7,
+            depth: 0.1,
+        };
+        let shifts = place_scripts(metrics, TEXT, ScriptShifts::default(), Some(sup), Some(sub));
+
+        // Rule 18e: the gap is at least four rule thicknesses
+        let gap = (shifts.sup - sup.depth) - (sub.height - shifts.sub);
+        assert!(gap >= 4.0f64.mul_add(metrics.default_rule_thickness, -EPS));
+        // ...and the superscript's bottom does not rise above 4/5 x_height
+        assert!(shifts.sup - sup.depth <= 0.8f64.mul_add(metrics.x_height, EPS));
+    }
+
+    #[test]
+    fn test_both_scripts_without_clash() {
+        let metrics = text_metrics();
+        let small = ScriptBox {
+            height: 0.1,
+            depth: 0.0,
+        };
+        let shifts = place_scripts(
+            metrics,
+            TEXT,
+            ScriptShifts::default(),
+            Some(small),
+            Some(small),
+        );
+        assert_eq!(shifts.sup, metrics.sup2);
+        assert_eq!(shifts.sub, metrics.sub2);
+    }
+
+    #[test]
+    fn test_kerns() {
+        assert_eq!(subscript_kern(0.05), -0.05);
+        let metrics = text_metrics();
+        let space = script_space(metrics, 1.0);
+        assert!((space - 0.5 / metrics.pt_per_em).abs() < EPS);
+        assert!(2.0f64.mul_add(-space, script_space(metrics, 0.5)).abs() < EPS);
+    }
+}
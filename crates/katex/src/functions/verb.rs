@@ -24,6 +24,7 @@ pub fn define_verb(ctx: &mut KatexContext) {
         props: FunctionPropSpec {
             num_args: 0,
             allowed_in_text: true,
+            examples: &[r"\verb|x|", r"\verb*|a b|"],
             ..Default::default()
         },
         handler: Some(
@@ -11,10 +11,13 @@
 
 use crate::namespace::KeyMap;
 use crate::types::{
-    LexerInterface, ParseError, ParseErrorKind, Settings, SourceLocation, Token, TokenText,
+    LexerInterface, ParseError, ParseErrorKind, Settings, SourceLocation, StrictMode,
+    StrictSetting, Token, TokenText,
 };
 use crate::utils::AdvanceWhile as _;
+use alloc::borrow::Cow;
 use alloc::sync::Arc;
+use core::ops::Range;
 
 /// Returns the byte index of the last character in the string `s`
 /// that is **not** a Unicode combining diacritical mark
@@ -97,6 +100,45 @@ fn match_normal_char_with_accents(s: &str) -> Option<usize> {
     None
 }
 
+/// Whether `b` is a printable ASCII character other than `\\`, which always
+/// lexes as a single-character token
+#[inline]
+const fn is_plain_ascii(b: u8) -> bool {
+    matches!(b, 0x21..=0x5B | 0x5D..=0x7E)
+}
+
+/// Returns the length of the run of [plain ASCII](is_plain_ascii) bytes at
+/// the start of `bytes`.
+///
+/// Eight bytes are tested at a time with word-wide range checks, and only the
+/// word containing the end of the run is scanned byte by byte.
+fn plain_ascii_run(bytes: &[u8]) -> usize {
+    const ONES: u64 = u64::from_ne_bytes([0x01; 8]);
+    const HIGH: u64 = u64::from_ne_bytes([0x80; 8]);
+    const BACKSLASH: u64 = u64::from_ne_bytes([b'\\'; 8]);
+
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        let word = u64::from_ne_bytes(word);
+        // A byte below 0x21 borrows into its high bit, one above 0x7E
+        // carries into it, and a backslash becomes zero after the XOR
+        let below = word.wrapping_sub(ONES * 0x21) & !word;
+        let above = word.wrapping_add(ONES) | word;
+        let masked = word ^ BACKSLASH;
+        let backslash = masked.wrapping_sub(ONES) & !masked;
+        if (below | above | backslash) & HIGH != 0 {
+            let mut bytes = chunk.iter();
+            return len + bytes.advance_while(is_plain_ascii);
+        }
+        len += 8;
+    }
+    let mut rest = chunks.remainder().iter();
+    len + rest.advance_while(is_plain_ascii)
+}
+
 #[inline]
 fn match_control_symbol_after_bs(rest: &str) -> Option<usize> {
     let mut chars = rest.chars();
@@ -150,8 +192,11 @@ fn match_control_word_with_space_after_bs(rest: &str) -> Option<(usize, usize)>
 #[inline]
 fn exec(last_index: &mut usize, slice: &str) -> TokenMatch {
     debug_assert!(!slice.is_empty());
-    let bs = slice.as_bytes();
-    if is_ascii_space(bs[0])
+    if slice
+        .as_bytes()
+        .first()
+        .copied()
+        .is_some_and(is_ascii_space)
         && let Some(l) = match_space(slice)
     {
         *last_index += l;
@@ -162,9 +207,7 @@ fn exec(last_index: &mut usize, slice: &str) -> TokenMatch {
         };
     }
 
-    if bs[0] == b'\\' {
-        let rest = &slice[1..];
-
+    if let Some(rest) = slice.strip_prefix('\\') {
         if let Some(l) = match_control_space_after_bs(rest) {
             let m = 1 + l;
             *last_index += m;
@@ -247,12 +290,52 @@ struct TokenMatch {
     skip: usize,
 }
 
+/// Category codes of the characters a [`Lexer`] treats specially
+///
+/// The defaults, `%` as a comment character (14) and `~` as an active
+/// character (13), come from a static table. Changes are kept in an overlay
+/// that is only allocated on the first [`Catcodes::set`], and cloning shares
+/// the overlay until one of the clones changes a code. A lexer with the
+/// default codes, such as the one for each macro expansion, costs no
+/// allocation.
+#[derive(Debug, Clone, Default)]
+pub struct Catcodes {
+    overlay: Option<Arc<KeyMap<char, u8>>>,
+}
+
+impl Catcodes {
+    /// The category codes every lexer starts with
+    const DEFAULTS: &[(char, u8)] = &[('%', 14), ('~', 13)];
+
+    /// Returns the category code of `ch`, if it has one.
+    #[must_use]
+    pub fn get(&self, ch: char) -> Option<u8> {
+        if let Some(code) = self.overlay.as_ref().and_then(|overlay| overlay.get(&ch)) {
+            return Some(*code);
+        }
+        Self::DEFAULTS
+            .iter()
+            .find_map(|&(default, code)| (default == ch).then_some(code))
+    }
+
+    /// Sets the category code of `ch`, copying the overlay first if it is
+    /// shared.
+    pub fn set(&mut self, ch: char, code: u8) {
+        Arc::make_mut(self.overlay.get_or_insert_default()).insert(ch, code);
+    }
+}
+
 /// The core lexer for tokenizing LaTeX mathematical expressions in KaTeX.
 pub struct Lexer<'a> {
     input: Arc<str>,
     last_index: usize,
     settings: &'a Settings,
-    catcodes: KeyMap<char, u8>,
+    catcodes: Catcodes,
+    /// Byte range of the last run of plain ASCII characters found in the
+    /// input. Every position inside it starts a one-byte token, so `lex`
+    /// skips the general matcher there. The range stays valid across
+    /// [`Lexer::set_position`], since it only depends on the input.
+    plain_run: Range<usize>,
 }
 
 impl<'a> Lexer<'a> {
@@ -260,29 +343,26 @@ impl<'a> Lexer<'a> {
     /// string.
     #[must_use]
     pub fn new(input: Arc<str>, settings: &'a Settings) -> Self {
-        let mut catcodes = KeyMap::default();
-        catcodes.insert('%', 14); // comment character
-        catcodes.insert('~', 13); // active character
-
         Self {
             input,
             last_index: 0,
             settings,
-            catcodes,
+            catcodes: Catcodes::default(),
+            plain_run: 0..0,
         }
     }
 
     /// Sets the category code for a specific character, controlling its lexical
     /// behavior.
     pub fn set_catcode(&mut self, char: char, code: u8) {
-        self.catcodes.insert(char, code);
+        self.catcodes.set(char, code);
     }
 
     /// Retrieves the category code for a specific character, if one has been
     /// set.
     #[must_use]
     pub fn get_catcode(&self, ch: char) -> Option<u8> {
-        self.catcodes.get(&ch).copied()
+        self.catcodes.get(ch)
     }
 
     /// Tokenizes and returns the next token from the current position in the
@@ -302,8 +382,27 @@ impl<'a> Lexer<'a> {
             });
         }
 
-        let slice = &self.input[self.last_index..];
+        let start = self.last_index;
+        if self.plain_run.contains(&start)
+            && let Some(&byte) = self.input.as_bytes().get(start)
+            && self.catcodes.get(char::from(byte)) != Some(14)
+        {
+            self.last_index += 1;
+            return Ok(Token::new(
+                TokenText::slice(Arc::clone(&self.input), start, start + 1),
+                Some(SourceLocation {
+                    input: Arc::clone(&self.input),
+                    start,
+                    end: start + 1,
+                }),
+            ));
+        }
+
+        let slice = &self.input[start..];
         let matched = exec(&mut self.last_index, slice);
+        if matched.branch == BranchKind::NormalWithAccents && matched.mlen == 1 {
+            self.plain_run = self.scan_plain_run(start);
+        }
 
         let token_text = match matched.branch {
             BranchKind::Unknown => {
@@ -340,7 +439,7 @@ impl<'a> Lexer<'a> {
 
         if token_text.len() == 1
             && let Some(first_char) = token_text.as_str().chars().next()
-            && self.catcodes.get(&first_char) == Some(&14)
+            && self.catcodes.get(first_char) == Some(14)
         {
             // Comment character, skip to end of line
             if let Some(rel_pos) = slice.find('\n') {
@@ -363,6 +462,17 @@ impl<'a> Lexer<'a> {
         ))
     }
 
+    /// Finds the run of plain ASCII characters starting at `start`, leaving
+    /// out a final character that a combining mark may follow.
+    fn scan_plain_run(&self, start: usize) -> Range<usize> {
+        let bytes = self.input.as_bytes().get(start..).unwrap_or_default();
+        let mut len = plain_ascii_run(bytes);
+        if bytes.get(len).is_some_and(|b| !b.is_ascii()) {
+            len = len.saturating_sub(1);
+        }
+        start..start + len
+    }
+
     /// Returns the current byte position in the input string where the lexer
     /// will next read.
     #[must_use]
@@ -370,6 +480,12 @@ impl<'a> Lexer<'a> {
         self.last_index
     }
 
+    /// The input string being lexed
+    #[must_use]
+    pub const fn source(&self) -> &Arc<str> {
+        &self.input
+    }
+
     /// Manually sets the current position in the input string for lexing.
     pub const fn set_position(&mut self, last_index: usize) {
         self.last_index = last_index;
@@ -381,3 +497,178 @@ impl LexerInterface for Lexer<'_> {
         &self.input
     }
 }
+
+/// Commands whose URL argument is lexed with `%` as an ordinary character,
+/// see `Parser::parse_url_group`
+const URL_COMMANDS: &[&str] = &[r"\url", r"\href", r"\includegraphics"];
+
+/// Rewrites `input` into a canonical form that renders identically, so that
+/// render caches keyed on the input text hit for inputs that only differ in
+/// layout.
+///
+/// The input is run through the [`Lexer`], and the tokens are written back
+/// with:
+///
+/// - comments removed,
+/// - every run of whitespace collapsed to a single space,
+/// - leading whitespace and the whitespace the lexer skips after a control
+///   word dropped.
+///
+/// Other spaces are kept: they are significant in text mode and can end a
+/// delimited macro argument. The spaces before and after a comment are two
+/// tokens to the lexer, so they are kept apart by an empty comment. The `%`
+/// in the URL argument of `\url`, `\href` and `\includegraphics` is kept as
+/// well, since the parser lexes it as an ordinary character there.
+/// Input that cannot be lexed, including input ending in a comment without a
+/// newline, is returned unchanged, so that rendering it reports the same
+/// error. Combine the result with
+/// [`Settings::fingerprint`] for a complete cache key.
+///
+/// The mode of each token is not known here, so no space is dropped even
+/// where math mode ignores it. `a + b` and `a+b` get different forms, as do
+/// a comment with spaces on both sides and one with a space on one side.
+///
+/// ```rust
+/// use katex::canonicalize_input;
+///
+/// let tidy = canonicalize_input("\\frac{a}{b} % over b\n + \\sqrt{x}");
+/// let messy = canonicalize_input("  \\frac {a}{b}   % over b\n  + \\sqrt  {x}");
+/// assert_eq!(tidy, messy);
+/// assert_ne!(canonicalize_input("a + b"), canonicalize_input("a+b"));
+/// ```
+#[must_use]
+pub fn canonicalize_input(input: &str) -> Cow<'_, str> {
+    // Strict, so that a comment at the very end fails like it would in a
+    // strict render instead of being dropped
+    let settings = Settings::builder()
+        .strict(StrictSetting::Mode(StrictMode::Error))
+        .build();
+    let mut lexer = Lexer::new(Arc::from(input), &settings);
+    let mut output = String::with_capacity(input.len());
+    let mut after_control_word = false;
+    // Brace depth inside a URL argument, or `Some(0)` while waiting for it
+    let mut url_depth: Option<usize> = None;
+
+    loop {
+        let Ok(token) = lexer.lex() else {
+            return Cow::Borrowed(input);
+        };
+        let text = token.text.as_str();
+        if text == "EOF" {
+            break;
+        }
+        if text == " " {
+            if output.ends_with(' ') {
+                // Two space tokens, split by a comment in the input, render as
+                // two spaces in text mode; an empty comment keeps them apart
+                output.push_str("%\n ");
+            } else if !output.is_empty() {
+                output.push(' ');
+            }
+            after_control_word = false;
+            continue;
+        }
+
+        // A letter straight after a control word would become part of it
+        if after_control_word && text.bytes().next().is_some_and(is_ascii_alpha_or_at) {
+            output.push(' ');
+        }
+        output.push_str(text);
+        after_control_word = text
+            .strip_prefix('\\')
+            .is_some_and(|name| !name.is_empty() && name.bytes().all(is_ascii_alpha_or_at));
+
+        url_depth = match (url_depth, text) {
+            (None, _) if URL_COMMANDS.contains(&text) => Some(0),
+            (Some(0), "{") => {
+                lexer.set_catcode('%', 13);
+                Some(1)
+            }
+            (Some(1), "}") => {
+                lexer.set_catcode('%', 14);
+                None
+            }
+            (Some(depth), "{") if depth > 0 => Some(depth + 1),
+            (Some(depth), "}") if depth > 0 => Some(depth - 1),
+            (depth, _) => depth,
+        };
+    }
+
+    if output == input {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex_texts(input: &str) -> Vec<String> {
+        let settings = Settings::default();
+        let mut lexer = Lexer::new(Arc::from(input), &settings);
+        let mut texts = Vec::new();
+        loop {
+            let token = lexer.lex().unwrap();
+            if token.text == "EOF" {
+                return texts;
+            }
+            texts.push(token.text.to_string());
+        }
+    }
+
+    #[test]
+    fn test_plain_ascii_run_matches_bytewise_scan() {
+        for byte in 0..=u8::MAX {
+            for position in 0..20 {
+                let mut bytes = vec![b'a'; 20];
+                bytes[position] = byte;
+                let expected = bytes.iter().take_while(|&&b| is_plain_ascii(b)).count();
+                assert_eq!(plain_ascii_run(&bytes), expected, "{byte:#x} at {position}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_plain_runs_keep_combining_marks() {
+        assert_eq!(
+            lex_texts("abcdefghije\u{301}x"),
+            [
+                "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "e\u{301}", "x"
+            ]
+        );
+        assert_eq!(lex_texts(r"ab\cd ef"), ["a", "b", r"\cd", "e", "f"]);
+    }
+
+    #[test]
+    fn test_catcodes_overlay_is_copied_on_write() {
+        let mut catcodes = Catcodes::default();
+        assert_eq!(catcodes.get('%'), Some(14));
+        assert_eq!(catcodes.get('~'), Some(13));
+        assert_eq!(catcodes.get('a'), None);
+        assert!(catcodes.overlay.is_none());
+
+        catcodes.set('%', 13);
+        let shared = catcodes.clone();
+        catcodes.set('~', 12);
+        assert_eq!(shared.get('%'), Some(13));
+        assert_eq!(shared.get('~'), Some(13));
+        assert_eq!(catcodes.get('~'), Some(12));
+    }
+
+    #[test]
+    fn test_plain_runs_survive_repositioning() {
+        let settings = Settings::default();
+        let mut lexer = Lexer::new(Arc::from("abcdefgh%x\nyz"), &settings);
+        lexer.lex().unwrap();
+        lexer.set_position(5);
+        assert_eq!(lexer.lex().unwrap().text, "f");
+        lexer.set_catcode('%', 12);
+        lexer.set_position(8);
+        assert_eq!(lexer.lex().unwrap().text, "%");
+        lexer.set_catcode('%', 14);
+        lexer.set_position(8);
+        assert_eq!(lexer.lex().unwrap().text, "y");
+    }
+}
@@ -8,18 +8,35 @@
 #![warn(clippy::std_instead_of_alloc)]
 #![warn(clippy::std_instead_of_core)]
 #![allow(clippy::approx_constant)]
+// Rendering untrusted input must report errors instead of panicking; tests
+// are free to unwrap
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable
+    )
+)]
 
 extern crate alloc;
+pub mod braille;
 pub mod build_common;
 pub mod build_html;
 pub mod build_mathml;
 pub mod build_tree;
+pub mod content_mathml;
 pub mod context;
+pub mod convert;
 pub mod core;
 pub mod define_environment;
 pub mod define_function;
 pub mod delimiter;
 pub mod dom_tree;
+pub mod escape;
 pub mod font_metrics;
 pub mod font_metrics_data;
 pub mod functions;
@@ -27,14 +44,24 @@ pub mod lexer;
 pub mod macro_expander;
 pub mod macros;
 pub mod mathml_tree;
+pub mod measure;
+#[cfg(feature = "nonblocking")]
+pub mod nonblocking;
 pub mod options;
 /// Utilities for working with parse trees and converting them to ParseNode
 pub mod parse_tree;
 /// Core parsing logic for LaTeX mathematical expressions.
 pub mod parser;
+pub mod rendered;
+pub mod reverse;
+pub mod security;
 pub mod spacing_data;
+pub mod speech;
+pub mod steps;
 pub mod stretchy;
 pub mod style;
+pub mod sublanguage;
+pub mod support;
 pub mod svg_geometry;
 pub mod symbols;
 pub mod tree;
@@ -49,6 +76,9 @@ pub mod wide_character;
 
 pub use crate::types::ClassList;
 
+/// Canonical form of an input expression, for keying render caches
+pub use crate::lexer::canonicalize_input;
+
 /// Global context for KaTeX operations, containing all registered functions,
 /// HTML/MathML builders, symbols, environments, and macros. This context is
 /// essential for parsing and rendering mathematical expressions and provides
@@ -141,6 +171,38 @@ pub use crate::context::KatexContext;
 /// [`Settings`] objects rather than creating new ones for each render call.
 pub use crate::core::render_to_string;
 
+/// Render an expression and list the CSS classes its markup uses
+///
+/// This is [`render_to_string`] plus a summary of the classes in the output,
+/// for CSS purging tools that would otherwise have to keep every rule of
+/// `katex.css`. Collect the classes of all formulas on a page and keep the
+/// rules that mention them:
+///
+/// ```rust
+/// use std::collections::BTreeSet;
+///
+/// use katex::{KatexContext, Settings, render_to_output};
+///
+/// let ctx = KatexContext::default();
+/// let settings = Settings::default();
+///
+/// let mut used = BTreeSet::new();
+/// for formula in [r"\frac{a}{b}", r"\sqrt{x}"] {
+///     let output = render_to_output(&ctx, formula, &settings).unwrap();
+///     used.extend(output.classes_used);
+/// }
+/// assert!(used.contains("mfrac"));
+/// assert!(used.contains("sqrt"));
+/// assert!(!used.contains("overline"));
+/// ```
+pub use crate::core::render_to_output;
+
+/// Markup and CSS class summary returned by [`render_to_output`]
+pub use crate::core::RenderOutput;
+
+/// Rendered markup marked as safe to embed in templates without escaping
+pub use crate::rendered::RenderedMath;
+
 /// Parse an expression and return the parse tree
 ///
 /// This function parses a LaTeX expression and returns the raw parse tree,
@@ -198,6 +260,127 @@ pub use crate::core::render_to_dom_tree;
 /// A `Result` containing the HTML DOM tree or a `ParseError`
 pub use crate::core::render_to_html_tree;
 
+/// Measure where the first relation of an expression falls
+///
+/// Slides and similar layouts often render each equation of a derivation as
+/// a separate element. To line them up on their `=` signs, measure every
+/// expression, merge the results and render each one with the merged
+/// alignment:
+///
+/// ```rust
+/// use katex::{Alignment, KatexContext, Settings, measure_alignment, render_to_string};
+///
+/// let ctx = KatexContext::default();
+/// let settings = Settings::default();
+/// let lines = ["x = 1", "x + y + z = 10"];
+///
+/// let shared = lines
+///     .iter()
+///     .map(|line| measure_alignment(&ctx, line, &settings))
+///     .try_fold(Alignment::default(), |shared, line| {
+///         line.map(|line| shared.merge(line))
+///     })
+///     .unwrap();
+///
+/// let aligned = Settings::builder().align_to(shared).build();
+/// for line in lines {
+///     let html = render_to_string(&ctx, line, &aligned).unwrap();
+///     assert!(html.contains("katex-html"));
+/// }
+/// ```
+///
+/// Only the HTML output is padded; the boxes holding the formulas must all
+/// start at the same position for the relations to line up.
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to measure
+/// * `settings` - Settings the expression will be rendered with
+///
+/// # Returns
+/// A `Result` containing the [`Alignment`] or a `ParseError`
+pub use crate::core::measure_alignment;
+
+/// Describe an expression in words, MathSpeak style
+///
+/// The speech is returned as [`speech::SpeechSpan`]s that point back at the
+/// source range of the node each chunk describes, so that read-aloud tools
+/// can highlight the formula as it is spoken:
+///
+/// ```rust
+/// use katex::speech::{SpeechOptions, Verbosity, speech_text};
+/// use katex::{KatexContext, Settings, render_to_speech};
+///
+/// let ctx = KatexContext::default();
+/// let options = SpeechOptions {
+///     verbosity: Verbosity::Brief,
+///     ..SpeechOptions::default()
+/// };
+/// let spans = render_to_speech(&ctx, r"\frac{1}{x}", &Settings::default(), &options).unwrap();
+/// assert_eq!(speech_text(&spans), "StartFrac 1 Over x EndFrac");
+/// ```
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to describe
+/// * `settings` - Settings for parsing
+/// * `options` - Verbosity and locale of the speech
+///
+/// # Returns
+/// A `Result` containing the speech spans or a `ParseError`
+pub use crate::core::render_to_speech;
+
+/// Transcribe an expression into braille
+///
+/// The result is a string of Unicode braille patterns in the Nemeth Code or
+/// in Unified English Braille, ready for a braille display or embosser:
+///
+/// ```rust
+/// use katex::braille::BrailleCode;
+/// use katex::{KatexContext, Settings, render_to_braille};
+///
+/// let ctx = KatexContext::default();
+/// let braille = render_to_braille(&ctx, "x^2", &Settings::default(), BrailleCode::Nemeth).unwrap();
+/// assert_eq!(braille, "\u{282D}\u{2818}\u{2806}");
+/// ```
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to transcribe
+/// * `settings` - Settings for parsing
+/// * `code` - The braille code to use
+///
+/// # Returns
+/// A `Result` containing the braille string or a `ParseError`
+pub use crate::core::render_to_braille;
+
+/// Render an expression to Content MathML
+///
+/// Content MathML encodes the meaning of a formula rather than its layout,
+/// as operators applied to arguments. The structure is inferred on a
+/// best-effort basis; unrecognised parts become `<semantics>` annotations:
+///
+/// ```rust
+/// use katex::{KatexContext, Settings, render_to_content_mathml};
+///
+/// let ctx = KatexContext::default();
+/// let markup = render_to_content_mathml(&ctx, "x+1", &Settings::default()).unwrap();
+/// assert_eq!(
+///     markup,
+///     "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">\
+///      <apply><plus/><ci>x</ci><cn>1</cn></apply></math>"
+/// );
+/// ```
+///
+/// # Parameters
+/// * `ctx` - The KaTeX context
+/// * `expression` - The LaTeX expression to convert
+/// * `settings` - Settings for parsing
+///
+/// # Returns
+/// A `Result` containing the `<math>` markup or a `ParseError`
+pub use crate::core::render_to_content_mathml;
+
 /// Retrieves character metrics for a specific character in a given font family
 /// and mode.
 ///
@@ -371,7 +554,13 @@ pub use crate::font_metrics_data::FontMetricsData;
 /// error handling patterns.
 pub use crate::types::ParseError;
 
-pub use crate::types::OutputFormat;
+/// Widths on either side of a formula's first relation, used by
+/// [`Settings::align_to`].
+pub use crate::measure::Alignment;
+
+/// Lists the supported commands and environments with their support status,
+/// for generating a "Supported Functions" page. See the [`support`] module.
+pub use crate::support::{SupportEntry, support_table};
 /// Main configuration structure for KaTeX rendering behavior.
 ///
 /// This struct contains all the settings that control how mathematical
@@ -414,6 +603,7 @@ pub use crate::types::OutputFormat;
 /// - `error_color`: `"#cc0000"` (red)
 /// - `max_size`: `f64::INFINITY`
 /// - `max_expand`: `1000`
+/// - `max_depth`: `100`
 /// - Other fields have appropriate defaults
 ///
 /// # Performance Considerations
@@ -421,15 +611,19 @@ pub use crate::types::OutputFormat;
 /// For high-performance applications, create [`Settings`] objects once and
 /// reuse them rather than creating new ones for each render operation.
 pub use crate::types::Settings;
+pub use crate::types::{Charset, OutputFormat, OverflowMode};
 /// Strictness and trust configuration types used by [`Settings`].
 ///
 /// These enums and callback types mirror KaTeX's JavaScript configuration and
 /// control validation of potentially unsafe commands (`trust`) as well as how
 /// strictly to enforce LaTeX syntax (`strict`).
 pub use crate::types::{
-    StrictFunction, StrictMode, StrictReturn, StrictSetting, TrustContext, TrustFunction,
-    TrustSetting,
+    LegacyStrictFunction, StrictContext, StrictFunction, StrictMode, StrictReturn, StrictSetting,
+    TrustContext, TrustFunction, TrustSetting,
 };
+/// Hook for formatting the digit runs of MathML output, used by
+/// [`Settings::number_formatter`].
+pub use crate::types::{NumberFormatFunction, NumberFormatter};
 
 // Build utilities for advanced users creating custom DOM structures
 /// Creates a line span with the given className, options, and thickness.
@@ -452,3 +646,50 @@ pub mod namespace;
 
 /// Current version of the KaTeX Rust implementation
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+mod upstream {
+    include!(concat!(env!("OUT_DIR"), "/upstream_generated.rs"));
+}
+
+/// The KaTeX stylesheet (`katex.min.css`) matching the generated markup
+///
+/// It is vendored from the KaTeX release this crate tracks, so its class
+/// names always agree with the HTML produced by [`render_to_string`]. Its
+/// `@font-face` rules load fonts from a relative `fonts/` directory, which
+/// must be served next to the stylesheet.
+///
+/// Empty when the crate was built without the vendored stylesheet.
+pub const KATEX_CSS: &str = upstream::CSS;
+
+/// Version of the upstream KaTeX release this crate tracks
+///
+/// Compare it with [`stylesheet_version`] to detect a stylesheet from a
+/// different release. Empty when the crate was built without the vendored
+/// upstream data.
+pub const UPSTREAM_VERSION: &str = upstream::VERSION;
+
+/// Returns the KaTeX version a stylesheet was built for.
+///
+/// KaTeX stylesheets carry their version in a `.katex-version::after` rule.
+/// Returns `None` when `css` has no such rule.
+///
+/// # Examples
+///
+/// ```rust
+/// let css = r#".katex .katex-version::after{content:"0.16.22"}"#;
+/// assert_eq!(katex::stylesheet_version(css), Some("0.16.22"));
+///
+/// // At startup, check the stylesheet being served against the renderer
+/// if let Some(version) = katex::stylesheet_version(katex::KATEX_CSS) {
+///     assert_eq!(version, katex::UPSTREAM_VERSION);
+/// }
+/// ```
+#[must_use]
+pub fn stylesheet_version(css: &str) -> Option<&str> {
+    let (_, rule) = css.split_once(".katex-version")?;
+    let (_, content) = rule.split_once("content:")?;
+    let content = content.trim_start();
+    let quote = content.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let (version, _) = content[1..].split_once(quote)?;
+    Some(version)
+}
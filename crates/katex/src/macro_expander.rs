@@ -8,8 +8,13 @@ use crate::lexer::Lexer;
 use crate::macros::builtins::BUILTIN_MACROS;
 use crate::namespace::{KeyMap, Namespace};
 use crate::types::TokenText;
-use crate::types::{Mode, ParseError, ParseErrorKind, Settings, SourceLocation, Token};
+use crate::types::trace::detokenize;
+use crate::types::{
+    Mode, ParseError, ParseErrorKind, Settings, SourceLocation, Token, TraceEventKind,
+};
+use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::mem;
 
 use crate::macros::{
     MacroArg, MacroContextInterface, MacroDefinition, MacroExpansion, MacroExpansionResult,
@@ -27,6 +32,15 @@ pub const IMPLICIT_COMMANDS: phf::Set<&str> = phf::phf_set! {
     "\\nolimits",
 };
 
+/// A balanced group on the stack of a [`MacroExpander`]
+#[derive(Debug, Clone, Copy)]
+struct ScannedGroup {
+    /// Stack index of the opening brace
+    open: usize,
+    /// Number of tokens from the opening to the closing brace, inclusive
+    len: usize,
+}
+
 /// MacroExpander: expands macros until only non-macro tokens remain
 pub struct MacroExpander<'a> {
     settings: &'a Settings,
@@ -34,6 +48,14 @@ pub struct MacroExpander<'a> {
     lexer: Lexer<'a>,
     macros: Namespace<'a, MacroDefinition>,
     stack: Vec<Token>, // tokens in REVERSE order
+    /// Balanced `{...}` groups on `stack` found by
+    /// [`MacroExpander::scan_argument`], sorted by the index of their opening
+    /// brace. A group stays known until its opening brace is popped, so
+    /// nested arguments are not scanned again at every level.
+    scanned_groups: Vec<ScannedGroup>,
+    /// Tokens lexed ahead by [`MacroExpander::peek_n`], in input order. They
+    /// come after everything on `stack` and before the rest of the input.
+    lookahead: VecDeque<Token>,
     mode: Mode,
     /// No global object in Rust; pass context reference around
     ctx: &'a KatexContext,
@@ -47,25 +69,24 @@ impl<'a> MacroExpander<'a> {
         let globals = settings.macros.borrow_mut();
         let macros = Namespace::new(&BUILTIN_MACROS, globals);
 
-        let mut me = Self {
+        Self {
             lexer: Lexer::new(Arc::from(input), settings),
             settings,
             expansion_count: 0,
             macros,
             mode,
             stack: Vec::new(),
-
+            scanned_groups: Vec::new(),
+            lookahead: VecDeque::new(),
             ctx,
-        };
-        // Initialize by feeding the input
-        me.feed(input);
-        me
+        }
     }
 
     /// Feed a new input string to the same MacroExpander (with existing macros
     /// etc.).
     pub fn feed(&mut self, input: &str) {
         self.lexer = Lexer::new(Arc::from(input), self.settings);
+        self.lookahead.clear();
     }
 
     /// Switches between text and math modes
@@ -79,10 +100,153 @@ impl<'a> MacroExpander<'a> {
     }
 
     /// Sets the category code for a character in the lexer
+    ///
+    /// Tokens lexed ahead by [`MacroExpander::peek_n`] are dropped and lexed
+    /// again, so that the new category code applies to them too.
     pub fn set_catcode(&mut self, char: char, code: u8) {
+        if let Some(loc) = self.lookahead.front().and_then(|token| token.loc.as_ref()) {
+            self.lexer.set_position(loc.start);
+        }
+        self.lookahead.clear();
         self.lexer.set_catcode(char, code);
     }
 
+    /// Takes the input up to the next occurrence of `delimiter` as a single
+    /// token, without lexing or expanding it, and leaves the lexer at the
+    /// delimiter.
+    ///
+    /// Tokens that were pushed back or lexed ahead are given back to the
+    /// input first. This fails if any of them came from a macro expansion,
+    /// since those have no raw text, or if the rest of the input does not
+    /// contain `delimiter`.
+    pub fn scan_raw_until(&mut self, delimiter: &str) -> Result<Token, ParseError> {
+        let start = self.raw_input_start()?;
+        let Some(len) = self.lexer.source()[start..].find(delimiter) else {
+            return Err(ParseError::new(ParseErrorKind::UnterminatedRawText {
+                delimiter: delimiter.to_owned(),
+            }));
+        };
+        let end = start + len;
+        Ok(self.take_raw_input(start, end, end))
+    }
+
+    /// Takes the next `{...}` group of the input as a single token holding
+    /// the text between the braces, without lexing or expanding it, and
+    /// leaves the lexer after the closing brace.
+    ///
+    /// Braces nest, except those escaped with a backslash. Like
+    /// [`MacroExpander::scan_raw_until`], this fails if the upcoming tokens
+    /// came from a macro expansion, and also if the input does not continue
+    /// with a closed group. `context` names the group in that error.
+    pub fn scan_raw_group(&mut self, context: &str) -> Result<Token, ParseError> {
+        let start = self.raw_input_start()?;
+        let source = self.lexer.source();
+        let open = start + (source.len() - start - source[start..].trim_start().len());
+        if !source[open..].starts_with('{') {
+            return Err(ParseError::new(ParseErrorKind::ExpectedGroupAs {
+                context: context.to_owned(),
+            }));
+        }
+
+        let mut depth = 0usize;
+        let mut chars = source[open..].char_indices();
+        let close = loop {
+            let Some((offset, ch)) = chars.next() else {
+                return Err(ParseError::new(ParseErrorKind::UnterminatedRawText {
+                    delimiter: "}".to_owned(),
+                }));
+            };
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break open + offset;
+                    }
+                }
+                _ => {}
+            }
+        };
+        Ok(self.take_raw_input(open + 1, close, close + 1))
+    }
+
+    /// Gives back the tokens that were pushed back or lexed ahead, and
+    /// returns where the first of them starts in the input.
+    fn raw_input_start(&self) -> Result<usize, ParseError> {
+        let source = self.lexer.source();
+        let input_start = |token: &Token| {
+            token
+                .loc
+                .as_ref()
+                .filter(|loc| Arc::ptr_eq(&loc.input, source))
+                .map(|loc| loc.start)
+        };
+        if !self.stack.iter().all(|token| input_start(token).is_some()) {
+            return Err(ParseError::new(ParseErrorKind::RawTextFromExpansion));
+        }
+        Ok(self
+            .stack
+            .last()
+            .or_else(|| self.lookahead.front())
+            .and_then(input_start)
+            .unwrap_or_else(|| self.lexer.position()))
+    }
+
+    /// Drops the upcoming tokens, makes the lexer continue at `resume`, and
+    /// returns the input from `start` to `end` as a token.
+    fn take_raw_input(&mut self, start: usize, end: usize, resume: usize) -> Token {
+        let source = Arc::clone(self.lexer.source());
+        self.stack.clear();
+        self.scanned_groups.clear();
+        self.lookahead.clear();
+        self.lexer.set_position(resume);
+
+        Token {
+            text: TokenText::slice(Arc::clone(&source), start, end),
+            loc: Some(SourceLocation {
+                input: source,
+                start,
+                end,
+            }),
+            noexpand: None,
+            treat_as_relax: None,
+        }
+    }
+
+    /// Returns the `k`-th upcoming token without expanding or consuming
+    /// anything, with `peek_n(0)` being the next token.
+    ///
+    /// Tokens past the pushed-back ones are lexed into a lookahead buffer and
+    /// handed out from there later, so looking several tokens ahead costs no
+    /// more than reading them once. At the end of the input, the `EOF` token
+    /// is returned for every further `k`.
+    pub fn peek_n(&mut self, k: usize) -> Result<&Token, ParseError> {
+        let Some(ahead) = k.checked_sub(self.stack.len()) else {
+            return self
+                .stack
+                .get(self.stack.len() - 1 - k)
+                .ok_or_else(|| ParseError::new(ParseErrorKind::EmptyMacroExpanderStack));
+        };
+        while self.lookahead.len() <= ahead {
+            let token = self.lexer.lex()?;
+            self.lookahead.push_back(token);
+        }
+        self.lookahead
+            .get(ahead)
+            .ok_or_else(|| ParseError::new(ParseErrorKind::EmptyMacroExpanderStack))
+    }
+
+    /// Takes the next token from the lookahead buffer, or the lexer
+    fn next_input_token(&mut self) -> Result<Token, ParseError> {
+        match self.lookahead.pop_front() {
+            Some(token) => Ok(token),
+            None => self.lexer.lex(),
+        }
+    }
+
     /// Add a token to the stack
     pub fn push_token(&mut self, token: Token) {
         self.stack.push(token);
@@ -93,31 +257,56 @@ impl<'a> MacroExpander<'a> {
         self.stack.extend(tokens);
     }
 
+    /// The tokens pushed back onto the expander, in input order, without
+    /// those of the input that were not lexed yet
+    #[must_use]
+    pub fn pushed_tokens(&self) -> impl DoubleEndedIterator<Item = &Token> {
+        self.stack.iter().rev()
+    }
+
     /// Find a macro argument without expanding tokens and append the array of
     /// tokens to the token stack Returns a Token representing the argument
     /// range, or None for missing optional arg
+    ///
+    /// The argument is left in place on the stack, with an `EOF` token after
+    /// it, so finding it does not move the tokens it holds. Groups scanned
+    /// while finding it are remembered, so that scanning an argument nested
+    /// in it later skips over them instead of reading them again.
     pub fn scan_argument(&mut self, is_optional: bool) -> Result<Option<Token>, ParseError> {
-        let (start_tok, end_tok, tokens);
-        if is_optional {
-            self.consume_spaces()?;
-            if self.future_mut()?.text != "[" {
+        self.consume_spaces()?;
+        let first = if is_optional {
+            if self.peek_n(0)?.text != "[" {
                 return Ok(None);
             }
-            let start = self.pop_token()?; // drop [
-            let arg = self.consume_arg(Some(&vec!["]".to_owned()]))?;
-            start_tok = start;
-            end_tok = arg.end;
-            tokens = arg.tokens;
+            1
         } else {
-            let arg = self.consume_arg(None)?;
-            start_tok = arg.start;
-            end_tok = arg.end;
-            tokens = arg.tokens;
-        }
+            0
+        };
+        let end = self.find_argument_end(first, is_optional)?;
 
-        // indicate the end of an argument
-        self.push_token(Token::new("EOF", None));
-        self.push_tokens(tokens);
+        let top = self.stack.len() - 1;
+        let start_tok = self.stack[top].clone();
+        let end_tok = if is_optional || start_tok.text == "{" {
+            // The closing `]` or `}` becomes the end of the argument
+            mem::replace(&mut self.stack[top - end], Token::new("EOF", None))
+        } else {
+            // A single token, with nothing scanned above the stack top
+            self.stack.insert(top, Token::new("EOF", None));
+            start_tok.clone()
+        };
+        if is_optional {
+            self.pop_stack(); // drop [
+            // Remove outermost braces if present
+            if end > first + 1
+                && self.stack[top - first].text == "{"
+                && self.stack[top - (end - 1)].text == "}"
+            {
+                self.remove_from_stack(top - (end - 1));
+                self.pop_stack();
+            }
+        } else if start_tok.text == "{" {
+            self.pop_stack();
+        }
 
         // compute range token with empty text
         let loc = SourceLocation::range(start_tok.loc, end_tok.loc);
@@ -129,6 +318,118 @@ impl<'a> MacroExpander<'a> {
         }))
     }
 
+    /// Finds the last token of an argument that starts with the `first`-th
+    /// upcoming token, and makes sure the argument is on the stack.
+    ///
+    /// An optional argument ends with the first `]` outside braces, any other
+    /// argument is a single token or a `{...}` group. Groups found on the way
+    /// are added to [`MacroExpander::scanned_groups`].
+    fn find_argument_end(&mut self, first: usize, is_optional: bool) -> Result<usize, ParseError> {
+        let mut opens = Vec::new();
+        let mut groups = Vec::new();
+        let mut pos = first;
+        let end = loop {
+            let token = self.peek_n(pos)?;
+            match token.text.as_str() {
+                "{" => match self.scanned_group_len(pos) {
+                    // Skip to the closing brace
+                    Some(len) => pos += len - 1,
+                    None => opens.push(pos),
+                },
+                "}" => match opens.pop() {
+                    Some(open) => groups.push((open, pos)),
+                    None => {
+                        return Err(ParseError::with_token(
+                            ParseErrorKind::ExtraCloseBrace,
+                            token,
+                        ));
+                    }
+                },
+                "EOF" => {
+                    let expected = if is_optional { "]" } else { "}" };
+                    return Err(ParseError::with_token(
+                        ParseErrorKind::UnexpectedEndOfMacroArgument {
+                            expected: expected.to_owned(),
+                        },
+                        token,
+                    ));
+                }
+                "]" if is_optional && opens.is_empty() => break pos,
+                _ => {}
+            }
+            if !is_optional && opens.is_empty() {
+                break pos;
+            }
+            pos += 1;
+        };
+
+        // Move the tokens lexed ahead below the stack
+        if let Some(missing) = (end + 1)
+            .checked_sub(self.stack.len())
+            .filter(|&missing| missing > 0)
+        {
+            self.stack
+                .splice(0..0, self.lookahead.drain(..missing).rev());
+            for group in &mut self.scanned_groups {
+                group.open += missing;
+            }
+        }
+        let top = self.stack.len() - 1;
+        // Record the groups from the last one in the input, which is the
+        // lowest on the stack, to keep the list sorted
+        groups.sort_unstable_by_key(|&(open, _)| open);
+        for (open, close) in groups.into_iter().rev() {
+            let group = ScannedGroup {
+                open: top - open,
+                len: close - open + 1,
+            };
+            let at = self
+                .scanned_groups
+                .partition_point(|known| known.open < group.open);
+            self.scanned_groups.insert(at, group);
+        }
+        Ok(end)
+    }
+
+    /// Length of the known group opened by the `k`-th upcoming token
+    fn scanned_group_len(&self, k: usize) -> Option<usize> {
+        let open = self.stack.len().checked_sub(k + 1)?;
+        let at = self
+            .scanned_groups
+            .binary_search_by_key(&open, |group| group.open)
+            .ok()?;
+        Some(self.scanned_groups[at].len)
+    }
+
+    /// Pops the top of the stack, forgetting the group it opened
+    fn pop_stack(&mut self) -> Option<Token> {
+        let token = self.stack.pop()?;
+        let len = self.stack.len();
+        while self
+            .scanned_groups
+            .last()
+            .is_some_and(|group| group.open >= len)
+        {
+            self.scanned_groups.pop();
+        }
+        Some(token)
+    }
+
+    /// Removes the token at `index` from the stack, moving the known groups
+    /// above it down
+    fn remove_from_stack(&mut self, index: usize) {
+        self.stack.remove(index);
+        let at = self
+            .scanned_groups
+            .partition_point(|group| group.open <= index);
+        for group in &mut self.scanned_groups[at..] {
+            if group.open + 1 - group.len <= index {
+                group.len -= 1;
+            }
+            group.open -= 1;
+        }
+    }
+
     /// Consume specified number of arguments with optional delimiters
     fn consume_args_with_delims(
         &mut self,
@@ -221,11 +522,11 @@ impl<'a> MacroExpander<'a> {
                         continue;
                     } else if tok.text.len() == 1
                         && let Ok(parsed) = tok.text.as_str().parse::<usize>()
+                        && let Some(arg) = parsed.checked_sub(1).and_then(|index| args.get(index))
                     {
-                        let arg_index = parsed - 1;
                         // replace placeholder (#n) with arg tokens
                         // remove the two tokens (# and n) at positions i-1 and i
-                        tokens.splice((i as usize - 1)..=(i as usize), args[arg_index].clone());
+                        tokens.splice((i as usize - 1)..=(i as usize), arg.clone());
                         i -= 2; // step past inserted
                         continue;
                     }
@@ -240,6 +541,15 @@ impl<'a> MacroExpander<'a> {
                 i -= 1;
             }
         }
+        if let Some(trace) = &self.settings.trace {
+            trace.record(
+                TraceEventKind::MacroExpansion {
+                    name,
+                    expansion: detokenize(tokens.iter().rev()),
+                },
+                top_token.loc.as_ref(),
+            );
+        }
         self.push_tokens(tokens.clone());
         Ok(Some(tokens.len() as isize))
     }
@@ -252,8 +562,7 @@ impl<'a> MacroExpander<'a> {
         while self.stack.len() > old_len {
             if self.expand_once_internal(true)?.is_none() {
                 let mut token = self
-                    .stack
-                    .pop()
+                    .pop_stack()
                     .ok_or_else(|| ParseError::new(ParseErrorKind::MacroStackUnexpectedlyEmpty))?;
                 if token.treat_as_relax == Some(true) {
                     // the expansion of \noexpand is the token itself
@@ -308,6 +617,8 @@ impl<'a> MacroExpander<'a> {
             }
         }
 
+        // Like KaTeX, a macro body is lexed with the default category codes,
+        // not the ones in effect where the macro is expanded
         let mut body_lexer = Lexer::new(Arc::from(expansion), self.settings);
         let mut tokens: Vec<Token> = Vec::new();
         while let Ok(tok) = body_lexer.lex() {
@@ -345,7 +656,7 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
 
     fn future_mut(&mut self) -> Result<Token, ParseError> {
         if self.stack.is_empty() {
-            let tok = self.lexer.lex()?;
+            let tok = self.next_input_token()?;
             self.push_token(tok);
         }
         self.stack
@@ -354,10 +665,13 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
             .ok_or_else(|| ParseError::new(ParseErrorKind::EmptyMacroExpanderStack))
     }
 
+    fn peek_n(&mut self, k: usize) -> Result<&Token, ParseError> {
+        MacroExpander::peek_n(self, k)
+    }
+
     fn pop_token(&mut self) -> Result<Token, ParseError> {
         self.future_mut()?;
-        self.stack
-            .pop()
+        self.pop_stack()
             .ok_or_else(|| ParseError::new(ParseErrorKind::EmptyMacroExpanderStack))
     }
 
@@ -365,7 +679,7 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
         loop {
             let token = self.future_mut()?;
             if token.text == " " {
-                self.stack.pop();
+                self.pop_stack();
             } else {
                 break;
             }
@@ -386,8 +700,7 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
         loop {
             if self.expand_once_internal(false)?.is_none() {
                 let mut token = self
-                    .stack
-                    .pop()
+                    .pop_stack()
                     .ok_or_else(|| ParseError::new(ParseErrorKind::MacroStackUnexpectedlyEmpty))?;
                 if token.treat_as_relax == Some(true) {
                     token.set_text("\\relax");
@@ -535,7 +848,10 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
                 };
             }
 
-            self.ctx.functions.contains_key(name) && !self.ctx.functions[name].primitive
+            self.ctx
+                .functions
+                .get(name)
+                .is_some_and(|function| !function.primitive)
         }
     }
 
@@ -547,3 +863,132 @@ impl<'a> MacroContextInterface<'a> for MacroExpander<'a> {
         self.macros.end_group()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::iter;
+
+    fn texts(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(Token::text).collect()
+    }
+
+    fn with_expander(input: &str, test: impl FnOnce(&mut MacroExpander<'_>)) {
+        let settings = Settings::default();
+        let ctx = KatexContext::default();
+        let mut expander = MacroExpander::new(input, &settings, Mode::Math, &ctx);
+        test(&mut expander);
+    }
+
+    #[test]
+    fn test_peek_n_does_not_consume() {
+        with_expander("abc", |expander| {
+            assert_eq!(expander.peek_n(2).unwrap().text, "c");
+            assert_eq!(expander.peek_n(0).unwrap().text, "a");
+            let popped: Vec<Token> = iter::repeat_with(|| expander.pop_token().unwrap())
+                .take(4)
+                .collect();
+            assert_eq!(texts(&popped), ["a", "b", "c", "EOF"]);
+        });
+    }
+
+    #[test]
+    fn test_peek_n_sees_pushed_back_tokens_first() {
+        with_expander("cd", |expander| {
+            assert_eq!(expander.peek_n(1).unwrap().text, "d");
+            expander.push_tokens(vec![Token::new("b", None), Token::new("a", None)]);
+            let peeked: Vec<Token> = (0..5)
+                .map(|k| expander.peek_n(k).unwrap().clone())
+                .collect();
+            assert_eq!(texts(&peeked), ["a", "b", "c", "d", "EOF"]);
+            assert_eq!(expander.pop_token().unwrap().text, "a");
+            assert_eq!(expander.peek_n(2).unwrap().text, "d");
+        });
+    }
+
+    #[test]
+    fn test_peek_n_past_the_end_returns_eof() {
+        with_expander("x", |expander| {
+            assert_eq!(expander.peek_n(5).unwrap().text, "EOF");
+            assert_eq!(expander.pop_token().unwrap().text, "x");
+            assert_eq!(expander.pop_token().unwrap().text, "EOF");
+        });
+    }
+
+    #[test]
+    fn test_set_catcode_relexes_lookahead() {
+        with_expander("a%b\nc", |expander| {
+            assert_eq!(expander.peek_n(1).unwrap().text, "c");
+            expander.set_catcode('%', 13);
+            assert_eq!(expander.pop_token().unwrap().text, "a");
+            assert_eq!(expander.pop_token().unwrap().text, "%");
+            assert_eq!(expander.pop_token().unwrap().text, "b");
+        });
+    }
+
+    #[test]
+    fn test_scan_raw_until_gives_back_lookahead() {
+        with_expander(r"a\frac{%b\end{x}c", |expander| {
+            assert_eq!(expander.pop_token().unwrap().text, "a");
+            assert_eq!(expander.peek_n(1).unwrap().text, "{");
+            let raw = expander.scan_raw_until(r"\end{x}").unwrap();
+            assert_eq!(raw.text, r"\frac{%b");
+            assert_eq!(expander.pop_token().unwrap().text, r"\end");
+            assert!(matches!(
+                *expander.scan_raw_until("z").unwrap_err().kind,
+                ParseErrorKind::UnterminatedRawText { .. }
+            ));
+        });
+    }
+
+    #[test]
+    fn test_scan_raw_until_rejects_expanded_tokens() {
+        with_expander("ab", |expander| {
+            expander.push_token(Token::new("c", None));
+            assert!(matches!(
+                *expander.scan_raw_until("b").unwrap_err().kind,
+                ParseErrorKind::RawTextFromExpansion
+            ));
+        });
+    }
+
+    #[test]
+    fn test_expansion_leaves_lookahead_in_place() {
+        with_expander(r"\@firstoftwo{a}{b}c", |expander| {
+            assert_eq!(expander.peek_n(7).unwrap().text, "c");
+            let expanded: Vec<Token> = iter::repeat_with(|| expander.expand_next_token().unwrap())
+                .take(3)
+                .collect();
+            assert_eq!(texts(&expanded), ["a", "c", "EOF"]);
+        });
+    }
+
+    #[test]
+    fn test_scan_argument_leaves_the_argument_in_place() {
+        with_expander("[{a{b}c}] x", |expander| {
+            expander.scan_argument(true).unwrap();
+            let pushed: Vec<Token> = expander.pushed_tokens().cloned().collect();
+            assert_eq!(texts(&pushed), ["a", "{", "b", "}", "c", "EOF"]);
+            // The inner group is known and is not scanned again
+            assert_eq!(expander.pop_token().unwrap().text, "a");
+            assert_eq!(expander.scanned_group_len(0), Some(3));
+            let arg = expander.scan_argument(false).unwrap().unwrap();
+            assert_eq!(arg.loc.map(|loc| (loc.start, loc.end)), Some((3, 6)));
+            let pushed: Vec<Token> = expander.pushed_tokens().cloned().collect();
+            assert_eq!(texts(&pushed), ["b", "EOF", "c", "EOF"]);
+        });
+    }
+
+    #[test]
+    fn test_popping_a_group_forgets_it() {
+        with_expander("{{a}}", |expander| {
+            expander.scan_argument(false).unwrap();
+            assert_eq!(expander.scanned_group_len(0), Some(3));
+            let open = expander.pop_token().unwrap();
+            assert_eq!(expander.scanned_group_len(0), None);
+            // Pushing the brace back does not make the group known again
+            expander.push_token(open);
+            assert_eq!(expander.scanned_group_len(0), None);
+        });
+    }
+}
@@ -128,8 +128,7 @@ fn braket_helper(
             let mut doubled = double;
             if !double && !middle_double.is_empty() {
                 // Mimic \@ifnextchar - check if next token is |
-                let next_token = context.future_mut()?;
-                if next_token.text == "|" {
+                if context.peek_n(0)?.text == "|" {
                     context.pop_token()?; // consume the |
                     doubled = true;
                 }
@@ -381,7 +380,7 @@ pub const BUILTIN_MACROS: phf::Map<&str, MacroDefinition> = phf_map! {
             while let Ok(tok) = context.future_mut() && tok.text != "EOF" {
                 let digit = text_to_value(tok.text.as_str());
                 if let Some(digit) = digit && digit < base {
-                    number = number * base + digit;
+                    number = number.saturating_mul(base).saturating_add(digit);
                     context.pop_token()?;
                 } else {
                     break;
@@ -534,6 +533,9 @@ pub const BUILTIN_MACROS: phf::Map<&str, MacroDefinition> = phf_map! {
     // \underbar from TeXbook p 353
     "\\underbar" => MacroDefinition::StaticStr("\\underline{\\text{#1}}"),
 
+    // \arraystretch from lttab.dtx, defined so that \renewcommand can set it
+    "\\arraystretch" => MacroDefinition::StaticStr("1"),
+
     // \not is defined by base/fontmath.ltx via
     "\\not" => MacroDefinition::StaticStr("\\html@mathml{\\mathrel{\\mathrlap\\@not}}{\\char\"338}"),
 
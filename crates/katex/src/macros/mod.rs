@@ -6,6 +6,7 @@
 
 use alloc::sync::Arc;
 use core::fmt::{self, Debug};
+use core::ptr;
 
 use crate::{
     KatexContext, ParseError,
@@ -219,6 +220,29 @@ impl MacroDefinition {
     }
 }
 
+/// Definitions are equal when they expand alike: string bodies compare by
+/// text, token bodies ignore source locations, and functions compare by
+/// identity.
+impl PartialEq for MacroDefinition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Expansion(a), Self::Expansion(b)) => {
+                a.num_args == b.num_args
+                    && a.delimiters == b.delimiters
+                    && a.unexpandable == b.unexpandable
+                    && a.tokens.len() == b.tokens.len()
+                    && a.tokens
+                        .iter()
+                        .zip(&b.tokens)
+                        .all(|(a, b)| a.text == b.text && a.noexpand == b.noexpand)
+            }
+            (Self::Function(a), Self::Function(b)) => Arc::ptr_eq(a, b),
+            (Self::StaticFunction(a), Self::StaticFunction(b)) => ptr::fn_addr_eq(*a, *b),
+            _ => self.as_str().is_some() && self.as_str() == other.as_str(),
+        }
+    }
+}
+
 impl Debug for MacroDefinition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -361,6 +385,19 @@ pub trait MacroContextInterface<'a> {
     /// is empty.
     fn future_mut(&mut self) -> Result<Token, ParseError>;
 
+    /// Peeks at the `k`-th upcoming token without expanding or consuming
+    /// anything.
+    ///
+    /// `peek_n(0)` is the token [`Self::future_mut`] returns, without cloning
+    /// it. Larger `k` look further ahead, for constructs that decide on more
+    /// than one token; the tokens stay in the stream.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the upcoming [`Token`], or the `EOF` token past the end
+    /// of the input.
+    fn peek_n(&mut self, k: usize) -> Result<&Token, ParseError>;
+
     /// Consumes and returns the next token from the stream.
     ///
     /// This method removes the next token from the input stream and returns it,
@@ -8,12 +8,15 @@
 use crate::ParseError;
 #[cfg(feature = "wasm")]
 use crate::dom_tree::create_class;
+use crate::dom_tree::insert_classes;
+use crate::escape::escape_into;
 use crate::tree::{DocumentFragment, VirtualNode};
 use crate::units::make_em;
-use crate::utils::escape_into;
+use crate::utils::with_stack;
 #[cfg(feature = "wasm")]
 use crate::web_context::WebContext;
 use crate::{namespace::KeyMap, types::ClassList, types::CssStyle};
+use alloc::collections::BTreeSet;
 use bon::bon;
 use core::fmt::{self, Debug, Write as _};
 use strum::AsRefStr;
@@ -103,6 +106,12 @@ pub enum MathNodeType {
     Mphantom,
     /// `<mglyph>` element
     Mglyph,
+    /// `<mmultiscripts>` element
+    Mmultiscripts,
+    /// `<mprescripts>` element
+    Mprescripts,
+    /// `<none>` placeholder inside `<mmultiscripts>`
+    None,
 }
 
 /// Get the appropriate space character based on width
@@ -247,6 +256,20 @@ impl MathNode {
     }
 }
 
+impl MathNode {
+    /// Add the CSS classes of this node and its descendants to `used`,
+    /// including those set through a `class` attribute.
+    pub fn collect_classes(&self, used: &mut BTreeSet<String>) {
+        insert_classes(&self.classes, used);
+        if let Some(class) = self.attributes.get("class") {
+            used.extend(class.split_whitespace().map(str::to_owned));
+        }
+        for child in &self.children {
+            child.collect_classes(used);
+        }
+    }
+}
+
 impl VirtualNode for MathNode {
     fn write_markup(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), ParseError> {
         map_fmt(write!(fmt, "<{}", self.node_type.as_ref()))?;
@@ -272,9 +295,11 @@ impl VirtualNode for MathNode {
 
         map_fmt(fmt.write_char('>'))?;
 
-        for child in &self.children {
-            child.write_markup(fmt)?;
-        }
+        with_stack(|| {
+            self.children
+                .iter()
+                .try_for_each(|child| child.write_markup(fmt))
+        })?;
 
         map_fmt(write!(fmt, "</{}>", self.node_type.as_ref()))?;
         Ok(())
@@ -407,6 +432,19 @@ impl VirtualNode for SpaceNode {
 }
 
 impl MathDomNode {
+    /// Add the CSS classes of this node and its descendants to `used`.
+    pub fn collect_classes(&self, used: &mut BTreeSet<String>) {
+        match self {
+            Self::Math(node) => node.collect_classes(used),
+            Self::Fragment(fragment) => {
+                for child in &fragment.children {
+                    child.collect_classes(used);
+                }
+            }
+            Self::Text(_) | Self::Space(_) => {}
+        }
+    }
+
     /// Convert the MathDomNode to plain text
     pub fn to_text(&self) -> String {
         match self {
@@ -0,0 +1,353 @@
+//! Width estimation for built HTML trees
+//!
+//! KaTeX only tracks the height and depth of the boxes it builds; the width is
+//! left to the browser. Some layout decisions have to be made before the
+//! markup reaches a browser, though, such as shrinking a formula that would
+//! overflow its container. This module walks a finished [`HtmlDomNode`] tree
+//! and adds up the advance widths of its glyphs and explicit spaces.
+//!
+//! The result is an estimate: it follows the structure KaTeX generates
+//! (sizing spans, vertical lists, laps) but does not reproduce CSS layout.
+//! SVG stretchy elements and images without an explicit width count as zero.
+//!
+//! [`alignment_of`] splits the estimate at the first top-level relation, so
+//! that formulas rendered separately can be lined up on their `=` signs.
+//!
+//! Hosts that measure text without a browser can use [`glyph_widths`], the
+//! advance widths the renderer assumes for every glyph of the KaTeX fonts.
+
+use core::fmt;
+use std::sync::OnceLock;
+
+use crate::dom_tree::{HtmlDomNode, Span};
+use crate::font_metrics::EXTRA_CHARACTER_MAP;
+use crate::font_metrics_data::FONT_METRICS_INDEX;
+use crate::options::SIZE_MULTIPLIERS;
+use crate::types::{ClassList, CssProperty, CssStyle};
+
+/// Widths on either side of a formula's alignment point, in ems.
+///
+/// The alignment point is the left edge of the first relation (`=`, `<`,
+/// `\to`, ...) on the outer level of the formula. A formula without one is
+/// aligned at its right edge, so `after` is zero.
+///
+/// Measure each formula with [`crate::measure_alignment`], combine the results
+/// with [`Alignment::merge`], and pass the combined value back as
+/// [`crate::Settings::align_to`] when rendering. Every formula is then padded
+/// to the same overall width with its relation at the same offset.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Alignment {
+    /// Width in front of the alignment point
+    pub before: f64,
+    /// Width from the alignment point to the end of the formula
+    pub after: f64,
+}
+
+impl Alignment {
+    /// Smallest alignment that has room for both `self` and `other`.
+    #[must_use]
+    pub const fn merge(self, other: Self) -> Self {
+        Self {
+            before: self.before.max(other.before),
+            after: self.after.max(other.after),
+        }
+    }
+}
+
+/// Splits the width of a `katex-html` node at its first top-level relation.
+///
+/// Top-level atoms are the children of the `base` spans that the HTML builder
+/// creates between line-break opportunities; relations nested inside groups,
+/// fractions or scripts do not count.
+#[must_use]
+pub fn alignment_of(html: &HtmlDomNode) -> Alignment {
+    let total = estimate_width(html);
+    let mut before = 0.0;
+    let HtmlDomNode::DomSpan(root) = html else {
+        return Alignment {
+            before: total,
+            after: 0.0,
+        };
+    };
+    for part in &root.children {
+        let atoms = match part {
+            HtmlDomNode::DomSpan(base) if base.classes.contains("base") => &base.children,
+            _ => {
+                before += estimate_width(part);
+                continue;
+            }
+        };
+        for atom in atoms {
+            if atom.has_class("mrel") {
+                return Alignment {
+                    before,
+                    after: (total - before).max(0.0),
+                };
+            }
+            before += estimate_width(atom);
+        }
+    }
+    Alignment {
+        before: total,
+        after: 0.0,
+    }
+}
+
+/// Estimated width of `node` in ems of the font size it is placed in.
+#[must_use]
+pub fn estimate_width(node: &HtmlDomNode) -> f64 {
+    match node {
+        HtmlDomNode::DomSpan(span) => span_width(span),
+        HtmlDomNode::Anchor(anchor) => {
+            let content: f64 = anchor.children.iter().map(estimate_width).sum();
+            content + spacing(&anchor.style)
+        }
+        HtmlDomNode::Img(img) => em_length(&img.style, CssProperty::Width).unwrap_or(0.0),
+        HtmlDomNode::Symbol(symbol) => symbol.width + spacing(&symbol.style),
+        HtmlDomNode::Fragment(fragment) => fragment.children.iter().map(estimate_width).sum(),
+        HtmlDomNode::SvgNode(_) | HtmlDomNode::MathML(_) => 0.0,
+    }
+}
+
+/// Advance widths of the glyphs in one KaTeX font
+#[derive(Debug)]
+pub struct FontWidths {
+    /// Font name as used by the metrics, such as `Main-Regular`
+    pub font: &'static str,
+    /// Advance width in ems of each character, sorted by character
+    pub widths: Box<[(char, f64)]>,
+}
+
+impl FontWidths {
+    /// Advance width of `ch` in ems, as used when rendering.
+    ///
+    /// Like the renderer, this falls back to a similar-looking Latin glyph for
+    /// accented and Cyrillic letters that have no metrics of their own.
+    #[must_use]
+    pub fn width(&self, ch: char) -> Option<f64> {
+        self.lookup(ch)
+            .or_else(|| self.lookup(*EXTRA_CHARACTER_MAP.get(&ch)?))
+    }
+
+    fn lookup(&self, ch: char) -> Option<f64> {
+        let index = self.widths.binary_search_by_key(&ch, |&(c, _)| c).ok()?;
+        self.widths.get(index).map(|&(_, width)| width)
+    }
+}
+
+/// Advance widths of every glyph in the built-in KaTeX fonts, sorted by font.
+///
+/// The table is built from the font metrics on first use and cached. Custom
+/// metrics added to a [`crate::KatexContext`] are not included.
+#[must_use]
+pub fn glyph_widths() -> &'static [FontWidths] {
+    static TABLE: OnceLock<Box<[FontWidths]>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut fonts: Vec<_> = FONT_METRICS_INDEX
+            .entries()
+            .map(|(&font, metrics)| {
+                let mut widths: Vec<_> = metrics
+                    .entries()
+                    .filter_map(|(&code, metrics)| Some((char::from_u32(code)?, metrics.width)))
+                    .collect();
+                widths.sort_unstable_by_key(|&(ch, _)| ch);
+                FontWidths {
+                    font,
+                    widths: widths.into_boxed_slice(),
+                }
+            })
+            .collect();
+        fonts.sort_unstable_by_key(|font| font.font);
+        fonts.into_boxed_slice()
+    })
+}
+
+/// Writes [`glyph_widths`] as compact JSON for hosts in other languages.
+///
+/// The output maps each font name to an object from decimal code points to
+/// widths in ems, e.g. `{"Main-Regular":{"32":0.25,...},...}`.
+pub fn write_glyph_widths_json<W: fmt::Write>(out: &mut W) -> fmt::Result {
+    out.write_char('{')?;
+    for (i, font) in glyph_widths().iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "\"{}\":{{", font.font)?;
+        for (j, &(ch, width)) in font.widths.iter().enumerate() {
+            if j > 0 {
+                out.write_char(',')?;
+            }
+            write!(out, "\"{}\":{width}", u32::from(ch))?;
+        }
+        out.write_char('}')?;
+    }
+    out.write_char('}')
+}
+
+/// Width of a span, scaled by any size change it applies
+fn span_width(span: &Span<HtmlDomNode>) -> f64 {
+    let classes = &span.classes;
+    if ["llap", "rlap", "clap", "katex-mathml", "tag"]
+        .iter()
+        .any(|class| classes.contains(class))
+    {
+        return 0.0;
+    }
+
+    let content = em_length(&span.style, CssProperty::Width).unwrap_or_else(|| {
+        let widths = span.children.iter().map(estimate_width);
+        if classes.contains("vlist") {
+            // The rows of a vertical list are stacked, not laid side by side
+            widths.fold(0.0, f64::max)
+        } else {
+            widths.sum()
+        }
+    });
+
+    (content + spacing(&span.style)) * size_change(classes)
+}
+
+/// Ratio between the font size set by `sizing reset-sizeN sizeM` classes and
+/// the surrounding font size
+fn size_change(classes: &ClassList) -> f64 {
+    if !classes.contains("sizing") {
+        return 1.0;
+    }
+    let size = |prefix: &str| {
+        classes
+            .iter()
+            .find_map(|class| class.strip_prefix(prefix)?.parse::<usize>().ok())
+            .filter(|size| (1..=SIZE_MULTIPLIERS.len()).contains(size))
+            .map(|size| SIZE_MULTIPLIERS[size - 1])
+    };
+    match (size("reset-size"), size("size")) {
+        (Some(from), Some(to)) => to / from,
+        _ => 1.0,
+    }
+}
+
+/// Horizontal space an element's margins and padding add to its content
+fn spacing(style: &CssStyle) -> f64 {
+    [
+        CssProperty::MarginLeft,
+        CssProperty::MarginRight,
+        CssProperty::PaddingLeft,
+        CssProperty::PaddingRight,
+    ]
+    .into_iter()
+    .filter_map(|property| em_length(style, property))
+    .sum()
+}
+
+/// Reads a property given in ems; other units are ignored
+fn em_length(style: &CssStyle, property: CssProperty) -> Option<f64> {
+    style.get(property)?.strip_suffix("em")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KatexContext;
+    use crate::build_common::make_span;
+    use crate::dom_tree::SymbolNode;
+    use crate::font_metrics::get_character_metrics;
+    use crate::types::Mode;
+    use alloc::borrow::Cow;
+
+    fn glyph(width: f64) -> HtmlDomNode {
+        SymbolNode::builder().text("x").width(width).build().into()
+    }
+
+    #[test]
+    fn test_horizontal_sum_and_spacing() {
+        let mut span = make_span("mord", vec![glyph(0.5), glyph(0.25)], None, None);
+        assert!((estimate_width(&span.clone().into()) - 0.75).abs() < 1e-9);
+
+        span.style.insert(CssProperty::MarginRight, "0.1667em");
+        span.style.insert(CssProperty::MarginLeft, "2pt");
+        assert!((estimate_width(&span.into()) - 0.9167).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_vlist_rows_take_the_widest() {
+        let vlist = make_span("vlist", vec![glyph(0.5), glyph(1.5)], None, None);
+        assert!((estimate_width(&vlist.into()) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_alignment_splits_at_first_relation() {
+        let rel = make_span("mrel", vec![glyph(0.75)], None, None);
+        let base = make_span(
+            "base",
+            vec![glyph(1.0), rel.clone().into(), glyph(0.5), rel.into()],
+            None,
+            None,
+        );
+        let html = make_span("katex-html", vec![base.into()], None, None).into();
+        assert_eq!(
+            alignment_of(&html),
+            Alignment {
+                before: 1.0,
+                after: 2.0
+            }
+        );
+
+        let plain = make_span("base", vec![glyph(1.5)], None, None);
+        let html = make_span("katex-html", vec![plain.into()], None, None).into();
+        let alignment = alignment_of(&html);
+        assert_eq!(alignment.after, 0.0);
+        assert_eq!(
+            alignment.merge(Alignment {
+                before: 1.0,
+                after: 2.0
+            }),
+            Alignment {
+                before: 1.5,
+                after: 2.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_sizing_and_laps() {
+        let classes = vec![
+            Cow::Borrowed("sizing"),
+            Cow::Borrowed("reset-size6"),
+            Cow::Borrowed("size3"),
+        ];
+        let script = make_span(classes, vec![glyph(1.0)], None, None);
+        assert!((estimate_width(&script.into()) - 0.7).abs() < 1e-9);
+
+        let lap = make_span("rlap", vec![glyph(1.0)], None, None);
+        assert!(estimate_width(&lap.into()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_glyph_widths_match_renderer_metrics() {
+        let ctx = KatexContext::default();
+        let fonts = glyph_widths();
+        assert!(fonts.is_sorted_by_key(|font| font.font));
+        for font in fonts {
+            for &(ch, width) in &*font.widths {
+                let metrics = get_character_metrics(&ctx, ch, font.font, Mode::Math)
+                    .unwrap()
+                    .unwrap();
+                assert!((metrics.width - width).abs() < f64::EPSILON);
+            }
+        }
+
+        let main = fonts.iter().find(|f| f.font == "Main-Regular").unwrap();
+        assert_eq!(main.width('x'), Some(0.52778));
+        // Falls back to the unaccented glyph, as the renderer does
+        assert_eq!(main.width('\u{e5}'), main.width('a'));
+    }
+
+    #[test]
+    fn test_glyph_widths_json() {
+        let mut json = String::new();
+        write_glyph_widths_json(&mut json).unwrap();
+        assert!(json.starts_with("{\"AMS-Regular\":{\""));
+        assert!(json.contains("\"Main-Regular\":{\"32\":0.25,"));
+        assert!(json.ends_with("}}"));
+    }
+}
@@ -4,6 +4,10 @@
 //! A `Namespace` refers to a space of nameable things like macros or lengths,
 //! which can be set either globally or local to a nested group using an
 //! undo stack similar to how TeX implements this functionality.
+//!
+//! A [`Snapshot`] freezes the definitions of a namespace so they can be put
+//! back later with [`Namespace::restore`], and [`diff`] lists what changed
+//! between two snapshots, e.g. the `\gdef`s made by one notebook cell.
 
 use core::cell::RefMut;
 
@@ -110,6 +114,31 @@ impl<'a, V: Clone> Namespace<'a, V> {
         self.current.get(name).or_else(|| self.builtins.get(name))
     }
 
+    /// Copy the current (non-builtin) definitions.
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<V> {
+        Snapshot::from(&*self.current)
+    }
+
+    /// Replace the current definitions with those of `snapshot`.
+    ///
+    /// Every difference is applied as a global `set`, so the restored
+    /// definitions also outlive any groups that are currently open.
+    pub fn restore(&mut self, snapshot: &Snapshot<V>) {
+        let stale: Vec<_> = self
+            .current
+            .keys()
+            .filter(|name| !snapshot.definitions.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in stale {
+            self.set(&name, None, true);
+        }
+        for (name, value) in &snapshot.definitions {
+            self.set(name, Some(value.clone()), true);
+        }
+    }
+
     /// Set the current value of a name, and optionally set it globally too.
     ///
     /// Local `set` sets the current value and (when appropriate) adds an undo
@@ -151,3 +180,190 @@ impl<'a, V: Clone> Namespace<'a, V> {
         }
     }
 }
+
+/// A frozen copy of the definitions of a [`Namespace`]
+///
+/// Builtins are not included; they never change.
+#[derive(Debug, Clone)]
+pub struct Snapshot<V> {
+    definitions: Mapping<V>,
+}
+
+impl<V> Snapshot<V> {
+    /// The definition of `name` when the snapshot was taken.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.definitions.get(name)
+    }
+
+    /// Number of definitions in the snapshot.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    /// Whether the snapshot holds no definitions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Defined names, in sorted order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.definitions.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl<V> Default for Snapshot<V> {
+    fn default() -> Self {
+        Self {
+            definitions: Mapping::default(),
+        }
+    }
+}
+
+impl<V: Clone> From<&Mapping<V>> for Snapshot<V> {
+    fn from(mapping: &Mapping<V>) -> Self {
+        Self {
+            definitions: mapping.clone(),
+        }
+    }
+}
+
+impl<V> From<Snapshot<V>> for Mapping<V> {
+    fn from(snapshot: Snapshot<V>) -> Self {
+        snapshot.definitions
+    }
+}
+
+/// One difference between two [`Snapshot`]s, as reported by [`diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'s, V> {
+    /// `name` is defined only in the later snapshot.
+    Added {
+        /// The defined name
+        name: &'s str,
+        /// Its new definition
+        value: &'s V,
+    },
+    /// `name` is defined only in the earlier snapshot.
+    Removed {
+        /// The undefined name
+        name: &'s str,
+        /// Its former definition
+        value: &'s V,
+    },
+    /// `name` is defined in both snapshots, differently.
+    Changed {
+        /// The redefined name
+        name: &'s str,
+        /// Its former definition
+        from: &'s V,
+        /// Its new definition
+        to: &'s V,
+    },
+}
+
+impl<V> Change<'_, V> {
+    /// The name this change affects.
+    #[must_use]
+    pub const fn name(&self) -> &str {
+        match self {
+            Self::Added { name, .. } | Self::Removed { name, .. } | Self::Changed { name, .. } => {
+                name
+            }
+        }
+    }
+}
+
+/// Lists the definitions that differ between `before` and `after`, sorted by
+/// name.
+#[must_use]
+pub fn diff<'s, V: PartialEq>(
+    before: &'s Snapshot<V>,
+    after: &'s Snapshot<V>,
+) -> Vec<Change<'s, V>> {
+    let mut changes: Vec<_> = after
+        .definitions
+        .iter()
+        .filter_map(|(name, to)| match before.definitions.get(name) {
+            None => Some(Change::Added { name, value: to }),
+            Some(from) if from != to => Some(Change::Changed { name, from, to }),
+            Some(_) => None,
+        })
+        .chain(
+            before
+                .definitions
+                .iter()
+                .filter(|(name, _)| !after.definitions.contains_key(*name))
+                .map(|(name, value)| Change::Removed { name, value }),
+        )
+        .collect();
+    changes.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+
+    static BUILTINS: phf::Map<&'static str, u32> = phf::phf_map! { "\\builtin" => 0 };
+
+    #[test]
+    fn test_restore_outlives_open_groups() {
+        let globals = RefCell::new(Mapping::default());
+        let mut namespace = Namespace::new(&BUILTINS, globals.borrow_mut());
+        namespace.set("\\a", Some(1), true);
+        let snapshot = namespace.snapshot();
+
+        namespace.begin_group();
+        namespace.set("\\a", Some(2), false);
+        namespace.set("\\b", Some(3), true);
+        namespace.restore(&snapshot);
+        assert_eq!(namespace.get("\\a"), Some(&1));
+        assert!(!namespace.has("\\b"));
+        namespace.end_group().unwrap();
+
+        assert_eq!(namespace.get("\\a"), Some(&1));
+        assert!(!namespace.has("\\b"));
+        assert!(namespace.has("\\builtin"));
+        assert_eq!(snapshot.names(), ["\\a"]);
+    }
+
+    #[test]
+    fn test_diff_is_sorted_by_name() {
+        let before = Snapshot::from(&Mapping::from_iter([
+            ("\\kept".to_owned(), 1),
+            ("\\changed".to_owned(), 1),
+            ("\\removed".to_owned(), 1),
+        ]));
+        let after = Snapshot::from(&Mapping::from_iter([
+            ("\\kept".to_owned(), 1),
+            ("\\changed".to_owned(), 2),
+            ("\\added".to_owned(), 3),
+        ]));
+        assert_eq!(
+            diff(&before, &after),
+            [
+                Change::Added {
+                    name: "\\added",
+                    value: &3
+                },
+                Change::Changed {
+                    name: "\\changed",
+                    from: &1,
+                    to: &2
+                },
+                Change::Removed {
+                    name: "\\removed",
+                    value: &1
+                },
+            ]
+        );
+        assert!(diff(&after, &after).is_empty());
+    }
+}
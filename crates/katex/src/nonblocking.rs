@@ -0,0 +1,284 @@
+//! Rendering from async code without blocking the runtime
+//!
+//! Rendering is CPU-bound and a very large formula can take long enough to
+//! stall every other task on an async executor thread. The functions here
+//! move the work onto Tokio's blocking thread pool instead:
+//!
+//! - [`render`] hands a single render to [`tokio::task::spawn_blocking`].
+//! - [`RenderPool`] additionally bounds how many renders run at once and can
+//!   give up on a render after a per-task timeout.
+//!
+//! Both require a Tokio runtime, and [`RenderPool::with_timeout`] requires
+//! one with the time driver enabled. The module is only available with the
+//! `nonblocking` feature.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! use katex::nonblocking::RenderPool;
+//! use katex::{KatexContext, Settings};
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread()
+//!     .enable_time()
+//!     .build()
+//!     .unwrap();
+//! let pool = RenderPool::new(Arc::new(KatexContext::default()), 4)
+//!     .with_timeout(Duration::from_secs(5));
+//! let html = runtime
+//!     .block_on(pool.render(r"\frac{a}{b}".to_owned(), Settings::default()))
+//!     .unwrap();
+//! assert!(html.contains("mfrac"));
+//! ```
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinError};
+use tokio::time;
+
+use crate::context::KatexContext;
+use crate::core::render_to_string;
+use crate::types::{ParseError, Settings};
+
+/// Errors produced by an asynchronous render
+#[derive(Debug, Error)]
+pub enum RenderError {
+    /// The expression could not be parsed or built
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    /// The render did not finish within the pool's timeout
+    #[error("rendering timed out after {0:?}")]
+    TimedOut(Duration),
+    /// The render panicked on its worker thread
+    #[error("rendering panicked")]
+    Panicked,
+    /// The runtime shut down before the render finished
+    #[error("rendering was cancelled")]
+    Cancelled,
+}
+
+impl From<JoinError> for RenderError {
+    fn from(error: JoinError) -> Self {
+        if error.is_panic() {
+            Self::Panicked
+        } else {
+            Self::Cancelled
+        }
+    }
+}
+
+/// Renders `input` to an HTML string on Tokio's blocking thread pool.
+///
+/// This is [`render_to_string`] for async callers: the returned future waits
+/// for the render without occupying an executor thread. The number of
+/// concurrent renders is only limited by the runtime's blocking pool; use a
+/// [`RenderPool`] to cap it or to add a timeout.
+///
+/// # Panics
+///
+/// Panics when polled outside a Tokio runtime.
+pub async fn render(
+    ctx: Arc<KatexContext>,
+    input: String,
+    settings: Settings,
+) -> Result<String, RenderError> {
+    task::spawn_blocking(move || render_to_string(&ctx, &input, &settings))
+        .await?
+        .map_err(RenderError::from)
+}
+
+/// A bounded set of blocking workers for rendering from async code
+///
+/// At most `workers` renders run at the same time; further calls to
+/// [`RenderPool::render`] wait for a free worker. Cloning the pool shares
+/// its limit.
+#[derive(Clone)]
+pub struct RenderPool {
+    ctx: Arc<KatexContext>,
+    permits: Arc<Semaphore>,
+    timeout: Option<Duration>,
+}
+
+impl RenderPool {
+    /// Creates a pool that runs at most `workers` renders at once.
+    ///
+    /// A `workers` value of zero is treated as one.
+    #[must_use]
+    pub fn new(ctx: Arc<KatexContext>, workers: usize) -> Self {
+        Self {
+            ctx,
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+            timeout: None,
+        }
+    }
+
+    /// Gives up on each render that has not finished after `timeout`,
+    /// including the time spent waiting for a free worker.
+    ///
+    /// A render cannot be interrupted once it has started. When it times out
+    /// the caller gets [`RenderError::TimedOut`] straight away, but the
+    /// worker stays busy, and counts against the pool's limit, until the
+    /// render completes.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Renders `input` to an HTML string on one of the pool's workers.
+    ///
+    /// The returned future does not borrow the pool, so it can be spawned
+    /// as its own task.
+    ///
+    /// # Panics
+    ///
+    /// Panics when polled outside a Tokio runtime, or outside one with the
+    /// time driver enabled if the pool has a timeout.
+    pub fn render(
+        &self,
+        input: String,
+        settings: Settings,
+    ) -> impl Future<Output = Result<String, RenderError>> + Send + 'static {
+        let ctx = Arc::clone(&self.ctx);
+        let permits = Arc::clone(&self.permits);
+        let timeout = self.timeout;
+        async move {
+            let rendering = async move {
+                // The pool never closes its semaphore
+                let permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| RenderError::Cancelled)?;
+                task::spawn_blocking(move || {
+                    let _permit = permit;
+                    render_to_string(&ctx, &input, &settings)
+                })
+                .await?
+                .map_err(RenderError::from)
+            };
+            match timeout {
+                Some(limit) => time::timeout(limit, rendering)
+                    .await
+                    .map_err(|_| RenderError::TimedOut(limit))?,
+                None => rendering.await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::define_function::{FunctionDefSpec, FunctionPropSpec};
+    use crate::parser::parse_node::{ParseNode, ParseNodeOrdGroup};
+    use crate::types::OutputFormat;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Instant;
+    use tokio::runtime::{Builder, Runtime};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().enable_time().build().unwrap()
+    }
+
+    /// MathML only, since the order of inline styles in HTML varies
+    fn mathml() -> Settings {
+        Settings::builder().output(OutputFormat::Mathml).build()
+    }
+
+    #[test]
+    fn test_render_matches_render_to_string() {
+        let ctx = Arc::new(KatexContext::default());
+        let expected = render_to_string(&ctx, r"\frac{x^2}{2}", &mathml()).unwrap();
+        let markup = runtime()
+            .block_on(render(ctx, r"\frac{x^2}{2}".to_owned(), mathml()))
+            .unwrap();
+        assert_eq!(markup, expected);
+    }
+
+    #[test]
+    fn test_render_reports_parse_errors() {
+        let ctx = Arc::new(KatexContext::default());
+        let result = runtime().block_on(render(ctx, r"\frac{a}{".to_owned(), Settings::default()));
+        assert!(matches!(result, Err(RenderError::Parse(_))));
+    }
+
+    /// Renders running in `\meet` right now
+    static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+    /// Most renders that were in `\meet` at once
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    /// A context with `\meet`, which waits until two renders have been in
+    /// it at the same time
+    fn meeting_context() -> KatexContext {
+        let mut ctx = KatexContext::default();
+        ctx.define_function(FunctionDefSpec {
+            node_type: None,
+            names: &[r"\meet"],
+            props: FunctionPropSpec::default(),
+            handler: Some(|context, _args, _opt_args| {
+                let active = ACTIVE.fetch_add(1, Ordering::SeqCst) + 1;
+                PEAK.fetch_max(active, Ordering::SeqCst);
+                // Bounded, so a pool running renders one by one fails the
+                // test instead of hanging it
+                let deadline = Instant::now() + Duration::from_secs(10);
+                while PEAK.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                    thread::yield_now();
+                }
+                ACTIVE.fetch_sub(1, Ordering::SeqCst);
+                Ok(ParseNode::OrdGroup(ParseNodeOrdGroup {
+                    mode: context.parser.mode,
+                    loc: None,
+                    body: Vec::new(),
+                    semisimple: None,
+                }))
+            }),
+            html_builder: None,
+            mathml_builder: None,
+        });
+        ctx
+    }
+
+    #[test]
+    fn test_pool_limits_concurrent_renders() {
+        let ctx = Arc::new(meeting_context());
+        let pool = RenderPool::new(Arc::clone(&ctx), 2);
+        let inputs: Vec<String> = (0..8).map(|i| format!(r"\meet x_{i}")).collect();
+        let results = Builder::new_multi_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let tasks: Vec<_> = inputs
+                    .iter()
+                    .map(|input| tokio::spawn(pool.render(input.clone(), mathml())))
+                    .collect();
+                let mut results = Vec::new();
+                for task in tasks {
+                    results.push(task.await.unwrap().unwrap());
+                }
+                results
+            });
+        // Two renders ran at once, and never more
+        assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.permits.available_permits(), 2);
+        // Each result belongs to the render it was awaited from
+        for (input, markup) in inputs.iter().zip(&results) {
+            assert_eq!(*markup, render_to_string(&ctx, input, &mathml()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_pool_times_out_while_waiting_for_a_worker() {
+        let pool = RenderPool::new(Arc::new(KatexContext::default()), 1)
+            .with_timeout(Duration::from_millis(10));
+        let result = runtime().block_on(async {
+            // Hold the only worker so the render cannot start
+            let _busy = pool.permits.acquire().await.unwrap();
+            pool.render("x".to_owned(), Settings::default()).await
+        });
+        assert!(matches!(result, Err(RenderError::TimedOut(_))));
+    }
+}
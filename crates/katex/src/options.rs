@@ -9,6 +9,7 @@ use crate::style::TEXT;
 use crate::{
     font_metrics::{FONT_METRICS, FontMetrics},
     style::Style,
+    types::{BehaviorFlags, NumberFormatter},
 };
 use alloc::borrow::Cow;
 use bon::bon;
@@ -92,10 +93,18 @@ const SIZE_STYLE_MAP: [[usize; 3]; 11] = [
 ];
 
 /// Size multipliers corresponding to different sizes
-const SIZE_MULTIPLIERS: [f64; 11] = [0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.2, 1.44, 1.728, 2.074, 2.488];
-
-/// Calculate the size at a given style
-const fn size_at_style(size: usize, style: &Style) -> usize {
+pub const SIZE_MULTIPLIERS: [f64; 11] =
+    [0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.2, 1.44, 1.728, 2.074, 2.488];
+
+/// Calculate the size at a given style.
+///
+/// `size` is a size index (1–11, with 6 being `\normalsize`). Display and text
+/// styles keep the size, while script and scriptscript styles shrink it
+/// following TeX's `\scriptfont`/`\scriptscriptfont` rules. Whether a style
+/// is cramped is resolved separately by [`Style::sup`], [`Style::sub`] and
+/// friends and does not influence the size.
+#[must_use]
+pub const fn size_at_style(size: usize, style: &Style) -> usize {
     if style.size < 2 {
         size
     } else {
@@ -130,6 +139,32 @@ pub struct Options {
     pub max_size: f64,
     /// Minimum rule thickness
     pub min_rule_thickness: f64,
+    /// Smallest scale, relative to the text size, that scripts may shrink to
+    pub min_script_scale: f64,
+    /// Hook applied to digit runs in MathML output
+    pub number_formatter: Option<NumberFormatter>,
+    /// Whether text-mode spaces may break lines
+    pub text_wrap: bool,
+    /// Flags restoring behavior that a fix changed
+    pub behavior: BehaviorFlags,
+    /// Box of the array cell being laid out, for constructs that fill it
+    pub cell_box: Option<CellBox>,
+}
+
+/// Box of an array cell, which constructs like `\diagbox` stretch to fill
+///
+/// The array builder only knows the height and depth of a row once all of
+/// its cells are built, so it builds the cells that fill their box a second
+/// time with the row's box set in [`Options::cell_box`]. The width is left to
+/// the browser, which stretches the cell to the width of its column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellBox {
+    /// Height of the row above the baseline, in ems of the array
+    pub height: f64,
+    /// Depth of the row below the baseline, in ems of the array
+    pub depth: f64,
+    /// Size multiplier of the array, to convert to the ems of the cell
+    pub size_multiplier: f64,
 }
 
 #[bon]
@@ -148,6 +183,10 @@ impl Options {
         font_shape: Option<FontShape>,
         max_size: f64,
         min_rule_thickness: f64,
+        min_script_scale: Option<f64>,
+        number_formatter: Option<NumberFormatter>,
+        text_wrap: Option<bool>,
+        behavior: Option<BehaviorFlags>,
     ) -> Self {
         let size = size.unwrap_or(Self::BASESIZE);
         let multiplier_idx = cmp::min(size, SIZE_MULTIPLIERS.len());
@@ -165,6 +204,11 @@ impl Options {
             size_multiplier,
             max_size,
             min_rule_thickness,
+            min_script_scale: min_script_scale.unwrap_or(0.0),
+            number_formatter,
+            text_wrap: text_wrap.unwrap_or(false),
+            behavior: behavior.unwrap_or_default(),
+            cell_box: None,
         }
     }
 }
@@ -184,6 +228,11 @@ impl Default for Options {
             size_multiplier: SIZE_MULTIPLIERS[Self::BASESIZE - 1],
             max_size: 1000.0,
             min_rule_thickness: 0.04,
+            min_script_scale: 0.0,
+            number_formatter: None,
+            text_wrap: false,
+            behavior: BehaviorFlags::default(),
+            cell_box: None,
         }
     }
 }
@@ -192,6 +241,23 @@ impl Options {
     /// The base size index
     pub const BASESIZE: usize = 6;
 
+    /// Like [`size_at_style`], but never shrinks below `min_script_scale`
+    /// times the text size.
+    #[must_use]
+    pub fn script_size_at_style(&self, text_size: usize, style: &Style) -> usize {
+        let size = size_at_style(text_size, style);
+        if size >= text_size || self.min_script_scale <= 0.0 {
+            return size;
+        }
+
+        // Step up to the first size that is at least as large as the floor.
+        let floor = SIZE_MULTIPLIERS[text_size - 1] * self.min_script_scale;
+        SIZE_MULTIPLIERS[size - 1..text_size]
+            .iter()
+            .position(|&multiplier| multiplier >= floor - f64::EPSILON)
+            .map_or(text_size, |offset| size + offset)
+    }
+
     /// Return an options object with the given style. If `self.style ===
     /// style`, returns `self`.
     #[must_use]
@@ -199,7 +265,7 @@ impl Options {
         if ptr::eq(self.style, style) {
             self.clone()
         } else {
-            let size = size_at_style(self.text_size, style);
+            let size = self.script_size_at_style(self.text_size, style);
             let mut new_options = self.clone();
 
             // In Javascript there is a method called 'extend'
@@ -240,7 +306,7 @@ impl Options {
     #[must_use]
     pub fn having_base_style(&self, style: Option<&'static Style>) -> Self {
         let style = style.unwrap_or_else(|| self.style.text());
-        let want_size = size_at_style(Self::BASESIZE, style);
+        let want_size = self.script_size_at_style(Self::BASESIZE, style);
 
         if self.size == want_size && self.text_size == Self::BASESIZE && self.style == style {
             self.clone()
@@ -286,6 +352,14 @@ impl Options {
         new_options
     }
 
+    /// Create a new options object laying out a cell with the given box.
+    #[must_use]
+    pub fn with_cell_box(&self, cell_box: Option<CellBox>) -> Self {
+        let mut new_options = self.clone();
+        new_options.cell_box = cell_box;
+        new_options
+    }
+
     /// Creates a new options object with the given math font or old text font.
     #[must_use]
     pub fn with_font(&self, font: String) -> Self {
@@ -407,6 +481,27 @@ mod tests {
         assert_eq!(size_at_style(6, SCRIPTSCRIPT), 1);
     }
 
+    #[test]
+    fn test_min_script_scale() {
+        let options = Options::default();
+        assert_eq!(options.script_size_at_style(6, SCRIPTSCRIPT), 1);
+
+        let options = Options {
+            min_script_scale: 0.7,
+            ..Options::default()
+        };
+        assert_eq!(options.script_size_at_style(6, SCRIPT), 3);
+        assert_eq!(options.script_size_at_style(6, SCRIPTSCRIPT), 3);
+        assert_eq!(options.script_size_at_style(6, DISPLAY), 6);
+
+        // The floor is relative to the text size and rounds up to a real size
+        assert_eq!(options.script_size_at_style(8, SCRIPTSCRIPT), 7);
+
+        let nested = options.having_style(SCRIPTSCRIPT);
+        assert_eq!(nested.size, 3);
+        assert_eq!(nested.size_multiplier, SIZE_MULTIPLIERS[2]);
+    }
+
     #[test]
     fn test_options_creation() {
         let options = Options::builder()
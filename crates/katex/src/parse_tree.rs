@@ -40,3 +40,39 @@ pub fn parse_tree(
 
     Ok(tree)
 }
+
+/// Drops a parse tree one node at a time.
+///
+/// Groups, scripts and the other common containers nest as deeply as the
+/// input, and the drop the compiler generates recurses once per level. The
+/// render functions free their trees through this, and callers that parse
+/// untrusted input with a large `max_depth` should too.
+pub fn drop_tree(tree: Vec<ParseNode>) {
+    let mut stack = tree;
+    while let Some(node) = stack.pop() {
+        match node {
+            ParseNode::OrdGroup(mut group) => stack.append(&mut group.body),
+            ParseNode::SupSub(supsub) => {
+                let scripts = [supsub.base, supsub.sup, supsub.sub];
+                stack.extend(scripts.into_iter().flatten().map(|script| *script));
+            }
+            ParseNode::Genfrac(frac) => {
+                let frac = *frac;
+                stack.extend([*frac.numer, *frac.denom]);
+            }
+            ParseNode::Sqrt(sqrt) => {
+                let sqrt = *sqrt;
+                stack.push(sqrt.body);
+                stack.extend(sqrt.index);
+            }
+            ParseNode::LeftRight(mut left_right) => stack.append(&mut left_right.body),
+            ParseNode::Accent(accent) => stack.push(accent.base),
+            ParseNode::Font(font) => stack.push(*font.body),
+            ParseNode::Color(mut color) => stack.append(&mut color.body),
+            ParseNode::Styling(mut styling) => stack.append(&mut styling.body),
+            ParseNode::Sizing(mut sizing) => stack.append(&mut sizing.body),
+            ParseNode::Text(mut text) => stack.append(&mut text.body),
+            _ => {}
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use alloc::borrow::Cow;
-use core::iter;
+use core::{iter, mem};
 
 use crate::parser::parse_node::ParseNodeTextOrd;
-use crate::types::{SourceRangeRef as _, TokenText};
+use crate::types::{SourceLocation, SourceRangeRef as _, TokenText};
 use crate::unicode::unicode_sup_or_sub::U_SUBS_AND_SUPS;
 use crate::{
     KatexContext, ParseError, Settings,
@@ -12,15 +12,20 @@ use crate::{
     macros::{MacroContextInterface as _, MacroDefinition},
     parser::parse_node::{AnyParseNode, NodeType, ParseNode, ParseNodeSize},
     style::TEXT,
-    symbols::{Group, NonAtom},
-    types::{ArgType, BreakToken, ErrorLocationProvider, Mode, ParseErrorKind, Spec, Token},
+    symbols::{Atom, Group, NonAtom},
+    types::{
+        ArgType, BreakToken, ErrorLocationProvider as _, Mode, ParseErrorKind, Spec, Token,
+        TraceEventKind,
+    },
     unicode::{UNICODE_SYMBOLS, get_accent_mapping, supported_codepoint},
 };
 
 pub mod parse_node;
+use crate::parse_tree::drop_tree;
 use crate::spacing_data::MeasurementOwned;
-use crate::unicode::is_unicode_subscript;
+use crate::unicode::{is_unicode_subscript, prime_count};
 use crate::units::valid_unit;
+use crate::utils::with_stack;
 pub use parse_node::ParseNodeError;
 
 /// The core parser for KaTeX, responsible for converting LaTeX mathematical
@@ -78,6 +83,9 @@ pub struct Parser<'a> {
     pub settings: &'a Settings,
     /// Nesting depth for `\left`/`\right` pairs
     pub leftright_depth: f64,
+    /// Nesting depth of expressions being parsed, bounded by
+    /// [`Settings::max_depth`]
+    pub depth: usize,
     /// Cached lookahead token
     pub next_token: Option<Token>,
     /// Shared context containing functions and symbols
@@ -99,6 +107,93 @@ fn wrap_ordgroup(mut nodes: Vec<ParseNode>, mode: Mode) -> ParseNode {
     })
 }
 
+/// The token closing a group opened by `open`
+fn group_break_token(open: &Token) -> BreakToken {
+    if open.text == "{" {
+        BreakToken::RightBrace
+    } else {
+        BreakToken::EndGroup
+    }
+}
+
+/// An expression whose body is being parsed
+struct Expression {
+    body: Vec<ParseNode>,
+    break_on_infix: bool,
+    break_on_token_text: Option<BreakToken>,
+}
+
+/// An expression abandoned by an error can hold groups nested as deeply as
+/// the input, so its body is dropped without recursion.
+impl Drop for Expression {
+    fn drop(&mut self) {
+        drop_tree(mem::take(&mut self.body));
+    }
+}
+
+/// An expression suspended while a braced group inside it is parsed, see
+/// [`Parser::parse_expression`]
+struct GroupFrame {
+    /// The enclosing expression, parsed up to the group
+    outer: Expression,
+    /// The `{` or `\begingroup` token that opened the group
+    open: Token,
+    /// What the group becomes once it is closed
+    role: GroupRole,
+}
+
+/// Where a braced group goes in its enclosing expression
+enum GroupRole {
+    /// The base of a new atom
+    Base,
+    /// The superscript or subscript of an atom
+    Script {
+        atom: Box<Scripts>,
+        superscript: bool,
+    },
+}
+
+/// An atom whose superscripts and subscripts are being parsed
+struct Scripts {
+    base: Option<ParseNode>,
+    superscript: Option<ParseNode>,
+    subscript: Option<ParseNode>,
+}
+
+impl Scripts {
+    const fn new(base: Option<ParseNode>) -> Self {
+        Self {
+            base,
+            superscript: None,
+            subscript: None,
+        }
+    }
+}
+
+/// Like [`Expression`], an atom abandoned by an error drops its base and
+/// scripts without recursion.
+impl Drop for Scripts {
+    fn drop(&mut self) {
+        let nodes = [
+            self.base.take(),
+            self.superscript.take(),
+            self.subscript.take(),
+        ];
+        drop_tree(nodes.into_iter().flatten().collect());
+    }
+}
+
+/// The outcome of [`Parser::parse_scripts`]
+enum ScriptStep {
+    /// The finished atom, or `None` if there was neither base nor script
+    Done(Option<ParseNode>),
+    /// A script is a braced group to parse before continuing
+    Group {
+        atom: Box<Scripts>,
+        superscript: bool,
+    },
+}
+
 #[inline]
 fn parse_size_with_unit(s: &str) -> Option<(f64, String)> {
     let mut chars = s.chars().peekable();
@@ -204,6 +299,7 @@ impl<'a> Parser<'a> {
             settings,
             // Count leftright depth (for \middle errors)
             leftright_depth: 0.0,
+            depth: 0,
             next_token: None,
 
             ctx,
@@ -512,16 +608,61 @@ impl<'a> Parser<'a> {
     ///
     /// # Cross-references
     ///
-    /// - parse_atom - Parses individual atomic elements
+    /// - parse_scripts - Attaches superscripts and subscripts to atoms
     /// - handle_infix_nodes - Rewrites infix operators
     /// - form_ligatures - Applies text ligatures
     /// - [`BreakToken`] - Expression termination tokens
+    ///
+    /// Groups, arguments and environment bodies are all parsed here, so this
+    /// is where their nesting is counted against [`Settings::max_depth`].
     pub fn parse_expression(
         &mut self,
         break_on_infix: bool,
         break_on_token_text: Option<&BreakToken>,
     ) -> Result<Vec<ParseNode>, ParseError> {
-        let mut body: Vec<ParseNode> = Vec::new();
+        let depth = self.depth;
+        let body = with_stack(|| self.parse_nested_expression(break_on_infix, break_on_token_text));
+        self.depth = depth;
+        body
+    }
+
+    /// Counts one more level of nesting against [`Settings::max_depth`].
+    fn enter_nesting(&mut self) -> Result<(), ParseError> {
+        let max_depth = self.settings.max_depth;
+        if self.depth >= max_depth {
+            return Err(ParseError::with_token(
+                ParseErrorKind::MaxDepthExceeded { max_depth },
+                self.fetch()?,
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Parses an expression for [`Self::parse_expression`], which restores
+    /// the nesting depth afterwards.
+    ///
+    /// Braced groups, whether atoms of their own or the argument of `^` and
+    /// `_`, are the usual way formulas nest. Instead of recursing into them,
+    /// the enclosing expression is pushed onto a heap-allocated stack of
+    /// [`GroupFrame`]s, and popped again once the group is closed, so `{{{x}}}`
+    /// and `x^{x^{x}}` use no more native stack however deep they go. Other
+    /// nesting (function arguments, environments, `\left`/`\right`) still
+    /// recurses through [`Self::parse_expression`], which moves to a new stack
+    /// segment when the current one runs low.
+    fn parse_nested_expression(
+        &mut self,
+        break_on_infix: bool,
+        break_on_token_text: Option<&BreakToken>,
+    ) -> Result<Vec<ParseNode>, ParseError> {
+        self.enter_nesting()?;
+
+        let mut frames: Vec<GroupFrame> = Vec::new();
+        let mut expression = Expression {
+            body: Vec::new(),
+            break_on_infix,
+            break_on_token_text: break_on_token_text.cloned(),
+        };
 
         // Keep adding atoms to the body until we can't parse any more atoms (either
         // we reached the end, a }, or a \right)
@@ -531,62 +672,205 @@ impl<'a> Parser<'a> {
                 self.consume_spaces()?;
             }
 
-            let (should_break, infix_candidate) = {
-                let token = self.fetch()?;
-                let lex_text = token.text.as_str();
-                let end_of_expression =
-                    matches!(lex_text, "}" | "\\endgroup" | "\\end" | "\\right" | "&");
-                let explicit_break = match break_on_token_text {
-                    Some(BreakToken::RightBrace) => lex_text == "}",
-                    Some(BreakToken::EndGroup) => lex_text == "\\endgroup",
-                    Some(BreakToken::End) => lex_text == "\\end",
-                    Some(BreakToken::RightBracket) => lex_text == "]",
-                    Some(BreakToken::RightParen) => lex_text == "\\)",
-                    Some(BreakToken::DoubleBackslash) => lex_text == "\\\\",
-                    Some(BreakToken::Dollar) => lex_text == "$",
-                    Some(BreakToken::Eof) => lex_text == "EOF",
-                    None => false,
-                };
-                let should_stop = end_of_expression || explicit_break;
-                let candidate = (!should_stop && break_on_infix).then(|| token.text.clone());
-                (should_stop, candidate)
-            };
-
-            let should_break = if should_break {
-                true
-            } else if let Some(token_text) = infix_candidate {
-                self.ctx
-                    .functions
-                    .get(token_text.as_str())
-                    .is_some_and(|func| func.infix)
+            let mut step = if self.at_expression_end(&expression)? {
+                None
+            } else if self.at_group_start()? {
+                frames.push(self.open_group(&mut expression, GroupRole::Base)?);
+                continue;
             } else {
-                false
+                // Parse the base via function/symbol helpers
+                match self.parse_group("atom", expression.break_on_token_text.as_ref())? {
+                    // Internal nodes (e.g. \relax) do not appear in the parse tree and
+                    // cannot support super/subscripts; those are picked up with a
+                    // blank base next round.
+                    Some(ParseNode::Internal(_)) => continue,
+                    Some(base) if self.mode == Mode::Text => Some(ScriptStep::Done(Some(base))),
+                    None if self.mode == Mode::Text => None,
+                    base => Some(self.parse_scripts(Scripts::new(base))?),
+                }
             };
 
-            if should_break {
-                break;
-            }
-
-            // Parse one atom
-            if let Some(atom) = self.parse_atom(break_on_token_text)? {
-                // Internal nodes do not appear in parse tree
-                if let ParseNode::Internal(_) = atom {
-                    continue;
+            loop {
+                match step {
+                    Some(ScriptStep::Done(Some(atom))) => {
+                        expression.body.push(atom);
+                        break;
+                    }
+                    Some(ScriptStep::Group { atom, superscript }) => {
+                        let role = GroupRole::Script { atom, superscript };
+                        frames.push(self.open_group(&mut expression, role)?);
+                        break;
+                    }
+                    // The current expression has ended: finish it, and resume the
+                    // expression around it if it was a braced group
+                    None | Some(ScriptStep::Done(None)) => {
+                        let body = self.finish_expression(mem::take(&mut expression.body))?;
+                        let Some(frame) = frames.pop() else {
+                            return Ok(body);
+                        };
+                        expression = frame.outer;
+                        let group = self.close_group(&frame.open, body)?;
+                        self.depth -= 1;
+                        step = Some(match frame.role {
+                            GroupRole::Base if self.mode == Mode::Text => {
+                                ScriptStep::Done(Some(group))
+                            }
+                            GroupRole::Base => self.parse_scripts(Scripts::new(Some(group)))?,
+                            GroupRole::Script {
+                                mut atom,
+                                superscript,
+                            } => {
+                                if superscript {
+                                    atom.superscript = Some(group);
+                                } else {
+                                    atom.subscript = Some(group);
+                                }
+                                self.parse_scripts(*atom)?
+                            }
+                        });
+                    }
                 }
-                body.push(atom);
-            } else {
-                break;
             }
         }
+    }
+
+    /// Whether the lookahead token ends `expression`.
+    fn at_expression_end(&mut self, expression: &Expression) -> Result<bool, ParseError> {
+        let (should_break, infix_candidate) = {
+            let token = self.fetch()?;
+            let lex_text = token.text.as_str();
+            let end_of_expression =
+                matches!(lex_text, "}" | "\\endgroup" | "\\end" | "\\right" | "&");
+            let explicit_break = match expression.break_on_token_text {
+                Some(BreakToken::RightBrace) => lex_text == "}",
+                Some(BreakToken::EndGroup) => lex_text == "\\endgroup",
+                Some(BreakToken::End) => lex_text == "\\end",
+                Some(BreakToken::RightBracket) => lex_text == "]",
+                Some(BreakToken::RightParen) => lex_text == "\\)",
+                Some(BreakToken::DoubleBackslash) => lex_text == "\\\\",
+                Some(BreakToken::Dollar) => lex_text == "$",
+                Some(BreakToken::Eof) => lex_text == "EOF",
+                None => false,
+            };
+            let should_stop = end_of_expression || explicit_break;
+            let candidate = (!should_stop && expression.break_on_infix).then(|| token.text.clone());
+            (should_stop, candidate)
+        };
 
+        Ok(if should_break {
+            true
+        } else if let Some(token_text) = infix_candidate {
+            self.ctx
+                .functions
+                .get(token_text.as_str())
+                .is_some_and(|func| func.infix)
+        } else {
+            false
+        })
+    }
+
+    /// Applies the rewrites that need a complete expression body.
+    fn finish_expression(
+        &mut self,
+        mut body: Vec<ParseNode>,
+    ) -> Result<Vec<ParseNode>, ParseError> {
         // In text mode, turn common ASCII sequences into ligatures.
         if self.mode == Mode::Text {
             self.form_ligatures(&mut body);
         }
+        if self.mode == Mode::Math && self.settings.decimal_comma {
+            Self::form_decimal_commas(&mut body);
+        }
 
         self.handle_infix_nodes(body)
     }
 
+    /// Whether the lookahead token opens a `{...}` or
+    /// `\begingroup...\endgroup` group.
+    fn at_group_start(&mut self) -> Result<bool, ParseError> {
+        Ok(matches!(self.fetch()?.text.as_str(), "{" | "\\begingroup"))
+    }
+
+    /// Opens the group at the lookahead token, making it the current
+    /// expression and returning the frame to resume `expression` from.
+    fn open_group(
+        &mut self,
+        expression: &mut Expression,
+        role: GroupRole,
+    ) -> Result<GroupFrame, ParseError> {
+        self.enter_nesting()?;
+        let open = self.begin_group()?;
+        let inner = Expression {
+            body: Vec::new(),
+            break_on_infix: false,
+            break_on_token_text: Some(group_break_token(&open)),
+        };
+        Ok(GroupFrame {
+            outer: mem::replace(expression, inner),
+            open,
+            role,
+        })
+    }
+
+    /// Consumes the token opening a group and starts its macro scope.
+    fn begin_group(&mut self) -> Result<Token, ParseError> {
+        let open = self.fetch()?.clone();
+        self.consume();
+        self.gullet.begin_group();
+        if let Some(trace) = &self.settings.trace {
+            trace.record(
+                TraceEventKind::GroupBegin {
+                    open: open.text.to_owned_string(),
+                },
+                open.loc.as_ref(),
+            );
+        }
+        Ok(open)
+    }
+
+    /// Consumes the token closing the group opened by `open`, ends its macro
+    /// scope and wraps its `body`.
+    fn close_group(&mut self, open: &Token, body: Vec<ParseNode>) -> Result<ParseNode, ParseError> {
+        let break_token = group_break_token(open);
+        let last_loc = match self.end_group(&break_token) {
+            Ok(last_loc) => last_loc,
+            Err(error) => {
+                // The body of an unclosed group can nest as deeply as the input
+                drop_tree(body);
+                return Err(error);
+            }
+        };
+
+        Ok(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
+            mode: self.mode,
+            loc: open.loc().range_ref(last_loc.as_ref()),
+            body,
+            // A group formed by \begingroup...\endgroup is a semi-simple group
+            // which doesn't affect spacing in math mode, i.e., is transparent.
+            semisimple: (break_token == BreakToken::EndGroup).then_some(true),
+        }))
+    }
+
+    /// Consumes `break_token` and ends the macro scope of its group, returning
+    /// where the token was.
+    fn end_group(
+        &mut self,
+        break_token: &BreakToken,
+    ) -> Result<Option<SourceLocation>, ParseError> {
+        let last_loc = self.fetch()?.loc().cloned();
+        self.expect(break_token.as_ref(), true)?;
+        self.gullet.end_group()?;
+        if let Some(trace) = &self.settings.trace {
+            trace.record(
+                TraceEventKind::GroupEnd {
+                    close: break_token.as_ref().to_owned(),
+                },
+                last_loc.as_ref(),
+            );
+        }
+        Ok(last_loc)
+    }
+
     /// Consumes consecutive space tokens, advancing to the next non-space
     /// token.
     ///
@@ -653,32 +937,13 @@ impl<'a> Parser<'a> {
     // Helpers (Parser.js ports)
     // ------------------------
 
-    /// Parse a base (group/function/symbol) and attach super/subscripts.
+    /// Attach super/subscripts to an atom's base.
     /// Rough port of Parser.js parseAtom with limited coverage.
-    fn parse_atom(
-        &mut self,
-        break_on_token_text: Option<&BreakToken>,
-    ) -> Result<Option<ParseNode>, ParseError> {
-        // 1) Parse the base via group/function/symbol helpers
-        let mut base_opt = self.parse_group("atom", break_on_token_text)?;
-
-        if let Some(base) = &base_opt
-            && matches!(base, ParseNode::Internal(_))
-        {
-            // Internal nodes (e.g. \relax) cannot support super/subscripts.
-            // Instead we will pick up super/subscripts with blank base next round.
-            // No super/subscripts on internal nodes or text mode nodes
-            return Ok(base_opt);
-        }
-
-        if self.mode == Mode::Text {
-            return Ok(base_opt);
-        }
-
-        let mut superscript = None;
-        let mut subscript = None;
-
-        // 2) Handle superscripts/subscripts chain: ^, _, ', and Unicode sub/sup
+    ///
+    /// Stops early with [`ScriptStep::Group`] when a script is a braced
+    /// group, which the caller parses without recursion before resuming here.
+    fn parse_scripts(&mut self, mut atom: Scripts) -> Result<ScriptStep, ParseError> {
+        // Handle superscripts/subscripts chain: ^, _, ', and Unicode sub/sup
         // In text mode, raw ^/_ should error (like KaTeX); we implement minimal check.
         loop {
             self.consume_spaces()?; // math mode ignores spaces, but safe in both
@@ -687,13 +952,16 @@ impl<'a> Parser<'a> {
                 text if matches!(text, "\\limits" | "\\nolimits") => {
                     // Handle \limits and \nolimits
                     let limits = text == "\\limits";
-                    if let Some(ParseNode::Op(base)) = &mut base_opt {
+                    if let Some(ParseNode::Op(base)) = &mut atom.base {
                         *base.limits_mut() = limits;
                         *base.always_handle_sup_sub_mut() = Some(true);
-                    } else if let Some(ParseNode::OperatorName(base)) = &mut base_opt {
+                    } else if let Some(ParseNode::OperatorName(base)) = &mut atom.base {
                         if base.always_handle_sup_sub {
                             base.limits = limits;
                         }
+                    } else if let Some(ParseNode::Sideset(base)) = &mut atom.base {
+                        base.limits = limits;
+                        base.always_handle_sup_sub = true;
                     } else {
                         return Err(ParseError::with_token(
                             ParseErrorKind::LimitsMustFollowBase,
@@ -703,34 +971,47 @@ impl<'a> Parser<'a> {
                     self.consume();
                 }
                 "^" => {
-                    if superscript.is_some() {
+                    if atom.superscript.is_some() {
                         return Err(ParseError::with_token(
                             ParseErrorKind::DoubleSuperscript,
                             token,
                         ));
                     }
-                    superscript = Some(self.handle_sup_subscript("superscript")?);
+                    let Some(script) = self.parse_script("superscript")? else {
+                        return Ok(ScriptStep::Group {
+                            atom: Box::new(atom),
+                            superscript: true,
+                        });
+                    };
+                    atom.superscript = Some(script);
                 }
                 "_" => {
-                    if subscript.is_some() {
+                    if atom.subscript.is_some() {
                         return Err(ParseError::with_token(
                             ParseErrorKind::DoubleSubscript,
                             token,
                         ));
                     }
-                    subscript = Some(self.handle_sup_subscript("subscript")?);
+                    let Some(script) = self.parse_script("subscript")? else {
+                        return Ok(ScriptStep::Group {
+                            atom: Box::new(atom),
+                            superscript: false,
+                        });
+                    };
+                    atom.subscript = Some(script);
                 }
-                "'" => {
-                    if superscript.is_some() {
+                text if prime_count(text).is_some() => {
+                    if atom.superscript.is_some() {
                         return Err(ParseError::with_token(
                             ParseErrorKind::DoubleSuperscript,
                             token,
                         ));
                     }
-                    let mut n = 1;
+                    // ASCII and Unicode primes may be mixed freely: f'′ is f''
+                    let mut n = prime_count(text).unwrap_or(1);
                     self.consume();
-                    while self.fetch()?.text == "'" {
-                        n += 1;
+                    while let Some(count) = prime_count(self.fetch()?.text.as_str()) {
+                        n += count;
                         self.consume();
                     }
                     let mut primes = iter::repeat_n(
@@ -745,7 +1026,7 @@ impl<'a> Parser<'a> {
                     if self.fetch()?.text == "^" {
                         primes.push(self.handle_sup_subscript("superscript")?);
                     }
-                    superscript = Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
+                    atom.superscript = Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
                         mode: self.mode,
                         loc: None,
                         body: primes,
@@ -759,11 +1040,7 @@ impl<'a> Parser<'a> {
                         let is_sub = is_unicode_subscript(ch);
                         let mut subsup_tokens = vec![Token::new(mapped, None)];
                         self.consume();
-                        loop {
-                            let Some(c) = self.fetch()?.text.as_str().chars().next() else {
-                                break;
-                            };
-
+                        while let Some(c) = self.fetch()?.text.as_str().chars().next() {
                             if let Some(&mapped) = U_SUBS_AND_SUPS.get(&c)
                                 && is_sub == is_unicode_subscript(c)
                             {
@@ -775,21 +1052,16 @@ impl<'a> Parser<'a> {
                         }
                         subsup_tokens.reverse();
                         let body = self.subparse(subsup_tokens)?;
+                        let group = Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
+                            mode: Mode::Math,
+                            loc: None,
+                            body,
+                            semisimple: None,
+                        }));
                         if is_sub {
-                            subscript = Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
-                                mode: Mode::Math,
-                                loc: None,
-                                body,
-                                semisimple: None,
-                            }));
+                            atom.subscript = group;
                         } else {
-                            superscript =
-                                Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
-                                    mode: Mode::Math,
-                                    loc: None,
-                                    body,
-                                    semisimple: None,
-                                }));
+                            atom.superscript = group;
                         }
                     } else {
                         // If it wasn't ^, _, or ', stop parsing super/subscripts
@@ -799,17 +1071,19 @@ impl<'a> Parser<'a> {
             }
         }
 
-        if superscript.is_some() || subscript.is_some() {
-            return Ok(Some(ParseNode::SupSub(parse_node::ParseNodeSupSub {
-                base: base_opt.map(Box::new),
-                sup: superscript.map(Box::new),
-                sub: subscript.map(Box::new),
-                mode: self.mode,
-                loc: None,
-            })));
+        if atom.superscript.is_some() || atom.subscript.is_some() {
+            return Ok(ScriptStep::Done(Some(ParseNode::SupSub(
+                parse_node::ParseNodeSupSub {
+                    base: atom.base.take().map(Box::new),
+                    sup: atom.superscript.take().map(Box::new),
+                    sub: atom.subscript.take().map(Box::new),
+                    mode: self.mode,
+                    loc: None,
+                },
+            ))));
         }
 
-        Ok(base_opt)
+        Ok(ScriptStep::Done(atom.base.take()))
     }
 
     /// Rewrites infix operators such as \over and \choose with corresponding
@@ -821,18 +1095,24 @@ impl<'a> Parser<'a> {
         // Find any infix function in body
         let mut infix_pos: Option<usize> = None;
         let mut func_name: Option<String> = None;
+        let mut error = None;
         for (i, node) in body.iter().enumerate() {
             if let ParseNode::Infix(n) = node {
                 if infix_pos.is_some() {
-                    return Err(ParseError::with_token(
+                    error = Some(ParseError::with_token(
                         ParseErrorKind::MultipleInfixOperators,
                         &n.token,
                     ));
+                    break;
                 }
                 infix_pos = Some(i);
                 func_name = Some(n.replace_with.clone());
             }
         }
+        if let Some(error) = error {
+            drop_tree(body);
+            return Err(error);
+        }
 
         let (Some(over_idx), Some(func_name)) = (infix_pos, func_name) else {
             // No infix found, return original body
@@ -916,6 +1196,43 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Brace commas written between digits, so that `3,14` parses like
+    /// `3{,}14` and renders without the space after punctuation.
+    ///
+    /// The comma must touch the following digit; `1, 2` keeps its space.
+    /// Nodes without a source location (e.g. from macro expansion) are
+    /// taken to be adjacent.
+    fn form_decimal_commas(group: &mut [ParseNode]) {
+        let is_digit = |node: &ParseNode| {
+            node.text()
+                .is_some_and(|text| text.starts_with(|ch: char| ch.is_ascii_digit()))
+        };
+        let touches = |a: &ParseNode, b: &ParseNode| match (a.loc(), b.loc()) {
+            (Some(a), Some(b)) => a.end == b.start,
+            _ => true,
+        };
+
+        for i in 1..group.len().saturating_sub(1) {
+            let is_comma = matches!(
+                &group[i],
+                ParseNode::Atom(atom) if atom.family == Atom::Punct && atom.text.as_str() == ","
+            );
+            if is_comma
+                && is_digit(&group[i - 1])
+                && is_digit(&group[i + 1])
+                && touches(&group[i], &group[i + 1])
+            {
+                let comma = group[i].clone();
+                group[i] = ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
+                    mode: Mode::Math,
+                    loc: comma.loc().cloned(),
+                    body: vec![comma],
+                    semisimple: None,
+                });
+            }
+        }
+    }
+
     // ============ Additional parsing helpers (port of Parser.js) ============
 
     /// Parse a group using a validation function, similar to Parser.js
@@ -1024,7 +1341,7 @@ impl<'a> Parser<'a> {
         optional: bool,
     ) -> Result<Option<ParseNodeSize>, ParseError> {
         self.gullet.consume_spaces()?;
-        let res = if !optional && self.gullet.future_mut()?.text != "{" {
+        let res = if !optional && self.gullet.peek_n(0)?.text != "{" {
             Some(self.parse_regex_group(
                 |s| {
                     let t = s.trim();
@@ -1113,6 +1430,49 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    /// Captures the input up to the next occurrence of `delimiter` as raw
+    /// text, without tokenizing it, and stops in front of the delimiter.
+    ///
+    /// Meant for environments whose body is not TeX, like a future
+    /// `\begin{verbatim}`, and for sub-parsers with their own syntax. See
+    /// [`MacroExpander::scan_raw_until`] for when this fails.
+    pub fn parse_raw_until(
+        &mut self,
+        delimiter: &str,
+    ) -> Result<parse_node::ParseNodeRaw, ParseError> {
+        if let Some(token) = self.next_token.take() {
+            self.gullet.push_token(token);
+        }
+        let token = self.gullet.scan_raw_until(delimiter)?;
+        Ok(parse_node::ParseNodeRaw {
+            mode: self.mode,
+            loc: token.loc,
+            string: token.text,
+        })
+    }
+
+    /// Captures the next `{...}` group as raw text, without tokenizing it,
+    /// and continues after the closing brace.
+    ///
+    /// This is how sub-language commands like `\ce` take their argument; see
+    /// [`crate::sublanguage`]. The location of the node covers the text
+    /// between the braces. See [`MacroExpander::scan_raw_group`] for when
+    /// this fails.
+    pub fn parse_raw_group(
+        &mut self,
+        context: &str,
+    ) -> Result<parse_node::ParseNodeRaw, ParseError> {
+        if let Some(token) = self.next_token.take() {
+            self.gullet.push_token(token);
+        }
+        let token = self.gullet.scan_raw_group(context)?;
+        Ok(parse_node::ParseNodeRaw {
+            mode: self.mode,
+            loc: token.loc,
+            string: token.text,
+        })
+    }
+
     /// Parse a URL group; simplified unescape.
     fn parse_url_group(&mut self, optional: bool) -> Result<Option<ParseNode>, ParseError> {
         // Set catcode for % to active character (13) and ~ to other character (12)
@@ -1149,7 +1509,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Parse an argument group (wrapped in ordgroup) with optional mode switch.
-    fn parse_argument_group(
+    pub fn parse_argument_group(
         &mut self,
         optional: bool,
         mode: Option<Mode>,
@@ -1245,27 +1605,9 @@ impl<'a> Parser<'a> {
         let first_token = self.fetch()?.clone();
         let text = first_token.text.as_str();
         if text == "{" || text == "\\begingroup" {
-            self.consume();
-            let break_token = if text == "{" {
-                BreakToken::RightBrace
-            } else {
-                BreakToken::EndGroup
-            };
-
-            self.gullet.begin_group();
-            let expression = self.parse_expression(false, Some(&break_token))?;
-            let last_loc = self.fetch()?.loc().cloned();
-            self.expect(break_token.as_ref(), true)?;
-            self.gullet.end_group()?;
-
-            Ok(Some(ParseNode::OrdGroup(parse_node::ParseNodeOrdGroup {
-                mode: self.mode,
-                loc: first_token.loc().range_ref(last_loc.as_ref()),
-                body: expression,
-                // A group formed by \begingroup...\endgroup is a semi-simple group
-                // which doesn't affect spacing in math mode, i.e., is transparent.
-                semisimple: (text == "\\begingroup").then_some(true),
-            })))
+            let open = self.begin_group()?;
+            let body = self.parse_expression(false, Some(&group_break_token(&open)))?;
+            Ok(Some(self.close_group(&open, body)?))
         } else {
             let result = self.parse_function(break_on_token_text, Some(name))?;
             let mut result = if result.is_some() {
@@ -1382,18 +1724,23 @@ impl<'a> Parser<'a> {
         {
             self.consume();
             let star = arg.starts_with('*');
-            let body = if star { &arg[1..] } else { arg };
+            let body = if star {
+                arg.get(1..).unwrap_or_default()
+            } else {
+                arg
+            };
 
-            // Validate that body has matching delimiters
-            if body.len() < 2 || body.chars().next() != body.chars().last() {
+            // Validate that body has matching delimiters, which need not be
+            // ASCII, and extract the content between them
+            let mut chars = body.chars();
+            let delimiters = (chars.next(), chars.next_back());
+            if !matches!(delimiters, (Some(open), Some(close)) if open == close) {
                 return Err(ParseError::with_token(
                     ParseErrorKind::VerbAssertionFailed,
                     &nucleus,
                 ));
             }
-
-            // Extract content between delimiters
-            let inner_body = &body[1..body.len() - 1];
+            let inner_body = chars.as_str();
             let inner_body = match text {
                 Cow::Borrowed(_s) => {
                     // This path means we borrowed from nucleus.text (which is TokenText).
@@ -1420,6 +1767,11 @@ impl<'a> Parser<'a> {
             })));
         }
 
+        // Unicode primes are superscripts, picked up by parse_atom like `'`
+        if self.mode == Mode::Math && prime_count(text.as_ref()).is_some() {
+            return Ok(None);
+        }
+
         // Expand any accented base symbol according to unicodeSymbols.
         if let Some(first_char) = text.as_ref().chars().next()
             && let Some(mapped) = UNICODE_SYMBOLS.get(&first_char)
@@ -1434,10 +1786,7 @@ impl<'a> Parser<'a> {
                 self.settings.report_nonstrict(
                     "unicodeTextInMathMode",
                     &format!("Accented Unicode text character \"{first_char}\" used in math mode"),
-                    nucleus
-                        .loc
-                        .as_ref()
-                        .map(|loc| loc as &dyn ErrorLocationProvider),
+                    Some(&nucleus),
                 )?;
             }
             let rest: String = text.as_ref().chars().skip(1).collect();
@@ -1510,19 +1859,13 @@ impl<'a> Parser<'a> {
                         "Unrecognized Unicode character \"{text}\" (0x{:X})",
                         first_char as u32
                     ),
-                    nucleus
-                        .loc
-                        .as_ref()
-                        .map(|loc| loc as &dyn ErrorLocationProvider),
+                    Some(&nucleus),
                 )?;
             } else if self.mode == Mode::Math {
                 self.settings.report_nonstrict(
                     "unicodeTextInMathMode",
                     &format!("Unicode text character \"{text}\" used in math mode"),
-                    nucleus
-                        .loc
-                        .as_ref()
-                        .map(|loc| loc as &dyn ErrorLocationProvider),
+                    Some(&nucleus),
                 )?;
             }
             let token_text = TokenText::from(text.clone());
@@ -1602,27 +1945,39 @@ impl<'a> Parser<'a> {
         &mut self,
         name: &str, // For error reporting.
     ) -> Result<ParseNode, ParseError> {
+        if let Some(group) = self.parse_script(name)? {
+            return Ok(group);
+        }
+        let open = self.begin_group()?;
+        let body = self.parse_expression(false, Some(&group_break_token(&open)))?;
+        self.close_group(&open, body)
+    }
+
+    /// Consumes `^` or `_` and parses its argument, leaving a braced group
+    /// unparsed at the lookahead (returning `None`) for the caller to handle.
+    fn parse_script(&mut self, name: &str) -> Result<Option<ParseNode>, ParseError> {
         let symbol_token = self.fetch()?.clone();
         self.consume();
         self.consume_spaces()?; // ignore spaces before sup/subscript argument
 
         // Skip over allowed internal nodes such as \relax
-        let mut group = self.parse_group(name, None)?;
-        while let Some(ParseNode::Internal(_)) = group {
-            group = self.parse_group(name, None)?;
+        loop {
+            if self.at_group_start()? {
+                return Ok(None);
+            }
+            match self.parse_group(name, None)? {
+                Some(ParseNode::Internal(_)) => {}
+                Some(group) => return Ok(Some(group)),
+                None => {
+                    return Err(ParseError::with_token(
+                        ParseErrorKind::ExpectedGroupAfterSymbol {
+                            symbol: symbol_token.text.to_owned_string(),
+                        },
+                        &symbol_token,
+                    ));
+                }
+            }
         }
-
-        group.map_or_else(
-            || {
-                Err(ParseError::with_token(
-                    ParseErrorKind::ExpectedGroupAfterSymbol {
-                        symbol: symbol_token.text.to_owned_string(),
-                    },
-                    &symbol_token,
-                ))
-            },
-            Ok,
-        )
     }
 
     /// Call a function handler with a suitable context and arguments.
@@ -1640,6 +1995,14 @@ impl<'a> Parser<'a> {
         if let Some(func) = func
             && let Some(handler) = func.handler
         {
+            if let Some(trace) = &self.settings.trace {
+                trace.record(
+                    TraceEventKind::FunctionDispatch {
+                        name: name.to_owned(),
+                    },
+                    token.and_then(|token| token.loc.as_ref()),
+                );
+            }
             let context = FunctionContext {
                 func_name: name,
                 parser: self,
@@ -1685,7 +2048,15 @@ impl<'a> Parser<'a> {
                 arg_type.copied()
             };
 
-            let arg = self.parse_group_of_type(&arg_context, arg_type.as_ref(), is_optional)?;
+            let arg = match self.parse_group_of_type(&arg_context, arg_type.as_ref(), is_optional) {
+                Ok(arg) => arg,
+                Err(error) => {
+                    // Earlier arguments can nest as deeply as the input
+                    args.extend(opt_args.into_iter().flatten());
+                    drop_tree(args);
+                    return Err(error);
+                }
+            };
 
             if is_optional {
                 opt_args.push(arg);
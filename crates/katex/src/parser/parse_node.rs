@@ -107,6 +107,13 @@ pub enum AlignSpec {
 /// processed by the rendering engine. They can be traversed, transformed, and
 /// analyzed using the provided utility functions.
 ///
+/// # Size
+///
+/// Every slot in an expression list is as large as the largest variant, so
+/// variants whose payload exceeds the common leaf size are boxed. A
+/// compile-time assertion below the enum keeps new variants from growing it;
+/// box the new payload if it fails.
+///
 /// # See Also
 ///
 /// * [`NodeType`] - The discriminant type for runtime type checking
@@ -120,7 +127,7 @@ pub enum AlignSpec {
 pub enum AnyParseNode {
     /// ## Structural Nodes
     /// Array/matrix environments with rows and columns
-    Array(ParseNodeArray),
+    Array(Box<ParseNodeArray>),
     /// Ordered groups of expressions (parentheses, etc.)
     OrdGroup(ParseNodeOrdGroup),
     /// Superscript/subscript combinations
@@ -128,7 +135,7 @@ pub enum AnyParseNode {
     /// Generalized fractions (fractions, binomials)
     Genfrac(Box<ParseNodeGenfrac>),
     /// Left-right delimiter pairs
-    LeftRight(ParseNodeLeftRight),
+    LeftRight(Box<ParseNodeLeftRight>),
     #[strum_discriminants(strum(serialize = "leftright-right"))]
     /// Right delimiters in left-right delimiter pairs (\left...\right).
     LeftRightRight(ParseNodeLeftRightRight),
@@ -169,7 +176,7 @@ pub enum AnyParseNode {
     /// Vertical phantom content for spacing (\vphantom{...}).
     Vphantom(ParseNodeVphantom),
     /// Horizontal/vertical rules
-    Rule(ParseNodeRule),
+    Rule(Box<ParseNodeRule>),
 
     /// ## Miscellaneous Nodes
     /// Labels for arrows in commutative diagram environments
@@ -207,8 +214,10 @@ pub enum AnyParseNode {
     Cr(ParseNodeCr),
     /// Delimiter sizing for proper enclosure (\big, \Big, \bigg, etc.).
     Delimsizing(ParseNodeDelimsizing),
+    /// Boxes split by a diagonal rule, for table headers (\diagbox{a}{b}).
+    Diagbox(Box<ParseNodeDiagbox>),
     /// Enclosed expressions with styling (\boxed, \colorbox, etc.).
-    Enclose(ParseNodeEnclose),
+    Enclose(Box<ParseNodeEnclose>),
     /// Custom mathematical environments (\begin{env}...\end{env}).
     Environment(Box<ParseNodeEnvironment>),
     /// Horizontal boxes for grouping content (\hbox{...}).
@@ -218,13 +227,13 @@ pub enum AnyParseNode {
     /// Hyperlinks with custom text (\href{url}{text}).
     Href(ParseNodeHref),
     /// Embedded HTML content within math expressions.
-    Html(ParseNodeHtml),
+    Html(Box<ParseNodeHtml>),
     /// Content renderable in both HTML and MathML formats.
     HtmlMathMl(ParseNodeHtmlMathMl),
     /// Included graphics/images (\includegraphics{...}).
-    Includegraphics(ParseNodeIncludegraphics),
+    Includegraphics(Box<ParseNodeIncludegraphics>),
     /// Custom infix operators between operands.
-    Infix(ParseNodeInfix),
+    Infix(Box<ParseNodeInfix>),
     /// Internal parser nodes for implementation details.
     Internal(ParseNodeInternal),
     /// Explicit kerning/spacing adjustments (\kern, \mkern).
@@ -233,22 +242,28 @@ pub enum AnyParseNode {
     Lap(ParseNodeLap),
     /// Different renderings for display/text/script modes
     /// (\mathchoice{...}{...}{...}{...}).
-    MathChoice(ParseNodeMathChoice),
+    MathChoice(Box<ParseNodeMathChoice>),
     /// Middle delimiters in expressions (\middle|).
     Middle(ParseNodeMiddle),
     /// Math class specifications for spacing and rendering (mord, mbin, mrel,
     /// etc.).
     Mclass(ParseNodeMclass),
+    /// Nuclides with mass and atomic numbers as prescripts (\nuclide[14][6]{C}).
+    Nuclide(Box<ParseNodeNuclide>),
     /// Operator names with special formatting (\operatorname{...}).
     OperatorName(ParseNodeOperatorName),
     /// Poor man's bold text formatting (\pmb{...}).
     Pmb(ParseNodePmb),
     /// Raised or lowered content (\raisebox{...}{...}).
     Raisebox(ParseNodeRaisebox),
+    /// Big operators with pre- and post-scripts (\sideset{...}{...}\sum).
+    Sideset(ParseNodeSideset),
     /// Size changes for expressions (\scriptsize, \large, etc.).
     Sizing(ParseNodeSizing),
     /// Smashed content ignoring height/depth (\smash{...}).
     Smash(ParseNodeSmash),
+    /// Tensors with staggered index slots (\tensor{T}{^a_b}).
+    Tensor(Box<ParseNodeTensor>),
     /// Vertically centered content (\vcenter{...}).
     Vcenter(ParseNodeVcenter),
     #[strum_discriminants(strum(serialize = "x-arrow"))]
@@ -256,6 +271,10 @@ pub enum AnyParseNode {
     XArrow(ParseNodeXArrow),
 }
 
+// Boxing the nine largest payloads took this from 184 to 96 bytes; see
+// docs/BENCHMARK.md for the effect on parse tree memory
+const _: () = assert!(size_of::<AnyParseNode>() <= 96);
+
 impl AnyParseNode {
     /// TeXbook algorithms often reference "character boxes", which are simply
     /// groups with a single character in them. To decide if something is a
@@ -339,6 +358,7 @@ impl AnyParseNode {
             Self::AccentUnder(node) => node.mode,
             Self::Cr(node) => node.mode,
             Self::Delimsizing(node) => node.mode,
+            Self::Diagbox(node) => node.mode,
             Self::Enclose(node) => node.mode,
             Self::Environment(node) => node.mode,
             Self::Hbox(node) => node.mode,
@@ -357,6 +377,9 @@ impl AnyParseNode {
             Self::OperatorName(node) => node.mode,
             Self::Pmb(node) => node.mode,
             Self::Raisebox(node) => node.mode,
+            Self::Sideset(node) => node.mode,
+            Self::Nuclide(node) => node.mode,
+            Self::Tensor(node) => node.mode,
             Self::Sizing(node) => node.mode,
             Self::Smash(node) => node.mode,
             Self::Vcenter(node) => node.mode,
@@ -1414,6 +1437,36 @@ pub struct ParseNodeDelimsizing {
     pub delim: String,
 }
 
+/// Represents a box split by a diagonal rule into two labelled halves.
+///
+/// This struct handles the `\diagbox` command from the LaTeX diagbox
+/// package, which labels the rows and the columns of a table in its top left
+/// cell. Inside an array the box fills its whole cell.
+///
+/// # Fields
+///
+/// * `mode` - The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+/// * `loc` - Optional source location for error reporting
+/// * `lower` - The label below the rule, in the bottom left corner
+/// * `upper` - The label above the rule, in the top right corner
+///
+/// # LaTeX Correspondence
+///
+/// ```latex
+/// \begin{array}{c|cc} \diagbox{x}{y} & 0 & 1 \\ \hline 0 & a & b \end{array}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNodeDiagbox {
+    /// The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+    pub mode: Mode,
+    /// Optional source location for error reporting
+    pub loc: Option<SourceLocation>,
+    /// The label in the bottom left corner
+    pub lower: Box<AnyParseNode>,
+    /// The label in the top right corner
+    pub upper: Box<AnyParseNode>,
+}
+
 /// Represents enclosed mathematical expressions with background and border
 /// styling.
 ///
@@ -2174,6 +2227,134 @@ pub struct ParseNodeOperatorName {
     pub parent_is_sup_sub: bool,
 }
 
+/// Represents a nuclide with its mass and atomic numbers as prescripts.
+///
+/// This struct handles the `\nuclide` command, the notation for isotopes
+/// used in chemistry and nuclear physics. Both numbers sit in one column
+/// before the element symbol and are aligned to its right, so they hug the
+/// symbol whatever their widths.
+///
+/// # Fields
+///
+/// * `mode` - The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+/// * `loc` - Optional source location for error reporting
+/// * `base` - The element symbol, set upright
+/// * `mass` - Optional mass number, placed like a superscript
+/// * `atomic` - Optional atomic number, placed like a subscript
+///
+/// # LaTeX Correspondence
+///
+/// ```latex
+/// \nuclide[14][6]{C}
+/// \nuclide[235]{U}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNodeNuclide {
+    /// The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+    pub mode: Mode,
+    /// Optional source location for error reporting
+    pub loc: Option<SourceLocation>,
+    /// The element symbol
+    pub base: Box<AnyParseNode>,
+    /// The mass number
+    pub mass: Option<Box<AnyParseNode>>,
+    /// The atomic number
+    pub atomic: Option<Box<AnyParseNode>>,
+}
+
+/// Represents a big operator decorated with scripts on both sides.
+///
+/// This struct handles amsmath's `\sideset`, which places sub/superscripts
+/// to the left and right of an operator while leaving the usual limits
+/// position free for a following `_` and `^`.
+///
+/// # Fields
+///
+/// * `mode` - The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+/// * `loc` - Optional source location for error reporting
+/// * `base` - The decorated operator, with its own limits disabled
+/// * `pre_sup` / `pre_sub` - Scripts placed before the operator
+/// * `post_sup` / `post_sub` - Scripts placed after the operator
+/// * `limits` - Whether trailing scripts are set above/below in display style
+/// * `always_handle_sup_sub` - Whether `\limits`/`\nolimits` forced the limit
+///   placement regardless of style
+///
+/// # LaTeX Correspondence
+///
+/// ```latex
+/// \sideset{_a^b}{_c^d}\sum
+/// \sideset{}{'}\sum_{n<k}
+/// ```
+///
+/// # Usage
+///
+/// Sideset nodes keep the operator spacing of the base while attaching
+/// chemistry-style or physics-style pre-scripts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNodeSideset {
+    /// The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+    pub mode: Mode,
+    /// Optional source location for error reporting
+    pub loc: Option<SourceLocation>,
+    /// The decorated operator
+    pub base: Box<AnyParseNode>,
+    /// Superscript placed before the operator
+    pub pre_sup: Option<Box<AnyParseNode>>,
+    /// Subscript placed before the operator
+    pub pre_sub: Option<Box<AnyParseNode>>,
+    /// Superscript placed after the operator
+    pub post_sup: Option<Box<AnyParseNode>>,
+    /// Subscript placed after the operator
+    pub post_sub: Option<Box<AnyParseNode>>,
+    /// Whether to place limits above/below (true) or as scripts (false)
+    pub limits: bool,
+    /// Force special subscript/superscript handling
+    pub always_handle_sup_sub: bool,
+}
+
+/// Represents a tensor with staggered index slots.
+///
+/// This struct handles the `\tensor` command from the LaTeX tensor package.
+/// Every index occupies its own horizontal slot, so upper and lower indices
+/// never share a column and their order is preserved.
+///
+/// # Fields
+///
+/// * `mode` - The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+/// * `loc` - Optional source location for error reporting
+/// * `base` - The tensor symbol the indices are attached to
+/// * `pre` - Index slots placed before the base, in source order
+/// * `post` - Index slots placed after the base, in source order
+///
+/// # LaTeX Correspondence
+///
+/// ```latex
+/// \tensor{R}{^a_{bcd}}
+/// \tensor[^a_b]{T}{^c_d}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNodeTensor {
+    /// The parsing mode ([`Mode::Math`] or [`Mode::Text`])
+    pub mode: Mode,
+    /// Optional source location for error reporting
+    pub loc: Option<SourceLocation>,
+    /// The tensor symbol
+    pub base: Box<AnyParseNode>,
+    /// Index slots placed before the base
+    pub pre: Vec<TensorIndex>,
+    /// Index slots placed after the base
+    pub post: Vec<TensorIndex>,
+}
+
+/// A single index slot of a [`ParseNodeTensor`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorIndex {
+    /// An upper (contravariant) index, written `^a`
+    Upper(AnyParseNode),
+    /// A lower (covariant) index, written `_a`
+    Lower(AnyParseNode),
+}
+
 /// Represents overlines above mathematical expressions.
 ///
 /// This struct handles horizontal lines drawn above mathematical content
@@ -2842,7 +3023,7 @@ pub fn assert_node_type(
 /// assert!(check_symbol_node_type(Some(&atom_node)).is_some());
 ///
 /// // Non-symbol node - will return None
-/// let array_node = AnyParseNode::Array(ParseNodeArray {
+/// let array_node = AnyParseNode::Array(Box::new(ParseNodeArray {
 ///     mode: Mode::Math,
 ///     loc: None,
 ///     col_separation_type: None,
@@ -2856,7 +3037,7 @@ pub fn assert_node_type(
 ///     tags: None,
 ///     leqno: None,
 ///     is_cd: None,
-/// });
+/// }));
 /// assert!(check_symbol_node_type(Some(&array_node)).is_none());
 ///
 /// // None input - will return None
@@ -3022,7 +3203,7 @@ mod tests {
 
     #[test]
     fn test_check_symbol_node_type_failure() {
-        let node = AnyParseNode::Array(ParseNodeArray {
+        let node = AnyParseNode::Array(Box::new(ParseNodeArray {
             mode: Mode::Math,
             loc: None,
             col_separation_type: None,
@@ -3036,7 +3217,7 @@ mod tests {
             tags: None,
             leqno: None,
             is_cd: None,
-        });
+        }));
 
         let result = check_symbol_node_type(Some(&node));
         assert!(result.is_none());
@@ -3057,7 +3238,7 @@ mod tests {
 
     #[test]
     fn test_assert_symbol_node_type_failure() {
-        let node = AnyParseNode::Array(ParseNodeArray {
+        let node = AnyParseNode::Array(Box::new(ParseNodeArray {
             mode: Mode::Math,
             loc: None,
             col_separation_type: None,
@@ -3071,7 +3252,7 @@ mod tests {
             tags: None,
             leqno: None,
             is_cd: None,
-        });
+        }));
 
         let result = assert_symbol_node_type(Some(&node));
         assert!(result.is_err());
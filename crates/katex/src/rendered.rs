@@ -0,0 +1,149 @@
+//! Rendered markup that templates can embed without escaping it again
+//!
+//! [`render_to_string`] returns a plain [`String`]. Template engines escape
+//! interpolated strings by default, so dropping it into a template prints the
+//! markup as text (`&lt;span class=&quot;katex&quot;&gt;...`). The usual
+//! workaround, disabling escaping at every call site, is easy to forget and
+//! just as easy to apply to untrusted input by mistake.
+//!
+//! [`RenderedMath`] marks the output of a render as HTML that is already safe
+//! to embed. It is created by rendering rather than from arbitrary strings,
+//! and it implements the raw output traits of the supported template
+//! engines:
+//!
+//! - `maud`: `maud::Render`, with the `maud` feature
+//! - `askama`: `askama::filters::HtmlSafe`, with the `askama` feature
+//!
+//! ```rust
+//! use katex::{KatexContext, RenderedMath, Settings};
+//!
+//! let ctx = KatexContext::default();
+//! let math = RenderedMath::render(&ctx, r"\frac{a}{b}", &Settings::default()).unwrap();
+//! assert!(math.as_str().starts_with("<span class=\"katex\">"));
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "askama")]
+use askama::filters::HtmlSafe;
+
+use crate::context::KatexContext;
+use crate::core::{RenderOutput, render_to_string};
+use crate::types::{ParseError, Settings};
+
+/// HTML markup produced by KaTeX, trusted to be embedded as is
+///
+/// Everything from the input expression that ends up in the markup has been
+/// escaped by the renderer, so the markup itself must not be escaped again.
+/// See the [module documentation](self) for the template integrations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RenderedMath(String);
+
+impl RenderedMath {
+    /// Renders `expression` like [`render_to_string`] and marks the result
+    /// as trusted markup.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ParseError`] from [`render_to_string`].
+    pub fn render(
+        ctx: &KatexContext,
+        expression: &str,
+        settings: &Settings,
+    ) -> Result<Self, ParseError> {
+        render_to_string(ctx, expression, settings).map(Self)
+    }
+
+    /// The markup as a string slice
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps the markup, giving up the trusted marker
+    #[must_use]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl From<RenderOutput> for RenderedMath {
+    fn from(output: RenderOutput) -> Self {
+        Self(output.markup)
+    }
+}
+
+impl From<RenderedMath> for String {
+    fn from(math: RenderedMath) -> Self {
+        math.0
+    }
+}
+
+impl AsRef<str> for RenderedMath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RenderedMath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "maud")]
+impl maud::Render for RenderedMath {
+    fn render_to(&self, buffer: &mut String) {
+        buffer.push_str(&self.0);
+    }
+}
+
+#[cfg(feature = "askama")]
+impl HtmlSafe for RenderedMath {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(expression: &str) -> RenderedMath {
+        RenderedMath::render(&KatexContext::default(), expression, &Settings::default()).unwrap()
+    }
+
+    #[test]
+    fn test_display_writes_markup_unchanged() {
+        let math = rendered("a < b");
+        assert_eq!(math.to_string(), math.as_str());
+        assert!(math.as_str().contains("&lt;"));
+    }
+
+    #[test]
+    fn test_render_reports_errors() {
+        let result =
+            RenderedMath::render(&KatexContext::default(), r"\frac{a}{", &Settings::default());
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "maud")]
+    #[test]
+    fn test_maud_does_not_escape() {
+        let math = rendered("x^2");
+        let page = maud::html! { p { (math) } };
+        assert_eq!(page.into_string(), format!("<p>{}</p>", math.as_str()));
+    }
+
+    #[cfg(feature = "askama")]
+    #[test]
+    fn test_askama_does_not_escape() {
+        use askama::Template;
+
+        #[derive(Template)]
+        #[template(source = "<p>{{ math }}</p>", ext = "html")]
+        struct Page {
+            math: RenderedMath,
+        }
+
+        let math = rendered("x^2");
+        let expected = format!("<p>{}</p>", math.as_str());
+        assert_eq!(Page { math }.render().unwrap(), expected);
+    }
+}
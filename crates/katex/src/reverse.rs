@@ -0,0 +1,786 @@
+//! Recovery of parse trees from rendered KaTeX markup
+//!
+//! Pages that were rendered ahead of time often keep only the generated
+//! HTML. [`from_html`] reads such markup back into a parse tree so that
+//! migration tools can re-render or convert formulas whose source is lost.
+//!
+//! Two paths are tried in order:
+//!
+//! 1. The MathML half of KaTeX's default output carries the original TeX in
+//!    an `<annotation encoding="application/x-tex">` element. When it is
+//!    present, that source is parsed and the result is exact.
+//! 2. Otherwise the `katex-html` span tree is walked and a TeX approximation
+//!    is rebuilt from its classes: glyphs, fractions, binomials, scripts,
+//!    operator limits, radicals, accents, over- and underlines, `\left` and
+//!    `\right` delimiters, fonts, text, spacing and arrays. Colours, sizes,
+//!    style changes and equation tags do not survive this path, and SVG-only
+//!    constructions such as stretchy arrows are dropped.
+//!
+//! The input is expected to hold one formula, for instance the outer
+//! `<span class="katex">` or `<span class="katex-display">` element. When it
+//! holds several, the first one is recovered.
+
+use core::fmt::Write as _;
+
+use thiserror::Error;
+
+use crate::context::KatexContext;
+use crate::parse_tree::parse_tree;
+use crate::parser::parse_node::AnyParseNode;
+use crate::types::{ParseError, Settings};
+
+/// Errors produced while recovering a formula from rendered markup
+#[derive(Debug, Error)]
+pub enum ReverseError {
+    /// The markup contains neither a TeX annotation nor a `katex-html` tree
+    #[error("no KaTeX markup found")]
+    NotKatex,
+    /// The recovered TeX source could not be parsed
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// How the TeX source of a formula was recovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Taken verbatim from the `application/x-tex` annotation
+    Annotation,
+    /// Rebuilt from the structure of the `katex-html` spans
+    Structure,
+}
+
+/// TeX source recovered from rendered markup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    /// The TeX source of the formula
+    pub tex: String,
+    /// Whether the formula was rendered in display mode
+    pub display_mode: bool,
+    /// Which path produced [`Recovered::tex`]
+    pub recovery: Recovery,
+}
+
+/// Recovers the TeX source of a formula from KaTeX-generated HTML.
+///
+/// # Errors
+///
+/// Returns [`ReverseError::NotKatex`] when the markup holds no KaTeX output.
+pub fn recover_tex(ctx: &KatexContext, html: &str) -> Result<Recovered, ReverseError> {
+    let root = Element {
+        name: String::new(),
+        attributes: Vec::new(),
+        children: parse_html(html),
+    };
+    let display_mode = root
+        .find(&|e| e.has_class("katex-display") || e.attribute("display") == Some("block"))
+        .is_some();
+    let annotation = root
+        .find(&|e| e.name == "annotation" && e.attribute("encoding") == Some("application/x-tex"));
+    if let Some(annotation) = annotation {
+        return Ok(Recovered {
+            tex: annotation.text(),
+            display_mode,
+            recovery: Recovery::Annotation,
+        });
+    }
+    let html = root
+        .find(&|e| e.has_class("katex-html"))
+        .ok_or(ReverseError::NotKatex)?;
+    let mut writer = Writer {
+        ctx,
+        out: String::new(),
+    };
+    writer.sequence(&html.children);
+    Ok(Recovered {
+        tex: writer.out.trim().to_owned(),
+        display_mode,
+        recovery: Recovery::Structure,
+    })
+}
+
+/// Reconstructs the parse tree of a formula from KaTeX-generated HTML.
+///
+/// The TeX source is recovered with [`recover_tex`] and parsed in the mode
+/// the formula was rendered in.
+///
+/// # Errors
+///
+/// Returns [`ReverseError::NotKatex`] when the markup holds no KaTeX output,
+/// and [`ReverseError::Parse`] when the recovered source does not parse.
+pub fn from_html(ctx: &KatexContext, html: &str) -> Result<Vec<AnyParseNode>, ReverseError> {
+    let recovered = recover_tex(ctx, html)?;
+    let settings = Settings::builder()
+        .display_mode(recovered.display_mode)
+        .build();
+    Ok(parse_tree(ctx, &recovered.tex, &settings)?)
+}
+
+/// A node of the parsed markup
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// An element of the parsed markup
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.attribute("class")
+            .is_some_and(|classes| classes.split_ascii_whitespace().any(|c| c == class))
+    }
+
+    /// Reads a length in `em` from the inline style, e.g. `top` or `height`.
+    fn style_em(&self, property: &str) -> Option<f64> {
+        self.attribute("style")?.split(';').find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim() == property)
+                .then(|| value.trim().strip_suffix("em")?.parse().ok())
+                .flatten()
+        })
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Finds the first element in document order, including `self`.
+    fn find(&self, predicate: &dyn Fn(&Self) -> bool) -> Option<&Self> {
+        if predicate(self) {
+            return Some(self);
+        }
+        self.elements().find_map(|child| child.find(predicate))
+    }
+
+    fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text
+    }
+
+    fn collect_text(&self, text: &mut String) {
+        for child in &self.children {
+            match child {
+                Node::Element(element) => element.collect_text(text),
+                Node::Text(t) => text.push_str(t),
+            }
+        }
+    }
+
+    /// The rows of the first vertical list inside this element, bottom first,
+    /// skipping rows that hold nothing but a strut.
+    fn vlist_rows(&self) -> Vec<&Self> {
+        self.find(&|e| e.has_class("vlist"))
+            .map(|vlist| {
+                vlist
+                    .elements()
+                    .filter(|row| row.elements().any(|e| !e.has_class("pstrut")))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Elements whose content must not be copied into the recovered source
+const SKIPPED_CLASSES: &[&str] = &[
+    "strut",
+    "pstrut",
+    "vlist-s",
+    "katex-mathml",
+    "frac-line",
+    "nulldelimiter",
+    "hide-tail",
+    "tag",
+    "arraycolsep",
+    "vertical-separator",
+    "overline-line",
+    "underline-line",
+];
+
+/// Font classes and the commands that produce them
+const FONTS: &[(&str, &str)] = &[
+    ("mathbb", "\\mathbb"),
+    ("mathcal", "\\mathcal"),
+    ("mathfrak", "\\mathfrak"),
+    ("mathscr", "\\mathscr"),
+    ("mathsf", "\\mathsf"),
+    ("mathtt", "\\mathtt"),
+    ("mathbf", "\\mathbf"),
+    ("mathrm", "\\mathrm"),
+    ("mathit", "\\mathit"),
+    ("boldsymbol", "\\boldsymbol"),
+];
+
+/// Accent glyphs and the commands that produce them
+const ACCENTS: &[(&str, &str)] = &[
+    ("^", "\\hat"),
+    ("\u{2c6}", "\\hat"),
+    ("~", "\\tilde"),
+    ("\u{2dc}", "\\tilde"),
+    ("\u{2c9}", "\\bar"),
+    ("\u{af}", "\\bar"),
+    ("\u{2d9}", "\\dot"),
+    ("\u{a8}", "\\ddot"),
+    ("\u{2c7}", "\\check"),
+    ("\u{2d8}", "\\breve"),
+    ("\u{2ca}", "\\acute"),
+    ("\u{b4}", "\\acute"),
+    ("\u{2cb}", "\\grave"),
+    ("`", "\\grave"),
+    ("\u{2da}", "\\mathring"),
+];
+
+/// Explicit spaces by width in `em`
+const SPACES: &[(f64, &str)] = &[
+    (0.1667, "\\,"),
+    (0.2222, "\\:"),
+    (0.2778, "\\;"),
+    (-0.1667, "\\!"),
+    (1.0, "\\quad"),
+    (2.0, "\\qquad"),
+];
+
+/// Atom classes next to which KaTeX inserts spacing on its own
+const SPACED_CLASSES: &[&str] = &["mbin", "mrel", "mop", "mpunct", "minner"];
+
+struct Writer<'a> {
+    ctx: &'a KatexContext,
+    out: String,
+}
+
+impl Writer<'_> {
+    /// Appends source, separating it from a preceding control word.
+    fn push(&mut self, s: &str) {
+        let ends_with_word = self
+            .out
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .ends_with('\\')
+            && self.out.ends_with(|c: char| c.is_ascii_alphabetic());
+        if ends_with_word && s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            self.out.push(' ');
+        }
+        self.out.push_str(s);
+    }
+
+    fn group(&mut self, command: &str, nodes: &[Node]) {
+        self.push(command);
+        self.out.push('{');
+        self.sequence(nodes);
+        self.out.push('}');
+    }
+
+    fn row(&mut self, command: &str, row: Option<&Element>) {
+        self.push(command);
+        self.out.push('{');
+        if let Some(row) = row {
+            self.sequence(&row.children);
+        }
+        self.out.push('}');
+    }
+
+    fn sequence(&mut self, nodes: &[Node]) {
+        for (i, node) in nodes.iter().enumerate() {
+            match node {
+                Node::Text(text) => self.math_text(text),
+                Node::Element(e) if e.has_class("newline") => self.push("\\\\"),
+                Node::Element(e) if e.has_class("mspace") => {
+                    let spaced = |node: Option<&Node>| {
+                        matches!(node, Some(Node::Element(n))
+                            if SPACED_CLASSES.iter().any(|c| n.has_class(c)))
+                    };
+                    let before = i.checked_sub(1).and_then(|j| nodes.get(j));
+                    if !spaced(before) && !spaced(nodes.get(i + 1)) {
+                        self.space(e);
+                    }
+                }
+                Node::Element(e) => self.element(e),
+            }
+        }
+    }
+
+    fn space(&mut self, e: &Element) {
+        let Some(width) = e.style_em("margin-right") else {
+            return;
+        };
+        if let Some((_, command)) = SPACES.iter().find(|(w, _)| (w - width).abs() < 1e-3) {
+            self.push(command);
+        } else {
+            self.push("\\hspace");
+            _ = write!(self.out, "{{{width}em}}");
+        }
+    }
+
+    fn element(&mut self, e: &Element) {
+        if e.name == "svg" || SKIPPED_CLASSES.iter().any(|c| e.has_class(c)) {
+            return;
+        }
+        if self.fraction(e) {
+            return;
+        }
+        if e.has_class("msupsub") {
+            self.scripts(e);
+        } else if e.has_class("op-limits") {
+            self.limits(e);
+        } else if e.has_class("sqrt") {
+            self.sqrt(e);
+        } else if e.has_class("accent") {
+            self.accent(e);
+        } else if e.has_class("overline") || e.has_class("underline") {
+            let command = if e.has_class("overline") {
+                "\\overline"
+            } else {
+                "\\underline"
+            };
+            let rows = e.vlist_rows();
+            let body = rows.iter().find(|row| {
+                row.find(&|n| n.has_class("overline-line") || n.has_class("underline-line"))
+                    .is_none()
+            });
+            self.row(command, body.copied());
+        } else if e.has_class("mtable") {
+            self.table(e);
+        } else if e.has_class("text") {
+            self.push("\\text{");
+            self.text_mode(&e.text());
+            self.out.push('}');
+        } else if let Some((_, command)) = FONTS.iter().find(|(class, _)| e.has_class(class)) {
+            self.group(command, &e.children);
+        } else if e.has_class("mop") && e.find(&|n| n.has_class("vlist")).is_none() {
+            self.operator(&e.text());
+        } else if !self.delimited(e) {
+            self.sequence(&e.children);
+        }
+    }
+
+    /// Writes an operator name such as `sin`, or a large operator glyph.
+    fn operator(&mut self, name: &str) {
+        if name.chars().count() > 1 && name.chars().all(|c| c.is_ascii_alphabetic()) {
+            let command = format!("\\{name}");
+            if self.ctx.functions.contains_key(&command) {
+                self.push(&command);
+            } else {
+                self.push("\\operatorname{");
+                self.out.push_str(name);
+                self.out.push('}');
+            }
+        } else {
+            self.math_text(name);
+        }
+    }
+
+    /// Writes `\left … \right` for an inner atom bounded by delimiters.
+    fn delimited(&mut self, e: &Element) -> bool {
+        let children: Vec<_> = e.children.iter().collect();
+        let (Some(Node::Element(open)), Some(Node::Element(close))) =
+            (children.first(), children.last())
+        else {
+            return false;
+        };
+        if !e.has_class("minner") || !open.has_class("mopen") || !close.has_class("mclose") {
+            return false;
+        }
+        self.push("\\left");
+        self.delimiter(&open.text());
+        self.sequence(&e.children[1..e.children.len() - 1]);
+        self.push("\\right");
+        self.delimiter(&close.text());
+        true
+    }
+
+    fn delimiter(&mut self, glyph: &str) {
+        match glyph {
+            "" => self.out.push('.'),
+            "{" => self.out.push_str("\\{"),
+            "}" => self.out.push_str("\\}"),
+            "\u{2016}" => self.out.push_str("\\|"),
+            _ => self.math_text(glyph),
+        }
+    }
+
+    /// Writes a fraction together with the delimiters around it, which
+    /// KaTeX renders as siblings of the `mfrac` span.
+    fn fraction(&mut self, e: &Element) -> bool {
+        let elements: Vec<_> = e.elements().collect();
+        let [open, frac, close] = elements[..] else {
+            return false;
+        };
+        if !frac.has_class("mfrac") || !open.has_class("mopen") || !close.has_class("mclose") {
+            return false;
+        }
+        let rows = frac.vlist_rows();
+        let has_line = rows
+            .iter()
+            .any(|row| row.find(&|n| n.has_class("frac-line")).is_some());
+        let parts: Vec<_> = rows
+            .into_iter()
+            .filter(|row| row.find(&|n| n.has_class("frac-line")).is_none())
+            .collect();
+        let (denominator, numerator) = (parts.first().copied(), parts.last().copied());
+        let (left, right) = (open.text(), close.text());
+        let delimited = !(left.is_empty() && right.is_empty());
+        if !has_line && left == "(" && right == ")" {
+            self.row("\\binom", numerator);
+            self.row("", denominator);
+            return true;
+        }
+        if delimited {
+            self.push("\\left");
+            self.delimiter(&left);
+        }
+        if has_line {
+            self.row("\\frac", numerator);
+            self.row("", denominator);
+        } else {
+            self.out.push('{');
+            if let Some(numerator) = numerator {
+                self.sequence(&numerator.children);
+            }
+            self.push("\\atop ");
+            if let Some(denominator) = denominator {
+                self.sequence(&denominator.children);
+            }
+            self.out.push('}');
+        }
+        if delimited {
+            self.push("\\right");
+            self.delimiter(&right);
+        }
+        true
+    }
+
+    fn scripts(&mut self, e: &Element) {
+        match e.vlist_rows()[..] {
+            [sub, sup] => {
+                self.row("_", Some(sub));
+                self.row("^", Some(sup));
+            }
+            [row] => {
+                // A superscript is raised above the baseline of its strut,
+                // a subscript is lowered below it.
+                let strut = row
+                    .find(&|n| n.has_class("pstrut"))
+                    .and_then(|p| p.style_em("height"))
+                    .unwrap_or(2.7);
+                let raised = row.style_em("top").is_some_and(|top| top < -strut);
+                self.row(if raised { "^" } else { "_" }, Some(row));
+            }
+            _ => {}
+        }
+    }
+
+    fn limits(&mut self, e: &Element) {
+        let rows = e.vlist_rows();
+        let Some(base) = rows
+            .iter()
+            .position(|row| row.find(&|n| n.has_class("mop")).is_some())
+        else {
+            return;
+        };
+        self.sequence(&rows[base].children);
+        if rows.len() > 1 {
+            self.push("\\limits");
+        }
+        if base > 0 {
+            self.row("_", Some(rows[0]));
+        }
+        if let Some(sup) = rows.get(base + 1) {
+            self.row("^", Some(sup));
+        }
+    }
+
+    fn sqrt(&mut self, e: &Element) {
+        if let Some(index) = e.elements().find(|n| n.has_class("root")) {
+            self.push("\\sqrt[");
+            if let Some(row) = index.vlist_rows().first() {
+                self.sequence(&row.children);
+            }
+            self.out.push(']');
+        } else {
+            self.push("\\sqrt");
+        }
+        let body = e.find(&|n| n.has_class("svg-align"));
+        self.row("", body);
+    }
+
+    fn accent(&mut self, e: &Element) {
+        let rows = e.vlist_rows();
+        let accent_body = |row: &Element| row.find(&|n| n.has_class("accent-body")).is_some();
+        let body = rows.iter().find(|row| !accent_body(row)).copied();
+        let command = rows
+            .iter()
+            .find_map(|row| row.find(&|n| n.has_class("accent-body")))
+            .and_then(|accent| {
+                if accent.find(&|n| n.has_class("overlay")).is_some() {
+                    return Some("\\vec");
+                }
+                let glyph = accent.text();
+                ACCENTS
+                    .iter()
+                    .find(|(g, _)| *g == glyph)
+                    .map(|(_, command)| *command)
+            });
+        match command {
+            Some(command) => self.row(command, body),
+            None => {
+                if let Some(body) = body {
+                    self.sequence(&body.children);
+                }
+            }
+        }
+    }
+
+    /// Writes an array, which KaTeX lays out column by column.
+    fn table(&mut self, e: &Element) {
+        let mut alignment = String::new();
+        let mut columns = Vec::new();
+        for column in e.elements() {
+            let Some(align) = ["l", "c", "r"]
+                .into_iter()
+                .find(|a| column.has_class(&format!("col-align-{a}")))
+            else {
+                continue;
+            };
+            alignment.push_str(align);
+            // Rows are listed from the top, the opposite of other lists.
+            columns.push(column.vlist_rows());
+        }
+        let rows = columns.iter().map(Vec::len).max().unwrap_or(0);
+        let centered = alignment.chars().all(|c| c == 'c');
+        if centered {
+            self.push("\\begin{matrix}");
+        } else {
+            self.push("\\begin{array}");
+            _ = write!(self.out, "{{{alignment}}}");
+        }
+        for r in 0..rows {
+            if r > 0 {
+                self.out.push_str(" \\\\ ");
+            }
+            for (c, column) in columns.iter().enumerate() {
+                if c > 0 {
+                    self.out.push_str(" & ");
+                }
+                if let Some(cell) = column.get(r) {
+                    self.sequence(&cell.children);
+                }
+            }
+        }
+        self.push(if centered {
+            "\\end{matrix}"
+        } else {
+            "\\end{array}"
+        });
+    }
+
+    fn math_text(&mut self, text: &str) {
+        for c in text.chars() {
+            let escaped = match c {
+                '\u{200b}' => continue,
+                '\u{2212}' => "-",
+                '\u{2217}' => "*",
+                '\u{a0}' => "~",
+                '#' => "\\#",
+                '$' => "\\$",
+                '%' => "\\%",
+                '&' => "\\&",
+                '_' => "\\_",
+                '{' => "\\{",
+                '}' => "\\}",
+                '\\' => "\\backslash",
+                '~' => "\\sim",
+                '^' => "\\wedge",
+                _ => {
+                    let mut buffer = [0; 4];
+                    self.push(c.encode_utf8(&mut buffer));
+                    continue;
+                }
+            };
+            self.push(escaped);
+        }
+    }
+
+    fn text_mode(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\u{200b}' => {}
+                '\u{a0}' => self.out.push(' '),
+                '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                    self.out.push('\\');
+                    self.out.push(c);
+                }
+                '\\' => self.out.push_str("\\textbackslash "),
+                '~' => self.out.push_str("\\textasciitilde "),
+                '^' => self.out.push_str("\\textasciicircum "),
+                _ => self.out.push(c),
+            }
+        }
+    }
+}
+
+/// Elements that never have content or a closing tag
+const VOID_ELEMENTS: &[&str] = &["area", "br", "col", "hr", "img", "input", "link", "meta"];
+
+/// Parses markup into a node list, tolerating unclosed and stray tags.
+fn parse_html(html: &str) -> Vec<Node> {
+    let mut stack = vec![Element {
+        name: String::new(),
+        attributes: Vec::new(),
+        children: Vec::new(),
+    }];
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            let name = after[..end].trim().to_ascii_lowercase();
+            rest = after.get(end + 1..).unwrap_or("");
+            if let Some(depth) = stack.iter().rposition(|e| e.name == name)
+                && depth > 0
+            {
+                while stack.len() > depth {
+                    close_element(&mut stack);
+                }
+            }
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if rest
+            .strip_prefix('<')
+            .is_some_and(|after| after.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            let (element, self_closing, after) = parse_tag(&rest[1..]);
+            rest = after;
+            let void = self_closing || VOID_ELEMENTS.contains(&element.name.as_str());
+            stack.push(element);
+            if void {
+                close_element(&mut stack);
+            }
+        } else {
+            // A `<` that does not open a tag is kept as text.
+            let skip = usize::from(rest.starts_with('<'));
+            let end = rest[skip..].find('<').map_or(rest.len(), |i| i + skip);
+            let text = unescape(&rest[..end]);
+            rest = &rest[end..];
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(Node::Text(text));
+            }
+        }
+    }
+    while stack.len() > 1 {
+        close_element(&mut stack);
+    }
+    stack.pop().map(|root| root.children).unwrap_or_default()
+}
+
+fn close_element(stack: &mut Vec<Element>) {
+    if let Some(element) = stack.pop()
+        && let Some(parent) = stack.last_mut()
+    {
+        parent.children.push(Node::Element(element));
+    }
+}
+
+/// Parses an opening tag after its `<`, returning the element, whether the
+/// tag closed itself, and the remaining input.
+fn parse_tag(input: &str) -> (Element, bool, &str) {
+    let name_end = input
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(input.len());
+    let mut element = Element {
+        name: input[..name_end].to_ascii_lowercase(),
+        attributes: Vec::new(),
+        children: Vec::new(),
+    };
+    let mut rest = &input[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return (element, true, after);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (element, false, after);
+        }
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+        if rest.is_empty() {
+            return (element, false, rest);
+        }
+        let key_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            rest = after.trim_start();
+            let (raw, after) = if let Some(quote @ ('"' | '\'')) = rest.chars().next() {
+                let body = &rest[1..];
+                let end = body.find(quote).unwrap_or(body.len());
+                (&body[..end], body.get(end + 1..).unwrap_or(""))
+            } else {
+                let end = rest
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            };
+            value = unescape(raw);
+            rest = after;
+        }
+        element.attributes.push((key, value));
+    }
+}
+
+/// Decodes the character references KaTeX and common serializers emit.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let number = entity.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        if let Some((c, end)) = decoded {
+            out.push(c);
+            rest = &rest[end + 1..];
+        } else {
+            out.push('&');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
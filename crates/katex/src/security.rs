@@ -0,0 +1,540 @@
+//! URL validation for trust-gated commands
+//!
+//! `\href`, `\url` and `\includegraphics` put user-supplied URLs into the
+//! output. All of them ask [`Settings::is_trusted`] for permission, which
+//! first validates the URL with [`check_url`]. The check
+//!
+//! - rejects control characters and bidirectional formatting characters,
+//!   which browsers strip or which disguise how a URL reads,
+//! - determines the protocol the way browsers do, including through HTML
+//!   entities for the colon (see [`protocol_from_url`]),
+//! - rejects protocols that run scripts ([`SCRIPT_PROTOCOLS`]), even when
+//!   all content is trusted,
+//! - extracts the host and converts internationalized domain names to their
+//!   ASCII form, so that trust functions compare hosts without being fooled
+//!   by lookalike Unicode characters.
+//!
+//! The protocol and host are passed on to the trust function in the
+//! [`TrustContext`]. [`allow_protocols`] builds a trust setting from a
+//! protocol allowlist.
+//!
+//! [`Settings::is_trusted`]: crate::types::Settings::is_trusted
+//! [`TrustContext`]: crate::types::TrustContext
+
+use alloc::sync::Arc;
+
+use thiserror::Error;
+
+#[cfg(test)]
+use crate::types::TrustContext;
+use crate::types::TrustSetting;
+
+/// Protocols that execute code when a link is followed, rejected regardless
+/// of the trust setting
+pub const SCRIPT_PROTOCOLS: &[&str] = &["javascript", "vbscript"];
+
+/// Reasons a URL is refused before it reaches the trust setting
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum UrlError {
+    /// The URL contains a control or bidirectional formatting character
+    #[error("URL contains the disallowed character U+{code:04X} at byte {index}")]
+    DisallowedCharacter {
+        /// Byte offset of the character in the URL
+        index: usize,
+        /// Code point of the character
+        code: u32,
+    },
+    /// The protocol is not a valid URL scheme
+    #[error("URL has a malformed protocol")]
+    MalformedProtocol,
+    /// The protocol runs scripts, see [`SCRIPT_PROTOCOLS`]
+    #[error("the {0}: protocol is not allowed")]
+    ScriptProtocol(String),
+    /// The host cannot be converted to an ASCII domain name
+    #[error("URL host {0:?} is not a valid domain name")]
+    InvalidHost(String),
+}
+
+/// The parts of a URL that trust decisions are based on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlInfo {
+    /// Lowercased protocol without the colon, or `"_relative"` for a relative
+    /// URL
+    pub protocol: String,
+    /// Lowercased host in ASCII form, with internationalized labels encoded
+    /// as punycode (`xn--...`). `None` when the URL has no authority part,
+    /// as in `mailto:` links and most relative URLs.
+    pub host: Option<String>,
+}
+
+/// Validates `url` and extracts its protocol and host.
+///
+/// ```rust
+/// use katex::security::{UrlError, check_url};
+///
+/// let info = check_url("https://b\u{fc}cher.example/index.html").unwrap();
+/// assert_eq!(info.protocol, "https");
+/// assert_eq!(info.host.as_deref(), Some("xn--bcher-kva.example"));
+///
+/// assert!(matches!(
+///     check_url("JavaScript:alert(1)"),
+///     Err(UrlError::ScriptProtocol(_))
+/// ));
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`UrlError`] describing the first problem found.
+pub fn check_url(url: &str) -> Result<UrlInfo, UrlError> {
+    if let Some((index, ch)) = url.char_indices().find(|&(_, ch)| is_disallowed(ch)) {
+        return Err(UrlError::DisallowedCharacter {
+            index,
+            code: u32::from(ch),
+        });
+    }
+
+    let protocol = protocol_from_url(url).ok_or(UrlError::MalformedProtocol)?;
+    if SCRIPT_PROTOCOLS.contains(&protocol.as_str()) {
+        return Err(UrlError::ScriptProtocol(protocol));
+    }
+
+    let host = authority(url.trim_start(), &protocol)
+        .map(host_of)
+        .map(ascii_host)
+        .transpose()?;
+    Ok(UrlInfo { protocol, host })
+}
+
+/// Builds a [`TrustSetting`] that trusts URLs with one of the given
+/// protocols and nothing else.
+///
+/// Include `"_relative"` to allow relative URLs. Commands without a URL, such
+/// as `\htmlClass`, are not trusted.
+///
+/// ```rust
+/// use katex::security::allow_protocols;
+/// use katex::{KatexContext, Settings, render_to_string};
+///
+/// let settings = Settings::builder()
+///     .trust(allow_protocols(["https", "mailto"]))
+///     .build();
+/// let ctx = KatexContext::default();
+/// let html = render_to_string(&ctx, r"\href{https://katex.org}{K}", &settings).unwrap();
+/// assert!(html.contains("<a href"));
+/// let html = render_to_string(&ctx, r"\href{ftp://katex.org}{K}", &settings).unwrap();
+/// assert!(!html.contains("<a href"));
+/// ```
+pub fn allow_protocols<I, S>(protocols: I) -> TrustSetting
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let allowed: Vec<String> = protocols
+        .into_iter()
+        .map(|protocol| protocol.as_ref().to_ascii_lowercase())
+        .collect();
+    TrustSetting::Function(Arc::new(move |context| {
+        Some(
+            context
+                .protocol
+                .as_ref()
+                .is_some_and(|protocol| allowed.contains(protocol)),
+        )
+    }))
+}
+
+/// C0 and C1 controls, and the characters that reorder bidirectional text
+const fn is_disallowed(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{0}'..='\u{1f}'
+            | '\u{7f}'..='\u{9f}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// The authority part of `url`: whatever follows `//` up to the path,
+/// query or fragment
+fn authority<'a>(url: &'a str, protocol: &str) -> Option<&'a str> {
+    let rest = if protocol == "_relative" {
+        url
+    } else {
+        &url[protocol.len() + 1..]
+    };
+    let rest = rest
+        .strip_prefix("//")
+        .or_else(|| rest.strip_prefix(r"\\"))?;
+    let end = rest.find(['/', '\\', '?', '#']).unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// The host of an authority, without user information and port
+fn host_of(authority: &str) -> &str {
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.starts_with('[') {
+        // IPv6 literal, whose colons are not a port separator
+        return host.find(']').map_or(host, |end| &host[..=end]);
+    }
+    host.rsplit_once(':').map_or(host, |(host, _)| host)
+}
+
+/// Lowercases `host` and encodes its non-ASCII labels as punycode.
+///
+/// The ideographic and fullwidth full stops separate labels like `.` does in
+/// browsers. This is a subset of the UTS #46 mapping: labels are lowercased
+/// but not otherwise normalized.
+fn ascii_host(host: &str) -> Result<String, UrlError> {
+    let mut ascii = String::with_capacity(host.len());
+    for (i, label) in host
+        .split(['.', '\u{3002}', '\u{ff0e}', '\u{ff61}'])
+        .enumerate()
+    {
+        if i > 0 {
+            ascii.push('.');
+        }
+        let label = label.to_lowercase();
+        if label.is_ascii() {
+            ascii.push_str(&label);
+        } else {
+            let encoded = punycode(&label).ok_or_else(|| UrlError::InvalidHost(host.to_owned()))?;
+            ascii.push_str("xn--");
+            ascii.push_str(&encoded);
+        }
+    }
+    Ok(ascii)
+}
+
+/// Encodes `input` with the punycode algorithm of RFC 3492, returning `None`
+/// on overflow.
+fn punycode(input: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+    const INITIAL_BIAS: u32 = 72;
+    const INITIAL_N: u32 = 0x80;
+
+    const fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+        let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+        delta += delta / num_points;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+    }
+
+    fn digit(value: u32) -> char {
+        // `value` is below BASE, so both branches are in range
+        let byte = if value < 26 { b'a' } else { b'0' - 26 };
+        char::from(byte.wrapping_add(u8::try_from(value).unwrap_or_default()))
+    }
+
+    let code_points: Vec<u32> = input.chars().map(u32::from).collect();
+    let mut output: String = input.chars().filter(char::is_ascii).collect();
+    let basic = u32::try_from(output.len()).ok()?;
+    let total = u32::try_from(code_points.len()).ok()?;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let mut code_point = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic;
+    while handled < total {
+        let next = code_points
+            .iter()
+            .copied()
+            .filter(|&point| point >= code_point)
+            .min()?;
+        delta = delta.checked_add((next - code_point).checked_mul(handled + 1)?)?;
+        code_point = next;
+        for &point in &code_points {
+            if point < code_point {
+                delta = delta.checked_add(1)?;
+            }
+            if point == code_point {
+                let mut rest = delta;
+                let mut level = BASE;
+                loop {
+                    let threshold = if level <= bias {
+                        T_MIN
+                    } else if level >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        level - bias
+                    };
+                    if rest < threshold {
+                        break;
+                    }
+                    output.push(digit(threshold + (rest - threshold) % (BASE - threshold)));
+                    rest = (rest - threshold) / (BASE - threshold);
+                    level += BASE;
+                }
+                output.push(digit(rest));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta = delta.checked_add(1)?;
+        code_point += 1;
+    }
+    Some(output)
+}
+
+/// Extracts the protocol from a URL string.
+///
+/// This function parses the URL to determine its protocol (scheme),
+/// handling various edge cases like HTML entities for colons.
+/// Useful for validating URLs in mathematical content that may include links.
+///
+/// # Parameters
+/// - `url`: The URL string to parse.
+///
+/// # Returns
+/// - `Some(protocol)`: The lowercase protocol string if valid.
+/// - `Some("_relative")`: If the URL is relative (no protocol).
+/// - `None`: If the protocol is invalid.
+///
+/// # Examples
+/// ```
+/// use katex::security::protocol_from_url;
+///
+/// assert_eq!(
+///     protocol_from_url("https://example.com"),
+///     Some("https".to_string())
+/// );
+/// assert_eq!(
+///     protocol_from_url("/path/to/file"),
+///     Some("_relative".to_string())
+/// );
+/// assert_eq!(protocol_from_url("1invalid://example.com"), None);
+/// ```
+///
+/// # Error Handling
+/// - Invalid schemes (not starting with letter, containing invalid chars)
+///   return `None`.
+/// - HTML entities for colon are handled (e.g., `&#58;`, `&colon;`).
+///
+/// # Cross-references
+/// See validate_and_return for scheme validation logic.
+/// See match_html_colon_entity for HTML entity parsing.
+#[must_use]
+pub fn protocol_from_url(url: &str) -> Option<String> {
+    let mut s = url;
+
+    while let Some(first) = s.chars().next() {
+        if first <= '\u{20}' {
+            s = &s[first.len_utf8()..];
+        } else {
+            break;
+        }
+    }
+
+    for (i, ch) in s.char_indices() {
+        if ch == ':' {
+            return validate_and_return(&s[..i], ":", &s[i + 1..]);
+        } else if ch == '&' {
+            let rest = &s[i..];
+            if let Some((entity, skip)) = match_html_colon_entity(rest) {
+                return validate_and_return(&s[..i], entity, &s[i + skip..]);
+            }
+        } else if ch == '\\' || ch == '/' || ch == '#' || ch == '?' {
+            return Some("_relative".into());
+        }
+    }
+
+    Some("_relative".into())
+}
+
+fn match_html_colon_entity(s: &str) -> Option<(&'static str, usize)> {
+    // &#0*58
+    if let Some(decimal) = s.strip_prefix("&#") {
+        let mut idx = 0;
+        while decimal[idx..].starts_with('0') {
+            idx += 1;
+        }
+        if decimal[idx..].starts_with("58") {
+            let after = idx + 2;
+            return Some(("&#0*58", 2 + after));
+        }
+        if decimal[idx..].starts_with('x') || decimal[idx..].starts_with('X') {
+            let hexpart = &decimal[idx + 1..];
+            let mut idx2 = 0;
+            while hexpart[idx2..].starts_with('0') {
+                idx2 += 1;
+            }
+            if hexpart[idx2..].to_ascii_lowercase().starts_with("3a") {
+                let after = idx + 1 + idx2 + 2;
+                return Some(("&#x0*3a", 2 + after));
+            }
+        }
+    }
+    // &colon
+    if s.to_ascii_lowercase().starts_with("&colon") {
+        return Some(("&colon", 6));
+    }
+    None
+}
+
+fn validate_and_return(scheme: &str, colon_match: &str, _rest: &str) -> Option<String> {
+    if colon_match != ":" {
+        return None;
+    }
+
+    let mut chars = scheme.chars();
+    if !matches!(chars.next(), Some(c) if c.is_ascii_alphabetic()) {
+        return None;
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') {
+        return None;
+    }
+    Some(scheme.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_from_url() {
+        assert_eq!(
+            protocol_from_url("https://example.com"),
+            Some("https".to_owned())
+        );
+        assert_eq!(
+            protocol_from_url("http://example.com"),
+            Some("http".to_owned())
+        );
+        assert_eq!(
+            protocol_from_url("ftp://example.com"),
+            Some("ftp".to_owned())
+        );
+        assert_eq!(
+            protocol_from_url("/path/to/file"),
+            Some("_relative".to_owned())
+        );
+        assert_eq!(protocol_from_url("1invalid://example.com"), None);
+        assert_eq!(protocol_from_url("weird:colon"), Some("weird".to_owned()));
+    }
+
+    #[test]
+    fn test_protocol_from_url_misc() {
+        assert_eq!(protocol_from_url("/path/to/file"), Some("_relative".into()));
+        assert_eq!(
+            protocol_from_url("  \u{0007}../rel"),
+            Some("_relative".into())
+        );
+
+        assert_eq!(
+            protocol_from_url("https://example.com"),
+            Some("https".into())
+        );
+        assert_eq!(protocol_from_url("FTP://example.com"), Some("ftp".into()));
+        assert_eq!(
+            protocol_from_url("mailto:user@example.com"),
+            Some("mailto".into())
+        );
+
+        assert_eq!(protocol_from_url("http&#058//foo"), None);
+        assert_eq!(protocol_from_url("http&#x03a//foo"), None);
+        assert_eq!(protocol_from_url("http&colon//foo"), None);
+
+        assert_eq!(protocol_from_url("1abc://foo"), None);
+        assert_eq!(protocol_from_url("abc^://foo"), None);
+        assert_eq!(protocol_from_url("ht tp://foo"), None);
+    }
+
+    #[test]
+    fn test_check_url_rejects_disallowed_characters() {
+        assert_eq!(
+            check_url("java\tscript:alert(1)"),
+            Err(UrlError::DisallowedCharacter { index: 4, code: 9 })
+        );
+        assert!(check_url("https://a.example/\u{202e}fdp.exe").is_err());
+        assert!(check_url("https://a.example/\u{85}").is_err());
+        assert!(check_url("https://a.example/caf\u{e9}").is_ok());
+    }
+
+    #[test]
+    fn test_check_url_rejects_script_protocols() {
+        for url in [
+            "javascript:alert(1)",
+            " JavaScript:alert(1)",
+            "vbscript:msgbox",
+        ] {
+            assert!(
+                matches!(check_url(url), Err(UrlError::ScriptProtocol(_))),
+                "{url}"
+            );
+        }
+        assert_eq!(
+            check_url("javascript&colon;alert(1)"),
+            Err(UrlError::MalformedProtocol)
+        );
+    }
+
+    #[test]
+    fn test_check_url_extracts_hosts() {
+        let host = |url| check_url(url).unwrap().host;
+        assert_eq!(host("https://Example.COM/path"), Some("example.com".into()));
+        assert_eq!(
+            host("http://user:pw@example.com:8080/"),
+            Some("example.com".into())
+        );
+        assert_eq!(host("http://[::1]:8080/"), Some("[::1]".into()));
+        assert_eq!(host("//cdn.example/x.png"), Some("cdn.example".into()));
+        assert_eq!(host("mailto:someone@example.com"), None);
+        assert_eq!(host("images/x.png"), None);
+    }
+
+    #[test]
+    fn test_check_url_encodes_internationalized_hosts() {
+        let host = |url| check_url(url).unwrap().host;
+        assert_eq!(
+            host("https://M\u{fc}nchen.example"),
+            Some("xn--mnchen-3ya.example".into())
+        );
+        // Cyrillic "пример" in the IDNA test vectors
+        assert_eq!(
+            host("https://\u{43f}\u{440}\u{438}\u{43c}\u{435}\u{440}\u{3002}example"),
+            Some("xn--e1afmkfd.example".into())
+        );
+        // A Cyrillic "а" does not pass for the Latin one
+        assert_ne!(host("https://p\u{430}ypal.com"), Some("paypal.com".into()));
+    }
+
+    #[test]
+    fn test_punycode_matches_rfc_3492() {
+        assert_eq!(punycode("b\u{fc}cher").as_deref(), Some("bcher-kva"));
+        assert_eq!(
+            punycode("\u{4ed6}\u{4eec}\u{4e3a}\u{4ec0}\u{4e48}\u{4e0d}\u{8bf4}\u{4e2d}\u{6587}")
+                .as_deref(),
+            Some("ihqwcrb4cv8a8dqg056pqjye")
+        );
+    }
+
+    #[test]
+    fn test_allow_protocols() {
+        let TrustSetting::Function(trust) = allow_protocols(["HTTPS", "_relative"]) else {
+            panic!("expected a trust function");
+        };
+        let context = |url: &str| TrustContext {
+            protocol: protocol_from_url(url),
+            ..Default::default()
+        };
+        assert_eq!(trust(&mut context("https://a.example")), Some(true));
+        assert_eq!(trust(&mut context("x.png")), Some(true));
+        assert_eq!(trust(&mut context("http://a.example")), Some(false));
+        assert_eq!(trust(&mut TrustContext::default()), Some(false));
+    }
+}
@@ -0,0 +1,763 @@
+//! MathSpeak-style spoken descriptions of parse trees
+//!
+//! This module turns a parse tree into the words a screen reader would speak,
+//! following the conventions of MathSpeak as implemented by the Speech Rule
+//! Engine: structures are bracketed by spoken markers (`StartFraction ... Over
+//! ... EndFraction`), scripts announce their level (`Superscript`,
+//! `Baseline`), and accents are read as `ModifyingAbove x With caret`.
+//!
+//! The output is a list of [`SpeechSpan`]s rather than a single string. Each
+//! span records the parse node it was spoken for and that node's range in
+//! the source, so read-aloud features can highlight the part of the formula
+//! being spoken. [`speech_text`] joins the spans into plain text.
+//!
+//! The amount of markup is selected by [`Verbosity`], and all words come from
+//! a [`SpeechLocale`]. [`English`] is the built-in locale; other languages
+//! implement the trait, usually delegating to [`English`] for anything they do
+//! not translate.
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Range;
+use core::slice;
+
+use crate::parser::parse_node::{AnyParseNode, NodeType, ParseNodeOp, TensorIndex};
+use crate::types::ErrorLocationProvider as _;
+
+/// How much structural markup is spoken, after MathSpeak's three levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Verbosity {
+    /// Full words: `StartFraction x Over y EndFraction`
+    #[default]
+    Verbose,
+    /// Abbreviated markers: `StartFrac x Over y EndFrac`
+    Brief,
+    /// Shortest markers: `Frac x Over y EndFrac`
+    SuperBrief,
+}
+
+/// Structural markers spoken around sub-expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpeechTerm {
+    /// Opens a fraction
+    StartFraction,
+    /// Separates numerator and denominator
+    Over,
+    /// Closes a fraction
+    EndFraction,
+    /// Opens a fraction without a bar, such as `\binom`
+    StartBinomial,
+    /// Separates the parts of a binomial
+    Choose,
+    /// Closes a binomial
+    EndBinomial,
+    /// Enters a superscript
+    Superscript,
+    /// Enters a subscript
+    Subscript,
+    /// Returns from scripts to the main line
+    Baseline,
+    /// Enters a limit placed below an operator
+    Underscript,
+    /// Enters a limit placed above an operator
+    Overscript,
+    /// Closes the limits of an operator
+    Endscripts,
+    /// Opens a radical
+    StartRoot,
+    /// Closes a radical
+    EndRoot,
+    /// Introduces the index of an nth root
+    RootIndex,
+    /// Introduces an expression with an accent above it
+    ModifyingAbove,
+    /// Introduces an expression with an accent below it
+    ModifyingBelow,
+    /// Introduces the accent itself
+    With,
+    /// Opens an array
+    StartLayout,
+    /// Closes an array
+    EndLayout,
+    /// Announces a row of an array, after its ordinal
+    Row,
+    /// Announces a column of an array, after its ordinal
+    Column,
+}
+
+/// Source of every word the speech builder produces.
+pub trait SpeechLocale: Send + Sync {
+    /// Word for a structural marker at the given verbosity.
+    fn term(&self, term: SpeechTerm, verbosity: Verbosity) -> Cow<'static, str>;
+
+    /// Word for a symbol as it appears in the parse tree, such as `+`,
+    /// `\alpha` or `\hat`. `None` falls back to the symbol itself, without a
+    /// leading backslash.
+    fn symbol(&self, text: &str) -> Option<Cow<'static, str>>;
+
+    /// Ordinal number used to announce rows and columns, starting at 1.
+    fn ordinal(&self, n: usize) -> String;
+}
+
+/// The built-in English locale, following MathSpeak's wording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct English;
+
+impl SpeechLocale for English {
+    fn term(&self, term: SpeechTerm, verbosity: Verbosity) -> Cow<'static, str> {
+        use SpeechTerm as T;
+        use Verbosity::{Brief, SuperBrief, Verbose};
+
+        let word = match (term, verbosity) {
+            (T::StartFraction, Verbose) => "StartFraction",
+            (T::StartFraction, Brief) => "StartFrac",
+            (T::StartFraction, SuperBrief) => "Frac",
+            (T::Over, _) | (T::Overscript, Brief | SuperBrief) => "Over",
+            (T::EndFraction, Verbose) => "EndFraction",
+            (T::EndFraction, _) => "EndFrac",
+            (T::StartBinomial, Verbose) => "StartBinomial",
+            (T::StartBinomial, Brief) => "StartBinom",
+            (T::StartBinomial, SuperBrief) => "Binom",
+            (T::Choose, _) => "Choose",
+            (T::EndBinomial, Verbose) => "EndBinomial",
+            (T::EndBinomial, _) => "EndBinom",
+            (T::Superscript, Verbose) => "Superscript",
+            (T::Superscript, _) => "Sup",
+            (T::Subscript, Verbose) => "Subscript",
+            (T::Subscript, _) => "Sub",
+            (T::Baseline, Verbose) => "Baseline",
+            (T::Baseline, _) => "Base",
+            (T::Underscript, Verbose) => "Underscript",
+            (T::Underscript, _) => "Under",
+            (T::Overscript, Verbose) => "Overscript",
+            (T::Endscripts, _) => "Endscripts",
+            (T::StartRoot, SuperBrief) => "Root",
+            (T::StartRoot, _) => "StartRoot",
+            (T::EndRoot, _) => "EndRoot",
+            (T::RootIndex, SuperBrief) => "Index",
+            (T::RootIndex, _) => "RootIndex",
+            (T::ModifyingAbove, Verbose) => "ModifyingAbove",
+            (T::ModifyingAbove, _) => "ModAbove",
+            (T::ModifyingBelow, Verbose) => "ModifyingBelow",
+            (T::ModifyingBelow, _) => "ModBelow",
+            (T::With, _) => "With",
+            (T::StartLayout, SuperBrief) => "Layout",
+            (T::StartLayout, _) => "StartLayout",
+            (T::EndLayout, _) => "EndLayout",
+            (T::Row, _) => "Row",
+            (T::Column, _) => "Column",
+        };
+        Cow::Borrowed(word)
+    }
+
+    fn symbol(&self, text: &str) -> Option<Cow<'static, str>> {
+        let word = match text {
+            "+" => "plus",
+            "-" | "\u{2212}" => "minus",
+            "=" => "equals",
+            "<" => "less-than",
+            ">" => "greater-than",
+            "\\leq" | "\\le" | "\u{2264}" => "less-than-or-equal-to",
+            "\\geq" | "\\ge" | "\u{2265}" => "greater-than-or-equal-to",
+            "\\neq" | "\\ne" | "\u{2260}" => "not-equals",
+            "\\approx" => "almost-equals",
+            "\\equiv" => "identical-to",
+            "\\times" => "times",
+            "\\cdot" | "\\dot" => "dot",
+            "\\div" => "divided-by",
+            "\\pm" => "plus-or-minus",
+            "\\mp" => "minus-or-plus",
+            "(" => "left-parenthesis",
+            ")" => "right-parenthesis",
+            "[" | "\\lbrack" => "left-bracket",
+            "]" | "\\rbrack" => "right-bracket",
+            "\\{" | "\\lbrace" => "left-brace",
+            "\\}" | "\\rbrace" => "right-brace",
+            "|" | "\\vert" | "\\lvert" | "\\rvert" => "vertical-bar",
+            "\\|" | "\\Vert" | "\\lVert" | "\\rVert" => "double-vertical-bar",
+            "," => "comma",
+            "!" => "exclamation-mark",
+            "'" | "\\prime" | "\u{2032}" => "prime",
+            "\\infty" => "infinity",
+            "\\partial" => "partial-differential",
+            "\\nabla" => "nabla",
+            "\\in" => "element-of",
+            "\\notin" => "not-an-element-of",
+            "\\subset" => "subset-of",
+            "\\subseteq" => "subset-of-or-equal-to",
+            "\\cap" => "intersection",
+            "\\cup" => "union",
+            "\\forall" => "for-all",
+            "\\exists" => "there-exists",
+            "\\neg" | "\\lnot" => "not",
+            "\\ldots" | "\\cdots" | "\\dots" | "\\dotsc" | "\\dotsb" => "ellipsis",
+            "\\sum" => "sigma-summation",
+            "\\prod" => "product",
+            "\\int" => "integral",
+            "\\oint" => "contour-integral",
+            "\\to" | "\\rightarrow" | "\\xrightarrow" | "\\vec" | "\\overrightarrow" => {
+                "right-arrow"
+            }
+            "\\leftarrow" | "\\gets" | "\\xleftarrow" | "\\overleftarrow" => "left-arrow",
+            "\\Rightarrow" | "\\implies" => "right-double-arrow",
+            "\\Leftrightarrow" | "\\iff" => "left-right-double-arrow",
+            "\\hat" | "\\widehat" => "caret",
+            "\\bar" | "\\overline" | "\\underline" => "bar",
+            "\\tilde" | "\\widetilde" => "tilde",
+            "\\ddot" => "double-dot",
+            "\\acute" => "acute",
+            "\\grave" => "grave",
+            "\\breve" => "breve",
+            "\\check" => "caron",
+            "\\overbrace" => "top-brace",
+            "\\underbrace" => "bottom-brace",
+            _ => {
+                let mut chars = text.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(letter), None) if letter.is_ascii_uppercase() => {
+                        Some(Cow::Owned(format!("upper {letter}")))
+                    }
+                    _ => None,
+                };
+            }
+        };
+        Some(Cow::Borrowed(word))
+    }
+
+    fn ordinal(&self, n: usize) -> String {
+        let suffix = match (n % 10, n % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        format!("{n}{suffix}")
+    }
+}
+
+/// A chunk of speech and the parse node it describes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeechSpan {
+    /// The words to speak
+    pub text: String,
+    /// Type of the node the words were produced for
+    pub node: NodeType,
+    /// Byte range of that node in the source expression, if known
+    pub source: Option<Range<usize>>,
+}
+
+/// Options for [`crate::render_to_speech`].
+#[derive(Clone)]
+pub struct SpeechOptions {
+    /// How much structural markup is spoken
+    pub verbosity: Verbosity,
+    /// Source of the spoken words
+    pub locale: Arc<dyn SpeechLocale>,
+}
+
+impl Default for SpeechOptions {
+    fn default() -> Self {
+        Self {
+            verbosity: Verbosity::default(),
+            locale: Arc::new(English),
+        }
+    }
+}
+
+impl fmt::Debug for SpeechOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpeechOptions")
+            .field("verbosity", &self.verbosity)
+            .field("locale", &"<locale>")
+            .finish()
+    }
+}
+
+/// Joins speech spans into a single string separated by spaces.
+#[must_use]
+pub fn speech_text(spans: &[SpeechSpan]) -> String {
+    spans
+        .iter()
+        .map(|span| span.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds the speech for a parse tree.
+///
+/// Returning from scripts is only announced when more of the formula follows,
+/// so `x^2` reads `x Superscript 2` while `x^2+1` reads `x Superscript 2
+/// Baseline plus 1`. `display` selects whether operators with limits read them as
+/// `Underscript`/`Overscript`, as they are drawn in display style, or as
+/// ordinary scripts.
+#[must_use]
+pub fn build_speech(
+    tree: &[AnyParseNode],
+    options: &SpeechOptions,
+    display: bool,
+) -> Vec<SpeechSpan> {
+    let mut speaker = Speaker {
+        verbosity: options.verbosity,
+        locale: options.locale.as_ref(),
+        display,
+        spans: Vec::new(),
+        scripts: Vec::new(),
+        number_run: false,
+        pending_level: None,
+    };
+    speaker.expression(tree);
+    speaker.spans
+}
+
+/// Walks the tree, keeping track of the script level being spoken
+struct Speaker<'a> {
+    verbosity: Verbosity,
+    locale: &'a dyn SpeechLocale,
+    display: bool,
+    spans: Vec<SpeechSpan>,
+    /// Superscript/subscript path from the baseline to the current position
+    scripts: Vec<SpeechTerm>,
+    /// Whether the last span is a number that following digits extend
+    number_run: bool,
+    /// Node whose scripts just ended; the level reached is spoken before
+    /// anything else
+    pending_level: Option<(NodeType, Option<Range<usize>>)>,
+}
+
+impl Speaker<'_> {
+    fn push(&mut self, text: String, node: &AnyParseNode) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some((node, source)) = self.pending_level.take() {
+            let text = self.level_words();
+            self.spans.push(SpeechSpan { text, node, source });
+        }
+        self.spans.push(SpeechSpan {
+            text,
+            node: NodeType::from(node),
+            source: node.loc().map(|loc| loc.start..loc.end),
+        });
+        self.number_run = false;
+    }
+
+    fn term(&mut self, term: SpeechTerm, node: &AnyParseNode) {
+        let word = self.locale.term(term, self.verbosity).into_owned();
+        self.push(word, node);
+    }
+
+    fn symbol(&mut self, text: &str, node: &AnyParseNode) {
+        let is_number = !text.is_empty() && text.chars().all(|c| c.is_ascii_digit() || c == '.');
+        if is_number && self.number_run {
+            // Read consecutive digits as one number
+            if let Some(last) = self.spans.last_mut() {
+                last.text.push_str(text);
+                if let (Some(range), Some(loc)) = (last.source.as_mut(), node.loc()) {
+                    range.end = range.end.max(loc.end);
+                }
+                return;
+            }
+        }
+
+        let word = self.locale.symbol(text).map_or_else(
+            || text.strip_prefix('\\').unwrap_or(text).to_owned(),
+            Cow::into_owned,
+        );
+        self.push(word, node);
+        self.number_run = is_number;
+    }
+
+    /// Marker for the current script level, such as `SuperscriptSubscript`
+    fn level_words(&self) -> String {
+        if self.scripts.is_empty() {
+            self.locale
+                .term(SpeechTerm::Baseline, self.verbosity)
+                .into_owned()
+        } else {
+            self.scripts
+                .iter()
+                .map(|term| self.locale.term(*term, self.verbosity))
+                .collect()
+        }
+    }
+
+    /// Speaks `body` one script level deeper
+    fn script(&mut self, term: SpeechTerm, body: &AnyParseNode, node: &AnyParseNode) {
+        self.scripts.push(term);
+        let words = self.level_words();
+        self.push(words, node);
+        self.node(body);
+        // The next script or the return announces the level itself
+        self.pending_level = None;
+        self.scripts.pop();
+    }
+
+    /// Announces the return from scripts once more speech follows
+    fn end_scripts(&mut self, node: &AnyParseNode) {
+        self.pending_level = Some((
+            NodeType::from(node),
+            node.loc().map(|loc| loc.start..loc.end),
+        ));
+        self.number_run = false;
+    }
+
+    fn expression(&mut self, nodes: &[AnyParseNode]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    /// Collects the characters of a text run into one word
+    fn collect_text(nodes: &[AnyParseNode], out: &mut String) {
+        for node in nodes {
+            match node {
+                AnyParseNode::Spacing(_) => out.push(' '),
+                AnyParseNode::OrdGroup(group) => Self::collect_text(&group.body, out),
+                AnyParseNode::Styling(styling) => Self::collect_text(&styling.body, out),
+                AnyParseNode::Sizing(sizing) => Self::collect_text(&sizing.body, out),
+                AnyParseNode::Color(color) => Self::collect_text(&color.body, out),
+                AnyParseNode::Font(font) => {
+                    Self::collect_text(slice::from_ref(&*font.body), out);
+                }
+                AnyParseNode::Text(text) => Self::collect_text(&text.body, out),
+                _ => {
+                    if let Some(text) = node.text() {
+                        out.push_str(text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_prime(node: &AnyParseNode) -> bool {
+        match node {
+            AnyParseNode::OrdGroup(group) => {
+                !group.body.is_empty() && group.body.iter().all(Self::is_prime)
+            }
+            _ => matches!(node.text(), Some("\\prime" | "'" | "\u{2032}")),
+        }
+    }
+
+    const fn has_limits(&self, base: Option<&AnyParseNode>) -> bool {
+        self.display
+            && match base {
+                Some(AnyParseNode::Op(op)) => op.limits(),
+                Some(AnyParseNode::OperatorName(name)) => name.limits,
+                _ => false,
+            }
+    }
+
+    #[expect(clippy::too_many_lines)]
+    fn node(&mut self, node: &AnyParseNode) {
+        match node {
+            AnyParseNode::MathOrd(ord) => self.symbol(&ord.text, node),
+            AnyParseNode::TextOrd(ord) => self.symbol(&ord.text, node),
+            AnyParseNode::Atom(atom) => self.symbol(&atom.text, node),
+            AnyParseNode::OpToken(token) => self.symbol(&token.text, node),
+            AnyParseNode::AccentToken(token) => self.symbol(&token.text, node),
+            AnyParseNode::Op(ParseNodeOp::Symbol { name, .. }) => self.symbol(name, node),
+            AnyParseNode::Op(ParseNodeOp::Body { body, .. }) => self.expression(body),
+            AnyParseNode::Delimsizing(delim) => self.symbol(&delim.delim, node),
+            AnyParseNode::Middle(middle) => self.symbol(&middle.delim, node),
+
+            AnyParseNode::Text(text) => {
+                let mut words = String::new();
+                Self::collect_text(&text.body, &mut words);
+                self.push(words.trim().to_owned(), node);
+            }
+            AnyParseNode::OperatorName(name) => {
+                let mut words = String::new();
+                Self::collect_text(&name.body, &mut words);
+                self.push(words, node);
+            }
+            AnyParseNode::Verb(verb) => self.push(verb.body.to_string(), node),
+            AnyParseNode::Includegraphics(graphics) => self.push(graphics.alt.clone(), node),
+            AnyParseNode::Url(url) => self.push(url.url.clone(), node),
+
+            AnyParseNode::OrdGroup(group) => self.expression(&group.body),
+            AnyParseNode::Styling(styling) => self.expression(&styling.body),
+            AnyParseNode::Sizing(sizing) => self.expression(&sizing.body),
+            AnyParseNode::Color(color) => self.expression(&color.body),
+            AnyParseNode::Mclass(mclass) => self.expression(&mclass.body),
+            AnyParseNode::Pmb(pmb) => self.expression(&pmb.body),
+            AnyParseNode::Hbox(hbox) => self.expression(&hbox.body),
+            AnyParseNode::Html(html) => self.expression(&html.body),
+            AnyParseNode::Href(href) => self.expression(&href.body),
+            AnyParseNode::HtmlMathMl(both) => self.expression(&both.mathml),
+            AnyParseNode::MathChoice(choice) => self.expression(&choice.display),
+            AnyParseNode::Tag(tag) => self.expression(&tag.body),
+            AnyParseNode::Font(font) => self.node(&font.body),
+            AnyParseNode::Enclose(enclose) => self.node(&enclose.body),
+            AnyParseNode::Lap(lap) => self.node(&lap.body),
+            AnyParseNode::Raisebox(raisebox) => self.node(&raisebox.body),
+            AnyParseNode::Smash(smash) => self.node(&smash.body),
+            AnyParseNode::Vcenter(vcenter) => self.node(&vcenter.body),
+            AnyParseNode::CdLabel(label) => self.node(&label.label),
+            AnyParseNode::CdLabelParent(parent) => self.node(&parent.fragment),
+
+            AnyParseNode::SupSub(supsub) => {
+                let base = supsub.base.as_deref();
+                if let Some(base) = base {
+                    self.node(base);
+                }
+                if supsub.sub.is_none()
+                    && let Some(sup) = supsub.sup.as_deref()
+                    && Self::is_prime(sup)
+                {
+                    // Primes are read as symbols rather than as a superscript
+                    self.node(sup);
+                } else if self.has_limits(base) {
+                    if let Some(sub) = supsub.sub.as_deref() {
+                        self.term(SpeechTerm::Underscript, node);
+                        self.node(sub);
+                    }
+                    if let Some(sup) = supsub.sup.as_deref() {
+                        self.term(SpeechTerm::Overscript, node);
+                        self.node(sup);
+                    }
+                    self.term(SpeechTerm::Endscripts, node);
+                } else {
+                    if let Some(sub) = supsub.sub.as_deref() {
+                        self.script(SpeechTerm::Subscript, sub, node);
+                    }
+                    if let Some(sup) = supsub.sup.as_deref() {
+                        self.script(SpeechTerm::Superscript, sup, node);
+                    }
+                    self.end_scripts(node);
+                }
+                self.number_run = false;
+            }
+            AnyParseNode::Nuclide(nuclide) => {
+                self.node(&nuclide.base);
+                let scripts = [
+                    (SpeechTerm::Superscript, &nuclide.mass),
+                    (SpeechTerm::Subscript, &nuclide.atomic),
+                ];
+                for (term, script) in scripts {
+                    if let Some(script) = script.as_deref() {
+                        self.script(term, script, node);
+                    }
+                }
+                if scripts.iter().any(|(_, script)| script.is_some()) {
+                    self.end_scripts(node);
+                }
+            }
+            AnyParseNode::Diagbox(diagbox) => {
+                self.node(&diagbox.lower);
+                self.node(&diagbox.upper);
+            }
+            AnyParseNode::Sideset(sideset) => {
+                let post = [
+                    (SpeechTerm::Subscript, &sideset.post_sub),
+                    (SpeechTerm::Superscript, &sideset.post_sup),
+                ];
+                self.node(&sideset.base);
+                for (term, script) in post {
+                    if let Some(script) = script.as_deref() {
+                        self.script(term, script, node);
+                    }
+                }
+                if post.iter().any(|(_, script)| script.is_some()) {
+                    self.end_scripts(node);
+                }
+            }
+            AnyParseNode::Tensor(tensor) => {
+                self.node(&tensor.base);
+                for index in &tensor.post {
+                    match index {
+                        TensorIndex::Upper(index) => {
+                            self.script(SpeechTerm::Superscript, index, node);
+                        }
+                        TensorIndex::Lower(index) => {
+                            self.script(SpeechTerm::Subscript, index, node);
+                        }
+                    }
+                }
+                if !tensor.post.is_empty() {
+                    self.end_scripts(node);
+                }
+            }
+
+            AnyParseNode::Genfrac(frac) => {
+                let (start, middle, end) = if frac.has_bar_line {
+                    (
+                        SpeechTerm::StartFraction,
+                        SpeechTerm::Over,
+                        SpeechTerm::EndFraction,
+                    )
+                } else {
+                    (
+                        SpeechTerm::StartBinomial,
+                        SpeechTerm::Choose,
+                        SpeechTerm::EndBinomial,
+                    )
+                };
+                self.term(start, node);
+                self.node(&frac.numer);
+                self.term(middle, node);
+                self.node(&frac.denom);
+                self.term(end, node);
+            }
+            AnyParseNode::Sqrt(sqrt) => {
+                if let Some(index) = &sqrt.index {
+                    self.term(SpeechTerm::RootIndex, node);
+                    self.node(index);
+                }
+                self.term(SpeechTerm::StartRoot, node);
+                self.node(&sqrt.body);
+                self.term(SpeechTerm::EndRoot, node);
+            }
+            AnyParseNode::LeftRight(leftright) => {
+                if leftright.left != "." {
+                    self.symbol(&leftright.left, node);
+                }
+                self.expression(&leftright.body);
+                if leftright.right != "." {
+                    self.symbol(&leftright.right, node);
+                }
+            }
+
+            AnyParseNode::Accent(accent) => {
+                self.modified(
+                    SpeechTerm::ModifyingAbove,
+                    &accent.base,
+                    &accent.label,
+                    node,
+                );
+            }
+            AnyParseNode::AccentUnder(accent) => {
+                self.modified(
+                    SpeechTerm::ModifyingBelow,
+                    &accent.base,
+                    &accent.label,
+                    node,
+                );
+            }
+            AnyParseNode::Overline(overline) => {
+                self.modified(
+                    SpeechTerm::ModifyingAbove,
+                    &overline.body,
+                    "\\overline",
+                    node,
+                );
+            }
+            AnyParseNode::Underline(underline) => {
+                self.modified(
+                    SpeechTerm::ModifyingBelow,
+                    &underline.body,
+                    "\\underline",
+                    node,
+                );
+            }
+            AnyParseNode::HorizBrace(brace) => {
+                let term = if brace.is_over {
+                    SpeechTerm::ModifyingAbove
+                } else {
+                    SpeechTerm::ModifyingBelow
+                };
+                self.modified(term, &brace.base, &brace.label, node);
+            }
+            AnyParseNode::XArrow(arrow) => {
+                self.symbol(&arrow.label, node);
+                if let Some(body) = arrow.body.as_deref() {
+                    self.term(SpeechTerm::Overscript, node);
+                    self.node(body);
+                }
+                if let Some(below) = arrow.below.as_deref() {
+                    self.term(SpeechTerm::Underscript, node);
+                    self.node(below);
+                }
+                if arrow.body.is_some() || arrow.below.is_some() {
+                    self.term(SpeechTerm::Endscripts, node);
+                }
+            }
+
+            AnyParseNode::Array(array) => {
+                let columns = array.body.iter().map(Vec::len).max().unwrap_or(0);
+                self.term(SpeechTerm::StartLayout, node);
+                for (i, row) in array.body.iter().enumerate() {
+                    let row_word = format!(
+                        "{} {}",
+                        self.locale.ordinal(i + 1),
+                        self.locale.term(SpeechTerm::Row, self.verbosity)
+                    );
+                    self.push(row_word, node);
+                    for (j, cell) in row.iter().enumerate() {
+                        if columns > 1 {
+                            let column_word = format!(
+                                "{} {}",
+                                self.locale.ordinal(j + 1),
+                                self.locale.term(SpeechTerm::Column, self.verbosity)
+                            );
+                            self.push(column_word, node);
+                        }
+                        self.node(cell);
+                    }
+                }
+                self.term(SpeechTerm::EndLayout, node);
+            }
+
+            // Invisible or purely presentational nodes have nothing to say
+            AnyParseNode::Spacing(_)
+            | AnyParseNode::Phantom(_)
+            | AnyParseNode::Hphantom(_)
+            | AnyParseNode::Vphantom(_)
+            | AnyParseNode::Kern(_)
+            | AnyParseNode::Rule(_)
+            | AnyParseNode::Cr(_)
+            | AnyParseNode::ColorToken(_)
+            | AnyParseNode::Raw(_)
+            | AnyParseNode::Size(_)
+            | AnyParseNode::Internal(_)
+            | AnyParseNode::Infix(_)
+            | AnyParseNode::Environment(_)
+            | AnyParseNode::LeftRightRight(_) => {}
+        }
+    }
+
+    /// Speaks an accented expression, `ModifyingAbove x With caret`
+    fn modified(
+        &mut self,
+        term: SpeechTerm,
+        base: &AnyParseNode,
+        label: &str,
+        node: &AnyParseNode,
+    ) {
+        self.term(term, node);
+        self.node(base);
+        self.term(SpeechTerm::With, node);
+        self.symbol(label, node);
+        self.number_run = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_ordinals() {
+        let words: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 101]
+            .into_iter()
+            .map(|n| English.ordinal(n))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "101st"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_english_terms_by_verbosity() {
+        let frac = |verbosity| English.term(SpeechTerm::StartFraction, verbosity);
+        assert_eq!(frac(Verbosity::Verbose), "StartFraction");
+        assert_eq!(frac(Verbosity::Brief), "StartFrac");
+        assert_eq!(frac(Verbosity::SuperBrief), "Frac");
+        assert_eq!(English.symbol("A").as_deref(), Some("upper A"));
+        assert_eq!(English.symbol("x"), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Step-by-step macro expansion of a formula
+//!
+//! [`expansion_steps`] lists a formula after each single macro expansion,
+//! written back as LaTeX. Teaching tools can animate the list to show how
+//! macros unfold into the commands that are finally typeset:
+//!
+//! ```rust
+//! use katex::Settings;
+//! use katex::steps::expansion_steps;
+//!
+//! let steps = expansion_steps(r"\newcommand{\half}{\frac12}\half+\half", &Settings::default())
+//!     .unwrap();
+//! assert_eq!(
+//!     steps,
+//!     [
+//!         r"\newcommand{\half}{\frac12}\half+\half",
+//!         r"\half+\half",
+//!         r"\frac12+\half",
+//!         r"\frac12+\frac12",
+//!     ]
+//! );
+//! ```
+//!
+//! The leftmost macro is expanded first, the way the parser meets them.
+//! Only the macro expander runs, so the formula is read as math throughout,
+//! and macros defined by `\def` and its variants, which take effect while
+//! parsing, are left unexpanded. `\newcommand` and the macros of the settings
+//! are expanded.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::context::KatexContext;
+use crate::macro_expander::MacroExpander;
+use crate::macros::MacroContextInterface as _;
+use crate::types::trace::detokenize;
+use crate::types::{Mode, ParseError, Settings};
+
+/// Lists the formula after each macro expansion step with the default
+/// context, starting with the formula itself. See
+/// [`KatexContext::expansion_steps`].
+pub fn expansion_steps(input: &str, settings: &Settings) -> Result<Vec<String>, ParseError> {
+    KatexContext::default().expansion_steps(input, settings)
+}
+
+impl KatexContext {
+    /// Lists the formula after each macro expansion step, starting with the
+    /// formula itself.
+    ///
+    /// Fails like parsing would if a macro misses an argument, or after
+    /// [`Settings::max_expand`] expansions.
+    pub fn expansion_steps(
+        &self,
+        input: &str,
+        settings: &Settings,
+    ) -> Result<Vec<String>, ParseError> {
+        let mut expander = MacroExpander::new(input, settings, Mode::Math, self);
+        // Like the parser, keep definitions local to this formula
+        expander.begin_group();
+        let steps = expand_stepwise(&mut expander);
+        expander.end_groups();
+        steps
+    }
+}
+
+/// Expands the input of `expander` one macro at a time, recording the
+/// formula after each step.
+fn expand_stepwise(expander: &mut MacroExpander<'_>) -> Result<Vec<String>, ParseError> {
+    // Lex the whole input up front, so that the rest of the formula can be
+    // written out after each step
+    let mut tokens = Vec::new();
+    loop {
+        let token = expander.pop_token()?;
+        if token.text == "EOF" {
+            break;
+        }
+        tokens.push(token);
+    }
+    tokens.reverse();
+    expander.push_tokens(tokens);
+
+    let mut done = Vec::new();
+    let mut steps = vec![detokenize(expander.pushed_tokens())];
+    while expander.pushed_tokens().next().is_some() {
+        if expander.expand_once(Some(false))?.is_some() {
+            steps.push(detokenize(done.iter().chain(expander.pushed_tokens())));
+        } else {
+            done.push(expander.pop_token()?);
+        }
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::MacroDefinition;
+    use crate::types::ParseErrorKind;
+
+    #[test]
+    fn test_unfolds_builtin_macros() {
+        let steps = expansion_steps(r"a\iff b", &Settings::default()).unwrap();
+        assert_eq!(steps[0], r"a\iff b");
+        assert_eq!(steps[1], r"a\DOTSB\;\Longleftrightarrow\;b");
+        assert_eq!(steps[2], r"a\relax\;\Longleftrightarrow\;b");
+    }
+
+    #[test]
+    fn test_expands_settings_macros_with_arguments() {
+        let settings = Settings::default();
+        settings
+            .macros
+            .borrow_mut()
+            .insert(r"\sq".to_owned(), MacroDefinition::StaticStr("#1^2"));
+        assert_eq!(
+            expansion_steps(r"\sq{x+1}", &settings).unwrap(),
+            [r"\sq{x+1}", "x+1^2"]
+        );
+    }
+
+    #[test]
+    fn test_keeps_definitions_local() {
+        let settings = Settings::default();
+        expansion_steps(r"\newcommand\x{y}\x", &settings).unwrap();
+        assert!(!settings.macros.borrow().contains_key(r"\x"));
+    }
+
+    #[test]
+    fn test_reports_runaway_expansion() {
+        let error = expansion_steps(r"\newcommand\x{\x}\x", &Settings::default()).unwrap_err();
+        assert!(matches!(
+            *error.kind,
+            ParseErrorKind::MacroTooManyExpansions
+        ));
+    }
+}
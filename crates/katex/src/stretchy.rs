@@ -328,6 +328,7 @@ pub fn svg_span(group: &AnyParseNode, options: &Options) -> Result<HtmlDomNode,
 }
 
 /// Create an enclosing span for elements like cancel, fbox, etc.
+#[must_use]
 pub fn enclose_span(
     inner: &HtmlDomNode,
     label: &str,
@@ -354,57 +355,67 @@ pub fn enclose_span(
         span
     } else {
         // Handle cancel, bcancel, xcancel
-        let mut lines = Vec::new();
+        let diagonals: &[Diagonal] = match label {
+            "bcancel" => &[Diagonal::Falling],
+            "xcancel" => &[Diagonal::Falling, Diagonal::Rising],
+            _ => &[Diagonal::Rising],
+        };
+        diagonal_rule_span(diagonals, total_height, options)
+    }
+}
 
-        if label == "bcancel" || label == "xcancel" {
-            lines.push(LineNode {
-                attributes: [
-                    ("x1".to_owned(), "0".to_owned()),
-                    ("y1".to_owned(), "0".to_owned()),
-                    ("x2".to_owned(), "100%".to_owned()),
-                    ("y2".to_owned(), "100%".to_owned()),
-                    ("stroke-width".to_owned(), "0.046em".to_owned()),
-                ]
-                .iter()
-                .cloned()
-                .collect(),
-            });
-        }
+/// Direction of a rule drawn corner to corner across a box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagonal {
+    /// From the bottom left to the top right corner, like `\cancel`
+    Rising,
+    /// From the top left to the bottom right corner, like `\bcancel`
+    Falling,
+}
 
-        if label == "cancel" || label == "xcancel" {
-            lines.push(LineNode {
+/// Create a span of the given height with rules drawn corner to corner.
+///
+/// The rules span the full width of the box they are placed in, so they
+/// stretch with it, e.g. across a cell of an array.
+#[must_use]
+pub fn diagonal_rule_span(diagonals: &[Diagonal], height: f64, options: &Options) -> DomSpan {
+    let lines = diagonals
+        .iter()
+        .map(|diagonal| {
+            let (y1, y2) = match diagonal {
+                Diagonal::Rising => ("100%", "0"),
+                Diagonal::Falling => ("0", "100%"),
+            };
+            SvgChildNode::Line(LineNode {
                 attributes: [
                     ("x1".to_owned(), "0".to_owned()),
-                    ("y1".to_owned(), "100%".to_owned()),
+                    ("y1".to_owned(), y1.to_owned()),
                     ("x2".to_owned(), "100%".to_owned()),
-                    ("y2".to_owned(), "0".to_owned()),
+                    ("y2".to_owned(), y2.to_owned()),
                     ("stroke-width".to_owned(), "0.046em".to_owned()),
                 ]
-                .iter()
-                .cloned()
+                .into_iter()
                 .collect(),
-            });
-        }
-
-        let svg_attributes = [
-            ("width".to_owned(), "100%".to_owned()),
-            ("height".to_owned(), make_em(total_height)),
-        ]
-        .iter()
-        .cloned()
+            })
+        })
         .collect();
 
-        let svg_node = SvgNode::builder()
-            .children(lines.into_iter().map(SvgChildNode::Line).collect())
-            .attributes(svg_attributes)
-            .build();
-
-        let mut span = make_svg_span(vec![], vec![svg_node], options);
-        span.style
-            .insert(CssProperty::Height, make_em(total_height));
-        span.height = total_height;
-        span
-    }
+    let svg_attributes = [
+        ("width".to_owned(), "100%".to_owned()),
+        ("height".to_owned(), make_em(height)),
+    ]
+    .into_iter()
+    .collect();
+
+    let svg_node = SvgNode::builder()
+        .children(lines)
+        .attributes(svg_attributes)
+        .build();
+
+    let mut span = make_svg_span(vec![], vec![svg_node], options);
+    span.style.insert(CssProperty::Height, make_em(height));
+    span.height = height;
+    span
 }
 
 /// Create a MathML node for stretchy elements
@@ -0,0 +1,228 @@
+//! Commands whose argument is written in a language of its own
+//!
+//! Some commands take an argument that is not TeX, like the chemical
+//! equations of mhchem's `\ce{2H2 + O2 -> 2H2O}`. Lexing such an argument as
+//! TeX would drop its spaces and trip over characters that mean something
+//! else there. A sub-language instead claims the raw text between the braces
+//! and reads it with a parser of its own, which returns parse nodes. Parts
+//! of the argument that are TeX after all can be handed back to the main
+//! parser with [`SublanguageContext::parse_tex`].
+//!
+//! The nodes keep [`SourceLocation`]s into the whole input rather than into
+//! the argument, so errors and source maps point at the right place:
+//!
+//! ```rust
+//! use katex::parser::parse_node::{ParseNode, ParseNodeVerb};
+//! use katex::sublanguage::{SublanguageContext, SublanguageSpec};
+//! use katex::types::{ErrorLocationProvider as _, ParseError};
+//! use katex::{KatexContext, Settings, parse_tree::parse_tree};
+//!
+//! /// Shows code verbatim, except for formulas between `$` signs
+//! fn parse_code(context: &mut SublanguageContext<'_, '_>) -> Result<Vec<ParseNode>, ParseError> {
+//!     let text = context.text().to_owned();
+//!     let mut nodes = Vec::new();
+//!     let mut start = 0;
+//!     for (i, part) in text.split('$').enumerate() {
+//!         let range = start..start + part.len();
+//!         if i % 2 == 1 {
+//!             nodes.extend(context.parse_tex(range)?);
+//!         } else if !part.is_empty() {
+//!             nodes.push(ParseNode::Verb(ParseNodeVerb {
+//!                 mode: context.parser.mode,
+//!                 loc: context.loc(range),
+//!                 body: part.to_owned().into(),
+//!                 star: false,
+//!             }));
+//!         }
+//!         start += part.len() + 1;
+//!     }
+//!     Ok(nodes)
+//! }
+//!
+//! let mut ctx = KatexContext::default();
+//! ctx.define_sublanguage(SublanguageSpec {
+//!     names: &["\\code"],
+//!     parser: parse_code,
+//! });
+//! let tree = parse_tree(&ctx, r"\code{pow(x, 2) % $\alpha$}", &Settings::default()).unwrap();
+//! let ParseNode::OrdGroup(group) = &tree[0] else {
+//!     panic!("expected a group");
+//! };
+//! assert!(matches!(&group.body[0], ParseNode::Verb(verb) if verb.body == "pow(x, 2) % "));
+//! // The formula is located in the input, not in the argument
+//! assert_eq!(group.body[1].loc().map(|loc| loc.start), Some(19));
+//! ```
+
+use alloc::borrow::ToOwned as _;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::context::KatexContext;
+use crate::define_function::{FunctionContext, FunctionDefSpec, FunctionPropSpec};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::parser::parse_node::{ParseNode, ParseNodeOrdGroup, ParseNodeRaw};
+use crate::types::{ParseError, ParseErrorKind, SourceLocation};
+
+/// Parser of the argument of a sub-language command
+pub type SublanguageParser =
+    for<'c, 'p, 'a> fn(&'c mut SublanguageContext<'p, 'a>) -> Result<Vec<ParseNode>, ParseError>;
+
+/// Definition of a sub-language, see [`KatexContext::define_sublanguage`]
+#[derive(Clone, Copy)]
+pub struct SublanguageSpec {
+    /// Names of the commands taking an argument in the language, e.g. `\ce`
+    pub names: &'static [&'static str],
+    /// Reads the argument
+    pub parser: SublanguageParser,
+}
+
+/// What a [`SublanguageParser`] reads the argument from
+pub struct SublanguageContext<'p, 'a> {
+    /// Name of the command, e.g. `\ce`
+    pub func_name: &'p str,
+    /// The main parser, positioned after the argument
+    pub parser: &'p mut Parser<'a>,
+    /// Raw text between the braces of the argument, located in the input
+    pub argument: ParseNodeRaw,
+}
+
+impl SublanguageContext<'_, '_> {
+    /// The text of the argument, without the braces.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        self.argument.string.as_str()
+    }
+
+    /// Where the bytes `range` of [`Self::text`] are in the input.
+    #[must_use]
+    pub fn loc(&self, range: Range<usize>) -> Option<SourceLocation> {
+        let argument = self.argument.loc.as_ref()?;
+        Some(SourceLocation {
+            input: Arc::clone(&argument.input),
+            start: argument.start + range.start,
+            end: argument.start + range.end,
+        })
+    }
+
+    /// Parses the bytes `range` of [`Self::text`] as TeX, in the mode of the
+    /// command.
+    ///
+    /// The tokens are lexed from the input itself, so the nodes and any
+    /// error are located there. If the argument has no location, the range
+    /// is lexed as an input of its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseErrorKind::InvalidRawTextRange`] if `range` does not lie
+    /// within [`Self::text`] or splits a character, and any error of parsing
+    /// the TeX.
+    pub fn parse_tex(&mut self, range: Range<usize>) -> Result<Vec<ParseNode>, ParseError> {
+        let Some(text) = self.text().get(range.clone()) else {
+            return Err(ParseError::new(ParseErrorKind::InvalidRawTextRange {
+                start: range.start,
+                end: range.end,
+            }));
+        };
+        // An argument without a location is lexed on its own
+        let loc = self.loc(range).unwrap_or_else(|| SourceLocation {
+            input: Arc::from(text),
+            start: 0,
+            end: text.len(),
+        });
+        let mut lexer = Lexer::new(loc.input, self.parser.settings);
+        lexer.set_position(loc.start);
+        let mut tokens = Vec::new();
+        loop {
+            let mut token = lexer.lex()?;
+            if token.text == "EOF" {
+                break;
+            }
+            let Some(token_loc) = token
+                .loc
+                .as_mut()
+                .filter(|token_loc| token_loc.start < loc.end)
+            else {
+                break;
+            };
+            // A control word also takes the spaces after it, which may lie
+            // outside the range
+            token_loc.end = token_loc.end.min(loc.end);
+            tokens.push(token);
+        }
+        tokens.reverse();
+        self.parser.subparse(tokens)
+    }
+}
+
+/// Handler of every sub-language command, which looks up the parser of the
+/// command in [`KatexContext::sublanguages`].
+fn handler(
+    context: FunctionContext<'_, '_, '_>,
+    _args: Vec<ParseNode>,
+    _opt_args: Vec<Option<ParseNode>>,
+) -> Result<ParseNode, ParseError> {
+    let FunctionContext {
+        func_name,
+        parser,
+        token,
+        ..
+    } = context;
+    let Some(&sublanguage) = parser.ctx.sublanguages.get(func_name) else {
+        return Err(ParseError::new(ParseErrorKind::NoFunctionHandler {
+            name: func_name.to_owned(),
+        }));
+    };
+    let argument = parser.parse_raw_group(&format!("argument to '{func_name}'"))?;
+    // From the command to the closing brace
+    let loc = SourceLocation::range(
+        token.and_then(|token| token.loc.clone()),
+        argument.loc.clone().map(|loc| SourceLocation {
+            start: loc.end,
+            end: loc.end + 1,
+            ..loc
+        }),
+    );
+
+    let mode = parser.mode;
+    let mut context = SublanguageContext {
+        func_name,
+        parser,
+        argument,
+    };
+    Ok(ParseNode::OrdGroup(ParseNodeOrdGroup {
+        mode,
+        loc,
+        body: sublanguage(&mut context)?,
+        semisimple: None,
+    }))
+}
+
+impl KatexContext {
+    /// Registers commands whose argument is parsed by `spec.parser` instead
+    /// of as TeX. Their parse nodes are grouped in an ordgroup.
+    ///
+    /// The commands work in math and text mode, and take their argument in
+    /// braces, which must be balanced apart from those escaped with a
+    /// backslash.
+    pub fn define_sublanguage(&mut self, spec: SublanguageSpec) {
+        for name in spec.names {
+            self.sublanguages.insert((*name).to_owned(), spec.parser);
+        }
+        self.define_function(FunctionDefSpec {
+            node_type: None,
+            names: spec.names,
+            props: FunctionPropSpec {
+                num_args: 0,
+                allowed_in_text: true,
+                allowed_in_argument: true,
+                ..Default::default()
+            },
+            handler: Some(handler),
+            html_builder: None,
+            mathml_builder: None,
+        });
+    }
+}
@@ -0,0 +1,225 @@
+//! Table of the supported LaTeX commands
+//!
+//! [`support_table`] lists every command and environment a context knows,
+//! generated from its function, environment and symbol tables and the
+//! built-in macros. Documentation sites can render it as a "Supported
+//! Functions" page that stays in sync with the renderer:
+//!
+//! ```rust
+//! use katex::support::{SupportStatus, support_table};
+//!
+//! let table = support_table();
+//! let frac = table.iter().find(|entry| entry.name == r"\frac").unwrap();
+//! assert_eq!(frac.status, SupportStatus::Full);
+//! let href = table.iter().find(|entry| entry.name == r"\href").unwrap();
+//! assert_eq!(href.status, SupportStatus::Partial);
+//! ```
+//!
+//! Commands are fully supported unless [`ANNOTATIONS`] says otherwise.
+//! Internal commands, whose names contain `@`, are left out.
+
+use alloc::borrow::ToOwned as _;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use strum::AsRefStr;
+
+use crate::context::KatexContext;
+use crate::macros::builtins::BUILTIN_MACROS;
+use crate::types::Mode;
+
+/// How a command is implemented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum CommandKind {
+    /// A macro, expanded before parsing
+    Macro,
+    /// A function with a parse handler
+    Function,
+    /// A symbol from the symbol table
+    Symbol,
+    /// An environment, used with `\begin{name}`
+    Environment,
+}
+
+/// How completely a command is supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum SupportStatus {
+    /// Works like in LaTeX
+    Full,
+    /// Works with restrictions, explained in the entry's note
+    Partial,
+    /// Recognized, but only to report an error
+    Error,
+}
+
+/// One row of the [`support_table`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportEntry {
+    /// Command name with its backslash, or the environment name
+    pub name: String,
+    /// How the command is implemented
+    pub kind: CommandKind,
+    /// Whether the command can be used in math mode
+    pub math: bool,
+    /// Whether the command can be used in text mode
+    pub text: bool,
+    /// How completely the command is supported
+    pub status: SupportStatus,
+    /// Restrictions of a partially supported command, or why a command
+    /// reports an error
+    pub note: Option<&'static str>,
+}
+
+const TRUST_NOTE: &str = "Requires the trust setting to allow it";
+const HTML_NOTE: &str = "Requires the trust setting to allow it; has no effect on MathML output";
+const DISPLAY_NOTE: &str = "Only in display mode";
+
+/// Commands and environments that are not fully supported, with their
+/// status and a note explaining the restriction
+pub const ANNOTATIONS: &[(&str, SupportStatus, &str)] = &[
+    (r"\href", SupportStatus::Partial, TRUST_NOTE),
+    (r"\url", SupportStatus::Partial, TRUST_NOTE),
+    (r"\includegraphics", SupportStatus::Partial, TRUST_NOTE),
+    (r"\htmlClass", SupportStatus::Partial, HTML_NOTE),
+    (r"\htmlId", SupportStatus::Partial, HTML_NOTE),
+    (r"\htmlStyle", SupportStatus::Partial, HTML_NOTE),
+    (r"\htmlData", SupportStatus::Partial, HTML_NOTE),
+    (r"\tag", SupportStatus::Partial, DISPLAY_NOTE),
+    ("align", SupportStatus::Partial, DISPLAY_NOTE),
+    ("align*", SupportStatus::Partial, DISPLAY_NOTE),
+    ("alignat", SupportStatus::Partial, DISPLAY_NOTE),
+    ("alignat*", SupportStatus::Partial, DISPLAY_NOTE),
+    ("CD", SupportStatus::Partial, DISPLAY_NOTE),
+    ("equation", SupportStatus::Partial, DISPLAY_NOTE),
+    ("equation*", SupportStatus::Partial, DISPLAY_NOTE),
+    ("gather", SupportStatus::Partial, DISPLAY_NOTE),
+    ("gather*", SupportStatus::Partial, DISPLAY_NOTE),
+    ("split", SupportStatus::Partial, DISPLAY_NOTE),
+    (
+        r"\]",
+        SupportStatus::Error,
+        "Reports a mismatched delimiter; use the display mode setting for display math",
+    ),
+];
+
+/// Lists the commands and environments of the default context, sorted by
+/// name. See [`KatexContext::support_table`].
+#[must_use]
+pub fn support_table() -> Vec<SupportEntry> {
+    KatexContext::default().support_table()
+}
+
+impl KatexContext {
+    /// Lists the commands and environments this context supports, including
+    /// the built-in macros, sorted by name.
+    ///
+    /// A name defined several ways is listed once, as the kind the parser
+    /// uses: a macro before a function before a symbol. It is usable in a
+    /// mode if any of its definitions is.
+    #[must_use]
+    pub fn support_table(&self) -> Vec<SupportEntry> {
+        let mut commands = BTreeMap::new();
+        let mut add = |name: &str, kind, math, text| {
+            // `@` names and `\\`-prefixed ones such as `\\atopfrac` are
+            // internal and can't be entered directly
+            if name.contains('@') || (name.starts_with(r"\\") && name != r"\\") {
+                return;
+            }
+            commands
+                .entry((kind == CommandKind::Environment, name.to_owned()))
+                .and_modify(|entry: &mut SupportEntry| {
+                    entry.kind = entry.kind.min(kind);
+                    entry.math |= math;
+                    entry.text |= text;
+                })
+                .or_insert_with(|| {
+                    let annotation = ANNOTATIONS
+                        .iter()
+                        .find(|&&(annotated, _, _)| annotated == name);
+                    SupportEntry {
+                        name: name.to_owned(),
+                        kind,
+                        math,
+                        text,
+                        status: annotation.map_or(SupportStatus::Full, |&(_, status, _)| status),
+                        note: annotation.map(|&(_, _, note)| note),
+                    }
+                });
+        };
+
+        for name in BUILTIN_MACROS.keys() {
+            add(name, CommandKind::Macro, true, true);
+        }
+        for (name, spec) in &self.functions {
+            add(
+                name,
+                CommandKind::Function,
+                spec.allowed_in_math,
+                spec.allowed_in_text,
+            );
+        }
+        for name in self.symbols.names(Mode::Math) {
+            add(name, CommandKind::Symbol, true, false);
+        }
+        for name in self.symbols.names(Mode::Text) {
+            add(name, CommandKind::Symbol, false, true);
+        }
+        for name in self.environments.keys() {
+            add(name, CommandKind::Environment, true, false);
+        }
+
+        let mut table: Vec<SupportEntry> = commands.into_values().collect();
+        table.sort_by(|a, b| a.name.cmp(&b.name).then(a.kind.cmp(&b.kind)));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations_name_registered_commands() {
+        let table = support_table();
+        for &(name, status, _) in ANNOTATIONS {
+            let entry = table.iter().find(|entry| entry.name == name);
+            assert_eq!(entry.map(|entry| entry.status), Some(status), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_lists_each_kind_once() {
+        let table = support_table();
+        let find = |name: &str| table.iter().filter(|entry| entry.name == name).count();
+        assert_eq!(find(r"\frac"), 1);
+        assert_eq!(find("matrix"), 1);
+        assert!(!table.iter().any(|entry| entry.name.contains('@')));
+        assert_eq!(find(r"\\"), 1);
+        for internal in [
+            r"\\cdleft",
+            r"\\cdright",
+            r"\\cdleftarrow",
+            r"\\globalfuture",
+        ] {
+            assert_eq!(find(internal), 0, "{internal}");
+        }
+
+        let kind = |name: &str| table.iter().find(|entry| entry.name == name).unwrap().kind;
+        assert_eq!(kind(r"\alpha"), CommandKind::Symbol);
+        assert_eq!(kind(r"\frac"), CommandKind::Function);
+        assert_eq!(kind(r"\iff"), CommandKind::Macro);
+        assert_eq!(kind("pmatrix"), CommandKind::Environment);
+    }
+
+    #[test]
+    fn test_merges_modes() {
+        let table = support_table();
+        let dollar = table.iter().find(|entry| entry.name == r"\$").unwrap();
+        assert!(dollar.math && dollar.text);
+        let alpha = table.iter().find(|entry| entry.name == r"\alpha").unwrap();
+        assert!(alpha.math && !alpha.text);
+    }
+}
@@ -307,6 +307,30 @@ pub fn phase_path(y: f64) -> String {
     )
 }
 
+/// Generate the bracket of a long division
+///
+/// The bracket is a `)`-shaped arc down the left edge, `bulge` wide at its
+/// middle, joined at the top to a bar running to the right edge. Both
+/// strokes are `line` thick.
+///
+/// # Arguments
+/// * `height` - Height of the bracket
+/// * `bulge` - How far the middle of the arc reaches to the right
+/// * `line` - Thickness of the arc and the bar
+///
+/// # Returns
+/// SVG path string for the long division bracket
+#[must_use]
+pub fn longdiv_path(height: f64, bulge: f64, line: f64) -> String {
+    // A quadratic curve reaches half way to its control point
+    let control = 2.0 * bulge;
+    let middle = height / 2.0;
+    format!(
+        "M0 0H400000v{line}H{line}Q{} {middle} {line} {height}H0Q{control} {middle} 0 0z",
+        control + line
+    )
+}
+
 /// Generate SVG path for tall square root symbol
 ///
 /// Produces an SVG path for a dynamically sized tall square root symbol in
@@ -845,6 +869,14 @@ mod tests {
         assert!(path.contains("H400000z"));
     }
 
+    #[test]
+    fn test_longdiv_path() {
+        let path = longdiv_path(1000.0, 200.0, 40.0);
+        assert!(path.starts_with("M0 0H400000v40H40"));
+        assert!(path.contains("Q440 500 40 1000"));
+        assert!(path.ends_with("Q400 500 0 0z"));
+    }
+
     #[test]
     fn test_inner_path_vertical_bar() {
         let path = inner_path("\u{2223}", 100.0);
@@ -39,7 +39,7 @@ use crate::ParseError;
 use crate::namespace::KeyMap;
 use crate::types::ParseErrorKind;
 use phf::phf_map;
-pub use types::{Atom, CharInfo, Font, Group, Mode, NonAtom};
+pub use types::{Atom, CharInfo, Font, Group, Mode, NonAtom, SizedGlyph};
 
 /// Core symbol table for mathematical typesetting
 ///
@@ -67,6 +67,9 @@ pub struct Symbols {
     math: KeyMap<String, CharInfo>,
     /// Symbol mappings for text content
     text: KeyMap<String, CharInfo>,
+    /// Alternate glyphs for big operators and delimiters, keyed by symbol
+    /// name, then by `SizeN` font size
+    sized_glyphs: KeyMap<String, KeyMap<usize, SizedGlyph>>,
 }
 
 include!(concat!(env!("OUT_DIR"), "/generated_symbols_data.rs"));
@@ -115,6 +118,7 @@ impl Symbols {
         Self {
             math: KeyMap::default(),
             text: KeyMap::default(),
+            sized_glyphs: KeyMap::default(),
         }
     }
 
@@ -206,9 +210,7 @@ impl Symbols {
     /// ```
     #[must_use]
     pub fn get_math(&self, name: &str) -> Option<&CharInfo> {
-        self.math
-            .get(name)
-            .or_else(|| POPULATE_MATH_SYMBOLS_MAP.get(name))
+        self.math.get(name).or_else(|| populate_math_symbols(name))
     }
 
     /// Retrieves character information for a symbol in text mode
@@ -235,9 +237,7 @@ impl Symbols {
     /// ```
     #[must_use]
     pub fn get_text(&self, name: &str) -> Option<&CharInfo> {
-        self.text
-            .get(name)
-            .or_else(|| POPULATE_TEXT_SYMBOLS_MAP.get(name))
+        self.text.get(name).or_else(|| populate_text_symbols(name))
     }
 
     /// Retrieves character information for a symbol in the specified mode
@@ -273,16 +273,84 @@ impl Symbols {
         }
     }
 
+    /// Registers an alternate glyph for a symbol at a `SizeN` font size
+    ///
+    /// Big operators consult sizes 1 (text style) and 2 (display style); large
+    /// delimiters consult sizes 1 through 4. Symbols without a registered
+    /// glyph keep using the KaTeX `Size1`–`Size4` fonts.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - The LaTeX command name (e.g., `"\\sum"`)
+    /// * `size` - The font size the glyph replaces, from 1 to 4
+    /// * `glyph` - The substituted glyph
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use katex::symbols::{SizedGlyph, Symbols};
+    ///
+    /// let mut symbols = Symbols::new();
+    /// symbols.define_sized_glyph(
+    ///     "\\sum",
+    ///     2,
+    ///     SizedGlyph {
+    ///         text: "∑".to_owned(),
+    ///         font: "Brand-Regular".to_owned(),
+    ///         class: "brand-op".to_owned(),
+    ///     },
+    /// );
+    /// assert!(symbols.get_sized_glyph("\\sum", 2).is_some());
+    /// assert!(symbols.get_sized_glyph("\\sum", 1).is_none());
+    /// ```
+    pub fn define_sized_glyph(&mut self, name: &str, size: usize, glyph: SizedGlyph) {
+        self.sized_glyphs
+            .entry(name.to_owned())
+            .or_default()
+            .insert(size, glyph);
+    }
+
+    /// Retrieves the alternate glyph registered for a symbol at a `SizeN` font
+    /// size, if any
+    #[must_use]
+    pub fn get_sized_glyph(&self, name: &str, size: usize) -> Option<&SizedGlyph> {
+        self.sized_glyphs.get(name)?.get(&size)
+    }
+
+    /// Lists the names of every symbol defined in the specified mode
+    ///
+    /// Built-in symbols come first, in sorted order, followed by the symbols
+    /// added with [`Symbols::define_symbol`] that do not replace a built-in
+    /// one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use katex::symbols::{Mode, create_symbols};
+    ///
+    /// let symbols = create_symbols();
+    /// assert!(symbols.names(Mode::Math).any(|name| name == "\\alpha"));
+    /// assert!(!symbols.names(Mode::Text).any(|name| name == "\\alpha"));
+    /// ```
+    pub fn names(&self, mode: Mode) -> impl Iterator<Item = &str> {
+        let (builtin, defined) = match mode {
+            Mode::Math => (MATH_SYMBOL_NAMES, &self.math),
+            Mode::Text => (TEXT_SYMBOL_NAMES, &self.text),
+        };
+        builtin.iter().copied().chain(
+            defined
+                .keys()
+                .map(String::as_str)
+                .filter(|name| builtin.binary_search(name).is_err()),
+        )
+    }
+
     /// Checks if a symbol exists in the specified mode
     #[must_use]
     pub fn contains(&self, mode: Mode, name: &str) -> bool {
         match mode {
-            Mode::Math => {
-                self.math.contains_key(name) || POPULATE_MATH_SYMBOLS_MAP.contains_key(name)
-            }
-            Mode::Text => {
-                self.text.contains_key(name) || POPULATE_TEXT_SYMBOLS_MAP.contains_key(name)
-            }
+            Mode::Math => self.math.contains_key(name) || populate_math_symbols(name).is_some(),
+            Mode::Text => self.text.contains_key(name) || populate_text_symbols(name).is_some(),
         }
     }
 }
@@ -990,4 +1058,79 @@ mod tests {
         assert!(symbols.get_math("\\grave").is_some());
         assert!(symbols.get_math("\\tilde").is_some());
     }
+
+    /// Decodes the JavaScript escapes that extraction keeps in symbols.json
+    fn decode_js_escapes(value: &str) -> Option<String> {
+        let mut result = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next()? {
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    result.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                escaped => result.push(escaped),
+            }
+        }
+        Some(result)
+    }
+
+    /// Checks the tables generated by build.rs against data/symbols.json, so
+    /// that extraction or code generation regressions fail here rather than
+    /// as missing glyphs.
+    #[test]
+    fn test_generated_symbols_match_extracted_data() {
+        use crate::font_metrics_data::FONT_METRICS_INDEX;
+
+        let data: Vec<serde_json::Value> =
+            serde_json::from_str(include_str!("../../data/symbols.json")).unwrap();
+        let symbols = create_symbols();
+        let mut failures = Vec::new();
+
+        for symbol in &data {
+            let field = |key: &str| symbol[key].as_str().unwrap_or_default();
+            let mode = match field("mode") {
+                "math" => Mode::Math,
+                _ => Mode::Text,
+            };
+
+            let metrics_font = match field("font") {
+                "main" => "Main-Regular",
+                "ams" => "AMS-Regular",
+                other => other,
+            };
+            if !FONT_METRICS_INDEX.contains_key(metrics_font) {
+                failures.push(format!("{symbol}: no metrics for font {metrics_font}"));
+            }
+
+            let Some(name) = decode_js_escapes(field("name")) else {
+                failures.push(format!("{symbol}: invalid escape in name"));
+                continue;
+            };
+            if symbols.get(mode, &name).is_none() {
+                failures.push(format!("{symbol}: {name} does not resolve"));
+            }
+
+            if symbol["acceptUnicodeChar"].as_bool() == Some(true) {
+                let Some(unicode) = decode_js_escapes(field("replace")) else {
+                    failures.push(format!("{symbol}: invalid escape in replace"));
+                    continue;
+                };
+                match symbols.get(mode, &unicode) {
+                    Some(info) if info.replace.is_some_and(|ch| unicode.starts_with(ch)) => {}
+                    Some(info) => failures.push(format!(
+                        "{symbol}: {unicode} resolves with replace {:?}",
+                        info.replace
+                    )),
+                    None => failures.push(format!("{symbol}: {unicode} does not resolve")),
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
 }
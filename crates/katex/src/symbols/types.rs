@@ -296,3 +296,26 @@ pub struct CharInfo {
     /// original symbol should be used directly.
     pub replace: Option<char>,
 }
+
+/// An alternate glyph substituted for a symbol at one of the `Size1`–`Size4`
+/// font sizes.
+///
+/// Big operators use `Size1` in text style and `Size2` in display style, while
+/// large delimiters use all four sizes. Registering a [`SizedGlyph`] through
+/// [`Symbols::define_sized_glyph`](super::Symbols::define_sized_glyph) replaces
+/// the KaTeX font glyph at that size, e.g. with a branded summation sign.
+///
+/// The metrics for `text` are looked up in `font`, so custom fonts must be
+/// registered with
+/// [`FontMetricsData::add_custom_metrics`](crate::FontMetricsData::add_custom_metrics)
+/// first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizedGlyph {
+    /// The text rendered in place of the symbol.
+    pub text: String,
+    /// The font metrics name used for `text`, e.g. `"Brand-Regular"`.
+    pub font: String,
+    /// CSS class that selects the font for `text`. It replaces KaTeX's own
+    /// size classes on the glyph, so it should set `font-family`.
+    pub class: String,
+}
@@ -8,7 +8,8 @@ use core::fmt;
 
 use crate::ClassList;
 use crate::ParseError;
-use crate::types::{CssStyle, ParseErrorKind};
+use crate::escape::CharsetWriter;
+use crate::types::{Charset, CssStyle, ParseErrorKind};
 #[cfg(feature = "wasm")]
 use crate::web_context::WebContext;
 #[cfg(feature = "wasm")]
@@ -200,3 +201,50 @@ pub fn markup_to_string<T: VirtualNode + ?Sized>(node: &T) -> Result<String, Par
 
     Ok(buffer)
 }
+
+/// Writes the markup produced by `write` into `fmt`, encoded for `charset`.
+///
+/// A node that carries a [`Charset`] writes its contents through this, so
+/// that every way of getting its markup honors the charset.
+pub(crate) fn write_markup_encoded<F>(
+    fmt: &mut fmt::Formatter<'_>,
+    charset: Charset,
+    write: F,
+) -> Result<(), ParseError>
+where
+    F: Fn(&mut fmt::Formatter<'_>) -> Result<(), ParseError>,
+{
+    struct EncodedMarkup<'a, F> {
+        write: F,
+        error: &'a RefCell<Option<ParseError>>,
+    }
+
+    impl<F> fmt::Display for EncodedMarkup<'_, F>
+    where
+        F: Fn(&mut fmt::Formatter<'_>) -> Result<(), ParseError>,
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (self.write)(f).map_err(|err| {
+                self.error.replace(Some(err));
+                fmt::Error
+            })
+        }
+    }
+
+    let error = RefCell::new(None);
+    let markup = EncodedMarkup {
+        write,
+        error: &error,
+    };
+    if fmt::write(
+        &mut CharsetWriter::new(fmt, charset),
+        format_args!("{markup}"),
+    )
+    .is_err()
+    {
+        return Err(error
+            .into_inner()
+            .unwrap_or_else(|| ParseErrorKind::MarkupWriteFailure.into()));
+    }
+    Ok(())
+}
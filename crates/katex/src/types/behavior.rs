@@ -0,0 +1,115 @@
+//! Flags restoring behavior that a fix changed
+//!
+//! A correctness fix that changes the output for existing input ships
+//! together with a flag that restores the previous behavior. Deployments
+//! that depend on the old output set the flag in
+//! [`Settings::behavior`](crate::Settings::behavior) and migrate at their own
+//! pace instead of pinning an old release:
+//!
+//! ```rust
+//! use katex::types::BehaviorFlags;
+//! use katex::{KatexContext, Settings, render_to_string};
+//!
+//! let settings = Settings::builder()
+//!     .behavior(BehaviorFlags {
+//!         uncollapsed_primes: true,
+//!         ..BehaviorFlags::default()
+//!     })
+//!     .build();
+//! let html = render_to_string(&KatexContext::default(), "f''", &settings).unwrap();
+//! assert!(!html.contains('\u{2033}'));
+//! ```
+//!
+//! Every flag is deprecated from the start: the first render with a flag set
+//! logs a warning naming it, once per process, and flags are removed in the
+//! next breaking release.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use strum::{AsRefStr, EnumCount, EnumIter, IntoEnumIterator as _};
+
+/// Behavior flags of [`Settings`](crate::Settings), all off by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BehaviorFlags {
+    /// Emit every prime of a prime run as its own `<mo>` in MathML, instead
+    /// of one combined glyph: `f''` gets two `′` rather than one `″`.
+    pub uncollapsed_primes: bool,
+}
+
+/// A single flag of [`BehaviorFlags`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, AsRefStr, EnumCount, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum BehaviorFlag {
+    /// [`BehaviorFlags::uncollapsed_primes`]
+    UncollapsedPrimes,
+}
+
+impl BehaviorFlag {
+    /// The behavior the flag restores
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::UncollapsedPrimes => "MathML output with one <mo> per prime",
+        }
+    }
+}
+
+/// Whether the deprecation warning of each flag was logged
+static WARNED: [AtomicBool; BehaviorFlag::COUNT] =
+    [const { AtomicBool::new(false) }; BehaviorFlag::COUNT];
+
+impl BehaviorFlags {
+    /// Whether `flag` is set
+    #[must_use]
+    pub const fn is_set(&self, flag: BehaviorFlag) -> bool {
+        match flag {
+            BehaviorFlag::UncollapsedPrimes => self.uncollapsed_primes,
+        }
+    }
+
+    /// The flags that are set
+    pub fn set_flags(&self) -> impl Iterator<Item = BehaviorFlag> + '_ {
+        BehaviorFlag::iter().filter(|&flag| self.is_set(flag))
+    }
+
+    /// Logs a deprecation warning for every set flag that has not been
+    /// warned about in this process yet.
+    #[expect(clippy::print_stderr)]
+    pub fn warn_deprecated(&self) {
+        for flag in self.set_flags() {
+            if !WARNED[flag as usize].swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "KaTeX behavior flag '{}' is deprecated and will be removed in the next \
+                     breaking release; it restores {}",
+                    flag.as_ref(),
+                    flag.description()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_flags_are_set_by_default() {
+        assert_eq!(BehaviorFlags::default().set_flags().count(), 0);
+    }
+
+    #[test]
+    fn test_set_flags_lists_set_flags() {
+        let flags = BehaviorFlags {
+            uncollapsed_primes: true,
+        };
+        assert_eq!(
+            flags.set_flags().collect::<Vec<_>>(),
+            [BehaviorFlag::UncollapsedPrimes]
+        );
+        assert_eq!(
+            BehaviorFlag::UncollapsedPrimes.as_ref(),
+            "uncollapsed_primes"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Abstraction for managing CSS class lists across DOM nodes.
 
 use alloc::borrow::Cow;
+use core::mem;
 use core::ptr;
 use core::slice;
 
@@ -142,15 +143,11 @@ impl ClassList {
                 v.extend(slice.iter().map(|&s| Cow::Borrowed(s)));
                 v
             }
-            Self::Owned(_) => unreachable!(),
+            Self::Owned(v) => mem::take(v),
         };
 
         *self = Self::Owned(v);
-        if let Self::Owned(v) = self {
-            v
-        } else {
-            unreachable!()
-        }
+        self.ensure_owned()
     }
 
     /// Pushes a class onto the list, ignoring empty entries.
@@ -7,9 +7,9 @@ use core::fmt;
 
 use crate::define_environment::EnvSpec;
 use crate::define_function::FunctionSpec;
+use crate::escape::escape_into;
 use crate::namespace::KeyMap;
 use crate::parser::parse_node::NodeType;
-use crate::utils::escape_into;
 use rapidhash::HashMapExt as _;
 pub use source_location::{LexerInterface, SourceLocation};
 use strum::AsRefStr;
@@ -28,10 +28,17 @@ pub use crate::symbols::Mode;
 pub use class_list::ClassList;
 pub use tokens::{Token, TokenText};
 
+mod behavior;
+pub use behavior::{BehaviorFlag, BehaviorFlags};
+
+pub(crate) mod trace;
+pub use trace::{TraceEvent, TraceEventKind, TraceSink};
+
 mod settings;
 pub use settings::{
-    OutputFormat, Settings, StrictFunction, StrictMode, StrictReturn, StrictSetting, TrustContext,
-    TrustFunction, TrustSetting,
+    Charset, LegacyStrictFunction, NumberFormatFunction, NumberFormatter, OutputFormat,
+    OverflowMode, Settings, StrictContext, StrictFunction, StrictMode, StrictReturn, StrictSetting,
+    TrustContext, TrustFunction, TrustSetting,
 };
 
 pub use source_location::SourceRangeRef;
@@ -92,16 +99,32 @@ pub enum CssProperty {
     MarginRight,
     /// Sets the margin area on the top side of an element. See: <https://developer.mozilla.org/docs/Web/CSS/margin-top>
     MarginTop,
+    /// Sets the maximum width of an element. See: <https://developer.mozilla.org/docs/Web/CSS/max-width>
+    MaxWidth,
     /// Sets the minimum width of an element. See: <https://developer.mozilla.org/docs/Web/CSS/min-width>
     MinWidth,
+    /// Sets what shows when content overflows an element horizontally. See: <https://developer.mozilla.org/docs/Web/CSS/overflow-x>
+    OverflowX,
+    /// Sets what shows when content overflows an element vertically. See: <https://developer.mozilla.org/docs/Web/CSS/overflow-y>
+    OverflowY,
     /// Sets the padding on the left side of an element. See: <https://developer.mozilla.org/docs/Web/CSS/padding-left>
     PaddingLeft,
+    /// Sets the padding on the right side of an element. See: <https://developer.mozilla.org/docs/Web/CSS/padding-right>
+    PaddingRight,
     /// Specifies how an element is positioned in the document. See: <https://developer.mozilla.org/docs/Web/CSS/position>
     Position,
+    /// Sets the horizontal alignment of the inline content of a block. See: <https://developer.mozilla.org/docs/Web/CSS/text-align>
+    TextAlign,
     /// Applies one or more shadows to text. See: <https://developer.mozilla.org/docs/Web/CSS/text-shadow>
     TextShadow,
     /// Specifies how far the top edge of an element is from the top edge of its containing block. See: <https://developer.mozilla.org/docs/Web/CSS/top>
     Top,
+    /// Rotates, scales, skews, or translates an element. See: <https://developer.mozilla.org/docs/Web/CSS/transform>
+    Transform,
+    /// Sets the origin for an element's transformations. See: <https://developer.mozilla.org/docs/Web/CSS/transform-origin>
+    TransformOrigin,
+    /// Sets how white space inside an element is handled. See: <https://developer.mozilla.org/docs/Web/CSS/white-space>
+    WhiteSpace,
     /// Specifies the width of an element. See: <https://developer.mozilla.org/docs/Web/CSS/width>
     Width,
     /// Sets the vertical alignment of an inline or table-cell element. See: <https://developer.mozilla.org/docs/Web/CSS/vertical-align>
@@ -10,7 +10,7 @@ extern crate alloc;
 use crate::parser::ParseNodeError;
 use crate::parser::parse_node::{AnyParseNode, NodeType, ParseNodeOp};
 use crate::symbols::Mode;
-use crate::types::SourceLocation;
+use crate::types::{SourceLocation, Token};
 use alloc::boxed::Box;
 use alloc::string::String;
 use core::fmt;
@@ -138,6 +138,12 @@ pub enum ParseErrorKind {
     ExpectedEnvironmentAfterEnd { found: String },
     #[error(r"Mismatched: \begin{{{begin}}} matched by \end{{{end}}}")]
     MismatchedEnvironmentEnd { begin: String, end: String },
+    #[error("Raw text cannot come from a macro expansion")]
+    RawTextFromExpansion,
+    #[error("Expected '{delimiter}' to end the raw text")]
+    UnterminatedRawText { delimiter: String },
+    #[error("Range {start}..{end} does not lie within the raw text")]
+    InvalidRawTextRange { start: usize, end: usize },
     #[error(r"Invalid number: '{value}' in \includegraphics")]
     InvalidIncludeGraphicsNumber { value: String },
     #[error(r"Invalid unit: '{unit}' in \includegraphics")]
@@ -192,6 +198,8 @@ pub enum ParseErrorKind {
     MacroDelimiterLengthMismatch,
     #[error("Too many expansions: infinite loop or need to increase maxExpand setting")]
     MacroTooManyExpansions,
+    #[error("Too deeply nested: groups may nest at most {max_depth} levels (maxDepth setting)")]
+    MaxDepthExceeded { max_depth: usize },
     #[error("Incomplete placeholder at end of macro body")]
     MacroIncompletePlaceholder,
     #[error("Internal error: stack unexpectedly empty during token expansion")]
@@ -208,6 +216,8 @@ pub enum ParseErrorKind {
     FunctionDisallowedInMode { func: String, mode: Mode },
     #[error("Undefined control sequence: {name}")]
     UndefinedControlSequence { name: String },
+    #[error("Internal error: no handler for {name}, please report this as a bug")]
+    UnhandledCommand { name: String },
     #[error("Unexpected end of input in a macro argument, expected '{expected}'")]
     UnexpectedEndOfMacroArgument { expected: String },
     #[error("Invalid color: '{color}'")]
@@ -344,6 +354,10 @@ pub enum ParseErrorKind {
     TagNotAllowedInInlineMode,
     #[error("Empty string passed to lookup_symbol")]
     EmptyLookupSymbolInput,
+    #[error(r"\sideset expects only sub/superscripts in its first two arguments")]
+    SidesetExpectedScripts,
+    #[error(r"Expected ^ or _ in \tensor indices, got '{found}'")]
+    TensorExpectedIndex { found: String },
     #[error("Optional smash argument must be an ordgroup")]
     OptionalSmashArgumentMustBeOrdGroup,
     #[error("\\\\abovefrac second argument must be an Infix node")]
@@ -446,6 +460,11 @@ const fn adjust_char_boundary(input: &str, mut index: usize, forward: bool) -> u
 pub trait ErrorLocationProvider {
     /// Get the source location if available
     fn loc(&self) -> Option<&SourceLocation>;
+
+    /// Get the token this location belongs to, if the provider is one
+    fn token(&self) -> Option<&Token> {
+        None
+    }
 }
 
 /// Implementation of [`ErrorLocationProvider`] for [`AnyParseNode`].
@@ -499,6 +518,7 @@ impl ErrorLocationProvider for AnyParseNode {
             Self::AccentUnder(node) => node.loc.as_ref(),
             Self::Cr(node) => node.loc.as_ref(),
             Self::Delimsizing(node) => node.loc.as_ref(),
+            Self::Diagbox(node) => node.loc.as_ref(),
             Self::Enclose(node) => node.loc.as_ref(),
             Self::Environment(node) => node.loc.as_ref(),
             Self::Font(node) => node.loc.as_ref(),
@@ -525,6 +545,9 @@ impl ErrorLocationProvider for AnyParseNode {
             Self::Vphantom(node) => node.loc.as_ref(),
             Self::Pmb(node) => node.loc.as_ref(),
             Self::Raisebox(node) => node.loc.as_ref(),
+            Self::Sideset(node) => node.loc.as_ref(),
+            Self::Nuclide(node) => node.loc.as_ref(),
+            Self::Tensor(node) => node.loc.as_ref(),
             Self::Rule(node) => node.loc.as_ref(),
             Self::Sizing(node) => node.loc.as_ref(),
             Self::Smash(node) => node.loc.as_ref(),
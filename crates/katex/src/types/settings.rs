@@ -3,12 +3,18 @@ use core::fmt;
 
 use alloc::sync::Arc;
 use bon::bon;
+use rapidhash::v3::rapidhash_v3;
 
 use crate::macro_expander::MacroMap;
-use crate::namespace::KeyMap;
+use crate::macros::MacroDefinition;
+use crate::measure::Alignment;
+use crate::namespace::{KeyMap, Snapshot};
 
-use crate::types::{ErrorLocationProvider, ParseError, ParseErrorKind};
-use crate::utils::protocol_from_url;
+use crate::security::{check_url, protocol_from_url};
+use crate::types::{
+    BehaviorFlags, ErrorLocationProvider, ParseError, ParseErrorKind, SourceLocation, Token,
+    TraceSink,
+};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -52,6 +58,39 @@ pub enum OutputFormat {
     Mathml,
 }
 
+/// How a formula wider than [`Settings::max_width_em`] is made to fit.
+///
+/// The width of the HTML output is estimated from font metrics while it is
+/// built (see [`crate::measure::estimate_width`]); the chosen mode is applied
+/// to the `katex-html` element when the estimate exceeds the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Shrink the formula with a CSS `transform: scale(...)`.
+    ///
+    /// A negative right margin removes the space the unscaled formula would
+    /// still occupy, so the surrounding text flows around the scaled size.
+    #[default]
+    Scale,
+    /// Keep the natural size and let the formula scroll horizontally.
+    Scroll,
+}
+
+/// How characters outside ASCII are written in HTML and MathML output.
+///
+/// The choice only changes the encoding of the markup, not what a browser
+/// displays. The root span of a rendered tree carries it, so the markup is
+/// encoded as it is written, see [`crate::escape`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Write them as raw UTF-8, e.g. `α`.
+    #[default]
+    Utf8,
+    /// Write them as numeric character references, e.g. `&#x3b1;`, so that
+    /// the markup is plain ASCII and survives systems that mangle UTF-8,
+    /// such as some email gateways.
+    Ascii,
+}
+
 /// Levels of strictness for LaTeX compatibility checking in KaTeX.
 ///
 /// This enum defines how KaTeX responds to input that deviates from standard
@@ -168,6 +207,13 @@ pub struct Settings {
     ///
     /// Prevents infinite loops in macro expansion.
     pub max_expand: usize,
+    /// Maximum nesting depth of groups, arguments and environments.
+    ///
+    /// Parsing and building recurse once per level, so deeply nested input
+    /// such as `{{{{...}}}}` could otherwise exhaust the stack, which is small
+    /// in WebAssembly. Exceeding it is a [`ParseErrorKind::MaxDepthExceeded`]
+    /// error. The whole formula is the first level, so `{x}` is two deep.
+    pub max_depth: usize,
     /// Whether settings persist globally across render calls.
     ///
     /// When `true`, settings remain active for subsequent expressions.
@@ -181,6 +227,62 @@ pub struct Settings {
     /// CSS color value used for rendering mathematical expressions.
     #[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
     pub color: Option<String>,
+    /// Smallest scale, relative to the surrounding text size, that
+    /// superscripts and subscripts may shrink to.
+    ///
+    /// TeX shrinks first-level scripts to 70% and second-level scripts to 50%
+    /// of `\normalsize`. A value of `0.7` keeps third-level scripts at the
+    /// first-level size. Scripts snap up to the next available size, so the
+    /// clamp never makes them smaller than TeX would. `0.0` disables clamping.
+    pub min_script_scale: f64,
+    /// Whether a comma between two digits is a decimal separator.
+    ///
+    /// When `true`, `3,14` renders as tightly as `3{,}14`, the way the
+    /// `icomma` package treats it. A comma followed by a space or by
+    /// anything but a digit stays punctuation, so `(1, 2)` is unaffected.
+    pub decimal_comma: bool,
+    /// Hook applied to digit runs in MathML output.
+    ///
+    /// See [`NumberFormatter`]. `None` emits the digits unchanged.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub number_formatter: Option<NumberFormatter>,
+    /// Whether long `\text{...}` runs may wrap onto several lines.
+    ///
+    /// When `true`, breakable spaces inside text (` `, `\ `, `\space`) are
+    /// emitted as ordinary spaces instead of no-break spaces, and text spans
+    /// are styled with `white-space: normal` so that the browser can break
+    /// them. `~` and `\nobreakspace` still never break. MathML output is not
+    /// affected.
+    pub text_wrap: bool,
+    /// Widest a formula may be, in ems of its own font size.
+    ///
+    /// Wider formulas are fitted according to [`Settings::overflow`].
+    /// `None` never constrains the width.
+    pub max_width_em: Option<f64>,
+    /// How a formula wider than [`Settings::max_width_em`] is made to fit.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub overflow: OverflowMode,
+    /// Shared alignment to pad the HTML output to.
+    ///
+    /// Formulas rendered with the same [`Alignment`] line up on their first
+    /// relation when placed in equally aligned boxes. See
+    /// [`crate::measure_alignment`]. `None` adds no padding.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub align_to: Option<Alignment>,
+    /// Flags restoring behavior that a fix changed.
+    ///
+    /// See [`BehaviorFlags`]. All flags are off by default, and each one
+    /// logs a deprecation warning the first time it is used.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub behavior: BehaviorFlags,
+    /// Log to record macro expansions, groups and function dispatches in.
+    ///
+    /// See [`TraceSink`]. `None` records nothing.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub trace: Option<TraceSink>,
+    /// How characters outside ASCII are written in the markup.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub charset: Charset,
 }
 
 #[bon]
@@ -212,7 +314,18 @@ impl Settings {
     /// - `trust`: TrustSetting::Bool(false)
     /// - `max_size`: `f64::INFINITY`
     /// - `max_expand`: `1000`
+    /// - `max_depth`: `100`
     /// - `global_group`: `false`
+    /// - `min_script_scale`: `0.0` (no clamping)
+    /// - `decimal_comma`: `false`
+    /// - `number_formatter`: `None`
+    /// - `text_wrap`: `false`
+    /// - `max_width_em`: `None`
+    /// - `overflow`: [`OverflowMode::Scale`]
+    /// - `align_to`: `None`
+    /// - `behavior`: [`BehaviorFlags::default`] (no flags set)
+    /// - `trace`: `None`
+    /// - `charset`: [`Charset::Utf8`]
     #[must_use]
     #[builder]
     pub fn new(
@@ -243,12 +356,34 @@ impl Settings {
         max_size: Option<f64>,
         /// Maximum macro expansion iterations.
         max_expand: Option<usize>,
+        /// Maximum nesting depth of groups, arguments and environments.
+        max_depth: Option<usize>,
         /// Whether settings persist globally across render calls.
         global_group: Option<bool>,
         /// Size multiplier for rendering (scaling factor).
         size_multiplier: Option<f64>,
         /// Color for mathematical content.
         color: Option<String>,
+        /// Smallest relative size that scripts may shrink to.
+        min_script_scale: Option<f64>,
+        /// Whether a comma between digits is a decimal separator.
+        decimal_comma: Option<bool>,
+        /// Hook applied to digit runs in MathML output.
+        number_formatter: Option<NumberFormatter>,
+        /// Whether long text runs may wrap.
+        text_wrap: Option<bool>,
+        /// Widest a formula may be, in ems.
+        max_width_em: Option<f64>,
+        /// How an over-wide formula is made to fit.
+        overflow: Option<OverflowMode>,
+        /// Shared alignment to pad the output to.
+        align_to: Option<Alignment>,
+        /// Flags restoring behavior that a fix changed.
+        behavior: Option<BehaviorFlags>,
+        /// Log to record the parser's steps in.
+        trace: Option<TraceSink>,
+        /// How characters outside ASCII are written.
+        charset: Option<Charset>,
     ) -> Self {
         Self {
             display_mode: display_mode.unwrap_or(false),
@@ -264,9 +399,20 @@ impl Settings {
             trust: trust.unwrap_or_default(),
             max_size: max_size.unwrap_or(f64::INFINITY).max(0.0),
             max_expand: max_expand.unwrap_or(1000),
+            max_depth: max_depth.unwrap_or(100),
             global_group: global_group.unwrap_or(false),
             size_multiplier: size_multiplier.unwrap_or(1.0),
             color,
+            min_script_scale: min_script_scale.unwrap_or(0.0).max(0.0),
+            decimal_comma: decimal_comma.unwrap_or(false),
+            number_formatter,
+            text_wrap: text_wrap.unwrap_or(false),
+            max_width_em: max_width_em.filter(|width| *width > 0.0),
+            overflow: overflow.unwrap_or_default(),
+            align_to,
+            behavior: behavior.unwrap_or_default(),
+            trace,
+            charset: charset.unwrap_or_default(),
         }
     }
 
@@ -350,7 +496,7 @@ impl Settings {
         error_msg: &str,
         token: Option<&dyn ErrorLocationProvider>,
     ) -> bool {
-        match self.resolve_strict_catch(error_code, error_msg, token) {
+        match self.resolve_strict(error_code, error_msg, token) {
             StrictMode::Ignore => false,
             StrictMode::Error => true,
             StrictMode::Warn => {
@@ -382,25 +528,174 @@ impl Settings {
     /// malformed or has an invalid protocol, it returns `false`
     /// immediately.
     ///
+    /// # URL Validation
+    /// URLs are also checked with [`crate::security::check_url`], which fills
+    /// in `context.host`. URLs containing control characters and
+    /// `javascript:`-like protocols are rejected even when the trust setting
+    /// accepts them; the trust function is still consulted, so it sees every
+    /// URL that was attempted.
+    ///
     /// # Security Considerations
     /// This method is critical for preventing XSS attacks and other security
     /// vulnerabilities. Trust functions should carefully validate all aspects
     /// of the context before granting trust.
     pub fn is_trusted(&self, context: &mut TrustContext) -> bool {
-        if context.protocol.is_none()
-            && let Some(url) = &context.url
-        {
-            if let Some(protocol) = protocol_from_url(url) {
-                context.protocol = Some(protocol);
-            } else {
-                return false;
+        let mut url_check = Ok(());
+        if let Some(url) = &context.url {
+            if context.protocol.is_none() {
+                if let Some(protocol) = protocol_from_url(url) {
+                    context.protocol = Some(protocol);
+                } else {
+                    return false;
+                }
+            }
+            match check_url(url) {
+                Ok(info) => context.host = info.host,
+                Err(err) => url_check = Err(err),
             }
         }
 
-        match &self.trust {
+        let trusted = match &self.trust {
             TrustSetting::Bool(b) => *b,
             TrustSetting::Function(f) => f(context).unwrap_or(false),
+        };
+        trusted && url_check.is_ok()
+    }
+
+    /// Copies the macros defined so far, e.g. by `\gdef` in earlier renders.
+    ///
+    /// See [`crate::namespace::Namespace::snapshot`]; the macro namespace of a render starts
+    /// from, and writes global definitions back to, [`Settings::macros`].
+    #[must_use]
+    pub fn snapshot_macros(&self) -> Snapshot<MacroDefinition> {
+        Snapshot::from(&*self.macros.borrow())
+    }
+
+    /// Puts back the macros of an earlier [`Settings::snapshot_macros`],
+    /// dropping everything defined since.
+    pub fn restore_macros(&self, snapshot: &Snapshot<MacroDefinition>) {
+        *self.macros.borrow_mut() = snapshot.clone().into();
+    }
+
+    /// Hashes every option that can change the output, including the
+    /// contents of [`Settings::macros`], into a deterministic 64-bit key.
+    ///
+    /// Settings with equal fingerprints render every expression the same way,
+    /// so render caches and CDN keys can use it instead of tracking each
+    /// field by hand. The key is rapidhash V3 over a canonical encoding:
+    /// macros are sorted by name and `strict: true` is the same as
+    /// [`StrictMode::Error`]. It is therefore stable across processes,
+    /// platforms and releases as long as no callbacks are involved.
+    ///
+    /// Callbacks cannot be compared by content. Strict and trust functions,
+    /// number formatters and function macros contribute the address of their
+    /// allocation, so settings that carry one only fingerprint the same way
+    /// within one process, and only while they share that callback.
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        // Destructured so that a new field cannot be forgotten here.
+        let Self {
+            display_mode,
+            output,
+            leqno,
+            fleqn,
+            throw_on_error,
+            error_color,
+            macros,
+            min_rule_thickness,
+            color_is_text_color,
+            strict,
+            trust,
+            max_size,
+            max_expand,
+            max_depth,
+            global_group,
+            size_multiplier,
+            color,
+            min_script_scale,
+            decimal_comma,
+            number_formatter,
+            text_wrap,
+            max_width_em,
+            overflow,
+            align_to,
+            behavior,
+            // Recording the parser's steps does not change the output
+            trace: _,
+            charset,
+        } = self;
+        let mut key = Fingerprint::default();
+        key.bool(*display_mode);
+        key.byte(match output {
+            OutputFormat::HtmlAndMathml => 0,
+            OutputFormat::Html => 1,
+            OutputFormat::Mathml => 2,
+        });
+        key.bool(*leqno);
+        key.bool(*fleqn);
+        key.bool(*throw_on_error);
+        key.text(error_color);
+        let macros = macros.borrow();
+        let mut entries: Vec<_> = macros.iter().collect();
+        entries.sort_unstable_by_key(|&(name, _)| name);
+        key.number(entries.len() as u64);
+        for (name, definition) in entries {
+            key.text(name);
+            key.macro_definition(definition);
+        }
+        key.float(*min_rule_thickness);
+        key.bool(*color_is_text_color);
+        match strict {
+            StrictSetting::Mode(mode) => key.strict_mode(*mode),
+            StrictSetting::Bool(true) => key.strict_mode(StrictMode::Error),
+            StrictSetting::Bool(false) => key.strict_mode(StrictMode::Ignore),
+            StrictSetting::Function(function) => {
+                key.byte(3);
+                key.address(Arc::as_ptr(function));
+            }
         }
+        match trust {
+            TrustSetting::Bool(trusted) => key.bool(*trusted),
+            TrustSetting::Function(function) => {
+                key.byte(2);
+                key.address(Arc::as_ptr(function));
+            }
+        }
+        key.float(*max_size);
+        key.number(*max_expand as u64);
+        key.number(*max_depth as u64);
+        key.bool(*global_group);
+        key.float(*size_multiplier);
+        key.optional_text(color.as_deref());
+        key.float(*min_script_scale);
+        key.bool(*decimal_comma);
+        match number_formatter {
+            Some(formatter) => key.address(Arc::as_ptr(&formatter.0)),
+            None => key.byte(0),
+        }
+        key.bool(*text_wrap);
+        key.bool(max_width_em.is_some());
+        if let Some(width) = max_width_em {
+            key.float(*width);
+        }
+        key.byte(match overflow {
+            OverflowMode::Scale => 0,
+            OverflowMode::Scroll => 1,
+        });
+        key.bool(align_to.is_some());
+        if let Some(alignment) = align_to {
+            key.float(alignment.before);
+            key.float(alignment.after);
+        }
+        key.number(behavior.set_flags().count() as u64);
+        for flag in behavior.set_flags() {
+            key.text(flag.as_ref());
+        }
+        key.byte(match charset {
+            Charset::Utf8 => 0,
+            Charset::Ascii => 1,
+        });
+        rapidhash_v3(&key.0)
     }
 
     /// Helper: resolve strict setting into a concrete mode. Any boolean true
@@ -411,75 +706,133 @@ impl Settings {
         error_msg: &str,
         token: Option<&dyn ErrorLocationProvider>,
     ) -> StrictMode {
-        match &self.strict {
-            StrictSetting::Mode(m) => *m,
-            StrictSetting::Bool(b) => {
-                if *b {
-                    StrictMode::Error
-                } else {
-                    StrictMode::Ignore
+        let strict_return = match &self.strict {
+            StrictSetting::Mode(m) => return *m,
+            StrictSetting::Bool(b) => StrictReturn::Bool(*b),
+            StrictSetting::Function(f) => {
+                let context = StrictContext {
+                    code: error_code,
+                    message: error_msg,
+                    token: token.and_then(ErrorLocationProvider::token),
+                    loc: token.and_then(ErrorLocationProvider::loc),
+                };
+                match f(&context) {
+                    Some(strict_return) => strict_return,
+                    None => return StrictMode::Ignore,
                 }
             }
-            StrictSetting::Function(f) => match f(error_code, error_msg, token) {
-                Some(StrictReturn::Mode(m)) => m,
-                Some(StrictReturn::Bool(b)) => {
-                    if b {
-                        StrictMode::Error
-                    } else {
-                        StrictMode::Ignore
-                    }
-                }
-                None => StrictMode::Ignore,
-            },
+        };
+        match strict_return {
+            StrictReturn::Mode(m) => m,
+            StrictReturn::Bool(true) => StrictMode::Error,
+            StrictReturn::Bool(false) => StrictMode::Ignore,
         }
     }
+}
 
-    /// Helper variant for use_strict_behavior: if the function errors, treat as
-    /// Error.
-    fn resolve_strict_catch(
-        &self,
-        error_code: &str,
-        error_msg: &str,
-        token: Option<&dyn ErrorLocationProvider>,
-    ) -> StrictMode {
-        match &self.strict {
-            StrictSetting::Mode(m) => *m,
-            StrictSetting::Bool(b) => {
-                if *b {
-                    StrictMode::Error
-                } else {
-                    StrictMode::Ignore
-                }
+impl Default for Settings {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Canonical byte encoding hashed by [`Settings::fingerprint`]
+///
+/// Variable-length values are prefixed with their length, and optional
+/// values with a presence byte, so distinct settings never encode alike.
+#[derive(Default)]
+struct Fingerprint(Vec<u8>);
+
+impl Fingerprint {
+    fn byte(&mut self, byte: u8) {
+        self.0.push(byte);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.byte(u8::from(value));
+    }
+
+    fn number(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn float(&mut self, value: f64) {
+        // `-0.0 == 0.0`, and both render alike.
+        self.number(if value == 0.0 { 0 } else { value.to_bits() });
+    }
+
+    fn text(&mut self, text: &str) {
+        self.number(text.len() as u64);
+        self.0.extend_from_slice(text.as_bytes());
+    }
+
+    fn optional_text(&mut self, text: Option<&str>) {
+        self.bool(text.is_some());
+        if let Some(text) = text {
+            self.text(text);
+        }
+    }
+
+    fn address<T: ?Sized>(&mut self, pointer: *const T) {
+        self.byte(1);
+        self.number(pointer.cast::<()>().addr() as u64);
+    }
+
+    fn strict_mode(&mut self, mode: StrictMode) {
+        self.byte(match mode {
+            StrictMode::Ignore => 0,
+            StrictMode::Warn => 1,
+            StrictMode::Error => 2,
+        });
+    }
+
+    fn macro_definition(&mut self, definition: &MacroDefinition) {
+        match definition {
+            MacroDefinition::String(text) => {
+                self.byte(0);
+                self.text(text);
             }
-            StrictSetting::Function(func) => {
-                // Mimic JS semantics: if function panics, treat as "error".
-                let f = Arc::clone(func);
-                let error_code = error_code.to_owned();
-                let error_msg = error_msg.to_owned();
-                let token_ref = token;
-                let res = f(&error_code, &error_msg, token_ref);
-                match res {
-                    Some(StrictReturn::Mode(m)) => m,
-                    Some(StrictReturn::Bool(b)) => {
-                        if b {
-                            StrictMode::Error
-                        } else {
-                            StrictMode::Ignore
+            MacroDefinition::StaticStr(text) => {
+                self.byte(0);
+                self.text(text);
+            }
+            MacroDefinition::Expansion(expansion) => {
+                self.byte(1);
+                self.number(expansion.tokens.len() as u64);
+                for token in &expansion.tokens {
+                    self.text(&token.text);
+                }
+                self.number(expansion.num_args as u64);
+                match &expansion.delimiters {
+                    Some(delimiters) => {
+                        self.number(delimiters.len() as u64 + 1);
+                        for delimiter in delimiters {
+                            self.number(delimiter.len() as u64);
+                            for text in delimiter {
+                                self.text(text);
+                            }
                         }
                     }
-                    None => StrictMode::Ignore,
+                    None => self.number(0),
                 }
+                self.byte(match expansion.unexpandable {
+                    None => 0,
+                    Some(false) => 1,
+                    Some(true) => 2,
+                });
+            }
+            MacroDefinition::Function(function) => {
+                self.byte(2);
+                self.address(Arc::as_ptr(function));
+            }
+            MacroDefinition::StaticFunction(function) => {
+                self.byte(3);
+                self.number(*function as usize as u64);
             }
         }
     }
 }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Self::builder().build()
-    }
-}
-
 /// Return type for strict validation functions in KaTeX.
 ///
 /// This enum represents the possible return values from strict mode functions,
@@ -511,8 +864,35 @@ pub enum StrictReturn {
     Mode(StrictMode),
 }
 
+/// What a strict function is asked about: one LaTeX-incompatible construct
+/// in the input.
+///
+/// The location is resolved up front, so handlers can point at the offending
+/// input without knowing which kind of node reported it.
+///
+/// # Cross-references
+/// - Passed to [`StrictFunction`] through [`StrictSetting::Function`].
+#[derive(Debug, Clone, Copy)]
+pub struct StrictContext<'a> {
+    /// Identifier of the kind of violation, e.g. `"unicodeTextInMathMode"`.
+    pub code: &'a str,
+    /// Human-readable description of the violation.
+    pub message: &'a str,
+    /// The token that triggered the violation, when it was reported for a
+    /// token rather than a parse node.
+    pub token: Option<&'a Token>,
+    /// Where in the input the violation is, if known.
+    pub loc: Option<&'a SourceLocation>,
+}
+
 /// Function signature for custom strict mode evaluation in KaTeX.
-pub type StrictFunction =
+pub type StrictFunction = dyn Fn(&StrictContext<'_>) -> Option<StrictReturn> + Send + Sync;
+
+/// The strict function signature before [`StrictContext`], taking the code,
+/// the message and the location provider as separate arguments.
+///
+/// Wrap such closures with [`StrictSetting::from_legacy`].
+pub type LegacyStrictFunction =
     dyn Fn(&str, &str, Option<&dyn ErrorLocationProvider>) -> Option<StrictReturn> + Send + Sync;
 
 /// Configuration for strict mode behavior in KaTeX parsing and rendering.
@@ -544,13 +924,9 @@ pub enum StrictSetting {
     Bool(bool),
     /// Custom function for dynamic strictness evaluation.
     ///
-    /// The function receives error code, message, and optional location,
-    /// returning an optional [`StrictReturn`] to determine behavior.
-    ///
-    /// # Function Parameters
-    /// - `error_code`: String identifier for the type of strict violation.
-    /// - `error_msg`: Human-readable description of the issue.
-    /// - `token`: Optional location information for error reporting.
+    /// The function receives a [`StrictContext`] with the error code,
+    /// message, and the token and location of the violation, returning an
+    /// optional [`StrictReturn`] to determine behavior.
     ///
     /// # Function Return
     /// - `Some(StrictReturn)` to specify strictness behavior.
@@ -558,6 +934,38 @@ pub enum StrictSetting {
     Function(Arc<StrictFunction>),
 }
 
+impl StrictSetting {
+    /// Wraps a strict function written against the [`LegacyStrictFunction`]
+    /// signature.
+    ///
+    /// The location provider passed to `function` is the token when there is
+    /// one, and the bare location otherwise.
+    ///
+    /// ```rust
+    /// use katex::{StrictMode, StrictReturn, StrictSetting};
+    ///
+    /// let strict = StrictSetting::from_legacy(|code, _message, _token| {
+    ///     (code == "unicodeTextInMathMode").then_some(StrictReturn::Mode(StrictMode::Warn))
+    /// });
+    /// assert!(matches!(strict, StrictSetting::Function(_)));
+    /// ```
+    pub fn from_legacy<F>(function: F) -> Self
+    where
+        F: Fn(&str, &str, Option<&dyn ErrorLocationProvider>) -> Option<StrictReturn>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self::Function(Arc::new(move |context| {
+            let provider = context
+                .token
+                .map(|token| token as &dyn ErrorLocationProvider)
+                .or_else(|| context.loc.map(|loc| loc as &dyn ErrorLocationProvider));
+            function(context.code, context.message, provider)
+        }))
+    }
+}
+
 impl fmt::Debug for StrictSetting {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -611,6 +1019,13 @@ pub struct TrustContext {
     /// Automatically populated when `url` is provided. Used to enforce
     /// protocol-specific security policies.
     pub protocol: Option<String>,
+    /// The host of the URL in lowercase ASCII form (e.g., "katex.org").
+    ///
+    /// Automatically populated when `url` has an authority part.
+    /// Internationalized domain names are punycode-encoded (`xn--...`), so
+    /// lookalike Unicode hosts cannot pass for an allowed one. See
+    /// [`crate::security::check_url`].
+    pub host: Option<String>,
     /// Optional CSS class name for HTML class attributes.
     ///
     /// Used with commands like `\htmlClass` to specify CSS classes
@@ -695,3 +1110,48 @@ impl Default for TrustSetting {
         Self::Bool(false)
     }
 }
+
+/// Function signature for formatting digit runs in MathML output.
+pub type NumberFormatFunction = dyn Fn(&str) -> String + Send + Sync;
+
+/// Hook applied to every run of digits that MathML output groups into a
+/// single `<mn>` element.
+///
+/// The function receives the concatenated run exactly as it would be
+/// emitted (e.g. `"1234567"`, or `"3.14"` including number punctuation)
+/// and returns the text to emit instead, for instance with thin spaces
+/// inserted every three digits. HTML output is not affected.
+///
+/// # Cross-references
+/// - See [`Settings::number_formatter`] for installing the hook.
+/// - The runs are formed by [`crate::build_mathml::build_expression`].
+#[derive(Clone)]
+pub struct NumberFormatter(Arc<NumberFormatFunction>);
+
+impl NumberFormatter {
+    /// Wraps a closure as a number formatter.
+    pub fn new<F>(format: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        Self(Arc::new(format))
+    }
+
+    /// Formats one digit run.
+    #[must_use]
+    pub fn format(&self, digits: &str) -> String {
+        (self.0)(digits)
+    }
+}
+
+impl fmt::Debug for NumberFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NumberFormatter(<fn>)")
+    }
+}
+
+impl PartialEq for NumberFormatter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
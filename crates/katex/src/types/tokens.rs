@@ -245,6 +245,10 @@ impl ErrorLocationProvider for Token {
     fn loc(&self) -> Option<&SourceLocation> {
         self.loc.as_ref()
     }
+
+    fn token(&self) -> Option<&Token> {
+        Some(self)
+    }
 }
 
 /// Implementation of `ErrorLocationProvider` for `Option<Token>`.
@@ -262,4 +266,8 @@ impl ErrorLocationProvider for Option<Token> {
         let t = self.as_ref()?;
         t.loc.as_ref()
     }
+
+    fn token(&self) -> Option<&Token> {
+        self.as_ref()
+    }
 }
@@ -0,0 +1,183 @@
+//! Log of how the parser expanded and read an expression
+//!
+//! Setting [`Settings::trace`](crate::Settings::trace) to a [`TraceSink`]
+//! makes the parser record every macro expansion, every group it enters and
+//! leaves, and every function it dispatches, in the order they happen and
+//! with the location of the token that caused them. Reading the log answers
+//! questions like "why did this expand that way":
+//!
+//! ```rust
+//! use katex::types::{TraceEventKind, TraceSink};
+//! use katex::{KatexContext, Settings, render_to_string};
+//!
+//! let trace = TraceSink::new();
+//! let settings = Settings::builder().trace(trace.clone()).build();
+//! render_to_string(&KatexContext::default(), r"\def\x{\frac12}{\x}", &settings).unwrap();
+//!
+//! let events = trace.take();
+//! assert!(events.iter().any(|event| matches!(
+//!     &event.kind,
+//!     TraceEventKind::MacroExpansion { name, expansion } if name == r"\x" && expansion == r"\frac12"
+//! )));
+//! for event in &events {
+//!     println!("{event}");
+//! }
+//! ```
+//!
+//! A sink can be shared by several renders, which append to the same log.
+
+use core::{fmt, mem};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use alloc::sync::Arc;
+
+use crate::types::{SourceLocation, Token};
+
+/// What happened at one step of a [`TraceEvent`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEventKind {
+    /// A macro was replaced by its expansion, with its arguments substituted
+    MacroExpansion {
+        /// Name of the macro, e.g. `\iff`
+        name: String,
+        /// Text of the tokens the macro expanded to, with a space after each
+        /// control word that is followed by a letter
+        expansion: String,
+    },
+    /// A group was opened, by `{` or `\begingroup`
+    GroupBegin {
+        /// The token that opened the group
+        open: String,
+    },
+    /// A group was closed, by `}` or `\endgroup`
+    GroupEnd {
+        /// The token that closed the group
+        close: String,
+    },
+    /// The handler of a function was called with its parsed arguments
+    FunctionDispatch {
+        /// Name of the function, e.g. `\frac`
+        name: String,
+    },
+}
+
+/// One entry of the log recorded by a [`TraceSink`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// What happened
+    pub kind: TraceEventKind,
+    /// Where the token that caused it was read from: the input, or the
+    /// definition of the macro it was expanded from
+    pub loc: Option<SourceLocation>,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TraceEventKind::MacroExpansion { name, expansion } => {
+                write!(f, "expand {name} -> {expansion}")?;
+            }
+            TraceEventKind::GroupBegin { open } => write!(f, "begin group {open}")?,
+            TraceEventKind::GroupEnd { close } => write!(f, "end group {close}")?,
+            TraceEventKind::FunctionDispatch { name } => write!(f, "call {name}")?,
+        }
+        if let Some(loc) = &self.loc {
+            write!(f, " at {}..{}", loc.start, loc.end)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes tokens back as text, keeping a control word apart from a letter
+/// after it so that `\frac` followed by `a` does not read as `\fraca`
+pub fn detokenize<'a, I>(tokens: I) -> String
+where
+    I: IntoIterator<Item = &'a Token>,
+{
+    let mut text = String::new();
+    let mut after_control_word = false;
+    for token in tokens {
+        let token = token.text.as_str();
+        if after_control_word && token.starts_with(char::is_alphabetic) {
+            text.push(' ');
+        }
+        text.push_str(token);
+        after_control_word = token.len() > 1
+            && token.starts_with('\\')
+            && token[1..].starts_with(char::is_alphabetic);
+    }
+    text
+}
+
+/// Shared log that the parser appends [`TraceEvent`]s to
+///
+/// Clones share the same log, so a clone can be put into the settings and
+/// the original read after rendering.
+#[derive(Clone, Default)]
+pub struct TraceSink(Arc<Mutex<Vec<TraceEvent>>>);
+
+impl TraceSink {
+    /// Creates an empty log.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an event to the log.
+    pub fn record(&self, kind: TraceEventKind, loc: Option<&SourceLocation>) {
+        self.events_mut().push(TraceEvent {
+            kind,
+            loc: loc.cloned(),
+        });
+    }
+
+    /// Copies the events recorded so far.
+    #[must_use]
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events_mut().clone()
+    }
+
+    /// Removes and returns the events recorded so far.
+    #[must_use]
+    pub fn take(&self) -> Vec<TraceEvent> {
+        mem::take(&mut *self.events_mut())
+    }
+
+    fn events_mut(&self) -> MutexGuard<'_, Vec<TraceEvent>> {
+        // A panic while pushing cannot leave the log half-written
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for TraceSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TraceSink({} events)", self.events_mut().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_log() {
+        let sink = TraceSink::new();
+        let clone = sink.clone();
+        clone.record(
+            TraceEventKind::FunctionDispatch {
+                name: r"\frac".to_owned(),
+            },
+            None,
+        );
+        assert_eq!(sink.events().len(), 1);
+        assert_eq!(sink.take()[0].to_string(), r"call \frac");
+        assert!(sink.events().is_empty());
+    }
+
+    #[test]
+    fn test_detokenize_separates_control_words_from_letters() {
+        let tokens = [r"\frac", "a", r"\;", "b", r"\alpha", "1", r"\beta", r"\x"]
+            .map(|text| Token::new(text, None));
+        assert_eq!(detokenize(&tokens), r"\frac a\;b\alpha1\beta\x");
+    }
+}
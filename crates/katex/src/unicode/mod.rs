@@ -14,7 +14,9 @@ pub use unicode_scripts::Script;
 pub use unicode_scripts::all_blocks_len;
 pub use unicode_scripts::script_from_codepoint;
 pub use unicode_scripts::supported_codepoint;
-pub use unicode_sup_or_sub::{get_base_character, is_unicode_subscript, is_unicode_sup_or_sub};
+pub use unicode_sup_or_sub::{
+    combined_prime, get_base_character, is_unicode_subscript, is_unicode_sup_or_sub, prime_count,
+};
 pub use unicode_symbols::UNICODE_SYMBOLS;
 
 /// Get the LaTeX mapping for a Unicode accent character
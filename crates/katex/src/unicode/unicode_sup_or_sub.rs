@@ -167,7 +167,36 @@ pub fn is_unicode_sup_or_sub(ch: char) -> bool {
 /// Convert a subscript or superscript character to its base form
 /// Returns the original character if no mapping exists
 pub fn to_base_character(ch: char) -> String {
-    get_base_character(ch).map_or_else(|| ch.to_string(), ToString::to_string)
+    get_base_character(ch).map_or_else(|| ch.to_string(), str::to_owned)
+}
+
+/// Number of primes written by a prime token
+///
+/// Returns `Some(n)` for the ASCII apostrophe and the Unicode prime
+/// characters (′ ″ ‴ ⁗), which all feed the same prime-superscript
+/// machinery in the parser, and `None` for any other token.
+#[must_use]
+pub fn prime_count(text: &str) -> Option<usize> {
+    match text {
+        "'" | "\u{2032}" => Some(1),
+        "\u{2033}" => Some(2),
+        "\u{2034}" => Some(3),
+        "\u{2057}" => Some(4),
+        _ => None,
+    }
+}
+
+/// The single character spelling `count` consecutive primes, if Unicode
+/// has one (′ ″ ‴ ⁗)
+#[must_use]
+pub const fn combined_prime(count: usize) -> Option<char> {
+    match count {
+        1 => Some('\u{2032}'),
+        2 => Some('\u{2033}'),
+        3 => Some('\u{2034}'),
+        4 => Some('\u{2057}'),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -289,4 +318,22 @@ mod tests {
         assert!(is_unicode_superscript('\u{1D31}')); // ᴱ
         assert!(is_unicode_superscript('\u{1D33}')); // ᴳ
     }
+
+    #[test]
+    fn test_prime_count() {
+        assert_eq!(prime_count("'"), Some(1));
+        assert_eq!(prime_count("\u{2032}"), Some(1));
+        assert_eq!(prime_count("\u{2033}"), Some(2));
+        assert_eq!(prime_count("\u{2034}"), Some(3));
+        assert_eq!(prime_count("\u{2057}"), Some(4));
+        assert_eq!(prime_count("''"), None);
+        assert_eq!(prime_count("\\prime"), None);
+
+        for count in 1..=4 {
+            let glyph = combined_prime(count).unwrap();
+            assert_eq!(prime_count(&glyph.to_string()), Some(count));
+        }
+        assert_eq!(combined_prime(0), None);
+        assert_eq!(combined_prime(5), None);
+    }
 }
@@ -2,7 +2,6 @@
 //! Provides common utility functions for string manipulation, type checking,
 //! and helper operations.
 
-use core::fmt;
 use core::slice;
 
 /// Converts a camelCase string to hyphen-case.
@@ -47,148 +46,13 @@ pub fn hyphenate(s: &str) -> String {
     out
 }
 
-/// Writes the escaped HTML representation of `text` into the provided writer.
-///
-/// This helper avoids intermediate string allocations by emitting directly
-/// into any `fmt::Write` implementor.
-#[inline]
-pub fn escape_into<W: fmt::Write>(writer: &mut W, text: &str) -> fmt::Result {
-    let mut last = 0;
-    for (idx, ch) in text.char_indices() {
-        let replacement = match ch {
-            '&' => Some("&amp;"),
-            '>' => Some("&gt;"),
-            '<' => Some("&lt;"),
-            '"' => Some("&quot;"),
-            '\'' => Some("&#x27;"),
-            _ => None,
-        };
-
-        if let Some(rep) = replacement {
-            if last < idx {
-                writer.write_str(&text[last..idx])?;
-            }
-            writer.write_str(rep)?;
-            last = idx + ch.len_utf8();
-        }
-    }
-
-    if last < text.len() {
-        writer.write_str(&text[last..])
-    } else {
-        Ok(())
-    }
-}
-
-/// Extracts the protocol from a URL string.
-///
-/// This function parses the URL to determine its protocol (scheme),
-/// handling various edge cases like HTML entities for colons.
-/// Useful for validating URLs in mathematical content that may include links.
-///
-/// # Parameters
-/// - `url`: The URL string to parse.
-///
-/// # Returns
-/// - `Some(protocol)`: The lowercase protocol string if valid.
-/// - `Some("_relative")`: If the URL is relative (no protocol).
-/// - `None`: If the protocol is invalid.
-///
-/// # Examples
-/// ```
-/// use katex::utils::protocol_from_url;
-///
-/// assert_eq!(
-///     protocol_from_url("https://example.com"),
-///     Some("https".to_string())
-/// );
-/// assert_eq!(
-///     protocol_from_url("/path/to/file"),
-///     Some("_relative".to_string())
-/// );
-/// assert_eq!(protocol_from_url("1invalid://example.com"), None);
-/// ```
-///
-/// # Error Handling
-/// - Invalid schemes (not starting with letter, containing invalid chars)
-///   return `None`.
-/// - HTML entities for colon are handled (e.g., `&#58;`, `&colon;`).
-///
-/// # Cross-references
-/// See validate_and_return for scheme validation logic.
-/// See match_html_colon_entity for HTML entity parsing.
-#[must_use]
-pub fn protocol_from_url(url: &str) -> Option<String> {
-    let mut s = url;
-
-    while let Some(first) = s.chars().next() {
-        if first <= '\u{20}' {
-            s = &s[first.len_utf8()..];
-        } else {
-            break;
-        }
-    }
-
-    for (i, ch) in s.char_indices() {
-        if ch == ':' {
-            return validate_and_return(&s[..i], ":", &s[i + 1..]);
-        } else if ch == '&' {
-            let rest = &s[i..];
-            if let Some((entity, skip)) = match_html_colon_entity(rest) {
-                return validate_and_return(&s[..i], entity, &s[i + skip..]);
-            }
-        } else if ch == '\\' || ch == '/' || ch == '#' || ch == '?' {
-            return Some("_relative".into());
-        }
-    }
+/// Writes the escaped HTML representation of `text`; see
+/// [`crate::escape::escape_into`].
+pub use crate::escape::escape_into;
 
-    Some("_relative".into())
-}
-
-fn match_html_colon_entity(s: &str) -> Option<(&'static str, usize)> {
-    // &#0*58
-    if let Some(decimal) = s.strip_prefix("&#") {
-        let mut idx = 0;
-        while decimal[idx..].starts_with('0') {
-            idx += 1;
-        }
-        if decimal[idx..].starts_with("58") {
-            let after = idx + 2;
-            return Some(("&#0*58", 2 + after));
-        }
-        if decimal[idx..].starts_with('x') || decimal[idx..].starts_with('X') {
-            let hexpart = &decimal[idx + 1..];
-            let mut idx2 = 0;
-            while hexpart[idx2..].starts_with('0') {
-                idx2 += 1;
-            }
-            if hexpart[idx2..].to_ascii_lowercase().starts_with("3a") {
-                let after = idx + 1 + idx2 + 2;
-                return Some(("&#x0*3a", 2 + after));
-            }
-        }
-    }
-    // &colon
-    if s.to_ascii_lowercase().starts_with("&colon") {
-        return Some(("&colon", 6));
-    }
-    None
-}
-
-fn validate_and_return(scheme: &str, colon_match: &str, _rest: &str) -> Option<String> {
-    if colon_match != ":" {
-        return None;
-    }
-
-    let mut chars = scheme.chars();
-    if !matches!(chars.next(), Some(c) if c.is_ascii_alphabetic()) {
-        return None;
-    }
-    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') {
-        return None;
-    }
-    Some(scheme.to_lowercase())
-}
+/// Extracts the protocol from a URL string; see
+/// [`crate::security::protocol_from_url`].
+pub use crate::security::protocol_from_url;
 
 /// Push a value onto a vector and return a reference to the new element and the
 /// vector.
@@ -206,6 +70,24 @@ pub fn push_and_get_mut<T>(vec: &mut Vec<T>, value: T) -> &mut T {
     &mut vec[idx]
 }
 
+/// Stack that must be left for [`with_stack`] to run its closure in place
+const STACK_RED_ZONE: usize = 128 * 1024;
+
+/// Size of each stack segment [`with_stack`] allocates
+const STACK_SEGMENT_SIZE: usize = 1024 * 1024;
+
+/// Runs `f`, on a new stack segment if the current stack is nearly used up.
+///
+/// Parsing and building recurse once per level of nesting in the input, and
+/// an unoptimized build uses tens of kilobytes of stack per level. The
+/// recursive entry points run through this, so a formula can nest as deeply
+/// as [`Settings::max_depth`](crate::Settings::max_depth) allows on any
+/// thread instead of overflowing its stack.
+#[inline]
+pub(crate) fn with_stack<R, F: FnOnce() -> R>(f: F) -> R {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, f)
+}
+
 /// Sets a panic hook for better error messages in WebAssembly.
 #[allow(clippy::missing_const_for_fn)]
 pub fn set_panic_hook() {
@@ -255,53 +137,4 @@ mod tests {
         assert_eq!(hyphenate("camelCase"), "camel-case");
         assert_eq!(hyphenate("XMLHttpRequest"), "x-m-l-http-request");
     }
-
-    #[test]
-    fn test_protocol_from_url() {
-        assert_eq!(
-            protocol_from_url("https://example.com"),
-            Some("https".to_owned())
-        );
-        assert_eq!(
-            protocol_from_url("http://example.com"),
-            Some("http".to_owned())
-        );
-        assert_eq!(
-            protocol_from_url("ftp://example.com"),
-            Some("ftp".to_owned())
-        );
-        assert_eq!(
-            protocol_from_url("/path/to/file"),
-            Some("_relative".to_owned())
-        );
-        assert_eq!(protocol_from_url("1invalid://example.com"), None);
-        assert_eq!(protocol_from_url("weird:colon"), Some("weird".to_owned()));
-    }
-
-    #[test]
-    fn test_protocol_from_url_misc() {
-        assert_eq!(protocol_from_url("/path/to/file"), Some("_relative".into()));
-        assert_eq!(
-            protocol_from_url("  \u{0007}../rel"),
-            Some("_relative".into())
-        );
-
-        assert_eq!(
-            protocol_from_url("https://example.com"),
-            Some("https".into())
-        );
-        assert_eq!(protocol_from_url("FTP://example.com"), Some("ftp".into()));
-        assert_eq!(
-            protocol_from_url("mailto:user@example.com"),
-            Some("mailto".into())
-        );
-
-        assert_eq!(protocol_from_url("http&#058//foo"), None);
-        assert_eq!(protocol_from_url("http&#x03a//foo"), None);
-        assert_eq!(protocol_from_url("http&colon//foo"), None);
-
-        assert_eq!(protocol_from_url("1abc://foo"), None);
-        assert_eq!(protocol_from_url("abc^://foo"), None);
-        assert_eq!(protocol_from_url("ht tp://foo"), None);
-    }
 }
@@ -0,0 +1,199 @@
+//! Coverage corpus
+//!
+//! Renders every function, environment and symbol of the default context at
+//! least once. The corpus is generated from the context's tables with the
+//! smallest arguments each command accepts, so a command registered without
+//! a working handler or builder fails here even if no other test uses it.
+//! Functions whose syntax the generator cannot guess declare examples in
+//! their specs, and the other commands are listed in [`OVERRIDES`].
+//!
+//! Each expression must render without panicking, and to something other
+//! than the empty formula.
+
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+
+use katex::symbols::{Group, Mode, NonAtom};
+use katex::types::{ArgType, Settings, StrictSetting, TrustSetting};
+use katex::{KatexContext, render_to_string};
+use katex_test_utils::check_function_examples;
+
+/// Expressions for commands that need more than generated arguments
+const OVERRIDES: &[(&str, &str)] = &[
+    ("array", r"\begin{array}{cc}a&b\\c&d\end{array}"),
+    ("darray", r"\begin{darray}{cc}a&b\\c&d\end{darray}"),
+    ("subarray", r"\begin{subarray}{c}a\\b\end{subarray}"),
+    ("alignat", r"\begin{alignat}{1}a&b\\c&d\end{alignat}"),
+    ("alignat*", r"\begin{alignat*}{1}a&b\\c&d\end{alignat*}"),
+    ("alignedat", r"\begin{alignedat}{1}a&b\\c&d\end{alignedat}"),
+    ("equation", r"\begin{equation}a\end{equation}"),
+    ("equation*", r"\begin{equation*}a\end{equation*}"),
+];
+
+/// Commands that only exist to report an error, like a `\]` without `\[`
+const REPORTS_ERRORS: &[&str] = &[r"\]"];
+
+fn settings() -> Settings {
+    Settings::builder()
+        .display_mode(true)
+        .trust(TrustSetting::Bool(true))
+        .strict(StrictSetting::Bool(false))
+        .build()
+}
+
+/// The smallest argument of type `arg_type`
+const fn argument(arg_type: Option<&ArgType>) -> &'static str {
+    match arg_type {
+        Some(ArgType::Color) => "{red}",
+        Some(ArgType::Size) => "{1em}",
+        Some(ArgType::Url) => "{https://katex.org}",
+        Some(ArgType::Primitive) => " (",
+        _ => "{x}",
+    }
+}
+
+fn in_mode(mode: Mode, expression: &str) -> String {
+    match mode {
+        Mode::Math => expression.to_owned(),
+        Mode::Text => format!(r"\text{{{expression}}}"),
+    }
+}
+
+fn override_for(name: &str) -> Option<String> {
+    OVERRIDES
+        .iter()
+        .find(|&&(command, _)| command == name)
+        .map(|&(_, expression)| expression.to_owned())
+}
+
+/// Renders every expression, returning a description of each failure
+fn render_all(ctx: &KatexContext, corpus: &[(String, String)]) -> Vec<String> {
+    let settings = settings();
+    let empty = render_to_string(ctx, "", &settings).ok();
+    let mut failures = Vec::new();
+    for (name, expression) in corpus {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            render_to_string(ctx, expression, &settings)
+        }));
+        match result {
+            Err(_) => failures.push(format!("{name}: `{expression}` panicked")),
+            Ok(Err(_)) if REPORTS_ERRORS.contains(&name.as_str()) => {}
+            Ok(Ok(_)) if REPORTS_ERRORS.contains(&name.as_str()) => {
+                failures.push(format!("{name}: `{expression}` did not report an error"));
+            }
+            Ok(Err(error)) => failures.push(format!("{name}: `{expression}` failed: {error}")),
+            Ok(Ok(markup)) if empty.as_ref() == Some(&markup) => {
+                failures.push(format!("{name}: `{expression}` rendered nothing"));
+            }
+            Ok(Ok(_)) => {}
+        }
+    }
+    failures
+}
+
+fn assert_renders(corpus: &[(String, String)]) {
+    let failures = render_all(&KatexContext::default(), corpus);
+    assert!(
+        failures.is_empty(),
+        "{} of {} expressions failed:\n{}",
+        failures.len(),
+        corpus.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn every_function_renders() {
+    let ctx = KatexContext::default();
+    let mut corpus: Vec<(String, String)> = ctx
+        .functions
+        .iter()
+        .map(|(name, spec)| {
+            let example = spec
+                .examples
+                .iter()
+                .find(|example| example.contains(name.as_str()));
+            let expression = example.map_or_else(
+                || {
+                    let arg_types = spec.arg_types.as_deref().unwrap_or_default();
+                    let args: String = arg_types
+                        .get(spec.num_optional_args..)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(Some)
+                        .chain(iter::repeat(None))
+                        .take(spec.num_args)
+                        .map(argument)
+                        .collect();
+                    let expression = if spec.infix {
+                        format!("a {name} b")
+                    } else {
+                        format!("{name}{args}")
+                    };
+                    let mode = if spec.allowed_in_math {
+                        Mode::Math
+                    } else {
+                        Mode::Text
+                    };
+                    in_mode(mode, &expression)
+                },
+                |&example| example.to_owned(),
+            );
+            (name.clone(), expression)
+        })
+        .collect();
+    corpus.sort();
+    assert!(corpus.len() > 300, "only {} functions", corpus.len());
+    assert_renders(&corpus);
+}
+
+#[test]
+fn every_environment_renders() {
+    let ctx = KatexContext::default();
+    let mut corpus: Vec<(String, String)> = ctx
+        .environments
+        .keys()
+        .map(|name| {
+            let expression = override_for(name)
+                .unwrap_or_else(|| format!(r"\begin{{{name}}}a&b\\c&d\end{{{name}}}"));
+            (name.clone(), expression)
+        })
+        .collect();
+    corpus.sort();
+    assert!(corpus.len() > 30, "only {} environments", corpus.len());
+    assert_renders(&corpus);
+}
+
+#[test]
+fn every_symbol_renders() {
+    let ctx = KatexContext::default();
+    let mut corpus = Vec::new();
+    for mode in [Mode::Math, Mode::Text] {
+        for name in ctx.symbols.names(mode) {
+            let expression = override_for(name).unwrap_or_else(|| {
+                let is_accent = ctx
+                    .symbols
+                    .get(mode, name)
+                    .is_some_and(|info| info.group == Group::NonAtom(NonAtom::AccentToken));
+                in_mode(
+                    mode,
+                    &if is_accent {
+                        format!("{name}{{x}}")
+                    } else {
+                        name.to_owned()
+                    },
+                )
+            });
+            corpus.push((name.to_owned(), expression));
+        }
+    }
+    assert!(corpus.len() > 2000, "only {} symbols", corpus.len());
+    assert_renders(&corpus);
+}
+
+#[test]
+fn every_function_example_renders() {
+    let ctx = KatexContext::default();
+    let count = check_function_examples(&ctx).unwrap_or_else(|error| panic!("{error}"));
+    assert!(count > 50, "only {count} examples");
+}
@@ -0,0 +1,87 @@
+//! Vendored data integrity
+//!
+//! `cargo xtask extract-data` records the KaTeX revision it extracted from and
+//! a rapidhash of every file in `data/` in `data/checksums.json`. These tests
+//! catch hand edits to the generated files and a submodule pin that moved
+//! without regenerating them. `cargo xtask extract-data --verify-data`
+//! performs the full check against a fresh extraction.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rapidhash::v3::rapidhash_v3;
+use serde_json::Value;
+
+fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("data")
+}
+
+fn checksums() -> Result<Value, Box<dyn Error>> {
+    let contents = fs::read_to_string(data_dir().join("checksums.json"))?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+#[test]
+fn data_files_match_their_checksums() -> Result<(), Box<dyn Error>> {
+    let checksums = checksums()?;
+    let recorded = checksums["files"]
+        .as_object()
+        .ok_or("data/checksums.json has no \"files\" object")?;
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(data_dir())? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name != "checksums.json" {
+            names.push(name);
+        }
+    }
+    names.sort();
+    let mut listed: Vec<_> = recorded.keys().cloned().collect();
+    listed.sort();
+    assert_eq!(
+        names, listed,
+        "data/ and data/checksums.json list different files; \
+         run `cargo xtask extract-data`"
+    );
+
+    for (name, checksum) in recorded {
+        let contents = fs::read(data_dir().join(name))?;
+        assert_eq!(
+            format!("{:016x}", rapidhash_v3(&contents)),
+            checksum
+                .as_str()
+                .ok_or_else(|| format!("checksum of data/{name} is not a string"))?,
+            "data/{name} was modified after extraction; \
+             run `cargo xtask extract-data` instead of editing it"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn data_matches_pinned_katex_revision() -> Result<(), Box<dyn Error>> {
+    let checksums = checksums()?;
+    let commit = checksums["katex_commit"]
+        .as_str()
+        .ok_or("data/checksums.json has no \"katex_commit\" string")?;
+
+    // Only present in a repository checkout, not in the published crate
+    let gitmodules = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../.gitmodules");
+    if let Ok(gitmodules) = fs::read_to_string(gitmodules) {
+        let pinned = gitmodules
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("commit = "))
+            .ok_or(".gitmodules does not pin a KaTeX commit")?;
+        assert_eq!(
+            commit, pinned,
+            "data/ was extracted from a different KaTeX revision than the pinned submodule"
+        );
+    }
+
+    if let Ok(upstream) = fs::read_to_string(data_dir().join("upstream.json")) {
+        let upstream: Value = serde_json::from_str(&upstream)?;
+        assert_eq!(upstream["commit"].as_str(), Some(commit));
+    }
+    Ok(())
+}
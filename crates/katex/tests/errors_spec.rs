@@ -2,9 +2,14 @@
 
 mod setup;
 
-use katex::types::{Mode, ParseErrorKind};
+use katex::render_to_string;
+use katex::types::{Mode, ParseErrorKind, Settings, StrictSetting};
 use setup::*;
 
+/// Malformed inputs whose diagnostics are snapshotted, see the file for the
+/// format
+const ERROR_CORPUS: &str = include_str!("data/error_corpus.txt");
+
 #[test]
 fn parser_handle_infix_nodes() {
     it("rejects repeated infix operators", || {
@@ -98,7 +103,14 @@ fn parser_parse_atom() {
     });
 
     it("rejects double superscripts involving primes", || {
-        for expr in [r"1'_2^3", r"1^2'", r"1^2_3'", r"1'_2'"] {
+        for expr in [
+            r"1'_2^3",
+            r"1^2'",
+            r"1^2_3'",
+            r"1'_2'",
+            "1^2\u{2032}",
+            "1\u{2033}_2'",
+        ] {
             let error = expect!(expr).parse_error(&strict_settings())?;
             assert!(matches!(
                 error.kind.as_ref(),
@@ -613,3 +625,40 @@ fn unicode_accents() {
         },
     );
 }
+
+/// What a user is shown for `input`: the input with a caret under the
+/// reported span, the error message, and the inline rendering of the error.
+fn diagnostics(input: &str) -> TestResult<String> {
+    let error = expect!(input).parse_error(&strict_settings())?;
+    let mut report = format!("{input}\n");
+    if let Some(position) = error.position() {
+        let span = &input[position..position + error.length().unwrap_or(0)];
+        report.push_str(&" ".repeat(input[..position].chars().count()));
+        report.push_str(&"^".repeat(span.chars().count().max(1)));
+        report.push('\n');
+    }
+    report.push_str(&error.to_string());
+    report.push('\n');
+
+    let settings = Settings::builder()
+        .throw_on_error(false)
+        .strict(StrictSetting::Bool(true))
+        .build();
+    let html = render_to_string(default_ctx(), input, &settings)?;
+    report.push_str(&normalize_markup(&html));
+    Ok(report)
+}
+
+#[test]
+fn error_corpus() {
+    it("should keep the diagnostics of every corpus input", || {
+        let entries = ERROR_CORPUS
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for entry in entries {
+            let (name, input) = entry.split_once(' ').unwrap_or((entry, ""));
+            insta::assert_snapshot!(format!("error_corpus_{name}"), diagnostics(input)?);
+        }
+        Ok(())
+    });
+}
@@ -2,21 +2,49 @@
 
 mod setup;
 use katex::{
-    CharacterMetrics, KatexContext, Settings, TrustSetting,
-    build_html::DomType,
+    CharacterMetrics, Charset, KATEX_CSS, KatexContext, Settings, StrictMode, StrictSetting,
+    TrustSetting, UPSTREAM_VERSION,
+    braille::BrailleCode,
+    build_html::{
+        DomType, GroupType, apply_spacing, build_expression, build_html, spacing_between,
+    },
+    canonicalize_input,
+    convert::{ConvertError, to_sympy, to_typst, to_unicode_math, to_wolfram},
+    define_environment::{EnvDefSpec, EnvProps},
     dom_tree::HtmlDomNode,
+    escape,
+    functions::{self, binrel_class},
     macros::{MacroDefinition, MacroExpansion},
-    parser::parse_node::{AlignSpec, ParseNode},
-    render_to_dom_tree, render_to_string,
+    measure::estimate_width,
+    namespace::{Change, diff},
+    options::Options,
+    parse_tree::{drop_tree, parse_tree},
+    parser::{
+        Parser,
+        parse_node::{AlignSpec, NodeType, ParseNode, ParseNodeRaw, ParseNodeVerb, TensorIndex},
+    },
+    render_to_dom_tree, render_to_output, render_to_string,
+    reverse::{Recovery, ReverseError, from_html, recover_tex},
+    security::allow_protocols,
+    speech::{English, SpeechLocale, SpeechOptions, SpeechTerm, Verbosity, speech_text},
     style::{DISPLAY, SCRIPTSCRIPT},
-    symbols::{Atom, Font, Group, NonAtom},
+    stylesheet_version,
+    sublanguage::{SublanguageContext, SublanguageSpec},
+    symbols::{Atom, Font, Group, NonAtom, SizedGlyph},
     tree::VirtualNode as _,
-    types::{CssProperty, Mode, Token},
+    types::{
+        BehaviorFlags, CssProperty, ErrorLocationProvider as _, Mode, OutputFormat, OverflowMode,
+        ParseError, ParseErrorKind, Token, TraceSink,
+    },
 };
 use setup::*;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::io::Read as _;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[test]
 
@@ -142,6 +170,47 @@ fn a_punct_parser() {
         Ok(())
     });
 }
+
+#[test]
+fn a_decimal_comma_parser() {
+    let decimal_comma_settings = || {
+        let mut settings = strict_settings();
+        settings.decimal_comma = true;
+        settings
+    };
+
+    it("should leave commas alone by default", || {
+        let parsed = get_parsed_strict("3,14")?;
+        assert_let!(ParseNode::Atom(atom) = &parsed[1]);
+        assert_eq!(atom.family, Atom::Punct);
+        Ok(())
+    });
+
+    it("should brace commas between digits", || {
+        let settings = decimal_comma_settings();
+        expect!("3,14").to_parse_like("3{,}14", &settings)?;
+        expect!("1,5+2,75").to_parse_like("1{,}5+2{,}75", &settings)
+    });
+
+    it("should keep punctuation that is not between digits", || {
+        let settings = decimal_comma_settings();
+        for expr in ["1, 2", "x,1", "1,x", "f(1,)"] {
+            let mut parsed = get_parsed(expr, &settings)?;
+            let mut expected = get_parsed_strict(expr)?;
+            strip_positions(&mut parsed);
+            strip_positions(&mut expected);
+            assert_eq!(parsed, expected, "{expr}");
+        }
+        Ok(())
+    });
+
+    it("should render the decimal as a single number", || {
+        let settings = decimal_comma_settings();
+        let markup = build_mathml_with_settings("3,14", &settings)?.to_markup()?;
+        assert!(markup.contains("<mn>3,14</mn>"), "{markup}");
+        Ok(())
+    });
+}
 #[test]
 fn an_open_parser() {
     let expression = "([";
@@ -357,6 +426,151 @@ fn a_parser_with_limit_controls() {
         },
     );
 }
+
+#[test]
+fn a_sideset_parser() {
+    it("should not fail", || {
+        expect!(r"\sideset{_a^b}{_c^d}\sum").to_parse(&strict_settings())?;
+        expect!(r"\sideset{}{'}\sum_{n<k} a_n").to_parse(&strict_settings())?;
+        expect!(r"\sideset{^*}{}\prod\limits_i").to_parse(&strict_settings())
+    });
+
+    it("should collect the pre- and post-scripts", || {
+        let parsed = get_parsed_strict(r"\sideset{_a^b}{_c}\sum")?;
+        assert_let!(ParseNode::Sideset(sideset) = &parsed[0]);
+        assert!(sideset.pre_sub.is_some());
+        assert!(sideset.pre_sup.is_some());
+        assert!(sideset.post_sub.is_some());
+        assert!(sideset.post_sup.is_none());
+        assert_let!(ParseNode::Op(op_node) = sideset.base.as_ref());
+        assert!(!op_node.limits());
+        assert!(sideset.limits);
+        Ok(())
+    });
+
+    it("should take the limits from the decorated operator", || {
+        let parsed = get_parsed_strict(r"\sideset{}{'}\int")?;
+        assert_let!(ParseNode::Sideset(sideset) = &parsed[0]);
+        assert!(!sideset.limits);
+
+        let parsed = get_parsed_strict(r"\sideset{}{'}\int\limits_0^1")?;
+        assert_let!(ParseNode::SupSub(supsub) = &parsed[0]);
+        assert_let!(Some(ParseNode::Sideset(sideset)) = supsub.base.as_deref());
+        assert!(sideset.limits);
+        assert!(sideset.always_handle_sup_sub);
+        Ok(())
+    });
+
+    it("should reject arguments that are not scripts", || {
+        expect!(r"\sideset{x}{}\sum").not_to_parse(&strict_settings())?;
+        expect!(r"\sideset{_a x}{}\sum").not_to_parse(&strict_settings())
+    });
+}
+
+#[test]
+fn a_sideset_builder() {
+    it("should not fail", || {
+        expect!(r"\sideset{_a^b}{_c^d}\sum").to_build(&strict_settings())?;
+        expect!(r"\sideset{_a^b}{_c^d}\sum_{i}^{n}").to_build(&display_settings())
+    });
+
+    it("should render as a single op", || {
+        let built = get_built(r"\sideset{_a^b}{_c^d}\sum", &strict_settings())?;
+        assert_eq!(built.len(), 1);
+        assert!(built[0].classes().contains("mop"));
+        Ok(())
+    });
+
+    it(
+        "should right-align the pre-scripts against the operator",
+        || {
+            let built = get_built(r"\sideset{_{abc}^x}{}\sum", &strict_settings())?;
+            assert_let!(HtmlDomNode::DomSpan(op) = &built[0]);
+            // Phantom scripts for the width, the lapped scripts, the operator
+            assert_eq!(op.children.len(), 3);
+            let markup = op.children[1].to_markup()?;
+            assert_eq!(markup.matches("class=\"llap").count(), 2, "{markup}");
+            assert!(!op.children[0].to_markup()?.contains("llap"));
+            Ok(())
+        },
+    );
+
+    it("should set limits above and below in display mode", || {
+        let built = get_built(r"\sideset{}{'}\sum_{i}^{n}", &display_settings())?;
+        assert!(built[0].classes().contains("op-limits"));
+        let built = get_built(r"\sideset{}{'}\sum_{i}^{n}", &strict_settings())?;
+        assert!(!built[0].classes().contains("op-limits"));
+        Ok(())
+    });
+
+    it("should render mmultiscripts in MathML", || {
+        let markup = build_mathml(r"\sideset{_a}{^b}\sum")?.to_markup()?;
+        assert!(markup.contains(
+            "<mmultiscripts><mo>∑</mo><none></none><mi>b</mi>\
+             <mprescripts></mprescripts><mi>a</mi><none></none></mmultiscripts>"
+        ));
+        Ok(())
+    });
+}
+
+#[test]
+fn a_tensor_parser() {
+    it("should not fail", || {
+        expect!(r"\tensor{R}{^a_{bcd}}").to_parse(&strict_settings())?;
+        expect!(r"\tensor[^a_b]{T}{^c_d}").to_parse(&strict_settings())?;
+        expect!(r"\tensor{T}{ ^a ^b _c }").to_parse(&strict_settings())
+    });
+
+    it("should keep every index in its own slot", || {
+        let parsed = get_parsed_strict(r"\tensor[_x]{T}{^a^b_c}")?;
+        assert_let!(ParseNode::Tensor(tensor) = &parsed[0]);
+        assert_eq!(tensor.pre.len(), 1);
+        assert_let!(TensorIndex::Lower(_) = &tensor.pre[0]);
+        assert_eq!(tensor.post.len(), 3);
+        assert_let!(TensorIndex::Upper(_) = &tensor.post[0]);
+        assert_let!(TensorIndex::Upper(_) = &tensor.post[1]);
+        assert_let!(TensorIndex::Lower(_) = &tensor.post[2]);
+        Ok(())
+    });
+
+    it("should accept an empty index list", || {
+        let parsed = get_parsed_strict(r"\tensor{T}{}")?;
+        assert_let!(ParseNode::Tensor(tensor) = &parsed[0]);
+        assert!(tensor.pre.is_empty());
+        assert!(tensor.post.is_empty());
+        Ok(())
+    });
+
+    it("should reject indices without a script marker", || {
+        expect!(r"\tensor{T}{a}").not_to_parse(&strict_settings())?;
+        expect!(r"\tensor{T}{^a b}").not_to_parse(&strict_settings())?;
+        expect!(r"\tensor{T}{^}").not_to_parse(&strict_settings())
+    });
+}
+
+#[test]
+fn a_tensor_builder() {
+    it("should not fail", || {
+        expect!(r"\tensor{R}{^a_{bcd}}").to_build(&strict_settings())?;
+        expect!(r"\tensor[^a_b]{T}{^c_d}").to_build(&display_settings())
+    });
+
+    it("should render as a single ord", || {
+        let built = get_built(r"\tensor{R}{^a_{bcd}}", &strict_settings())?;
+        assert_eq!(built.len(), 1);
+        assert!(built[0].classes().contains("mord"));
+        Ok(())
+    });
+
+    it("should render mmultiscripts in MathML", || {
+        let markup = build_mathml(r"\tensor[_x]{T}{^a_b}")?.to_markup()?;
+        assert!(markup.contains(
+            "<mmultiscripts><mi>T</mi><none></none><mi>a</mi><mi>b</mi><none></none>\
+             <mprescripts></mprescripts><mi>x</mi><none></none></mmultiscripts>"
+        ));
+        Ok(())
+    });
+}
 #[test]
 fn a_group_parser() {
     it("should not fail", || {
@@ -899,177 +1113,629 @@ fn a_text_parser() {
 }
 
 #[test]
-fn a_texvc_builder() {
-    it("should not fail", || {
-        expect!(r"\lang\N\darr\R\dArr\Z\Darr\alef\rang").to_build(&strict_settings())?;
-        expect!(r"\alefsym\uarr\Alpha\uArr\Beta\Uarr\Chi").to_build(&strict_settings())?;
-        expect!(r"\clubs\diamonds\hearts\spades\cnums\Complex").to_build(&strict_settings())?;
-        expect!(r"\Dagger\empty\harr\Epsilon\hArr\Eta\Harr\exist").to_build(&strict_settings())?;
-        expect!(r"\image\larr\infin\lArr\Iota\Larr\isin\Kappa").to_build(&strict_settings())?;
-        expect!(r"\Mu\lrarr\natnums\lrArr\Nu\Lrarr\Omicron").to_build(&strict_settings())?;
-        expect!(r"\real\rarr\plusmn\rArr\reals\Rarr\Reals\Rho").to_build(&strict_settings())?;
-        expect!(r"\text{\sect}\sdot\sub\sube\supe").to_build(&strict_settings())?;
-        expect!(r"\Tau\thetasym\weierp\Zeta").to_build(&strict_settings())
+fn a_text_wrap_builder() {
+    let render = |expr: &str, text_wrap: bool| {
+        let settings = Settings::builder()
+            .output(OutputFormat::Html)
+            .text_wrap(text_wrap)
+            .build();
+        katex::render_to_string(default_ctx(), expr, &settings)
+    };
+
+    it("should keep text on one line by default", || {
+        let markup = render(r"\text{a b}", false)?;
+        assert!(markup.contains('\u{a0}'), "{markup}");
+        assert!(!markup.contains("white-space"), "{markup}");
+        Ok(())
     });
+
+    it(
+        "should emit breakable spaces when wrapping is enabled",
+        || {
+            let markup = render(r"\text{a b\ c\space d}", true)?;
+            assert!(!markup.contains('\u{a0}'), "{markup}");
+            assert!(markup.contains("white-space:normal"), "{markup}");
+            Ok(())
+        },
+    );
+
+    it(
+        "should keep non-breaking spaces when wrapping is enabled",
+        || {
+            let markup = render(r"\text{a~b\nobreakspace c}", true)?;
+            assert_eq!(markup.matches('\u{a0}').count(), 2, "{markup}");
+            Ok(())
+        },
+    );
 }
 
 #[test]
-fn a_color_parser() {
-    let color_expression = r"\blue{x}";
-    let new_color_expression = r"\redA{x}";
-    let custom_color_expression1 = r"\textcolor{#fA6}{x}";
-    let custom_color_expression2 = r"\textcolor{#fA6fA6}{x}";
-    let custom_color_expression3 = r"\textcolor{fA6fA6}{x}";
-    let bad_custom_color_expression1 = r"\textcolor{bad-color}{x}";
-    let bad_custom_color_expression2 = r"\textcolor{#fA6f1}{x}";
-    let bad_custom_color_expression3 = r"\textcolor{#gA6}{x}";
-    let old_color_expression = r"\color{#fA6}xy";
+fn a_max_width_builder() {
+    let render = |expr: &str, overflow: OverflowMode| {
+        let settings = Settings::builder()
+            .output(OutputFormat::Html)
+            .max_width_em(3.0)
+            .overflow(overflow)
+            .build();
+        katex::render_to_string(default_ctx(), expr, &settings)
+    };
+    let wide = "a+b+c+d+e+f";
 
-    it("should not fail", || {
-        expect!(color_expression).to_parse(&strict_settings())
+    it("should leave formulas that fit untouched", || {
+        let markup = render("x+y", OverflowMode::Scale)?;
+        assert!(!markup.contains("transform"), "{markup}");
+        Ok(())
     });
 
-    it("should build a color node", || {
-        let parsed = get_parsed_strict(color_expression)?;
-        assert_let!(ParseNode::Color(color_node) = &parsed[0]);
-        assert!(!color_node.body.is_empty());
+    it("should scale down formulas that are too wide", || {
+        let markup = render(wide, OverflowMode::Scale)?;
+        assert!(markup.contains("transform:scale(0."), "{markup}");
+        assert!(markup.contains("transform-origin:left bottom"), "{markup}");
+        assert!(markup.contains("margin-right:-"), "{markup}");
         Ok(())
     });
 
-    it("should parse a custom color", || {
-        expect!(custom_color_expression1).to_parse(&strict_settings())?;
-        expect!(custom_color_expression2).to_parse(&strict_settings())?;
-        expect!(custom_color_expression3).to_parse(&strict_settings())
+    it("should let formulas that are too wide scroll", || {
+        let markup = render(wide, OverflowMode::Scroll)?;
+        assert!(markup.contains("max-width:3em"), "{markup}");
+        assert!(markup.contains("overflow-x:auto"), "{markup}");
+        assert!(!markup.contains("transform"), "{markup}");
+        Ok(())
     });
 
-    it("should correctly extract the custom color", || {
-        let parsed1 = get_parsed_strict(custom_color_expression1)?;
-        assert_let!(ParseNode::Color(color_node1) = &parsed1[0]);
-        assert_eq!(color_node1.color, "#fA6");
-
-        let parsed2 = get_parsed_strict(custom_color_expression2)?;
-        assert_let!(ParseNode::Color(color_node2) = &parsed2[0]);
-        assert_eq!(color_node2.color, "#fA6fA6");
-
-        let parsed3 = get_parsed_strict(custom_color_expression3)?;
-        assert_let!(ParseNode::Color(color_node3) = &parsed3[0]);
-        assert_eq!(color_node3.color, "#fA6fA6");
-
+    it("should estimate widths from glyph metrics", || {
+        let width = |expr: &str| -> TestResult<f64> {
+            let tree = katex::parse(default_ctx(), expr, &strict_settings()).map_err(|source| {
+                TestError::Parse {
+                    expression: expr.to_owned(),
+                    location: TestLocation::UNKNOWN,
+                    source,
+                }
+            })?;
+            let html = build_html(default_ctx(), &tree, &Options::default())?;
+            Ok(estimate_width(&html))
+        };
+        assert!(2.0f64.mul_add(-width("x")?, width("xx")?).abs() < 1e-9);
+        assert!(width("x^2")? > width("x")?);
+        assert!(width(r"\dfrac{abc}{d}")? >= width("abc")?);
+        assert!((width(r"\quad")? - 1.0).abs() < 1e-9);
         Ok(())
     });
+}
 
-    it("should not parse a bad custom color", || {
-        expect!(bad_custom_color_expression1).not_to_parse(&strict_settings())?;
-        expect!(bad_custom_color_expression2).not_to_parse(&strict_settings())?;
-        expect!(bad_custom_color_expression3).not_to_parse(&strict_settings())
-    });
+#[test]
+fn a_shared_alignment_builder() {
+    let measure = |expr: &str| {
+        let settings = Settings::builder().output(OutputFormat::Html).build();
+        katex::measure_alignment(default_ctx(), expr, &settings)
+    };
 
-    it("should parse new colors from the branding guide", || {
-        expect!(new_color_expression).to_parse(&strict_settings())
-    });
+    it("should split the width at the first relation", || {
+        let short = measure("x=1")?;
+        let long = measure("x+y+z=10")?;
+        assert!(long.before > short.before);
+        assert!(long.after > short.after);
+        assert!(long.after > 0.0);
 
-    it("should use one-argument \\color by default", || {
-        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{xy}", &strict_settings())
+        let plain = measure("x+y")?;
+        assert!(plain.before > 0.0);
+        assert!(plain.after.abs() < 1e-9);
+        Ok(())
     });
 
-    it("should use one-argument \\color if requested", || {
-        let mut settings = strict_settings();
-        settings.color_is_text_color = false;
-        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{xy}", &settings)
-    });
+    it("should pad formulas to the shared alignment", || {
+        let shared = measure("x=1")?.merge(measure("x+y+z=10")?);
+        let aligned = Settings::builder()
+            .output(OutputFormat::Html)
+            .align_to(shared)
+            .build();
 
-    it("should use two-argument \\color if requested", || {
-        let mut settings = strict_settings();
-        settings.color_is_text_color = true;
-        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{x}y", &settings)
-    });
+        let short = katex::render_to_string(default_ctx(), "x=1", &aligned)?;
+        assert!(short.contains("padding-left:"), "{short}");
+        assert!(short.contains("padding-right:"), "{short}");
 
-    it("should not define \\color in global context", || {
-        let mut settings = strict_settings();
-        settings.color_is_text_color = true;
-        settings.global_group = true;
-        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{x}y", &settings)
+        let long = katex::render_to_string(default_ctx(), "x+y+z=10", &aligned)?;
+        assert!(!long.contains("padding-left:"), "{long}");
+        Ok(())
     });
 }
 
 #[test]
-fn alpha_hex_color_parser() {
-    it("should correctly extract alpha hex colors", || {
-        let alpha_color_expression1 = r"\textcolor{#ff000080}{x}";
-        let alpha_color_expression2 = r"\textcolor{#1234ABCD}{z}";
-        let alpha_color_expression3 = r"\textcolor{#abc8}{w}"; // 4-digit with alpha
-
-        let parsed1 = get_parsed_strict(alpha_color_expression1)?;
-        assert_let!(ParseNode::Color(color_node1) = &parsed1[0]);
-        assert_eq!(color_node1.color, "#ff000080");
-
-        let parsed2 = get_parsed_strict(alpha_color_expression2)?;
-        assert_let!(ParseNode::Color(color_node2) = &parsed2[0]);
-        assert_eq!(color_node2.color, "#1234ABCD");
+fn a_speech_builder() {
+    let speak = |expr: &str, verbosity: Verbosity| -> TestResult<String> {
+        let options = SpeechOptions {
+            verbosity,
+            ..SpeechOptions::default()
+        };
+        let spans = katex::render_to_speech(default_ctx(), expr, &Settings::default(), &options)?;
+        Ok(speech_text(&spans))
+    };
 
-        let parsed3 = get_parsed_strict(alpha_color_expression3)?;
-        assert_let!(ParseNode::Color(color_node3) = &parsed3[0]);
-        assert_eq!(color_node3.color, "#abc8");
+    it("should read structures with MathSpeak markers", || {
+        let verbose = |expr: &str| speak(expr, Verbosity::Verbose);
+        assert_eq!(verbose("x^2+1")?, "x Superscript 2 Baseline plus 1");
+        assert_eq!(
+            verbose("x^{y^2}+1")?,
+            "x Superscript y SuperscriptSuperscript 2 Baseline plus 1"
+        );
+        assert_eq!(verbose(r"\sqrt[3]{x}")?, "RootIndex 3 StartRoot x EndRoot");
+        assert_eq!(verbose(r"\hat{x}")?, "ModifyingAbove x With caret");
+        assert_eq!(
+            verbose("f'(x)")?,
+            "f prime left-parenthesis x right-parenthesis"
+        );
+        assert_eq!(verbose("10.5")?, "10.5");
+        Ok(())
+    });
 
+    it("should shorten markers with the verbosity", || {
+        let expr = r"\frac{a}{b^2}";
+        assert_eq!(
+            speak(expr, Verbosity::Verbose)?,
+            "StartFraction a Over b Superscript 2 Baseline EndFraction"
+        );
+        assert_eq!(
+            speak(expr, Verbosity::Brief)?,
+            "StartFrac a Over b Sup 2 Base EndFrac"
+        );
+        assert_eq!(
+            speak(expr, Verbosity::SuperBrief)?,
+            "Frac a Over b Sup 2 Base EndFrac"
+        );
         Ok(())
     });
 
-    it("should not parse invalid alpha hex colors", || {
-        expect!(r"\textcolor{#ff00008g}{x}").not_to_parse(&strict_settings())?;
-        expect!(r"\textcolor{#ff00008}{x}").not_to_parse(&strict_settings())?;
-        expect!(r"\textcolor{#ff000080f}{x}").not_to_parse(&strict_settings())
+    it("should map spans back to the source", || {
+        let options = SpeechOptions::default();
+        let spans = katex::render_to_speech(default_ctx(), "12+x", &Settings::default(), &options)?;
+        let sources: Vec<_> = spans
+            .iter()
+            .map(|span| (span.text.as_str(), span.node, span.source.clone()))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                ("12", NodeType::TextOrd, Some(0..2)),
+                ("plus", NodeType::Atom, Some(2..3)),
+                ("x", NodeType::MathOrd, Some(3..4)),
+            ]
+        );
+        Ok(())
     });
 
-    it("should build correctly with alpha colors", || {
-        expect!(r"\textcolor{#ff000080}{x}").to_build(&strict_settings())?;
-        expect!(r"\textcolor{#1234ABCD}{z}").to_build(&strict_settings())?;
-        expect!(r"\textcolor{#abc8}{w}").to_build(&strict_settings())
+    it("should take words from the locale", || {
+        struct Spanish;
+        impl SpeechLocale for Spanish {
+            fn term(&self, term: SpeechTerm, verbosity: Verbosity) -> Cow<'static, str> {
+                English.term(term, verbosity)
+            }
+            fn symbol(&self, text: &str) -> Option<Cow<'static, str>> {
+                match text {
+                    "+" => Some(Cow::Borrowed("más")),
+                    _ => English.symbol(text),
+                }
+            }
+            fn ordinal(&self, n: usize) -> String {
+                format!("{n}.º")
+            }
+        }
+        let options = SpeechOptions {
+            verbosity: Verbosity::Verbose,
+            locale: Arc::new(Spanish),
+        };
+        let spans = katex::render_to_speech(default_ctx(), "a+b", &Settings::default(), &options)?;
+        assert_eq!(speech_text(&spans), "a más b");
+        Ok(())
     });
 }
 
 #[test]
-fn a_tie_parser() {
-    let math_tie = "a~b";
-    let text_tie = r"\text{a~ b}";
-
-    it("should parse ties in math mode", || {
-        expect!(math_tie).to_parse(&strict_settings())
-    });
-
-    it("should parse ties in text mode", || {
-        expect!(text_tie).to_parse(&strict_settings())
-    });
-
-    it("should produce spacing in math mode", || {
-        let parsed = get_parsed_strict(math_tie)?;
-        assert_let!(ParseNode::Spacing(_) = &parsed[1]);
-        Ok(())
-    });
+fn a_braille_builder() {
+    let braille = |expr: &str, code: BrailleCode| {
+        katex::render_to_braille(default_ctx(), expr, &Settings::default(), code)
+    };
 
-    it("should produce spacing in text mode", || {
-        let parsed = get_parsed_strict(text_tie)?;
-        assert_let!(ParseNode::Text(text_node) = &parsed[0]);
-        assert_let!(ParseNode::Spacing(_) = &text_node.body[1]);
+    it("should write Nemeth Code", || {
+        let nemeth = |expr: &str| braille(expr, BrailleCode::Nemeth);
+        // Numbers use the lower part of the cell, and the numeric indicator
+        // only appears after a space
+        assert_eq!(nemeth("x^2 = 1")?, "⠭⠘⠆⠀⠨⠅⠀⠼⠂");
+        assert_eq!(nemeth("x_1 + y")?, "⠭⠂⠬⠽");
+        assert_eq!(nemeth("x^{y^2}+1")?, "⠭⠘⠽⠘⠘⠆⠐⠬⠂");
+        assert_eq!(nemeth(r"\frac{1}{2}")?, "⠹⠂⠌⠆⠼");
+        assert_eq!(nemeth(r"\frac{1}{\frac{a}{b}}")?, "⠠⠹⠂⠠⠌⠹⠁⠌⠃⠼⠠⠼");
+        assert_eq!(nemeth(r"\sqrt[3]{x}")?, "⠣⠒⠜⠭⠻");
+        assert_eq!(nemeth(r"\alpha + \Sigma")?, "⠨⠁⠬⠨⠠⠎");
         Ok(())
     });
 
-    it("should not contract with spaces in text mode", || {
-        let parsed = get_parsed_strict(text_tie)?;
-        assert_let!(ParseNode::Text(text_node) = &parsed[0]);
-        assert_let!(ParseNode::Spacing(_) = &text_node.body[2]);
+    it("should write Unified English Braille", || {
+        let ueb = |expr: &str| braille(expr, BrailleCode::Ueb);
+        assert_eq!(ueb("x^2 = 1")?, "⠭⠔⠼⠃⠐⠶⠼⠁");
+        assert_eq!(ueb("x^{y+1}")?, "⠭⠔⠣⠽⠐⠖⠼⠁⠜");
+        assert_eq!(ueb(r"\frac{3}{4}")?, "⠼⠉⠌⠙");
+        assert_eq!(ueb(r"\frac{a}{b}")?, "⠷⠁⠨⠌⠃⠾");
+        assert_eq!(ueb("3a")?, "⠼⠉⠰⠁");
+        assert_eq!(ueb(r"\sqrt{x}")?, "⠩⠭⠬");
         Ok(())
     });
 }
 
 #[test]
-fn a_delimiter_sizing_parser() {
-    let normal_delim = r"\bigl |";
-    let not_delim = r"\bigl x";
-    let big_delim = r"\Biggr \langle";
+fn a_convert_builder() {
+    let tree = |expr: &str| parse_tree(default_ctx(), expr, &Settings::default());
 
-    it("should parse normal delimiters", || {
-        expect!(normal_delim).to_parse(&strict_settings())?;
-        expect!(big_delim).to_parse(&strict_settings())
+    it("should write SymPy expressions", || {
+        let sympy = |expr: &str| -> Result<Result<String, ConvertError>, ParseError> {
+            Ok(to_sympy(default_ctx(), &tree(expr)?))
+        };
+        assert_eq!(
+            sympy("2x^2-(a-b)=y")?,
+            Ok("Eq(2*x**2 - (a - b), y)".to_owned())
+        );
+        assert_eq!(
+            sympy(r"\frac{\sin x}{\sqrt{x+1}}")?,
+            Ok("sin(x)/sqrt(x + 1)".to_owned())
+        );
+        assert_eq!(
+            sympy(r"\sum_{k=1}^{n} k^2")?,
+            Ok("Sum(k**2, (k, 1, n))".to_owned())
+        );
+        assert_eq!(
+            sympy(r"\int_0^\infty e^{-t}\,dt")?,
+            Ok("Integral(e**(-t), (t, 0, oo))".to_owned())
+        );
+        assert_eq!(
+            sympy(r"\log_2(x+1)^3")?,
+            Ok("log((x + 1)**3, 2)".to_owned())
+        );
+        // Digits before a script belong to the number
+        assert_eq!(sympy("10^{-3}")?, Ok("10**(-3)".to_owned()));
+        assert_eq!(sympy("1.5^2")?, Ok("1.5**2".to_owned()));
+        Ok(())
+    });
+
+    it("should write Wolfram Language expressions", || {
+        let wolfram = |expr: &str| -> Result<Result<String, ConvertError>, ParseError> {
+            Ok(to_wolfram(default_ctx(), &tree(expr)?))
+        };
+        assert_eq!(
+            wolfram("2x^2-(a-b)=y")?,
+            Ok("2*x^2 - (a - b) == y".to_owned())
+        );
+        assert_eq!(
+            wolfram(r"\sqrt[3]{\alpha_1}")?,
+            Ok("Power[Subscript[alpha, 1], 1/3]".to_owned())
+        );
+        assert_eq!(
+            wolfram(r"\lim_{x\to 0}\frac{\sin x}{x}")?,
+            Ok("Limit[Sin[x]/x, x -> 0]".to_owned())
+        );
+        assert_eq!(wolfram("10^{-3}")?, Ok("10^(-3)".to_owned()));
+        assert_eq!(wolfram("1.5^2")?, Ok("1.5^2".to_owned()));
+        Ok(())
+    });
+
+    it("should write Typst markup", || {
+        let typst = |expr: &str| -> Result<String, ParseError> {
+            Ok(to_typst(default_ctx(), &tree(expr)?))
+        };
+        assert_eq!(
+            typst(r"x^2+y_1^{n+1} \leq \frac{\alpha}{\sqrt[3]{b}}")?,
+            "x^2 + y_1^(n + 1) <= frac(alpha, root(3, b))"
+        );
+        assert_eq!(
+            typst(r"\int_0^\infty e^{-x}\,dx")?,
+            "integral_0^infinity e^(- x) thin d x"
+        );
+        assert_eq!(
+            typst(r"\left( a, b \right) \cdot \mathbb{R}^{12}")?,
+            r"lr(( a \, b )) dot.op bb(R)^12"
+        );
+        assert_eq!(
+            typst(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}")?,
+            r#"mat(delim: "(", a, b; c, d)"#
+        );
+        assert_eq!(
+            typst(r"|x|=\begin{cases}x&x\ge0\\-x&\text{else}\end{cases}")?,
+            r#"| x | = cases(x & x >= 0, - x & "else")"#
+        );
+        assert_eq!(typst("x^{\\prime\\prime} + y\u{2032}")?, "x'' + y'");
+        assert_eq!(typst("10^{-3}")?, "10^(- 3)");
+        assert_eq!(typst("1.5^2")?, "1.5^2");
+        assert_eq!(typst(r"\overbrace{a+b}^{n} f'")?, "overbrace(a + b, n) f'");
+        assert_eq!(typst(r"a/b \verb|c|")?, r"a \/ b /* \verb|c| */");
+        Ok(())
+    });
+
+    it("should write UnicodeMath", || {
+        let unicode_math = |expr: &str| -> Result<String, ParseError> {
+            Ok(to_unicode_math(default_ctx(), &tree(expr)?))
+        };
+        // A space ends the operand of a script or fraction
+        assert_eq!(unicode_math(r"x^2 y+\frac{a+b}{c}")?, "x^2 y+(a+b)/c");
+        assert_eq!(
+            unicode_math(r"\sqrt{x+1}\leq\sqrt[3]{\alpha}")?,
+            "√(x+1) ≤∛α"
+        );
+        assert_eq!(
+            unicode_math(r"\lim_{x\to 0}\frac{\sin x}{x}")?,
+            "lim┬(x→0)\u{2061} (sin\u{2061}x)/x"
+        );
+        assert_eq!(unicode_math(r"\left(\frac{a}{b}\right)^2")?, "(a/b)^2");
+        assert_eq!(
+            unicode_math(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}")?,
+            "(■(a&b@c&d))"
+        );
+        assert_eq!(
+            unicode_math(r"\begin{cases}1&x>0\\0&\text{else}\end{cases}")?,
+            "{■(1&x>0@0&\"else\")┤"
+        );
+        assert_eq!(unicode_math(r"\mathbb{R}^n\mathcal{L}")?, "ℝ^n ℒ");
+        assert_eq!(unicode_math("f'(x) + g\u{2032}")?, "f′(x)+g′");
+        assert_eq!(unicode_math(r"a/b \verb|c|")?, "a\\/b\"\\verb|c|\"");
+        Ok(())
+    });
+
+    it("should refuse what it cannot translate", || {
+        let sympy = |expr: &str| -> Result<Result<String, ConvertError>, ParseError> {
+            Ok(to_sympy(default_ctx(), &tree(expr)?))
+        };
+        assert_eq!(
+            sympy(r"x+\overbrace{y}")?,
+            Err(ConvertError::Unsupported {
+                what: r"\overbrace{y}".to_owned()
+            })
+        );
+        assert_eq!(
+            sympy(r"\sum_k k")?,
+            Err(ConvertError::Incomplete {
+                operator: "sum",
+                missing: "a bound variable"
+            })
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn a_reverse_builder() {
+    let html_only = || Settings::builder().output(OutputFormat::Html).build();
+
+    it("should read the TeX annotation when present", || {
+        let settings = Settings::builder().display_mode(true).build();
+        let html = render_to_string(default_ctx(), r"\frac{a}{b} < c \& d", &settings)?;
+        let recovered = recover_tex(default_ctx(), &html).expect("annotation");
+        assert_eq!(recovered.tex, r"\frac{a}{b} < c \& d");
+        assert!(recovered.display_mode);
+        assert_eq!(recovered.recovery, Recovery::Annotation);
+        Ok(())
+    });
+
+    it("should rebuild the source from the HTML structure", || {
+        let rebuild = |expr: &str| -> Result<String, ParseError> {
+            let html = render_to_string(default_ctx(), expr, &html_only())?;
+            let recovered = recover_tex(default_ctx(), &html).expect("structure");
+            assert_eq!(recovered.recovery, Recovery::Structure);
+            Ok(recovered.tex)
+        };
+        assert_eq!(rebuild(r"\frac{a}{b}+c")?, r"\frac{a}{b}+c");
+        assert_eq!(rebuild("x_i^2")?, "x_{i}^{2}");
+        assert_eq!(rebuild(r"\sqrt[3]{x+1}")?, r"\sqrt[3]{x+1}");
+        assert_eq!(rebuild(r"\binom{n}{k}")?, r"\binom{n}{k}");
+        assert_eq!(rebuild(r"\sin^2 x\,\hat{y}")?, r"\sin^{2}x\,\hat{y}");
+        assert_eq!(rebuild(r"\operatorname{foo} x")?, r"\operatorname{foo}x");
+        assert_eq!(rebuild(r"\left(\alpha\right)")?, r"\left(α\right)");
+        assert_eq!(
+            rebuild(r"\mathbb{R}\text{ if }x")?,
+            r"\mathbb{R}\text{ if }x"
+        );
+        assert_eq!(
+            rebuild(r"\begin{pmatrix}a&b\\c&d\end{pmatrix}")?,
+            r"\left(\begin{matrix}a & b \\ c & d\end{matrix}\right)"
+        );
+        Ok(())
+    });
+
+    it(
+        "should parse recovered display formulas with limits",
+        || {
+            let settings = Settings::builder()
+                .display_mode(true)
+                .output(OutputFormat::Html)
+                .build();
+            let html = render_to_string(default_ctx(), r"\sum_{i=1}^n i", &settings)?;
+            let recovered = recover_tex(default_ctx(), &html).expect("structure");
+            assert_eq!(recovered.tex, r"∑\limits_{i=1}^{n}i");
+            assert!(recovered.display_mode);
+            let tree = from_html(default_ctx(), &html).expect("tree");
+            assert_eq!(tree.len(), 2);
+            assert!(matches!(tree[0], ParseNode::SupSub(_)));
+            Ok(())
+        },
+    );
+
+    it("should reject markup without KaTeX output", || {
+        assert!(matches!(
+            from_html(default_ctx(), "<p>no math</p>"),
+            Err(ReverseError::NotKatex)
+        ));
+        Ok(())
+    });
+}
+
+#[test]
+fn a_texvc_builder() {
+    it("should not fail", || {
+        expect!(r"\lang\N\darr\R\dArr\Z\Darr\alef\rang").to_build(&strict_settings())?;
+        expect!(r"\alefsym\uarr\Alpha\uArr\Beta\Uarr\Chi").to_build(&strict_settings())?;
+        expect!(r"\clubs\diamonds\hearts\spades\cnums\Complex").to_build(&strict_settings())?;
+        expect!(r"\Dagger\empty\harr\Epsilon\hArr\Eta\Harr\exist").to_build(&strict_settings())?;
+        expect!(r"\image\larr\infin\lArr\Iota\Larr\isin\Kappa").to_build(&strict_settings())?;
+        expect!(r"\Mu\lrarr\natnums\lrArr\Nu\Lrarr\Omicron").to_build(&strict_settings())?;
+        expect!(r"\real\rarr\plusmn\rArr\reals\Rarr\Reals\Rho").to_build(&strict_settings())?;
+        expect!(r"\text{\sect}\sdot\sub\sube\supe").to_build(&strict_settings())?;
+        expect!(r"\Tau\thetasym\weierp\Zeta").to_build(&strict_settings())
+    });
+}
+
+#[test]
+fn a_color_parser() {
+    let color_expression = r"\blue{x}";
+    let new_color_expression = r"\redA{x}";
+    let custom_color_expression1 = r"\textcolor{#fA6}{x}";
+    let custom_color_expression2 = r"\textcolor{#fA6fA6}{x}";
+    let custom_color_expression3 = r"\textcolor{fA6fA6}{x}";
+    let bad_custom_color_expression1 = r"\textcolor{bad-color}{x}";
+    let bad_custom_color_expression2 = r"\textcolor{#fA6f1}{x}";
+    let bad_custom_color_expression3 = r"\textcolor{#gA6}{x}";
+    let old_color_expression = r"\color{#fA6}xy";
+
+    it("should not fail", || {
+        expect!(color_expression).to_parse(&strict_settings())
+    });
+
+    it("should build a color node", || {
+        let parsed = get_parsed_strict(color_expression)?;
+        assert_let!(ParseNode::Color(color_node) = &parsed[0]);
+        assert!(!color_node.body.is_empty());
+        Ok(())
+    });
+
+    it("should parse a custom color", || {
+        expect!(custom_color_expression1).to_parse(&strict_settings())?;
+        expect!(custom_color_expression2).to_parse(&strict_settings())?;
+        expect!(custom_color_expression3).to_parse(&strict_settings())
+    });
+
+    it("should correctly extract the custom color", || {
+        let parsed1 = get_parsed_strict(custom_color_expression1)?;
+        assert_let!(ParseNode::Color(color_node1) = &parsed1[0]);
+        assert_eq!(color_node1.color, "#fA6");
+
+        let parsed2 = get_parsed_strict(custom_color_expression2)?;
+        assert_let!(ParseNode::Color(color_node2) = &parsed2[0]);
+        assert_eq!(color_node2.color, "#fA6fA6");
+
+        let parsed3 = get_parsed_strict(custom_color_expression3)?;
+        assert_let!(ParseNode::Color(color_node3) = &parsed3[0]);
+        assert_eq!(color_node3.color, "#fA6fA6");
+
+        Ok(())
+    });
+
+    it("should not parse a bad custom color", || {
+        expect!(bad_custom_color_expression1).not_to_parse(&strict_settings())?;
+        expect!(bad_custom_color_expression2).not_to_parse(&strict_settings())?;
+        expect!(bad_custom_color_expression3).not_to_parse(&strict_settings())
+    });
+
+    it("should parse new colors from the branding guide", || {
+        expect!(new_color_expression).to_parse(&strict_settings())
+    });
+
+    it("should use one-argument \\color by default", || {
+        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{xy}", &strict_settings())
+    });
+
+    it("should use one-argument \\color if requested", || {
+        let mut settings = strict_settings();
+        settings.color_is_text_color = false;
+        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{xy}", &settings)
+    });
+
+    it("should use two-argument \\color if requested", || {
+        let mut settings = strict_settings();
+        settings.color_is_text_color = true;
+        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{x}y", &settings)
+    });
+
+    it("should not define \\color in global context", || {
+        let mut settings = strict_settings();
+        settings.color_is_text_color = true;
+        settings.global_group = true;
+        expect!(old_color_expression).to_parse_like(r"\textcolor{#fA6}{x}y", &settings)
+    });
+}
+
+#[test]
+fn alpha_hex_color_parser() {
+    it("should correctly extract alpha hex colors", || {
+        let alpha_color_expression1 = r"\textcolor{#ff000080}{x}";
+        let alpha_color_expression2 = r"\textcolor{#1234ABCD}{z}";
+        let alpha_color_expression3 = r"\textcolor{#abc8}{w}"; // 4-digit with alpha
+
+        let parsed1 = get_parsed_strict(alpha_color_expression1)?;
+        assert_let!(ParseNode::Color(color_node1) = &parsed1[0]);
+        assert_eq!(color_node1.color, "#ff000080");
+
+        let parsed2 = get_parsed_strict(alpha_color_expression2)?;
+        assert_let!(ParseNode::Color(color_node2) = &parsed2[0]);
+        assert_eq!(color_node2.color, "#1234ABCD");
+
+        let parsed3 = get_parsed_strict(alpha_color_expression3)?;
+        assert_let!(ParseNode::Color(color_node3) = &parsed3[0]);
+        assert_eq!(color_node3.color, "#abc8");
+
+        Ok(())
+    });
+
+    it("should not parse invalid alpha hex colors", || {
+        expect!(r"\textcolor{#ff00008g}{x}").not_to_parse(&strict_settings())?;
+        expect!(r"\textcolor{#ff00008}{x}").not_to_parse(&strict_settings())?;
+        expect!(r"\textcolor{#ff000080f}{x}").not_to_parse(&strict_settings())
+    });
+
+    it("should build correctly with alpha colors", || {
+        expect!(r"\textcolor{#ff000080}{x}").to_build(&strict_settings())?;
+        expect!(r"\textcolor{#1234ABCD}{z}").to_build(&strict_settings())?;
+        expect!(r"\textcolor{#abc8}{w}").to_build(&strict_settings())
+    });
+}
+
+#[test]
+fn a_tie_parser() {
+    let math_tie = "a~b";
+    let text_tie = r"\text{a~ b}";
+
+    it("should parse ties in math mode", || {
+        expect!(math_tie).to_parse(&strict_settings())
+    });
+
+    it("should parse ties in text mode", || {
+        expect!(text_tie).to_parse(&strict_settings())
+    });
+
+    it("should produce spacing in math mode", || {
+        let parsed = get_parsed_strict(math_tie)?;
+        assert_let!(ParseNode::Spacing(_) = &parsed[1]);
+        Ok(())
+    });
+
+    it("should produce spacing in text mode", || {
+        let parsed = get_parsed_strict(text_tie)?;
+        assert_let!(ParseNode::Text(text_node) = &parsed[0]);
+        assert_let!(ParseNode::Spacing(_) = &text_node.body[1]);
+        Ok(())
+    });
+
+    it("should not contract with spaces in text mode", || {
+        let parsed = get_parsed_strict(text_tie)?;
+        assert_let!(ParseNode::Text(text_node) = &parsed[0]);
+        assert_let!(ParseNode::Spacing(_) = &text_node.body[2]);
+        Ok(())
+    });
+}
+
+#[test]
+fn a_delimiter_sizing_parser() {
+    let normal_delim = r"\bigl |";
+    let not_delim = r"\bigl x";
+    let big_delim = r"\Biggr \langle";
+
+    it("should parse normal delimiters", || {
+        expect!(normal_delim).to_parse(&strict_settings())?;
+        expect!(big_delim).to_parse(&strict_settings())
     });
 
     it("should not parse not-delimiters", || {
@@ -1529,6 +2195,47 @@ fn a_begin_end_parser() {
         Ok(())
     });
 
+    it("should grab \\arraystretch set with \\renewcommand", || {
+        let parsed = get_parsed_strict(
+            r"\renewcommand{\arraystretch}{1.5}\begin{pmatrix}a&b\\c&d\end{pmatrix}",
+        )?;
+        assert_let!(ParseNode::LeftRight(left_right) = &parsed[0]);
+        assert_let!(ParseNode::Array(array) = &left_right.body[0]);
+        assert_eq!(array.arraystretch, 1.5);
+
+        let parsed = get_parsed_strict(r"\begin{matrix}a&b\\c&d\end{matrix}")?;
+        assert_let!(ParseNode::Array(array) = &parsed[0]);
+        assert_eq!(array.arraystretch, 1.0);
+        Ok(())
+    });
+
+    it(
+        "should allow row gaps in every alignment environment",
+        || {
+            let settings = Settings::builder()
+                .display_mode(true)
+                .strict(StrictSetting::Mode(StrictMode::Error))
+                .build();
+            for env in [
+                "matrix",
+                "pmatrix*",
+                "aligned",
+                "align",
+                "align*",
+                "gathered",
+                "gather",
+                "split",
+                "cases",
+                "rcases",
+                "smallmatrix",
+            ] {
+                let expr = format!(r"\begin{{{env}}}a\\[0.5em]b\\ [c]\end{{{env}}}");
+                expect!(&expr).to_build(&settings)?;
+            }
+            Ok(())
+        },
+    );
+
     it(
         "should allow an optional argument in {matrix*} and company.",
         || {
@@ -1551,6 +2258,43 @@ fn a_begin_end_parser() {
         },
     );
 
+    it("should apply the optional argument to every column", || {
+        for (expr, expected) in [
+            (r"\begin{pmatrix*}[r] a & -1 \\ -1 & d \end{pmatrix*}", "r"),
+            (
+                r"\begin{bmatrix*}[ l ] a & -1 \\ -1 & d \end{bmatrix*}",
+                "l",
+            ),
+            (r"\begin{Vmatrix*} a & -1 \\ -1 & d \end{Vmatrix*}", "c"),
+        ] {
+            let parsed = get_parsed_strict(expr)?;
+            assert_let!(ParseNode::LeftRight(left_right) = &parsed[0]);
+            assert_let!(ParseNode::Array(array) = &left_right.body[0]);
+            let cols = array.cols.as_deref().unwrap_or_default();
+            assert_eq!(cols.len(), 2);
+            for col in cols {
+                assert_let!(AlignSpec::Align { align, .. } = col);
+                assert_eq!(align, expected);
+            }
+        }
+        expect!(r"\begin{pmatrix*}[x] a \end{pmatrix*}").not_to_parse(&strict_settings())
+    });
+
+    it(
+        "should not take an optional argument in unstarred matrices",
+        || {
+            let parsed = get_parsed_strict(r"\begin{pmatrix}[r] a \end{pmatrix}")?;
+            assert_let!(ParseNode::LeftRight(left_right) = &parsed[0]);
+            assert_let!(ParseNode::Array(array) = &left_right.body[0]);
+            assert_let!(
+                AlignSpec::Align { align, .. } = &array.cols.as_deref().unwrap_or_default()[0]
+            );
+            assert_eq!(align, "c");
+            assert_eq!(array.body[0].len(), 1);
+            Ok(())
+        },
+    );
+
     it("should allow blank columns", || {
         let parsed = get_parsed_strict(r"\begin{matrix*}[r] a \\ -1 & d \end{matrix*}")?;
         assert_let!(ParseNode::Array(array) = &parsed[0]);
@@ -1716,6 +2460,16 @@ fn a_tex_compliant_parser() {
         expect!("x''_2").to_parse(&strict_settings())
     });
 
+    it("should parse Unicode primes like apostrophes", || {
+        expect!("f\u{2032}").to_parse_like("f'", &strict_settings())?;
+        expect!("f\u{2033}").to_parse_like("f''", &strict_settings())?;
+        expect!("f\u{2034}").to_parse_like("f'''", &strict_settings())?;
+        expect!("f\u{2057}").to_parse_like("f''''", &strict_settings())?;
+        expect!("f'\u{2033}^2").to_parse_like("f'''^2", &strict_settings())?;
+        expect!("x_2\u{2032}").to_parse_like("x_2'", &strict_settings())?;
+        expect!("\u{2032}").to_parse_like("'", &strict_settings())
+    });
+
     it(
         "should fail when sup/subscripts are interspersed with arguments",
         || {
@@ -2005,6 +2759,66 @@ fn a_raise_parser() {
     });
 }
 
+#[test]
+fn a_canonicalized_input() {
+    it("should drop comments and insignificant whitespace", || {
+        assert_eq!(canonicalize_input("  x   +\n\ty  "), "x + y ");
+        assert_eq!(canonicalize_input("a %comment\n  b"), "a %\n b");
+        assert_eq!(canonicalize_input("a%comment\nb"), "ab");
+        assert_eq!(canonicalize_input(r"\alpha   \beta   ,"), r"\alpha\beta,");
+        Ok(())
+    });
+
+    it(
+        "should keep control words apart from following letters",
+        || {
+            assert_eq!(canonicalize_input("\\alpha %\nx"), r"\alpha x");
+            assert_eq!(canonicalize_input("\\alpha%\n@"), r"\alpha @");
+            assert_eq!(canonicalize_input(r"\, x"), r"\, x");
+            Ok(())
+        },
+    );
+
+    it("should copy verbatim and URL arguments", || {
+        assert_eq!(canonicalize_input(r"\verb|a  %b|"), r"\verb|a  %b|");
+        let url = "\\href{http://a.org/%20{x}%}{a %c\n}";
+        assert_eq!(canonicalize_input(url), r"\href{http://a.org/%20{x}%}{a }");
+        Ok(())
+    });
+
+    it("should leave unlexable input alone", || {
+        assert!(matches!(canonicalize_input("x%y"), Cow::Borrowed("x%y")));
+        assert!(matches!(canonicalize_input("x + y"), Cow::Borrowed(_)));
+        Ok(())
+    });
+
+    it("should render the same as the original input", || {
+        let settings = Settings::builder().output(OutputFormat::Html).build();
+        let render = |expr: &str| -> TestResult<String> {
+            let markup = render_to_string(default_ctx(), expr, &settings)?;
+            Ok(normalize_style_attributes(&normalize_html_attributes(
+                &markup,
+            )))
+        };
+        for expr in [
+            "\\frac {a} {b}  % fraction\n + \\sqrt [3]{x}",
+            "\\text{a   b %c\n c}\\mathrm  {d}",
+            "\\text{x \\ y}\\quad %\n\\text{z}",
+            "\\def\\f#1 {[#1]}  \\f x y",
+            "\\begin{matrix} a & b \\\\\n  c & d \\end{matrix}",
+        ] {
+            let canonical = canonicalize_input(expr);
+            assert_eq!(canonicalize_input(&canonical), canonical);
+            assert_eq!(
+                render(expr)?,
+                render(&canonical)?,
+                "{expr:?} canonicalized to {canonical:?}"
+            );
+        }
+        Ok(())
+    });
+}
+
 #[test]
 fn a_comment_parser() {
     it("should parse comments at the end of a line", || {
@@ -2615,6 +3429,73 @@ fn a_bin_builder() {
     });
 }
 
+#[test]
+fn a_spacing_pass() {
+    it("should look up glue between atom classes", || {
+        let thick = spacing_between(DomType::Mord, DomType::Mrel, false);
+        assert_eq!(thick.map(|space| space.number), Some(5.0));
+        assert!(spacing_between(DomType::Mord, DomType::Mrel, true).is_none());
+        assert!(spacing_between(DomType::Mopen, DomType::Mord, false).is_none());
+        Ok(())
+    });
+
+    it(
+        "should space prebuilt fragments like a single expression",
+        || {
+            let ctx = default_ctx();
+            let options = Options::default();
+            let mut groups = Vec::new();
+            for fragment in ["x", "=", "-y", r"\blue{+z}"] {
+                let tree = parse_tree(ctx, fragment, &strict_settings())?;
+                groups.extend(build_expression(
+                    ctx,
+                    &tree,
+                    &options,
+                    GroupType::False,
+                    (None, None),
+                )?);
+            }
+            apply_spacing(
+                ctx,
+                &mut groups,
+                &options,
+                &options,
+                GroupType::True,
+                (None, None),
+            )?;
+
+            let tree = parse_tree(ctx, r"x=-y\blue{+z}", &strict_settings())?;
+            let whole = build_expression(ctx, &tree, &options, GroupType::True, (None, None))?;
+            let markup = |nodes: &[HtmlDomNode]| {
+                nodes
+                    .iter()
+                    .map(HtmlDomNode::to_markup)
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            assert_eq!(markup(&groups)?, markup(&whole)?);
+            Ok(())
+        },
+    );
+
+    it("should leave partial groups to the enclosing list", || {
+        let ctx = default_ctx();
+        let options = Options::default();
+        let tree = parse_tree(ctx, "x=y", &strict_settings())?;
+        let mut groups = build_expression(ctx, &tree, &options, GroupType::False, (None, None))?;
+        let before = groups.len();
+        apply_spacing(
+            ctx,
+            &mut groups,
+            &options,
+            &options,
+            GroupType::False,
+            (None, None),
+        )?;
+        assert_eq!(groups.len(), before);
+        Ok(())
+    });
+}
+
 #[test]
 fn a_phantom_and_smash_builder() {
     it("should both build a mord", || {
@@ -3121,6 +4002,103 @@ fn phase() {
     });
 }
 
+#[test]
+fn longdiv() {
+    it("should fail in text mode", || {
+        expect!(r"\text{\longdiv{1234}{5}}").not_to_parse(&strict_settings())
+    });
+
+    it("should put the divisor before the bracket", || {
+        let parse = get_parsed(r"\longdiv{1234}{5}", &strict_settings())?;
+        let ParseNode::OrdGroup(group) = &parse[0] else {
+            panic!("expected an ordgroup, got {:?}", parse[0]);
+        };
+        assert_eq!(group.body.len(), 2);
+        let ParseNode::Enclose(enclose) = &group.body[1] else {
+            panic!("expected an enclose, got {:?}", group.body[1]);
+        };
+        assert_eq!(enclose.label, r"\longdiv");
+        Ok(())
+    });
+
+    it("should build a bracket taller than the dividend", || {
+        let markup = render_to_string_strict(r"\longdiv{1234}{5}")?;
+        assert!(markup.contains("hide-tail"));
+        expect!(r"\longdiv{\frac{1}{2}}{3}").to_build(&strict_settings())
+    });
+}
+
+#[test]
+fn diagbox() {
+    it("should parse its labels in order", || {
+        let parse = get_parsed(r"\diagbox{x}{y}", &strict_settings())?;
+        let ParseNode::Diagbox(diagbox) = &parse[0] else {
+            panic!("expected a diagbox, got {:?}", parse[0]);
+        };
+        assert!(matches!(&*diagbox.lower, ParseNode::OrdGroup(group)
+            if matches!(&group.body[..], [ParseNode::MathOrd(ord)] if ord.text == "x")));
+        assert!(matches!(&*diagbox.upper, ParseNode::OrdGroup(group)
+            if matches!(&group.body[..], [ParseNode::MathOrd(ord)] if ord.text == "y")));
+        Ok(())
+    });
+
+    it("should fail in text mode", || {
+        expect!(r"\text{\diagbox{x}{y}}").not_to_parse(&strict_settings())
+    });
+
+    it("should build on its own", || {
+        expect!(r"\diagbox{x}{y}").to_build(&strict_settings())?;
+        expect!(r"\diagbox{\frac{1}{2}}{\text{col}}").to_build(&strict_settings())
+    });
+
+    it("should fill its cell in an array", || {
+        let markup = render_to_string_strict(
+            r"\begin{array}{c|cc}\diagbox{x}{y}&0&1\\\hline 0&a&b\end{array}",
+        )?;
+        assert!(markup.contains("width:100%"));
+        assert!(markup.contains("<line"));
+
+        let alone = render_to_string_strict(r"\diagbox{x}{y}")?;
+        assert!(!alone.contains("width:100%"));
+        Ok(())
+    });
+}
+
+#[test]
+fn nuclide() {
+    it("should parse the optional numbers", || {
+        let parse = get_parsed(r"\nuclide[14][6]{C}", &strict_settings())?;
+        let ParseNode::Nuclide(nuclide) = &parse[0] else {
+            panic!("expected a nuclide, got {:?}", parse[0]);
+        };
+        assert!(nuclide.mass.is_some());
+        assert!(nuclide.atomic.is_some());
+
+        let parse = get_parsed(r"\nuclide[235]{U}", &strict_settings())?;
+        let ParseNode::Nuclide(nuclide) = &parse[0] else {
+            panic!("expected a nuclide, got {:?}", parse[0]);
+        };
+        assert!(nuclide.mass.is_some());
+        assert!(nuclide.atomic.is_none());
+        Ok(())
+    });
+
+    it("should build with any combination of numbers", || {
+        expect!(r"\nuclide[14][6]{C}").to_build(&strict_settings())?;
+        expect!(r"\nuclide[235]{U}").to_build(&strict_settings())?;
+        expect!(r"\nuclide[][1]{H}").to_build(&strict_settings())?;
+        expect!(r"\nuclide{C}").to_build(&strict_settings())?;
+        expect!(r"\nuclide[4][2]{He}^{2+}").to_build(&strict_settings())
+    });
+
+    it("should align the numbers to the right", || {
+        let markup = render_to_string_strict(r"\nuclide[235][92]{U}")?;
+        assert_eq!(markup.matches("text-align:right").count(), 2);
+        assert!(markup.contains(r#"<span class="mord mathrm">U</span>"#));
+        Ok(())
+    });
+}
+
 #[test]
 fn a_phantom_parser() {
     it("should not fail", || {
@@ -3775,6 +4753,153 @@ fn href_and_url_commands() {
     });
 }
 
+#[test]
+fn a_url_validation() {
+    fn rendered_link(expression: &str, settings: &Settings) -> Result<bool, ParseError> {
+        let html = render_to_string(default_ctx(), expression, settings)?;
+        Ok(html.contains("<a href") || html.contains("<img"))
+    }
+
+    it("should reject script protocols even when trusted", || {
+        for expression in [
+            r"\href{javascript:alert(1)}{x}",
+            r"\url{ VBScript:msgbox}",
+            r"\href{java&colon;script:alert(1)}{x}",
+            r"\includegraphics{javascript:alert(1)}",
+        ] {
+            assert!(
+                !rendered_link(expression, &trust_settings())?,
+                "{expression}"
+            );
+        }
+        Ok(())
+    });
+
+    it("should reject control and bidi characters in URLs", || {
+        assert!(!rendered_link(
+            "\\href{https://a.example/\u{202e}gpj.exe}{x}",
+            &trust_settings()
+        )?);
+        assert!(!rendered_link(
+            "\\url{java\tscript:alert(1)}",
+            &trust_settings()
+        )?);
+        assert!(rendered_link(
+            r"\href{https://a.example/}{x}",
+            &trust_settings()
+        )?);
+        Ok(())
+    });
+
+    it("should pass the ASCII host to trust functions", || {
+        let hosts = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&hosts);
+        let settings = Settings::builder()
+            .trust(TrustSetting::Function(Arc::new(move |context| {
+                seen.lock().unwrap().push(context.host.clone());
+                Some(context.host.as_deref() == Some("xn--bcher-kva.example"))
+            })))
+            .build();
+        assert!(rendered_link(
+            "\\url{https://B\u{dc}cher.example/x}",
+            &settings
+        )?);
+        assert!(!rendered_link(r"\includegraphics{img/x.png}", &settings)?);
+        assert_eq!(
+            *hosts.lock().unwrap(),
+            [Some("xn--bcher-kva.example".to_owned()), None]
+        );
+        Ok(())
+    });
+
+    it("should trust an allowlist of protocols", || {
+        let settings = Settings::builder()
+            .trust(allow_protocols(["https", "_relative"]))
+            .build();
+        assert!(rendered_link(r"\href{https://katex.org}{K}", &settings)?);
+        assert!(rendered_link(r"\includegraphics{img/x.png}", &settings)?);
+        assert!(!rendered_link(r"\href{http://katex.org}{K}", &settings)?);
+        Ok(())
+    });
+}
+
+#[test]
+fn an_escape_module() {
+    it("should escape like the HTML builder", || {
+        let html = render_to_string(
+            default_ctx(),
+            r#"\htmlData{title=a"b<c}{x}"#,
+            &trust_settings(),
+        )?;
+        let expected = format!(r#"data-title="{}""#, escape::html_attribute(r#"a"b<c"#));
+        assert!(html.contains(&expected), "{html}");
+        Ok(())
+    });
+
+    it("should escape like the MathML builder", || {
+        let expression = r"\text{a\&b's}";
+        let html = render_to_string(default_ctx(), expression, &Settings::default())?;
+        let expected = format!(
+            r#"<annotation encoding="application/x-tex">{}</annotation>"#,
+            escape::mathml_text(expression)
+        );
+        assert!(html.contains(&expected), "{html}");
+        Ok(())
+    });
+
+    it(
+        "should write non-ASCII characters as references with the ASCII charset",
+        || {
+            let settings = Settings::builder().charset(Charset::Ascii).build();
+            let html = render_to_string(default_ctx(), r"\alpha\le\text{é}", &settings)?;
+            assert!(html.is_ascii(), "{html}");
+            assert!(html.contains("<mi>&#x3b1;</mi>"), "{html}");
+            assert!(html.contains("&#x2264;"), "{html}");
+            assert!(html.contains("&#xe9;"), "{html}");
+
+            let utf8 = render_to_string(default_ctx(), r"\alpha\le\text{é}", &Settings::default())?;
+            assert!(utf8.contains("<mi>\u{3b1}</mi>"), "{utf8}");
+            Ok(())
+        },
+    );
+
+    it(
+        "should write the ASCII charset through every markup entry point",
+        || {
+            let settings = Settings::builder().charset(Charset::Ascii).build();
+            let expression = r"\alpha\text{\'e}";
+            let dom_tree = render_to_dom_tree(default_ctx(), expression, &settings)?;
+            let markup = dom_tree.to_markup()?;
+            assert!(markup.is_ascii(), "{markup}");
+            assert_eq!(
+                markup,
+                render_to_string(default_ctx(), expression, &settings)?
+            );
+            let html = katex::render_to_html_tree(default_ctx(), expression, &settings)?;
+            let html = html.to_markup()?;
+            assert!(html.is_ascii() && html.contains("&#x3b1;"), "{html}");
+            let output = render_to_output(default_ctx(), expression, &settings)?;
+            assert_eq!(output.markup, markup);
+
+            let error_settings = Settings::builder()
+                .charset(Charset::Ascii)
+                .throw_on_error(false)
+                .build();
+            let error = render_to_string(default_ctx(), "\u{3b1}\\undefined", &error_settings)?;
+            assert!(error.is_ascii() && error.contains("&#x3b1;"), "{error}");
+            Ok(())
+        },
+    );
+
+    it("should write the content MathML with the charset", || {
+        let settings = Settings::builder().charset(Charset::Ascii).build();
+        let markup = katex::render_to_content_mathml(default_ctx(), r"\alpha+1", &settings)?;
+        assert!(markup.is_ascii(), "{markup}");
+        assert!(markup.contains("&#x3b1;"), "{markup}");
+        Ok(())
+    });
+}
+
 #[test]
 fn a_raw_text_parser() {
     it("should return null for a omitted optional string", || {
@@ -3822,7 +4947,7 @@ fn a_parser_that_does_not_throw_on_unsupported_commands() {
         "should build katex-error span for other type of KaTeX error",
         || {
             let html = render_to_string_nonstrict("2^2^2")?;
-            let normalized = normalize_html_attributes(&normalize_style_attributes(&html));
+            let normalized = normalize_markup(&html);
             insta::assert_snapshot!("error_render_double_superscript", normalized);
             Ok(())
         },
@@ -3830,7 +4955,7 @@ fn a_parser_that_does_not_throw_on_unsupported_commands() {
 
     it("should properly escape LaTeX in errors", || {
         let html = render_to_string_nonstrict("2^&\"<>")?;
-        let normalized = normalize_html_attributes(&normalize_style_attributes(&html));
+        let normalized = normalize_markup(&html);
         insta::assert_snapshot!("error_render_escape", normalized);
         Ok(())
     });
@@ -3896,6 +5021,16 @@ fn parse_error_properties() {
     );
 }
 
+#[test]
+fn a_verb_parser() {
+    it("should accept \\verb delimiters outside ASCII", || {
+        let parsed = get_parsed_strict("\\verb\u{2272}x\u{2272}")?;
+        assert_let!(ParseNode::Verb(verb) = &parsed[0]);
+        assert_eq!(verb.body, "x");
+        Ok(())
+    });
+}
+
 #[test]
 fn the_symbol_table_integrity() {
     it("should treat certain symbols as synonyms", || {
@@ -4217,6 +5352,27 @@ fn a_macro_expander() {
         expect!(r"\underset{f}{\rightarrow} Y").to_build(&strict_settings())
     });
 
+    it(
+        "should stack annotations on both sides of a relation",
+        || {
+            expect!(r"\stackrel[g]{f}{\rightarrow} Y").to_build(&strict_settings())?;
+            expect!(r"\overunderset{f}{g}{\rightarrow} Y").to_build(&strict_settings())?;
+
+            let parsed = get_parsed_strict(r"\stackrel[g]{f}{=}")?;
+            assert_let!(ParseNode::Mclass(mclass) = &parsed[0]);
+            assert_eq!(mclass.mclass, DomType::Mrel);
+            assert_let!(ParseNode::SupSub(supsub) = &mclass.body[0]);
+            assert!(supsub.sup.is_some());
+            assert!(supsub.sub.is_some());
+
+            let parsed = get_parsed_strict(r"\stackrel{f}{=}")?;
+            assert_let!(ParseNode::Mclass(mclass) = &parsed[0]);
+            assert_let!(ParseNode::SupSub(supsub) = &mclass.body[0]);
+            assert!(supsub.sub.is_none());
+            Ok(())
+        },
+    );
+
     it("should build \\iff, \\implies, \\impliedby", || {
         expect!("X \\iff Y").to_build(&strict_settings())?;
         expect!("X \\implies Y").to_build(&strict_settings())?;
@@ -4334,6 +5490,11 @@ fn a_macro_expander() {
         Ok(())
     });
 
+    it("\\char rejects numbers that overflow", || {
+        expect!(r"\char99999999999").not_to_parse(&strict_settings())?;
+        expect!(r#"\char"fffffffffff"#).not_to_parse(&strict_settings())
+    });
+
     it("should build Unicode private area characters", || {
         expect!(r"\gvertneqq\lvertneqq\ngeqq\ngeqslant\nleqq").to_build(&strict_settings())?;
         expect!(r"\nleqslant\nshortmid\nshortparallel\varsubsetneq").to_build(&strict_settings())?;
@@ -4745,6 +5906,14 @@ fn a_macro_expander() {
             &strict_settings(),
         )
     });
+
+    it(
+        "should reject placeholders without a matching argument",
+        || {
+            expect!(r"\def\f#1{#0^2}\f{x}").not_to_parse(&strict_settings())?;
+            expect!(r"\newcommand{\f}[1]{#2}\f{x}").not_to_parse(&strict_settings())
+        },
+    );
 }
 
 #[test]
@@ -4848,6 +6017,19 @@ fn binrel_automatic_bin_rel_ord() {
         expect!(r"L\@binrel{=x}xR").to_parse_like(r"L\mathrel xR", &strict_settings())?;
         expect!(r"L\@binrel{xx}xR").to_parse_like(r"L\mathord xR", &strict_settings())
     });
+
+    it("should be available to custom functions", || {
+        let class_of = |expr: &str| -> Result<DomType, TestError> {
+            let parsed = get_parsed_strict(expr)?;
+            Ok(binrel_class(&parsed[0]))
+        };
+        assert_eq!(class_of("+")?, DomType::Mbin);
+        assert_eq!(class_of("{=x}")?, DomType::Mrel);
+        assert_eq!(class_of("x")?, DomType::Mord);
+        assert_eq!(DomType::from_atom(Atom::Rel), DomType::Mrel);
+        assert_eq!(DomType::from_atom(Atom::Punct).as_str(), "mpunct");
+        Ok(())
+    });
 }
 
 #[test]
@@ -5020,6 +6202,130 @@ fn the_max_size_setting() {
     );
 }
 
+#[test]
+fn the_settings_fingerprint() {
+    it("should be equal for equal settings", || {
+        let a = Settings::builder()
+            .display_mode(true)
+            .max_size(10.0)
+            .build();
+        let b = Settings::builder()
+            .max_size(10.0)
+            .display_mode(true)
+            .build();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(
+            Settings::builder()
+                .strict(StrictSetting::Bool(true))
+                .build()
+                .fingerprint(),
+            Settings::builder()
+                .strict(StrictSetting::Mode(StrictMode::Error))
+                .build()
+                .fingerprint()
+        );
+        Ok(())
+    });
+
+    it("should change with any render option", || {
+        let base = Settings::default().fingerprint();
+        assert_ne!(
+            base,
+            Settings::builder().display_mode(true).build().fingerprint()
+        );
+        assert_ne!(
+            base,
+            Settings::builder()
+                .output(OutputFormat::Html)
+                .build()
+                .fingerprint()
+        );
+        assert_ne!(
+            base,
+            Settings::builder()
+                .color("red".to_owned())
+                .build()
+                .fingerprint()
+        );
+        assert_ne!(
+            base,
+            Settings::builder().max_width_em(20.0).build().fingerprint()
+        );
+        assert_ne!(
+            base,
+            Settings::builder()
+                .trust(TrustSetting::Bool(true))
+                .build()
+                .fingerprint()
+        );
+        assert_ne!(
+            base,
+            Settings::builder()
+                .behavior(BehaviorFlags {
+                    uncollapsed_primes: true,
+                })
+                .build()
+                .fingerprint()
+        );
+        Ok(())
+    });
+
+    it("should cover the macro store regardless of order", || {
+        let define = |names: &[&str], body: &'static str| {
+            let settings = Settings::default();
+            for name in names {
+                settings
+                    .macros
+                    .borrow_mut()
+                    .insert((*name).to_owned(), MacroDefinition::StaticStr(body));
+            }
+            settings.fingerprint()
+        };
+        assert_eq!(define(&[r"\a", r"\b"], "x"), define(&[r"\b", r"\a"], "x"));
+        assert_ne!(define(&[r"\a"], "x"), define(&[r"\a"], "y"));
+        assert_ne!(define(&[r"\a"], "x"), Settings::default().fingerprint());
+
+        let settings = Settings::default();
+        let before = settings.fingerprint();
+        expect!(r"\gdef\foo{1}\foo").to_parse(&settings)?;
+        assert_ne!(settings.fingerprint(), before);
+        Ok(())
+    });
+}
+
+#[test]
+fn the_macro_store_snapshot() {
+    it(
+        "should report and reset the definitions of a render",
+        || {
+            let settings = Settings::default();
+            expect!(r"\gdef\foo{1}").to_parse(&settings)?;
+            let before = settings.snapshot_macros();
+
+            expect!(r"\gdef\baz{2}\gdef\foo{3}\gdef\same{}").to_parse(&settings)?;
+            let after = settings.snapshot_macros();
+            // Redefining with the same body from another source is no change.
+            expect!(r"\gdef\same{}").to_parse(&settings)?;
+            assert!(diff(&after, &settings.snapshot_macros()).is_empty());
+            let changed: Vec<_> = diff(&before, &after)
+                .iter()
+                .map(|change| match change {
+                    Change::Added { name, .. } => format!("+{name}"),
+                    Change::Removed { name, .. } => format!("-{name}"),
+                    Change::Changed { name, .. } => format!("~{name}"),
+                })
+                .collect();
+            assert_eq!(changed, [r"+\baz", r"~\foo", r"+\same"]);
+
+            settings.restore_macros(&before);
+            expect!(r"\foo").to_parse_like("1", &settings)?;
+            expect!(r"\baz").not_to_parse(&settings)?;
+            assert!(diff(&before, &settings.snapshot_macros()).is_empty());
+            Ok(())
+        },
+    );
+}
+
 #[test]
 fn the_max_expand_setting() {
     it("should prevent expansion", || {
@@ -5064,6 +6370,311 @@ fn the_max_expand_setting() {
     );
 }
 
+#[test]
+fn the_max_depth_setting() {
+    it("should limit nested groups", || {
+        let nested = |depth: usize| format!("{}x{}", "{".repeat(depth), "}".repeat(depth));
+        let settings = Settings::builder().max_depth(10).build();
+        expect!(&nested(9)).to_parse(&settings)?;
+        expect!(&nested(10)).not_to_parse(&settings)?;
+        expect!(&nested(99)).to_parse(&Settings::default())?;
+        expect!(&nested(100)).not_to_parse(&Settings::default())
+    });
+
+    it("should count function arguments", || {
+        let settings = Settings::builder().max_depth(3).build();
+        expect!(r"\frac{\frac{a}{b}}{c}").to_parse(&settings)?;
+        expect!(r"\frac{\frac{\frac{a}{b}}{c}}{d}").not_to_parse(&settings)?;
+        expect!("x^{x^{x^{x}}}").not_to_parse(&settings)
+    });
+
+    it(
+        "should reject deep input without exhausting a small stack",
+        || {
+            let input = format!("{}x{}", "{".repeat(100_000), "}".repeat(100_000));
+            let result = thread::Builder::new()
+                .stack_size(1 << 21)
+                .spawn(move || render_to_string(default_ctx(), &input, &Settings::default()))
+                .expect("spawn a thread")
+                .join()
+                .expect("no stack overflow");
+            assert_let!(Err(error) = result);
+            assert!(matches!(
+                *error.kind,
+                ParseErrorKind::MaxDepthExceeded { max_depth: 100 }
+            ));
+            Ok(())
+        },
+    );
+
+    it("should not count sibling groups", || {
+        let siblings = "{x}".repeat(1000);
+        expect!(&siblings).to_parse(&Settings::builder().max_depth(2).build())
+    });
+}
+
+#[test]
+fn the_parser_nesting() {
+    // Nesting is bounded by maxDepth rather than by the native stack: groups
+    // and scripts are parsed and dropped without recursion, and everything
+    // that does recurse moves to a new stack segment when the current one
+    // runs low.
+    let on_small_stack = |input: String, settings: Settings, render: bool| {
+        thread::Builder::new()
+            .stack_size(1 << 18)
+            .spawn(move || {
+                if render {
+                    render_to_string(default_ctx(), &input, &settings).map(|markup| markup.len())
+                } else {
+                    parse_tree(default_ctx(), &input, &settings).map(|tree| {
+                        let len = tree.len();
+                        drop_tree(tree);
+                        len
+                    })
+                }
+                .map_err(|error| error.to_string())
+            })
+            .expect("spawn a thread")
+            .join()
+            .expect("no stack overflow")
+    };
+    let unbounded = || Settings::builder().max_depth(usize::MAX).build();
+    let nested = |depth: usize, open: &str, close: &str| {
+        format!("{}x{}", open.repeat(depth), close.repeat(depth))
+    };
+
+    it("should parse deeply nested groups", || {
+        let braces = nested(10_000, "{", "}");
+        assert_eq!(on_small_stack(braces, unbounded(), false), Ok(1));
+        let groups = nested(10_000, r"\begingroup ", r"\endgroup");
+        assert_eq!(on_small_stack(groups, unbounded(), false), Ok(1));
+        Ok(())
+    });
+
+    it("should parse deeply nested scripts", || {
+        let superscripts = nested(10_000, "x^{", "}");
+        assert_eq!(on_small_stack(superscripts, unbounded(), false), Ok(1));
+        let mixed = nested(10_000, "{x_{", "}}");
+        assert_eq!(on_small_stack(mixed, unbounded(), false), Ok(1));
+        Ok(())
+    });
+
+    it("should still report unbalanced deep groups", || {
+        let unclosed = format!("{}x{}", "{".repeat(10_000), "}".repeat(9999));
+        assert!(on_small_stack(unclosed, unbounded(), false).is_err());
+        Ok(())
+    });
+
+    it(
+        "should render deeply nested groups, scripts and fractions",
+        || {
+            for (open, close) in [("{", "}"), ("x^{", "}"), (r"\frac{", "}{y}")] {
+                let input = nested(10_000, open, close);
+                assert!(on_small_stack(input, unbounded(), true).is_ok(), "{open}");
+            }
+            Ok(())
+        },
+    );
+
+    it("should render up to the default maxDepth", || {
+        for (open, close) in [("{", "}"), ("x^{", "}"), (r"\frac{", "}{y}")] {
+            let input = nested(99, open, close);
+            assert!(
+                on_small_stack(input, Settings::default(), true).is_ok(),
+                "{open}"
+            );
+        }
+        Ok(())
+    });
+
+    it("should parse nested groups like before", || {
+        expect!("{x_{y^{z}}'}").to_parse(&Settings::default())?;
+        expect!(r"\begingroup a^{\begingroup b\endgroup}\endgroup")
+            .to_parse(&Settings::default())?;
+        expect!("x^{2}_{3}").to_parse_like("x_{3}^{2}", &Settings::default())?;
+        expect!("{a \\over b}^{c}").to_parse(&Settings::default())?;
+        expect!("x^{y}^{z}").not_to_parse(&Settings::default())
+    });
+}
+
+#[test]
+fn the_argument_scanner() {
+    let nested_roots = |depth: usize, index: &str| {
+        format!(
+            "{}{index}{}",
+            r"\sqrt[{".repeat(depth),
+            "}]{y}".repeat(depth)
+        )
+    };
+
+    it(
+        "should parse a 100KB optional argument in near-linear time",
+        || {
+            // The fastest of a few runs, to keep scheduling noise out
+            let time = |input: &str| -> Result<Duration, ParseError> {
+                let mut fastest = Duration::MAX;
+                for _ in 0..3 {
+                    let start = Instant::now();
+                    parse_tree(default_ctx(), input, &Settings::default())?;
+                    fastest = fastest.min(start.elapsed());
+                }
+                Ok(fastest)
+            };
+            let flat = |len: usize| format!(r"\sqrt[{}]{{x}}", "a+".repeat(len / 2));
+            // Four times the input takes four times as long when scanning is
+            // linear, and sixteen times when it is quadratic
+            let quarter = time(&flat(25_000))?;
+            let full = time(&flat(100_000))?;
+            assert!(
+                full < quarter * 8,
+                "{quarter:?} for 25KB, {full:?} for 100KB"
+            );
+
+            // Each level used to scan everything nested in it again
+            let nested = time(&nested_roots(90, &"a+".repeat(50_000)))?;
+            assert!(nested < full * 4, "{full:?} flat, {nested:?} nested");
+            Ok(())
+        },
+    );
+
+    it("should split nested optional arguments like before", || {
+        expect!(&nested_roots(2, "x"))
+            .to_parse_like(r"\sqrt[{\sqrt[x]{y}}]{y}", &Settings::default())?;
+        expect!(r"\sqrt[{a}{b}]{x}").not_to_parse(&Settings::default())?;
+        expect!(r"\sqrt[{a]{x}").not_to_parse(&Settings::default())?;
+        expect!(r"\frac{\frac{a}{\sqrt[{b}]c}}{d}")
+            .to_parse_like(r"\frac{\frac a{\sqrt[b]{c}}}d", &Settings::default())
+    });
+}
+
+#[test]
+fn a_sublanguage_parser() {
+    /// Keeps the argument verbatim, except for TeX in parentheses
+    fn parse_parens(
+        context: &mut SublanguageContext<'_, '_>,
+    ) -> Result<Vec<ParseNode>, ParseError> {
+        let text = context.text().to_owned();
+        let (Some(open), Some(close)) = (text.find('('), text.rfind(')')) else {
+            return Ok(vec![ParseNode::Verb(ParseNodeVerb {
+                mode: context.parser.mode,
+                loc: context.loc(0..text.len()),
+                body: text.into(),
+                star: false,
+            })]);
+        };
+        context.parse_tex(open + 1..close)
+    }
+    let ctx = || {
+        let mut ctx = KatexContext::default();
+        ctx.define_sublanguage(SublanguageSpec {
+            names: &[r"\parens"],
+            parser: parse_parens,
+        });
+        ctx
+    };
+
+    it("should receive the argument as raw text", || {
+        let tree = parse_tree(&ctx(), r"\parens{ 2H2 % {O2}  \}}x", &Settings::default())?;
+        let [ParseNode::OrdGroup(group), ParseNode::MathOrd(_)] = &tree[..] else {
+            panic!("unexpected tree {tree:?}");
+        };
+        let [ParseNode::Verb(verb)] = &group.body[..] else {
+            panic!("unexpected group {group:?}");
+        };
+        assert_eq!(verb.body, r" 2H2 % {O2}  \}");
+        let loc = verb.loc.as_ref().expect("the text is located");
+        assert_eq!((loc.start, loc.end), (8, 23));
+        let loc = group.loc.as_ref().expect("the group is located");
+        assert_eq!((loc.start, loc.end), (0, 24));
+        Ok(())
+    });
+
+    it("should locate TeX parts in the whole input", || {
+        let tree = parse_tree(&ctx(), r"a\parens{junk (x^2)}", &Settings::default())?;
+        let ParseNode::OrdGroup(group) = &tree[1] else {
+            panic!("unexpected tree {tree:?}");
+        };
+        let ParseNode::SupSub(supsub) = &group.body[0] else {
+            panic!("unexpected group {group:?}");
+        };
+        let base = supsub.base.as_deref().and_then(ParseNode::loc);
+        assert_eq!(base.map(|loc| (loc.start, loc.end)), Some((15, 16)));
+        Ok(())
+    });
+
+    it("should point errors in TeX parts into the input", || {
+        let Err(error) = parse_tree(&ctx(), r"\parens{(x\foo)}", &Settings::default()) else {
+            panic!("expected an error");
+        };
+        assert_eq!(error.position(), Some(10));
+        Ok(())
+    });
+
+    it("should parse TeX in an argument without a location", || {
+        let (ctx, settings) = (ctx(), Settings::default());
+        let mut parser = Parser::new("", &settings, &ctx);
+        let mut context = SublanguageContext {
+            func_name: r"\parens",
+            parser: &mut parser,
+            argument: ParseNodeRaw {
+                mode: Mode::Math,
+                loc: None,
+                string: "junk (x^2)".into(),
+            },
+        };
+        let nodes = context.parse_tex(6..9)?;
+        assert!(matches!(&nodes[..], [ParseNode::SupSub(_)]), "{nodes:?}");
+        Ok(())
+    });
+
+    it("should need a closed group", || {
+        let settings = Settings::default();
+        for input in [r"\parens x", r"\parens{(x)", r"\parens{x\}"] {
+            assert!(parse_tree(&ctx(), input, &settings).is_err(), "{input}");
+        }
+        Ok(())
+    });
+
+    it(
+        "should reject ranges outside the argument or its characters",
+        || {
+            /// Parses the second byte, which is inside `é`
+            fn parse_split(
+                context: &mut SublanguageContext<'_, '_>,
+            ) -> Result<Vec<ParseNode>, ParseError> {
+                context.parse_tex(1..2)
+            }
+            /// Parses past the end of the argument
+            fn parse_past_end(
+                context: &mut SublanguageContext<'_, '_>,
+            ) -> Result<Vec<ParseNode>, ParseError> {
+                context.parse_tex(0..50)
+            }
+            let mut ctx = KatexContext::default();
+            ctx.define_sublanguage(SublanguageSpec {
+                names: &[r"\split"],
+                parser: parse_split,
+            });
+            ctx.define_sublanguage(SublanguageSpec {
+                names: &[r"\pastend"],
+                parser: parse_past_end,
+            });
+            let settings = Settings::default();
+            for (input, (start, end)) in [(r"\split{éx}", (1, 2)), (r"\pastend{ab}", (0, 50))] {
+                assert_let!(Err(error) = parse_tree(&ctx, input, &settings));
+                assert!(
+                    matches!(
+                        *error.kind,
+                        ParseErrorKind::InvalidRawTextRange { start: s, end: e } if (s, e) == (start, end)
+                    ),
+                    "{input}: {error}"
+                );
+            }
+            Ok(())
+        },
+    );
+}
+
 #[test]
 fn the_mathchoice_function() {
     let cmd = r"\sum_{k = 0}^{\infty} x^k";
@@ -5118,7 +6729,7 @@ fn newlines_via_backslash_and_newline() {
 
     it("\\\\ causes newline, even after mrel and mop", || {
         let markup = render_to_string_strict(r"M = \\ a + \\ b \\ c")?;
-        let normalized = normalize_style_attributes(&markup);
+        let normalized = normalize_markup(&markup);
         insta::assert_snapshot!("newline_markup", normalized);
         Ok(())
     });
@@ -5168,28 +6779,28 @@ fn strict_setting() {
         )?;
         expect!("é").to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Bool(false))
                 })))
                 .build(),
         )?;
         expect!("試").to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Bool(false))
                 })))
                 .build(),
         )?;
         expect!("é").to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Mode(katex::StrictMode::Ignore))
                 })))
                 .build(),
         )?;
         expect!("試").to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Mode(katex::StrictMode::Ignore))
                 })))
                 .build(),
@@ -5211,28 +6822,28 @@ fn strict_setting() {
         )?;
         expect!("é").not_to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Bool(true))
                 })))
                 .build(),
         )?;
         expect!("試").not_to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Bool(true))
                 })))
                 .build(),
         )?;
         expect!("é").not_to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Mode(katex::StrictMode::Error))
                 })))
                 .build(),
         )?;
         expect!("試").not_to_parse(
             &Settings::builder()
-                .strict(katex::StrictSetting::Function(Arc::new(|_, _, _| {
+                .strict(katex::StrictSetting::Function(Arc::new(|_| {
                     Some(katex::StrictReturn::Mode(katex::StrictMode::Error))
                 })))
                 .build(),
@@ -5261,6 +6872,47 @@ fn strict_setting() {
             expect!("x\\\\y").to_parse(&non_display_settings())
         },
     );
+
+    it("should pass the violation's token and location", || {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let record = Arc::clone(&seen);
+        let settings = Settings::builder()
+            .strict(katex::StrictSetting::Function(Arc::new(move |context| {
+                record.lock().unwrap().push((
+                    context.code.to_owned(),
+                    context.token.map(|token| token.text.to_string()),
+                    context.loc.map(|loc| (loc.start, loc.end)),
+                ));
+                Some(katex::StrictReturn::Bool(false))
+            })))
+            .build();
+        expect!("x + é").to_parse(&settings)?;
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(
+                "unicodeTextInMathMode".to_owned(),
+                Some("é".to_owned()),
+                Some((4, 6))
+            )]
+        );
+        Ok(())
+    });
+
+    it(
+        "should accept strict functions with the legacy signature",
+        || {
+            let settings = Settings::builder()
+                .strict(katex::StrictSetting::from_legacy(|code, _, token| {
+                    let located = token.and_then(|token| token.loc()).is_some();
+                    Some(katex::StrictReturn::Bool(
+                        code == "unicodeTextInMathMode" && located,
+                    ))
+                }))
+                .build();
+            expect!("é").not_to_parse(&settings)?;
+            expect!(r"\text{é}").to_parse(&settings)
+        },
+    );
 }
 
 #[test]
@@ -5312,6 +6964,133 @@ fn internal_interface() {
     );
 }
 
+#[test]
+fn the_embedded_stylesheet() {
+    it("should read the version from a KaTeX stylesheet", || {
+        let minified = r#".katex .katex-version:after{content:"0.16.22"}"#;
+        assert_eq!(stylesheet_version(minified), Some("0.16.22"));
+        let expanded = ".katex .katex-version::after {\n  content: '0.16.22';\n}";
+        assert_eq!(stylesheet_version(expanded), Some("0.16.22"));
+        assert_eq!(stylesheet_version(".katex { font-size: 1.21em; }"), None);
+        Ok(())
+    });
+
+    it("should match the upstream version when vendored", || {
+        if KATEX_CSS.is_empty() {
+            assert!(UPSTREAM_VERSION.is_empty());
+        } else {
+            assert_eq!(stylesheet_version(KATEX_CSS), Some(UPSTREAM_VERSION));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn a_class_summary() {
+    let classes_in_markup = |markup: &str| -> BTreeSet<String> {
+        markup
+            .split(" class=\"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .flat_map(str::split_whitespace)
+            .map(str::to_owned)
+            .collect()
+    };
+
+    it("should list exactly the classes in the markup", || {
+        for expr in [
+            r"\frac{a}{b} + \sqrt[3]{x}",
+            r"\left(\begin{matrix} a & b \\ c & d \end{matrix}\right)",
+            r"\vcenter{x} \color{red}{\overrightarrow{AB}}",
+        ] {
+            let output = render_to_output(default_ctx(), expr, &strict_settings())?;
+            let markup = render_to_string(default_ctx(), expr, &strict_settings())?;
+            assert_eq!(
+                normalize_style_attributes(&normalize_html_attributes(&output.markup)),
+                normalize_style_attributes(&normalize_html_attributes(&markup))
+            );
+            assert_eq!(output.classes_used, classes_in_markup(&output.markup));
+        }
+        Ok(())
+    });
+
+    it("should include classes added with \\htmlClass", || {
+        let output = render_to_output(
+            default_ctx(),
+            r"\htmlClass{first second}{x}",
+            &trust_settings(),
+        )?;
+        assert!(output.classes_used.contains("first"));
+        assert!(output.classes_used.contains("second"));
+        assert!(output.classes_used.contains("enclosing"));
+        Ok(())
+    });
+
+    it("should report the error box when not throwing", || {
+        let settings = Settings::builder().throw_on_error(false).build();
+        let output = render_to_output(default_ctx(), r"\frac{a}{", &settings)?;
+        assert!(output.classes_used.contains("katex-error"));
+        assert!(!output.classes_used.contains("mfrac"));
+        Ok(())
+    });
+}
+
+#[test]
+fn a_minimal_katex_context() {
+    it("should render symbols, groups and scripts", || {
+        let ctx = KatexContext::minimal();
+        let settings = Settings::default();
+        let expr = r"x_1^2 + {a'} < \infty";
+        let tree = parse_tree(&ctx, expr, &settings)?;
+        assert_eq!(tree, parse_tree(default_ctx(), expr, &settings)?);
+        render_to_string(&ctx, expr, &settings)?;
+        Ok(())
+    });
+
+    it("should not know any commands or environments", || {
+        let ctx = KatexContext::minimal();
+        let settings = Settings::default();
+        assert!(!ctx.functions.contains_key(r"\frac"));
+        assert!(ctx.environments.is_empty());
+        assert_let!(Err(error) = render_to_string(&ctx, r"\frac12", &settings));
+        assert!(matches!(
+            *error.kind,
+            ParseErrorKind::UndefinedControlSequence { .. }
+        ));
+        Ok(())
+    });
+
+    it("should take only the functions registered on it", || {
+        let mut ctx = KatexContext::minimal();
+        let settings = Settings::default();
+        functions::define_genfrac(&mut ctx);
+        let tree = parse_tree(&ctx, r"\frac12", &settings)?;
+        assert_eq!(tree, parse_tree(default_ctx(), r"\frac12", &settings)?);
+        render_to_string(&ctx, r"\frac12", &settings)?;
+        assert!(parse_tree(&ctx, r"\sqrt2", &settings).is_err());
+        Ok(())
+    });
+
+    it(
+        "should become the default context with all functions",
+        || {
+            let ctx = KatexContext::minimal().with_all_functions();
+            let default = default_ctx();
+            assert_eq!(ctx.functions.len(), default.functions.len());
+            assert_eq!(ctx.environments.len(), default.environments.len());
+            assert_eq!(
+                ctx.html_group_builders.len(),
+                default.html_group_builders.len()
+            );
+            assert_eq!(
+                ctx.mathml_group_builders.len(),
+                default.mathml_group_builders.len()
+            );
+            Ok(())
+        },
+    );
+}
+
 #[test]
 fn extending_katex_by_new_fonts_and_symbols() {
     let get_context = || -> KatexContext {
@@ -5420,10 +7199,154 @@ fn extending_katex_by_new_fonts_and_symbols() {
                 location: TestLocation::UNKNOWN,
                 source,
             })?;
-        let normalized_html = normalize_style_attributes(&html);
+        let normalized_html = normalize_markup(&html);
         insta::assert_snapshot!("eastern_arabic_numerals_html", normalized_html);
         Ok(())
     });
+
+    it(
+        "should substitute registered glyphs for sized symbols",
+        || {
+            let mut ctx = KatexContext::default();
+            ctx.font_metrics.add_custom_metrics(
+                "Brand-Regular".to_owned(),
+                0x2211,
+                CharacterMetrics {
+                    depth: 0.5,
+                    height: 1.5,
+                    italic: 0.0,
+                    skew: 0.0,
+                    width: 1.2,
+                },
+            );
+            for (name, size) in [("\\sum", 2), ("(", 3)] {
+                ctx.symbols.define_sized_glyph(
+                    name,
+                    size,
+                    SizedGlyph {
+                        text: "∑".to_owned(),
+                        font: "Brand-Regular".to_owned(),
+                        class: "brand-op".to_owned(),
+                    },
+                );
+            }
+
+            let html = render_to_string(&ctx, r"\sum", &display_settings())?;
+            assert!(html.contains("mop op-symbol brand-op"));
+            assert!(!html.contains("large-op"));
+
+            // Text style still uses the KaTeX Size1 glyph
+            let html = render_to_string(&ctx, r"\sum", &strict_settings())?;
+            assert!(html.contains("small-op"));
+
+            let html = render_to_string(&ctx, r"\biggl(", &strict_settings())?;
+            assert!(html.contains("brand-op"));
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn extending_katex_by_new_environments() {
+    let get_context = || -> KatexContext {
+        let mut ctx = KatexContext::default();
+        ctx.define_environment(EnvDefSpec {
+            node_type: NodeType::Verb,
+            names: vec!["rawtext".to_owned()],
+            props: EnvProps {
+                allowed_in_text: Some(true),
+                raw_body: Some(true),
+                ..Default::default()
+            },
+            handler: |context, _args, _opt_args| {
+                let body = context.raw_body.expect("rawtext has a raw body");
+                Ok(ParseNode::Verb(ParseNodeVerb {
+                    mode: context.mode,
+                    loc: body.loc,
+                    body: body.string,
+                    star: false,
+                }))
+            },
+            html_builder: None,
+            mathml_builder: None,
+        });
+        ctx
+    };
+
+    it("should capture a raw body up to its \\end", || {
+        let ctx = get_context();
+        let tree = parse_tree(
+            &ctx,
+            r"x\begin{rawtext}a & \frac{ %}\end{verbatim}\end{rawtext}y",
+            &strict_settings(),
+        )?;
+        assert_eq!(tree.len(), 3);
+        assert_let!(ParseNode::Verb(verb) = &tree[1]);
+        assert_eq!(verb.body.as_str(), r"a & \frac{ %}\end{verbatim}");
+        assert_let!(ParseNode::MathOrd(after) = &tree[2]);
+        assert_eq!(after.text, "y");
+        render_to_string(&ctx, r"\begin{rawtext}\\\end{rawtext}", &strict_settings())?;
+        Ok(())
+    });
+
+    it("should fail without the matching \\end", || {
+        let ctx = get_context();
+        assert_let!(
+            Err(error) = parse_tree(&ctx, r"\begin{rawtext}a\end{matrix}", &strict_settings())
+        );
+        assert!(matches!(
+            *error.kind,
+            ParseErrorKind::UnterminatedRawText { .. }
+        ));
+        Ok(())
+    });
+}
+
+#[test]
+fn a_parser_trace() {
+    let trace_of = |expr: &str| -> Result<Vec<String>, TestError> {
+        let trace = TraceSink::new();
+        let settings = Settings::builder().trace(trace.clone()).build();
+        expect!(expr).to_parse(&settings)?;
+        Ok(trace.take().iter().map(ToString::to_string).collect())
+    };
+
+    it(
+        "should record expansions, groups and dispatches in order",
+        || {
+            assert_eq!(
+                trace_of(r"\def\x#1{\frac#1b}{\x a}")?,
+                [
+                    r"call \def at 0..4",
+                    "begin group { at 18..19",
+                    r"expand \x -> \frac ab at 19..22",
+                    r"call \frac at 9..14",
+                    "end group } at 23..24",
+                ]
+            );
+            assert_eq!(
+                trace_of(r"\begingroup x\endgroup")?,
+                [
+                    r"begin group \begingroup at 0..12",
+                    r"end group \endgroup at 13..22",
+                ]
+            );
+            Ok(())
+        },
+    );
+
+    it(
+        "should not change the parse tree or the fingerprint",
+        || {
+            let settings = Settings::builder().trace(TraceSink::new()).build();
+            assert_eq!(settings.fingerprint(), Settings::default().fingerprint());
+            assert_eq!(
+                parse_tree(default_ctx(), r"\iff\frac12", &settings)?,
+                parse_tree(default_ctx(), r"\iff\frac12", &Settings::default())?
+            );
+            Ok(())
+        },
+    );
 }
 
 #[test]
@@ -2,7 +2,10 @@
 
 mod setup;
 
-use katex::types::{Settings, StrictSetting, TrustSetting};
+use katex::render_to_content_mathml;
+use katex::types::{
+    BehaviorFlags, NumberFormatter, ParseError, Settings, StrictSetting, TrustSetting,
+};
 use setup::*;
 
 #[test]
@@ -10,30 +13,111 @@ fn a_mathml_builder() {
     it("should generate the right types of nodes", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\sin{x}+1\;\text{a}", &settings)?;
-        insta::assert_snapshot!("mathml_spec__right_types_of_nodes", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__right_types_of_nodes",
+            normalize_markup(&markup)
+        );
         Ok(())
     });
 
     it("should concatenate digits into single <mn>", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\sin{\alpha}=0.34=.34^1", &settings)?;
-        insta::assert_snapshot!("mathml_spec__concatenate_digits", markup);
+        insta::assert_snapshot!("mathml_spec__concatenate_digits", normalize_markup(&markup));
         let markup = mathml_markup(r"1{,}000{,}000", &settings)?;
-        insta::assert_snapshot!("mathml_spec__concatenate_digits_commas", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__concatenate_digits_commas",
+            normalize_markup(&markup)
+        );
         Ok(())
     });
 
+    it(
+        "should pass whole digit runs to the number formatter",
+        || {
+            let group_thousands = |digits: &str| {
+                let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+                let mut grouped = String::new();
+                for (i, ch) in int.chars().enumerate() {
+                    if i > 0 && (int.len() - i) % 3 == 0 {
+                        grouped.push('\u{2009}');
+                    }
+                    grouped.push(ch);
+                }
+                if !frac.is_empty() {
+                    grouped.push('.');
+                    grouped.push_str(frac);
+                }
+                grouped
+            };
+            let settings = Settings::builder()
+                .number_formatter(NumberFormatter::new(group_thousands))
+                .build();
+
+            let markup = mathml_markup("1234567.5+12^{34567}", &settings)?;
+            assert!(
+                markup.contains("<mn>1\u{2009}234\u{2009}567.5</mn>"),
+                "{markup}"
+            );
+            assert!(markup.contains("<mn>12</mn>"), "{markup}");
+            assert!(markup.contains("<mn>34\u{2009}567</mn>"), "{markup}");
+
+            // Runs split by braces are concatenated before formatting
+            let markup = mathml_markup("1{234}5", &settings)?;
+            assert!(markup.contains("<mn>12\u{2009}345</mn>"), "{markup}");
+            Ok(())
+        },
+    );
+
     it("should make prime operators into <mo> nodes", || {
         let settings = Settings::default();
         let markup = mathml_markup("f'", &settings)?;
-        insta::assert_snapshot!("mathml_spec__prime_operator", markup);
+        insta::assert_snapshot!("mathml_spec__prime_operator", normalize_markup(&markup));
         Ok(())
     });
 
+    it(
+        "should collapse consecutive primes into combined glyphs",
+        || {
+            let settings = Settings::default();
+            for (expr, glyph) in [
+                ("f''", "\u{2033}"),
+                ("f'''", "\u{2034}"),
+                ("f'\u{2032}'", "\u{2034}"),
+                ("f''''", "\u{2057}"),
+            ] {
+                let markup = mathml_markup(expr, &settings)?;
+                assert!(
+                    markup.contains(&format!("<mo mathvariant=\"normal\">{glyph}</mo>")),
+                    "{expr}: {markup}"
+                );
+            }
+            let markup = mathml_markup("f'''''^2", &settings)?;
+            insta::assert_snapshot!("mathml_spec__collapsed_primes", normalize_markup(&markup));
+            Ok(())
+        },
+    );
+
+    it(
+        "should keep primes separate with the uncollapsed_primes flag",
+        || {
+            let settings = Settings::builder()
+                .behavior(BehaviorFlags {
+                    uncollapsed_primes: true,
+                })
+                .build();
+            let markup = mathml_markup("f''", &settings)?;
+            let prime = "<mo mathvariant=\"normal\">\u{2032}</mo>";
+            assert!(markup.contains(&prime.repeat(2)), "{markup}");
+            assert!(!markup.contains('\u{2033}'), "{markup}");
+            Ok(())
+        },
+    );
+
     it("should generate <mphantom> nodes for \\phantom", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\phantom{x}", &settings)?;
-        insta::assert_snapshot!("mathml_spec__phantom", markup);
+        insta::assert_snapshot!("mathml_spec__phantom", normalize_markup(&markup));
         Ok(())
     });
 
@@ -41,7 +125,10 @@ fn a_mathml_builder() {
         let mut settings = Settings::default();
         settings.display_mode = true;
         let markup = mathml_markup(r"\displaystyle\sum_a^b", &settings)?;
-        insta::assert_snapshot!("mathml_spec__munderover_large_operators", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__munderover_large_operators",
+            normalize_markup(&markup)
+        );
         Ok(())
     });
 
@@ -52,14 +139,17 @@ fn a_mathml_builder() {
             r"\displaystyle\int_a^b + \oiint_a^b + \oiiint_a^b",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__msupsub_integrals", markup);
+        insta::assert_snapshot!("mathml_spec__msupsub_integrals", normalize_markup(&markup));
         Ok(())
     });
 
     it("should use <msupsub> for regular operators", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\textstyle\sum_a^b", &settings)?;
-        insta::assert_snapshot!("mathml_spec__msupsub_regular_operators", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__msupsub_regular_operators",
+            normalize_markup(&markup)
+        );
         Ok(())
     });
 
@@ -68,7 +158,7 @@ fn a_mathml_builder() {
         || {
             let settings = Settings::default();
             let markup = mathml_markup(r"\limsup_{x \\rightarrow \\infty}", &settings)?;
-            insta::assert_snapshot!("mathml_spec__limsup_textstyle", markup);
+            insta::assert_snapshot!("mathml_spec__limsup_textstyle", normalize_markup(&markup));
             Ok(())
         },
     );
@@ -78,7 +168,7 @@ fn a_mathml_builder() {
         || {
             let settings = Settings::builder().display_mode(true).build();
             let markup = mathml_markup(r"\limsup_{x \\rightarrow \\infty}", &settings)?;
-            insta::assert_snapshot!("mathml_spec__limsup_displaymode", markup);
+            insta::assert_snapshot!("mathml_spec__limsup_displaymode", normalize_markup(&markup));
             Ok(())
         },
     );
@@ -86,7 +176,7 @@ fn a_mathml_builder() {
     it("should use <mpadded> for raisebox", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\raisebox{0.25em}{b}", &settings)?;
-        insta::assert_snapshot!("mathml_spec__raisebox", markup);
+        insta::assert_snapshot!("mathml_spec__raisebox", normalize_markup(&markup));
         Ok(())
     });
 
@@ -96,14 +186,56 @@ fn a_mathml_builder() {
             r"(M) \\big(M\\big) \\Big(M\\Big) \\bigg(M\\bigg) \\Bigg(M\\Bigg)",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__sized_delimiters", markup);
+        insta::assert_snapshot!("mathml_spec__sized_delimiters", normalize_markup(&markup));
         Ok(())
     });
 
     it("should use <menclose> for colorbox", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\colorbox{red}{b}", &settings)?;
-        insta::assert_snapshot!("mathml_spec__menclose_colorbox", markup);
+        insta::assert_snapshot!("mathml_spec__menclose_colorbox", normalize_markup(&markup));
+        Ok(())
+    });
+
+    it("should use <menclose> for longdiv", || {
+        let settings = Settings::default();
+        let markup = mathml_markup(r"\longdiv{1234}{5}", &settings)?;
+        assert!(markup.contains(r#"<mn>5</mn><menclose notation="longdiv"><mn>1234</mn>"#));
+        Ok(())
+    });
+
+    it("should add row gaps to the row spacing", || {
+        let settings = Settings::default();
+        let markup = mathml_markup(r"\begin{matrix}a\\[1em]b\\c\end{matrix}", &settings)?;
+        assert!(markup.contains(r#"rowspacing="1.16em 0.16em""#));
+        let markup = mathml_markup(r"\begin{matrix}a\\b\end{matrix}", &settings)?;
+        assert!(markup.contains(r#"rowspacing="0.16em""#));
+        // Negative gaps pull the rows together, as in LaTeX
+        let markup = mathml_markup(r"\begin{matrix}a\\[-1em]b\end{matrix}", &settings)?;
+        assert!(markup.contains(r#"rowspacing="-0.84em""#), "{markup}");
+        Ok(())
+    });
+
+    it("should strike a table for diagbox", || {
+        let settings = Settings::default();
+        let markup = mathml_markup(r"\diagbox{x}{y}", &settings)?;
+        assert!(markup.contains(
+            "<menclose notation=\"downdiagonalstrike\"><mtable>\
+             <mtr><mtd columnalign=\"right\"><mi>y</mi></mtd></mtr>\
+             <mtr><mtd columnalign=\"left\"><mi>x</mi></mtd></mtr></mtable></menclose>"
+        ));
+        Ok(())
+    });
+
+    it("should use <mmultiscripts> for nuclide", || {
+        let settings = Settings::default();
+        let markup = mathml_markup(r"\nuclide[14][6]{C}", &settings)?;
+        assert!(markup.contains(
+            "<mmultiscripts><mi mathvariant=\"normal\">C</mi><mprescripts></mprescripts>\
+             <mn>6</mn><mn>14</mn></mmultiscripts>"
+        ));
+        let markup = mathml_markup(r"\nuclide[235]{U}", &settings)?;
+        assert!(markup.contains("<mprescripts></mprescripts><none></none><mn>235</mn>"));
         Ok(())
     });
 
@@ -116,14 +248,14 @@ fn a_mathml_builder() {
             r"\begin{CD} A @>a>> B\\\\ @VVbV @VVcV\\\\ C @>d>> D \end{CD}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__cd_environment", markup);
+        insta::assert_snapshot!("mathml_spec__cd_environment", normalize_markup(&markup));
         Ok(())
     });
 
     it("should set href attribute for href appropriately", || {
         let settings = Settings::builder().trust(TrustSetting::Bool(true)).build();
         let markup = mathml_markup(r"\href{http://example.org}{\alpha}", &settings)?;
-        insta::assert_snapshot!("mathml_spec__href_trusted", markup);
+        insta::assert_snapshot!("mathml_spec__href_trusted", normalize_markup(&markup));
 
         let default_settings = Settings::default();
         let _ = mathml_markup(
@@ -139,22 +271,25 @@ fn a_mathml_builder() {
             r"\displaystyle\mathchoice{\sum_{k = 0}^{\infty} x^k}{T}{S}{SS}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__mathchoice_display", markup);
+        insta::assert_snapshot!("mathml_spec__mathchoice_display", normalize_markup(&markup));
         let markup = mathml_markup(
             r"\mathchoice{D}{\sum_{k = 0}^{\infty} x^k}{S}{SS}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__mathchoice_inline", markup);
+        insta::assert_snapshot!("mathml_spec__mathchoice_inline", normalize_markup(&markup));
         let markup = mathml_markup(
             r"x_{\mathchoice{D}{T}{\sum_{k = 0}^{\infty} x^k}{SS}}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__mathchoice_subscript", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__mathchoice_subscript",
+            normalize_markup(&markup)
+        );
         let markup = mathml_markup(
             r"x_{y_{\mathchoice{D}{T}{S}{\sum_{k = 0}^{\infty} x^k}}}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__mathchoice_nested", markup);
+        insta::assert_snapshot!("mathml_spec__mathchoice_nested", normalize_markup(&markup));
         Ok(())
     });
 
@@ -163,7 +298,10 @@ fn a_mathml_builder() {
         || {
             let settings = Settings::default();
             let markup = mathml_markup(r"\boldsymbol{Ax2k\omega\Omega\imath+}", &settings)?;
-            insta::assert_snapshot!("mathml_spec__boldsymbol_mathvariants", markup);
+            insta::assert_snapshot!(
+                "mathml_spec__boldsymbol_mathvariants",
+                normalize_markup(&markup)
+            );
             Ok(())
         },
     );
@@ -175,7 +313,7 @@ fn a_mathml_builder() {
                 .strict(StrictSetting::Bool(false))
                 .build();
             let markup = mathml_markup("über fiancée", &settings)?;
-            insta::assert_snapshot!("mathml_spec__accent_mover", markup);
+            insta::assert_snapshot!("mathml_spec__accent_mover", normalize_markup(&markup));
             Ok(())
         },
     );
@@ -183,14 +321,14 @@ fn a_mathml_builder() {
     it("tags use <mlabeledtr>", || {
         let settings = Settings::builder().display_mode(true).build();
         let markup = mathml_markup(r"\tag{hi} x+y^2", &settings)?;
-        insta::assert_snapshot!("mathml_spec__tag_mlabeledtr", markup);
+        insta::assert_snapshot!("mathml_spec__tag_mlabeledtr", normalize_markup(&markup));
         Ok(())
     });
 
     it("normal spaces render normally", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\kern1em\kern1ex", &settings)?;
-        insta::assert_snapshot!("mathml_spec__normal_spaces", markup);
+        insta::assert_snapshot!("mathml_spec__normal_spaces", normalize_markup(&markup));
         Ok(())
     });
 
@@ -200,7 +338,7 @@ fn a_mathml_builder() {
             r"\,\thinspace\:\>\medspace\;\thickspace\!\negthinspace\negmedspace\negthickspace\mkern1mu\mkern3mu\mkern4mu\mkern5mu\mkern-1mu\mkern-3mu\mkern-4mu\mkern-5mu",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__special_spaces", markup);
+        insta::assert_snapshot!("mathml_spec__special_spaces", normalize_markup(&markup));
         Ok(())
     });
 
@@ -210,7 +348,7 @@ fn a_mathml_builder() {
             r"\text{```Hi----'''}`--\texttt{```Hi----'''}`\text{\tt ```Hi----'''}`",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__ligatures", markup);
+        insta::assert_snapshot!("mathml_spec__ligatures", normalize_markup(&markup));
         Ok(())
     });
 
@@ -220,14 +358,133 @@ fn a_mathml_builder() {
             r"\text{roman\textit{italic\textbf{bold italic}}\textbf{bold}\textsf{ss\textit{italic\textbf{bold italic}}\textbf{bold}}\texttt{tt\textit{italic\textbf{bold italic}}\textbf{bold}}}",
             &settings,
         )?;
-        insta::assert_snapshot!("mathml_spec__text_fonts_mathvariant", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__text_fonts_mathvariant",
+            normalize_markup(&markup)
+        );
         Ok(())
     });
 
     it("\\html@mathml makes clean symbols", || {
         let settings = Settings::default();
         let markup = mathml_markup(r"\copyright\neq\notin\u2258\KaTeX", &settings)?;
-        insta::assert_snapshot!("mathml_spec__htmlmathml_clean_symbols", markup);
+        insta::assert_snapshot!(
+            "mathml_spec__htmlmathml_clean_symbols",
+            normalize_markup(&markup)
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn a_content_mathml_builder() {
+    let content = |expr: &str| -> Result<String, ParseError> {
+        let markup = render_to_content_mathml(default_ctx(), expr, &Settings::default())?;
+        Ok(markup
+            .trim_start_matches("<math xmlns=\"http://www.w3.org/1998/Math/MathML\">")
+            .trim_end_matches("</math>")
+            .to_owned())
+    };
+
+    it("should apply operators by precedence", || {
+        assert_eq!(
+            content("2x^2+1=y")?,
+            "<apply><eq/><apply><plus/><apply><times/><cn>2</cn>\
+             <apply><power/><ci>x</ci><cn>2</cn></apply></apply><cn>1</cn></apply>\
+             <ci>y</ci></apply>"
+        );
+        assert_eq!(
+            content("-(a+b)c")?,
+            "<apply><minus/><apply><times/><apply><plus/><ci>a</ci><ci>b</ci></apply>\
+             <ci>c</ci></apply></apply>"
+        );
+        assert_eq!(
+            content("a<b<c")?,
+            "<apply><lt/><ci>a</ci><ci>b</ci><ci>c</ci></apply>"
+        );
+        Ok(())
+    });
+
+    it("should recognise fractions, roots and functions", || {
+        assert_eq!(
+            content(r"\frac{\sqrt[3]{x}}{2}")?,
+            "<apply><divide/><apply><root/><degree><cn>3</cn></degree><ci>x</ci></apply>\
+             <cn>2</cn></apply>"
+        );
+        assert_eq!(
+            content(r"\sin^2 x + \log_2 n!")?,
+            "<apply><plus/><apply><power/><apply><sin/><ci>x</ci></apply><cn>2</cn></apply>\
+             <apply><log/><logbase><cn>2</cn></logbase>\
+             <apply><factorial/><ci>n</ci></apply></apply></apply>"
+        );
+        assert_eq!(
+            content(r"\left|x_1\right|")?,
+            "<apply><abs/><ci>x_1</ci></apply>"
+        );
+        Ok(())
+    });
+
+    it(
+        "should bind variables of sums, integrals and limits",
+        || {
+            assert_eq!(
+                content(r"\sum_{i=1}^n i")?,
+                "<apply><sum/><bvar><ci>i</ci></bvar><lowlimit><cn>1</cn></lowlimit>\
+             <uplimit><ci>n</ci></uplimit><ci>i</ci></apply>"
+            );
+            assert_eq!(
+                content(r"\int_0^\infty f(t)\,dt")?,
+                "<apply><int/><bvar><ci>t</ci></bvar><lowlimit><cn>0</cn></lowlimit>\
+             <uplimit><infinity/></uplimit><apply><ci>f</ci><ci>t</ci></apply></apply>"
+            );
+            assert_eq!(
+                content(r"\lim_{x\to 0} x")?,
+                "<apply><limit/><bvar><ci>x</ci></bvar><lowlimit><cn>0</cn></lowlimit>\
+             <ci>x</ci></apply>"
+            );
+            Ok(())
+        },
+    );
+
+    it("should keep numbers whole before their scripts", || {
+        assert_eq!(
+            content("10^{-3}")?,
+            "<apply><power/><cn>10</cn><apply><minus/><cn>3</cn></apply></apply>"
+        );
+        assert_eq!(
+            content("1.5^2")?,
+            "<apply><power/><cn>1.5</cn><cn>2</cn></apply>"
+        );
+        Ok(())
+    });
+
+    it("should name primed identifiers", || {
+        assert_eq!(
+            content("x'+y\u{2032}")?,
+            "<apply><plus/><ci>x′</ci><ci>y′</ci></apply>"
+        );
+        Ok(())
+    });
+
+    it("should recognise relations defined by macros", || {
+        assert_eq!(
+            content(r"a \neq b")?,
+            "<apply><neq/><ci>a</ci><ci>b</ci></apply>"
+        );
+        assert_eq!(
+            content(r"x \notin A")?,
+            "<apply><notin/><ci>x</ci><ci>A</ci></apply>"
+        );
+        Ok(())
+    });
+
+    it("should annotate structure it does not understand", || {
+        assert_eq!(
+            content(r"x + \overbrace{y}")?,
+            "<apply><plus/><ci>x</ci><semantics><csymbol>unknown</csymbol>\
+             <annotation encoding=\"application/x-tex\">\\overbrace{y}</annotation>\
+             </semantics></apply>"
+        );
         Ok(())
     });
 }
@@ -4,556 +4,26 @@
 use std::backtrace::Backtrace;
 use std::{
     collections::HashSet,
-    fmt,
     panic::{UnwindSafe, catch_unwind},
-    sync::OnceLock,
 };
 
 #[cfg(feature = "backtrace")]
 use btparse::deserialize;
 
 use katex::{
-    KatexContext, ParseError, Settings,
+    ParseError, Settings,
     dom_tree::Span,
     options::Options,
     parse,
-    parser::parse_node::{ParseNode, ParseNodeArrayTag},
+    parser::parse_node::ParseNode,
     render_to_dom_tree, render_to_string,
     style::DISPLAY,
     tree::{HtmlDomNode, VirtualNode as _},
     types::ParseErrorKind,
 };
-use regex::{Captures, Regex};
-use thiserror::Error;
-
-static DEFAULT_CONTEXT: OnceLock<KatexContext> = OnceLock::new();
-pub fn default_ctx() -> &'static KatexContext {
-    DEFAULT_CONTEXT.get_or_init(KatexContext::default)
-}
-
-pub struct TestExpr<'a> {
-    pub expr: String,
-    pub ctx: &'a KatexContext,
-    pub file: &'static str,
-    pub line: u32,
-    pub code: &'static str,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct TestLocation {
-    pub file: &'static str,
-    pub line: u32,
-    pub code: &'static str,
-}
-
-impl fmt::Display for TestLocation {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.code.is_empty() {
-            write!(f, "{}:{}", self.file, self.line)
-        } else {
-            write!(f, "{}:{} ({})", self.file, self.line, self.code)
-        }
-    }
-}
-
-impl TestLocation {
-    pub const UNKNOWN: Self = Self {
-        file: "<unknown>",
-        line: 0,
-        code: "",
-    };
-}
-
-#[derive(Debug, Error)]
-pub enum TestError {
-    #[error("Failed to parse '{expression}' at {location}: {source}")]
-    Parse {
-        expression: String,
-        location: TestLocation,
-        #[source]
-        source: ParseError,
-    },
-    #[error("Expected parsing to fail for '{expression}' at {location}")]
-    ExpectedParseFailure {
-        expression: String,
-        location: TestLocation,
-    },
-    #[error("Expected building to fail for '{expression}' at {location}")]
-    ExpectedBuildFailure {
-        expression: String,
-        location: TestLocation,
-    },
-    #[error("{0}")]
-    DomMismatch(Box<DomMismatchDetails>),
-    #[error(
-        "Expected HTML rendering to fail for '{expression}' at {location}, but it succeeded: {html}"
-    )]
-    ExpectedHtmlFailure {
-        expression: String,
-        location: TestLocation,
-        html: String,
-    },
-}
-
-#[derive(Debug)]
-pub struct DomMismatchDetails {
-    pub left_expr: String,
-    pub right_expr: String,
-    pub left_dom: String,
-    pub right_dom: String,
-    pub location: TestLocation,
-}
-
-impl fmt::Display for DomMismatchDetails {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "DOM mismatch at {} between '{}' and '{}':\n{}\n\n{}",
-            self.location, self.left_expr, self.right_expr, self.left_dom, self.right_dom
-        )
-    }
-}
-
-impl From<ParseError> for TestError {
-    fn from(source: ParseError) -> Self {
-        Self::Parse {
-            expression: "<unknown expression>".to_owned(),
-            location: TestLocation::UNKNOWN,
-            source,
-        }
-    }
-}
-
-pub type TestResult<T> = Result<T, TestError>;
-
-/// Set all `loc` to None for easier comparison
-pub fn strip_positions(nodes: &mut [ParseNode]) {
-    for node in nodes.iter_mut() {
-        match node {
-            katex::parser::parse_node::AnyParseNode::Array(parse_node_array) => {
-                parse_node_array.loc = None;
-                for row in &mut parse_node_array.body {
-                    strip_positions(row);
-                }
-                if let Some(tags) = &mut parse_node_array.tags {
-                    for tag in tags {
-                        if let ParseNodeArrayTag::Nodes(nodes) = tag {
-                            strip_positions(nodes);
-                        }
-                    }
-                }
-            }
-            katex::parser::parse_node::AnyParseNode::OrdGroup(parse_node_ord_group) => {
-                parse_node_ord_group.loc = None;
-                strip_positions(&mut parse_node_ord_group.body);
-            }
-            katex::parser::parse_node::AnyParseNode::SupSub(parse_node_sup_sub) => {
-                parse_node_sup_sub.loc = None;
-                if let Some(base) = &mut parse_node_sup_sub.base {
-                    strip_positions_single(base);
-                }
-                if let Some(sup) = &mut parse_node_sup_sub.sup {
-                    strip_positions_single(sup);
-                }
-                if let Some(sub) = &mut parse_node_sup_sub.sub {
-                    strip_positions_single(sub);
-                }
-            }
-            katex::parser::parse_node::AnyParseNode::Genfrac(parse_node_genfrac) => {
-                parse_node_genfrac.loc = None;
-                strip_positions_single(&mut parse_node_genfrac.numer);
-                strip_positions_single(&mut parse_node_genfrac.denom);
-            }
-            katex::parser::parse_node::AnyParseNode::LeftRight(parse_node_left_right) => {
-                parse_node_left_right.loc = None;
-                strip_positions(&mut parse_node_left_right.body);
-            }
-            katex::parser::parse_node::AnyParseNode::LeftRightRight(
-                parse_node_left_right_right,
-            ) => {
-                parse_node_left_right_right.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Sqrt(parse_node_sqrt) => {
-                parse_node_sqrt.loc = None;
-                strip_positions_single(&mut parse_node_sqrt.body);
-                if let Some(index) = &mut parse_node_sqrt.index {
-                    strip_positions_single(index);
-                }
-            }
-            katex::parser::parse_node::AnyParseNode::Atom(parse_node_atom) => {
-                parse_node_atom.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::MathOrd(parse_node_math_ord) => {
-                parse_node_math_ord.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Op(parse_node_op) => match parse_node_op {
-                katex::parser::parse_node::ParseNodeOp::Symbol { loc, .. } => *loc = None,
-                katex::parser::parse_node::ParseNodeOp::Body { loc, body, .. } => {
-                    *loc = None;
-                    strip_positions(body);
-                }
-            },
-            katex::parser::parse_node::AnyParseNode::Spacing(parse_node_spacing) => {
-                parse_node_spacing.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Text(parse_node_text) => {
-                parse_node_text.loc = None;
-                strip_positions(&mut parse_node_text.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Styling(parse_node_styling) => {
-                parse_node_styling.loc = None;
-                strip_positions(&mut parse_node_styling.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Font(parse_node_font) => {
-                parse_node_font.loc = None;
-                strip_positions_single(&mut parse_node_font.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Color(parse_node_color) => {
-                parse_node_color.loc = None;
-                strip_positions(&mut parse_node_color.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Accent(parse_node_accent) => {
-                parse_node_accent.loc = None;
-                strip_positions_single(&mut parse_node_accent.base);
-            }
-            katex::parser::parse_node::AnyParseNode::Overline(parse_node_overline) => {
-                parse_node_overline.loc = None;
-                strip_positions_single(&mut parse_node_overline.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Underline(parse_node_underline) => {
-                parse_node_underline.loc = None;
-                strip_positions_single(&mut parse_node_underline.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Phantom(parse_node_phantom) => {
-                parse_node_phantom.loc = None;
-                strip_positions(&mut parse_node_phantom.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Hphantom(parse_node_hphantom) => {
-                parse_node_hphantom.loc = None;
-                strip_positions_single(&mut parse_node_hphantom.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Vphantom(parse_node_vphantom) => {
-                parse_node_vphantom.loc = None;
-                strip_positions_single(&mut parse_node_vphantom.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Rule(parse_node_rule) => {
-                parse_node_rule.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::CdLabel(parse_node_cd_label) => {
-                parse_node_cd_label.loc = None;
-                strip_positions_single(&mut parse_node_cd_label.label);
-            }
-            katex::parser::parse_node::AnyParseNode::CdLabelParent(parse_node_cd_label_parent) => {
-                parse_node_cd_label_parent.loc = None;
-                strip_positions_single(&mut parse_node_cd_label_parent.fragment);
-            }
-            katex::parser::parse_node::AnyParseNode::ColorToken(parse_node_color_token) => {
-                parse_node_color_token.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Raw(parse_node_raw) => {
-                parse_node_raw.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Size(parse_node_size) => {
-                parse_node_size.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Tag(parse_node_tag) => {
-                parse_node_tag.loc = None;
-                strip_positions(&mut parse_node_tag.body);
-                strip_positions(&mut parse_node_tag.tag);
-            }
-            katex::parser::parse_node::AnyParseNode::Url(parse_node_url) => {
-                parse_node_url.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Verb(parse_node_verb) => {
-                parse_node_verb.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::TextOrd(parse_node_text_ord) => {
-                parse_node_text_ord.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::AccentToken(parse_node_accent_token) => {
-                parse_node_accent_token.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::OpToken(parse_node_op_token) => {
-                parse_node_op_token.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::AccentUnder(parse_node_accent_under) => {
-                parse_node_accent_under.loc = None;
-                strip_positions_single(&mut parse_node_accent_under.base);
-            }
-            katex::parser::parse_node::AnyParseNode::Cr(parse_node_cr) => {
-                parse_node_cr.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Delimsizing(parse_node_delimsizing) => {
-                parse_node_delimsizing.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Enclose(parse_node_enclose) => {
-                parse_node_enclose.loc = None;
-                strip_positions_single(&mut parse_node_enclose.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Environment(parse_node_environment) => {
-                parse_node_environment.loc = None;
-                strip_positions_single(&mut parse_node_environment.name_group);
-            }
-            katex::parser::parse_node::AnyParseNode::Hbox(parse_node_hbox) => {
-                parse_node_hbox.loc = None;
-                strip_positions(&mut parse_node_hbox.body);
-            }
-            katex::parser::parse_node::AnyParseNode::HorizBrace(parse_node_horiz_brace) => {
-                parse_node_horiz_brace.loc = None;
-                strip_positions_single(&mut parse_node_horiz_brace.base);
-            }
-            katex::parser::parse_node::AnyParseNode::Href(parse_node_href) => {
-                parse_node_href.loc = None;
-                strip_positions(&mut parse_node_href.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Html(parse_node_html) => {
-                parse_node_html.loc = None;
-                strip_positions(&mut parse_node_html.body);
-            }
-            katex::parser::parse_node::AnyParseNode::HtmlMathMl(parse_node_html_math_ml) => {
-                parse_node_html_math_ml.loc = None;
-                strip_positions(&mut parse_node_html_math_ml.html);
-                strip_positions(&mut parse_node_html_math_ml.mathml);
-            }
-            katex::parser::parse_node::AnyParseNode::Includegraphics(
-                parse_node_includegraphics,
-            ) => {
-                parse_node_includegraphics.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Infix(parse_node_infix) => {
-                parse_node_infix.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Internal(parse_node_internal) => {
-                parse_node_internal.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Kern(parse_node_kern) => {
-                parse_node_kern.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Lap(parse_node_lap) => {
-                parse_node_lap.loc = None;
-                strip_positions_single(&mut parse_node_lap.body);
-            }
-            katex::parser::parse_node::AnyParseNode::MathChoice(parse_node_math_choice) => {
-                parse_node_math_choice.loc = None;
-                strip_positions(&mut parse_node_math_choice.display);
-                strip_positions(&mut parse_node_math_choice.text);
-                strip_positions(&mut parse_node_math_choice.script);
-                strip_positions(&mut parse_node_math_choice.scriptscript);
-            }
-            katex::parser::parse_node::AnyParseNode::Middle(parse_node_middle) => {
-                parse_node_middle.loc = None;
-            }
-            katex::parser::parse_node::AnyParseNode::Mclass(parse_node_mclass) => {
-                parse_node_mclass.loc = None;
-                strip_positions(&mut parse_node_mclass.body);
-            }
-            katex::parser::parse_node::AnyParseNode::OperatorName(parse_node_operator_name) => {
-                parse_node_operator_name.loc = None;
-                strip_positions(&mut parse_node_operator_name.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Pmb(parse_node_pmb) => {
-                parse_node_pmb.loc = None;
-                strip_positions(&mut parse_node_pmb.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Raisebox(parse_node_raisebox) => {
-                parse_node_raisebox.loc = None;
-                strip_positions_single(&mut parse_node_raisebox.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Sizing(parse_node_sizing) => {
-                parse_node_sizing.loc = None;
-                strip_positions(&mut parse_node_sizing.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Smash(parse_node_smash) => {
-                parse_node_smash.loc = None;
-                strip_positions_single(&mut parse_node_smash.body);
-            }
-            katex::parser::parse_node::AnyParseNode::Vcenter(parse_node_vcenter) => {
-                parse_node_vcenter.loc = None;
-                strip_positions_single(&mut parse_node_vcenter.body);
-            }
-            katex::parser::parse_node::AnyParseNode::XArrow(parse_node_xarrow) => {
-                parse_node_xarrow.loc = None;
-                if let Some(body) = &mut parse_node_xarrow.body {
-                    strip_positions_single(body);
-                }
-                if let Some(below) = &mut parse_node_xarrow.below {
-                    strip_positions_single(below);
-                }
-            }
-        }
-    }
-}
-
-/// Helper function to strip positions from a single node
-fn strip_positions_single(node: &mut ParseNode) {
-    let mut temp_vec = vec![node.clone()];
-    strip_positions(&mut temp_vec);
-    *node = temp_vec.into_iter().next().unwrap();
-}
-
-impl TestExpr<'_> {
-    pub fn to_parse(&self, settings: &Settings) -> TestResult<()> {
-        self.wrap_parse(parse(self.ctx, &self.expr, settings))?;
-        Ok(())
-    }
-
-    pub fn not_to_parse(self, settings: &Settings) -> TestResult<()> {
-        let TestExpr {
-            expr,
-            ctx,
-            file,
-            line,
-            code,
-        } = self;
-        let location = TestLocation { file, line, code };
-        match parse(ctx, &expr, settings) {
-            Ok(_) => Err(TestError::ExpectedParseFailure {
-                expression: expr,
-                location,
-            }),
-            Err(_) => Ok(()),
-        }
-    }
-
-    pub fn to_parse_like(&self, other: &str, settings: &Settings) -> TestResult<()> {
-        // Compare parse trees for equivalence
-        let mut tree1 = self.wrap_parse(parse(self.ctx, &self.expr, settings))?;
-        let mut tree2 = self.wrap_parse_with_expr(other, parse(self.ctx, other, settings))?;
-
-        strip_positions(&mut tree1);
-        strip_positions(&mut tree2);
-
-        assert_eq!(
-            tree1, tree2,
-            "Parse trees do not match between '{}' and '{}'",
-            self.expr, other
-        );
-        Ok(())
-    }
-
-    pub fn parse_error(&self, settings: &Settings) -> TestResult<ParseError> {
-        let location = self.location();
-        match parse(self.ctx, &self.expr, settings) {
-            Ok(_) => Err(TestError::ExpectedParseFailure {
-                expression: self.expr.clone(),
-                location,
-            }),
-            Err(error) => Ok(error),
-        }
-    }
-
-    pub fn to_build(&self, settings: &Settings) -> TestResult<()> {
-        self.wrap_parse(render_to_dom_tree(self.ctx, &self.expr, settings))?;
-        Ok(())
-    }
-
-    pub fn not_to_build(self, settings: &Settings) -> TestResult<()> {
-        let TestExpr {
-            expr,
-            ctx,
-            file,
-            line,
-            code,
-        } = self;
-        let location = TestLocation { file, line, code };
-        match render_to_dom_tree(ctx, &expr, settings) {
-            Ok(_) => Err(TestError::ExpectedBuildFailure {
-                expression: expr,
-                location,
-            }),
-            Err(_) => Ok(()),
-        }
-    }
-
-    pub fn to_build_like(&self, other: &str, settings: &Settings) -> TestResult<()> {
-        // Compare rendered DOM trees for equivalence
-        let dom1 = self.wrap_parse(render_to_dom_tree(self.ctx, &self.expr, settings))?;
-        let dom2 =
-            self.wrap_parse_with_expr(other, render_to_dom_tree(self.ctx, other, settings))?;
-
-        // Simple comparison - in a real implementation, we'd do proper DOM comparison
-        let dom1_debug = format!("{dom1:?}");
-        let dom2_debug = format!("{dom2:?}");
-        if dom1_debug == dom2_debug {
-            Ok(())
-        } else {
-            Err(TestError::DomMismatch(Box::new(DomMismatchDetails {
-                left_expr: self.expr.clone(),
-                right_expr: other.to_owned(),
-                left_dom: dom1_debug,
-                right_dom: dom2_debug,
-                location: self.location(),
-            })))
-        }
-    }
-
-    pub fn not_to_html(self, settings: &Settings) -> TestResult<()> {
-        let TestExpr {
-            expr,
-            ctx,
-            file,
-            line,
-            code,
-        } = self;
-        let location = TestLocation { file, line, code };
-        match render_to_string(ctx, &expr, settings) {
-            Ok(html) => Err(TestError::ExpectedHtmlFailure {
-                expression: expr,
-                location,
-                html,
-            }),
-            Err(_) => Ok(()),
-        }
-    }
-}
-
-impl TestExpr<'_> {
-    const fn location(&self) -> TestLocation {
-        TestLocation {
-            file: self.file,
-            line: self.line,
-            code: self.code,
-        }
-    }
-
-    fn wrap_parse<T>(&self, result: Result<T, ParseError>) -> TestResult<T> {
-        result.map_err(|source| TestError::Parse {
-            expression: self.expr.clone(),
-            location: self.location(),
-            source,
-        })
-    }
-
-    fn wrap_parse_with_expr<T>(&self, expr: &str, result: Result<T, ParseError>) -> TestResult<T> {
-        result.map_err(|source| TestError::Parse {
-            expression: expr.to_owned(),
-            location: self.location(),
-            source,
-        })
-    }
-}
-
-pub fn expect_impl(
-    expr: &str,
-    file: &'static str,
-    line: u32,
-    code: &'static str,
-) -> TestExpr<'static> {
-    TestExpr {
-        expr: expr.to_owned(),
-        ctx: default_ctx(),
-        file,
-        line,
-        code,
-    }
-}
-
-#[macro_export]
-macro_rules! expect {
-    ($e:expr) => {
-        $crate::expect_impl($e, file!(), line!(), stringify!($e))
-    };
-}
+pub use katex_test_utils::snapshot::*;
+pub use katex_test_utils::*;
+use regex::Regex;
 
 #[cfg(feature = "backtrace")]
 fn format_backtrace(bt_serialized: &Backtrace) -> String {
@@ -628,27 +98,6 @@ where
     }
 }
 
-/// Settings helpers
-pub fn strict_settings() -> Settings {
-    Settings::builder()
-        .throw_on_error(true)
-        .strict(katex::StrictSetting::Bool(true))
-        .build()
-}
-
-pub fn nonstrict_settings() -> Settings {
-    Settings::builder()
-        .throw_on_error(false)
-        .strict(katex::StrictSetting::Bool(false))
-        .build()
-}
-
-pub fn trust_settings() -> Settings {
-    Settings::builder()
-        .trust(katex::TrustSetting::Bool(true))
-        .build()
-}
-
 pub fn trust_non_strict_settings() -> Settings {
     Settings::builder()
         .trust(katex::TrustSetting::Bool(true))
@@ -753,6 +202,10 @@ pub fn build_mathml_with_settings(
         options = options.having_base_style(Some(DISPLAY));
     }
     options.max_size = settings.max_size;
+    options
+        .number_formatter
+        .clone_from(&settings.number_formatter);
+    options.behavior = settings.behavior;
 
     katex::build_mathml::build_mathml(
         default_ctx(),
@@ -876,100 +329,3 @@ pub fn assert_html_eq_unordered_styles(markup: &str, rendered: &str) {
         }
     }
 }
-
-/// Normalize style attribute values by sorting declarations for stable
-/// comparisons
-pub fn normalize_style_attributes(markup: &str) -> String {
-    let style_regex = Regex::new(r#"style="([^"]*)""#).unwrap();
-    style_regex
-        .replace_all(markup, |caps: &Captures<'_>| {
-            let had_trailing_semicolon = caps[1].trim_end().ends_with(';');
-            let mut styles: Vec<&str> = caps[1]
-                .split(';')
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .collect();
-            styles.sort_unstable();
-            let mut normalized = styles.join(";");
-            if had_trailing_semicolon && !normalized.is_empty() {
-                normalized.push(';');
-            }
-            format!("style=\"{normalized}\"")
-        })
-        .to_string()
-}
-
-/// Normalize attribute order within HTML tags for deterministic comparisons
-pub fn normalize_html_attributes(markup: &str) -> String {
-    let tag_regex = Regex::new("<([a-zA-Z0-9]+)([^>]*)>").unwrap();
-    let attr_regex = Regex::new(r#"([^\s"'=<>]+)="([^"]*)""#).unwrap();
-
-    tag_regex
-        .replace_all(markup, |caps: &Captures<'_>| {
-            let tag = &caps[1];
-            let mut attr_str = caps[2].trim();
-            let self_closing = attr_str.ends_with('/');
-            if self_closing {
-                attr_str = attr_str[..attr_str.len() - 1].trim_end();
-            }
-            if attr_str.is_empty() {
-                if self_closing {
-                    format!("<{tag}/>")
-                } else {
-                    format!("<{tag}>")
-                }
-            } else {
-                let mut attrs: Vec<(&str, &str)> = attr_regex
-                    .captures_iter(attr_str)
-                    .map(|attr_caps| {
-                        (
-                            attr_caps.get(1).unwrap().as_str(),
-                            attr_caps.get(2).unwrap().as_str(),
-                        )
-                    })
-                    .collect();
-                attrs.sort_by(|a, b| a.0.cmp(b.0));
-                let formatted = attrs
-                    .into_iter()
-                    .map(|(name, value)| format!("{name}=\"{value}\""))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                if self_closing {
-                    format!("<{tag} {formatted}/>")
-                } else {
-                    format!("<{tag} {formatted}>")
-                }
-            }
-        })
-        .to_string()
-}
-
-/// Normalize debug output by sorting attribute entries for deterministic
-/// snapshots
-pub fn normalize_debug_snapshot(debug: &str) -> String {
-    let mut result = Vec::new();
-    let mut lines = debug.lines().peekable();
-
-    while let Some(line) = lines.next() {
-        if line.trim_end().ends_with("attributes: {") {
-            result.push(line.to_owned());
-            let mut attrs = Vec::new();
-            while let Some(next_line) = lines.peek() {
-                let trimmed = next_line.trim();
-                if trimmed == "}," || trimmed == "}" {
-                    break;
-                }
-                attrs.push(lines.next().unwrap().to_owned());
-            }
-            attrs.sort();
-            result.extend(attrs);
-            if let Some(closing) = lines.next() {
-                result.push(closing.to_owned());
-            }
-        } else {
-            result.push(line.to_owned());
-        }
-    }
-
-    result.join("\n")
-}
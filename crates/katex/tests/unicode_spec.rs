@@ -195,8 +195,8 @@ fn unicode() {
         expect!(r"\text{✌}").not_to_parse(&strict)?;
 
         let settings = Settings::builder()
-            .strict(StrictSetting::Function(Arc::new(|error_code, _, _| {
-                if error_code == "unknownSymbol" {
+            .strict(StrictSetting::Function(Arc::new(|context| {
+                if context.code == "unknownSymbol" {
                     Some(StrictReturn::Mode(StrictMode::Error))
                 } else {
                     Some(StrictReturn::Mode(StrictMode::Ignore))
@@ -214,8 +214,8 @@ fn unicode() {
         expect!(r"\text{✌}").to_parse(&settings)?;
 
         let settings = Settings::builder()
-            .strict(StrictSetting::Function(Arc::new(|error_code, _, _| {
-                if error_code == "unknownSymbol" {
+            .strict(StrictSetting::Function(Arc::new(|context| {
+                if context.code == "unknownSymbol" {
                     Some(StrictReturn::Mode(StrictMode::Ignore))
                 } else {
                     Some(StrictReturn::Mode(StrictMode::Error))
@@ -0,0 +1,81 @@
+//! Rendering on `wasm32-wasip1`
+//!
+//! These tests run on every target, but they exist for WASI: the crate must
+//! render without any filesystem or environment access, since font metrics
+//! and symbol tables are embedded at build time. With `wasmtime` installed,
+//! run them in a sandbox that has no preopened directories and no
+//! environment variables:
+//!
+//! ```bash
+//! rustup target add wasm32-wasip1
+//! cargo test -p katex-rs --target wasm32-wasip1 --test wasi_spec
+//! ```
+//!
+//! The runner is configured in `.cargo/config.toml`.
+
+use katex::types::OutputFormat;
+use katex::{KatexContext, Settings, render_to_string};
+
+const EXPRESSIONS: &[&str] = &[
+    r"x = \frac{-b \pm \sqrt{b^2 - 4ac}}{2a}",
+    r"\sum_{i=1}^{n} x_i^2 \le \int_0^\infty e^{-t}\,dt",
+    r"\left(\begin{matrix} a & b \\ c & d \end{matrix}\right)",
+    r"\mathbb{R} \mathcal{L} \mathfrak{g} \text{text and } \textbf{bold}",
+    r"\overrightarrow{AB} \widehat{xyz} \xrightarrow[below]{above}",
+    r"\def\f#1{#1^2} \f{y} + \operatorname{sinc} x",
+];
+
+#[cfg(target_os = "wasi")]
+#[test]
+fn runs_without_filesystem_or_environment() {
+    use std::{env, fs};
+
+    // Guards against running the WASI build with host access granted, which
+    // would let an accidental file read or env lookup pass unnoticed
+    assert!(
+        fs::metadata(".").is_err(),
+        "the sandbox preopens a directory"
+    );
+    assert_eq!(env::vars_os().count(), 0, "the sandbox passes environment");
+}
+
+#[test]
+fn renders_html_and_mathml() {
+    let ctx = KatexContext::default();
+    for display_mode in [false, true] {
+        let settings = Settings::builder().display_mode(display_mode).build();
+        for expression in EXPRESSIONS {
+            let html = render_to_string(&ctx, expression, &settings)
+                .unwrap_or_else(|err| panic!("{expression}: {err}"));
+            assert!(html.contains("katex-html"), "{expression}: {html}");
+            assert!(html.contains("<math"), "{expression}: {html}");
+        }
+    }
+}
+
+#[test]
+fn renders_every_output_format() {
+    let ctx = KatexContext::default();
+    for output in [
+        OutputFormat::Html,
+        OutputFormat::Mathml,
+        OutputFormat::HtmlAndMathml,
+    ] {
+        let settings = Settings::builder().output(output).build();
+        for expression in EXPRESSIONS {
+            render_to_string(&ctx, expression, &settings)
+                .unwrap_or_else(|err| panic!("{expression}: {err}"));
+        }
+    }
+}
+
+#[test]
+fn reports_errors() {
+    let ctx = KatexContext::default();
+    let settings = Settings::default();
+    assert!(render_to_string(&ctx, r"\frac{a}{", &settings).is_err());
+
+    let settings = Settings::builder().throw_on_error(false).build();
+    let html = render_to_string(&ctx, r"\frac{a}{", &settings).unwrap_or_default();
+    assert!(html.contains("katex-error"));
+}
@@ -0,0 +1,15 @@
+// Entry point for bundlers with WebAssembly ESM integration, such as webpack
+// with `experiments.asyncWebAssembly` or Vite with `vite-plugin-wasm`.
+//
+// The bundler instantiates the module before this one is evaluated, so
+// `ensureLoaded()` resolves immediately. It is exported so that code written
+// against the other entry points works unchanged.
+import * as bindings from "./bundler/katex.js";
+
+export * from "./bundler/katex.js";
+
+export function ensureLoaded() {
+  return Promise.resolve();
+}
+
+export default { ...bindings, ensureLoaded };
@@ -0,0 +1,19 @@
+// Types shared by every entry point. The bindings are identical across
+// targets; only how the WebAssembly module is loaded differs.
+import * as bindings from "./node/katex.js";
+import type { InitInput } from "./web/katex.js";
+
+export * from "./node/katex.js";
+
+/**
+ * Resolves once the WebAssembly module is ready. In browsers, the module is
+ * fetched from `moduleOrPath`, or from next to the entry point if omitted;
+ * in Node.js and with bundlers, it resolves immediately.
+ *
+ * The other exports are synchronous but must not be called before the
+ * promise resolves.
+ */
+export function ensureLoaded(moduleOrPath?: InitInput | Promise<InitInput>): Promise<void>;
+
+declare const katex: typeof bindings & { ensureLoaded: typeof ensureLoaded };
+export default katex;
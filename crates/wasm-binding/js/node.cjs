@@ -0,0 +1,13 @@
+// Entry point for Node.js CommonJS modules.
+//
+// Node loads the WebAssembly module synchronously, so `ensureLoaded()`
+// resolves immediately. It is exported so that code written against the
+// browser entry point works unchanged.
+const bindings = require("./node/katex.js");
+
+function ensureLoaded() {
+  return Promise.resolve();
+}
+
+module.exports = { ...bindings, ensureLoaded };
+module.exports.default = module.exports;
@@ -0,0 +1,24 @@
+// Entry point for Node.js ES modules.
+//
+// Node loads the WebAssembly module synchronously, so `ensureLoaded()`
+// resolves immediately. It is exported so that code written against the
+// browser entry point works unchanged.
+import bindings from "./node/katex.js";
+
+export const {
+  render,
+  renderToString,
+  renderToHTML,
+  renderToMathML,
+  renderToContentMathML,
+  renderToSpeech,
+  renderToBraille,
+  measureAlignment,
+  version,
+} = bindings;
+
+export function ensureLoaded() {
+  return Promise.resolve();
+}
+
+export default { ...bindings, ensureLoaded };
@@ -0,0 +1,56 @@
+// Entry point for browsers and bundlers without WebAssembly ESM integration.
+//
+// The WebAssembly module is fetched and compiled asynchronously. Await
+// `ensureLoaded()` once; every other export is synchronous afterwards and
+// throws if called before the module has loaded.
+import init, * as bindings from "./web/katex.js";
+
+let loading;
+let loaded = false;
+
+export function ensureLoaded(moduleOrPath) {
+  loading ??= init(moduleOrPath === undefined ? undefined : { module_or_path: moduleOrPath }).then(
+    () => {
+      loaded = true;
+    },
+    (error) => {
+      // Let a later call retry, e.g. after a network failure
+      loading = undefined;
+      throw error;
+    },
+  );
+  return loading;
+}
+
+function requireLoaded(name) {
+  const binding = bindings[name];
+  return (...args) => {
+    if (!loaded) {
+      throw new Error(`katex-rs: await ensureLoaded() before calling ${name}()`);
+    }
+    return binding(...args);
+  };
+}
+
+export const render = requireLoaded("render");
+export const renderToString = requireLoaded("renderToString");
+export const renderToHTML = requireLoaded("renderToHTML");
+export const renderToMathML = requireLoaded("renderToMathML");
+export const renderToContentMathML = requireLoaded("renderToContentMathML");
+export const renderToSpeech = requireLoaded("renderToSpeech");
+export const renderToBraille = requireLoaded("renderToBraille");
+export const measureAlignment = requireLoaded("measureAlignment");
+export const version = requireLoaded("version");
+
+export default {
+  ensureLoaded,
+  render,
+  renderToString,
+  renderToHTML,
+  renderToMathML,
+  renderToContentMathML,
+  renderToSpeech,
+  renderToBraille,
+  measureAlignment,
+  version,
+};
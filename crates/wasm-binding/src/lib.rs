@@ -15,6 +15,11 @@
 //! exported names match the canonical camelCase spellings. This allows the
 //! generated `pkg/katex.js` bundle to be dropped into existing KaTeX tooling –
 //! including the upstream screenshotter – without additional glue code.
+//!
+//! `cargo xtask wasm-package` builds this crate for the `web`, `nodejs` and
+//! `bundler` targets and wraps the outputs in the entry points in `js/`, which
+//! add an `ensureLoaded()` promise so callers can load the module the same
+//! way on every platform.
 
 use std::sync::OnceLock;
 
@@ -22,12 +27,16 @@ use js_sys::{Array, Object, Reflect};
 use wasm_bindgen::JsCast as _;
 use wasm_bindgen::prelude::*;
 
-use katex::ParseError;
+use katex::braille::BrailleCode;
 use katex::context::KatexContext;
 use katex::core;
 use katex::macro_expander::MacroMap;
 use katex::macros::MacroDefinition;
-use katex::types::{OutputFormat, Settings, StrictMode, StrictSetting, TrustSetting};
+use katex::speech::{SpeechOptions, Verbosity};
+use katex::types::{
+    Charset, OutputFormat, OverflowMode, Settings, StrictMode, StrictSetting, TrustSetting,
+};
+use katex::{Alignment, ParseError};
 
 /// Cached global [`KatexContext`].
 fn get_context() -> &'static KatexContext {
@@ -64,6 +73,25 @@ fn parse_js_string(value: &JsValue, key: &str) -> Result<String, JsValue> {
         .ok_or_else(|| js_error(&format!("option '{key}' must be a string")))
 }
 
+fn parse_js_alignment(value: &JsValue) -> Result<Alignment, JsValue> {
+    const MESSAGE: &str =
+        "option 'alignTo' must be an object with non-negative 'before' and 'after' numbers";
+    if !value.is_object() {
+        return Err(js_error(MESSAGE));
+    }
+    let width = |key: &str| -> Result<f64, JsValue> {
+        Reflect::get(value, &JsValue::from_str(key))
+            .ok()
+            .and_then(|v| v.as_f64())
+            .filter(|v| v.is_finite() && *v >= 0.0)
+            .ok_or_else(|| js_error(MESSAGE))
+    };
+    Ok(Alignment {
+        before: width("before")?,
+        after: width("after")?,
+    })
+}
+
 fn parse_js_options(options: JsValue) -> Result<JsSettings, JsValue> {
     if options.is_undefined() || options.is_null() {
         return Ok(JsSettings {
@@ -141,6 +169,12 @@ fn parse_js_options(options: JsValue) -> Result<JsSettings, JsValue> {
     if let Some(global_group) = opt_bool("globalGroup")? {
         settings.global_group = global_group;
     }
+    if let Some(decimal_comma) = opt_bool("decimalComma")? {
+        settings.decimal_comma = decimal_comma;
+    }
+    if let Some(text_wrap) = opt_bool("textWrap")? {
+        settings.text_wrap = text_wrap;
+    }
 
     if let Some(min_rule_thickness) = opt_number("minRuleThickness")? {
         if !(min_rule_thickness.is_finite() && min_rule_thickness >= 0.0) {
@@ -160,6 +194,53 @@ fn parse_js_options(options: JsValue) -> Result<JsSettings, JsValue> {
         settings.size_multiplier = size_multiplier;
     }
 
+    if let Some(min_script_scale) = opt_number("minScriptScale")? {
+        if !(min_script_scale.is_finite() && min_script_scale >= 0.0) {
+            return Err(js_error(
+                "option 'minScriptScale' must be a non-negative finite number",
+            ));
+        }
+        settings.min_script_scale = min_script_scale;
+    }
+
+    if let Some(max_width_em) = opt_number("maxWidthEm")? {
+        if !(max_width_em.is_finite() && max_width_em > 0.0) {
+            return Err(js_error(
+                "option 'maxWidthEm' must be a positive finite number",
+            ));
+        }
+        settings.max_width_em = Some(max_width_em);
+    }
+
+    if let Some(overflow) = opt_string("overflow")? {
+        settings.overflow = match overflow.to_lowercase().as_str() {
+            "scale" => OverflowMode::Scale,
+            "scroll" => OverflowMode::Scroll,
+            other => {
+                return Err(js_error(&format!(
+                    "option 'overflow' must be one of 'scale', 'scroll'; received '{other}'",
+                )));
+            }
+        };
+    }
+
+    if let Some(charset) = opt_string("charset")? {
+        settings.charset = match charset.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Charset::Utf8,
+            "ascii" => Charset::Ascii,
+            other => {
+                return Err(js_error(&format!(
+                    "option 'charset' must be one of 'utf-8', 'ascii'; received '{other}'",
+                )));
+            }
+        };
+    }
+
+    let align_to = get("alignTo")?;
+    if !(align_to.is_undefined() || align_to.is_null()) {
+        settings.align_to = Some(parse_js_alignment(&align_to)?);
+    }
+
     if let Some(max_size) = opt_number("maxSize")? {
         if !(max_size.is_finite() && max_size >= 0.0) {
             return Err(js_error(
@@ -181,6 +262,18 @@ fn parse_js_options(options: JsValue) -> Result<JsSettings, JsValue> {
         settings.max_expand = max_expand as usize;
     }
 
+    if let Some(max_depth) = opt_number("maxDepth")? {
+        if !(max_depth.is_finite() && max_depth >= 0.0) {
+            return Err(js_error(
+                "option 'maxDepth' must be a finite non-negative integer",
+            ));
+        }
+        if max_depth.fract() != 0.0 {
+            return Err(js_error("option 'maxDepth' must be an integer"));
+        }
+        settings.max_depth = max_depth as usize;
+    }
+
     let strict_value = get("strict")?;
     if !strict_value.is_undefined() && !strict_value.is_null() {
         if let Some(strict_bool) = strict_value.as_bool() {
@@ -261,8 +354,41 @@ fn parse_js_options(options: JsValue) -> Result<JsSettings, JsValue> {
     })
 }
 
-fn map_parse_error(error: ParseError) -> JsValue {
-    JsValue::from(error)
+/// Converts a [`ParseError`] for `tex` into the error KaTeX.js throws: an
+/// `Error` named `"ParseError"` whose `message` includes the location
+/// context, with `rawMessage` holding the message alone and `position` and
+/// `length` locating the offending input. Like in KaTeX.js, `position` and
+/// `length` are `undefined` when the error has no location, and count UTF-16
+/// code units so that `tex.slice(position, position + length)` works.
+fn map_parse_error(error: &ParseError, tex: &str) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    js_error.set_name("ParseError");
+    let set = |key: &str, value: JsValue| {
+        // Setting a data property on a fresh `Error` object cannot fail
+        let _ = Reflect::set(&js_error, &JsValue::from_str(key), &value);
+    };
+    set("rawMessage", JsValue::from_str(&error.kind.to_string()));
+    let (position, length) = match (error.position(), error.length()) {
+        (Some(start), Some(length)) => {
+            let position = utf16_offset(tex, start);
+            let end = utf16_offset(tex, start + length);
+            (
+                JsValue::from_f64(position as f64),
+                JsValue::from_f64((end - position) as f64),
+            )
+        }
+        _ => (JsValue::UNDEFINED, JsValue::UNDEFINED),
+    };
+    set("position", position);
+    set("length", length);
+    js_error.into()
+}
+
+/// Converts a byte offset into `text` into the UTF-16 offset JavaScript
+/// strings are indexed by.
+fn utf16_offset(text: &str, byte: usize) -> usize {
+    text.get(..byte)
+        .map_or(0, |prefix| prefix.encode_utf16().count())
 }
 
 fn element_from_js(element: JsValue) -> Result<web_sys::Element, JsValue> {
@@ -293,7 +419,7 @@ pub fn render(tex: &str, element: JsValue, options: JsValue) -> Result<(), JsVal
     let settings = normalize_settings(parsed, Some(OutputFormat::HtmlAndMathml));
 
     let node: web_sys::Node = element.unchecked_into();
-    core::render(get_context(), tex, &node, &settings).map_err(map_parse_error)
+    core::render(get_context(), tex, &node, &settings).map_err(|error| map_parse_error(&error, tex))
 }
 
 /// Exported as `katex.renderToString`.
@@ -301,7 +427,8 @@ pub fn render(tex: &str, element: JsValue, options: JsValue) -> Result<(), JsVal
 pub fn render_to_string(tex: &str, options: JsValue) -> Result<String, JsValue> {
     let parsed = parse_js_options(options)?;
     let settings = normalize_settings(parsed, Some(OutputFormat::HtmlAndMathml));
-    core::render_to_string(get_context(), tex, &settings).map_err(map_parse_error)
+    core::render_to_string(get_context(), tex, &settings)
+        .map_err(|error| map_parse_error(&error, tex))
 }
 
 /// Exported as `katex.renderToHTML`.
@@ -310,7 +437,8 @@ pub fn render_to_html(tex: &str, options: JsValue) -> Result<String, JsValue> {
     let parsed = parse_js_options(options)?;
     let mut settings = parsed.settings;
     settings.output = OutputFormat::Html;
-    core::render_to_string(get_context(), tex, &settings).map_err(map_parse_error)
+    core::render_to_string(get_context(), tex, &settings)
+        .map_err(|error| map_parse_error(&error, tex))
 }
 
 /// Exported as `katex.renderToMathML`.
@@ -319,7 +447,111 @@ pub fn render_to_mathml(tex: &str, options: JsValue) -> Result<String, JsValue>
     let parsed = parse_js_options(options)?;
     let mut settings = parsed.settings;
     settings.output = OutputFormat::Mathml;
-    core::render_to_string(get_context(), tex, &settings).map_err(map_parse_error)
+    core::render_to_string(get_context(), tex, &settings)
+        .map_err(|error| map_parse_error(&error, tex))
+}
+
+/// Exported as `katex.measureAlignment`.
+///
+/// Returns `{ before, after }`, the widths in ems on either side of the first
+/// relation. Merge the results for several formulas by taking the maximum of
+/// each field and pass them back as the `alignTo` option.
+#[wasm_bindgen(js_name = measureAlignment)]
+pub fn measure_alignment(tex: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    let parsed = parse_js_options(options)?;
+    let alignment = core::measure_alignment(get_context(), tex, &parsed.settings)
+        .map_err(|error| map_parse_error(&error, tex))?;
+    let result = Object::new();
+    Reflect::set(&result, &"before".into(), &alignment.before.into())?;
+    Reflect::set(&result, &"after".into(), &alignment.after.into())?;
+    Ok(result.into())
+}
+
+/// Exported as `katex.renderToSpeech`.
+///
+/// Returns an array of `{ text, node, start, end }` objects, where `start`
+/// and `end` are UTF-16 offsets into `tex` of the node each chunk of speech
+/// describes. `verbosity` is one of `'verbose'` (the default), `'brief'` or
+/// `'superbrief'`.
+#[wasm_bindgen(js_name = renderToSpeech)]
+pub fn render_to_speech(
+    tex: &str,
+    options: JsValue,
+    verbosity: Option<String>,
+) -> Result<Array, JsValue> {
+    let parsed = parse_js_options(options)?;
+    let verbosity = verbosity.map(|mut verbosity| {
+        verbosity.make_ascii_lowercase();
+        verbosity
+    });
+    let verbosity = match verbosity.as_deref() {
+        None | Some("verbose") => Verbosity::Verbose,
+        Some("brief") => Verbosity::Brief,
+        Some("superbrief") => Verbosity::SuperBrief,
+        Some(other) => {
+            return Err(js_error(&format!(
+                "verbosity must be one of 'verbose', 'brief', 'superbrief'; received '{other}'",
+            )));
+        }
+    };
+    let speech_options = SpeechOptions {
+        verbosity,
+        ..SpeechOptions::default()
+    };
+    let spans = core::render_to_speech(get_context(), tex, &parsed.settings, &speech_options)
+        .map_err(|error| map_parse_error(&error, tex))?;
+
+    let result = Array::new();
+    for span in spans {
+        let chunk = Object::new();
+        Reflect::set(&chunk, &"text".into(), &span.text.into())?;
+        Reflect::set(&chunk, &"node".into(), &span.node.to_string().into())?;
+        if let Some(source) = span.source {
+            Reflect::set(
+                &chunk,
+                &"start".into(),
+                &utf16_offset(tex, source.start).into(),
+            )?;
+            Reflect::set(&chunk, &"end".into(), &utf16_offset(tex, source.end).into())?;
+        }
+        result.push(&chunk);
+    }
+    Ok(result)
+}
+
+/// Exported as `katex.renderToBraille`.
+///
+/// `code` is `'nemeth'` (the default) or `'ueb'`.
+#[wasm_bindgen(js_name = renderToBraille)]
+pub fn render_to_braille(
+    tex: &str,
+    options: JsValue,
+    code: Option<String>,
+) -> Result<String, JsValue> {
+    let parsed = parse_js_options(options)?;
+    let code = code.map(|mut code| {
+        code.make_ascii_lowercase();
+        code
+    });
+    let code = match code.as_deref() {
+        None | Some("nemeth") => BrailleCode::Nemeth,
+        Some("ueb") => BrailleCode::Ueb,
+        Some(other) => {
+            return Err(js_error(&format!(
+                "braille code must be one of 'nemeth', 'ueb'; received '{other}'",
+            )));
+        }
+    };
+    core::render_to_braille(get_context(), tex, &parsed.settings, code)
+        .map_err(|error| map_parse_error(&error, tex))
+}
+
+/// Exported as `katex.renderToContentMathML`.
+#[wasm_bindgen(js_name = renderToContentMathML)]
+pub fn render_to_content_mathml(tex: &str, options: JsValue) -> Result<String, JsValue> {
+    let parsed = parse_js_options(options)?;
+    core::render_to_content_mathml(get_context(), tex, &parsed.settings)
+        .map_err(|error| map_parse_error(&error, tex))
 }
 
 /// Exported as `katex.version`.
@@ -334,3 +566,27 @@ pub fn version() -> String {
 pub fn start() {
     console_error_panic_hook::set_once();
 }
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    /// `position` and `length` of the error thrown for `tex`
+    fn error_range(tex: &str) -> (Option<f64>, Option<f64>) {
+        let Err(error) = render_to_string(tex, JsValue::UNDEFINED) else {
+            panic!("{tex} should not parse");
+        };
+        let get = |key: &str| Reflect::get(&error, &JsValue::from_str(key)).ok()?.as_f64();
+        (get("position"), get("length"))
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_error_locates_non_ascii_input_in_utf16() {
+        // α is two bytes but one UTF-16 code unit, 𝑥 four bytes but two
+        assert_eq!(error_range("\u{3b1}\\frac{"), (Some(7.0), Some(0.0)));
+        assert_eq!(error_range("\u{3b1}\\foo"), (Some(1.0), Some(4.0)));
+        assert_eq!(error_range("\u{1d465}\u{3b1}\\foo"), (Some(3.0), Some(4.0)));
+    }
+}
@@ -0,0 +1,27 @@
+//! Renders arbitrary input and fails on any panic
+//!
+//! Malformed input must come back as a `ParseError` or, with
+//! `throw_on_error` off, as rendered error markup. Run with
+//! `cargo +nightly fuzz run render` from the repository root.
+
+#![no_main]
+
+use std::sync::LazyLock;
+
+use katex::types::{OutputFormat, TrustSetting};
+use katex::{KatexContext, Settings, render_to_string};
+use libfuzzer_sys::fuzz_target;
+
+static CTX: LazyLock<KatexContext> = LazyLock::new(KatexContext::default);
+
+fuzz_target!(|input: &str| {
+    for display_mode in [false, true] {
+        let settings = Settings::builder()
+            .display_mode(display_mode)
+            .output(OutputFormat::HtmlAndMathml)
+            .throw_on_error(display_mode)
+            .trust(TrustSetting::Bool(true))
+            .build();
+        let _ = render_to_string(&CTX, input, &settings);
+    }
+});
@@ -1,24 +1,45 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
+use std::process::Command;
 
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::Args;
 use color_eyre::eyre::{Context, ContextCompat, Result, bail};
+use rapidhash::v3::rapidhash_v3;
 use regex::Regex;
 use serde::Serialize;
 use serde_json::{Value, json};
 
+use crate::screenshotter::{BuildMode, ensure_katex_dist_assets};
+
+/// Name of the file recording the submodule revision and data checksums.
+const CHECKSUMS_FILE: &str = "checksums.json";
+
 #[derive(Args, Default)]
-pub struct ExtractDataArgs {}
+pub struct ExtractDataArgs {
+    /// Regenerate the data in a scratch directory and fail if it differs from
+    /// the vendored files, instead of overwriting them.
+    #[arg(long)]
+    pub verify_data: bool,
+}
 
-pub fn run(_args: ExtractDataArgs) -> Result<()> {
+pub fn run(args: &ExtractDataArgs) -> Result<()> {
     let root = project_root();
+    let data_dir = root.join("crates").join("katex").join("data");
+
+    if args.verify_data {
+        return verify_data(&root, &data_dir);
+    }
+
+    extract(&root, &data_dir)?;
+    write_checksums(&root, &data_dir)
+}
+
+fn extract(root: &Utf8Path, output_dir: &Utf8Path) -> Result<()> {
     let katex_src = root.join("KaTeX").join("src");
-    let output_dir = root.join("crates").join("katex").join("data");
 
-    std::fs::create_dir_all(&output_dir)
-        .with_context(|| format!("failed to create {}", output_dir))?;
+    fs::create_dir_all(output_dir).with_context(|| format!("failed to create {output_dir}"))?;
 
     write_pretty_json(
         output_dir.join("font_metrics_data.json"),
@@ -34,10 +55,113 @@ pub fn run(_args: ExtractDataArgs) -> Result<()> {
     write_pretty_json(output_dir.join("symbols.json"), &symbols)?;
     println!("Extracted {} symbols", count);
 
+    ensure_katex_dist_assets(root, BuildMode::Auto)?;
+    let css_path = root.join("KaTeX").join("dist").join("katex.min.css");
+    let css_output = output_dir.join("katex.min.css");
+    fs::copy(&css_path, &css_output)
+        .with_context(|| format!("failed to copy {css_path} to {css_output}"))?;
+    println!("Successfully copied {css_output}");
+
+    write_pretty_json(
+        output_dir.join("upstream.json"),
+        extract_upstream(&root.join("KaTeX"))?,
+    )?;
+
+    Ok(())
+}
+
+/// Records the submodule revision and a rapidhash of every data file, so the
+/// `data_spec` tests can tell when a vendored file was edited by hand.
+fn write_checksums(root: &Utf8Path, data_dir: &Utf8Path) -> Result<()> {
+    let mut files = BTreeMap::new();
+    for entry in data_dir
+        .read_dir_utf8()
+        .with_context(|| format!("failed to list {data_dir}"))?
+    {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == CHECKSUMS_FILE || !entry.file_type()?.is_file() {
+            continue;
+        }
+        let contents =
+            fs::read(entry.path()).with_context(|| format!("failed to read {}", entry.path()))?;
+        files.insert(name.to_owned(), format!("{:016x}", rapidhash_v3(&contents)));
+    }
+
+    write_pretty_json(
+        data_dir.join(CHECKSUMS_FILE),
+        json!({
+            "katex_commit": git_output(&root.join("KaTeX"), &["rev-parse", "HEAD"])?,
+            "files": files,
+        }),
+    )
+}
+
+/// Regenerates the data from the pinned submodule into a scratch directory
+/// and compares it with the vendored files byte for byte.
+fn verify_data(root: &Utf8Path, data_dir: &Utf8Path) -> Result<()> {
+    let pinned = git_output(
+        root,
+        &["config", "--file", ".gitmodules", "submodule.KaTeX.commit"],
+    )?;
+    let checked_out = git_output(&root.join("KaTeX"), &["rev-parse", "HEAD"])?;
+    if pinned != checked_out {
+        bail!(
+            "KaTeX submodule is at {checked_out}, but .gitmodules pins {pinned}. \
+             Check out the pinned revision first."
+        );
+    }
+
+    let scratch = root.join("target").join("xtask").join("extract-data");
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch).with_context(|| format!("failed to clear {scratch}"))?;
+    }
+    extract(root, &scratch)?;
+    write_checksums(root, &scratch)?;
+
+    let mut mismatches = Vec::new();
+    for entry in scratch
+        .read_dir_utf8()
+        .with_context(|| format!("failed to list {scratch}"))?
+    {
+        let entry = entry?;
+        let vendored = data_dir.join(entry.file_name());
+        let expected = fs::read(entry.path())?;
+        match fs::read(&vendored) {
+            Ok(actual) if actual == expected => {}
+            Ok(_) => mismatches.push(format!("{vendored} differs from the extracted data")),
+            Err(_) => mismatches.push(format!("{vendored} is missing")),
+        }
+    }
+
+    if !mismatches.is_empty() {
+        bail!(
+            "vendored data does not match KaTeX {pinned}:\n  {}\nRun `cargo xtask extract-data` to regenerate it.",
+            mismatches.join("\n  ")
+        );
+    }
+    println!("Vendored data matches KaTeX {pinned}");
     Ok(())
 }
 
-fn project_root() -> Utf8PathBuf {
+fn git_output(dir: &Utf8Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir.as_std_path())
+        .output()
+        .with_context(|| format!("failed to run git {} in {dir}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed in {dir} with status {}",
+            args.join(" "),
+            output.status
+        );
+    }
+    let stdout = String::from_utf8(output.stdout).context("git printed non-UTF-8 output")?;
+    Ok(stdout.trim().to_owned())
+}
+
+pub fn project_root() -> Utf8PathBuf {
     let manifest_dir = Utf8Path::new(env!("CARGO_MANIFEST_DIR"));
     manifest_dir
         .parent()
@@ -45,6 +169,24 @@ fn project_root() -> Utf8PathBuf {
         .to_owned()
 }
 
+fn extract_upstream(katex_dir: &Utf8Path) -> Result<Value> {
+    let path = katex_dir.join("package.json");
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path))?;
+    let package: Value =
+        serde_json::from_str(&contents).with_context(|| format!("failed to parse {path}"))?;
+    let version = package["version"]
+        .as_str()
+        .with_context(|| format!("no version in {path}"))?;
+
+    let commit = git_output(katex_dir, &["rev-parse", "HEAD"])?;
+
+    Ok(json!({
+        "version": version,
+        "commit": commit,
+    }))
+}
+
 fn extract_font_metrics(katex_src: &Utf8Path) -> Result<Value> {
     let path = katex_src.join("fontMetricsData.js");
     let contents =
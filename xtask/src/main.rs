@@ -1,5 +1,6 @@
 mod extract_data;
 mod screenshotter;
+mod wasm_package;
 
 use clap::{Parser, Subcommand};
 use color_eyre::eyre::Result;
@@ -22,6 +23,8 @@ enum Command {
     Screenshotter(Box<screenshotter::ScreenshotterArgs>),
     /// Regenerate JSON data extracted from the upstream KaTeX repository.
     ExtractData(extract_data::ExtractDataArgs),
+    /// Build the npm package for Node.js, browsers and bundlers.
+    WasmPackage(wasm_package::WasmPackageArgs),
 }
 
 fn main() -> Result<()> {
@@ -31,6 +34,7 @@ fn main() -> Result<()> {
 
     match cli.command {
         Command::Screenshotter(args) => screenshotter::run(*args),
-        Command::ExtractData(args) => extract_data::run(args),
+        Command::ExtractData(args) => extract_data::run(&args),
+        Command::WasmPackage(args) => wasm_package::run(&args),
     }
 }
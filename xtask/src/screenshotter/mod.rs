@@ -10,5 +10,6 @@ mod server;
 mod viewport;
 mod webdriver;
 
-pub use self::args::ScreenshotterArgs;
+pub use self::args::{BuildMode, ScreenshotterArgs};
+pub use self::build::{ensure_command_available, ensure_katex_dist_assets};
 pub use runner::run;
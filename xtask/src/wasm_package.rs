@@ -0,0 +1,110 @@
+use std::fs;
+use std::process::Command;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use color_eyre::eyre::{Context as _, Result, bail};
+use serde_json::{Value, json};
+
+use crate::extract_data::project_root;
+use crate::screenshotter::ensure_command_available;
+
+/// wasm-pack targets, with the subdirectory of the package each is built into.
+const TARGETS: [(&str, &str); 3] = [("web", "web"), ("nodejs", "node"), ("bundler", "bundler")];
+
+/// Hand-written entry points that wrap the wasm-pack outputs, in
+/// `crates/wasm-binding/js`.
+const ENTRY_POINTS: [&str; 5] = ["web.js", "bundler.js", "node.mjs", "node.cjs", "index.d.ts"];
+
+#[derive(Args)]
+pub struct WasmPackageArgs {
+    /// Directory to write the package to, relative to the repository root.
+    #[arg(long, default_value = "crates/wasm-binding/dist")]
+    pub out_dir: Utf8PathBuf,
+    /// Build without optimizations, for faster iteration.
+    #[arg(long)]
+    pub dev: bool,
+}
+
+/// Builds the npm package: one wasm-pack output per target, the entry points
+/// that pick between them, and a `package.json` mapping Node.js, browsers and
+/// bundlers to the matching entry point.
+pub fn run(args: &WasmPackageArgs) -> Result<()> {
+    let root = project_root();
+    let binding_dir = root.join("crates").join("wasm-binding");
+    let out_dir = root.join(&args.out_dir);
+
+    ensure_command_available("wasm-pack")?;
+    fs::create_dir_all(&out_dir).with_context(|| format!("failed to create {out_dir}"))?;
+
+    for (target, subdir) in TARGETS {
+        let target_dir = out_dir.join(subdir);
+        let profile = if args.dev { "--dev" } else { "--release" };
+        let status = Command::new("wasm-pack")
+            .args(["build", profile, "--target", target, "--out-name", "katex"])
+            .arg("--out-dir")
+            .arg(target_dir.as_std_path())
+            .current_dir(binding_dir.as_std_path())
+            .status()
+            .context("failed to run wasm-pack build")?;
+        if !status.success() {
+            bail!("wasm-pack build --target {target} failed with status {status}");
+        }
+        println!("Built the {target} target into {target_dir}");
+    }
+
+    for entry_point in ENTRY_POINTS {
+        copy(
+            &binding_dir.join("js").join(entry_point),
+            &out_dir.join(entry_point),
+        )?;
+    }
+    copy(&root.join("README.md"), &out_dir.join("README.md"))?;
+    copy(&root.join("LICENSE"), &out_dir.join("LICENSE"))?;
+
+    let manifest = out_dir.join("package.json");
+    let json = serde_json::to_string_pretty(&package_json()).context("failed to serialize JSON")?;
+    fs::write(&manifest, json + "\n").with_context(|| format!("failed to write {manifest}"))?;
+    println!("Successfully wrote {manifest}");
+
+    Ok(())
+}
+
+fn package_json() -> Value {
+    let mut files: Vec<&str> = TARGETS.iter().map(|&(_, subdir)| subdir).collect();
+    files.extend(ENTRY_POINTS);
+    json!({
+        "name": "katex-rs",
+        "version": env!("CARGO_PKG_VERSION"),
+        "description": "KaTeX-compatible math rendering compiled to WebAssembly",
+        "license": "MIT",
+        "repository": {
+            "type": "git",
+            "url": "https://github.com/katex-rs/katex-rs"
+        },
+        "main": "./node.cjs",
+        "module": "./web.js",
+        "types": "./index.d.ts",
+        "exports": {
+            ".": {
+                "types": "./index.d.ts",
+                "node": {
+                    "import": "./node.mjs",
+                    "require": "./node.cjs"
+                },
+                "default": "./web.js"
+            },
+            "./bundler": {
+                "types": "./index.d.ts",
+                "default": "./bundler.js"
+            },
+            "./package.json": "./package.json"
+        },
+        "files": files
+    })
+}
+
+fn copy(from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+    fs::copy(from, to).with_context(|| format!("failed to copy {from} to {to}"))?;
+    Ok(())
+}